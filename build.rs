@@ -0,0 +1,67 @@
+//! Embeds what `tinywx --version -v` reports beyond the version: the git
+//! commit, whether the tree had changes, the target, and the reqwest version.
+//! Builds from a crate tarball have no git, and get "unknown" instead.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    let dir = env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let dir = Path::new(&dir);
+
+    let commit = git(dir, &["rev-parse", "--short=12", "HEAD"]);
+    let dirty = match git(dir, &["status", "--porcelain", "--untracked-files=no"]) {
+        Some(status) => (!status.is_empty()).to_string(),
+        None => "unknown".to_string(),
+    };
+    println!("cargo:rustc-env=TINYWX_GIT_COMMIT={}", commit.unwrap_or_else(|| "unknown".into()));
+    println!("cargo:rustc-env=TINYWX_GIT_DIRTY={}", dirty);
+    println!("cargo:rustc-env=TINYWX_TARGET={}", env::var("TARGET").unwrap_or_else(|_| "unknown".into()));
+    println!("cargo:rustc-env=TINYWX_REQWEST_VERSION={}", locked_version(dir, "reqwest"));
+
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    // A new commit moves HEAD, or the branch it points to; a change to a
+    // tracked file touches the index once it is staged.
+    for file in [".git/HEAD", ".git/index"] {
+        if dir.join(file).exists() {
+            println!("cargo:rerun-if-changed={}", file);
+        }
+    }
+    if let Some(branch) = fs::read_to_string(dir.join(".git/HEAD"))
+        .ok()
+        .and_then(|head| head.strip_prefix("ref: ").map(|r| r.trim().to_string()))
+    {
+        if dir.join(".git").join(&branch).exists() {
+            println!("cargo:rerun-if-changed=.git/{}", branch);
+        }
+    }
+}
+
+/// Runs git with `args` in `dir`, or returns `None` without git or outside
+/// a repository.
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).current_dir(dir).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+}
+
+/// Returns the version of `package` in Cargo.lock, or "unknown".
+fn locked_version(dir: &Path, package: &str) -> String {
+    let lock = fs::read_to_string(dir.join("Cargo.lock")).unwrap_or_default();
+    let name = format!("name = \"{}\"", package);
+    let mut lines = lock.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() == name {
+            let version = lines.next().and_then(|line| line.trim().strip_prefix("version = "));
+            if let Some(version) = version {
+                return version.trim_matches('"').to_string();
+            }
+        }
+    }
+    "unknown".to_string()
+}
@@ -0,0 +1,25 @@
+#!/bin/sh
+# Checks that the wx crate compiles with no default features, with each
+# feature on its own and with all of them, and that every item renders or says
+# why not in each: wx's with its parse tests, the tinywx binary's, with and
+# without its default features, in every output format. Builds without TLS
+# are checked to fail for https, the binary to build with rustls, and its
+# offline lookups of cities with offline-geo.
+set -e
+cd "$(dirname "$0")/../wx"
+
+cargo check --no-default-features
+cargo test --no-default-features --test parse
+for feature in http time icons onecall async native-tls rustls offline-geo; do
+    cargo check --no-default-features --features "$feature"
+    cargo test --no-default-features --features "$feature" --test parse
+done
+cargo test --no-default-features --features http --test client
+cargo check
+cargo check --all-features
+
+cd ..
+cargo test --test matrix
+cargo test --no-default-features --test matrix
+cargo check --no-default-features --features rustls
+cargo test --features offline-geo --test offline
@@ -0,0 +1,73 @@
+#!/bin/sh
+# Times runs of a release build that answer from the cache, like a bar running
+# tinywx every minute, and fails if they take longer than MAX_MS on average.
+# The cache is seeded by one run against a local server answering with the
+# fixture of the wx tests, which is stopped before the timed runs, so that
+# they fail if they make a request.
+# Usage: scripts/startup-time.sh [RUNS] [MAX_MS]
+set -e
+cd "$(dirname "$0")/.."
+
+runs=${1:-20}
+max_ms=${2:-50}
+
+cargo build --release --quiet
+bin=target/release/tinywx
+
+dir=$(mktemp -d)
+server=
+cleanup() {
+    [ -z "$server" ] || kill "$server" 2>/dev/null || true
+    rm -rf "$dir"
+}
+trap cleanup EXIT
+trap 'exit 1' INT TERM
+
+port=$(python3 -c 'import socket; s = socket.socket(); s.bind(("127.0.0.1", 0)); print(s.getsockname()[1])')
+python3 -c '
+import http.server, sys
+body = open(sys.argv[2], "rb").read()
+class Fixture(http.server.BaseHTTPRequestHandler):
+    def do_GET(self):
+        self.send_response(200)
+        self.send_header("Content-Type", "application/json")
+        self.send_header("Content-Length", str(len(body)))
+        self.end_headers()
+        self.wfile.write(body)
+    def log_message(self, *args):
+        pass
+http.server.HTTPServer(("127.0.0.1", int(sys.argv[1])), Fixture).serve_forever()
+' "$port" wx/tests/fixtures/current.json &
+server=$!
+
+export HOME="$dir" XDG_CACHE_HOME="$dir/cache" XDG_CONFIG_HOME="$dir/config"
+export TINYWX_API_KEY=0123456789abcdef0123456789abcdef
+run() {
+    "$bin" --api-url "http://127.0.0.1:$port/" --coords 52.374,4.8897 -d icon temp "$@" >/dev/null
+}
+i=0
+until run --retries 0 2>/dev/null; do
+    i=$((i + 1))
+    if [ "$i" -ge 50 ]; then
+        echo "the server for the cache did not start" >&2
+        exit 1
+    fi
+    sleep 0.1
+done
+kill "$server"
+server=
+
+start=$(date +%s%N)
+i=0
+while [ "$i" -lt "$runs" ]; do
+    run
+    i=$((i + 1))
+done
+end=$(date +%s%N)
+
+average_ms=$(( (end - start) / runs / 1000000 ))
+echo "$runs runs from the cache, $average_ms ms on average, $(wc -c <"$bin") bytes"
+if [ "$average_ms" -gt "$max_ms" ]; then
+    echo "slower than $max_ms ms" >&2
+    exit 1
+fi
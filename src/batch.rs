@@ -0,0 +1,218 @@
+//! Several locations in one run: coordinates from stdin, one location per
+//! line, or the `[[locations]]` of the config file. Each result is labeled
+//! with its location so the lines can be told apart.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::output;
+
+/// An entry of `[[locations]]` in the config file: a city, or coordinates,
+/// as the location keys at the top level take them.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct Location {
+    #[serde(default)]
+    pub city: String,
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub country: String,
+    pub zip: Option<String>,
+    pub coords: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    /// What to label the results with, instead of what `label_from` says.
+    pub name: Option<String>,
+}
+
+impl Location {
+    /// Returns the location as it was written, as in "Austin,TX,US",
+    /// "78701,US" or "30.27,-97.74", for labels and errors.
+    pub fn query(&self) -> String {
+        match (&self.coords, self.lat, self.lon) {
+            (Some(coords), _, _) => coords.trim().to_string(),
+            (None, Some(lat), Some(lon)) => format!("{},{}", lat, lon),
+            _ if self.zip.is_some() => format!("{},{}", self.zip.as_deref().unwrap_or_default().trim(), self.country.trim()),
+            _ => [&self.city, &self.state, &self.country]
+                .iter()
+                .map(|part| part.trim())
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+/// What a result is labeled with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelFrom {
+    /// The name of the city OpenWeatherMap resolved the location to, or the
+    /// query if there is none.
+    #[default]
+    Name,
+    /// The line as it was read.
+    Query,
+}
+
+impl FromStr for LabelFrom {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(LabelFrom::Name),
+            "query" => Ok(LabelFrom::Query),
+            _ => Err(anyhow!("unknown label source '{}'", s)),
+        }
+    }
+}
+
+impl LabelFrom {
+    /// Returns the label of the result for `query`, with `name` the city it
+    /// resolved to.
+    pub fn label(self, query: &str, name: Option<&str>) -> String {
+        match (self, name) {
+            (LabelFrom::Name, Some(name)) => name.to_string(),
+            _ => query.trim().to_string(),
+        }
+    }
+}
+
+/// Renders the `(label, query, output)` results together. Plain lines get
+/// the label in a column of its own, cards a heading, and JSON has the
+/// objects under their labels. Waybar's text has each location's after its
+/// label, the tooltips are one after the other and the classes those of all of
+/// them, once each. A label that is taken already gets the query
+/// after it.
+pub fn render(format: output::Format, results: &[(String, String, String)]) -> String {
+    let mut labels: Vec<String> = Vec::new();
+    for (label, query, _) in results {
+        let label = if labels.contains(label) {
+            format!("{} ({})", label, query.trim())
+        } else {
+            label.clone()
+        };
+        labels.push(label);
+    }
+    let outputs = results.iter().map(|(_, _, output)| output);
+    match format {
+        output::Format::Plain => {
+            let column = labels.iter().map(|label| width(label)).max().unwrap_or(0);
+            labels
+                .iter()
+                .zip(outputs)
+                .map(|(label, output)| format!("{}{}  {}", label, " ".repeat(column - width(label)), output))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        output::Format::Card => labels
+            .iter()
+            .zip(outputs)
+            .map(|(label, output)| format!("{}\n{}", label, output))
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        output::Format::Json | output::Format::RawJson => {
+            let object: Map<String, Value> = labels
+                .into_iter()
+                .zip(outputs)
+                .map(|(label, output)| (label, serde_json::from_str(output).unwrap_or(Value::Null)))
+                .collect();
+            Value::Object(object).to_string()
+        }
+        output::Format::Waybar => {
+            let (mut texts, mut tooltips, mut classes) = (Vec::new(), Vec::new(), Vec::new());
+            for (label, output) in labels.iter().zip(outputs) {
+                let object: Value = serde_json::from_str(output).unwrap_or(Value::Null);
+                let field = |name: &str| object[name].as_str().unwrap_or_default().to_string();
+                texts.push(format!("{} {}", label, field("text")));
+                tooltips.push(format!("{}\n{}", label, field("tooltip")));
+                for class in object["class"].as_array().into_iter().flatten().filter_map(Value::as_str) {
+                    if !classes.iter().any(|c| c == class) {
+                        classes.push(class.to_string());
+                    }
+                }
+            }
+            json!({ "text": texts.join("  "), "tooltip": tooltips.join("\n\n"), "class": classes }).to_string()
+        }
+    }
+}
+
+/// Returns the columns `s` takes in a terminal: two for wide characters, as
+/// in Chinese, Japanese and Korean, and none for combining marks.
+fn width(s: &str) -> usize {
+    s.chars()
+        .map(|c| match u32::from(c) {
+            0x0300..=0x036f | 0x200b..=0x200f | 0xfe00..=0xfe0f => 0,
+            0x1100..=0x115f
+            | 0x2e80..=0xa4cf
+            | 0xac00..=0xd7a3
+            | 0xf900..=0xfaff
+            | 0xfe30..=0xfe4f
+            | 0xff00..=0xff60
+            | 0xffe0..=0xffe6
+            | 0x1f300..=0x1f64f
+            | 0x1f900..=0x1f9ff
+            | 0x20000..=0x3fffd => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(labels: &[(&str, &str)], output: &str) -> Vec<(String, String, String)> {
+        labels
+            .iter()
+            .map(|(label, query)| (label.to_string(), query.to_string(), output.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn labels_taken_already_get_their_query() {
+        let twice = results(&[("Amsterdam", "52.37,4.89"), ("Amsterdam", " 52.44,4.83 ")], "12°");
+        let plain = render(output::Format::Plain, &twice);
+        assert_eq!(plain, "Amsterdam               12°\nAmsterdam (52.44,4.83)  12°");
+        let json = render(output::Format::Json, &results(&[("a", "1"), ("a", "2")], "{\"temp\":\"12°\"}"));
+        assert_eq!(json, "{\"a\":{\"temp\":\"12°\"},\"a (2)\":{\"temp\":\"12°\"}}");
+    }
+
+    #[test]
+    fn columns_of_wide_and_combining_characters() {
+        assert_eq!(width("Zaandam"), 7);
+        assert_eq!(width("東京"), 4);
+        assert_eq!(width("서울"), 4);
+        assert_eq!(width("Mu\u{0308}nchen"), 7);
+        let plain = render(output::Format::Plain, &results(&[("東京", "1"), ("Zaandam", "2")], "12°"));
+        assert_eq!(plain, "東京     12°\nZaandam  12°");
+    }
+
+    #[test]
+    fn waybar_has_each_class_once() {
+        let output = "{\"text\":\"12°\",\"tooltip\":\"rain\",\"class\":[\"rain\",\"cold\"]}";
+        let mut results = results(&[("a", "1"), ("b", "2")], output);
+        results[1].2 = "{\"text\":\"30°\",\"tooltip\":\"sun\",\"class\":[\"hot\",\"rain\"]}".to_string();
+        let waybar: Value = serde_json::from_str(&render(output::Format::Waybar, &results)).unwrap();
+        assert_eq!(waybar["text"], "a 12°  b 30°");
+        assert_eq!(waybar["tooltip"], "a\nrain\n\nb\nsun");
+        assert_eq!(waybar["class"], json!(["rain", "cold", "hot"]));
+    }
+
+    #[test]
+    fn queries_as_they_were_written() {
+        let (city, state, country) = (" Austin ".into(), "TX".into(), "US".into());
+        let city = Location { city, state, country, ..Location::default() };
+        assert_eq!(city.query(), "Austin,TX,US");
+        let zip = Location { zip: Some("78701".into()), country: "US".into(), ..Location::default() };
+        assert_eq!(zip.query(), "78701,US");
+        let coords = Location { lat: Some(30.27), lon: Some(-97.74), ..Location::default() };
+        assert_eq!(coords.query(), "30.27,-97.74");
+        assert_eq!(LabelFrom::Name.label("30.27,-97.74", Some("Austin")), "Austin");
+        assert_eq!(LabelFrom::Query.label(" 30.27,-97.74 ", Some("Austin")), "30.27,-97.74");
+        assert_eq!(LabelFrom::Name.label("30.27,-97.74", None), "30.27,-97.74");
+    }
+}
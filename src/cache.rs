@@ -0,0 +1,266 @@
+//! The response cache: what the API answered, kept in the cache directory
+//! for as long as that kind of data stays fresh. The current weather changes
+//! every few minutes and a place's coordinates hardly ever, so each endpoint
+//! has a time to live of its own, which the `[cache]` table of the config file
+//! can change.
+
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Error, Result};
+use serde::{Deserialize, Deserializer};
+
+use crate::paths;
+
+/// First word of every entry, before its header fields.
+const MAGIC: &str = "tinywx-cache";
+/// Version of the format of entries, after the first word; bumped whenever
+/// it changes, so that entries written before are ignored, not misread.
+const SCHEMA: u32 = 1;
+
+/// The kinds of data the cache tells apart, by endpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Class {
+    Current,
+    Forecast,
+    Pollution,
+    Geocode,
+    OneCall,
+}
+
+impl Class {
+    pub const ALL: [Class; 5] =
+        [Class::Current, Class::Forecast, Class::Pollution, Class::Geocode, Class::OneCall];
+
+    /// Returns the class of responses from the endpoint at `path`, or `None`
+    /// for endpoints that are not cached.
+    pub fn of(path: &str) -> Option<Class> {
+        match path {
+            wx::explain::CURRENT_ENDPOINT => Some(Class::Current),
+            wx::forecast::FORECAST_ENDPOINT => Some(Class::Forecast),
+            wx::pollution::FORECAST_ENDPOINT | wx::pollution::CURRENT_ENDPOINT => Some(Class::Pollution),
+            wx::geo::GEOCODING_ENDPOINT | wx::geo::ZIP_ENDPOINT => Some(Class::Geocode),
+            "data/3.0/onecall" => Some(Class::OneCall),
+            _ => None,
+        }
+    }
+
+    /// Returns the class's name, as in the `[cache]` table and in entries.
+    pub fn name(self) -> &'static str {
+        match self {
+            Class::Current => "current",
+            Class::Forecast => "forecast",
+            Class::Pollution => "pollution",
+            Class::Geocode => "geocode",
+            Class::OneCall => "onecall",
+        }
+    }
+}
+
+/// A time to live, written as a number of seconds or with a unit, as in
+/// "90s", "10m", "1h" or "30d".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ttl(pub Duration);
+
+impl FromStr for Ttl {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || anyhow!("invalid time to live '{}': expected a duration, as in 90s, 10m, 1h or 30d", s);
+        let text = s.trim();
+        let (number, seconds) = match text.char_indices().last().ok_or_else(invalid)? {
+            (i, 's') => (&text[..i], 1),
+            (i, 'm') => (&text[..i], 60),
+            (i, 'h') => (&text[..i], 3600),
+            (i, 'd') => (&text[..i], 86400),
+            _ => (text, 1),
+        };
+        let number: u64 = number.parse().map_err(|_| invalid())?;
+        Ok(Ttl(Duration::from_secs(number.checked_mul(seconds).ok_or_else(invalid)?)))
+    }
+}
+
+impl fmt::Display for Ttl {
+    /// Writes the time to live in the largest unit that divides it.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.0.as_secs();
+        let unit = [(86400, 'd'), (3600, 'h'), (60, 'm')]
+            .into_iter()
+            .find(|(unit, _)| seconds.is_multiple_of(*unit));
+        match unit {
+            Some((unit, suffix)) if seconds > 0 => write!(f, "{}{}", seconds / unit, suffix),
+            _ => write!(f, "{}s", seconds),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Ttl {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Seconds(u64),
+            Text(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Seconds(seconds) => Ok(Ttl(Duration::from_secs(seconds))),
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// The `[cache]` table.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct CacheConfig {
+    /// Whether responses are cached at all; `--no-cache` turns it off.
+    pub enabled: Option<bool>,
+    current: Option<Ttl>,
+    forecast: Option<Ttl>,
+    pollution: Option<Ttl>,
+    geocode: Option<Ttl>,
+    onecall: Option<Ttl>,
+}
+
+impl CacheConfig {
+    /// Returns how long responses of `class` stay fresh.
+    pub fn ttl(&self, class: Class) -> Ttl {
+        let (set, default) = match class {
+            Class::Current => (self.current, 10 * 60),
+            Class::Forecast => (self.forecast, 3600),
+            Class::Pollution => (self.pollution, 3600),
+            Class::Geocode => (self.geocode, 30 * 86400),
+            Class::OneCall => (self.onecall, 3 * 3600),
+        };
+        set.unwrap_or(Ttl(Duration::from_secs(default)))
+    }
+
+    /// Keeps the current weather for at most `max`, as watch mode needs each
+    /// refresh to get new readings.
+    pub fn cap_current(&mut self, max: Duration) {
+        self.current = Some(Ttl(self.ttl(Class::Current).0.min(max)));
+    }
+}
+
+/// The cache in the cache directory, as of a time.
+#[derive(Debug)]
+pub struct Disk {
+    dir: PathBuf,
+    config: CacheConfig,
+}
+
+impl Disk {
+    /// Returns the cache with the times to live of `config`, or `None` if
+    /// there is no cache directory.
+    pub fn new(config: CacheConfig) -> Option<Self> {
+        Some(Disk { dir: dir()?, config })
+    }
+
+    fn path(&self, class: Class, key: &str) -> PathBuf {
+        self.dir.join(class.name()).join(paths::file_name(key))
+    }
+}
+
+impl wx::ResponseCache for Disk {
+    fn get(&self, endpoint: &str, key: &str) -> Option<String> {
+        let class = Class::of(endpoint)?;
+        let entry = Entry::parse(&fs::read_to_string(self.path(class, key)).ok()?)?;
+        let age = crate::unix_now().saturating_sub(entry.fetched);
+        let fresh = entry.class == class && (0..self.config.ttl(class).0.as_secs() as i64).contains(&age);
+        fresh.then_some(entry.body)
+    }
+
+    fn last(&self, endpoint: &str, key: &str) -> Option<(String, i64)> {
+        let class = Class::of(endpoint)?;
+        let entry = Entry::parse(&fs::read_to_string(self.path(class, key)).ok()?)?;
+        (entry.class == class).then_some((entry.body, entry.fetched))
+    }
+
+    fn evict(&self, endpoint: &str, key: &str) {
+        if let Some(class) = Class::of(endpoint) {
+            let _ = fs::remove_file(self.path(class, key));
+        }
+    }
+
+    fn put(&self, endpoint: &str, key: &str, body: &str) {
+        let Some(class) = Class::of(endpoint) else {
+            return;
+        };
+        // A time to live of 0 turns the cache off for the class.
+        if self.config.ttl(class).0.is_zero() {
+            return;
+        }
+        let path = self.path(class, key);
+        let entry = format!("{} {} {} {}\n{}", MAGIC, SCHEMA, class.name(), crate::unix_now(), body);
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&path, entry));
+        if let Err(e) = written {
+            crate::exit::warning!("response not cached: cannot write {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// A cached response: a header line with the version of the format, the
+/// class of the endpoint and when it was fetched, in unix seconds, then the
+/// body.
+struct Entry {
+    class: Class,
+    fetched: i64,
+    body: String,
+}
+
+impl Entry {
+    fn parse(text: &str) -> Option<Entry> {
+        let (header, body) = text.split_once('\n')?;
+        let mut fields = header.split(' ');
+        if fields.next()? != MAGIC || fields.next()?.parse::<u32>().ok()? != SCHEMA {
+            return None;
+        }
+        let name = fields.next()?;
+        let class = Class::ALL.into_iter().find(|class| class.name() == name)?;
+        let fetched = fields.next()?.parse().ok()?;
+        Some(Entry { class, fetched, body: body.to_string() })
+    }
+}
+
+/// Entries of one class in the cache, for `tinywx cache stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub entries: usize,
+    /// Entries still within their time to live.
+    pub fresh: usize,
+    pub bytes: u64,
+}
+
+/// Counts the entries in the cache per class, with the times to live of
+/// `config`.
+pub fn stats(config: &CacheConfig) -> Vec<(Class, Stats)> {
+    let now = crate::unix_now();
+    Class::ALL
+        .into_iter()
+        .map(|class| {
+            let mut stats = Stats::default();
+            let files = dir().and_then(|dir| fs::read_dir(dir.join(class.name())).ok());
+            for file in files.into_iter().flatten().flatten() {
+                let Some(entry) = fs::read_to_string(file.path()).ok().and_then(|text| Entry::parse(&text)) else {
+                    continue;
+                };
+                stats.entries += 1;
+                stats.bytes += entry.body.len() as u64;
+                let age = now.saturating_sub(entry.fetched);
+                if (0..config.ttl(class).0.as_secs() as i64).contains(&age) {
+                    stats.fresh += 1;
+                }
+            }
+            (class, stats)
+        })
+        .collect()
+}
+
+fn dir() -> Option<PathBuf> {
+    Some(paths::cache_dir()?.join("responses"))
+}
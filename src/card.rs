@@ -0,0 +1,125 @@
+//! The card: the items on a few lines, for people rather than bars.
+
+use wx::DataItem;
+
+/// Lines of the card and the items on them. Requested items on none of these
+/// get a line of their own, after them.
+const LINES: &[&[DataItem]] = &[
+    &[DataItem::Icon, DataItem::Temp, DataItem::FeelsLike],
+    &[DataItem::Description],
+    &[DataItem::Wind, DataItem::Humidity],
+    &[DataItem::Sunrise, DataItem::Sunset],
+];
+
+/// Renders the items as a card, with no line wider than `width` columns.
+pub fn render(items: &[(DataItem, String)], width: usize) -> String {
+    let value = |item: DataItem| {
+        items
+            .iter()
+            .find(|(i, value)| *i == item && !value.is_empty())
+            .map(|(i, value)| labeled(*i, value))
+    };
+    let grouped = LINES.iter().map(|line| line.iter().filter_map(|&item| value(item)).collect::<Vec<_>>());
+    let rest = items
+        .iter()
+        .filter(|(item, value)| !value.is_empty() && !LINES.iter().any(|line| line.contains(item)))
+        .map(|(item, value)| vec![labeled(*item, value)]);
+
+    grouped
+        .chain(rest)
+        .filter(|line| !line.is_empty())
+        .map(|line| truncate(&wx::format::single_line(&line, "  "), width))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns the value with a label, where it would not be clear without one.
+fn labeled(item: DataItem, value: &str) -> String {
+    match item {
+        DataItem::Icon | DataItem::Temp | DataItem::Description | DataItem::City => value.to_string(),
+        DataItem::FeelsLike => format!("feels {}", value),
+        _ => format!("{} {}", item.name().replace('_', " "), value),
+    }
+}
+
+/// Cuts `line` to `width` visible characters, ending it with "…" if it was
+/// longer. ANSI escape sequences and polybar tags, as in dimmed icons, take
+/// no room.
+fn truncate(line: &str, width: usize) -> String {
+    let pieces = pieces(line);
+    if pieces.iter().filter(|(_, visible)| *visible).count() <= width {
+        return line.to_string();
+    }
+    let mut truncated = String::new();
+    let mut room = width.saturating_sub(1);
+    for (piece, visible) in pieces {
+        if visible {
+            if room == 0 {
+                continue;
+            }
+            room -= 1;
+        }
+        truncated.push_str(piece);
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// Splits `line` into characters and tags, telling which are visible.
+fn pieces(line: &str) -> Vec<(&str, bool)> {
+    let mut pieces = Vec::new();
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        let end = if rest.starts_with("\x1b[") {
+            rest.find('m').map(|i| (i + 1, false))
+        } else if rest.starts_with("%{") {
+            rest.find('}').map(|i| (i + 1, false))
+        } else {
+            None
+        };
+        let (len, visible) = end.unwrap_or((c.len_utf8(), true));
+        pieces.push((&rest[..len], visible));
+        rest = &rest[len..];
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(items: &[(DataItem, &str)]) -> Vec<(DataItem, String)> {
+        items.iter().map(|&(item, value)| (item, value.to_string())).collect()
+    }
+
+    #[test]
+    fn items_are_grouped_on_their_lines_in_order() {
+        let items = items(&[
+            (DataItem::Humidity, "81%"),
+            (DataItem::City, "Amsterdam"),
+            (DataItem::Temp, "12°"),
+            (DataItem::WindDir, "SW"),
+            (DataItem::Description, ""),
+            (DataItem::Wind, "6"),
+        ]);
+        assert_eq!(render(&items, 80), "12°\nwind 6  humidity 81%\nAmsterdam\nwind dir SW");
+    }
+
+    #[test]
+    fn lines_are_cut_to_the_width() {
+        let items = items(&[(DataItem::Temp, "12°"), (DataItem::FeelsLike, "11°")]);
+        assert_eq!(render(&items, 14), "12°  feels 11°");
+        assert_eq!(render(&items, 13), "12°  feels 1…");
+        assert_eq!(render(&items, 1), "…");
+        assert_eq!(render(&items, 0), "…");
+    }
+
+    #[test]
+    fn escapes_and_tags_take_no_room() {
+        assert_eq!(truncate("\x1b[2m\x1b[0m 12°", 4), "\x1b[2m\x1b[0m 12°");
+        assert_eq!(truncate("\x1b[31m12°\x1b[0m feels", 4), "\x1b[31m12°\x1b[0m…");
+        assert_eq!(truncate("%{F#888}%{F-} 12°", 3), "%{F#888}%{F-} 1…");
+        // An escape that never ends is text.
+        assert_eq!(truncate("\x1b[31", 3), "\x1b[…");
+    }
+}
@@ -0,0 +1,248 @@
+//! Terminal charts of `(time, value)` series, drawn with block characters.
+
+/// Rows of the chart area.
+pub const HEIGHT: usize = 8;
+/// Narrowest chart area, in columns; anything narrower gets a sparkline.
+pub const MIN_WIDTH: usize = 24;
+
+/// Blocks filling one to eight eighths of a cell, from the bottom.
+const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// Background of the shaded series.
+const SHADE: char = '░';
+/// Spacing of the hour axis ticks, in seconds.
+const TICK: i64 = 6 * 3600;
+
+/// A series to chart, with an optional second one shaded behind it.
+pub struct Chart<'a> {
+    /// `(time, value)` pairs in chronological order, times in unix seconds.
+    pub series: &'a [(i64, f64)],
+    /// `(time, fraction)` pairs with fractions from 0 to 1, shaded from the
+    /// bottom up to that fraction of the height.
+    pub shade: Option<&'a [(i64, f64)]>,
+    /// Renders a value of `series`, for the minimum and maximum labels.
+    pub label: &'a dyn Fn(f64) -> String,
+    /// Renders the day starting at a time, to label midnight with instead of
+    /// "00:00".
+    pub day_label: Option<&'a dyn Fn(i64) -> String>,
+    /// Shift from UTC in seconds, for the hour axis.
+    pub offset: i64,
+}
+
+impl Chart<'_> {
+    /// Renders the chart `width` columns wide, labels included, with the
+    /// line drawn across values that are not numbers. If that leaves less
+    /// than [`MIN_WIDTH`] columns, or there are fewer than two values,
+    /// renders a sparkline instead.
+    pub fn render(&self, width: usize) -> String {
+        let Some((min, max)) = range(self.series) else {
+            return String::new();
+        };
+        let series: Vec<(i64, f64)> = self.series.iter().copied().filter(|(_, value)| value.is_finite()).collect();
+        let (min_label, max_label) = ((self.label)(min), (self.label)(max));
+        let gutter = min_label.chars().count().max(max_label.chars().count());
+        let columns = width.saturating_sub(gutter + 2);
+        if columns < MIN_WIDTH || series.len() < 2 {
+            return self.sparkline();
+        }
+
+        let first = series[0].0;
+        let last = series[series.len() - 1].0;
+        let times: Vec<i64> = (0..columns)
+            .map(|c| first + ((last - first) as f64 * c as f64 / (columns - 1) as f64).round() as i64)
+            .collect();
+        // Heights in eighths of a cell, at least one so the line is visible.
+        let levels: Vec<usize> = times
+            .iter()
+            .map(|&t| {
+                let fraction = fraction(interpolate(&series, t), min, max).unwrap_or(0.5);
+                1 + (fraction * (HEIGHT * 8 - 1) as f64).round() as usize
+            })
+            .collect();
+        let shades: Vec<f64> = times
+            .iter()
+            .map(|&t| self.shade.map_or(0.0, |shade| interpolate(shade, t)))
+            .collect();
+
+        let mut lines = Vec::with_capacity(HEIGHT + 2);
+        for row in 0..HEIGHT {
+            let cell = HEIGHT - 1 - row;
+            let (label, axis) = match row {
+                0 => (max_label.as_str(), '┤'),
+                _ if row == HEIGHT - 1 => (min_label.as_str(), '┤'),
+                _ => ("", '│'),
+            };
+            let mut line = format!("{:>width$} {}", label, axis, width = gutter);
+            for (level, shade) in levels.iter().zip(&shades) {
+                let eighths = level.saturating_sub(cell * 8).min(8);
+                line.push(match eighths {
+                    0 if (cell as f64) < shade * HEIGHT as f64 => SHADE,
+                    0 => ' ',
+                    n => BLOCKS[n - 1],
+                });
+            }
+            lines.push(line.trim_end().to_string());
+        }
+
+        // Ticks where the local time passes a multiple of six hours, labeled
+        // when there is room.
+        let mut axis = format!("{:>width$} └", "", width = gutter);
+        let mut hours = " ".repeat(gutter + 2);
+        for (c, &t) in times.iter().enumerate() {
+            let tick = c > 0 && (t + self.offset).div_euclid(TICK) != (times[c - 1] + self.offset).div_euclid(TICK);
+            axis.push(if tick { '┬' } else { '─' });
+            let column = gutter + 2 + c;
+            if tick && hours.chars().count() <= column {
+                let boundary = (t + self.offset).div_euclid(TICK) * TICK;
+                let label = match (boundary.rem_euclid(86400) / 3600, self.day_label) {
+                    (0, Some(day_label)) => day_label(boundary - self.offset),
+                    (hour, _) => format!("{:02}:00", hour),
+                };
+                hours.push_str(&" ".repeat(column - hours.chars().count()));
+                hours.push_str(&label);
+                hours.push(' ');
+            }
+        }
+        lines.push(axis);
+        lines.push(hours.trim_end().to_string());
+        lines.join("\n")
+    }
+
+    /// Renders the series on one line, one block per value, between the
+    /// minimum and maximum labels, and a space for a value that is not a
+    /// number.
+    pub fn sparkline(&self) -> String {
+        let Some((min, max)) = range(self.series) else {
+            return String::new();
+        };
+        let blocks: String = self
+            .series
+            .iter()
+            .map(|&(_, value)| match fraction(value, min, max) {
+                Some(fraction) => BLOCKS[(fraction * 7.0).round() as usize],
+                None => ' ',
+            })
+            .collect();
+        format!("{} {} {}", (self.label)(min), blocks, (self.label)(max))
+    }
+}
+
+/// Returns the smallest and largest value of `series`.
+fn range(series: &[(i64, f64)]) -> Option<(f64, f64)> {
+    let values = series.iter().map(|&(_, value)| value).filter(|v| v.is_finite());
+    let min = values.clone().reduce(f64::min)?;
+    let max = values.reduce(f64::max)?;
+    Some((min, max))
+}
+
+/// Returns where `value` is between `min` and `max`, from 0 to 1, or the
+/// middle if they are the same; `None` for infinities and NaN.
+fn fraction(value: f64, min: f64, max: f64) -> Option<f64> {
+    if !value.is_finite() {
+        return None;
+    }
+    let fraction = if max > min { (value - min) / (max - min) } else { 0.5 };
+    Some(fraction.clamp(0.0, 1.0))
+}
+
+/// Returns the value of `series` at `time`, interpolated linearly between the
+/// nearest pairs and clamped to the first and last.
+fn interpolate(series: &[(i64, f64)], time: i64) -> f64 {
+    let after = series.partition_point(|&(t, _)| t < time);
+    match (after.checked_sub(1).and_then(|i| series.get(i)), series.get(after)) {
+        (Some(&(t0, v0)), Some(&(t1, v1))) if t1 > t0 => v0 + (v1 - v0) * (time - t0) as f64 / (t1 - t0) as f64,
+        (_, Some(&(_, v))) | (Some(&(_, v)), None) => v,
+        (None, None) => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Midnight UTC on 1 May 2024.
+    const MIDNIGHT: i64 = 1_714_521_600;
+
+    fn series(values: &[f64]) -> Vec<(i64, f64)> {
+        values.iter().enumerate().map(|(i, &value)| (MIDNIGHT + i as i64 * 10800, value)).collect()
+    }
+
+    fn degrees(value: f64) -> String {
+        format!("{}°", value.round())
+    }
+
+    fn chart<'a>(series: &'a [(i64, f64)], shade: Option<&'a [(i64, f64)]>) -> Chart<'a> {
+        Chart { series, shade, label: &degrees, day_label: None, offset: 0 }
+    }
+
+    fn temperatures() -> Vec<(i64, f64)> {
+        series(&[4.0, 3.0, 5.0, 9.0, 13.0, 14.0, 11.0, 8.0, 6.0])
+    }
+
+    fn precipitation() -> Vec<(i64, f64)> {
+        series(&[0.0, 0.0, 0.2, 0.6, 0.9, 0.4, 0.1, 0.0, 0.0])
+    }
+
+    #[test]
+    fn too_narrow_for_a_chart() {
+        let temperatures = temperatures();
+        assert_eq!(chart(&temperatures, None).render(20), "3° ▂▁▂▅▇█▆▄▃ 14°");
+    }
+
+    #[test]
+    fn chart_at_30_columns() {
+        let (temperatures, precipitation) = (temperatures(), precipitation());
+        let expected = concat!(
+            "14° ┤            ▂▄▆█▂\n",
+            "    │           ▃█████▅\n",
+            "    │          ▃███████▇▁\n",
+            "    │         ▃██████████▃\n",
+            "    │        ▄████████████▆▂\n",
+            "    │       ▄███████████████▆▂\n",
+            "    │     ▁▄██████████████████\n",
+            " 3° ┤▇▅▃▁▅████████████████████\n",
+            "    └──────┬─────┬─────┬─────┬\n",
+            "           06:00 12:00 18:00 00:00"
+        );
+        assert_eq!(chart(&temperatures, Some(&precipitation)).render(30), expected);
+    }
+
+    #[test]
+    fn chart_at_40_columns() {
+        let (temperatures, precipitation) = (temperatures(), precipitation());
+        let expected = concat!(
+            "14° ┤                 ▂▄▅▆█▅▁\n",
+            "    │               ░▅███████▅▁\n",
+            "    │              ▂▇██████████▅▁\n",
+            "    │            ░▅██████████████▅▁\n",
+            "    │           ▂▇█████████████████▅▂\n",
+            "    │          ▅██████████████████████▅▂\n",
+            "    │       ░▃▇█████████████████████████\n",
+            " 3° ┤▇▅▄▃▁▃▆████████████████████████████\n",
+            "    └─────────┬───────┬────────┬───────┬\n",
+            "              06:00   12:00    18:00   00:00"
+        );
+        assert_eq!(chart(&temperatures, Some(&precipitation)).render(40), expected);
+    }
+
+    #[test]
+    fn values_that_are_not_numbers() {
+        let values = series(&[1.0, f64::INFINITY, 2.0, f64::NAN, f64::NEG_INFINITY, 3.0]);
+        assert_eq!(chart(&values, None).sparkline(), "1° ▁ ▅  █ 3°");
+        let expected = concat!(
+            "3° ┤                      ▂▄▆█\n",
+            "   │                  ▁▃▅█████\n",
+            "   │              ▁▃▅▇████████\n",
+            "   │          ▁▃▅▇████████████\n",
+            "   │        ▂▅████████████████\n",
+            "   │     ▁▄▇██████████████████\n",
+            "   │   ▂▆█████████████████████\n",
+            "1° ┤▁▄▇███████████████████████\n",
+            "   └──────────┬─────────┬─────\n",
+            "              06:00     12:00"
+        );
+        assert_eq!(chart(&values, None).render(30), expected);
+        let none = series(&[f64::NAN, f64::INFINITY]);
+        assert_eq!(chart(&none, None).render(30), "");
+        assert_eq!(chart(&none, None).sparkline(), "");
+    }
+}
@@ -0,0 +1,266 @@
+//! Validation of the config file, for `tinywx config check` and before every
+//! run: keys nothing reads, unknown data items, country codes that are not
+//! two letters and locations given in more than one way, each reported with
+//! the key and the line it is on.
+
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use toml::value::{Table, Value};
+use wx::DataItem;
+
+use crate::{deprecation, layers};
+
+/// Keys of the top level that are not in [`layers::KEYS`], as they have no
+/// environment variable or flag.
+const TOP_LEVEL: &[&str] = &[
+    "api_key_cmd",
+    "locations",
+    "outputs",
+    "colors",
+    "urgency",
+    "quiet_hours",
+    "deliver_after_quiet",
+    "format",
+    "cache",
+];
+
+/// Keys of the tables of the file, as `[format]`.
+const TABLES: &[(&str, &[&str])] = &[
+    ("format", &["unit_style", "time_format", "dry", "items"]),
+    ("cache", &["enabled", "current", "forecast", "pollution", "geocode", "onecall"]),
+    ("colors", &["metric", "imperial", "standard"]),
+    ("urgency", &["metric", "imperial", "standard"]),
+];
+
+/// Keys of each `[[locations]]` entry.
+const LOCATION: &[&str] = &["city", "state", "country", "zip", "coords", "lat", "lon", "name"];
+
+/// Something wrong with the file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Problem {
+    /// The key, with its tables joined by dots, as in "format.unit_stle".
+    pub key: String,
+    /// Line of the file the key is on, from 1, if it could be found.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}: {}", line, self.key, self.message),
+            None => write!(f, "{}: {}", self.key, self.message),
+        }
+    }
+}
+
+/// Reads the config file at `path` and returns what is wrong with it, if
+/// anything. Deprecated keys are not problems, as they still work.
+pub fn file(path: &Path) -> Result<Vec<Problem>> {
+    let contents = std::fs::read_to_string(path).map_err(|e| anyhow!("cannot read {}: {}", path.display(), e))?;
+    let mut table: Table = toml::from_str(&contents).map_err(|e| anyhow!("cannot read {}: {}", path.display(), e))?;
+    deprecation::migrate_file(&mut table, &path.display().to_string());
+    Ok(problems(&contents, &table))
+}
+
+/// Returns what is wrong with `table`, as read from `contents`.
+fn problems(contents: &str, table: &Table) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let find = |header: Option<&str>, nth: usize, key: &str| line_of(contents, header, nth, key);
+    for key in table.keys() {
+        let known = layers::KEYS.iter().any(|k| k.name.split('.').next() == Some(key.as_str()))
+            || TOP_LEVEL.contains(&key.as_str());
+        if !known {
+            problems.push(unknown(key, find(None, 0, key)));
+        }
+    }
+    for (name, keys) in TABLES {
+        let Some(Value::Table(inner)) = table.get(*name) else {
+            continue;
+        };
+        for key in inner.keys().filter(|key| !keys.contains(&key.as_str())) {
+            problems.push(unknown(&format!("{}.{}", name, key), find(Some(name), 0, key)));
+        }
+    }
+    if let Some(Value::Array(items)) = table.get("data") {
+        for item in items.iter().filter_map(Value::as_str) {
+            if let Err(e) = item.parse::<DataItem>() {
+                problems.push(Problem {
+                    key: "data".to_string(),
+                    line: find(None, 0, "data"),
+                    message: e.to_string(),
+                });
+            }
+        }
+    }
+    problems.extend(location(table, "", &|key| find(None, 0, key)));
+    if let Some(Value::Array(locations)) = table.get("locations") {
+        for (nth, entry) in locations.iter().enumerate() {
+            let Value::Table(entry) = entry else {
+                continue;
+            };
+            let prefix = format!("locations[{}].", nth);
+            for key in entry.keys().filter(|key| !LOCATION.contains(&key.as_str())) {
+                problems.push(unknown(&format!("{}{}", prefix, key), find(Some("locations"), nth, key)));
+            }
+            problems.extend(location(entry, &prefix, &|key| find(Some("locations"), nth, key)));
+        }
+    }
+    problems.sort_by_key(|problem| problem.line.unwrap_or(usize::MAX));
+    problems
+}
+
+fn unknown(key: &str, line: Option<usize>) -> Problem {
+    Problem {
+        key: key.to_string(),
+        line,
+        message: "unknown key".to_string(),
+    }
+}
+
+/// Checks the location keys of `table`, which are named after `prefix`:
+/// the country code, and that the location is given one way only.
+fn location(table: &Table, prefix: &str, find: &dyn Fn(&str) -> Option<usize>) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let problem = |key: &str, message: &str| Problem {
+        key: format!("{}{}", prefix, key),
+        line: find(key),
+        message: message.to_string(),
+    };
+    if let Some(country) = table.get("country").and_then(Value::as_str) {
+        let country = country.trim();
+        if !country.is_empty() && (country.len() != 2 || !country.chars().all(|c| c.is_ascii_alphabetic())) {
+            let message = format!("'{}' is not a country code: expected two letters, as in nl or us", country);
+            problems.push(problem("country", &message));
+        }
+    }
+    let set = |key: &str| match table.get(key) {
+        Some(Value::String(s)) => !s.trim().is_empty(),
+        Some(_) => true,
+        None => false,
+    };
+    let ways: Vec<&str> = ["city", "zip", "coords", "lat"].into_iter().filter(|key| set(key)).collect();
+    if set("coords") && (set("lat") || set("lon")) {
+        problems.push(problem("coords", "specify coordinates either as coords or as lat and lon, not both"));
+    } else if ways.len() > 1 {
+        let message = format!("specify only one of city, zip, coords or lat and lon, not {}", ways.join(" and "));
+        problems.push(problem(ways[1], &message));
+    }
+    if set("lat") != set("lon") {
+        let key = if set("lat") { "lat" } else { "lon" };
+        problems.push(problem(key, "lat and lon must be given together"));
+    }
+    problems
+}
+
+/// Returns the line, from 1, setting `key` in the table `header`, as
+/// "format" for `[format]`, or at the top level for `None`. For an array of
+/// tables, `nth` tells which of its entries.
+fn line_of(contents: &str, header: Option<&str>, nth: usize, key: &str) -> Option<usize> {
+    let mut current = None;
+    let mut entries = 0;
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            let name = line.trim_matches(|c| c == '[' || c == ']').trim();
+            if Some(name) == header {
+                entries += 1;
+            }
+            current = Some(name);
+            continue;
+        }
+        let Some(rest) = line.strip_prefix(key) else {
+            continue;
+        };
+        if current == header && entries == nth + usize::from(header.is_some()) && rest.trim_start().starts_with('=') {
+            return Some(number + 1);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the problems of `contents`, each as it is printed.
+    fn check(contents: &str) -> Vec<String> {
+        let table: Table = toml::from_str(contents).unwrap();
+        problems(contents, &table).iter().map(Problem::to_string).collect()
+    }
+
+    #[test]
+    fn a_clean_file_has_no_problems() {
+        let contents = "city = \"amsterdam\"\ncountry = \"nl\"\ndata = [\"temp\", \"humidity\"]\n\n\
+            [format]\nunit_style = \"spaced\"\n\n[[locations]]\ncoords = \"52.374,4.8897\"\nname = \"home\"\n";
+        assert!(check(contents).is_empty(), "{:?}", check(contents));
+    }
+
+    #[test]
+    fn unknown_keys_are_named_with_their_tables() {
+        let contents = "city = \"amsterdam\"\ncolour = \"always\"\n\n[format]\nunit_stle = \"spaced\"\n\n\
+            [[locations]]\ncity = \"paris\"\n\n[[locations]]\ncity = \"rome\"\nlabel = \"Rome\"\n";
+        let expected = [
+            "line 2: colour: unknown key",
+            "line 5: format.unit_stle: unknown key",
+            "line 12: locations[1].label: unknown key",
+        ];
+        assert_eq!(check(contents), expected);
+    }
+
+    #[test]
+    fn unknown_items_get_a_suggestion() {
+        let problems = check("city = \"amsterdam\"\ndata = [\"temperature\", \"humidity\", \"zzz\"]\n");
+        let expected = [
+            "line 2: data: unknown data item 'temperature', did you mean 'temp'?",
+            "line 2: data: unknown data item 'zzz'",
+        ];
+        assert_eq!(problems, expected);
+    }
+
+    #[test]
+    fn country_codes_are_two_letters() {
+        for country in ["nl", "US", " de ", ""] {
+            assert!(check(&format!("city = \"x\"\ncountry = \"{}\"\n", country)).is_empty(), "{:?}", country);
+        }
+        for country in ["nld", "n1", "n"] {
+            let expected = format!(
+                "line 2: country: '{}' is not a country code: expected two letters, as in nl or us",
+                country
+            );
+            assert_eq!(check(&format!("city = \"x\"\ncountry = \"{}\"\n", country)), [expected]);
+        }
+    }
+
+    #[test]
+    fn a_location_is_given_one_way() {
+        assert_eq!(
+            check("city = \"paris\"\nzip = \"75001\"\n"),
+            ["line 2: zip: specify only one of city, zip, coords or lat and lon, not city and zip"]
+        );
+        assert_eq!(
+            check("coords = \"1,2\"\nlat = 1.0\nlon = 2.0\n"),
+            ["line 1: coords: specify coordinates either as coords or as lat and lon, not both"]
+        );
+        assert_eq!(check("lat = 1.0\n"), ["line 1: lat: lat and lon must be given together"]);
+        assert!(check("lat = 1.0\nlon = 2.0\ncity = \"\"\n").is_empty());
+        let contents = "[[locations]]\ncity = \"paris\"\n\n[[locations]]\nzip = \"75001\"\ncoords = \"1,2\"\n";
+        let expected = "line 6: locations[1].coords: specify only one of city, zip, coords or lat and lon, \
+            not zip and coords";
+        assert_eq!(check(contents), [expected]);
+    }
+
+    #[test]
+    fn lines_are_of_the_key_in_its_table() {
+        let contents = "# city = \"x\"\ncity = \"y\"\n\n[format]\ncity = \"z\"\n\n[[locations]]\ncity = \"a\"\n\n\
+            [[locations]]\n  city   = \"b\"\n";
+        assert_eq!(line_of(contents, None, 0, "city"), Some(2));
+        assert_eq!(line_of(contents, Some("format"), 0, "city"), Some(5));
+        assert_eq!(line_of(contents, Some("locations"), 0, "city"), Some(8));
+        assert_eq!(line_of(contents, Some("locations"), 1, "city"), Some(11));
+        assert_eq!(line_of(contents, Some("locations"), 2, "city"), None);
+        assert_eq!(line_of("cityname = 1\n", None, 0, "city"), None);
+    }
+}
@@ -0,0 +1,23 @@
+//! Places from the database of large cities built into tinywx with the
+//! offline-geo feature, for `--offline`, and for when the geocoding API cannot
+//! be reached.
+
+use anyhow::Result;
+
+/// Finds up to `limit` places `location` could be, most likely first, in
+/// the database of cities. It has no states, so only the city and the
+/// country of the location tell, and nothing is found for a zip code,
+/// coordinates or a city id.
+#[cfg(feature = "offline-geo")]
+pub fn places(location: &wx::Location, limit: usize) -> Result<Vec<wx::GeoResult>> {
+    let wx::Location::City { city, country, .. } = location else {
+        return Ok(Vec::new());
+    };
+    let country = Some(country.as_str()).filter(|country| !country.trim().is_empty());
+    Ok(wx::cities::find(city, country, limit).into_iter().map(wx::GeoResult::from).collect())
+}
+
+#[cfg(not(feature = "offline-geo"))]
+pub fn places(_: &wx::Location, _: usize) -> Result<Vec<wx::GeoResult>> {
+    anyhow::bail!("tinywx was built without the offline-geo feature, which --offline needs")
+}
@@ -0,0 +1,122 @@
+//! City ids: OpenWeatherMap recommends querying by city id rather than by
+//! name, so the id a name resolves to is kept on disk and used from then on.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+
+use crate::paths;
+
+/// How to query a city.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Query {
+    Name,
+    Id(u64),
+}
+
+/// Result of a query, as far as the stored id is concerned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The city was found; the response carries its id, if it has one.
+    Found(Option<u64>),
+    /// The API answered 404.
+    NotFound,
+    /// Any other failure.
+    Failed,
+}
+
+/// What to do with the stored id after a query.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Update {
+    Keep,
+    Store(u64),
+    /// Drop the id, and query by name instead.
+    Forget,
+}
+
+/// Decides how to query a city, given the stored id.
+pub fn query(prefer_id: bool, stored: Option<u64>) -> Query {
+    match stored {
+        Some(id) if prefer_id => Query::Id(id),
+        _ => Query::Name,
+    }
+}
+
+/// Decides what to do with the stored id after querying with `query`. An id
+/// that is not found (anymore) is forgotten; a name query that resolves to a
+/// new id stores it.
+pub fn update(query: Query, stored: Option<u64>, outcome: Outcome) -> Update {
+    match (query, outcome) {
+        (Query::Id(_), Outcome::NotFound) => Update::Forget,
+        (Query::Name, Outcome::Found(Some(id))) if stored != Some(id) => Update::Store(id),
+        _ => Update::Keep,
+    }
+}
+
+/// Returns the id stored for `key`, if any.
+pub fn load(key: &str) -> Option<u64> {
+    read().get(key).copied()
+}
+
+/// Stores `id` for `key`, or removes the stored id if `id` is `None`.
+pub fn save(key: &str, id: Option<u64>) -> Result<()> {
+    let mut ids = read();
+    match id {
+        Some(id) => ids.insert(key.to_string(), id),
+        None => ids.remove(key),
+    };
+
+    let path = path().ok_or_else(|| anyhow!("no cache directory (is $HOME set?)"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(&ids)?)
+        .map_err(|e| anyhow!("cannot write {}: {}", path.display(), e))
+}
+
+/// Reads all stored ids. A missing or unreadable file is treated as empty.
+fn read() -> BTreeMap<String, u64> {
+    path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn path() -> Option<PathBuf> {
+    Some(paths::cache_dir()?.join("city_ids.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_ids_are_queried_when_preferred() {
+        assert_eq!(query(true, Some(2759794)), Query::Id(2759794));
+        assert_eq!(query(true, None), Query::Name);
+        assert_eq!(query(false, Some(2759794)), Query::Name);
+        assert_eq!(query(false, None), Query::Name);
+    }
+
+    #[test]
+    fn names_that_resolve_store_their_id() {
+        assert_eq!(update(Query::Name, None, Outcome::Found(Some(1))), Update::Store(1));
+        assert_eq!(update(Query::Name, Some(2), Outcome::Found(Some(1))), Update::Store(1));
+        assert_eq!(update(Query::Name, Some(1), Outcome::Found(Some(1))), Update::Keep);
+        assert_eq!(update(Query::Name, None, Outcome::Found(None)), Update::Keep);
+        assert_eq!(update(Query::Name, None, Outcome::NotFound), Update::Keep);
+        assert_eq!(update(Query::Name, Some(1), Outcome::Failed), Update::Keep);
+    }
+
+    #[test]
+    fn ids_not_found_are_forgotten() {
+        assert_eq!(update(Query::Id(1), Some(1), Outcome::NotFound), Update::Forget);
+        // Other failures may pass; the id is kept for the next run.
+        assert_eq!(update(Query::Id(1), Some(1), Outcome::Failed), Update::Keep);
+        assert_eq!(update(Query::Id(1), Some(1), Outcome::Found(Some(1))), Update::Keep);
+        // An id query never stores what it finds.
+        assert_eq!(update(Query::Id(1), Some(1), Outcome::Found(Some(2))), Update::Keep);
+    }
+}
@@ -0,0 +1,276 @@
+//! Colors in the terminal: the temperatures tinted from blue through green
+//! and yellow to red as they get warmer, and the icon and the description in
+//! the color of the weather, grey for clouds, blue for rain and white for
+//! snow. The temperatures the tint changes at are in the `[colors]` table.
+
+use std::env;
+use std::io::{self, IsTerminal};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error};
+use serde::{Deserialize, Deserializer};
+use wx::condition::Condition;
+use wx::{DataItem, Units};
+
+const BLUE: &str = "34";
+const GREEN: &str = "32";
+const YELLOW: &str = "33";
+const RED: &str = "31";
+const GREY: &str = "90";
+const WHITE: &str = "97";
+
+/// When to color the output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum When {
+    /// When stdout is a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for When {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "auto" => Ok(When::Auto),
+            "always" => Ok(When::Always),
+            "never" => Ok(When::Never),
+            _ => Err(anyhow!("invalid color '{}': expected auto, always or never", s)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for When {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl When {
+    /// Returns whether to color what is printed to stdout.
+    pub fn enabled(self) -> bool {
+        match self {
+            When::Auto => io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none(),
+            When::Always => true,
+            When::Never => false,
+        }
+    }
+}
+
+/// The temperatures at which the tint turns green, yellow and red, in
+/// increasing order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Thresholds([f64; 3]);
+
+impl<'de> Deserialize<'de> for Thresholds {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let thresholds = <[f64; 3]>::deserialize(deserializer)?;
+        if !thresholds.windows(2).all(|pair| pair[0] < pair[1]) {
+            return Err(serde::de::Error::custom(format!(
+                "invalid thresholds {:?}: expected three increasing temperatures, as in [10, 20, 28]",
+                thresholds
+            )));
+        }
+        Ok(Thresholds(thresholds))
+    }
+}
+
+/// The `[colors]` table: the thresholds of the tint for each of the units.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ColorConfig {
+    metric: Option<Thresholds>,
+    imperial: Option<Thresholds>,
+    /// In Kelvin; the metric ones converted if not set.
+    standard: Option<Thresholds>,
+}
+
+impl ColorConfig {
+    /// Returns the palette for temperatures in `units`.
+    pub fn palette(&self, units: Units) -> Palette {
+        let metric = self.metric.unwrap_or(Thresholds([10.0, 20.0, 28.0]));
+        let thresholds = match units {
+            Units::Metric => metric,
+            Units::Imperial => self.imperial.unwrap_or(Thresholds([50.0, 68.0, 82.0])),
+            Units::Standard => self.standard.unwrap_or(Thresholds(metric.0.map(|t| t + 273.15))),
+        };
+        Palette { thresholds }
+    }
+}
+
+/// How the items are colored.
+#[derive(Clone, Copy, Debug)]
+pub struct Palette {
+    thresholds: Thresholds,
+}
+
+impl Palette {
+    /// Returns the items, the ones that have a color in it. `number` gives the
+    /// reading of a temperature item and `condition` the weather the icon or
+    /// the description of an item is of.
+    pub fn paint(
+        &self,
+        items: &[(DataItem, String)],
+        number: impl Fn(DataItem) -> Option<f64>,
+        condition: impl Fn(DataItem) -> Condition,
+    ) -> Vec<(DataItem, String)> {
+        items
+            .iter()
+            .map(|(x, value)| {
+                let code = match x {
+                    _ if value.is_empty() => None,
+                    DataItem::Temp | DataItem::FeelsLike | DataItem::TempMin | DataItem::TempMax => {
+                        number(*x).map(|t| self.temperature(t))
+                    }
+                    DataItem::Icon | DataItem::Description => of_condition(condition(*x)),
+                    _ => None,
+                };
+                match code {
+                    Some(code) => (*x, format!("\x1b[{}m{}\x1b[0m", code, value)),
+                    None => (*x, value.clone()),
+                }
+            })
+            .collect()
+    }
+
+    fn temperature(&self, t: f64) -> &'static str {
+        [BLUE, GREEN, YELLOW, RED][self.thresholds.0.iter().filter(|&&threshold| t >= threshold).count()]
+    }
+}
+
+/// Returns the color of a condition, or `None` for those that have none.
+fn of_condition(condition: Condition) -> Option<&'static str> {
+    match condition {
+        Condition::Clouds(_) => Some(GREY),
+        Condition::Drizzle(_) | Condition::Rain(_) | Condition::Thunderstorm(_) => Some(BLUE),
+        Condition::Snow(_) => Some(WHITE),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wx::format::{below_freezing, fixed, with_unit, Quantity, UnitStyle};
+
+    /// Temperatures around zero, with how they show, their tint with a
+    /// threshold at zero and whether they are below freezing. The tint and
+    /// freezing compare the unrounded value, so from -0.4° up to -0° a
+    /// temperature shows as 0° yet is tinted and counted as below zero.
+    #[test]
+    fn temperatures_around_zero() {
+        let cases = [
+            (-0.6, "-1°", BLUE),
+            (-0.5, "-1°", BLUE),
+            (-0.4, "0°", BLUE),
+            (-0.1, "0°", BLUE),
+            (-0.0, "0°", GREEN),
+            (0.0, "0°", GREEN),
+            (0.1, "0°", GREEN),
+            (0.4, "0°", GREEN),
+            (0.5, "1°", GREEN),
+            (0.6, "1°", GREEN),
+        ];
+        let style = UnitStyle::default();
+        for units in [Units::Metric, Units::Imperial] {
+            let palette = Palette { thresholds: Thresholds([0.0, 20.0, 28.0]) };
+            let freezing = if units == Units::Metric { 0.0 } else { 32.0 };
+            for (t, shown, tint) in cases {
+                assert_eq!(with_unit(&fixed(t, 0), Quantity::Temperature, units, style), shown, "{} in {:?}", t, units);
+                assert_eq!(palette.temperature(t), tint, "{} in {:?}", t, units);
+                assert_eq!(below_freezing(t, units), t < freezing, "{} in {:?}", t, units);
+                assert_eq!(below_freezing(t, units), units == Units::Imperial || tint == BLUE, "{}", t);
+            }
+            // The same around the freezing point of the units.
+            let palette = Palette { thresholds: Thresholds([freezing, 68.0, 82.0]) };
+            for (t, _, tint) in cases {
+                let t = t + freezing;
+                assert_eq!(palette.temperature(t), tint, "{} in {:?}", t, units);
+                assert_eq!(below_freezing(t, units), tint == BLUE, "{} in {:?}", t, units);
+            }
+        }
+    }
+
+    /// Returns the palette of the default thresholds for `units`.
+    fn palette(units: Units) -> Palette {
+        ColorConfig::default().palette(units)
+    }
+
+    #[test]
+    fn temperatures_tint_from_blue_to_red() {
+        let cases = [
+            (Units::Metric, [(-5.0, BLUE), (9.9, BLUE), (10.0, GREEN), (19.5, GREEN), (24.0, YELLOW), (28.0, RED)]),
+            (Units::Imperial, [(14.0, BLUE), (49.9, BLUE), (50.0, GREEN), (67.0, GREEN), (75.0, YELLOW), (90.0, RED)]),
+            (
+                Units::Standard,
+                [(268.0, BLUE), (283.0, BLUE), (283.15, GREEN), (293.0, GREEN), (297.0, YELLOW), (301.15, RED)],
+            ),
+        ];
+        for (units, temperatures) in cases {
+            for (t, tint) in temperatures {
+                assert_eq!(palette(units).temperature(t), tint, "{} in {:?}", t, units);
+            }
+        }
+    }
+
+    #[test]
+    fn items_are_wrapped_in_escape_sequences() {
+        let items = [
+            (DataItem::Temp, "24°".to_string()),
+            (DataItem::FeelsLike, "8°".to_string()),
+            (DataItem::Description, "light rain".to_string()),
+            (DataItem::Humidity, "81%".to_string()),
+            (DataItem::Icon, String::new()),
+        ];
+        let number = |x| match x {
+            DataItem::Temp => Some(24.0),
+            DataItem::FeelsLike => Some(8.0),
+            _ => None,
+        };
+        let painted = palette(Units::Metric).paint(&items, number, |_| Condition::Rain(500));
+        let values: Vec<&str> = painted.iter().map(|(_, value)| value.as_str()).collect();
+        // What has no color, and what is empty, is left as it is.
+        assert_eq!(values, ["\x1b[33m24°\x1b[0m", "\x1b[34m8°\x1b[0m", "\x1b[34mlight rain\x1b[0m", "81%", ""]);
+    }
+
+    #[test]
+    fn conditions_have_their_colors() {
+        let cases = [
+            (Condition::Clouds(803), Some(GREY)),
+            (Condition::Rain(502), Some(BLUE)),
+            (Condition::Drizzle(300), Some(BLUE)),
+            (Condition::Thunderstorm(211), Some(BLUE)),
+            (Condition::Snow(601), Some(WHITE)),
+            (Condition::Clear, None),
+            (Condition::Atmosphere(741), None),
+        ];
+        for (condition, code) in cases {
+            assert_eq!(of_condition(condition), code, "{:?}", condition);
+        }
+    }
+
+    #[test]
+    fn thresholds_of_the_colors_table() {
+        let colors: ColorConfig = toml::from_str("metric = [0, 15, 25]\nimperial = [32, 59, 77]\n").unwrap();
+        assert_eq!(colors.palette(Units::Metric).temperature(14.0), GREEN);
+        assert_eq!(colors.palette(Units::Imperial).temperature(60.0), YELLOW);
+        // Kelvin follows the metric thresholds unless it has its own.
+        assert_eq!(colors.palette(Units::Standard).temperature(290.0), YELLOW);
+        for table in ["metric = [20, 10, 28]", "metric = [10, 10, 28]", "metric = [10, 20]"] {
+            assert!(toml::from_str::<ColorConfig>(table).is_err(), "{}", table);
+        }
+    }
+
+    #[test]
+    fn when_by_name() {
+        assert_eq!("always".parse::<When>().unwrap(), When::Always);
+        assert_eq!(" never ".parse::<When>().unwrap(), When::Never);
+        assert_eq!("auto".parse::<When>().unwrap(), When::Auto);
+        let e = "yes".parse::<When>().unwrap_err();
+        assert_eq!(e.to_string(), "invalid color 'yes': expected auto, always or never");
+        assert!(When::Always.enabled());
+        assert!(!When::Never.enabled());
+    }
+}
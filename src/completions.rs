@@ -0,0 +1,391 @@
+//! Shell completions for bash, zsh and fish, generated from the definition of
+//! the command line, so that they offer every subcommand and flag there is
+//! and the possible values of flags, like the items `-d` takes.
+
+use std::fmt::Write;
+
+use clap::{App, Arg};
+
+/// The shells completions are generated for, by name.
+pub const SHELLS: [&str; 3] = ["bash", "zsh", "fish"];
+
+/// A command, with the names of the subcommands that lead to it.
+struct Command<'a> {
+    path: Vec<&'a str>,
+    app: &'a App<'static>,
+}
+
+impl Command<'_> {
+    /// Returns the name of the command's function in the scripts, as in
+    /// `tinywx__config__show`.
+    fn function(&self) -> String {
+        self.path.join("__")
+    }
+}
+
+/// What completions know of a flag.
+struct Flag {
+    short: Option<char>,
+    long: Option<&'static str>,
+    help: String,
+    /// Whether the flag takes a value.
+    value: bool,
+    /// Whether the flag takes several values, as `-d` does.
+    multiple: bool,
+    /// Whether the flag can be given more than once.
+    repeated: bool,
+    /// The values the flag takes, if they are a known few.
+    values: Vec<&'static str>,
+    /// Whether the value is a path.
+    path: bool,
+    /// The name of the value, as in `FILE`.
+    value_name: &'static str,
+}
+
+impl Flag {
+    fn of(arg: &Arg<'static>) -> Flag {
+        let value_name = arg.get_value_names().and_then(|names| names.first().copied()).unwrap_or("VALUE");
+        Flag {
+            short: arg.get_short(),
+            long: arg.get_long(),
+            help: arg.get_help().unwrap_or_default().to_string(),
+            value: arg.is_takes_value_set(),
+            multiple: arg.is_multiple_values_set(),
+            repeated: arg.is_multiple_occurrences_set(),
+            values: arg
+                .get_possible_values()
+                .unwrap_or_default()
+                .iter()
+                .filter(|value| !value.is_hide_set())
+                .map(|value| value.get_name())
+                .collect(),
+            path: matches!(value_name, "FILE" | "PATH"),
+            value_name,
+        }
+    }
+
+    /// Returns the ways to write the flag, as in `-d` and `--data`.
+    fn names(&self) -> Vec<String> {
+        let short = self.short.map(|short| format!("-{}", short));
+        let long = self.long.map(|long| format!("--{}", long));
+        short.into_iter().chain(long).collect()
+    }
+}
+
+/// Returns the script completing `command` in `shell`, one of [`SHELLS`].
+pub fn generate(shell: &str, mut command: App<'static>) -> String {
+    // Building adds what clap adds itself, like --help and the help
+    // subcommand.
+    command.build();
+    let mut commands = Vec::new();
+    collect(&command, vec![command.get_name()], &mut commands);
+    match shell {
+        "bash" => bash(&commands),
+        "zsh" => zsh(&commands),
+        _ => fish(&commands),
+    }
+}
+
+/// Adds `app`, at `path`, and then its subcommands to `commands`.
+fn collect<'a>(app: &'a App<'static>, path: Vec<&'a str>, commands: &mut Vec<Command<'a>>) {
+    commands.push(Command { path: path.clone(), app });
+    for sub in subcommands(app) {
+        let mut sub_path = path.clone();
+        sub_path.push(sub.get_name());
+        collect(sub, sub_path, commands);
+    }
+}
+
+/// Returns the flags of `app`.
+fn flags(app: &App<'static>) -> Vec<Flag> {
+    app.get_arguments()
+        .filter(|arg| !arg.is_positional() && !arg.is_hide_set())
+        .map(Flag::of)
+        .collect()
+}
+
+/// Returns the values of the positional arguments of `app`, as in the shells
+/// `completions` takes.
+fn positional_values(app: &App<'static>) -> Vec<&'static str> {
+    app.get_arguments()
+        .filter(|arg| arg.is_positional())
+        .flat_map(|arg| Flag::of(arg).values)
+        .collect()
+}
+
+/// Returns the subcommands directly under `app`.
+fn subcommands<'a>(app: &'a App<'static>) -> impl Iterator<Item = &'a App<'static>> {
+    app.get_subcommands().filter(|sub| !sub.is_hide_set())
+}
+
+/// Quotes `text` in single quotes, for all three shells.
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+fn bash(commands: &[Command<'_>]) -> String {
+    let mut script = String::new();
+    let root = &commands[0].path[0];
+    let _ = writeln!(script, "_{}() {{", root);
+    script.push_str("    local cur=${COMP_WORDS[COMP_CWORD]}\n");
+    let _ = writeln!(script, "    local cmd={} word i", root);
+    script.push_str("    # The subcommand so far, from the words naming one.\n");
+    script.push_str("    for ((i = 1; i < COMP_CWORD; i++)); do\n");
+    script.push_str("        word=${COMP_WORDS[i]}\n");
+    script.push_str("        case \"$cmd:$word\" in\n");
+    for command in commands {
+        for sub in subcommands(command.app) {
+            let _ = writeln!(
+                script,
+                "            {}:{}) cmd={}__{} ;;",
+                command.function(),
+                sub.get_name(),
+                command.function(),
+                sub.get_name()
+            );
+        }
+    }
+    script.push_str("        esac\n");
+    script.push_str("    done\n");
+    script.push_str("    # The last flag before the cursor, and how many words came after it.\n");
+    script.push_str("    local flag=\"\" after=0\n");
+    script.push_str("    for ((i = COMP_CWORD - 1; i > 0; i--)); do\n");
+    script.push_str("        if [[ ${COMP_WORDS[i]} == -* ]]; then\n");
+    script.push_str("            flag=${COMP_WORDS[i]}\n");
+    script.push_str("            break\n");
+    script.push_str("        fi\n");
+    script.push_str("        after=$((after + 1))\n");
+    script.push_str("    done\n");
+    script.push_str("    case \"$cmd:$flag\" in\n");
+    for command in commands {
+        for flag in flags(command.app).iter().filter(|flag| flag.value) {
+            let pattern: Vec<String> =
+                flag.names().iter().map(|name| format!("{}:{}", command.function(), name)).collect();
+            let complete = if !flag.values.is_empty() {
+                format!("COMPREPLY=($(compgen -W {} -- \"$cur\"))", quote(&flag.values.join(" ")))
+            } else if flag.path {
+                "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string()
+            } else {
+                "COMPREPLY=()".to_string()
+            };
+            let condition = if flag.multiple { "[[ $cur != -* ]]" } else { "[[ $after -eq 0 ]]" };
+            let _ = writeln!(script, "        {})", pattern.join("|"));
+            let _ = writeln!(script, "            if {}; then", condition);
+            let _ = writeln!(script, "                {}", complete);
+            script.push_str("                return\n");
+            script.push_str("            fi\n");
+            script.push_str("            ;;\n");
+        }
+    }
+    script.push_str("    esac\n");
+    script.push_str("    case \"$cmd\" in\n");
+    for command in commands {
+        let words: Vec<String> = flags(command.app)
+            .iter()
+            .flat_map(Flag::names)
+            .chain(subcommands(command.app).map(|sub| sub.get_name().to_string()))
+            .chain(positional_values(command.app).into_iter().map(str::to_string))
+            .collect();
+        let _ = writeln!(
+            script,
+            "        {}) COMPREPLY=($(compgen -W {} -- \"$cur\")) ;;",
+            command.function(),
+            quote(&words.join(" "))
+        );
+    }
+    script.push_str("    esac\n");
+    script.push_str("}\n\n");
+    let _ = writeln!(script, "complete -F _{} {}", root, root);
+    script
+}
+
+/// Escapes `text` for the brackets of an `_arguments` spec.
+fn zsh_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('[', "\\[").replace(']', "\\]")
+}
+
+fn zsh(commands: &[Command<'_>]) -> String {
+    let mut script = String::new();
+    let root = &commands[0].path[0];
+    let _ = writeln!(script, "#compdef {}\n", root);
+    for command in commands {
+        let _ = writeln!(script, "_{}() {{", command.function());
+        script.push_str("    local context state state_descr line\n");
+        script.push_str("    typeset -A opt_args\n");
+        script.push_str("    _arguments -s -S \\\n");
+        for flag in flags(command.app) {
+            let names = flag.names();
+            let exclusive = match (names.len(), flag.repeated) {
+                (_, true) => "*".to_string(),
+                (1, false) => String::new(),
+                _ => format!("({})", names.join(" ")),
+            };
+            let mut spec = format!("[{}]", zsh_escape(&flag.help));
+            if flag.value {
+                let action = if !flag.values.is_empty() {
+                    format!("({})", flag.values.join(" "))
+                } else if flag.path {
+                    "_files".to_string()
+                } else {
+                    " ".to_string()
+                };
+                // Values up to the next flag, for those that take several.
+                let until = if flag.multiple { "*-*:" } else { "" };
+                let _ = write!(spec, ":{}{}:{}", until, flag.value_name, action);
+            }
+            let written = match names.as_slice() {
+                [one] => quote(&format!("{}{}{}", exclusive, one, spec)),
+                _ => format!("{}{{{}}}{}", quote(&exclusive), names.join(","), quote(&spec)),
+            };
+            let _ = writeln!(script, "        {} \\", written);
+        }
+        let values = positional_values(command.app);
+        if !values.is_empty() {
+            let _ = writeln!(script, "        '1:VALUE:({})' \\", values.join(" "));
+        }
+        let subs: Vec<&App<'static>> = subcommands(command.app).collect();
+        if subs.is_empty() {
+            script.push_str("        && return\n");
+        } else {
+            script.push_str("        '1: :->command' \\\n");
+            script.push_str("        '*:: :->args' \\\n");
+            script.push_str("        && return\n");
+            script.push_str("    case $state in\n");
+            script.push_str("        command)\n");
+            script.push_str("            local -a commands\n");
+            script.push_str("            commands=(\n");
+            for sub in &subs {
+                let entry = format!("{}:{}", sub.get_name(), sub.get_about().unwrap_or_default());
+                let _ = writeln!(script, "                {}", quote(&entry));
+            }
+            script.push_str("            )\n");
+            let title = format!("{} commands", command.path.join(" "));
+            let _ = writeln!(script, "            _describe -t commands {} commands", quote(&title));
+            script.push_str("            ;;\n");
+            script.push_str("        args)\n");
+            script.push_str("            case $line[1] in\n");
+            for sub in &subs {
+                let name = sub.get_name();
+                let _ = writeln!(script, "                {}) _{}__{} ;;", name, command.function(), name);
+            }
+            script.push_str("            esac\n");
+            script.push_str("            ;;\n");
+            script.push_str("    esac\n");
+        }
+        script.push_str("}\n\n");
+    }
+    let _ = writeln!(script, "_{} \"$@\"", root);
+    script
+}
+
+fn fish(commands: &[Command<'_>]) -> String {
+    let mut script = String::new();
+    let root = &commands[0].path[0];
+    let _ = writeln!(script, "function __{}_command", root);
+    let _ = writeln!(script, "    set -l cmd {}", root);
+    script.push_str("    for word in (commandline -opc)[2..-1]\n");
+    script.push_str("        switch $cmd:$word\n");
+    for command in commands {
+        for sub in subcommands(command.app) {
+            let _ = writeln!(script, "            case {}:{}", command.function(), sub.get_name());
+            let _ = writeln!(script, "                set cmd {}__{}", command.function(), sub.get_name());
+        }
+    }
+    script.push_str("        end\n");
+    script.push_str("    end\n");
+    script.push_str("    echo $cmd\n");
+    script.push_str("end\n\n");
+    let _ = writeln!(script, "complete -c {} -f", root);
+    for command in commands {
+        let condition = format!("-n 'test (__{}_command) = {}'", root, command.function());
+        for sub in subcommands(command.app) {
+            let _ = writeln!(
+                script,
+                "complete -c {} {} -a {} -d {}",
+                root,
+                condition,
+                sub.get_name(),
+                quote(sub.get_about().unwrap_or_default())
+            );
+        }
+        let values = positional_values(command.app);
+        if !values.is_empty() {
+            let _ = writeln!(script, "complete -c {} {} -a {}", root, condition, quote(&values.join(" ")));
+        }
+        for flag in flags(command.app) {
+            let mut line = format!("complete -c {} {}", root, condition);
+            if let Some(short) = flag.short {
+                let _ = write!(line, " -s {}", short);
+            }
+            if let Some(long) = flag.long {
+                let _ = write!(line, " -l {}", long);
+            }
+            if flag.value && flag.path {
+                line.push_str(" -r -F");
+            } else if flag.value {
+                line.push_str(" -x");
+            }
+            if !flag.values.is_empty() {
+                let _ = write!(line, " -a {}", quote(&flag.values.join(" ")));
+            }
+            let _ = write!(line, " -d {}", quote(&flag.help));
+            let _ = writeln!(script, "{}", line);
+        }
+    }
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli;
+    use wx::item::ITEMS;
+
+    /// Returns the line of `script` that completes the values of `-d`.
+    fn data_values(script: &str, marker: &str) -> String {
+        let line = script.lines().skip_while(|line| !line.contains(marker)).find(|line| line.contains("temp"));
+        line.unwrap_or_default().to_string()
+    }
+
+    #[test]
+    fn every_item_completes_after_data() {
+        let bash = generate("bash", cli::command());
+        let zsh = generate("zsh", cli::command());
+        let fish = generate("fish", cli::command());
+        let lines = [
+            data_values(&bash, "tinywx:-d|tinywx:--data)"),
+            data_values(&zsh, "{-d,--data}"),
+            data_values(&fish, "-s d -l data"),
+        ];
+        for line in &lines {
+            let words: Vec<&str> = line.split(['\'', '(', ')', ' ']).collect();
+            for info in ITEMS {
+                assert!(words.contains(&info.name), "{} in {}", info.name, line);
+                // Aliases are taken, but not offered.
+                for alias in info.aliases.iter().chain(info.deprecated) {
+                    assert!(!words.contains(alias), "{} in {}", alias, line);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn subcommands_and_flags_of_subcommands() {
+        let bash = generate("bash", cli::command());
+        assert!(bash.contains("tinywx:config) cmd=tinywx__config ;;"), "{}", bash);
+        assert!(bash.contains("tinywx__config:show) cmd=tinywx__config__show ;;"), "{}", bash);
+        assert!(bash.contains("tinywx__completions) COMPREPLY=($(compgen -W "), "{}", bash);
+        assert!(bash.ends_with("complete -F _tinywx tinywx\n"));
+        let zsh = generate("zsh", cli::command());
+        assert!(zsh.starts_with("#compdef tinywx\n"));
+        assert!(zsh.contains("_tinywx__config__show() {"), "{}", zsh);
+        let fish = generate("fish", cli::command());
+        assert!(fish.contains("complete -c tinywx -n 'test (__tinywx_command) = tinywx' -a config"), "{}", fish);
+    }
+
+    #[test]
+    fn quotes_survive_each_shell() {
+        assert_eq!(quote("it's"), "'it'\\''s'");
+        assert_eq!(zsh_escape("[a] \\b"), "\\[a\\] \\\\b");
+    }
+}
@@ -0,0 +1,52 @@
+//! The CLI contract scripts can rely on, generated from the item registry,
+//! the output formats and the exit codes so that it cannot drift from them.
+
+use serde_json::{json, Value};
+use wx::item::ITEMS;
+
+use crate::exit::ErrorKind;
+use crate::output::Format;
+
+/// Returns the contract as JSON.
+pub fn json() -> Value {
+    let items: Vec<Value> = ITEMS
+        .iter()
+        .map(|info| {
+            json!({
+                "name": info.name,
+                "aliases": info.aliases,
+                "deprecated_names": info.deprecated,
+                "description": info.description,
+                "kind": info.kind.name(),
+                "units": info.unit.describe(),
+            })
+        })
+        .collect();
+
+    let formats: Vec<Value> = Format::ALL
+        .into_iter()
+        .map(|format| {
+            json!({
+                "name": format.name(),
+                "description": format.description(),
+                "schema": format.schema(),
+            })
+        })
+        .collect();
+
+    let exit_codes: Vec<Value> = [json!({ "code": 0, "meaning": "success" })]
+        .into_iter()
+        .chain(
+            ErrorKind::ALL
+                .into_iter()
+                .map(|kind| json!({ "code": kind.code(), "meaning": kind.meaning() })),
+        )
+        .collect();
+
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "items": items,
+        "formats": formats,
+        "exit_codes": exit_codes,
+    })
+}
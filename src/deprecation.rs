@@ -0,0 +1,210 @@
+//! Deprecated flags, config keys and item names. The old names keep working,
+//! but a run that uses any of them gets a single notice listing each one and
+//! what replaces it, and with `--strict` that is an error instead.
+//!
+//! Deprecating a flag or a key is an entry in [`FLAGS`] or [`KEYS`]: the
+//! flag's old name is registered as an alias of the new one, and the key's
+//! value is moved to the new key as the config is read. Deprecated item
+//! names are in the item registry, `wx::item::ITEMS`.
+
+use std::fmt::Write;
+
+use toml::value::{Table, Value};
+
+/// A flag renamed from `--old` to `--new`.
+pub struct Flag {
+    pub old: &'static str,
+    pub new: &'static str,
+}
+
+pub const FLAGS: &[Flag] = &[Flag { old: "file", new: "config" }];
+
+/// A config key replaced by another one.
+pub struct Key {
+    pub old: &'static str,
+    pub new: &'static str,
+    /// The value of the new key for a value of the old one, or `None` if the
+    /// old one does nothing.
+    pub migrate: fn(&Value) -> Option<Value>,
+    /// How to write the replacement, for the notice.
+    pub instead: &'static str,
+}
+
+pub const KEYS: &[Key] = &[Key {
+    old: "imperial",
+    new: "units",
+    migrate: |value| (value.as_bool() == Some(true)).then(|| Value::String("imperial".to_string())),
+    instead: "units = \"imperial\"",
+}];
+
+/// A deprecated name a run used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Used {
+    /// The old name, as it was used, with where it was used if that is not
+    /// the command line.
+    pub what: String,
+    pub instead: String,
+}
+
+/// Returns the old names of the flags renamed to `new`, for clap to accept
+/// as aliases.
+pub fn aliases(new: &str) -> Vec<&'static str> {
+    FLAGS.iter().filter(|flag| flag.new == new).map(|flag| flag.old).collect()
+}
+
+/// Returns the deprecated flags among the command-line arguments `args`, the
+/// program name first. Clap does not tell which alias was used, so the
+/// arguments are looked at as they were given, up to a `--`.
+pub fn flags_used(args: impl IntoIterator<Item = String>) -> Vec<Used> {
+    let mut used = Vec::new();
+    for arg in args.into_iter().skip(1).take_while(|arg| arg != "--") {
+        let Some(name) = arg.strip_prefix("--") else {
+            continue;
+        };
+        let name = name.split_once('=').map_or(name, |(name, _)| name);
+        if let Some(flag) = FLAGS.iter().find(|flag| flag.old == name) {
+            let entry = Used {
+                what: format!("--{}", flag.old),
+                instead: format!("--{}", flag.new),
+            };
+            if !used.contains(&entry) {
+                used.push(entry);
+            }
+        }
+    }
+    used
+}
+
+/// Moves the deprecated keys of a config file's `table` to their new keys,
+/// unless the file also sets those, and returns the ones it had. `origin`
+/// tells where the file is, for the notice.
+pub fn migrate_file(table: &mut Table, origin: &str) -> Vec<Used> {
+    let mut used = Vec::new();
+    for key in KEYS {
+        let Some(value) = table.remove(key.old) else {
+            continue;
+        };
+        if let Some(new) = (key.migrate)(&value) {
+            table.entry(key.new.to_string()).or_insert(new);
+        }
+        used.push(Used {
+            what: format!("{} in {}", key.old, origin),
+            instead: key.instead.to_string(),
+        });
+    }
+    used
+}
+
+/// Returns the deprecation of the config key `name`, if it is deprecated.
+pub fn key(name: &str) -> Option<&'static Key> {
+    KEYS.iter().find(|key| key.old == name)
+}
+
+/// Returns the deprecated item names among `names`.
+pub fn items_used<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<Used> {
+    let mut used = Vec::new();
+    for name in names {
+        if let Some(info) = wx::item::deprecated(name) {
+            let entry = Used {
+                what: format!("item '{}'", name),
+                instead: format!("'{}'", info.name),
+            };
+            if !used.contains(&entry) {
+                used.push(entry);
+            }
+        }
+    }
+    used
+}
+
+/// Returns the notice for the deprecated names a run `used`, or `None` if it
+/// used none.
+pub fn notice(used: &[Used]) -> Option<String> {
+    let (first, rest) = used.split_first()?;
+    if rest.is_empty() {
+        return Some(format!("{} is deprecated; use {} instead", first.what, first.instead));
+    }
+    let mut notice = String::from("these are deprecated:");
+    for entry in used {
+        let _ = write!(notice, "\n  {}: use {} instead", entry.what, entry.instead);
+    }
+    Some(notice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        ["tinywx"].iter().chain(args).map(|arg| arg.to_string()).collect()
+    }
+
+    fn used(what: &str, instead: &str) -> Used {
+        Used {
+            what: what.to_string(),
+            instead: instead.to_string(),
+        }
+    }
+
+    #[test]
+    fn renamed_flags_are_aliases_of_the_new_name() {
+        assert_eq!(aliases("config"), ["file"]);
+        assert!(aliases("units").is_empty());
+    }
+
+    #[test]
+    fn old_flags_are_found_once_before_a_double_dash() {
+        let file = used("--file", "--config");
+        assert_eq!(flags_used(args(&["--file", "a.toml", "-d", "temp"])), flags_used(args(&["--file=a.toml"])));
+        assert_eq!(flags_used(args(&["--file=a.toml", "--file", "b.toml"])), [file]);
+        assert!(flags_used(args(&["-f", "a.toml", "--config", "b.toml", "--files"])).is_empty());
+        assert!(flags_used(args(&["-c", "paris", "--", "--file"])).is_empty());
+        // The program name is not an argument.
+        assert!(flags_used(["--file".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn old_keys_move_to_the_new_one_unless_it_is_set() {
+        let mut table: Table = toml::from_str("imperial = true\ncity = \"paris\"").unwrap();
+        assert_eq!(migrate_file(&mut table, "a.toml"), [used("imperial in a.toml", "units = \"imperial\"")]);
+        assert_eq!(table.get("units"), Some(&Value::String("imperial".to_string())));
+        assert!(!table.contains_key("imperial"));
+
+        let mut table: Table = toml::from_str("imperial = true\nunits = \"metric\"").unwrap();
+        assert_eq!(migrate_file(&mut table, "a.toml").len(), 1);
+        assert_eq!(table.get("units"), Some(&Value::String("metric".to_string())));
+
+        // False did nothing, so nothing replaces it, but it is still noticed.
+        let mut table: Table = toml::from_str("imperial = false").unwrap();
+        assert_eq!(migrate_file(&mut table, "a.toml").len(), 1);
+        assert!(table.is_empty());
+
+        let mut table: Table = toml::from_str("units = \"imperial\"").unwrap();
+        assert!(migrate_file(&mut table, "a.toml").is_empty());
+    }
+
+    #[test]
+    fn keys_are_looked_up_by_their_old_name() {
+        assert_eq!(key("imperial").map(|key| key.new), Some("units"));
+        assert!(key("units").is_none());
+    }
+
+    #[test]
+    fn old_item_names_are_found_once() {
+        let time = used("item 'time'", "'obs_time'");
+        assert_eq!(items_used(["temp", "time", "obs_time", "time"]), [time]);
+        assert!(items_used(["temp", "humidity"]).is_empty());
+    }
+
+    #[test]
+    fn one_notice_for_all_of_a_run() {
+        assert_eq!(notice(&[]), None);
+        let file = used("--file", "--config");
+        assert_eq!(notice(std::slice::from_ref(&file)).unwrap(), "--file is deprecated; use --config instead");
+        let time = used("item 'time'", "'obs_time'");
+        assert_eq!(
+            notice(&[file, time]).unwrap(),
+            "these are deprecated:\n  --file: use --config instead\n  item 'time': use 'obs_time' instead"
+        );
+    }
+}
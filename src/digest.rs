@@ -0,0 +1,177 @@
+//! The daily digest: once a day in watch mode, from a set local time on, a
+//! summary of the rest of the day's forecast.
+//!
+//! The local day it was last sent on is kept in the cache directory, so that
+//! it is sent once a day even when tinywx is restarted, or the machine wakes
+//! up from suspend long after the time.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use crate::{paths, quiet, sink};
+
+/// Time the digest is sent at if none is set, in minutes after midnight.
+pub const DEFAULT_TIME: u32 = 7 * 60;
+
+/// Where the digest goes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Printed to stdout, as an extra block between the lines.
+    Stdout,
+    /// Shown as a desktop notification, with `notify-send`.
+    Notify,
+    /// Written to the stdin of a shell command.
+    Exec(String),
+}
+
+/// The `digest` setting: the targets without a command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Output {
+    Stdout,
+    Notify,
+}
+
+impl std::str::FromStr for Output {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stdout" => Ok(Output::Stdout),
+            "notify" => Ok(Output::Notify),
+            _ => Err(anyhow!("unknown digest output '{}'", s)),
+        }
+    }
+}
+
+/// When and where to send the digest.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    /// Local time, in minutes after midnight.
+    pub at: u32,
+    pub target: Target,
+}
+
+impl Target {
+    pub fn send(&self, digest: &str) -> Result<()> {
+        match self {
+            Target::Stdout => crate::print(digest),
+            Target::Notify => sink::notify(digest),
+            Target::Exec(command) => sink::exec(command, digest),
+        }
+    }
+}
+
+/// Wraps [`quiet::parse_clock`] with an error that names the digest time.
+pub fn parse_time(s: &str) -> Result<u32> {
+    quiet::parse_clock(s).ok_or_else(|| anyhow!("invalid digest time '{}': expected HH:MM, as in 07:00", s))
+}
+
+/// Returns the local day, in days since the epoch, to send the digest for at
+/// `now`, or `None` if it is not due: before `at` minutes after midnight, or
+/// already sent on day `last`. Local time is `offset` seconds from UTC.
+pub fn due(at: u32, last: Option<i64>, now: i64, offset: i64) -> Option<i64> {
+    let local = now.saturating_add(offset);
+    let day = local.div_euclid(86400);
+    let minute = local.rem_euclid(86400) / 60;
+    (minute >= i64::from(at) && last.is_none_or(|last| last < day)).then_some(day)
+}
+
+/// Returns the local day the digest for `key` was last sent on.
+pub fn load(key: &str) -> Option<i64> {
+    fs::read_to_string(path(key)?).ok()?.trim().parse().ok()
+}
+
+/// Records that the digest for `key` was sent on local day `day`.
+pub fn save(key: &str, day: i64) -> Result<()> {
+    let path = path(key).ok_or_else(|| anyhow!("no cache directory (is $HOME set?)"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, day.to_string()).map_err(|e| anyhow!("cannot write {}: {}", path.display(), e))
+}
+
+fn path(key: &str) -> Option<PathBuf> {
+    Some(paths::cache_dir()?.join("digest").join(paths::file_name(key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Midnight UTC of 2024-05-01.
+    const MIDNIGHT: i64 = 1_714_521_600;
+    const HOUR: i64 = 3600;
+
+    /// Steps a clock from `from` to `to` in steps of `step` seconds, sending
+    /// the digest whenever it is due, and returns the times it was sent.
+    fn run(at: u32, offset: i64, from: i64, to: i64, step: i64, last: &mut Option<i64>) -> Vec<i64> {
+        let mut sent = Vec::new();
+        let mut now = from;
+        while now <= to {
+            if let Some(day) = due(at, *last, now, offset) {
+                *last = Some(day);
+                sent.push(now);
+            }
+            now += step;
+        }
+        sent
+    }
+
+    #[test]
+    fn sent_once_a_day_at_the_time() {
+        let mut last = None;
+        let sent = run(DEFAULT_TIME, 0, MIDNIGHT, MIDNIGHT + 72 * HOUR, 60, &mut last);
+        assert_eq!(sent, [MIDNIGHT + 7 * HOUR, MIDNIGHT + 31 * HOUR, MIDNIGHT + 55 * HOUR]);
+        assert_eq!(last, Some(MIDNIGHT / 86400 + 2));
+    }
+
+    #[test]
+    fn local_days_cross_midnight_utc() {
+        // At UTC-8, 07:00 local is 15:00 UTC, and the local day begins at
+        // 08:00 UTC.
+        let mut last = None;
+        let sent = run(DEFAULT_TIME, -8 * HOUR, MIDNIGHT + 8 * HOUR, MIDNIGHT + 56 * HOUR, 60, &mut last);
+        assert_eq!(sent, [MIDNIGHT + 15 * HOUR, MIDNIGHT + 39 * HOUR]);
+        // At UTC+9, the local day begins at 15:00 UTC the day before, and
+        // 07:00 local is 22:00 UTC the day before.
+        let mut last = None;
+        let sent = run(DEFAULT_TIME, 9 * HOUR, MIDNIGHT - 9 * HOUR, MIDNIGHT + 39 * HOUR, 60, &mut last);
+        assert_eq!(sent, [MIDNIGHT - 2 * HOUR, MIDNIGHT + 22 * HOUR]);
+    }
+
+    #[test]
+    fn sent_once_after_waking_from_suspend() {
+        let mut last = None;
+        assert!(run(DEFAULT_TIME, 0, MIDNIGHT + 6 * HOUR, MIDNIGHT + 6 * HOUR, 60, &mut last).is_empty());
+        // Suspended from 06:00 on the first day to 15:00 on the third: the
+        // digest of the third day is sent on waking, and not again that day.
+        let woken = MIDNIGHT + 63 * HOUR;
+        assert_eq!(run(DEFAULT_TIME, 0, woken, MIDNIGHT + 72 * HOUR - 60, 60, &mut last), [woken]);
+        let next = run(DEFAULT_TIME, 0, MIDNIGHT + 72 * HOUR, MIDNIGHT + 80 * HOUR, 60, &mut last);
+        assert_eq!(next, [MIDNIGHT + 79 * HOUR]);
+    }
+
+    #[test]
+    fn a_day_already_sent_is_not_sent_again() {
+        // As after a restart, with the day read back from the cache.
+        let today = MIDNIGHT / 86400;
+        assert_eq!(due(DEFAULT_TIME, Some(today), MIDNIGHT + 8 * HOUR, 0), None);
+        assert_eq!(due(DEFAULT_TIME, Some(today - 1), MIDNIGHT + 8 * HOUR, 0), Some(today));
+        assert_eq!(due(DEFAULT_TIME, Some(today - 1), MIDNIGHT + 6 * HOUR, 0), None);
+    }
+
+    #[test]
+    fn times_and_outputs() {
+        assert_eq!(parse_time("07:00").unwrap(), DEFAULT_TIME);
+        assert_eq!(parse_time("23:59").unwrap(), 23 * 60 + 59);
+        let e = parse_time("7am").unwrap_err();
+        assert_eq!(e.to_string(), "invalid digest time '7am': expected HH:MM, as in 07:00");
+        assert_eq!("notify".parse::<Output>().unwrap(), Output::Notify);
+        assert_eq!("stdout".parse::<Output>().unwrap(), Output::Stdout);
+        assert_eq!("mail".parse::<Output>().unwrap_err().to_string(), "unknown digest output 'mail'");
+    }
+}
@@ -0,0 +1,559 @@
+//! `tinywx doctor`: checks the environment for the usual causes of trouble.
+//!
+//! Each check is a function of its inputs, with the network and the file
+//! system behind the [`Network`] and [`FileSystem`] traits, so that they can
+//! be run against stand-ins.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Result};
+
+use wx::IconSet;
+
+use crate::{layers, paths, version, watch, Config};
+
+/// Host all API requests go to.
+const API_HOST: &str = "api.openweathermap.org";
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Clock differences up to this many seconds are fine.
+const CLOCK_WARN: u64 = 60;
+/// Clock differences from this many seconds on make local times wrong.
+const CLOCK_FAIL: u64 = 3600;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::Pass => "pass",
+            Status::Warn => "warn",
+            Status::Fail => "fail",
+        })
+    }
+}
+
+/// Outcome of a single check.
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub message: String,
+    /// What to do about it, for checks that did not pass.
+    pub hint: Option<&'static str>,
+}
+
+impl Check {
+    fn pass(name: &'static str, message: impl Into<String>) -> Self {
+        Check { name, status: Status::Pass, message: message.into(), hint: None }
+    }
+
+    fn warn(name: &'static str, message: impl Into<String>, hint: &'static str) -> Self {
+        Check { name, status: Status::Warn, message: message.into(), hint: Some(hint) }
+    }
+
+    fn fail(name: &'static str, message: impl Into<String>, hint: &'static str) -> Self {
+        Check { name, status: Status::Fail, message: message.into(), hint: Some(hint) }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}  {:<8} {}", self.status, self.name, self.message)?;
+        if let Some(hint) = self.hint {
+            write!(f, "\n      {:<8} hint: {}", "", hint)?;
+        }
+        Ok(())
+    }
+}
+
+/// Access to the network, as far as the checks need it.
+pub trait Network {
+    /// Opens, and closes, a TCP connection to `host`.
+    fn connect(&self, host: &str, port: u16) -> Result<()>;
+    /// Returns the time in the `Date` header of a HEAD request to `host`.
+    fn server_time(&self, host: &str) -> Result<SystemTime>;
+    /// Makes a real API request with `api_key`.
+    fn validate_key(&self, api_key: &str) -> Result<()>;
+}
+
+/// Access to the file system, as far as the checks need it.
+pub trait FileSystem {
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+    /// Creates `dir` if needed, then writes and removes a file in it.
+    fn check_writable(&self, dir: &Path) -> io::Result<()>;
+}
+
+/// The real network.
+pub struct Live;
+
+impl Network for Live {
+    fn connect(&self, host: &str, port: u16) -> Result<()> {
+        let addr = (host, port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| anyhow!("{} has no addresses", host))?;
+        TcpStream::connect_timeout(&addr, TIMEOUT)?;
+        Ok(())
+    }
+
+    fn server_time(&self, host: &str) -> Result<SystemTime> {
+        let client = reqwest::blocking::Client::builder().timeout(TIMEOUT).build()?;
+        let response = client.head(format!("https://{}/", host)).send()?;
+        let date = response
+            .headers()
+            .get(reqwest::header::DATE)
+            .ok_or_else(|| anyhow!("no Date header in the response"))?;
+        Ok(httpdate::parse_http_date(date.to_str()?)?)
+    }
+
+    fn validate_key(&self, api_key: &str) -> Result<()> {
+        let client = wx::Client::builder().api_key(api_key).timeout(TIMEOUT).build()?;
+        client.current(&wx::Location::coords(51.51, -0.13))?;
+        Ok(())
+    }
+}
+
+/// The real file system.
+pub struct Disk;
+
+impl FileSystem for Disk {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn check_writable(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        let probe = dir.join(".doctor");
+        fs::write(&probe, b"")?;
+        fs::remove_file(&probe)
+    }
+}
+
+/// What the checks are run with.
+pub struct Options<'a> {
+    /// Configuration file given with `-f`, or the one in the config directory.
+    pub file: Option<&'a Path>,
+    /// API key given with `-k`.
+    pub api_key: Option<&'a str>,
+    /// Icon set given with `--icons`.
+    pub icons: Option<IconSet>,
+    /// Whether to make a real API request to validate the key.
+    pub live: bool,
+}
+
+/// Runs all checks.
+pub fn run(net: &dyn Network, fs: &dyn FileSystem, options: &Options) -> Vec<Check> {
+    let (config_check, cfg) = check_config(fs, options.file);
+    let icons = options.icons.or(cfg.as_ref().map(|cfg| cfg.icons)).unwrap_or_default();
+    let var = |name| env::var(name).ok().filter(|v| !v.is_empty());
+    let api_key = options
+        .api_key
+        .map(str::to_string)
+        .or_else(|| var("TINYWX_API_KEY"))
+        .or_else(|| cfg.map(|cfg| cfg.api_key).filter(|key| !key.is_empty()))
+        .or_else(|| var(layers::OPENWEATHERMAP_API_KEY))
+        .unwrap_or_default();
+
+    let locale = var("LC_ALL").or_else(|| var("LC_CTYPE")).or_else(|| var("LANG"));
+
+    let key_check = check_api_key(&api_key);
+    let validate = options.live && key_check.status != Status::Fail;
+    let mut checks = vec![check_version(&version::info()), config_check, key_check];
+    if validate {
+        checks.push(check_key_live(net, &api_key));
+    }
+    checks.extend([
+        check_network(net),
+        check_cache(fs, paths::cache_dir().as_deref()),
+        check_locale(locale.as_deref()),
+        check_icons(var("TERM").as_deref(), icons),
+        check_clock(net, SystemTime::now()),
+        check_hung(watch::load_hung(), crate::unix_now()),
+    ]);
+    checks
+}
+
+/// Reports the build, so that it is in what gets pasted into bug reports.
+pub fn check_version(info: &version::Info) -> Check {
+    let message = format!(
+        "{} ({}), features: {}, {}, {}",
+        info.version,
+        info.commit(),
+        info.features(),
+        info.target,
+        info.http
+    );
+    Check::pass("version", message)
+}
+
+/// Checks that the configuration file, if any, can be read and parsed.
+pub fn check_config(fs: &dyn FileSystem, file: Option<&Path>) -> (Check, Option<Config>) {
+    const NAME: &str = "config";
+    let Some(path) = file else {
+        return (Check::pass(NAME, "no file given or in the config directory, using command-line arguments"), None);
+    };
+    let contents = match fs.read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            let message = format!("cannot read {}: {}", path.display(), e);
+            return (Check::fail(NAME, message, "check the path given with -f"), None);
+        }
+    };
+    match toml::from_str::<Config>(&contents) {
+        Ok(cfg) => match cfg.units_alias_warning() {
+            Some(warning) => {
+                let message = format!("{} parsed, but {}", path.display(), warning);
+                (Check::warn(NAME, message, "write units as metric, imperial or standard"), Some(cfg))
+            }
+            None => (Check::pass(NAME, format!("{} parsed", path.display())), Some(cfg)),
+        },
+        Err(e) => {
+            let message = format!("cannot parse {}: {}", path.display(), e);
+            (Check::fail(NAME, message, "compare it with the example tinywx.toml"), None)
+        }
+    }
+}
+
+/// Checks that there is an API key and that it looks like one.
+pub fn check_api_key(api_key: &str) -> Check {
+    const NAME: &str = "api key";
+    let api_key = api_key.trim();
+    if api_key.is_empty() || api_key == "YOUR_API_KEY" {
+        return Check::fail(
+            NAME,
+            "no API key",
+            "pass one with -k, or set api_key or OPENWEATHERMAP_API_KEY; get one at https://openweathermap.org",
+        );
+    }
+    if !wx::plausible_api_key(api_key) {
+        return Check::warn(
+            NAME,
+            "does not look like an OpenWeatherMap key",
+            "keys are 32 hexadecimal characters; check for copy and paste mistakes",
+        );
+    }
+    Check::pass(NAME, "present")
+}
+
+/// Checks that the API accepts the key.
+pub fn check_key_live(net: &dyn Network, api_key: &str) -> Check {
+    const NAME: &str = "api key";
+    match net.validate_key(api_key) {
+        Ok(()) => Check::pass(NAME, "accepted by the API"),
+        Err(e) => Check::fail(
+            NAME,
+            format!("rejected: {}", e),
+            "new keys can take a couple of hours to become active",
+        ),
+    }
+}
+
+/// Checks that the API host can be reached.
+pub fn check_network(net: &dyn Network) -> Check {
+    const NAME: &str = "network";
+    match net.connect(API_HOST, 443) {
+        Ok(()) => Check::pass(NAME, format!("{} reachable", API_HOST)),
+        Err(e) => Check::fail(
+            NAME,
+            format!("cannot connect to {}: {}", API_HOST, e),
+            "check the connection, DNS and any proxy or firewall",
+        ),
+    }
+}
+
+/// Checks that the cache directory, used for the history, can be written.
+pub fn check_cache(fs: &dyn FileSystem, dir: Option<&Path>) -> Check {
+    const NAME: &str = "cache";
+    let Some(dir) = dir else {
+        return Check::warn(
+            NAME,
+            "no cache directory, history is unavailable",
+            if cfg!(windows) { "set LOCALAPPDATA or XDG_CACHE_HOME" } else { "set HOME or XDG_CACHE_HOME" },
+        );
+    };
+    match fs.check_writable(dir) {
+        Ok(()) => Check::pass(NAME, format!("{} writable", dir.display())),
+        Err(e) => Check::fail(
+            NAME,
+            format!("{} not writable: {}", dir.display(), e),
+            "fix its permissions or point XDG_CACHE_HOME elsewhere",
+        ),
+    }
+}
+
+/// Checks that the locale, the first set of `LC_ALL`, `LC_CTYPE` and `LANG`,
+/// uses UTF-8, which icons and the degree sign need.
+pub fn check_locale(locale: Option<&str>) -> Check {
+    const NAME: &str = "locale";
+    const HINT: &str = "use a UTF-8 locale, e.g. LANG=en_US.UTF-8";
+    match locale {
+        Some(locale) if is_utf8(locale) => Check::pass(NAME, locale),
+        Some(locale) => Check::warn(NAME, format!("{} is not UTF-8", locale), HINT),
+        None => Check::warn(NAME, "not set", HINT),
+    }
+}
+
+fn is_utf8(locale: &str) -> bool {
+    let locale = locale.to_lowercase();
+    locale.contains("utf-8") || locale.contains("utf8")
+}
+
+/// Checks whether the terminal is likely to render the icons of `set`. Only
+/// terminals known not to can be detected.
+pub fn check_icons(term: Option<&str>, set: IconSet) -> Check {
+    const NAME: &str = "icons";
+    let font = match set {
+        IconSet::NerdFont => "a Nerd Font",
+        IconSet::Emoji => "an emoji font",
+        IconSet::Text => return Check::pass(NAME, "icons are text, which any terminal can show"),
+    };
+    match term {
+        Some(term @ ("linux" | "dumb" | "vt100" | "vt220")) => Check::warn(
+            NAME,
+            format!("TERM={} cannot show icons from {}", term, font),
+            "leave out the icon item, or use a graphical terminal",
+        ),
+        _ => Check::pass(NAME, format!("icons need {}, which cannot be detected", font)),
+    }
+}
+
+/// Checks the local clock against the `Date` header of the API host.
+pub fn check_clock(net: &dyn Network, now: SystemTime) -> Check {
+    const NAME: &str = "clock";
+    const HINT: &str = "enable time synchronization (NTP)";
+    let server = match net.server_time(API_HOST) {
+        Ok(server) => server,
+        Err(e) => {
+            return Check::warn(
+                NAME,
+                format!("cannot compare with {}: {}", API_HOST, e),
+                "check the network first",
+            )
+        }
+    };
+    let skew = match now.duration_since(server) {
+        Ok(ahead) => ahead,
+        Err(behind) => behind.duration(),
+    }
+    .as_secs();
+    let message = format!("{} seconds off", skew);
+    if skew >= CLOCK_FAIL {
+        Check::fail(NAME, message, HINT)
+    } else if skew > CLOCK_WARN {
+        Check::warn(NAME, message, HINT)
+    } else {
+        Check::pass(NAME, "in sync with the API")
+    }
+}
+
+/// Reports the requests watch mode gave up on because they hung, as of `now`
+/// in unix seconds.
+pub fn check_hung(hung: Option<watch::Hung>, now: i64) -> Check {
+    const NAME: &str = "hung";
+    match hung {
+        None => Check::pass(NAME, "no hung requests in watch mode"),
+        Some(hung) => Check::warn(
+            NAME,
+            format!(
+                "watch mode gave up on {} hung requests, the last {} minutes ago",
+                hung.count,
+                now.saturating_sub(hung.last).max(0) / 60
+            ),
+            "a proxy or middlebox may be holding connections open; check the network",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+
+    /// A network that either answers, with the API's clock `skew` seconds
+    /// ahead of ours, or cannot be reached.
+    struct Fake {
+        reachable: bool,
+        skew: i64,
+        key_accepted: bool,
+    }
+
+    const ONLINE: Fake = Fake { reachable: true, skew: 0, key_accepted: true };
+    const OFFLINE: Fake = Fake { reachable: false, skew: 0, key_accepted: false };
+
+    fn now() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    }
+
+    impl Network for Fake {
+        fn connect(&self, _: &str, _: u16) -> Result<()> {
+            self.reachable.then_some(()).ok_or_else(|| anyhow!("connection refused"))
+        }
+
+        fn server_time(&self, host: &str) -> Result<SystemTime> {
+            self.connect(host, 443)?;
+            let skew = Duration::from_secs(self.skew.unsigned_abs());
+            Ok(if self.skew < 0 { now() - skew } else { now() + skew })
+        }
+
+        fn validate_key(&self, _: &str) -> Result<()> {
+            self.key_accepted.then_some(()).ok_or_else(|| anyhow!("401 Invalid API key"))
+        }
+    }
+
+    /// Files in memory, in directories that are all writable or none.
+    struct Files {
+        files: HashMap<PathBuf, String>,
+        writable: bool,
+    }
+
+    impl Files {
+        fn with(path: &str, contents: &str) -> Self {
+            Files { files: HashMap::from([(PathBuf::from(path), contents.to_string())]), writable: true }
+        }
+    }
+
+    impl FileSystem for Files {
+        fn read_to_string(&self, path: &Path) -> io::Result<String> {
+            self.files.get(path).cloned().ok_or_else(|| io::ErrorKind::NotFound.into())
+        }
+
+        fn check_writable(&self, _: &Path) -> io::Result<()> {
+            if self.writable {
+                Ok(())
+            } else {
+                Err(io::ErrorKind::PermissionDenied.into())
+            }
+        }
+    }
+
+    const KEY: &str = "0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn config_files() {
+        let path = Path::new("/etc/tinywx.toml");
+        let check = |contents: &str| check_config(&Files::with("/etc/tinywx.toml", contents), Some(path));
+
+        let (passed, cfg) = check("city = \"amsterdam\"\n");
+        assert_eq!((passed.status, passed.message.as_str()), (Status::Pass, "/etc/tinywx.toml parsed"));
+        assert_eq!(cfg.unwrap().city, "amsterdam");
+
+        let (alias, cfg) = check("units = \"celsius\"\n");
+        assert_eq!(alias.status, Status::Warn);
+        assert!(alias.message.ends_with("units = \"celsius\" is read as \"metric\"; write that instead"));
+        assert!(cfg.is_some());
+
+        let (broken, cfg) = check("city = [\n");
+        assert_eq!(broken.status, Status::Fail);
+        assert!(broken.message.starts_with("cannot parse /etc/tinywx.toml: "), "{}", broken.message);
+        assert!(cfg.is_none());
+
+        let (missing, _) = check_config(&Files::with("/elsewhere.toml", ""), Some(path));
+        assert_eq!(missing.status, Status::Fail);
+        assert_eq!(missing.hint, Some("check the path given with -f"));
+
+        let (none, cfg) = check_config(&Files::with("/etc/tinywx.toml", ""), None);
+        assert_eq!(none.status, Status::Pass);
+        assert!(cfg.is_none());
+    }
+
+    #[test]
+    fn api_keys() {
+        assert_eq!(check_api_key(KEY).status, Status::Pass);
+        assert_eq!(check_api_key(&format!(" {}\n", KEY)).status, Status::Pass);
+        assert_eq!(check_api_key("0123").status, Status::Warn);
+        assert_eq!(check_api_key("").status, Status::Fail);
+        assert_eq!(check_api_key("YOUR_API_KEY").status, Status::Fail);
+
+        assert_eq!(check_key_live(&ONLINE, KEY).status, Status::Pass);
+        let rejected = check_key_live(&OFFLINE, KEY);
+        assert_eq!((rejected.status, rejected.message.as_str()), (Status::Fail, "rejected: 401 Invalid API key"));
+    }
+
+    #[test]
+    fn the_network() {
+        assert_eq!(check_network(&ONLINE).status, Status::Pass);
+        let unreachable = check_network(&OFFLINE);
+        assert_eq!(unreachable.status, Status::Fail);
+        assert_eq!(unreachable.message, "cannot connect to api.openweathermap.org: connection refused");
+    }
+
+    #[test]
+    fn the_cache_directory() {
+        let mut files = Files::with("/x", "");
+        let dir = Path::new("/home/wx/.cache/tinywx");
+        assert_eq!(check_cache(&files, Some(dir)).status, Status::Pass);
+        assert_eq!(check_cache(&files, None).status, Status::Warn);
+        files.writable = false;
+        let read_only = check_cache(&files, Some(dir));
+        assert_eq!(read_only.status, Status::Fail);
+        assert!(read_only.message.starts_with("/home/wx/.cache/tinywx not writable: "), "{}", read_only.message);
+    }
+
+    #[test]
+    fn locales() {
+        assert_eq!(check_locale(Some("en_US.UTF-8")).status, Status::Pass);
+        assert_eq!(check_locale(Some("nl_NL.utf8")).status, Status::Pass);
+        assert_eq!(check_locale(Some("C")).status, Status::Warn);
+        assert_eq!(check_locale(None).status, Status::Warn);
+    }
+
+    #[test]
+    fn icons_on_terminals() {
+        assert_eq!(check_icons(Some("linux"), IconSet::NerdFont).status, Status::Warn);
+        assert_eq!(check_icons(Some("dumb"), IconSet::Emoji).status, Status::Warn);
+        assert_eq!(check_icons(Some("xterm-256color"), IconSet::NerdFont).status, Status::Pass);
+        assert_eq!(check_icons(None, IconSet::Emoji).status, Status::Pass);
+        assert_eq!(check_icons(Some("linux"), IconSet::Text).status, Status::Pass);
+    }
+
+    #[test]
+    fn clocks_at_the_thresholds() {
+        let clock = |skew| check_clock(&Fake { skew, ..ONLINE }, now());
+        for (skew, status) in [
+            (0, Status::Pass),
+            (60, Status::Pass),
+            (-60, Status::Pass),
+            (61, Status::Warn),
+            (-3599, Status::Warn),
+            (3600, Status::Fail),
+            (-7200, Status::Fail),
+        ] {
+            assert_eq!(clock(skew).status, status, "{}", skew);
+        }
+        assert_eq!(clock(-7200).message, "7200 seconds off");
+        assert_eq!(check_clock(&OFFLINE, now()).status, Status::Warn);
+    }
+
+    #[test]
+    fn hung_requests() {
+        assert_eq!(check_hung(None, 0).status, Status::Pass);
+        let hung = check_hung(Some(watch::Hung { count: 3, last: 1000 }), 1000 + 7 * 60 + 30);
+        assert_eq!(hung.status, Status::Warn);
+        assert_eq!(hung.message, "watch mode gave up on 3 hung requests, the last 7 minutes ago");
+    }
+
+    #[test]
+    fn checks_print_with_their_hint() {
+        assert_eq!(check_network(&ONLINE).to_string(), "pass  network  api.openweathermap.org reachable");
+        assert_eq!(
+            check_locale(None).to_string(),
+            "warn  locale   not set\n               hint: use a UTF-8 locale, e.g. LANG=en_US.UTF-8"
+        );
+    }
+}
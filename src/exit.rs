@@ -0,0 +1,99 @@
+//! Exit codes, and telling which one an error deserves; and `--quiet`,
+//! which keeps the codes but leaves out the messages.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether errors and warnings are left out, with `--quiet`.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Kinds of failure, each with its own exit code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    Generic,
+    /// Invalid arguments or configuration.
+    Usage,
+    /// The API could not be reached.
+    Network,
+    /// The API answered with an error, e.g. for an invalid key or an unknown
+    /// city.
+    Api,
+}
+
+impl ErrorKind {
+    /// All kinds, in order of their exit codes.
+    pub const ALL: [ErrorKind; 4] = [
+        ErrorKind::Generic,
+        ErrorKind::Usage,
+        ErrorKind::Network,
+        ErrorKind::Api,
+    ];
+
+    pub fn code(self) -> i32 {
+        match self {
+            ErrorKind::Generic => 1,
+            ErrorKind::Usage => 2,
+            ErrorKind::Network => 3,
+            ErrorKind::Api => 4,
+        }
+    }
+
+    pub fn meaning(self) -> &'static str {
+        match self {
+            ErrorKind::Generic => "any other failure",
+            ErrorKind::Usage => "invalid arguments or configuration",
+            ErrorKind::Network => "the API could not be reached",
+            ErrorKind::Api => "the API returned an error, e.g. invalid API key or city not found",
+        }
+    }
+
+    /// Tells what kind of failure `e` is.
+    pub fn of(e: &anyhow::Error) -> Self {
+        if e.is::<Usage>() || e.is::<toml::de::Error>() {
+            ErrorKind::Usage
+        } else if e.is::<wx::ApiError>() {
+            ErrorKind::Api
+        } else if e.is::<wx::NetworkError>() {
+            ErrorKind::Network
+        } else {
+            ErrorKind::Generic
+        }
+    }
+}
+
+/// An error in the arguments or configuration.
+#[derive(Debug)]
+pub struct Usage(pub String);
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Usage {}
+
+/// Returns an error in the arguments or configuration.
+pub fn usage(message: impl Into<String>) -> anyhow::Error {
+    Usage(message.into()).into()
+}
+
+/// Leaves out errors and warnings from now on, or prints them again.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Returns whether errors and warnings are left out.
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Prints a warning to stderr, unless `--quiet` was given.
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        if !$crate::exit::quiet() {
+            eprintln!("warning: {}", format_args!($($arg)*));
+        }
+    };
+}
+pub(crate) use warning;
@@ -0,0 +1,37 @@
+//! Simpler forms of a city query, to try when OpenWeatherMap does not find
+//! the city as it was given. A state, for one, can make it miss a city it
+//! finds without.
+
+use std::fmt;
+
+/// A form of a city query, in the order they are tried.
+#[derive(Clone, Debug)]
+pub enum Form {
+    /// The city and the country, without the state.
+    WithoutState(wx::Location),
+    /// The coordinates the geocoding API finds for the query as it was given.
+    Geocoded,
+}
+
+impl fmt::Display for Form {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Form::WithoutState(_) => f.write_str("without the state"),
+            Form::Geocoded => f.write_str("by the coordinates of the geocoding API"),
+        }
+    }
+}
+
+/// Returns the forms to try, in order, after `location` was not found. Only
+/// city names have any.
+pub fn ladder(location: &wx::Location) -> Vec<Form> {
+    let wx::Location::City { city, state, country } = location else {
+        return Vec::new();
+    };
+    let mut forms = Vec::new();
+    if !state.trim().is_empty() {
+        forms.push(Form::WithoutState(wx::Location::new(city, "", country)));
+    }
+    forms.push(Form::Geocoded);
+    forms
+}
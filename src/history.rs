@@ -0,0 +1,54 @@
+//! History ring: the most recent observations of each location, kept on disk
+//! so that items like `day_story` can look back.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use wx::history::Observation;
+
+use crate::paths;
+
+/// Maximum number of observations kept per location.
+const CAPACITY: usize = 96;
+/// Observations older than this, in seconds, are dropped.
+const MAX_AGE: i64 = 36 * 3600;
+
+/// Returns the stored observations for `key`, oldest first. A missing or
+/// unreadable history is treated as empty.
+pub fn load(key: &str) -> Vec<Observation> {
+    path(key)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Adds `observation` to the history for `key` and returns the observations
+/// that were there before it.
+pub fn record(key: &str, observation: Observation) -> Result<Vec<Observation>> {
+    let previous = load(key);
+
+    let mut ring: Vec<Observation> = previous
+        .iter()
+        .filter(|o| o.dt != observation.dt && observation.dt.saturating_sub(o.dt) < MAX_AGE)
+        .copied()
+        .collect();
+    ring.push(observation);
+    ring.sort_by_key(|o| o.dt);
+    if ring.len() > CAPACITY {
+        ring.drain(..ring.len() - CAPACITY);
+    }
+
+    let path = path(key).ok_or_else(|| anyhow!("no cache directory (is $HOME set?)"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, serde_json::to_string(&ring)?)
+        .map_err(|e| anyhow!("cannot write {}: {}", path.display(), e))?;
+
+    Ok(previous)
+}
+
+fn path(key: &str) -> Option<PathBuf> {
+    Some(paths::cache_dir()?.join("history").join(paths::file_name(key) + ".json"))
+}
@@ -0,0 +1,762 @@
+//! The configuration, resolved from layers: built-in defaults, then the config
+//! file, then `TINYWX_*` environment variables, then command-line flags. Each
+//! layer overrides the keys it sets, and the origin of every key is kept so
+//! that `tinywx config show --origins` can tell where a value came from. The
+//! API key can also come from OpenWeatherMap's own variable, which ranks
+//! below the config file.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use toml::value::{Table, Value};
+
+use crate::{cli, deprecation};
+
+/// Prefix of the environment variables, followed by the key in upper case.
+const ENV_PREFIX: &str = "TINYWX_";
+
+/// OpenWeatherMap's variable for the API key, which other tools read too.
+pub const OPENWEATHERMAP_API_KEY: &str = "OPENWEATHERMAP_API_KEY";
+
+/// The type of a key's value, which tells how to read it from an environment
+/// variable or a flag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Str,
+    Bool,
+    /// An unsigned integer; flags are read as `u32`.
+    Integer,
+    Float,
+    /// A list of strings, comma-separated in environment variables.
+    List,
+}
+
+/// How a key is set on the command line.
+#[derive(Clone, Copy, Debug)]
+pub enum Flag {
+    /// An option taking the value, by its argument id.
+    Value(&'static str),
+    /// A flag that sets the key to the given value when present.
+    Switch(&'static str, bool),
+}
+
+/// A configuration key.
+#[derive(Debug)]
+pub struct Key {
+    /// Name in the config file, with tables joined by dots.
+    pub name: &'static str,
+    pub kind: Kind,
+    pub flag: Flag,
+    /// What applies if no layer sets the key, for display; empty if nothing
+    /// does.
+    pub default: &'static str,
+}
+
+/// All keys the layers resolve, in the order they are shown. Other keys of
+/// the config file, like the `[format.items]` tables, are taken from the file
+/// as they are.
+pub const KEYS: &[Key] = &[
+    Key {
+        name: "city",
+        kind: Kind::Str,
+        flag: Flag::Value("city"),
+        default: "",
+    },
+    Key {
+        name: "state",
+        kind: Kind::Str,
+        flag: Flag::Value("state"),
+        default: "",
+    },
+    Key {
+        name: "country",
+        kind: Kind::Str,
+        flag: Flag::Value("country"),
+        default: "",
+    },
+    Key {
+        name: "zip",
+        kind: Kind::Str,
+        flag: Flag::Value("zip"),
+        default: "",
+    },
+    Key {
+        name: "coords",
+        kind: Kind::Str,
+        flag: Flag::Value("coords"),
+        default: "",
+    },
+    Key {
+        name: "lat",
+        kind: Kind::Float,
+        flag: Flag::Value("lat"),
+        default: "",
+    },
+    Key {
+        name: "lon",
+        kind: Kind::Float,
+        flag: Flag::Value("lon"),
+        default: "",
+    },
+    Key {
+        name: "labels",
+        kind: Kind::Bool,
+        flag: Flag::Switch("no_labels", false),
+        default: "true",
+    },
+    Key {
+        name: "label_from",
+        kind: Kind::Str,
+        flag: Flag::Value("label_from"),
+        default: "name",
+    },
+    Key {
+        name: "api_key",
+        kind: Kind::Str,
+        flag: Flag::Value("api_key"),
+        default: "",
+    },
+    Key {
+        name: "imperial",
+        kind: Kind::Bool,
+        flag: Flag::Switch("imperial", true),
+        default: "false",
+    },
+    Key {
+        name: "units",
+        kind: Kind::Str,
+        flag: Flag::Value("units"),
+        default: "metric",
+    },
+    Key {
+        name: "data",
+        kind: Kind::List,
+        flag: Flag::Value("data"),
+        default: "",
+    },
+    Key {
+        name: "at_offset",
+        kind: Kind::Str,
+        flag: Flag::Value("at_offset"),
+        default: "",
+    },
+    Key {
+        name: "template",
+        kind: Kind::Str,
+        flag: Flag::Value("template"),
+        default: "",
+    },
+    Key {
+        name: "lang",
+        kind: Kind::Str,
+        flag: Flag::Value("lang"),
+        default: "en",
+    },
+    Key {
+        name: "icons",
+        kind: Kind::Str,
+        flag: Flag::Value("icons"),
+        default: "nerdfont",
+    },
+    Key {
+        name: "night_dim",
+        kind: Kind::Bool,
+        flag: Flag::Switch("night_dim", true),
+        default: "false",
+    },
+    Key {
+        name: "dim_style",
+        kind: Kind::Str,
+        flag: Flag::Value("dim_style"),
+        default: "ansi",
+    },
+    Key {
+        name: "separator",
+        kind: Kind::Str,
+        flag: Flag::Value("separator"),
+        default: " ",
+    },
+    Key {
+        name: "item_labels",
+        kind: Kind::Bool,
+        flag: Flag::Switch("item_labels", true),
+        default: "false",
+    },
+    Key {
+        name: "color",
+        kind: Kind::Str,
+        flag: Flag::Value("color"),
+        default: "auto",
+    },
+    Key {
+        name: "uv_threshold",
+        kind: Kind::Float,
+        flag: Flag::Value("uv_threshold"),
+        default: "3",
+    },
+    Key {
+        name: "rain_threshold",
+        kind: Kind::Integer,
+        flag: Flag::Value("rain_threshold"),
+        default: "50",
+    },
+    Key {
+        name: "rain_hours",
+        kind: Kind::Integer,
+        flag: Flag::Value("rain_hours"),
+        default: "12",
+    },
+    Key {
+        name: "trend_delta",
+        kind: Kind::Float,
+        flag: Flag::Value("trend_delta"),
+        default: "0.5",
+    },
+    Key {
+        name: "trend_hours",
+        kind: Kind::Integer,
+        flag: Flag::Value("trend_hours"),
+        default: "3",
+    },
+    Key {
+        name: "history",
+        kind: Kind::Bool,
+        flag: Flag::Switch("history", true),
+        default: "false",
+    },
+    Key {
+        name: "accessible",
+        kind: Kind::Bool,
+        flag: Flag::Switch("accessible", true),
+        default: "false",
+    },
+    Key {
+        name: "call_budget",
+        kind: Kind::Integer,
+        flag: Flag::Value("call_budget"),
+        default: "50",
+    },
+    Key {
+        name: "max_staleness",
+        kind: Kind::Integer,
+        flag: Flag::Value("max_staleness"),
+        default: "",
+    },
+    Key {
+        name: "timeout",
+        kind: Kind::Integer,
+        flag: Flag::Value("timeout"),
+        default: "5",
+    },
+    Key {
+        name: "retries",
+        kind: Kind::Integer,
+        flag: Flag::Value("retries"),
+        default: "2",
+    },
+    Key {
+        name: "proxy",
+        kind: Kind::Str,
+        flag: Flag::Value("proxy"),
+        default: "",
+    },
+    Key {
+        name: "ca_cert",
+        kind: Kind::Str,
+        flag: Flag::Value("ca_cert"),
+        default: "",
+    },
+    Key {
+        name: "api_url",
+        kind: Kind::Str,
+        flag: Flag::Value("api_url"),
+        default: "",
+    },
+    Key {
+        name: "prefer_city_id",
+        kind: Kind::Bool,
+        flag: Flag::Switch("no_city_id", false),
+        default: "true",
+    },
+    Key {
+        name: "query_fallback",
+        kind: Kind::Bool,
+        flag: Flag::Switch("no_query_fallback", false),
+        default: "true",
+    },
+    Key {
+        name: "geocode",
+        kind: Kind::Bool,
+        flag: Flag::Switch("geocode", true),
+        default: "false",
+    },
+    Key {
+        name: "offline",
+        kind: Kind::Bool,
+        flag: Flag::Switch("offline", true),
+        default: "false",
+    },
+    Key {
+        name: "smoothing",
+        kind: Kind::Float,
+        flag: Flag::Value("smooth"),
+        default: "",
+    },
+    Key {
+        name: "output_file",
+        kind: Kind::Str,
+        flag: Flag::Value("output_file"),
+        default: "",
+    },
+    Key {
+        name: "json_sidecar",
+        kind: Kind::Str,
+        flag: Flag::Value("json_sidecar"),
+        default: "",
+    },
+    Key {
+        name: "textfile",
+        kind: Kind::Str,
+        flag: Flag::Value("textfile"),
+        default: "",
+    },
+    Key {
+        name: "notify",
+        kind: Kind::Bool,
+        flag: Flag::Switch("notify", true),
+        default: "false",
+    },
+    Key {
+        name: "offline_ok",
+        kind: Kind::Bool,
+        flag: Flag::Switch("offline_ok", true),
+        default: "false",
+    },
+    Key {
+        name: "stale_format",
+        kind: Kind::Str,
+        flag: Flag::Value("stale_format"),
+        default: "({age} ago)",
+    },
+    Key {
+        name: "digest",
+        kind: Kind::Str,
+        flag: Flag::Value("digest"),
+        default: "",
+    },
+    Key {
+        name: "digest_exec",
+        kind: Kind::Str,
+        flag: Flag::Value("digest_exec"),
+        default: "",
+    },
+    Key {
+        name: "digest_time",
+        kind: Kind::Str,
+        flag: Flag::Value("digest_time"),
+        default: "07:00",
+    },
+    Key {
+        name: "format.unit_style",
+        kind: Kind::Str,
+        flag: Flag::Value("unit_style"),
+        default: "symbolless",
+    },
+    Key {
+        name: "format.time_format",
+        kind: Kind::Str,
+        flag: Flag::Value("time_format"),
+        default: "",
+    },
+    Key {
+        name: "cache.enabled",
+        kind: Kind::Bool,
+        flag: Flag::Switch("no_cache", false),
+        default: "true",
+    },
+    Key {
+        name: "cache.current",
+        kind: Kind::Str,
+        flag: Flag::Value("cache_ttl"),
+        default: "10m",
+    },
+];
+
+impl Key {
+    /// Returns the environment variable setting the key, e.g. `TINYWX_API_KEY`
+    /// for `api_key` and `TINYWX_FORMAT_UNIT_STYLE` for `format.unit_style`.
+    pub fn env_var(&self) -> String {
+        format!("{}{}", ENV_PREFIX, self.name.replace('.', "_").to_uppercase())
+    }
+
+    /// Renders the default like a value that is set.
+    fn default_display(&self) -> String {
+        match self.default {
+            "" => "(not set)".to_string(),
+            // Unlike a variable, a default string is taken as it is, spaces
+            // and all.
+            default if self.kind == Kind::Str => Value::String(default.to_string()).to_string(),
+            default => self.parse(default).map_or_else(|_| default.to_string(), |value| value.to_string()),
+        }
+    }
+
+    /// Reads the key from the text of an environment variable.
+    fn parse(&self, text: &str) -> Result<Value> {
+        let text = text.trim();
+        let value = match self.kind {
+            Kind::Str => Value::String(text.to_string()),
+            Kind::Bool => match text.to_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => Value::Boolean(true),
+                "0" | "false" | "no" | "off" => Value::Boolean(false),
+                _ => return Err(anyhow!("expected true or false, got '{}'", text)),
+            },
+            Kind::Integer => Value::Integer(
+                text.parse::<u32>()
+                    .map_err(|_| anyhow!("expected a whole number, got '{}'", text))?
+                    .into(),
+            ),
+            Kind::Float => Value::Float(text.parse().map_err(|_| anyhow!("expected a number, got '{}'", text))?),
+            Kind::List => Value::Array(
+                text.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| Value::String(s.to_string()))
+                    .collect(),
+            ),
+        };
+        Ok(value)
+    }
+
+    /// Reads the key from the command line, if it is given there.
+    fn read_args(&self, args: &cli::Args) -> Option<Value> {
+        match self.flag {
+            Flag::Switch(id, value) => args.is_present(id).then_some(Value::Boolean(value)),
+            Flag::Value(id) => match self.kind {
+                Kind::Str => args.value_of(id).map(|s| Value::String(s.to_string())),
+                Kind::Bool => args.is_present(id).then_some(Value::Boolean(true)),
+                Kind::Integer => args.get::<u32>(id).map(|n| Value::Integer(n.into())),
+                Kind::Float => args.get::<f64>(id).map(Value::Float),
+                Kind::List => {
+                    let values = args.values_of(id);
+                    (!values.is_empty())
+                        .then(|| Value::Array(values.into_iter().map(|s| Value::String(s.to_string())).collect()))
+                }
+            },
+        }
+    }
+}
+
+/// The layer a value came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    File(String),
+    Env(String),
+    CommandLine,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => f.write_str("default"),
+            Origin::File(path) => write!(f, "file {}", path),
+            Origin::Env(var) => write!(f, "env {}", var),
+            Origin::CommandLine => f.write_str("command line"),
+        }
+    }
+}
+
+/// The merged layers: the table the configuration is read from, where each
+/// of [`KEYS`] came from, and the deprecated keys the layers had.
+#[derive(Debug, Default)]
+pub struct Layers {
+    table: Table,
+    origins: HashMap<&'static str, Origin>,
+    deprecated: Vec<deprecation::Used>,
+    /// The deprecated keys moved to their new keys, with where they were.
+    migrated: HashMap<&'static str, (&'static str, Origin)>,
+}
+
+impl Layers {
+    /// Starts from the config file at `path`, if any.
+    pub fn new(path: Option<&Path>) -> Result<Self> {
+        let mut layers = Layers::default();
+        let Some(path) = path else {
+            return Ok(layers);
+        };
+        let contents = std::fs::read_to_string(path).map_err(|e| anyhow!("cannot read {}: {}", path.display(), e))?;
+        layers.table = toml::from_str(&contents).map_err(|e| anyhow!("cannot read {}: {}", path.display(), e))?;
+        let origin = Origin::File(path.display().to_string());
+        for deprecated in deprecation::KEYS.iter().filter(|key| layers.table.contains_key(key.old)) {
+            layers.migrated.insert(deprecated.old, (deprecated.new, origin.clone()));
+        }
+        layers.deprecated = deprecation::migrate_file(&mut layers.table, &path.display().to_string());
+        for key in KEYS {
+            if lookup(&layers.table, key.name).is_some() {
+                layers.origins.insert(key.name, origin.clone());
+            }
+        }
+        Ok(layers)
+    }
+
+    /// Adds the environment variables, as returned by `var` for their names.
+    /// Empty variables are ignored.
+    pub fn env(mut self, var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        for key in KEYS {
+            let name = key.env_var();
+            let Some(text) = var(&name).filter(|text| !text.trim().is_empty()) else {
+                continue;
+            };
+            let value = key.parse(&text).map_err(|e| anyhow!("{}: {}", name, e))?;
+            if let Some(deprecated) = deprecation::key(key.name) {
+                self.migrate_env(deprecated, &value, &name, &var);
+                continue;
+            }
+            self.set(key, value, Origin::Env(name));
+        }
+        // Only a key set nowhere else, nor a command to get it, is taken from
+        // OpenWeatherMap's variable.
+        let key_set = lookup(&self.table, "api_key").is_some_and(|value| value.as_str() != Some(""));
+        if !key_set && lookup(&self.table, "api_key_cmd").is_none() {
+            if let Some(text) = var(OPENWEATHERMAP_API_KEY).filter(|text| !text.trim().is_empty()) {
+                if let Some(key) = KEYS.iter().find(|key| key.name == "api_key") {
+                    let origin = Origin::Env(OPENWEATHERMAP_API_KEY.to_string());
+                    self.set(key, Value::String(text.trim().to_string()), origin);
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// Moves the value of the deprecated key set by the environment variable
+    /// `name` to its new key, unless the environment also sets that.
+    fn migrate_env(
+        &mut self,
+        deprecated: &deprecation::Key,
+        value: &Value,
+        name: &str,
+        var: impl Fn(&str) -> Option<String>,
+    ) {
+        self.deprecated.push(deprecation::Used {
+            what: name.to_string(),
+            instead: deprecated.instead.to_string(),
+        });
+        self.migrated.insert(deprecated.old, (deprecated.new, Origin::Env(name.to_string())));
+        let Some(new) = KEYS.iter().find(|key| key.name == deprecated.new) else {
+            return;
+        };
+        if var(&new.env_var()).filter(|text| !text.trim().is_empty()).is_some() {
+            return;
+        }
+        if let Some(value) = (deprecated.migrate)(value) {
+            self.set(new, value, Origin::Env(name.to_string()));
+        }
+    }
+
+    /// Returns the deprecated keys the config file and the environment had,
+    /// which were moved to their new keys, and what else [`Layers::deprecate`]
+    /// noted.
+    pub fn deprecated(&self) -> &[deprecation::Used] {
+        &self.deprecated
+    }
+
+    /// Notes something deprecated the configuration came from, other than a
+    /// key, as the old name of the config file.
+    pub fn deprecate(&mut self, used: deprecation::Used) {
+        self.deprecated.push(used);
+    }
+
+    /// Adds the flags given on the command line.
+    pub fn args(mut self, args: &cli::Args) -> Self {
+        for key in KEYS {
+            if let Some(value) = key.read_args(args) {
+                self.set(key, value, Origin::CommandLine);
+            }
+        }
+        self
+    }
+
+    fn set(&mut self, key: &'static Key, value: Value, origin: Origin) {
+        let mut table = &mut self.table;
+        let mut path = key.name.split('.').peekable();
+        while let Some(part) = path.next() {
+            if path.peek().is_none() {
+                table.insert(part.to_string(), value);
+                break;
+            }
+            let entry = table.entry(part.to_string()).or_insert_with(|| Value::Table(Table::new()));
+            if !entry.is_table() {
+                *entry = Value::Table(Table::new());
+            }
+            let Value::Table(inner) = entry else {
+                unreachable!("replaced by a table above");
+            };
+            table = inner;
+        }
+        self.origins.insert(key.name, origin);
+    }
+
+    /// Reads the merged layers into `T`.
+    pub fn resolve<T: DeserializeOwned>(&self) -> Result<T> {
+        Value::Table(self.table.clone())
+            .try_into()
+            .map_err(|e| anyhow!("invalid configuration: {}", e))
+    }
+
+    /// Returns each of [`KEYS`] with its effective value, as TOML, and its
+    /// origin. A deprecated key that was moved to its new key, and is not
+    /// set otherwise, says so, with where it was.
+    pub fn show(&self) -> Vec<(&'static Key, String, Origin)> {
+        KEYS.iter()
+            .map(|key| match (lookup(&self.table, key.name), self.migrated.get(key.name)) {
+                (Some(value), _) => {
+                    let origin = self.origins.get(key.name).cloned().unwrap_or(Origin::Default);
+                    (key, display(key, value), origin)
+                }
+                (None, Some((new, origin))) => (key, format!("migrated to {}", new), origin.clone()),
+                (None, None) => (key, key.default_display(), Origin::Default),
+            })
+            .collect()
+    }
+}
+
+/// Returns the value at the dotted `path` in `table`.
+fn lookup<'a>(table: &'a Table, path: &str) -> Option<&'a Value> {
+    let (first, rest) = match path.split_once('.') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (path, None),
+    };
+    match (table.get(first)?, rest) {
+        (value, None) => Some(value),
+        (Value::Table(inner), Some(rest)) => lookup(inner, rest),
+        (_, Some(_)) => None,
+    }
+}
+
+/// Renders a value for display, with all but the end of the API key hidden.
+fn display(key: &Key, value: &Value) -> String {
+    match (key.name, value) {
+        ("api_key", Value::String(s)) if s.chars().count() > 4 => {
+            let end: String = s.chars().skip(s.chars().count() - 4).collect();
+            format!("\"…{}\"", end)
+        }
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// Writes `contents` to a config file of its own for the test `name`.
+    fn config_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("tinywx-layers-{}-{}.toml", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// Returns an environment of the `vars` only.
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| vars.iter().find(|(var, _)| *var == name).map(|(_, value)| value.to_string())
+    }
+
+    /// Returns the value and the origin `show` gives for the key `name`.
+    fn shown(layers: &Layers, name: &str) -> (String, Origin) {
+        let (_, value, origin) = layers.show().into_iter().find(|(key, _, _)| key.name == name).unwrap();
+        (value, origin)
+    }
+
+    #[test]
+    fn each_layer_overrides_the_one_before() {
+        let path = config_file("overrides", "units = \"imperial\"\ncity = \"paris\"\nlang = \"fr\"\n");
+        let file = Origin::File(path.display().to_string());
+        let matches = cli::command().get_matches_from(["tinywx", "--lang", "de"]);
+        let vars = [("TINYWX_UNITS", "standard"), ("TINYWX_LANG", "nl")];
+        let layers = Layers::new(Some(&path)).unwrap().env(env(&vars)).unwrap().args(&cli::Args::new(&matches));
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(shown(&layers, "separator"), ("\" \"".to_string(), Origin::Default));
+        assert_eq!(shown(&layers, "city"), ("\"paris\"".to_string(), file));
+        assert_eq!(shown(&layers, "units"), ("\"standard\"".to_string(), Origin::Env("TINYWX_UNITS".to_string())));
+        assert_eq!(shown(&layers, "lang"), ("\"de\"".to_string(), Origin::CommandLine));
+        let table: Table = layers.resolve().unwrap();
+        assert_eq!(table["units"].as_str(), Some("standard"));
+        assert_eq!(table["lang"].as_str(), Some("de"));
+    }
+
+    #[test]
+    fn variables_are_read_as_their_kind() {
+        let vars = [
+            ("TINYWX_DATA", "temp, humidity,,wind"),
+            ("TINYWX_NIGHT_DIM", "yes"),
+            ("TINYWX_RETRIES", "3"),
+            ("TINYWX_SMOOTHING", "0.5"),
+            ("TINYWX_FORMAT_UNIT_STYLE", "compact"),
+            ("TINYWX_CITY", "  "),
+        ];
+        let layers = Layers::new(None).unwrap().env(env(&vars)).unwrap();
+        assert_eq!(shown(&layers, "data").0, "[\"temp\", \"humidity\", \"wind\"]");
+        assert_eq!(shown(&layers, "night_dim").0, "true");
+        assert_eq!(shown(&layers, "retries").0, "3");
+        assert_eq!(shown(&layers, "smoothing").0, "0.5");
+        let origin = Origin::Env("TINYWX_FORMAT_UNIT_STYLE".to_string());
+        assert_eq!(shown(&layers, "format.unit_style"), ("\"compact\"".to_string(), origin));
+        // An empty variable is not set.
+        assert_eq!(shown(&layers, "city"), ("(not set)".to_string(), Origin::Default));
+    }
+
+    #[test]
+    fn bad_variables_are_named() {
+        for (var, text, error) in [
+            ("TINYWX_NIGHT_DIM", "maybe", "TINYWX_NIGHT_DIM: expected true or false, got 'maybe'"),
+            ("TINYWX_RETRIES", "-1", "TINYWX_RETRIES: expected a whole number, got '-1'"),
+            ("TINYWX_LAT", "north", "TINYWX_LAT: expected a number, got 'north'"),
+        ] {
+            let e = Layers::new(None).unwrap().env(env(&[(var, text)])).unwrap_err();
+            assert_eq!(e.to_string(), error);
+        }
+    }
+
+    #[test]
+    fn the_api_key_of_openweathermap_ranks_below_the_file() {
+        let vars = [(OPENWEATHERMAP_API_KEY, "abcdefgh")];
+        let layers = Layers::new(None).unwrap().env(env(&vars)).unwrap();
+        let origin = Origin::Env(OPENWEATHERMAP_API_KEY.to_string());
+        assert_eq!(shown(&layers, "api_key"), ("\"…efgh\"".to_string(), origin));
+
+        let path = config_file("api-key", "api_key = \"0123456789\"\n");
+        let layers = Layers::new(Some(&path)).unwrap().env(env(&vars)).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(shown(&layers, "api_key"), ("\"…6789\"".to_string(), Origin::File(path.display().to_string())));
+    }
+
+    #[test]
+    fn deprecated_variables_move_to_their_new_keys() {
+        let layers = Layers::new(None).unwrap().env(env(&[("TINYWX_IMPERIAL", "true")])).unwrap();
+        let origin = Origin::Env("TINYWX_IMPERIAL".to_string());
+        assert_eq!(shown(&layers, "units"), ("\"imperial\"".to_string(), origin.clone()));
+        assert_eq!(shown(&layers, "imperial"), ("migrated to units".to_string(), origin));
+        assert_eq!(layers.deprecated()[0].what, "TINYWX_IMPERIAL");
+
+        let vars = [("TINYWX_IMPERIAL", "true"), ("TINYWX_UNITS", "metric")];
+        let layers = Layers::new(None).unwrap().env(env(&vars)).unwrap();
+        assert_eq!(shown(&layers, "units"), ("\"metric\"".to_string(), Origin::Env("TINYWX_UNITS".to_string())));
+    }
+
+    #[test]
+    fn every_key_has_its_variable() {
+        let key = |name: &str| KEYS.iter().find(|key| key.name == name).unwrap();
+        assert_eq!(key("api_key").env_var(), "TINYWX_API_KEY");
+        assert_eq!(key("cache.current").env_var(), "TINYWX_CACHE_CURRENT");
+        let mut vars: Vec<String> = KEYS.iter().map(Key::env_var).collect();
+        vars.sort();
+        vars.dedup();
+        assert_eq!(vars.len(), KEYS.len());
+    }
+}
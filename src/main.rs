@@ -1,145 +1,2290 @@
 use anyhow::Result;
-use clap::Arg;
 use serde::Deserialize;
-use std::fs;
-use std::path::Path;
+use std::collections::HashMap;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use wx::item::{Endpoint, ITEMS, PLACEHOLDER};
+use wx::format::{Quantity, UnitStyle};
+use wx::locale::Form;
+use wx::DataItem;
 
-#[derive(Debug, Default, Deserialize)]
+mod batch;
+mod cache;
+mod chart;
+mod card;
+mod check;
+mod cities;
+mod cityid;
+mod cli;
+mod color;
+mod completions;
+mod contract;
+mod deprecation;
+mod digest;
+mod doctor;
+mod exit;
+mod fallback;
+mod history;
+mod layers;
+mod metrics;
+mod notify;
+mod output;
+mod paths;
+mod plan;
+mod quiet;
+mod ratelimit;
+mod select;
+mod sink;
+mod slot;
+mod smooth;
+mod template;
+mod version;
+mod watch;
+
+#[derive(Clone, Debug, Default, Deserialize)]
 struct Config {
+    #[serde(default)]
     city: String,
     #[serde(default)]
     state: String,
+    #[serde(default)]
     country: String,
+    coords: Option<String>,
+    /// Zip or postal code, with `country`, instead of a city.
+    zip: Option<String>,
+    /// Coordinates as two keys, instead of `coords`.
+    lat: Option<f64>,
+    lon: Option<f64>,
+    /// Whether to label each result with coordinates from stdin.
+    labels: Option<bool>,
+    #[serde(default)]
+    label_from: batch::LabelFrom,
+    /// Locations fetched together, from `[[locations]]`, unless the config
+    /// or the command line sets a single one.
+    #[serde(default)]
+    locations: Vec<batch::Location>,
+    #[serde(default)]
     api_key: String,
+    /// Command printing the API key, as in "pass show owm", run if no layer
+    /// sets `api_key`.
+    api_key_cmd: Option<String>,
     #[serde(default)]
     imperial: bool,
+    /// Replaces `imperial` if set.
+    units: Option<UnitsSetting>,
+    #[serde(default)]
+    data: Vec<DataItem>,
+    #[serde(default)]
+    icons: wx::IconSet,
+    #[serde(default)]
+    night_dim: bool,
+    #[serde(default)]
+    dim_style: wx::Dim,
+    /// When to color the temperatures, the icon and the description.
+    #[serde(default)]
+    color: color::When,
+    #[serde(default)]
+    colors: color::ColorConfig,
+    uv_threshold: Option<f64>,
+    /// Probability of precipitation, in percent, from which next_rain tells
+    /// of it.
+    rain_threshold: Option<u32>,
+    /// Hours ahead next_rain looks.
+    rain_hours: Option<u32>,
+    /// Change in temperature, in the display units, from which trend tells
+    /// it rose or fell.
+    trend_delta: Option<f64>,
+    /// Hours back trend looks for the reading before.
+    trend_hours: Option<u32>,
+    #[serde(default)]
+    history: bool,
+    /// Whether to spell every item out for screen readers.
+    #[serde(default)]
+    accessible: bool,
+    call_budget: Option<u32>,
+    /// Oldest, in seconds, the data shown in watch mode may get.
+    max_staleness: Option<u32>,
+    /// Seconds to give each request.
+    timeout: Option<u32>,
+    /// Retries of a request that failed in a way that may pass.
+    retries: Option<u32>,
+    /// Proxy for all requests, instead of those of the environment.
+    proxy: Option<String>,
+    /// PEM file of a root certificate to trust besides the system's.
+    ca_cert: Option<String>,
+    /// Base URL of the API, instead of OpenWeatherMap's.
+    api_url: Option<String>,
+    prefer_city_id: Option<bool>,
+    /// Whether to try simpler forms of a city query that is not found.
+    query_fallback: Option<bool>,
+    /// Whether to query cities by the coordinates the geocoding API finds.
+    geocode: Option<bool>,
+    /// Whether to find cities in the database built in instead.
+    offline: Option<bool>,
+    /// Forecast slot the items of the current weather render from instead.
+    at_offset: Option<slot::Offset>,
+    lang: Option<String>,
+    output_file: Option<String>,
+    json_sidecar: Option<String>,
+    /// File to write the metrics of each fetch to, for node_exporter.
+    textfile: Option<String>,
+    /// Whether to also send the output as a desktop notification.
+    #[serde(default)]
+    notify: bool,
+    /// When notifications are critical.
+    #[serde(default)]
+    urgency: notify::UrgencyConfig,
+    /// Whether to show the last cached weather when the network is down.
+    #[serde(default)]
+    offline_ok: bool,
+    /// The mark of that weather, with `{age}` for how old it is.
+    stale_format: Option<String>,
+    /// More secondary outputs, from `[[outputs]]`.
+    #[serde(default)]
+    outputs: Vec<sink::OutputConfig>,
+    /// Quiet hours of the outputs that have none of their own.
+    #[serde(flatten)]
+    quiet: sink::QuietConfig,
+    /// Where to send the daily digest in watch mode, unless `digest_exec` is
+    /// set.
+    digest: Option<digest::Output>,
+    digest_exec: Option<String>,
+    /// Local time of the daily digest, as "HH:MM".
+    digest_time: Option<String>,
+    /// Weight of each new reading when smoothing in watch mode.
+    smoothing: Option<f64>,
+    /// Replaces `data` with the items it names.
+    template: Option<template::Template>,
+    /// What goes between the items on one line, with escape sequences.
+    separator: Option<String>,
+    /// Whether to prefix each item on one line with its label.
+    #[serde(default)]
+    item_labels: bool,
+    #[serde(default)]
+    format: FormatConfig,
+    #[serde(default)]
+    cache: cache::CacheConfig,
+    #[serde(skip)]
+    explain: bool,
+    #[serde(skip)]
+    verbose: bool,
+    #[serde(skip)]
+    debug_meta: bool,
+    #[serde(skip)]
+    pretty: bool,
+    #[serde(skip)]
+    output: output::Format,
+}
+
+/// The `[format]` table: how values are rendered, for all items and per item.
+#[derive(Clone, Debug, Default, Deserialize)]
+struct FormatConfig {
+    unit_style: Option<UnitStyle>,
+    /// How the time items are rendered, instead of "%H:%M:%S" for obs_time
+    /// and now_time and "%H:%M" for sunrise and sunset.
+    time_format: Option<String>,
+    /// How rain and snow render when it is dry.
+    dry: Option<Dry>,
+    #[serde(default)]
+    items: HashMap<DataItem, ItemFormat>,
+}
+
+/// Overrides of the `[format]` table for one item, in `[format.items.<item>]`.
+#[derive(Clone, Debug, Default, Deserialize)]
+struct ItemFormat {
+    unit_style: Option<UnitStyle>,
+    dry: Option<Dry>,
+}
+
+/// How the rain and snow items render when the response has no amount, as
+/// when it is dry.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Dry {
+    /// As 0, as in "0mm/h".
+    #[default]
+    Zero,
+    /// Empty, for bars to leave them out.
+    Empty,
+}
+
+/// The items of the `data` setting as they were named, to warn about
+/// deprecated names.
+#[derive(Debug, Default, Deserialize)]
+struct ItemNames {
     #[serde(default)]
     data: Vec<String>,
 }
 
+/// The `units` setting, which may have been written as an alias like
+/// "celsius".
+#[derive(Clone, Debug)]
+struct UnitsSetting {
+    /// `None` for "auto": the units of the location's country.
+    units: Option<wx::Units>,
+    /// The alias, as written, if it was one.
+    alias: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for UnitsSetting {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        if s.trim().eq_ignore_ascii_case("auto") {
+            return Ok(UnitsSetting { units: None, alias: None });
+        }
+        let (units, alias) = wx::Units::lookup(&s).map_err(serde::de::Error::custom)?;
+        Ok(UnitsSetting {
+            units: Some(units),
+            alias: alias.then_some(s),
+        })
+    }
+}
+
+impl Config {
+    /// Returns the units to display, deciding "auto" by the country code of
+    /// the query.
+    fn units(&self) -> wx::Units {
+        self.units_in(Some(self.country.as_str()).filter(|country| !country.is_empty()))
+    }
+
+    /// Returns the units to display for a location in `country`. Units that
+    /// are set, and `imperial`, beat "auto", which goes by the country.
+    fn units_in(&self, country: Option<&str>) -> wx::Units {
+        match &self.units {
+            Some(UnitsSetting { units: Some(units), .. }) => *units,
+            _ if self.imperial => wx::Units::Imperial,
+            Some(UnitsSetting { units: None, .. }) => country.map_or(wx::Units::Metric, wx::Units::for_country),
+            None => wx::Units::Metric,
+        }
+    }
+
+    /// Returns whether the units are decided by the country of each
+    /// response.
+    fn auto_units(&self) -> bool {
+        matches!(self.units, Some(UnitsSetting { units: None, .. })) && !self.imperial
+    }
+
+    /// Returns the units to fetch in. With "auto", the country may only be
+    /// known from the response, so the values are fetched in standard units
+    /// and converted once it is.
+    fn fetched_units(&self) -> wx::Units {
+        if self.auto_units() {
+            wx::Units::Standard
+        } else {
+            self.units()
+        }
+    }
+
+    /// Returns whether the locations to fetch are those of `[[locations]]`,
+    /// which neither the config nor the command line sets a single one
+    /// instead of.
+    fn several_locations(&self) -> bool {
+        let single = !self.city.is_empty()
+            || self.zip.is_some()
+            || self.coords.is_some()
+            || self.lat.is_some()
+            || self.lon.is_some();
+        !self.locations.is_empty() && !single
+    }
+
+    /// Returns the locations a run queries, as they were written, as in
+    /// "Austin,TX,US".
+    fn queries(&self) -> Vec<String> {
+        if self.several_locations() {
+            return self.locations.iter().map(batch::Location::query).collect();
+        }
+        let single = batch::Location {
+            city: self.city.clone(),
+            state: self.state.clone(),
+            country: self.country.clone(),
+            zip: self.zip.clone(),
+            coords: self.coords.clone(),
+            lat: self.lat,
+            lon: self.lon,
+            name: None,
+        };
+        Some(single.query()).filter(|query| !query.is_empty()).into_iter().collect()
+    }
+
+    /// Returns how the items on one line are joined.
+    fn joining(&self) -> output::Joining {
+        output::Joining {
+            separator: self.separator.as_deref().map_or_else(|| " ".to_string(), output::unescape),
+            labels: self.item_labels,
+        }
+    }
+
+    /// Returns a warning about an alias in the `units` setting, if it has
+    /// one.
+    fn units_alias_warning(&self) -> Option<String> {
+        let setting = self.units.as_ref()?;
+        let alias = setting.alias.as_deref()?;
+        Some(format!(
+            "units = \"{}\" is read as \"{}\"; write that instead",
+            alias,
+            setting.units?.as_str()
+        ))
+    }
+}
+
+/// Points out on stderr which units "auto" picked, once for each country.
+fn note_auto_units(country: Option<&str>, units: wx::Units) {
+    static NOTED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    let Ok(mut noted) = NOTED.lock() else {
+        return;
+    };
+    let country = country.unwrap_or_default().to_uppercase();
+    if noted.contains(&country) {
+        return;
+    }
+    match country.as_str() {
+        "" => exit::warning!("units = \"auto\": {}, as the country is not known", units.as_str()),
+        _ => exit::warning!("units = \"auto\": {} for {}", units.as_str(), country),
+    }
+    noted.push(country);
+}
+
+impl FormatConfig {
+    fn unit_style(&self, item: DataItem) -> UnitStyle {
+        self.items
+            .get(&item)
+            .and_then(|f| f.unit_style)
+            .or(self.unit_style)
+            .unwrap_or_default()
+    }
+
+    fn dry(&self, item: DataItem) -> Dry {
+        self.items.get(&item).and_then(|f| f.dry).or(self.dry).unwrap_or_default()
+    }
+}
+
+/// Kilometers in a mile, for distances OpenWeatherMap does not convert.
+const KM_PER_MILE: f64 = 1.609344;
+
 fn main() {
-    match app() {
-        Ok(x) => println!("{}", x),
-        Err(e) => eprintln!("{}", e),
-    }
-}
-
-fn app() -> Result<String> {
-    let matches = clap::App::new("tinywx")
-        .version("0.1.0")
-        .about("Fetch current weather from OpenWeatherMap.")
-        .arg(
-            Arg::new("city")
-                .short('c')
-                .long("city")
-                .value_name("CITY")
-                .required(true)
-                .help("City name (enclosed within quotes if it contains spaces)")
-        )
-        .arg(
-            Arg::new("state")
-                .short('s')
-                .long("state")
-                .value_name("STATE")
-                .required(false)
-                .help("State abbreviation")
-        )
-        .arg(
-            Arg::new("country")
-                .short('C')
-                .long("country")
-                .value_name("COUNTRY_CODE")
-                .required(true)
-                .help("Country code")
-        )
-        .arg(
-            Arg::new("data")
-                .short('d')
-                .long("data")
-                .value_name("WX_DATA")
-                .required(true)
-                .multiple_values(true)
-                .possible_values(&["icon", "temp", "feels_like", "description", "humidity"])
-                .help("Weather data to display"),
-        )
-        .arg(
-            Arg::new("imperial")
-                .short('i')
-                .long("imperial")
-                .required(false)
-                .help("Display imperial units instead of metric"),
-        )
-        .arg(
-            Arg::new("api_key")
-                .short('k')
-                .long("api-key")
-                .value_name("API_KEY")
-                .required(true)
-                .takes_value(true)
-                .help("OpenWeatherMap API key"),
-        )
-        .arg(
-            Arg::new("file")
-                .short('f')
-                .long("file")
-                .value_name("FILE")
-                .required(false)
-                .help("Path to TOML file to read configuration from")
-                .long_help(
-                    "Path to TOML file to read configuration from. Note that \
-                    arguments can be read from the command line \
-                    or from a configuration file, but not both at \
-                    the same time."
+    if let Err(e) = app() {
+        if !exit::quiet() {
+            eprintln!("{}", e);
+        }
+        process::exit(exit::ErrorKind::of(&e).code());
+    }
+}
+
+fn app() -> Result<()> {
+    let matches = match cli::command().try_get_matches() {
+        Ok(matches) => matches,
+        // The arguments are not parsed, so --quiet is looked for as given;
+        // help and the version are not errors, and are printed anyway.
+        Err(e) if e.use_stderr() && std::env::args().any(|arg| arg == "-q" || arg == "--quiet") => {
+            process::exit(exit::ErrorKind::Usage.code())
+        }
+        Err(e) => e.exit(),
+    };
+    let args = cli::Args::new(&matches);
+    exit::set_quiet(args.is_present("quiet"));
+
+    if args.is_present("version") {
+        if args.is_present("verbose") {
+            return print(version::info());
+        }
+        return print(format_args!("tinywx {}", env!("CARGO_PKG_VERSION")));
+    }
+
+    if args.is_present("contract") {
+        return print(serde_json::to_string_pretty(&contract::json())?);
+    }
+
+    if args.is_present("list_data") {
+        return list(ITEMS.iter().map(|info| (info.name, info.description)));
+    }
+
+    if args.is_present("list_transforms") {
+        return list(template::TRANSFORMS.iter().map(|info| (info.usage, info.description)));
+    }
+
+    if args.subcommand == "completions" {
+        let shell = args.value_of("shell").unwrap_or_default();
+        let script = completions::generate(shell, cli::command());
+        let mut stdout = io::stdout().lock();
+        return closed_pipe_is_done(stdout.write_all(script.as_bytes()).and_then(|()| stdout.flush()));
+    }
+
+    if args.subcommand == "config init" || args.is_present("init_config") {
+        let path = paths::default_config_file()
+            .ok_or_else(|| anyhow::anyhow!("no config directory (is $HOME set?)"))?;
+        init_config(&path)?;
+        return print(format_args!("wrote {}; set city, country and api_key in it", path.display()));
+    }
+
+    // Without -f, the config file in the config directory is read if there is
+    // one. With it, `~` and variables are expanded as by a shell.
+    let given = args.value_of("file").or_else(|| args.value_of("path"));
+    let file = given.map(paths::resolve).or_else(paths::config_file);
+    let legacy = paths::legacy_config_file();
+    let legacy_file = file.as_ref().filter(|path| given.is_none() && Some(*path) == legacy.as_ref());
+
+    if args.subcommand == "doctor" {
+        let options = doctor::Options {
+            file: file.as_deref(),
+            api_key: args.value_of("api_key"),
+            icons: args.value_of("icons").map(str::parse).transpose()?,
+            live: args.is_present("live"),
+        };
+        let checks = doctor::run(&doctor::Live, &doctor::Disk, &options);
+        for check in &checks {
+            print(check)?;
+        }
+        if checks.iter().any(|c| c.status == doctor::Status::Fail) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Settings come from the config file, then the environment, then the
+    // command line, each overriding the one before.
+    let mut layers = layers::Layers::new(file.as_deref())
+        .and_then(|layers| layers.env(|name| std::env::var(name).ok()))
+        .map_err(|e| exit::usage(e.to_string()))?
+        .args(&args);
+    if let Some(path) = legacy_file {
+        layers.deprecate(deprecation::Used {
+            what: format!("the config file {}", path.display()),
+            instead: "config.toml in the same directory".to_string(),
+        });
+    }
+
+    if args.subcommand == "config check" {
+        let problems = match &file {
+            Some(path) => check::file(path).map_err(|e| exit::usage(e.to_string()))?,
+            None => Vec::new(),
+        };
+        for problem in &problems {
+            print(problem)?;
+        }
+        for used in layers.deprecated() {
+            print(format_args!("{} is deprecated; use {} instead", used.what, used.instead))?;
+        }
+        // An alias of the units works, as a deprecated key does, but is
+        // reported as one is.
+        let alias = layers.resolve::<Config>().ok().and_then(|cfg| cfg.units_alias_warning());
+        if let Some(warning) = &alias {
+            print(warning)?;
+        }
+        if !problems.is_empty() || !layers.deprecated().is_empty() || alias.is_some() {
+            std::process::exit(1);
+        }
+        let cfg: Config = layers.resolve().map_err(|e| exit::usage(e.to_string()))?;
+        print("OK")?;
+        return summary(&cfg);
+    }
+
+    if args.subcommand == "config show" {
+        for (key, value, origin) in layers.show() {
+            if args.is_present("origins") {
+                print(format_args!("{:<18} {:<24} {}", key.name, value, origin))?;
+            } else {
+                print(format_args!("{:<18} {}", key.name, value))?;
+            }
+        }
+        if let Some(interval) = args.get::<u64>("watch") {
+            let cfg: Config = layers.resolve().map_err(|e| exit::usage(e.to_string()))?;
+            let budget = cfg.call_budget.unwrap_or(ratelimit::DEFAULT_BUDGET);
+            let calls = ratelimit::calls_per_refresh(&cfg.data, 1);
+            let interval = ratelimit::tune_interval(interval, calls, budget);
+            let staleness = watch::worst_staleness(interval, args.get::<u64>("prefetch").unwrap_or(0));
+            print(format_args!(
+                "\nrefreshing every {} seconds shows data up to {} seconds old, plus the time a late \
+                fetch takes",
+                interval, staleness
+            ))?;
+        }
+        return Ok(());
+    }
+
+    if args.subcommand == "cache stats" {
+        let cfg: Config = layers.resolve().map_err(|e| exit::usage(e.to_string()))?;
+        print(format_args!("{:<10} {:>7} {:>7} {:>10}  ttl", "endpoint", "entries", "fresh", "bytes"))?;
+        for (class, stats) in cache::stats(&cfg.cache) {
+            print(format_args!(
+                "{:<10} {:>7} {:>7} {:>10}  {}",
+                class.name(),
+                stats.entries,
+                stats.fresh,
+                stats.bytes,
+                cfg.cache.ttl(class)
+            ))?;
+        }
+        return Ok(());
+    }
+
+    // A mistake in the file fails every run, rather than being ignored.
+    if let Some(path) = &file {
+        let problems = check::file(path).map_err(|e| exit::usage(e.to_string()))?;
+        if !problems.is_empty() {
+            let lines: Vec<String> = problems.iter().map(|problem| format!("{}: {}", path.display(), problem)).collect();
+            return Err(exit::usage(lines.join("\n")));
+        }
+    }
+    let mut cfg: Config = layers.resolve().map_err(|e| exit::usage(e.to_string()))?;
+    if let (true, Some(command)) = (cfg.api_key.trim().is_empty(), &cfg.api_key_cmd) {
+        cfg.api_key = api_key_from(command).map_err(|e| exit::usage(e.to_string()))?;
+    }
+    if let Some(warning) = cfg.units_alias_warning() {
+        exit::warning!("{}", warning);
+    }
+    if !cfg.api_key.trim().is_empty() && !wx::plausible_api_key(&cfg.api_key) {
+        exit::warning!(
+            "the API key does not look like an OpenWeatherMap key, which has 32 \
+            hexadecimal characters"
+        );
+    }
+    let names: ItemNames = layers.resolve().map_err(|e| exit::usage(e.to_string()))?;
+    let template_names = cfg.template.as_ref().map(template::Template::names).unwrap_or_default();
+    // Deprecated names still work, but every run using them says so, once.
+    let mut deprecated = deprecation::flags_used(std::env::args());
+    deprecated.extend_from_slice(layers.deprecated());
+    deprecated.extend(deprecation::items_used(names.data.iter().map(String::as_str).chain(template_names)));
+    if let Some(notice) = deprecation::notice(&deprecated) {
+        if args.is_present("strict") {
+            return Err(exit::usage(notice));
+        }
+        exit::warning!("{}", notice);
+    }
+
+    if let Some(template) = &cfg.template {
+        cfg.data = template.items();
+    }
+    if let Some(format) = cfg.format.time_format.as_deref().filter(|f| !wx::format::valid_time_format(f)) {
+        return Err(exit::usage(format!(
+            "invalid time format '{}': expected a strftime format, as in %H:%M or %I:%M %p",
+            format
+        )));
+    }
+
+    cfg.explain = args.is_present("explain");
+    cfg.verbose = args.is_present("verbose");
+    cfg.debug_meta = args.is_present("debug_meta");
+    cfg.pretty = args.is_present("pretty");
+    // People get the card, and scripts and bars the line they always got.
+    cfg.output = match args.value_of("output") {
+        Some(format) => format.parse()?,
+        // The card abbreviates and cuts lines, which screen readers read badly.
+        None if io::stdout().is_terminal() && !cfg.accessible => output::Format::Card,
+        None => output::Format::Plain,
+    };
+
+    if args.subcommand == "today" {
+        cfg.data = vec![DataItem::DayStory];
+    }
+
+    if args.subcommand == "forecast" {
+        let hours = args.get::<i64>("hours").unwrap_or(24);
+        let width = args.get::<usize>("width").unwrap_or_else(terminal_width);
+        return forecast(&cfg, hours, args.is_present("graph").then_some(width));
+    }
+
+    if args.is_present("daily") {
+        return daily(&cfg);
+    }
+
+    if args.is_present("resolve") {
+        let select = args.is_present("select").then(|| args.get::<u64>("select"));
+        return resolve(&cfg, select, file.as_deref());
+    }
+
+    // Raw JSON has everything, whatever the items.
+    if cfg.data.is_empty() && cfg.output != output::Format::RawJson {
+        return Err(exit::usage("nothing to display: pass -d or -t, or set data or TINYWX_DATA"));
+    }
+
+    if let Some(steps) = args.get::<u64>("forecast") {
+        return upcoming(&cfg, steps as usize);
+    }
+
+    // Items this build cannot render keep their place, as a placeholder.
+    for &x in &cfg.data {
+        if let Some(why) = unsupported(x) {
+            exit::warning!("{} renders {}: {}", x, PLACEHOLDER, why);
+        }
+    }
+
+    if args.is_present("plan") || args.is_present("max_requests") {
+        let plan = plan(&cfg)?;
+        if args.is_present("plan") {
+            print(plan)?;
+            return Ok(());
+        }
+        if let Some(max) = args.get::<usize>("max_requests") {
+            if plan.max_requests() > max {
+                return Err(exit::usage(format!(
+                    "this could take {} API requests, more than --max-requests {} (see --plan)",
+                    plan.max_requests(),
+                    max
+                )));
+            }
+        }
+    }
+
+    if let Some(interval) = args.get::<u64>("watch") {
+        if cfg.several_locations() {
+            return Err(exit::usage("watch mode takes a single location, not [[locations]]"));
+        }
+        // Refreshing too often would exceed the rate limit of the API key.
+        let budget = cfg.call_budget.unwrap_or(ratelimit::DEFAULT_BUDGET);
+        let calls = ratelimit::calls_per_refresh(&cfg.data, 1);
+        let tuned = ratelimit::tune_interval(interval, calls, budget);
+        if tuned != interval {
+            let message = if tuned == ratelimit::MIN_INTERVAL {
+                format!(
+                    "refreshing every {} seconds is more often than the minimum of every {} seconds",
+                    interval,
+                    ratelimit::MIN_INTERVAL
                 )
-                .conflicts_with_all(&["city", "state", "country", "data", "imperial", "api_key"]),
-        )
-        .get_matches();
-
-    // If "file" was supplied, read configuration from it.
-    // Else, process the command line arguments.
-    let mut cfg = Config::default();
-
-    #[allow(clippy::unwrap_used)]
-    // We can use unwrap() here because Clap ensures that values are set.
-    if let Some(filename) = matches.get_one::<String>("file") {
-        cfg = toml_from_file(Path::new(filename))?;
-    } else {
-        cfg.city = matches.value_of("city").unwrap().to_string();
-        cfg.state = matches.value_of("state").unwrap_or("").to_string();
-        cfg.country = matches.value_of("country").unwrap().to_string();
+            } else {
+                format!(
+                    "refreshing every {} seconds makes {:.0} API calls per minute, more than the \
+                    budget of {}",
+                    interval,
+                    ratelimit::calls_per_minute(interval, calls),
+                    budget
+                )
+            };
+            if args.is_present("strict") {
+                return Err(exit::usage(message));
+            }
+            exit::warning!("{}; refreshing every {} seconds instead", message, tuned);
+        }
+        let interval = tuned;
 
-        cfg.imperial = matches.is_present("imperial");
+        let prefetch = args.get::<u64>("prefetch").unwrap_or(0);
+        if prefetch >= interval {
+            return Err(exit::usage("--prefetch must be shorter than the --watch interval"));
+        }
+        let staleness = watch::worst_staleness(interval, prefetch);
+        if let Some(max) = cfg.max_staleness.filter(|&max| staleness > u64::from(max)) {
+            return Err(exit::usage(format!(
+                "refreshing every {} seconds, with {} seconds of prefetch, shows data up to {} \
+                seconds old, more than max_staleness {}",
+                interval, prefetch, staleness, max
+            )));
+        }
+        let smoother = match cfg.smoothing {
+            Some(alpha) if !smooth::valid_alpha(alpha) => {
+                return Err(exit::usage("the smoothing weight must be more than 0 and at most 1"));
+            }
+            Some(alpha) => Some(Mutex::new(smooth::Smoother::new(alpha))),
+            None => None,
+        };
+        let digest = digest_settings(&cfg).map_err(|e| exit::usage(e.to_string()))?;
+        // Each refresh is to show new readings, not the ones before.
+        cfg.cache.cap_current(Duration::from_secs(interval / 2));
+        // One client for all refreshes, which reuses its connections.
+        let client = client(&cfg, cfg.fetched_units())?;
+        let mut sinks = sinks(&cfg, true);
+        let shown = cfg.clone();
+        let format = cfg.output;
+        return watch::run(
+            Duration::from_secs(interval),
+            Duration::from_secs(prefetch),
+            cfg.verbose,
+            move || fetch_with(&cfg, &client, smoother.as_ref()),
+            move |report, line| {
+                sink::deliver_all(&mut sinks, &report.rendered());
+                if let Some(settings) = &digest {
+                    send_digest(&shown, settings, report.timezone);
+                }
+                report.finish_into(shown.explain, line);
+            },
+            move |line| format.stale(line),
+        );
+    }
 
-        cfg.api_key = matches.value_of("api_key").unwrap().to_string();
+    let mut sinks = sinks(&cfg, false);
 
-        cfg.data = matches
-            .values_of("data")
-            .unwrap()
-            .map(ToString::to_string)
-            .collect();
+    if cfg.notify && (cfg.coords.as_deref() == Some("-") || cfg.several_locations()) {
+        return Err(exit::usage("--notify works with a single location"));
+    }
+    if cfg.offline_ok && (cfg.coords.as_deref() == Some("-") || cfg.several_locations()) {
+        return Err(exit::usage("--offline-ok works with a single location"));
+    }
+
+    // With coordinates "-", read one location per line from stdin. Labeled
+    // results are aligned, so they are printed once all are in.
+    if cfg.coords.as_deref() == Some("-") {
+        let labels = cfg.labels.unwrap_or(true);
+        let mut results = Vec::new();
+        for line in io::stdin().lock().lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            cfg.coords = Some(line.clone());
+            match fetch(&cfg, None) {
+                Ok(report) => {
+                    sink::deliver_all(&mut sinks, &report.rendered());
+                    let label = cfg.label_from.label(&line, report.city.as_deref());
+                    let output = report.finish(cfg.explain);
+                    if labels {
+                        results.push((label, line, output));
+                    } else {
+                        print(output)?;
+                    }
+                }
+                Err(e) if !exit::quiet() => eprintln!("{}", e),
+                Err(_) => {}
+            }
+        }
+        if !results.is_empty() {
+            print(batch::render(cfg.output, &results))?;
+        }
+        return Ok(());
+    }
+
+    let city_ids = args.get_all::<u64>("city_id");
+    if !city_ids.is_empty() {
+        return fetch_group(&cfg, &city_ids, &mut sinks);
+    }
+
+    if cfg.several_locations() {
+        return fetch_locations(&cfg, &mut sinks);
+    }
+
+    let report = match fetch(&cfg, None) {
+        Err(e) if cfg.offline_ok && e.downcast_ref::<wx::NetworkError>().is_some() => offline(&cfg, e)?,
+        result => result?,
+    };
+    print(report.painted.as_deref().unwrap_or(&report.line))?;
+    sink::deliver_all(&mut sinks, &report.rendered());
+    // The output is printed even if the notification fails, for bars.
+    let notified = report.notification.as_ref().map_or(Ok(()), notify::send);
+    report.finish(cfg.explain);
+    notified
+}
+
+/// Prints what a run with `cfg` queries, for `tinywx config check`.
+fn summary(cfg: &Config) -> Result<()> {
+    let locations = cfg.queries();
+    let items = cfg.template.as_ref().map(template::Template::items).unwrap_or_else(|| cfg.data.clone());
+    let or_none = |list: &[String], separator| match list.is_empty() {
+        true => "none".to_string(),
+        false => list.join(separator),
+    };
+    let names: Vec<String> = items.iter().map(DataItem::to_string).collect();
+    print(format_args!("locations:     {}", or_none(&locations, "; ")))?;
+    print(format_args!("items:         {}", or_none(&names, ", ")))?;
+    print(format_args!("calls per run: {}", ratelimit::calls_per_refresh(&items, locations.len().max(1))))
+}
+
+/// Describes where `weather` is for, as OpenWeatherMap resolved the query,
+/// as in "Weather for The Hague, NL (52.08,4.31)".
+fn resolved(weather: &wx::CurrentWeather) -> String {
+    let place = [weather.city_name(), weather.country()].into_iter().flatten().collect::<Vec<_>>().join(", ");
+    let coords = weather
+        .coords()
+        .map(|(lat, lon)| format!("{},{}", wx::format::fixed(lat, 2), wx::format::fixed(lon, 2)));
+    match (place.is_empty(), coords) {
+        (true, None) => "Weather for an unnamed place".to_string(),
+        (true, Some(coords)) => format!("Weather for {}", coords),
+        (false, None) => format!("Weather for {}", place),
+        (false, Some(coords)) => format!("Weather for {} ({})", place, coords),
+    }
+}
+
+/// Render the last current weather in the cache, marked with its age, as the
+/// network is down with `error`, which is returned if nothing is cached.
+fn offline(cfg: &Config, error: anyhow::Error) -> Result<Report> {
+    let client = client(cfg, cfg.fetched_units())?;
+    let location = location(cfg)?;
+    // The weather may have been fetched by the stored city id instead.
+    let stored = match &location {
+        wx::Location::City { .. } if cfg.prefer_city_id.unwrap_or(true) => cityid::load(&location.key()),
+        _ => None,
+    };
+    let cached = stored
+        .and_then(|id| client.current_last_cached(&wx::Location::city_id(id)))
+        .into_iter()
+        .chain(client.current_last_cached(&location))
+        .max_by_key(|&(_, fetched)| fetched);
+    let Some((weather, fetched)) = cached else {
+        return Err(error);
+    };
+    let age = wx::format::age(unix_now().saturating_sub(fetched));
+    if cfg.verbose {
+        eprintln!("{}; showing the weather cached {} ago", error, age);
+    }
+    let mark = cfg.stale_format.as_deref().unwrap_or("({age} ago)").replace("{age}", &age);
+    let mut report = report(cfg, &client, location, weather, None)?;
+    report.line = cfg.output.marked(&report.line, &mark);
+    report.painted = report.painted.map(|painted| cfg.output.marked(&painted, &mark));
+    Ok(report)
+}
+
+/// Fetch the weather at each of `[[locations]]`, with one client for all of
+/// them, and print the results together, labeled as those of coordinates
+/// from stdin are. A location that fails is reported and left out; only if
+/// all of them fail is that an error.
+fn fetch_locations(cfg: &Config, sinks: &mut [sink::Guarded]) -> Result<()> {
+    let client = client(cfg, cfg.fetched_units())?;
+    let labels = cfg.labels.unwrap_or(true);
+    let mut results = Vec::new();
+    let mut failed = Vec::new();
+    for entry in &cfg.locations {
+        let mut cfg = cfg.clone();
+        cfg.city = entry.city.clone();
+        cfg.state = entry.state.clone();
+        cfg.country = entry.country.clone();
+        cfg.zip = entry.zip.clone();
+        cfg.coords = entry.coords.clone();
+        cfg.lat = entry.lat;
+        cfg.lon = entry.lon;
+        let query = entry.query();
+        match fetch_with(&cfg, &client, None) {
+            Ok(report) => {
+                sink::deliver_all(sinks, &report.rendered());
+                let label = match &entry.name {
+                    Some(name) => name.clone(),
+                    None => cfg.label_from.label(&query, report.city.as_deref()),
+                };
+                let output = report.finish(cfg.explain);
+                if labels {
+                    results.push((label, query, output));
+                } else {
+                    print(output)?;
+                }
+            }
+            Err(e) => {
+                let message = format!("{}: {}", entry.name.as_deref().unwrap_or(&query), e);
+                failed.push(e.context(message));
+            }
+        }
+    }
+    let all_failed = failed.len() == cfg.locations.len();
+    let last = if all_failed { failed.pop() } else { None };
+    for e in failed.iter().filter(|_| !exit::quiet()) {
+        eprintln!("{}", e);
+    }
+    if !results.is_empty() {
+        print(batch::render(cfg.output, &results))?;
+    }
+    last.map_or(Ok(()), Err)
+}
+
+/// Set up the configured secondary outputs. Failing ones are retried later if
+/// `retry` is set, as in watch mode.
+fn sinks(cfg: &Config, retry: bool) -> Vec<sink::Guarded> {
+    let mut sinks: Vec<Box<dyn sink::OutputSink>> = Vec::new();
+    if let Some(path) = &cfg.output_file {
+        sinks.push(Box::new(sink::FileSink { path: path.into(), format: None }));
+    }
+    if let Some(path) = &cfg.json_sidecar {
+        sinks.push(Box::new(sink::JsonSidecar { path: path.into() }));
+    }
+    if let Some(path) = &cfg.textfile {
+        sinks.push(Box::new(sink::Textfile { path: path.into() }));
+    }
+    sinks.extend(cfg.outputs.iter().map(|output| output.sink(&cfg.quiet)));
+    sinks
+        .into_iter()
+        .map(|s| sink::Guarded::new(s, retry))
+        .collect()
+}
+
+/// Fetch the current weather of the cities with the ids `ids` in one request,
+/// and print one line for each, labeled with the city's name as the results
+/// of `[[locations]]` are.
+fn fetch_group(cfg: &Config, ids: &[u64], sinks: &mut [sink::Guarded]) -> Result<()> {
+    if ids.len() > wx::group::MAX_IDS {
+        return Err(exit::usage(format!(
+            "{} city ids given, but at most {} can be fetched together",
+            ids.len(),
+            wx::group::MAX_IDS
+        )));
+    }
+    let client = client(cfg, cfg.fetched_units())?;
+    let group = client.group(ids)?;
+    let found: Vec<u64> = group.iter().filter_map(wx::CurrentWeather::city_id).collect();
+    for id in ids.iter().filter(|id| !found.contains(id)) {
+        exit::warning!("no city with id {}", id);
+    }
+    let labels = cfg.labels.unwrap_or(true);
+    let mut results = Vec::new();
+    for weather in group {
+        let id = weather.city_id().unwrap_or_default();
+        let report = report(cfg, &client, wx::Location::city_id(id), weather, None)?;
+        sink::deliver_all(sinks, &report.rendered());
+        let label = report.city.clone().unwrap_or_else(|| id.to_string());
+        let output = report.finish(cfg.explain);
+        if labels {
+            results.push((label, id.to_string(), output));
+        } else {
+            print(output)?;
+        }
+    }
+    if !results.is_empty() {
+        print(batch::render(cfg.output, &results))?;
+    }
+    Ok(())
+}
+
+/// The requested weather data, and how each item was computed.
+struct Report {
+    line: String,
+    /// The line in color, as printed to stdout, if it is colored.
+    painted: Option<String>,
+    /// The same data as pretty-printed JSON, for the JSON sidecar.
+    json: String,
+    /// The numbers behind the data as metrics, for the textfile.
+    metrics: String,
+    /// The rendered items, and the smoothed values of some, for sinks with
+    /// a format of their own.
+    values: Vec<(DataItem, String)>,
+    smoothed: Vec<(DataItem, String)>,
+    /// The tooltip and class of the Waybar format.
+    status: output::Status,
+    joining: output::Joining,
+    /// The whole weather, in the raw JSON format.
+    raw: String,
+    /// Shift of the location from UTC in seconds.
+    timezone: i64,
+    /// The city OpenWeatherMap resolved the location to, if any.
+    city: Option<String>,
+    /// The desktop notification to send, with `--notify`.
+    notification: Option<notify::Notification>,
+    explanations: Vec<wx::Explanation>,
+}
+
+impl Report {
+    fn rendered(&self) -> sink::Rendered<'_> {
+        sink::Rendered {
+            line: &self.line,
+            json: &self.json,
+            metrics: &self.metrics,
+            items: &self.values,
+            smoothed: &self.smoothed,
+            status: &self.status,
+            joining: &self.joining,
+            raw: &self.raw,
+        }
+    }
+
+    /// Returns the line to print, after printing the explanations to stderr
+    /// if they were asked for.
+    fn finish(self, explain: bool) -> String {
+        self.explain(explain);
+        self.painted.unwrap_or(self.line)
     }
 
+    /// Like [`finish`](Self::finish), writing the line to `line` instead, as
+    /// watch mode does into the same buffer for all refreshes.
+    fn finish_into(self, explain: bool, line: &mut String) {
+        self.explain(explain);
+        line.push_str(self.painted.as_deref().unwrap_or(&self.line));
+    }
+
+    fn explain(&self, explain: bool) {
+        if explain {
+            for e in &self.explanations {
+                eprintln!("{}", e);
+            }
+        }
+    }
+}
+
+/// Fetch the current weather and render the requested data, smoothing what is
+/// displayed with `smoother` if given.
+fn fetch(cfg: &Config, smoother: Option<&Mutex<smooth::Smoother>>) -> Result<Report> {
+    fetch_with(cfg, &client(cfg, cfg.fetched_units())?, smoother)
+}
+
+/// Like [`fetch`], with `client`, which fetches in [`Config::fetched_units`].
+fn fetch_with(cfg: &Config, client: &wx::Client, smoother: Option<&Mutex<smooth::Smoother>>) -> Result<Report> {
     // Get the current weather from OpenWeatherMap.
-    let location = wx::Location::new(&cfg.city, &cfg.state, &cfg.country);
-    let units = if cfg.imperial {
-        wx::Units::Imperial
+    let location = location(cfg)?;
+    client.reset_cache_hits();
+    let current_weather = current(client, &location, cfg)?;
+    report(cfg, client, location, current_weather, smoother)
+}
+
+/// Render the requested data for `location` from its `current_weather`,
+/// fetching what else the items need.
+fn report(
+    cfg: &Config,
+    client: &wx::Client,
+    location: wx::Location,
+    mut current_weather: wx::CurrentWeather,
+    smoother: Option<&Mutex<smooth::Smoother>>,
+) -> Result<Report> {
+    let fetched = cfg.fetched_units();
+    let units = if cfg.auto_units() {
+        let country = match &location {
+            wx::Location::City { country, .. } | wx::Location::Zip { country, .. } if !country.is_empty() => {
+                Some(country.as_str())
+            }
+            _ => current_weather.country(),
+        };
+        let auto = cfg.units_in(country);
+        note_auto_units(country, auto);
+        current_weather.convert(fetched, auto);
+        auto
     } else {
-        wx::Units::Metric
+        fetched
     };
-    let current_weather = wx::get(location, units, &cfg.api_key)?;
 
-    // Return requested weather data as one string.
-    Ok(cfg
-        .data
+    // Point out when OpenWeatherMap picked a differently named city. There is
+    // nothing to compare with for coordinates, which often have no name.
+    if let (wx::Location::City { city, .. }, Some(name)) = (&location, current_weather.city_name()) {
+        if city.trim().to_lowercase() != name.to_lowercase() {
+            exit::warning!("\"{}\" was resolved to \"{}\"", city.trim(), name);
+        }
+    }
+    if cfg.verbose {
+        eprintln!("{}", resolved(&current_weather));
+    }
+
+    // Items looking back need the history, which then also records this
+    // reading.
+    let history = if cfg.history || cfg.data.iter().any(|x| x.info().history) {
+        let key = format!("{}-{}", location.key(), units.as_str());
+        history::record(&key, current_weather.observation()).unwrap_or_else(|e| {
+            exit::warning!("history not updated: {}", e);
+            Vec::new()
+        })
+    } else {
+        Vec::new()
+    };
+
+    // Endpoints are only asked for items this build can render.
+    let wants = |endpoint| cfg.data.iter().any(|x| x.needs(endpoint) && x.unsupported().is_none());
+
+    // UV items and the moon need a second request, one for all of them, and
+    // render the placeholder if it fails.
+    let mut one_call_error = None;
+    let one_call = if wants(Endpoint::OneCall) {
+        let threshold = cfg.uv_threshold.unwrap_or(3.0);
+        let daily = cfg.data.contains(&DataItem::MoonPhase);
+        one_call_items(client, &current_weather, threshold, daily).unwrap_or_else(|e| {
+            exit::warning!("One Call data unavailable: {}", e);
+            one_call_error = Some(e);
+            Default::default()
+        })
+    } else {
+        Default::default()
+    };
+
+    // The ventilation window needs the pollution forecast, and renders the
+    // placeholder if it fails.
+    let mut pollution_error = None;
+    let ventilation_window = if wants(Endpoint::AirPollution) {
+        ventilation_window(client, &current_weather).unwrap_or_else(|e| {
+            exit::warning!("air pollution forecast unavailable: {}", e);
+            pollution_error = Some(e);
+            String::new()
+        })
+    } else {
+        String::new()
+    };
+
+    // The air quality index needs the current air pollution, and renders the
+    // placeholder if it fails.
+    let mut air_quality_error = None;
+    let air_quality = if wants(Endpoint::AirQuality) {
+        air_quality(client, &current_weather)
+            .map_err(|e| {
+                exit::warning!("air quality unavailable: {}", e);
+                air_quality_error = Some(e);
+            })
+            .ok()
+    } else {
+        None
+    };
+
+    // Items about now rather than the observation, like the sun items, are
+    // rendered for the time of this fetch.
+    let now = unix_now();
+
+    // The sun items are computed locally from the coordinates.
+    let sun = current_weather.sun_position(now);
+    if sun.is_none() && cfg.data.iter().any(|x| is_sun_item(*x)) {
+        exit::warning!("sun position unavailable: no coordinates in the response");
+    }
+
+    // Where the station is can only be told from coordinates the user gave.
+    let station = match location {
+        wx::Location::Coords { lat, lon } => current_weather.station_offset((lat, lon)),
+        _ => None,
+    };
+
+    // Items at a forecast slot render from the step of the forecast nearest
+    // to it, which is fetched once for all slots.
+    let offsets: Vec<slot::Offset> = cfg
+        .at_offset
+        .into_iter()
+        .chain(cfg.template.iter().flat_map(template::Template::offsets))
+        .collect();
+    // Today's range needs it too, but falls back to the current weather's.
+    let mut forecast_error = None;
+    let forecast = if !offsets.is_empty() {
+        Some(client.forecast(&location)?)
+    } else if wants(Endpoint::Forecast) {
+        client
+            .forecast(&location)
+            .map_err(|e| {
+                exit::warning!(
+                    "forecast unavailable, today's range is the current weather's and next_rain is {}: {}",
+                    PLACEHOLDER,
+                    e
+                );
+                forecast_error = Some(e);
+            })
+            .ok()
+    } else {
+        None
+    };
+    let today = today_range(forecast.as_ref(), &current_weather, &history, now, fetched, units);
+    let slot_at = |offset: slot::Offset| -> Result<wx::CurrentWeather> {
+        let forecast = forecast.as_ref().ok_or_else(|| anyhow::anyhow!("no forecast"))?;
+        let mut slot = slot::at(forecast, now, offset)?;
+        slot.convert(fetched, units);
+        Ok(slot)
+    };
+    let at_offset = cfg.at_offset.map(slot_at).transpose()?;
+    let source = |x: DataItem| match &at_offset {
+        Some(slot) if slot::from_forecast(x) => slot,
+        _ => &current_weather,
+    };
+
+    // Render the requested weather data, explaining each item on the way.
+    let mut values = Vec::new();
+    let mut explanations = Vec::new();
+    let lang = cfg.lang.as_deref().unwrap_or("en");
+    let spoken = cfg.accessible.then_some(lang);
+    for &x in &cfg.data {
+        if let Some(why) = unsupported(x) {
+            let endpoint = x.info().endpoints.last().map_or(wx::explain::CURRENT_ENDPOINT, |e| e.path());
+            values.push((x, PLACEHOLDER.to_string()));
+            explanations.push(wx::Explanation::new(x.name(), endpoint).fallback(format!("rendered ? ({})", why)));
+            continue;
+        }
+        if matches!(x, DataItem::Rain | DataItem::Snow)
+            && cfg.format.dry(x) == Dry::Empty
+            && source(x).precipitation(x).is_none()
+        {
+            values.push((x, String::new()));
+            explanations.push(source(x).explain(x).fallback(format!("no {} in the response, rendered empty", x.name())));
+            continue;
+        }
+        // For screen readers, wx spells out the items of the current weather;
+        // the others are spelled out below, as they are rendered.
+        if let Some(value) = spoken.and_then(|lang| source(x).render_accessible(x, units, lang, now)) {
+            values.push((x, value));
+            explanations.push(source(x).explain(x));
+            continue;
+        }
+        let (value, explanation) = match x {
+            DataItem::Icon => (icon(cfg, source(x)), source(x).explain(x)),
+            DataItem::Uvi => {
+                let e = wx::Explanation::new(x.name(), "data/3.0/onecall").formula("current UV index and its WHO category");
+                match (one_call.uvi, &one_call_error) {
+                    (Some(uvi), _) => (format!("{} {}", wx::format::fixed(uvi, 1), wx::uv::category(uvi)), e.raw(uvi)),
+                    (None, Some(error)) => (PLACEHOLDER.to_string(), e.fallback(format!("rendered ? ({})", error))),
+                    (None, None) => {
+                        (PLACEHOLDER.to_string(), e.fallback("no current UV index in the response, rendered ?"))
+                    }
+                }
+            }
+            DataItem::MoonPhase => {
+                let e = wx::Explanation::new(x.name(), "data/3.0/onecall").formula("today's moon phase as a glyph");
+                match (one_call.moon_phase, &one_call_error) {
+                    (Some(phase), _) => (moon(cfg, phase, cfg.accessible), e.raw(phase)),
+                    (None, Some(error)) => (PLACEHOLDER.to_string(), e.fallback(format!("rendered ? ({})", error))),
+                    (None, None) => (String::new(), e.fallback("no moon phase for today, rendered empty")),
+                }
+            }
+            DataItem::UvSafeUntil | DataItem::UvPeak => {
+                let value = if x == DataItem::UvPeak { &one_call.peak } else { &one_call.safe_until };
+                let e = wx::Explanation::new(x.name(), "data/3.0/onecall").formula("hourly UV window");
+                match &one_call_error {
+                    Some(error) => (PLACEHOLDER.to_string(), e.fallback(format!("rendered ? ({})", error))),
+                    None => (value.clone(), e),
+                }
+            }
+            DataItem::VentilationWindow => {
+                let e = wx::Explanation::new(x.name(), wx::pollution::FORECAST_ENDPOINT)
+                    .formula("lowest PM2.5 + NO2 over 2-3 hours");
+                match &pollution_error {
+                    Some(error) => (PLACEHOLDER.to_string(), e.fallback(format!("rendered ? ({})", error))),
+                    None => (ventilation_window.clone(), e),
+                }
+            }
+            DataItem::Aqi | DataItem::AqiLevel => {
+                let e = wx::Explanation::new(x.name(), wx::pollution::CURRENT_ENDPOINT);
+                match (&air_quality, &air_quality_error) {
+                    (Some((level, name)), _) => {
+                        let value = if x == DataItem::Aqi { name.clone() } else { level.to_string() };
+                        (value, e.raw(level))
+                    }
+                    (None, Some(error)) => (PLACEHOLDER.to_string(), e.fallback(format!("rendered ? ({})", error))),
+                    (None, None) => (PLACEHOLDER.to_string(), e.fallback("rendered ?")),
+                }
+            }
+            DataItem::StationDistance | DataItem::StationBearing => {
+                let e = wx::Explanation::new(x.name(), wx::explain::CURRENT_ENDPOINT)
+                    .formula("haversine from the given coordinates");
+                match &station {
+                    Some(offset) => (
+                        station_item(x, offset, units, cfg.format.unit_style(x), spoken),
+                        e.raw(format!("{:.3} km, bearing {:.1}°", offset.distance, offset.bearing)),
+                    ),
+                    None if matches!(location, wx::Location::Coords { .. }) => {
+                        (PLACEHOLDER.to_string(), e.fallback("no coordinates in the response, rendered ?"))
+                    }
+                    None => (PLACEHOLDER.to_string(), e.fallback("not queried by coordinates, rendered ?")),
+                }
+            }
+            x if is_sun_item(x) => {
+                let e = wx::Explanation::new(x.name(), wx::explain::CURRENT_ENDPOINT)
+                    .formula("NOAA solar position");
+                match &sun {
+                    Some(p) => (
+                        sun_item(x, p, spoken),
+                        e.raw(format!("elevation {:.4}, azimuth {:.4}", p.elevation, p.azimuth)),
+                    ),
+                    None => (PLACEHOLDER.to_string(), e.fallback("no coordinates in the response, rendered ?")),
+                }
+            }
+            DataItem::ObsTime | DataItem::NowTime | DataItem::Sunrise | DataItem::Sunset
+                if cfg.format.time_format.is_some() =>
+            {
+                let format = cfg.format.time_format.as_deref().unwrap_or_default();
+                (
+                    current_weather.render_clock(x, now, format).unwrap_or_default(),
+                    current_weather.explain(x),
+                )
+            }
+            DataItem::ObsAge | DataItem::Daylight | DataItem::NowTime | DataItem::NowDate => (
+                current_weather.render_at(x, now).unwrap_or_default(),
+                current_weather.explain(x),
+            ),
+            DataItem::TodayHigh | DataItem::TodayLow => {
+                let value = if x == DataItem::TodayHigh { today.1 } else { today.0 };
+                let e = wx::Explanation::new(x.name(), wx::forecast::FORECAST_ENDPOINT)
+                    .formula("forecast for the rest of the local day, today's readings and the current one")
+                    .raw(value);
+                let e = match &forecast_error {
+                    Some(error) => e.fallback(format!("the current weather's temp_min and temp_max ({})", error)),
+                    None => e,
+                };
+                let rendered = match spoken {
+                    Some(lang) => wx::format::spoken(value, 0, x.info().unit, units, lang),
+                    None => wx::render_number(x, value, units, cfg.format.unit_style(x)).unwrap_or_default(),
+                };
+                (rendered, e)
+            }
+            DataItem::NextRain => {
+                let threshold = cfg.rain_threshold.unwrap_or(50).min(100);
+                let hours = cfg.rain_hours.unwrap_or(12);
+                let e = wx::Explanation::new(x.name(), wx::forecast::FORECAST_ENDPOINT)
+                    .formula("first forecast step with a high enough probability of precipitation");
+                let event = forecast
+                    .as_ref()
+                    .and_then(|forecast| forecast.next_precipitation(now, hours.into(), f64::from(threshold) / 100.0));
+                let dry = || match cfg.format.dry(x) {
+                    Dry::Zero => "dry".to_string(),
+                    Dry::Empty => String::new(),
+                };
+                match (event, &forecast_error) {
+                    (Some(event), _) => {
+                        let raw = format!("pop {}, at least {}%", event.probability, threshold);
+                        (event.render(), e.raw(raw))
+                    }
+                    (None, Some(error)) => (PLACEHOLDER.to_string(), e.fallback(format!("rendered ? ({})", error))),
+                    (None, None) => {
+                        let none = format!("no step within {}h with a probability of {}% or more", hours, threshold);
+                        (dry(), e.fallback(none))
+                    }
+                }
+            }
+            DataItem::WindDirLong => (
+                wind_direction(cfg, &current_weather),
+                current_weather.explain(x),
+            ),
+            DataItem::DayStory => (
+                match spoken {
+                    Some(lang) => current_weather.day_story_spoken(&history, units, lang),
+                    None => current_weather.day_story(&history),
+                },
+                wx::Explanation::new(x.name(), wx::explain::CURRENT_ENDPOINT)
+                    .formula("history narrative")
+                    .raw(format!("{} earlier observations", history.len())),
+            ),
+            DataItem::Trend => {
+                let delta = cfg.trend_delta.unwrap_or(0.5).abs();
+                let hours = cfg.trend_hours.unwrap_or(3).min(36);
+                let e = wx::Explanation::new(x.name(), wx::explain::CURRENT_ENDPOINT).formula("temp - previous temp");
+                let raw = format!("more than ±{}", delta);
+                match current_weather.trend(&history, delta, i64::from(hours) * 3600) {
+                    Some(trend) if cfg.accessible => (trend.name().to_string(), e.raw(raw)),
+                    Some(trend) => (trend.arrow().to_string(), e.raw(raw)),
+                    None => (String::new(), e.fallback(format!("no reading in the {}h before", hours))),
+                }
+            }
+            _ => (
+                source(x).render(x, units, cfg.format.unit_style(x)).unwrap_or_default(),
+                source(x).explain(x),
+            ),
+        };
+        let value = if cfg.accessible { wx::format::accessible(x, &spell_out(x, value)) } else { value };
+        values.push((x, value));
+        explanations.push(explanation);
+    }
+    let hits = client.cache_hits();
+    for e in &mut explanations {
+        e.cached = e.endpoints.iter().all(|endpoint| hits.contains(endpoint));
+    }
+
+    // Smoothing only changes what is displayed: structured output keeps the
+    // values as fetched, with the smoothed ones besides them.
+    let mut smoothed = Vec::new();
+    if let Some(Ok(mut smoother)) = smoother.map(Mutex::lock) {
+        let key = format!("{}-{}", location.key(), units.as_str());
+        for x in smooth::ITEMS.into_iter().filter(|x| cfg.data.contains(x)) {
+            let Some(raw) = current_weather.number(x) else {
+                continue;
+            };
+            let average = smoother.update(&key, x, raw);
+            if let Some(lang) = spoken {
+                let value = wx::format::spoken(average, 0, x.info().unit, units, lang);
+                smoothed.push((x, wx::format::accessible(x, &value)));
+            } else if let Some(value) = wx::render_number(x, average, units, cfg.format.unit_style(x)) {
+                smoothed.push((x, value));
+            }
+        }
+    }
+
+    let status = status(cfg, &current_weather, units);
+    let joining = cfg.joining();
+    let weather_report = current_weather.report(units);
+    let raw = if cfg.pretty {
+        serde_json::to_string_pretty(&weather_report)?
+    } else {
+        serde_json::to_string(&weather_report)?
+    };
+    let render = |values: &[(DataItem, String)], smoothed: &[(DataItem, String)]| -> Result<String> {
+        Ok(match &cfg.template {
+            _ if cfg.output == output::Format::RawJson => raw.clone(),
+            Some(template) if cfg.output != output::Format::Json => {
+                let mut slots = Vec::new();
+                for offset in template.offsets() {
+                    let slot = slot_at(offset)?;
+                    let values = template
+                        .items()
+                        .into_iter()
+                        .filter(|&x| slot::from_forecast(x))
+                        .map(|x| match x {
+                            _ if cfg.accessible => {
+                                (x, slot.render_accessible(x, units, lang, now).unwrap_or_default())
+                            }
+                            DataItem::Icon => (x, icon(cfg, &slot)),
+                            DataItem::WindDirLong => (x, wind_direction(cfg, &slot)),
+                            _ => (x, slot.render(x, units, cfg.format.unit_style(x)).unwrap_or_default()),
+                        })
+                        .collect();
+                    slots.push((offset, values));
+                }
+                let line = template.render(&output::displayed(values, smoothed), &slots, current_weather.timezone());
+                match cfg.output {
+                    output::Format::Waybar => output::waybar(&line, &status),
+                    _ => line,
+                }
+            }
+            _ if cfg.output == output::Format::Plain && cfg.accessible => output::accessible(values, smoothed),
+            _ if cfg.output == output::Format::Json && cfg.debug_meta => {
+                output::json_with_meta(values, smoothed, version::info().json())
+            }
+            _ => cfg.output.render(values, smoothed, &status, &joining),
+        })
+    };
+    let mut line = render(&values, &smoothed)?;
+    if cfg.explain && cfg.output == output::Format::Json {
+        line = output::explained(&line, &explanations)?;
+    }
+    if cfg.pretty && cfg.output == output::Format::Json {
+        line = serde_json::from_str::<serde_json::Value>(&line)
+            .and_then(|value| serde_json::to_string_pretty(&value))
+            .unwrap_or(line);
+    }
+    // Colors only change what is printed to stdout, not what sinks get.
+    let colored = matches!(cfg.output, output::Format::Plain | output::Format::Card) && !cfg.accessible;
+    let painted = if colored && cfg.color.enabled() {
+        let palette = cfg.colors.palette(units);
+        let number = |x| source(x).number(x);
+        let condition = |x| source(x).condition();
+        Some(render(
+            &palette.paint(&values, number, condition),
+            &palette.paint(&smoothed, number, condition),
+        )?)
+    } else {
+        None
+    };
+    let numbers: Vec<(DataItem, f64)> = metrics::METRICS
         .iter()
-        .map(|x| current_weather.get(x))
-        .collect::<Vec<String>>()
-        .join(" "))
+        .filter_map(|metric| Some((metric.item, current_weather.number(metric.item)?)))
+        .collect();
+    let label = current_weather.city_name().map_or_else(|| location.key(), str::to_string);
+    let notification = cfg.notify.then(|| notification(cfg, &current_weather, units, &line));
+    Ok(Report {
+        line,
+        painted,
+        json: output::sidecar(&values, &smoothed, now),
+        metrics: metrics::render(&numbers, &label, units, now),
+        values,
+        smoothed,
+        status,
+        joining,
+        raw,
+        timezone: current_weather.timezone(),
+        city: current_weather.city_name().map(str::to_string),
+        notification,
+        explanations,
+    })
+}
+
+/// Returns the Waybar tooltip and class for `weather`, rendered in `units`:
+/// the description, feels like, humidity and wind, and the weather group and
+/// the severity of the condition, and `night` at night with `--night-dim`.
+fn status(cfg: &Config, weather: &wx::CurrentWeather, units: wx::Units) -> output::Status {
+    let mut lines = vec![weather.descriptions()];
+    for (label, x) in [("feels like", DataItem::FeelsLike), ("humidity", DataItem::Humidity), ("wind", DataItem::Wind)] {
+        if let Some(value) = weather.render(x, units, cfg.format.unit_style(x)) {
+            lines.push(format!("{} {}", label, value));
+        }
+    }
+    lines.retain(|line| !line.is_empty());
+    let mut class = vec![weather.group().to_lowercase(), weather.condition().severity().name().to_string()];
+    if cfg.night_dim && weather.is_night() {
+        class.push("night".to_string());
+    }
+    output::Status { tooltip: lines.join("\n"), class }
+}
+
+/// Returns the desktop notification of `weather`, rendered in `units`, with
+/// `line` as its summary.
+fn notification(cfg: &Config, weather: &wx::CurrentWeather, units: wx::Units, line: &str) -> notify::Notification {
+    let mut lines = vec![weather.descriptions()];
+    for (label, x) in [("feels like", DataItem::FeelsLike), ("wind", DataItem::Wind)] {
+        if let Some(value) = weather.render(x, units, cfg.format.unit_style(x)) {
+            lines.push(format!("{} {}", label, value));
+        }
+    }
+    lines.retain(|line| !line.is_empty());
+    notify::Notification {
+        summary: line.to_string(),
+        body: lines.join("\n"),
+        urgency: cfg.urgency.of(weather.condition(), weather.number(DataItem::Temp), units),
+    }
+}
+
+/// Returns the lowest and highest temperature of the local day of `now`, in
+/// `units`: of the steps of the `forecast`, fetched in `fetched`, left in the
+/// day, of the readings of the day in the `history` and of the current
+/// `weather`. Without a forecast, the current weather's own range, if it has
+/// one, stands in for the forecast's.
+fn today_range(
+    forecast: Option<&wx::Forecast>,
+    weather: &wx::CurrentWeather,
+    history: &[wx::history::Observation],
+    now: i64,
+    fetched: wx::Units,
+    units: wx::Units,
+) -> (f64, f64) {
+    let day = |t: i64| t.saturating_add(weather.timezone()).div_euclid(86400);
+    let temp = weather.number(DataItem::Temp).unwrap_or_default();
+    let (mut low, mut high) = match forecast {
+        Some(forecast) => match forecast.rest_of_day(now) {
+            Some(summary) => (
+                fetched.convert_temperature(summary.low, units),
+                fetched.convert_temperature(summary.high, units),
+            ),
+            None => (temp, temp),
+        },
+        None => weather.temp_range().unwrap_or((temp, temp)),
+    };
+    for observed in history.iter().filter(|o| day(o.dt) == day(now)).map(|o| o.temp).chain([temp]) {
+        low = low.min(observed);
+        high = high.max(observed);
+    }
+    (low, high)
+}
+
+/// Renders the direction the wind comes from in `weather`, in the language
+/// of `cfg`.
+fn wind_direction(cfg: &Config, weather: &wx::CurrentWeather) -> String {
+    weather.wind_direction(cfg.lang.as_deref().unwrap_or("en")).unwrap_or_default().to_string()
+}
+
+/// Renders the icon of `weather`, dimmed at night if that is asked for. Only
+/// the text formats dim it: values in JSON have no markup, and Waybar's
+/// status has the `night` class instead (see [`status`]).
+fn icon(cfg: &Config, weather: &wx::CurrentWeather) -> String {
+    let icon = weather.icon(cfg.icons);
+    let text = matches!(cfg.output, output::Format::Plain | output::Format::Card);
+    if cfg.night_dim && text && weather.is_night() {
+        cfg.dim_style.apply(&icon)
+    } else {
+        icon
+    }
+}
+
+/// Render the moon at `phase` in the icon set, or named for screen readers.
+fn moon(cfg: &Config, phase: f64, accessible: bool) -> String {
+    let set = if accessible { wx::IconSet::Text } else { cfg.icons };
+    wx::icons::moon(phase, set).to_string()
+}
+
+/// Fetch the current weather. Cities are queried by the id they resolved to
+/// before, unless that is turned off; if the id is no longer found, it is
+/// forgotten and the city queried by name again. A name that is not found is
+/// tried in simpler forms, unless that is turned off too.
+fn current(client: &wx::Client, location: &wx::Location, cfg: &Config) -> Result<wx::CurrentWeather> {
+    match current_as_given(client, location, cfg) {
+        Err(e) if is_not_found(&e) && cfg.query_fallback.unwrap_or(true) => fall_back(client, location, cfg, e),
+        result => result,
+    }
+}
+
+/// Returns whether `e` is the API not finding what was asked for.
+fn is_not_found(e: &anyhow::Error) -> bool {
+    e.downcast_ref().is_some_and(wx::ApiError::is_not_found)
+}
+
+/// Tries the simpler forms of the query for `location`, which was not found
+/// with `error`, and returns the first that is found. Its city id is stored,
+/// so that the next query finds the city right away.
+fn fall_back(
+    client: &wx::Client,
+    location: &wx::Location,
+    cfg: &Config,
+    error: anyhow::Error,
+) -> Result<wx::CurrentWeather> {
+    use fallback::Form;
+
+    for form in fallback::ladder(location) {
+        if cfg.verbose {
+            eprintln!("querying {}", form);
+        }
+        let result = match &form {
+            Form::WithoutState(simpler) => client.current(simpler),
+            // A geocoding request that fails finds nothing either: what
+            // fails is the query as it was given.
+            Form::Geocoded => match geocode(client, location, cfg) {
+                Ok(Some((lat, lon))) => client.current(&wx::Location::Coords { lat, lon }),
+                Ok(None) => continue,
+                Err(e) => {
+                    if cfg.verbose {
+                        eprintln!("geocoding failed: {}", e);
+                    }
+                    continue;
+                }
+            },
+        };
+        match result {
+            Ok(weather) => {
+                exit::warning!("{} was not found as given; found it {}", describe(location), form);
+                if let (true, Some(id)) = (cfg.prefer_city_id.unwrap_or(true), weather.city_id()) {
+                    if let Err(e) = cityid::save(&location.key(), Some(id)) {
+                        exit::warning!("city id not updated: {}", e);
+                    }
+                }
+                return Ok(weather);
+            }
+            Err(e) if is_not_found(&e) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(error)
+}
+
+/// Prints the places the city of `cfg` could be, numbered and with their
+/// coordinates, best match first, to pin one with `coords`. With `select`,
+/// pins place N in the config file, `file` or else the default one, or with
+/// `Some(None)` asks which in a terminal.
+fn resolve(cfg: &Config, select: Option<Option<u64>>, file: Option<&std::path::Path>) -> Result<()> {
+    let location = location(cfg)?;
+    let Some((_, query)) = location.to_query_pairs().into_iter().find(|(name, _)| *name == "q") else {
+        return Err(exit::usage("--resolve takes a city, not coordinates or a zip code"));
+    };
+    let places = if cfg.offline.unwrap_or(false) {
+        cities::places(&location, 5)?
+    } else {
+        match client(cfg, cfg.fetched_units())?.places(&query, 5) {
+            Err(e) if cfg!(feature = "offline-geo") && e.downcast_ref::<wx::NetworkError>().is_some() => {
+                exit::warning!("{}; listing the places of the database of cities built in", e);
+                cities::places(&location, 5)?
+            }
+            places => places?,
+        }
+    };
+    if places.is_empty() {
+        anyhow::bail!("no places found for {}", describe(&location));
+    }
+    if cfg.output == output::Format::Json {
+        print(serde_json::to_string(&places)?)?;
+    } else {
+        for (n, place) in places.iter().enumerate() {
+            print(format_args!("{}. {}  {:.4},{:.4}", n + 1, place, place.lat, place.lon))?;
+        }
+    }
+    let n = match select {
+        None => return Ok(()),
+        Some(Some(n)) => usize::try_from(n).unwrap_or(usize::MAX),
+        Some(None) if io::stdin().is_terminal() => {
+            select::prompt(&mut io::stdin().lock(), &mut io::stderr(), places.len())?
+        }
+        Some(None) => {
+            return Err(exit::usage(
+                "--select asks which place in a terminal; pass --select N to pin place N of the list instead",
+            ))
+        }
+    };
+    let Some(place) = places.get(n - 1) else {
+        return Err(exit::usage(format!("--select {}: there are {} places to pick from", n, places.len())));
+    };
+    let path = match file {
+        Some(path) => path.to_path_buf(),
+        None => paths::default_config_file().ok_or_else(|| anyhow::anyhow!("no config directory (is $HOME set?)"))?,
+    };
+    select::write(&path, place)?;
+    eprintln!("pinned {} at {},{} in {}", place, place.lat, place.lon, path.display());
+    Ok(())
+}
+
+/// Looks up the coordinates of `location` with the geocoding API, or for a
+/// city with `--offline` in the database of cities built in, which also
+/// stands in for the API when it cannot be reached.
+fn geocode(client: &wx::Client, location: &wx::Location, cfg: &Config) -> Result<Option<(f64, f64)>> {
+    let by_name = matches!(location, wx::Location::City { .. });
+    if !(by_name && cfg.offline.unwrap_or(false)) {
+        match client.geocode(location) {
+            Err(e) if by_name && cfg!(feature = "offline-geo") && e.downcast_ref::<wx::NetworkError>().is_some() => {
+                exit::warning!("{}; finding {} in the database of cities built in", e, describe(location));
+            }
+            found => return found,
+        }
+    }
+    Ok(cities::places(location, 1)?.first().map(|place| (place.lat, place.lon)))
+}
+
+/// Describes a location for messages, e.g. "New York, NY, US".
+fn describe(location: &wx::Location) -> String {
+    match location {
+        wx::Location::City { city, state, country } => {
+            let parts: Vec<&str> = [city, state, country]
+                .into_iter()
+                .map(|part| part.trim())
+                .filter(|part| !part.is_empty())
+                .collect();
+            format!("\"{}\"", parts.join(", "))
+        }
+        _ => location.key(),
+    }
+}
+
+/// Fetch the current weather for `location` as it is given.
+fn current_as_given(client: &wx::Client, location: &wx::Location, cfg: &Config) -> Result<wx::CurrentWeather> {
+    use cityid::{Outcome, Query, Update};
+
+    let wx::Location::City { .. } = location else {
+        return client.current(location);
+    };
+    if cfg.geocode.unwrap_or(false) || cfg.offline.unwrap_or(false) {
+        if cfg.verbose {
+            eprintln!("querying by the coordinates geocoding finds");
+        }
+        return match geocode(client, location, cfg)? {
+            Some((lat, lon)) => client.current(&wx::Location::coords(lat, lon)),
+            None if cfg.offline.unwrap_or(false) => {
+                Err(anyhow::anyhow!("{} is not in the database of cities built in", describe(location)))
+            }
+            None => Err(anyhow::anyhow!("{} was not found by the geocoding API", describe(location))),
+        };
+    }
+    let prefer_id = cfg.prefer_city_id.unwrap_or(true);
+    let key = location.key();
+    let mut stored = if prefer_id { cityid::load(&key) } else { None };
+
+    let mut query = cityid::query(prefer_id, stored);
+    loop {
+        let result = match query {
+            Query::Id(id) => {
+                if cfg.verbose {
+                    eprintln!("querying by city id {}", id);
+                }
+                client.current(&wx::Location::city_id(id))
+            }
+            Query::Name => {
+                if cfg.verbose {
+                    eprintln!("querying by name");
+                }
+                client.current(location)
+            }
+        };
+        let outcome = match &result {
+            Ok(weather) => Outcome::Found(weather.city_id()),
+            Err(e) if is_not_found(e) => Outcome::NotFound,
+            Err(_) => Outcome::Failed,
+        };
+
+        let update = cityid::update(query, stored, outcome);
+        let saved = match update {
+            _ if !prefer_id => Ok(()),
+            Update::Keep => Ok(()),
+            Update::Store(id) => cityid::save(&key, Some(id)),
+            Update::Forget => cityid::save(&key, None),
+        };
+        if let Err(e) = saved {
+            exit::warning!("city id not updated: {}", e);
+        }
+        if update == Update::Forget {
+            (query, stored) = (Query::Name, None);
+            continue;
+        }
+        return result;
+    }
+}
+
+/// The items from One Call, from one request.
+#[derive(Debug, Default)]
+struct OneCallItems {
+    /// The current UV index, if the response has one.
+    uvi: Option<f64>,
+    safe_until: String,
+    peak: String,
+    /// Today's moon phase, from 0 to 1, if the daily forecast was fetched
+    /// and has today.
+    moon_phase: Option<f64>,
+}
+
+/// Fetch the current and hourly UV index, and the daily forecast if `daily`,
+/// and return the One Call items.
+#[cfg(feature = "onecall")]
+fn one_call_items(
+    client: &wx::Client,
+    weather: &wx::CurrentWeather,
+    threshold: f64,
+    daily: bool,
+) -> Result<OneCallItems> {
+    use wx::Part;
+
+    let coords = weather
+        .coords()
+        .ok_or_else(|| anyhow::anyhow!("no coordinates in the response"))?;
+    let exclude: &[Part] = if daily {
+        &[Part::Minutely, Part::Alerts]
+    } else {
+        &[Part::Minutely, Part::Daily, Part::Alerts]
+    };
+    let one_call = client.one_call(coords, exclude)?;
+    let now = unix_now();
+    Ok(OneCallItems {
+        uvi: one_call.current().map(|current| current.uvi),
+        safe_until: one_call.uv_safe_until(threshold, now),
+        peak: one_call.uv_peak(now),
+        moon_phase: one_call.today(now).map(|today| today.moon_phase),
+    })
 }
 
-/// Read contents of toml file into Config struct.
-fn toml_from_file(path: impl AsRef<Path>) -> Result<Config> {
-    let contents = fs::read_to_string(path)?;
-    let cfg: Config = toml::from_str(&contents)?;
-    Ok(cfg)
+#[cfg(not(feature = "onecall"))]
+fn one_call_items(_: &wx::Client, _: &wx::CurrentWeather, _: f64, _: bool) -> Result<OneCallItems> {
+    anyhow::bail!("tinywx was built without the onecall feature")
+}
+
+/// Fetch the air pollution forecast and return the ventilation_window item.
+fn ventilation_window(client: &wx::Client, weather: &wx::CurrentWeather) -> Result<String> {
+    let coords = weather
+        .coords()
+        .ok_or_else(|| anyhow::anyhow!("no coordinates in the response"))?;
+    let forecast = client.air_pollution_forecast(coords)?;
+    Ok(forecast.ventilation_window(unix_now(), weather.timezone()))
+}
+
+/// Fetch the current air pollution and return the air quality index, as a
+/// level from 1 to 5 and its name.
+fn air_quality(client: &wx::Client, weather: &wx::CurrentWeather) -> Result<(u8, String)> {
+    let coords = weather
+        .coords()
+        .ok_or_else(|| anyhow::anyhow!("no coordinates in the response"))?;
+    let pollution = client.air_pollution(coords)?;
+    let reading = pollution
+        .current()
+        .ok_or_else(|| anyhow::anyhow!("no reading in the response"))?;
+    let name = reading
+        .aqi_name()
+        .ok_or_else(|| anyhow::anyhow!("air quality index {} is not from 1 to 5", reading.aqi()))?;
+    Ok((reading.aqi(), name.to_string()))
+}
+
+/// Returns why this build cannot render `item`, which then renders
+/// [`PLACEHOLDER`], if it cannot.
+fn unsupported(item: DataItem) -> Option<String> {
+    match item.unsupported()? {
+        wx::Unsupported::Feature(feature) => Some(format!("tinywx was built without the '{}' feature", feature.name())),
+        why => Some(why.to_string()),
+    }
+}
+
+fn is_sun_item(item: DataItem) -> bool {
+    matches!(item, DataItem::SunElevation | DataItem::SunAzimuth | DataItem::GoldenHour)
+}
+
+/// Render a sun item, spelled out in the language `spoken` if given.
+fn sun_item(item: DataItem, position: &wx::sun::SunPosition, spoken: Option<&str>) -> String {
+    let degrees = |angle: f64| match spoken {
+        Some(lang) => wx::format::spoken(angle, 1, wx::item::Unit::Degrees, wx::Units::Metric, lang),
+        None => format!("{}°", wx::format::fixed(angle, 1)),
+    };
+    match item {
+        DataItem::SunElevation => degrees(position.elevation),
+        DataItem::SunAzimuth => degrees(position.azimuth),
+        _ if position.is_golden_hour() => "golden hour".to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Renders a station item: the distance in the requested units, or the compass
+/// point, spelled out in the language `spoken` if given.
+fn station_item(
+    item: DataItem,
+    offset: &wx::geo::Offset,
+    units: wx::Units,
+    style: UnitStyle,
+    spoken: Option<&str>,
+) -> String {
+    match (item, spoken) {
+        (DataItem::StationDistance, _) => {
+            let distance = match units {
+                wx::Units::Metric | wx::Units::Standard => offset.distance,
+                wx::Units::Imperial => offset.distance / KM_PER_MILE,
+            };
+            match spoken {
+                Some(lang) => wx::format::spoken(distance, 0, wx::item::Unit::Distance, units, lang),
+                None => wx::format::with_unit(&wx::format::fixed(distance, 0), Quantity::Distance, units, style),
+            }
+        }
+        (_, Some(lang)) if offset.direction().is_some() => {
+            wx::geo::compass_name(offset.bearing, lang).to_string()
+        }
+        _ => offset.direction().unwrap_or_default().to_string(),
+    }
+}
+
+/// Spells out the symbols in the value of an item tinywx renders itself, for
+/// `--accessible`.
+fn spell_out(item: DataItem, value: String) -> String {
+    match item {
+        DataItem::UvPeak => value.replacen(" @", " at ", 1),
+        DataItem::NextRain => value.replacen(" @", " at ", 1).replacen('%', " percent", 1),
+        DataItem::VentilationWindow => value.replacen('–', " to ", 1),
+        _ => value,
+    }
+}
+
+/// Current time in seconds since the unix epoch.
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+/// Determine the location to query: either coordinates, as `coords` or as
+/// `lat` and `lon`, a zip code or a city, but only one of them.
+fn location(cfg: &Config) -> Result<wx::Location> {
+    let by_coords = cfg.coords.is_some() || cfg.lat.is_some() || cfg.lon.is_some();
+    if by_coords && !cfg.city.is_empty() {
+        return Err(exit::usage("specify either a city or coordinates, not both"));
+    }
+    if let Some(zip) = &cfg.zip {
+        if by_coords || !cfg.city.is_empty() || !cfg.state.is_empty() {
+            return Err(exit::usage("specify either a zip code, a city or coordinates, not more than one"));
+        }
+        // Without a country, OpenWeatherMap takes the code for a US one.
+        if cfg.country.trim().is_empty() {
+            return Err(exit::usage(format!("a zip code needs a country code, as in --zip {} -C us", zip.trim())));
+        }
+        return Ok(wx::Location::zip(zip, &cfg.country));
+    }
+    let coords = match (&cfg.coords, cfg.lat, cfg.lon) {
+        (Some(_), Some(_), _) | (Some(_), _, Some(_)) => {
+            return Err(exit::usage("specify coordinates either as coords or as lat and lon, not both"))
+        }
+        (Some(coords), None, None) => coords.clone(),
+        (None, Some(lat), Some(lon)) => format!("{},{}", lat, lon),
+        (None, Some(_), None) | (None, None, Some(_)) => {
+            return Err(exit::usage("lat and lon must be given together"))
+        }
+        (None, None, None) if cfg.several_locations() => {
+            return Err(exit::usage("this takes a single location: [[locations]] are only for the current weather"))
+        }
+        (None, None, None) if cfg.city.is_empty() || cfg.country.is_empty() => {
+            return Err(exit::usage("a city and country, a zip code and country, or coordinates, are required"))
+        }
+        (None, None, None) => return Ok(wx::Location::new(&cfg.city, &cfg.state, &cfg.country)),
+    };
+    let (lat, lon) = wx::parse_coords(&coords).map_err(|e| exit::usage(e.to_string()))?;
+    Ok(wx::Location::coords(lat, lon))
+}
+
+/// Print the forecast for the coming `hours`: as a chart `graph` columns wide if
+/// set, else one line for every 3 hours.
+fn forecast(cfg: &Config, hours: i64, graph: Option<usize>) -> Result<()> {
+    let location = location(cfg)?;
+    let units = cfg.units();
+    let client = client(cfg, units)?;
+    let forecast = client.forecast(&location)?;
+    if forecast.truncated() > 0 {
+        exit::warning!(
+            "the forecast had {} more steps than the {} expected, which were left out",
+            forecast.truncated(),
+            wx::forecast::MAX_STEPS
+        );
+    }
+
+    let now = unix_now();
+    let temperatures = forecast.temperatures(now, hours);
+    let precipitation = forecast.precipitation(now, hours);
+    let style = cfg.format.unit_style(DataItem::Temp);
+    let label = |t: f64| wx::format::with_unit(&wx::format::fixed(t, 0), Quantity::Temperature, units, style);
+    let names = wx::locale::names(cfg.lang.as_deref().unwrap_or("en"));
+    match graph {
+        Some(width) => {
+            let day = |dt: i64| names.weekday(wx::locale::weekday(dt, forecast.timezone()), Form::Short).to_string();
+            let chart = chart::Chart {
+                series: &temperatures,
+                shade: Some(&precipitation),
+                label: &label,
+                day_label: Some(&day),
+                offset: forecast.timezone(),
+            };
+            print(chart.render(width))?;
+        }
+        None => {
+            for (&(dt, temp), &(_, pop)) in temperatures.iter().zip(&precipitation) {
+                let day = names.weekday(wx::locale::weekday(dt, forecast.timezone()), Form::Short);
+                let time = (dt + forecast.timezone()).rem_euclid(86400);
+                let pop = wx::format::fixed(pop * 100.0, 0);
+                let (hour, minute) = (time / 3600, time % 3600 / 60);
+                print(format_args!("{} {:02}:{:02} {} {}%", day, hour, minute, label(temp), pop))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints the next `steps` steps of the forecast, a line each, with the
+/// items of `data` rendered from each as from the current weather.
+fn upcoming(cfg: &Config, steps: usize) -> Result<()> {
+    let location = location(cfg)?;
+    let units = cfg.units();
+    let forecast = client(cfg, units)?.forecast(&location)?;
+    for step in forecast.upcoming(unix_now(), steps) {
+        let values: Vec<String> = cfg
+            .data
+            .iter()
+            .map(|&x| match x {
+                DataItem::Icon => icon(cfg, &step),
+                DataItem::WindDirLong => wind_direction(cfg, &step),
+                _ => step.render(x, units, cfg.format.unit_style(x)).unwrap_or_default(),
+            })
+            .collect();
+        print(wx::format::single_line(&values, " "))?;
+    }
+    Ok(())
+}
+
+/// Print today's icon, high and low from the daily forecast of One Call.
+#[cfg(feature = "onecall")]
+fn daily(cfg: &Config) -> Result<()> {
+    use wx::Part;
+
+    let location = location(cfg)?;
+    let units = cfg.units();
+    let client = client(cfg, units)?;
+    let coords = match location {
+        wx::Location::Coords { lat, lon } => (lat, lon),
+        _ => current(&client, &location, cfg)?
+            .coords()
+            .ok_or_else(|| anyhow::anyhow!("no coordinates in the response"))?,
+    };
+    let one_call = client.one_call(coords, &[Part::Current, Part::Minutely, Part::Hourly, Part::Alerts])?;
+    let today = one_call
+        .today(unix_now())
+        .ok_or_else(|| anyhow::anyhow!("no forecast for today in the response"))?;
+    let style = cfg.format.unit_style(DataItem::TodayHigh);
+    let temp = |t: f64| wx::format::with_unit(&wx::format::fixed(t, 0), Quantity::Temperature, units, style);
+    let range = format!("{}/{}", temp(today.temp.max), temp(today.temp.min));
+    // A day without its weather has no icon, rather than an empty one.
+    let values: Vec<String> = today.weather.first().map(|w| w.icon(cfg.icons)).into_iter().chain([range]).collect();
+    print(wx::format::single_line(&values, " "))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "onecall"))]
+fn daily(_: &Config) -> Result<()> {
+    anyhow::bail!("tinywx was built without the onecall feature")
+}
+
+/// Returns when and where to send the daily digest, if it is turned on.
+fn digest_settings(cfg: &Config) -> Result<Option<digest::Settings>> {
+    let target = match (&cfg.digest_exec, cfg.digest) {
+        (Some(command), _) => digest::Target::Exec(command.clone()),
+        (None, Some(digest::Output::Stdout)) => digest::Target::Stdout,
+        (None, Some(digest::Output::Notify)) => digest::Target::Notify,
+        (None, None) => return Ok(None),
+    };
+    let at = match &cfg.digest_time {
+        Some(time) => digest::parse_time(time)?,
+        None => digest::DEFAULT_TIME,
+    };
+    Ok(Some(digest::Settings { at, target }))
+}
+
+/// Send the daily digest, if it is due at the location `offset` seconds from
+/// UTC. It counts as sent once tried, so that a failing target is not retried
+/// on every refresh.
+fn send_digest(cfg: &Config, settings: &digest::Settings, offset: i64) {
+    let Ok(location) = location(cfg) else {
+        return;
+    };
+    let key = format!("{}-{}", location.key(), cfg.units().as_str());
+    let now = unix_now();
+    let Some(day) = digest::due(settings.at, digest::load(&key), now, offset) else {
+        return;
+    };
+    if let Err(e) = digest::save(&key, day) {
+        exit::warning!("daily digest may be sent again: {}", e);
+    }
+    let sent = client(cfg, cfg.units())
+        .and_then(|client| client.forecast(&location))
+        .and_then(|forecast| {
+            let summary = forecast
+                .rest_of_day(now)
+                .ok_or_else(|| anyhow::anyhow!("no forecast for the rest of the day"))?;
+            settings.target.send(&digest_text(cfg, &summary, forecast.timezone()))
+        });
+    if let Err(e) = sent {
+        exit::warning!("daily digest not sent: {}", e);
+    }
+}
+
+/// Render the daily digest.
+fn digest_text(cfg: &Config, summary: &wx::forecast::DaySummary, offset: i64) -> String {
+    let units = cfg.units();
+    let temp = |t: f64| {
+        let style = cfg.format.unit_style(DataItem::Temp);
+        wx::format::with_unit(&wx::format::fixed(t, 0), Quantity::Temperature, units, style)
+    };
+    let wind = wx::format::with_unit(
+        &wx::format::fixed(summary.wind, 0),
+        Quantity::Speed,
+        units,
+        cfg.format.unit_style(DataItem::Wind),
+    );
+    let mut text = format!(
+        "Today: {} to {}, {}% chance of precipitation, wind up to {} from the {}",
+        temp(summary.low),
+        temp(summary.high),
+        wx::format::fixed(summary.precipitation * 100.0, 0),
+        wind,
+        wx::geo::compass_name(summary.wind_deg, "en")
+    );
+    let clock = |epoch: i64| match epoch {
+        0 => None,
+        _ => wx::format::local_time(epoch, offset, "%H:%M"),
+    };
+    match (clock(summary.sunrise), clock(summary.sunset)) {
+        (Some(sunrise), Some(sunset)) => text.push_str(&format!("\nSunrise {}, sunset {}", sunrise, sunset)),
+        (Some(sunrise), None) => text.push_str(&format!("\nSunrise {}", sunrise)),
+        (None, Some(sunset)) => text.push_str(&format!("\nSunset {}", sunset)),
+        (None, None) => {}
+    }
+    text
+}
+
+/// Prints a listing of names and what they are, a line each, as [`print`]
+/// prints.
+fn list<'a>(mut entries: impl Iterator<Item = (&'a str, &'a str)>) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    let written = entries
+        .try_for_each(|(name, description)| writeln!(stdout, "{:<20} {}", name, description))
+        .and_then(|()| stdout.flush());
+    closed_pipe_is_done(written)
+}
+
+/// Prints `output` and a newline through a locked stdout. A reader that
+/// stops early, as `head` does, closes the pipe, which ends the output
+/// rather than failing it, or panicking as `println!` would.
+pub(crate) fn print(output: impl std::fmt::Display) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    closed_pipe_is_done(writeln!(stdout, "{}", output).and_then(|()| stdout.flush()))
+}
+
+/// Returns the outcome of writing to stdout, where a closed pipe is not an
+/// error.
+fn closed_pipe_is_done(written: io::Result<()>) -> Result<()> {
+    match written {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        written => written.map_err(|e| anyhow::anyhow!("cannot write the output: {}", e)),
+    }
+}
+
+/// Writes the example config file, with every setting commented out, to
+/// `path`, refusing to overwrite a file that is there already.
+fn init_config(path: &std::path::Path) -> Result<()> {
+    use std::io::Write;
+
+    let template: String = include_str!("../tinywx.toml")
+        .lines()
+        .map(|line| match line {
+            "" => "\n".to_string(),
+            _ if line.starts_with('#') => format!("{}\n", line),
+            _ => format!("#{}\n", line),
+        })
+        .collect();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| anyhow::anyhow!("cannot create {}: {}", dir.display(), e))?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::AlreadyExists => anyhow::anyhow!("{} already exists; not overwriting it", path.display()),
+            _ => anyhow::anyhow!("cannot write {}: {}", path.display(), e),
+        })?;
+    file.write_all(template.as_bytes())
+        .map_err(|e| anyhow::anyhow!("cannot write {}: {}", path.display(), e))
+}
+
+/// Build an API client for `cfg`, fetching values in `units`.
+fn client(cfg: &Config, units: wx::Units) -> Result<wx::Client> {
+    let mut builder = wx::Client::builder().api_key(&cfg.api_key).units(units);
+    if let Some(url) = &cfg.api_url {
+        builder = builder.base_url(url);
+    }
+    if let Some(timeout) = cfg.timeout {
+        builder = builder.timeout(Duration::from_secs(timeout.into()));
+    }
+    if let Some(count) = cfg.retries {
+        builder = builder.retries(wx::Retries { count, ..wx::Retries::default() });
+    }
+    if let Some(lang) = &cfg.lang {
+        wx::locale::validate(lang).map_err(|e| exit::usage(e.to_string()))?;
+        builder = builder.lang(lang);
+    }
+    if let Some(proxy) = &cfg.proxy {
+        builder = builder.proxy(proxy);
+    }
+    if let Some(path) = &cfg.ca_cert {
+        let pem = std::fs::read(path).map_err(|e| anyhow::anyhow!("cannot read the CA certificate {}: {}", path, e))?;
+        builder = builder.root_certificate(&pem);
+    }
+    if cfg.cache.enabled.unwrap_or(true) {
+        if let Some(cache) = cache::Disk::new(cfg.cache.clone()) {
+            builder = builder.cache(Arc::new(cache));
+        }
+    }
+    builder.build().map_err(|e| match e.downcast_ref::<wx::MissingApiKey>() {
+        Some(_) => exit::usage(
+            "no API key provided: pass -k, or set api_key, api_key_cmd, TINYWX_API_KEY or \
+            OPENWEATHERMAP_API_KEY",
+        ),
+        None => e,
+    })
+}
+
+/// Runs `command` with `sh -c` and returns the API key it prints: the first
+/// line of its output, as password managers print it.
+fn api_key_from(command: &str) -> Result<String> {
+    let output = process::Command::new("sh")
+        .args(["-c", command])
+        .stderr(process::Stdio::inherit())
+        .output()
+        .map_err(|e| anyhow::anyhow!("api_key_cmd: cannot run '{}': {}", command, e))?;
+    if !output.status.success() {
+        anyhow::bail!("api_key_cmd: '{}' failed ({})", command, output.status);
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout.lines().next().map(str::trim).filter(|key| !key.is_empty()) {
+        Some(key) => Ok(key.to_string()),
+        None => anyhow::bail!("api_key_cmd: '{}' printed no API key", command),
+    }
+}
+
+/// Returns the width of the terminal, as far as the shell tells.
+fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(80)
+}
+
+/// Work out the API requests a fetch for `cfg` makes, leaving out those the
+/// cache answers.
+fn plan(cfg: &Config) -> Result<plan::Plan> {
+    // Coordinates may still have to be read from stdin, but are looked up the
+    // same way whatever they are.
+    if cfg.coords.as_deref() == Some("-") {
+        return Ok(plan::Plan::new(&cfg.data, plan::Lookup::Coords));
+    }
+    let location = location(cfg)?;
+    let lookup = match &location {
+        wx::Location::Coords { .. } => plan::Lookup::Coords,
+        wx::Location::Zip { .. } => plan::Lookup::Zip,
+        wx::Location::City { .. } if cfg.offline.unwrap_or(false) => plan::Lookup::Geocoded { cached: true },
+        wx::Location::City { .. } if cfg.geocode.unwrap_or(false) => plan::Lookup::Geocoded { cached: false },
+        location => {
+            let prefer_id = cfg.prefer_city_id.unwrap_or(true);
+            let stored = if prefer_id { cityid::load(&location.key()) } else { None };
+            cityid::query(prefer_id, stored).into()
+        }
+    };
+    let mut plan = plan::Plan::new(&cfg.data, lookup);
+    // Without an API key there is nothing to look up, as the run fails anyway.
+    let Ok(client) = client(cfg, cfg.fetched_units()) else {
+        return Ok(plan);
+    };
+    if let plan::Lookup::Geocoded { cached: cached @ false } = &mut plan.lookup {
+        *cached = client.cached(wx::geo::GEOCODING_ENDPOINT, &location).is_some();
+    }
+    let current = match lookup {
+        // The coordinates are not known until geocoding finds them.
+        plan::Lookup::Geocoded { .. } => None,
+        plan::Lookup::CityId(id) => client.cached(Endpoint::Current.path(), &wx::Location::city_id(id)),
+        plan::Lookup::Coords | plan::Lookup::Name | plan::Lookup::Zip => {
+            client.cached(Endpoint::Current.path(), &location)
+        }
+    };
+    // The other endpoints are asked by the coordinates the current weather
+    // returns, which only a cached one tells ahead.
+    let coords = match &location {
+        wx::Location::Coords { lat, lon } => Some(wx::Location::coords(*lat, *lon)),
+        _ => current
+            .as_deref()
+            .and_then(|body| wx::CurrentWeather::from_json(body, wx::ParseMode::default()).ok())
+            .and_then(|weather| weather.coords())
+            .map(|(lat, lon)| wx::Location::coords(lat, lon)),
+    };
+    for &endpoint in &plan.endpoints {
+        let cached = match endpoint {
+            Endpoint::Current => current.is_some(),
+            Endpoint::Forecast => client.cached(endpoint.path(), &location).is_some(),
+            _ => coords.as_ref().is_some_and(|coords| client.cached(endpoint.path(), coords).is_some()),
+        };
+        if cached {
+            plan.cached.push(endpoint);
+        }
+    }
+    Ok(plan)
 }
@@ -0,0 +1,104 @@
+//! Gauges in the Prometheus text exposition format, for node_exporter's
+//! textfile collector. The metrics are defined here once, so that anything
+//! else exposing them names them the same.
+
+use std::fmt::Write;
+
+use wx::DataItem;
+
+/// A gauge with the value of an item.
+pub struct Metric {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub item: DataItem,
+}
+
+/// The gauges, for the items that have a number behind them.
+pub const METRICS: &[Metric] = &[
+    Metric {
+        name: "tinywx_temperature",
+        help: "Temperature, in degrees of the units label.",
+        item: DataItem::Temp,
+    },
+    Metric {
+        name: "tinywx_feels_like",
+        help: "Temperature accounting for human perception, in degrees of the units label.",
+        item: DataItem::FeelsLike,
+    },
+    Metric {
+        name: "tinywx_humidity_percent",
+        help: "Relative humidity, in percent.",
+        item: DataItem::Humidity,
+    },
+    Metric {
+        name: "tinywx_wind_speed",
+        help: "Wind speed, in m/s, or mph with imperial units.",
+        item: DataItem::Wind,
+    },
+];
+
+/// Gauge with the time of the last fetch that succeeded.
+pub const LAST_SUCCESS: &str = "tinywx_last_success_timestamp_seconds";
+
+/// Renders the `values` of [`METRICS`] items for `location`, in `units`,
+/// fetched at `now` in unix seconds.
+pub fn render(values: &[(DataItem, f64)], location: &str, units: wx::Units, now: i64) -> String {
+    let location = escape(location);
+    let mut out = String::new();
+    for metric in METRICS {
+        let Some((_, value)) = values.iter().find(|(item, _)| *item == metric.item) else {
+            continue;
+        };
+        let _ = writeln!(out, "# HELP {} {}", metric.name, metric.help);
+        let _ = writeln!(out, "# TYPE {} gauge", metric.name);
+        let _ = writeln!(
+            out,
+            "{}{{location=\"{}\",units=\"{}\"}} {}",
+            metric.name,
+            location,
+            units.as_str(),
+            value
+        );
+    }
+    let _ = writeln!(out, "# HELP {} Unix time of the last fetch that succeeded.", LAST_SUCCESS);
+    let _ = writeln!(out, "# TYPE {} gauge", LAST_SUCCESS);
+    let _ = writeln!(out, "{}{{location=\"{}\"}} {}", LAST_SUCCESS, location, now);
+    out
+}
+
+/// Escapes a label value: backslashes, double quotes and line feeds.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_items_with_values_are_rendered() {
+        let text = render(&[(DataItem::Humidity, 81.0)], "Springfield", wx::Units::Imperial, 1_714_550_400);
+        let expected = "# HELP tinywx_humidity_percent Relative humidity, in percent.\n\
+            # TYPE tinywx_humidity_percent gauge\n\
+            tinywx_humidity_percent{location=\"Springfield\",units=\"imperial\"} 81\n\
+            # HELP tinywx_last_success_timestamp_seconds Unix time of the last fetch that succeeded.\n\
+            # TYPE tinywx_last_success_timestamp_seconds gauge\n\
+            tinywx_last_success_timestamp_seconds{location=\"Springfield\"} 1714550400\n";
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(escape("plain"), "plain");
+        assert_eq!(escape("a\\b \"c\"\nd"), "a\\\\b \\\"c\\\"\\nd");
+    }
+
+    #[test]
+    fn each_metric_has_a_name_of_its_own() {
+        let mut names: Vec<&str> = METRICS.iter().map(|metric| metric.name).chain([LAST_SUCCESS]).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), METRICS.len() + 1);
+        assert!(names.iter().all(|name| name.starts_with("tinywx_")), "{:?}", names);
+    }
+}
@@ -0,0 +1,155 @@
+//! Desktop notifications, with `--notify`: the line printed to stdout as the
+//! summary and the description, feels like and wind as the body, sent with
+//! `notify-send`, which speaks the freedesktop notification spec. They are
+//! critical for thunderstorms, tornadoes and temperatures beyond the limits
+//! of the `[urgency]` table, and normal otherwise.
+
+use anyhow::Result;
+use serde::{Deserialize, Deserializer};
+use wx::condition::Condition;
+use wx::Units;
+
+/// The condition id of tornadoes.
+const TORNADO: u64 = 781;
+
+/// How urgent a notification is, as the spec names it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Urgency {
+    Normal,
+    Critical,
+}
+
+#[cfg(feature = "notify")]
+impl Urgency {
+    /// Returns the name of the urgency, as `notify-send` takes it.
+    pub fn name(self) -> &'static str {
+        match self {
+            Urgency::Normal => "normal",
+            Urgency::Critical => "critical",
+        }
+    }
+}
+
+/// The temperatures below and above which a notification is critical.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limits([f64; 2]);
+
+impl<'de> Deserialize<'de> for Limits {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let limits = <[f64; 2]>::deserialize(deserializer)?;
+        if limits[0] >= limits[1] {
+            return Err(serde::de::Error::custom(format!(
+                "invalid limits {:?}: expected a low and a higher temperature, as in [-10, 35]",
+                limits
+            )));
+        }
+        Ok(Limits(limits))
+    }
+}
+
+/// The `[urgency]` table: the limits of the temperature for each of the
+/// units.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct UrgencyConfig {
+    metric: Option<Limits>,
+    imperial: Option<Limits>,
+    /// In Kelvin; the metric ones converted if not set.
+    standard: Option<Limits>,
+}
+
+impl UrgencyConfig {
+    /// Returns the urgency of a notification of `condition` at `temp`, in
+    /// `units`.
+    pub fn of(&self, condition: Condition, temp: Option<f64>, units: Units) -> Urgency {
+        let metric = self.metric.unwrap_or(Limits([-10.0, 35.0]));
+        let Limits([low, high]) = match units {
+            Units::Metric => metric,
+            Units::Imperial => self.imperial.unwrap_or(Limits([14.0, 95.0])),
+            Units::Standard => self.standard.unwrap_or(Limits(metric.0.map(|t| t + 273.15))),
+        };
+        let severe = matches!(condition, Condition::Thunderstorm(_)) || condition.id() == TORNADO;
+        if severe || temp.is_some_and(|t| t < low || t > high) {
+            Urgency::Critical
+        } else {
+            Urgency::Normal
+        }
+    }
+}
+
+/// A desktop notification.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Notification {
+    pub summary: String,
+    pub body: String,
+    pub urgency: Urgency,
+}
+
+/// Sends `notification`, failing if there is no `notify-send` or no
+/// notification daemon to show it.
+#[cfg(feature = "notify")]
+pub fn send(notification: &Notification) -> Result<()> {
+    use anyhow::{anyhow, bail};
+    use std::process::Command;
+
+    let output = Command::new("notify-send")
+        .args(["--app-name", "tinywx", "--urgency", notification.urgency.name()])
+        .args([&notification.summary, &notification.body])
+        .output()
+        .map_err(|e| anyhow!("cannot send the notification: cannot run notify-send: {}", e))?;
+    if !output.status.success() {
+        bail!(
+            "cannot send the notification: notify-send failed ({}), is a notification daemon running? {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "notify"))]
+pub fn send(_: &Notification) -> Result<()> {
+    anyhow::bail!("tinywx was built without the notify feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urgency(toml: &str) -> UrgencyConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn thunderstorms_and_tornadoes_are_critical() {
+        let config = UrgencyConfig::default();
+        for id in [200, 211, 232, 781] {
+            assert_eq!(config.of(Condition::from_id(id), Some(20.0), Units::Metric), Urgency::Critical, "{}", id);
+        }
+        for id in [500, 771, 800] {
+            assert_eq!(config.of(Condition::from_id(id), Some(20.0), Units::Metric), Urgency::Normal, "{}", id);
+        }
+    }
+
+    #[test]
+    fn temperatures_beyond_the_limits_of_each_unit_are_critical() {
+        let config = UrgencyConfig::default();
+        let of = |temp: f64, units: Units| config.of(Condition::from_id(800), Some(temp), units);
+        assert_eq!((of(-10.0, Units::Metric), of(35.0, Units::Metric)), (Urgency::Normal, Urgency::Normal));
+        assert_eq!((of(-10.5, Units::Metric), of(35.5, Units::Metric)), (Urgency::Critical, Urgency::Critical));
+        assert_eq!((of(14.0, Units::Imperial), of(96.0, Units::Imperial)), (Urgency::Normal, Urgency::Critical));
+        assert_eq!((of(263.0, Units::Standard), of(300.0, Units::Standard)), (Urgency::Critical, Urgency::Normal));
+        assert_eq!(config.of(Condition::from_id(800), None, Units::Metric), Urgency::Normal);
+    }
+
+    #[test]
+    fn the_table_sets_the_limits() {
+        let config = urgency("metric = [0, 25]");
+        let of = |temp: f64, units: Units| config.of(Condition::from_id(800), Some(temp), units);
+        assert_eq!((of(-1.0, Units::Metric), of(26.0, Units::Metric)), (Urgency::Critical, Urgency::Critical));
+        // Kelvin follows the metric limits unless set itself.
+        assert_eq!((of(272.0, Units::Standard), of(290.0, Units::Standard)), (Urgency::Critical, Urgency::Normal));
+        assert_eq!(of(96.0, Units::Imperial), Urgency::Critical);
+        assert!(toml::from_str::<UrgencyConfig>("metric = [25, 0]").is_err());
+        assert!(toml::from_str::<UrgencyConfig>("metric = [0, 0]").is_err());
+    }
+}
@@ -0,0 +1,499 @@
+//! Output formats: how the rendered items are printed.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error};
+use serde::{Deserialize, Deserializer};
+use serde_json::{json, Map, Value};
+use wx::item::ITEMS;
+use wx::DataItem;
+
+use crate::card;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// The items on one line, separated by spaces.
+    #[default]
+    Plain,
+    /// A flat JSON object from item names to rendered values.
+    Json,
+    /// The items on a few lines, for people; the default in a terminal.
+    Card,
+    /// The items on one line in a JSON object with a tooltip and a CSS
+    /// class, as Waybar's custom modules read it.
+    Waybar,
+    /// Everything in the response, whatever the items, in the shape of
+    /// [`wx::WeatherReport`].
+    RawJson,
+}
+
+impl Format {
+    pub const ALL: [Format; 5] = [Format::Plain, Format::Json, Format::Card, Format::Waybar, Format::RawJson];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Plain => "plain",
+            Format::Json => "json",
+            Format::Card => "card",
+            Format::Waybar => "waybar",
+            Format::RawJson => "raw-json",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Format::Plain => "The items on one line, separated by spaces unless --separator says otherwise",
+            Format::Json => "A JSON object from item names to rendered values, on one line",
+            Format::Card => "The items on a few lines, grouped and labeled, cut to the terminal width",
+            Format::Waybar => {
+                "A JSON object with the items on one line as text, a tooltip and the weather group and severity as classes, for Waybar"
+            }
+            Format::RawJson => "Everything in the response, whatever the items, as a JSON object in a stable shape",
+        }
+    }
+
+    /// Renders the items, in the order they were requested. Formats for
+    /// people show the `smoothed` values of items instead, JSON has them in a
+    /// separate object. Waybar's also has the `status` of the weather. The
+    /// formats with the items on one line join them as `joining` says. Raw
+    /// JSON is rendered from the weather itself, not the items (see
+    /// [`wx::CurrentWeather::report`]); from the items alone it is the JSON
+    /// format's object.
+    pub fn render(
+        self,
+        items: &[(DataItem, String)],
+        smoothed: &[(DataItem, String)],
+        status: &Status,
+        joining: &Joining,
+    ) -> String {
+        match self {
+            Format::Plain => plain(items, smoothed, joining),
+            Format::Json | Format::RawJson => Value::Object(json(items, smoothed)).to_string(),
+            Format::Card => card::render(&displayed(items, smoothed), crate::terminal_width()),
+            Format::Waybar => waybar(&plain(items, smoothed, joining), status),
+        }
+    }
+
+    /// Marks `line`, rendered in this format, as stale: the last good one,
+    /// shown again in watch mode as a refresh failed. The text gets a
+    /// trailing `*`, and the JSON object `"stale": true`.
+    pub fn stale(self, line: &str) -> String {
+        self.marked(line, "*")
+    }
+
+    /// Marks `line`, rendered in this format, as stale with `mark` after the
+    /// text, as in "12° (35m ago)"; the JSON object gets `"stale": true`
+    /// instead.
+    pub fn marked(self, line: &str, mark: &str) -> String {
+        let object = match self {
+            Format::Plain | Format::Card => None,
+            Format::Json | Format::Waybar | Format::RawJson => match serde_json::from_str(line) {
+                Ok(Value::Object(object)) => Some(object),
+                _ => None,
+            },
+        };
+        let Some(mut object) = object else {
+            return format!("{} {}", line, mark);
+        };
+        if self == Format::Waybar {
+            let text = object.get("text").and_then(Value::as_str).unwrap_or_default();
+            object.insert("text".to_string(), Value::from(format!("{} {}", text, mark)));
+        } else {
+            object.insert("stale".to_string(), Value::Bool(true));
+        }
+        Value::Object(object).to_string()
+    }
+
+    /// Returns the JSON schema of the output, for structured formats.
+    pub fn schema(self) -> Option<Value> {
+        match self {
+            Format::Plain | Format::Card => None,
+            Format::RawJson => Some(raw_json_schema()),
+            Format::Waybar => Some(json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string", "description": "The items on one line, or the template" },
+                    "tooltip": {
+                        "type": "string",
+                        "description": "The description, feels like, humidity and wind, one per line",
+                    },
+                    "class": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "The group of the weather condition, lowercased, as in rain or clear, and its severity, as in heavy, and night at night with --night-dim; those of all locations, once each, for several locations",
+                    },
+                },
+                "additionalProperties": false,
+            })),
+            Format::Json => {
+                let mut properties: Map<String, Value> = ITEMS
+                    .iter()
+                    .map(|info| {
+                        let property = json!({ "type": "string", "description": info.description });
+                        (info.name.to_string(), property)
+                    })
+                    .collect();
+                properties.insert(
+                    "smoothed".to_string(),
+                    json!({
+                        "type": "object",
+                        "description": "In watch mode with smoothing, the smoothed values of temperature and wind items",
+                        "additionalProperties": { "type": "string" },
+                    }),
+                );
+                properties.insert(
+                    "stale".to_string(),
+                    json!({
+                        "type": "boolean",
+                        "description": "True when the values are the last good ones, shown again as a refresh failed in watch mode or the network is down with --offline-ok",
+                    }),
+                );
+                properties.insert(
+                    "explain".to_string(),
+                    json!({
+                        "type": "array",
+                        "description": "With --explain, where each item came from",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "item": { "type": "string" },
+                                "endpoints": { "type": "array", "items": { "type": "string" } },
+                                "cached": { "type": "boolean" },
+                                "raw": {
+                                    "type": ["string", "null"],
+                                    "description": "The value before rounding or conversion",
+                                },
+                                "formula": {
+                                    "type": ["string", "null"],
+                                    "description": "How a computed value is computed",
+                                },
+                                "fallback": {
+                                    "type": ["string", "null"],
+                                    "description": "What was done for missing data",
+                                },
+                            },
+                            "additionalProperties": false,
+                        },
+                    }),
+                );
+                properties.insert(
+                    "meta".to_string(),
+                    json!({
+                        "type": "object",
+                        "description": "With --debug-meta, the version and build of tinywx",
+                    }),
+                );
+                Some(json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "type": "object",
+                    "properties": properties,
+                    "additionalProperties": false,
+                }))
+            }
+        }
+    }
+}
+
+/// What Waybar shows besides the text: a tooltip, and classes for styling
+/// the module by the weather.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Status {
+    /// The description, feels like, humidity and wind, one per line.
+    pub tooltip: String,
+    /// The group of the weather condition, lowercased, as in "rain", and its
+    /// severity, as in "heavy", and "night" at night with `--night-dim`.
+    pub class: Vec<String>,
+}
+
+/// How the items on one line are joined.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Joining {
+    /// What goes between two items.
+    pub separator: String,
+    /// Whether each value is prefixed with its item's label, as in "hum:67%".
+    pub labels: bool,
+}
+
+impl Default for Joining {
+    fn default() -> Self {
+        Joining { separator: " ".to_string(), labels: false }
+    }
+}
+
+/// Returns `separator` with the escape sequences `\t`, `\n` and `\\`
+/// replaced by a tab, a newline and a backslash, for separators given on the
+/// command line or in single quotes in the config file.
+pub fn unescape(separator: &str) -> String {
+    let mut unescaped = String::with_capacity(separator.len());
+    let mut chars = separator.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('\\') => unescaped.push('\\'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Renders `text` in Waybar's JSON, with the tooltip and class of `status`.
+pub fn waybar(text: &str, status: &Status) -> String {
+    json!({ "text": text, "tooltip": status.tooltip, "class": status.class }).to_string()
+}
+
+/// Returns the items on one line, joined as `joining` says, with the
+/// `smoothed` values of items instead, where there are any. Joined other
+/// than by the default, empty items are left out, so that no separator or
+/// label dangles; items with no label are not labeled.
+fn plain(items: &[(DataItem, String)], smoothed: &[(DataItem, String)], joining: &Joining) -> String {
+    let custom = *joining != Joining::default();
+    let values: Vec<String> = displayed(items, smoothed)
+        .into_iter()
+        .filter(|(_, value)| !custom || !value.is_empty())
+        .map(|(item, value)| match item.label() {
+            label if joining.labels && !label.is_empty() && !value.is_empty() => format!("{}:{}", label, value),
+            _ => value,
+        })
+        .collect();
+    wx::format::single_line(&values, &joining.separator)
+}
+
+/// Returns the items as displayed to people: with the `smoothed` values of
+/// items instead, where there are any.
+pub fn displayed(items: &[(DataItem, String)], smoothed: &[(DataItem, String)]) -> Vec<(DataItem, String)> {
+    items
+        .iter()
+        .map(|(item, value)| match smoothed.iter().find(|(i, _)| i == item) {
+            Some(smoothed) => smoothed.clone(),
+            None => (*item, value.clone()),
+        })
+        .collect()
+}
+
+/// Renders the items spelled out for screen readers (see `--accessible`) as
+/// the plain format does, with commas between them for the reader to pause.
+pub fn accessible(items: &[(DataItem, String)], smoothed: &[(DataItem, String)]) -> String {
+    let values: Vec<String> = displayed(items, smoothed)
+        .into_iter()
+        .map(|(_, value)| value)
+        .filter(|value| !value.is_empty())
+        .collect();
+    wx::format::single_line(&values, ", ")
+}
+
+/// Renders the items as the JSON format does, pretty-printed and with the
+/// time of the fetch in unix seconds as "fetched_at".
+pub fn sidecar(items: &[(DataItem, String)], smoothed: &[(DataItem, String)], fetched_at: i64) -> String {
+    let mut object = json(items, smoothed);
+    object.insert("fetched_at".to_string(), Value::from(fetched_at));
+    serde_json::to_string_pretty(&object).unwrap_or_default()
+}
+
+/// Renders the items as the JSON format does, with `meta` about the build
+/// as "meta".
+pub fn json_with_meta(items: &[(DataItem, String)], smoothed: &[(DataItem, String)], meta: Value) -> String {
+    let mut object = json(items, smoothed);
+    object.insert("meta".to_string(), meta);
+    Value::Object(object).to_string()
+}
+
+/// Adds `explanations` to the JSON object `line` under `explain`, for
+/// `--explain`.
+pub fn explained(line: &str, explanations: &[wx::Explanation]) -> serde_json::Result<String> {
+    let Value::Object(mut object) = serde_json::from_str(line)? else {
+        return Ok(line.to_string());
+    };
+    object.insert("explain".to_string(), serde_json::to_value(explanations)?);
+    Ok(Value::Object(object).to_string())
+}
+
+/// Returns the JSON object of the JSON format.
+fn json(items: &[(DataItem, String)], smoothed: &[(DataItem, String)]) -> Map<String, Value> {
+    let mut object = object(items);
+    if !smoothed.is_empty() {
+        object.insert("smoothed".to_string(), Value::Object(self::object(smoothed)));
+    }
+    object
+}
+
+/// Returns a JSON object from item names to values.
+fn object(items: &[(DataItem, String)]) -> Map<String, Value> {
+    items
+        .iter()
+        .map(|(item, value)| (item.name().to_string(), Value::from(value.as_str())))
+        .collect()
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Format::ALL
+            .into_iter()
+            .find(|format| format.name() == s)
+            .ok_or_else(|| anyhow!("unknown output format '{}'", s))
+    }
+}
+
+impl<'de> Deserialize<'de> for Format {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Returns the JSON schema of [`wx::WeatherReport`], as raw JSON prints it.
+fn raw_json_schema() -> Value {
+    let number = |description: &str| json!({ "type": ["number", "null"], "description": description });
+    let string = |description: &str| json!({ "type": ["string", "null"], "description": description });
+    let object = |properties: Value| json!({ "type": "object", "properties": properties, "additionalProperties": false });
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "type": "object",
+        "properties": {
+            "schema": { "type": "integer", "description": "Version of the shape, bumped when a field changes or goes away" },
+            "units": { "enum": ["metric", "imperial", "standard"], "description": "The units of the temperatures and wind speeds" },
+            "place": object(json!({
+                "name": string("Name of the city the query resolved to"),
+                "country": string("ISO 3166 country code"),
+                "city_id": number("OpenWeatherMap's id of the city"),
+                "lat": number("Latitude, in decimal degrees"),
+                "lon": number("Longitude, in decimal degrees"),
+                "utc_offset": { "type": "integer", "description": "Shift of local time from UTC, in seconds" },
+            })),
+            "observed": { "type": "integer", "description": "Time of the observation, in unix seconds" },
+            "conditions": {
+                "type": "array",
+                "description": "The weather conditions, most significant first",
+                "items": object(json!({
+                    "id": { "type": "integer", "description": "Condition id, as in 502 for heavy rain" },
+                    "group": { "type": "string", "description": "Group of the condition, as in Rain" },
+                    "description": { "type": "string", "description": "In the language of the request" },
+                    "icon": { "type": "string", "description": "Icon id, as in 10d" },
+                    "severity": { "enum": ["none", "light", "moderate", "heavy", "extreme"] },
+                })),
+            },
+            "temperature": object(json!({
+                "current": number("Temperature"),
+                "feels_like": number("Feels-like temperature"),
+                "min": number("Lowest temperature at the moment, across the area"),
+                "max": number("Highest temperature at the moment, across the area"),
+                "dew_point": number("Dew point"),
+            })),
+            "pressure_hpa": number("Atmospheric pressure at sea level, in hPa"),
+            "humidity": number("Relative humidity, in percent"),
+            "clouds": number("Cloudiness, in percent"),
+            "visibility_m": number("Visibility in meters, up to 10 km"),
+            "wind": object(json!({
+                "speed": number("Wind speed"),
+                "gust": number("Gust speed"),
+                "deg": number("Direction the wind comes from, in degrees"),
+                "direction": string("That direction as one of 16 compass points, as in NNE"),
+                "beaufort": number("Force on the Beaufort scale, from 0 to 12"),
+            })),
+            "rain_mm_h": { "type": "number", "description": "Rain, in mm per hour; 0 when dry" },
+            "snow_mm_h": { "type": "number", "description": "Snow, in mm per hour; 0 when dry" },
+            "sun": object(json!({
+                "sunrise": number("Sunrise in unix seconds, null during polar day and night"),
+                "sunset": number("Sunset in unix seconds, null during polar day and night"),
+                "sunrise_local": string("Sunrise in local time, in RFC 3339"),
+                "sunset_local": string("Sunset in local time, in RFC 3339"),
+            })),
+            "stale": {
+                "type": "boolean",
+                "description": "True when the values are the last good ones, shown again as a refresh failed in watch mode or the network is down with --offline-ok",
+            },
+        },
+        "additionalProperties": false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(values: &[(DataItem, &str)]) -> Vec<(DataItem, String)> {
+        values.iter().map(|&(item, value)| (item, value.to_string())).collect()
+    }
+
+    fn joining(separator: &str, labels: bool) -> Joining {
+        Joining { separator: separator.to_string(), labels }
+    }
+
+    #[test]
+    fn separators_are_unescaped() {
+        let cases = [
+            (" | ", " | "),
+            ("\\t", "\t"),
+            ("\\n", "\n"),
+            ("a\\\\tb", "a\\tb"),
+            ("\\x", "\\x"),
+            ("end\\", "end\\"),
+            ("ü\\tö", "ü\tö"),
+            ("", ""),
+        ];
+        for (separator, expected) in cases {
+            assert_eq!(unescape(separator), expected, "{:?}", separator);
+        }
+    }
+
+    #[test]
+    fn the_default_joining_is_unchanged() {
+        let values = items(&[(DataItem::Temp, "12°"), (DataItem::Rain, ""), (DataItem::Humidity, "81%")]);
+        assert_eq!(plain(&values, &[], &Joining::default()), "12°  81%");
+    }
+
+    #[test]
+    fn items_are_joined_and_labeled() {
+        let values = items(&[
+            (DataItem::Icon, "🌧"),
+            (DataItem::Temp, "12°"),
+            (DataItem::Rain, ""),
+            (DataItem::Humidity, "81%"),
+        ]);
+        let cases = [
+            (joining(" | ", false), "🌧 | 12° | 81%"),
+            (joining("\t", false), "🌧\t12°\t81%"),
+            // The icon has no label.
+            (joining(" ", true), "🌧 temp:12° hum:81%"),
+            (joining(",", true), "🌧,temp:12°,hum:81%"),
+        ];
+        for (joining, expected) in cases {
+            assert_eq!(plain(&values, &[], &joining), expected, "{:?}", joining);
+        }
+        // A newline is kept, for a line of each item.
+        assert_eq!(wx::format::single_line(&["a", "b"], "\n"), "a\nb");
+    }
+
+    #[test]
+    fn one_item_has_no_separator() {
+        for joining in [joining(" | ", false), joining(" | ", true), Joining::default()] {
+            let line = plain(&items(&[(DataItem::Humidity, "81%")]), &[], &joining);
+            assert_eq!(line, if joining.labels { "hum:81%" } else { "81%" });
+            // Nor does one with an empty other.
+            let values = items(&[(DataItem::Humidity, "81%"), (DataItem::Rain, "")]);
+            let line = plain(&values, &[], &joining);
+            assert!(!line.ends_with('|') && !line.ends_with(':'), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn smoothed_values_are_joined_in_place() {
+        let values = items(&[(DataItem::Temp, "12°"), (DataItem::Humidity, "81%")]);
+        let smoothed = items(&[(DataItem::Temp, "11°")]);
+        assert_eq!(plain(&values, &smoothed, &joining(" | ", true)), "temp:11° | hum:81%");
+        let waybar = Format::Waybar.render(&values, &smoothed, &Status::default(), &joining("/", false));
+        let waybar: Value = serde_json::from_str(&waybar).unwrap();
+        assert_eq!(waybar["text"], "11°/81%");
+    }
+}
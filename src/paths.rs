@@ -0,0 +1,162 @@
+//! Locations of files tinywx keeps between runs.
+
+use std::env;
+use std::path::PathBuf;
+
+/// Directory for cached data: `tinywx` in `$XDG_CACHE_HOME`, falling back to
+/// `~/.cache`, or on macOS `~/Library/Caches` and on Windows
+/// `%LOCALAPPDATA%`.
+pub fn cache_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CACHE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ if cfg!(windows) => PathBuf::from(env::var_os("LOCALAPPDATA")?),
+        _ if cfg!(target_os = "macos") => home()?.join("Library/Caches"),
+        _ => home()?.join(".cache"),
+    };
+    Some(base.join("tinywx"))
+}
+
+/// Where the config file is looked for when none is given, whether or not it
+/// exists: `tinywx/config.toml` in `$XDG_CONFIG_HOME`, falling back to
+/// `~/.config`, or on macOS `~/Library/Application Support` and on Windows
+/// `%APPDATA%`.
+pub fn default_config_file() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ if cfg!(windows) => PathBuf::from(env::var_os("APPDATA")?),
+        _ if cfg!(target_os = "macos") => home()?.join("Library/Application Support"),
+        _ => home()?.join(".config"),
+    };
+    Some(base.join("tinywx").join("config.toml"))
+}
+
+/// The name the default config file had before, `tinywx.toml` next to
+/// [`default_config_file`]. It is still read, but is deprecated.
+pub fn legacy_config_file() -> Option<PathBuf> {
+    Some(default_config_file()?.with_file_name("tinywx.toml"))
+}
+
+/// The config file read when none is given: [`default_config_file`] if it
+/// exists, or else [`legacy_config_file`] if that does.
+pub fn config_file() -> Option<PathBuf> {
+    let exists = |path: &PathBuf| path.is_file();
+    default_config_file().filter(exists).or_else(|| legacy_config_file().filter(exists))
+}
+
+/// The home directory: `$HOME`, or on Windows `%USERPROFILE%` without it.
+fn home() -> Option<PathBuf> {
+    let var = |name| env::var_os(name).filter(|dir| !dir.is_empty());
+    let dir = var("HOME").or_else(|| if cfg!(windows) { var("USERPROFILE") } else { None })?;
+    Some(PathBuf::from(dir))
+}
+
+/// Expands a path given by the user the way a shell would: a leading `~` to
+/// the home directory and `$VAR` or `${VAR}` to the environment variable, so
+/// that `-f ~/tinywx.toml` also works where no shell expanded it, as in a
+/// quoted argument or a bar's config. Variables that are not set, and `~`
+/// without a home directory, are left as they are.
+pub fn expand(path: &str) -> PathBuf {
+    let mut expanded = String::new();
+    let mut rest = path;
+    if let Some(after) = rest.strip_prefix('~').filter(|after| after.is_empty() || after.starts_with(['/', '\\'])) {
+        if let Some(home) = home() {
+            expanded.push_str(&home.to_string_lossy());
+            rest = after;
+        }
+    }
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, len) = match after.strip_prefix('{').and_then(|inner| inner.find('}').map(|end| &inner[..end])) {
+            Some(name) => (name, name.len() + 2),
+            None => {
+                let end = after.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+        match env::var_os(name).filter(|_| !name.is_empty()) {
+            Some(value) => expanded.push_str(&value.to_string_lossy()),
+            None => expanded.push_str(&rest[start..start + 1 + len]),
+        }
+        rest = &after[len..];
+    }
+    expanded.push_str(rest);
+    PathBuf::from(expanded)
+}
+
+/// Expands `path` (see [`expand`]) and makes it absolute, so that errors show
+/// the file that was actually tried.
+pub fn resolve(path: &str) -> PathBuf {
+    let path = expand(path);
+    std::path::absolute(&path).unwrap_or(path)
+}
+
+/// Turns a key into a string that is safe to use as a file name.
+pub fn file_name(key: &str) -> String {
+    key.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A variable set for these tests, and one that is never set.
+    const SET: &str = "TINYWX_TEST_EXPAND";
+    const UNSET: &str = "TINYWX_TEST_EXPAND_UNSET";
+
+    fn expanded(path: &str) -> String {
+        env::set_var(SET, "/srv/wx");
+        env::remove_var(UNSET);
+        expand(path).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn a_leading_tilde_is_the_home_directory() {
+        let home = home().map(|home| home.to_string_lossy().into_owned()).unwrap_or_else(|| "~".to_string());
+        assert_eq!(expanded("~"), home);
+        assert_eq!(expanded("~/tinywx.toml"), format!("{}/tinywx.toml", home));
+        assert_eq!(expanded("~/"), format!("{}/", home));
+    }
+
+    #[test]
+    fn other_tildes_are_left_alone() {
+        for path in ["~user/tinywx.toml", "~user", "a/~/b", "/etc/~", "~~"] {
+            assert_eq!(expanded(path), path);
+        }
+    }
+
+    #[test]
+    fn variables_are_expanded() {
+        let cases = [
+            ("$TINYWX_TEST_EXPAND", "/srv/wx"),
+            ("${TINYWX_TEST_EXPAND}", "/srv/wx"),
+            ("$TINYWX_TEST_EXPAND/tinywx.toml", "/srv/wx/tinywx.toml"),
+            ("${TINYWX_TEST_EXPAND}.d/tinywx.toml", "/srv/wx.d/tinywx.toml"),
+            ("/etc/$TINYWX_TEST_EXPAND-x", "/etc//srv/wx-x"),
+            ("$TINYWX_TEST_EXPAND$TINYWX_TEST_EXPAND", "/srv/wx/srv/wx"),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(expanded(path), expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn unset_variables_and_bare_dollars_are_left_alone() {
+        let paths = [
+            "$TINYWX_TEST_EXPAND_UNSET/tinywx.toml",
+            "${TINYWX_TEST_EXPAND_UNSET}/tinywx.toml",
+            "$",
+            "a$",
+            "$/tinywx.toml",
+            "${}",
+            "${TINYWX_TEST_EXPAND",
+            "price$5",
+        ];
+        for path in paths {
+            assert_eq!(expanded(path), path);
+        }
+        assert_eq!(expanded("$TINYWX_TEST_EXPAND_UNSET:$TINYWX_TEST_EXPAND"), "$TINYWX_TEST_EXPAND_UNSET:/srv/wx");
+    }
+}
@@ -0,0 +1,175 @@
+//! What a run will ask the API for, worked out before making any request.
+
+use std::fmt;
+
+use wx::item::Endpoint;
+use wx::DataItem;
+
+use crate::cityid::Query;
+
+/// How the first request, for the current weather, finds the location.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lookup {
+    /// By the given coordinates.
+    Coords,
+    /// By the city's name.
+    Name,
+    /// By zip code.
+    Zip,
+    /// By the id the city resolved to in an earlier run.
+    CityId(u64),
+    /// By the coordinates the geocoding API finds for the city's name, which
+    /// takes a request of its own unless it is cached.
+    Geocoded { cached: bool },
+}
+
+impl From<Query> for Lookup {
+    fn from(query: Query) -> Self {
+        match query {
+            Query::Name => Lookup::Name,
+            Query::Id(id) => Lookup::CityId(id),
+        }
+    }
+}
+
+/// The requests a run makes, in order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Plan {
+    pub lookup: Lookup,
+    /// Every endpoint once, the current weather first: the other endpoints
+    /// need the coordinates it returns.
+    pub endpoints: Vec<Endpoint>,
+    /// The endpoints whose responses are in the cache and still fresh, which
+    /// take no request.
+    pub cached: Vec<Endpoint>,
+}
+
+impl Plan {
+    /// Plans the requests for `items`, leaving out those of items this build
+    /// cannot render, which render a placeholder instead.
+    pub fn new(items: &[DataItem], lookup: Lookup) -> Self {
+        let mut endpoints = vec![Endpoint::Current];
+        let supported = items.iter().filter(|item| item.unsupported().is_none());
+        for &endpoint in supported.flat_map(|item| item.info().endpoints) {
+            if !endpoints.contains(&endpoint) {
+                endpoints.push(endpoint);
+            }
+        }
+        Plan { lookup, endpoints, cached: Vec::new() }
+    }
+
+    /// Returns the number of requests made when everything goes as planned.
+    pub fn requests(&self) -> usize {
+        let geocoding = usize::from(self.lookup == Lookup::Geocoded { cached: false });
+        geocoding + self.endpoints.iter().filter(|endpoint| !self.cached.contains(endpoint)).count()
+    }
+
+    /// Returns the number of requests made at worst: a stored city id that is
+    /// no longer valid costs a second request, by name, unless the current
+    /// weather is cached.
+    pub fn max_requests(&self) -> usize {
+        match self.lookup {
+            Lookup::CityId(_) if !self.cached.contains(&Endpoint::Current) => self.requests() + 1,
+            Lookup::CityId(_) | Lookup::Coords | Lookup::Name | Lookup::Zip | Lookup::Geocoded { .. } => {
+                self.requests()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Lookup::Geocoded { cached } = self.lookup {
+            let cached = if cached { " (cached)" } else { "" };
+            writeln!(f, "GET {:<32} by city name{}", wx::geo::GEOCODING_ENDPOINT, cached)?;
+        }
+        for &endpoint in &self.endpoints {
+            let by = match (endpoint, self.lookup) {
+                (Endpoint::Current, Lookup::Coords) => "by coordinates".to_string(),
+                (Endpoint::Current, Lookup::Name) => "by city name".to_string(),
+                (Endpoint::Current, Lookup::Zip) => "by zip code".to_string(),
+                (Endpoint::Current, Lookup::Geocoded { .. }) => "by the coordinates geocoding finds".to_string(),
+                (Endpoint::Current, Lookup::CityId(id)) => {
+                    format!("by city id {}, stored from an earlier run", id)
+                }
+                (Endpoint::Forecast, _) => "by the location, as the current weather".to_string(),
+                _ => "by the coordinates of the current weather".to_string(),
+            };
+            if self.cached.contains(&endpoint) {
+                writeln!(f, "GET {:<32} {} (cached)", endpoint.path(), by)?;
+            } else {
+                writeln!(f, "GET {:<32} {}", endpoint.path(), by)?;
+            }
+        }
+        match self.requests() {
+            1 => write!(f, "1 request")?,
+            n => write!(f, "{} requests", n)?,
+        }
+        if self.max_requests() > self.requests() {
+            write!(f, ", {} if the stored city id is out of date", self.max_requests())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints_are_planned_once_the_current_weather_first() {
+        let plan = Plan::new(&[DataItem::TodayHigh, DataItem::Temp, DataItem::TodayLow], Lookup::Coords);
+        assert_eq!(plan.endpoints, [Endpoint::Current, Endpoint::Forecast]);
+        let plan = Plan::new(&[DataItem::VentilationWindow, DataItem::TodayHigh], Lookup::Name);
+        assert_eq!(plan.endpoints, [Endpoint::Current, Endpoint::AirPollution, Endpoint::Forecast]);
+        assert_eq!(Plan::new(&[], Lookup::Zip).endpoints, [Endpoint::Current]);
+    }
+
+    #[test]
+    fn requests_of_each_lookup_with_a_cold_and_a_warm_cache() {
+        let items = [DataItem::Temp, DataItem::TodayHigh];
+        // (lookup, requests and at most with a cold cache, with the current
+        // weather cached).
+        let cases = [
+            (Lookup::Coords, (2, 2), (1, 1)),
+            (Lookup::Name, (2, 2), (1, 1)),
+            (Lookup::Zip, (2, 2), (1, 1)),
+            (Lookup::CityId(2759794), (2, 3), (1, 1)),
+            (Lookup::Geocoded { cached: false }, (3, 3), (2, 2)),
+            (Lookup::Geocoded { cached: true }, (2, 2), (1, 1)),
+        ];
+        for (lookup, cold, warm) in cases {
+            let mut plan = Plan::new(&items, lookup);
+            assert_eq!((plan.requests(), plan.max_requests()), cold, "{:?}", lookup);
+            plan.cached.push(Endpoint::Current);
+            assert_eq!((plan.requests(), plan.max_requests()), warm, "{:?}", lookup);
+            plan.cached.push(Endpoint::Forecast);
+            assert_eq!(plan.requests(), warm.0 - 1, "{:?}", lookup);
+        }
+    }
+
+    #[test]
+    fn plans_print_a_request_a_line() {
+        let mut plan = Plan::new(&[DataItem::Temp, DataItem::TodayHigh], Lookup::CityId(2759794));
+        assert_eq!(
+            plan.to_string(),
+            "GET data/2.5/weather                 by city id 2759794, stored from an earlier run\n\
+             GET data/2.5/forecast                by the location, as the current weather\n\
+             2 requests, 3 if the stored city id is out of date"
+        );
+        plan.cached.push(Endpoint::Current);
+        assert_eq!(
+            plan.to_string(),
+            "GET data/2.5/weather                 by city id 2759794, stored from an earlier run (cached)\n\
+             GET data/2.5/forecast                by the location, as the current weather\n\
+             1 request"
+        );
+        let plan = Plan::new(&[DataItem::Temp], Lookup::Geocoded { cached: true });
+        assert_eq!(
+            plan.to_string(),
+            "GET geo/1.0/direct                   by city name (cached)\n\
+             GET data/2.5/weather                 by the coordinates geocoding finds\n\
+             1 request"
+        );
+    }
+}
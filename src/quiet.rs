@@ -0,0 +1,247 @@
+//! Quiet hours: windows of the machine's local time during which sinks that
+//! get someone's attention, like notifications and commands, hold back what
+//! they would deliver.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error, Result};
+use chrono::Timelike;
+use serde::{Deserialize, Deserializer};
+
+use crate::sink::{OutputSink, Rendered};
+
+/// A window of local time, from `start` up to `end`, both in minutes after
+/// midnight. A window that ends before it starts crosses midnight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Window {
+    start: u32,
+    end: u32,
+}
+
+impl Window {
+    /// Returns whether `minute` after midnight is in the window.
+    pub fn contains(self, minute: u32) -> bool {
+        if self.start <= self.end {
+            minute >= self.start && minute < self.end
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+}
+
+impl FromStr for Window {
+    type Err = Error;
+
+    /// Parses a window as "HH:MM-HH:MM", e.g. "22:00-07:00".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || anyhow!("invalid quiet hours '{}': expected HH:MM-HH:MM, as in 22:00-07:00", s);
+        let (start, end) = s.split_once('-').ok_or_else(invalid)?;
+        Ok(Window {
+            start: parse_clock(start).ok_or_else(invalid)?,
+            end: parse_clock(end).ok_or_else(invalid)?,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Window {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Parses a local time as "HH:MM", into minutes after midnight.
+pub fn parse_clock(s: &str) -> Option<u32> {
+    let (hours, minutes) = s.trim().split_once(':')?;
+    let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours <= 23 && minutes <= 59).then_some(hours * 60 + minutes)
+}
+
+/// Returns the machine's local time, in minutes after midnight. Quiet hours
+/// are about the person at the machine, not the place the weather is for.
+fn local_minute() -> u32 {
+    let now = chrono::Local::now();
+    now.hour() * 60 + now.minute()
+}
+
+/// A sink that holds back what it gets during quiet hours. What is held back
+/// is counted, and with `deliver_after` the first delivery after the quiet
+/// hours says how much there was, in front of the line; outputs in a format
+/// of their own are delivered as they are.
+pub struct Quiet {
+    pub sink: Box<dyn OutputSink>,
+    pub windows: Vec<Window>,
+    pub deliver_after: bool,
+    held: usize,
+}
+
+impl Quiet {
+    pub fn new(sink: Box<dyn OutputSink>, windows: Vec<Window>, deliver_after: bool) -> Self {
+        Quiet {
+            sink,
+            windows,
+            deliver_after,
+            held: 0,
+        }
+    }
+
+    /// Delivers `rendered` at `minute` after midnight, local time, unless
+    /// that is in the quiet hours.
+    pub fn deliver_at(&mut self, rendered: &Rendered, minute: u32) -> Result<()> {
+        if self.windows.iter().any(|w| w.contains(minute)) {
+            self.held += 1;
+            return Ok(());
+        }
+        let held = std::mem::take(&mut self.held);
+        if !self.deliver_after || held == 0 {
+            return self.sink.deliver(rendered);
+        }
+        let line = format!(
+            "{} {} held back during quiet hours; now:\n{}",
+            held,
+            if held == 1 { "update" } else { "updates" },
+            rendered.line
+        );
+        let result = self.sink.deliver(&Rendered { line: &line, ..*rendered });
+        if result.is_err() {
+            self.held = held;
+        }
+        result
+    }
+}
+
+impl OutputSink for Quiet {
+    fn name(&self) -> String {
+        self.sink.name()
+    }
+
+    fn deliver(&mut self, rendered: &Rendered) -> Result<()> {
+        self.deliver_at(rendered, local_minute())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::output::{Joining, Status};
+
+    /// What a [`Recorder`] got, and whether it fails.
+    #[derive(Default)]
+    struct Delivered {
+        lines: Mutex<Vec<String>>,
+        failing: AtomicBool,
+    }
+
+    /// A sink that records the lines it gets, and fails while `failing` is
+    /// set.
+    struct Recorder(Arc<Delivered>);
+
+    impl OutputSink for Recorder {
+        fn name(&self) -> String {
+            "recorder".to_string()
+        }
+
+        fn deliver(&mut self, rendered: &Rendered) -> Result<()> {
+            if self.0.failing.load(Ordering::SeqCst) {
+                return Err(anyhow!("failing"));
+            }
+            self.0.lines.lock().unwrap().push(rendered.line.to_string());
+            Ok(())
+        }
+    }
+
+    /// Delivers `line` at `minute` after midnight through `quiet`.
+    fn deliver(quiet: &mut Quiet, line: &str, minute: u32) -> Result<()> {
+        let (status, joining) = (Status::default(), Joining::default());
+        let rendered = Rendered {
+            line,
+            json: "",
+            metrics: "",
+            items: &[],
+            smoothed: &[],
+            status: &status,
+            joining: &joining,
+            raw: "",
+        };
+        quiet.deliver_at(&rendered, minute)
+    }
+
+    /// Returns a quiet sink for "22:00-07:00", and what the sink it delivers
+    /// to got.
+    fn quiet(deliver_after: bool) -> (Quiet, Arc<Delivered>) {
+        let delivered = Arc::new(Delivered::default());
+        let windows = vec!["22:00-07:00".parse().unwrap()];
+        (Quiet::new(Box::new(Recorder(Arc::clone(&delivered))), windows, deliver_after), delivered)
+    }
+
+    #[test]
+    fn clocks_from_midnight_to_a_minute_before() {
+        assert_eq!(parse_clock("00:00"), Some(0));
+        assert_eq!(parse_clock("07:00"), Some(420));
+        assert_eq!(parse_clock(" 7:05 "), Some(425));
+        assert_eq!(parse_clock("23:59"), Some(1439));
+        for s in ["24:00", "23:60", "12", "12:", ":30", "-1:00", "12:30:00", "noon", ""] {
+            assert_eq!(parse_clock(s), None, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn windows_start_at_their_start_and_end_before_their_end() {
+        let day: Window = "09:00-17:00".parse().unwrap();
+        let cases = [(539, false), (540, true), (1019, true), (1020, false), (0, false), (1439, false)];
+        for (minute, quiet) in cases {
+            assert_eq!(day.contains(minute), quiet, "{}", minute);
+        }
+    }
+
+    #[test]
+    fn windows_over_midnight() {
+        let night: Window = "22:00-07:00".parse().unwrap();
+        let cases = [(1319, false), (1320, true), (1439, true), (0, true), (419, true), (420, false), (720, false)];
+        for (minute, quiet) in cases {
+            assert_eq!(night.contains(minute), quiet, "{}", minute);
+        }
+        let empty: Window = "07:00-07:00".parse().unwrap();
+        assert!((0..1440).all(|minute| !empty.contains(minute)));
+    }
+
+    #[test]
+    fn windows_that_do_not_parse() {
+        for s in ["22:00", "22:00-", "-07:00", "22:00-24:00", "22:00–07:00", "10pm-7am"] {
+            let error = s.parse::<Window>().unwrap_err().to_string();
+            assert_eq!(error, format!("invalid quiet hours '{}': expected HH:MM-HH:MM, as in 22:00-07:00", s));
+        }
+    }
+
+    #[test]
+    fn outputs_in_the_quiet_hours_are_held_back() {
+        let (mut quiet, delivered) = quiet(false);
+        for (line, minute) in [("a", 1319), ("b", 1320), ("c", 0), ("d", 419), ("e", 420)] {
+            deliver(&mut quiet, line, minute).unwrap();
+        }
+        assert_eq!(*delivered.lines.lock().unwrap(), ["a", "e"]);
+    }
+
+    #[test]
+    fn the_first_output_after_says_how_many_were_held_back() {
+        let (mut quiet, delivered) = quiet(true);
+        deliver(&mut quiet, "a", 1320).unwrap();
+        deliver(&mut quiet, "b", 419).unwrap();
+        delivered.failing.store(true, Ordering::SeqCst);
+        assert!(deliver(&mut quiet, "c", 420).is_err());
+        delivered.failing.store(false, Ordering::SeqCst);
+        deliver(&mut quiet, "d", 421).unwrap();
+        deliver(&mut quiet, "e", 1320).unwrap();
+        deliver(&mut quiet, "f", 600).unwrap();
+        deliver(&mut quiet, "g", 601).unwrap();
+        let expected = [
+            "2 updates held back during quiet hours; now:\nd",
+            "1 update held back during quiet hours; now:\nf",
+            "g",
+        ];
+        assert_eq!(*delivered.lines.lock().unwrap(), expected);
+    }
+}
@@ -0,0 +1,85 @@
+//! Keeping watch mode within the API's rate limit.
+
+use wx::DataItem;
+
+use crate::plan::{Lookup, Plan};
+
+/// Default budget of API calls per minute; the free plan allows 60.
+pub const DEFAULT_BUDGET: u32 = 50;
+/// Shortest interval between refreshes, in seconds, whatever the budget.
+/// OpenWeatherMap updates its data every few minutes, so refreshing more
+/// often only uses up the rate limit.
+pub const MIN_INTERVAL: u64 = 60;
+
+/// Returns the number of API calls one refresh makes: one per endpoint the
+/// items need, for every location.
+pub fn calls_per_refresh(items: &[DataItem], locations: usize) -> usize {
+    Plan::new(items, Lookup::Name).requests() * locations
+}
+
+/// Returns the expected number of API calls per minute when refreshing every
+/// `interval` seconds.
+pub fn calls_per_minute(interval: u64, calls_per_refresh: usize) -> f64 {
+    calls_per_refresh as f64 * 60.0 / interval.max(1) as f64
+}
+
+/// Returns the shortest interval, at least `interval` and [`MIN_INTERVAL`],
+/// in seconds that keeps the calls per minute within `budget`.
+pub fn tune_interval(interval: u64, calls_per_refresh: usize, budget: u32) -> u64 {
+    let budget = u64::from(budget.max(1));
+    let minimum = (60 * calls_per_refresh as u64).div_ceil(budget);
+    interval.max(minimum).max(MIN_INTERVAL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refreshes_count_each_endpoint_for_each_location() {
+        assert_eq!(calls_per_refresh(&[DataItem::Temp, DataItem::Humidity], 1), 1);
+        assert_eq!(calls_per_refresh(&[DataItem::Temp, DataItem::Humidity], 3), 3);
+        assert_eq!(calls_per_refresh(&[DataItem::Temp, DataItem::TodayHigh], 1), 2);
+        assert_eq!(calls_per_refresh(&[DataItem::Temp, DataItem::TodayHigh], 3), 6);
+    }
+
+    #[test]
+    fn calls_per_minute_of_an_interval() {
+        assert_eq!(calls_per_minute(60, 1), 1.0);
+        assert_eq!(calls_per_minute(30, 2), 4.0);
+        assert_eq!(calls_per_minute(120, 3), 1.5);
+        assert_eq!(calls_per_minute(0, 1), 60.0);
+    }
+
+    #[test]
+    fn intervals_are_at_least_the_minimum() {
+        assert_eq!(tune_interval(0, 1, DEFAULT_BUDGET), MIN_INTERVAL);
+        assert_eq!(tune_interval(MIN_INTERVAL - 1, 1, DEFAULT_BUDGET), MIN_INTERVAL);
+        assert_eq!(tune_interval(MIN_INTERVAL, 1, DEFAULT_BUDGET), MIN_INTERVAL);
+        assert_eq!(tune_interval(MIN_INTERVAL + 1, 1, DEFAULT_BUDGET), MIN_INTERVAL + 1);
+        assert_eq!(tune_interval(600, 0, DEFAULT_BUDGET), 600);
+    }
+
+    #[test]
+    fn intervals_keep_to_the_budget_at_its_edge() {
+        // 50 calls a refresh on a budget of 50 a minute fit a refresh a minute.
+        assert_eq!(tune_interval(60, 50, 50), 60);
+        // One call more needs 61.2 seconds, rounded up.
+        assert_eq!(tune_interval(60, 51, 50), 62);
+        assert_eq!(tune_interval(62, 51, 50), 62);
+        assert_eq!(tune_interval(63, 51, 50), 63);
+        assert_eq!(tune_interval(60, 100, 50), 120);
+        assert_eq!(tune_interval(60, 101, 50), 122);
+        for (interval, calls, budget) in [(60, 51, 50), (60, 101, 50), (60, 7, 3), (90, 200, 60)] {
+            let tuned = tune_interval(interval, calls, budget);
+            assert!(calls_per_minute(tuned, calls) <= f64::from(budget), "{} {} {}", interval, calls, budget);
+            assert!(calls_per_minute(tuned - 1, calls) > f64::from(budget), "{} {} {}", interval, calls, budget);
+        }
+    }
+
+    #[test]
+    fn a_budget_of_0_is_1() {
+        assert_eq!(tune_interval(60, 2, 0), 120);
+        assert_eq!(tune_interval(60, 2, 0), tune_interval(60, 2, 1));
+    }
+}
@@ -0,0 +1,208 @@
+//! Picking one of the places `--resolve` lists, by number, and pinning its
+//! coordinates in the config file, so that the weather is always for that
+//! place and not the one OpenWeatherMap picks for the name.
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+/// The settings of the location at the top of the config file, which the
+/// pinned coordinates replace.
+const LOCATION_KEYS: [&str; 7] = ["city", "state", "country", "zip", "coords", "lat", "lon"];
+
+/// How the comment above pinned coordinates ends, to find it again.
+const PINNED: &str = ", picked with tinywx --resolve";
+
+/// Where the answers to the prompt come from: a line at a time, `None` at
+/// the end of the input.
+pub trait Input {
+    fn line(&mut self) -> io::Result<Option<String>>;
+}
+
+impl<R: BufRead> Input for R {
+    fn line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        Ok((self.read_line(&mut line)? > 0).then_some(line))
+    }
+}
+
+/// Asks on `out` for the number of one of `count` places until `input`
+/// answers with one, and returns it, from 1. Fails if the input ends first.
+pub fn prompt(input: &mut impl Input, out: &mut impl Write, count: usize) -> Result<usize> {
+    loop {
+        write!(out, "Pick a place, 1 to {}: ", count)?;
+        out.flush()?;
+        let Some(line) = input.line()? else {
+            writeln!(out)?;
+            return Err(anyhow!("no place picked"));
+        };
+        match line.trim() {
+            "" => {}
+            answer => match answer.parse::<usize>() {
+                Ok(n) if (1..=count).contains(&n) => return Ok(n),
+                _ => writeln!(out, "'{}' is not one of the numbers 1 to {}", answer, count)?,
+            },
+        }
+    }
+}
+
+/// Returns `config`, the text of a config file, with the location at its top
+/// replaced by the coordinates of `place`, named in a comment above them.
+/// The rest of the file, comments and all, is left as it is.
+pub fn pin(config: &str, place: &wx::GeoResult) -> String {
+    let mut lines: Vec<&str> = config.lines().collect();
+    // Settings at the top come before the first table, as in [format].
+    let top = lines.iter().position(|line| line.trim_start().starts_with('[')).unwrap_or(lines.len());
+    let mut at = None;
+    let mut i = 0;
+    let mut end = top;
+    while i < end {
+        let line = lines[i].trim();
+        let key = line.split('=').next().unwrap_or_default().trim();
+        if (line.contains('=') && LOCATION_KEYS.contains(&key)) || (line.starts_with('#') && line.ends_with(PINNED)) {
+            lines.remove(i);
+            at.get_or_insert(i);
+            end -= 1;
+        } else {
+            i += 1;
+        }
+    }
+    let comment = format!("# {}{}", place, PINNED);
+    let coords = format!("coords = \"{},{}\"", place.lat, place.lon);
+    let mut pinned = vec![comment.as_str(), coords.as_str()];
+    let at = match at {
+        Some(at) => at,
+        None => {
+            // After what is at the top, apart from a table right below it.
+            if end > 0 && !lines[end - 1].trim().is_empty() {
+                pinned.insert(0, "");
+            }
+            if end < lines.len() {
+                pinned.push("");
+            }
+            end
+        }
+    };
+    lines.splice(at..at, pinned);
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+/// Pins `place` in the config file at `path`, creating it if there is none.
+pub fn write(path: &Path, place: &wx::GeoResult) -> Result<()> {
+    let config = match fs::read_to_string(path) {
+        Ok(config) => config,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(anyhow!("cannot read {}: {}", path.display(), e)),
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| anyhow!("cannot create {}: {}", dir.display(), e))?;
+    }
+    fs::write(path, pin(&config, place)).map_err(|e| anyhow!("cannot write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn springfield() -> wx::GeoResult {
+        wx::GeoResult {
+            name: "Springfield".to_string(),
+            state: Some("Illinois".to_string()),
+            country: "US".to_string(),
+            lat: 39.7990175,
+            lon: -89.6439575,
+        }
+    }
+
+    /// Answers the prompt for 3 places with `answers`, and returns the number
+    /// picked, or the error, and what was written to the prompt.
+    fn answer(answers: &str) -> (Result<usize, String>, String) {
+        let mut out = Vec::new();
+        let picked = prompt(&mut answers.as_bytes(), &mut out, 3).map_err(|e| e.to_string());
+        (picked, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn a_number_picks_a_place() {
+        assert_eq!(answer("2\n"), (Ok(2), "Pick a place, 1 to 3: ".to_string()));
+        assert_eq!(answer(" 3 \r\n").0, Ok(3));
+        assert_eq!(answer("1").0, Ok(1));
+    }
+
+    #[test]
+    fn other_answers_ask_again() {
+        let (picked, out) = answer("0\n4\nSpringfield\n-1\n\n2\n");
+        assert_eq!(picked, Ok(2));
+        let expected = [
+            "Pick a place, 1 to 3: '0' is not one of the numbers 1 to 3",
+            "Pick a place, 1 to 3: '4' is not one of the numbers 1 to 3",
+            "Pick a place, 1 to 3: 'Springfield' is not one of the numbers 1 to 3",
+            "Pick a place, 1 to 3: '-1' is not one of the numbers 1 to 3",
+            "Pick a place, 1 to 3: Pick a place, 1 to 3: ",
+        ];
+        assert_eq!(out, expected.join("\n"));
+    }
+
+    #[test]
+    fn the_end_of_the_input_picks_nothing() {
+        assert_eq!(answer(""), (Err("no place picked".to_string()), "Pick a place, 1 to 3: \n".to_string()));
+        assert_eq!(answer("9\n").0, Err("no place picked".to_string()));
+    }
+
+    #[test]
+    fn pinning_replaces_the_location() {
+        let config = concat!(
+            "# The city.\n",
+            "city = \"springfield\"\n",
+            "#state = \"il\"\n",
+            "country = \"us\"\n",
+            "data = [\"temp\"]\n",
+            "\n",
+            "[[locations]]\n",
+            "city = \"berlin\"\n",
+        );
+        let expected = concat!(
+            "# The city.\n",
+            "# Springfield, Illinois, US, picked with tinywx --resolve\n",
+            "coords = \"39.7990175,-89.6439575\"\n",
+            "#state = \"il\"\n",
+            "data = [\"temp\"]\n",
+            "\n",
+            "[[locations]]\n",
+            "city = \"berlin\"\n",
+        );
+        let pinned = pin(config, &springfield());
+        assert_eq!(pinned, expected);
+        // Pinning again replaces the pinned place.
+        let mut elsewhere = springfield();
+        elsewhere.state = Some("Missouri".to_string());
+        let repinned = pin(&pinned, &elsewhere);
+        assert_eq!(repinned, expected.replace("Illinois", "Missouri"));
+    }
+
+    #[test]
+    fn pinning_without_a_location() {
+        let pinned = concat!(
+            "# Springfield, Illinois, US, picked with tinywx --resolve\n",
+            "coords = \"39.7990175,-89.6439575\"\n",
+        );
+        assert_eq!(pin("", &springfield()), pinned);
+        assert_eq!(pin("units = \"imperial\"\n", &springfield()), format!("units = \"imperial\"\n\n{}", pinned));
+        let config = "data = [\"temp\"]\n\n[format]\nunit_style = \"compact\"\n";
+        let expected = format!("data = [\"temp\"]\n\n{}\n[format]\nunit_style = \"compact\"\n", pinned);
+        assert_eq!(pin(config, &springfield()), expected);
+        assert_eq!(pin("[format]\n", &springfield()), format!("{}\n[format]\n", pinned));
+    }
+
+    #[test]
+    fn pinned_config_parses() {
+        let config = include_str!("../tinywx.toml");
+        let pinned: toml::Value = toml::from_str(&pin(config, &springfield())).unwrap();
+        assert_eq!(pinned["coords"].as_str(), Some("39.7990175,-89.6439575"));
+        assert!(pinned.get("city").is_none() && pinned.get("country").is_none());
+    }
+}
@@ -0,0 +1,563 @@
+//! Secondary outputs, which get each rendered line, or the structured data
+//! behind it, besides stdout. Besides the `output_file`, `json_sidecar` and
+//! `textfile` settings, any number of them can be listed as `[[outputs]]` in
+//! the config file, each in a format of its own.
+//!
+//! Sinks depend on things outside tinywx's control, like a writable path, so
+//! they fail softly: a failing sink is reported and then skipped, without
+//! affecting the primary output. In watch mode it is retried with increasing
+//! intervals; in a single run it is simply disabled.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use wx::DataItem;
+
+use crate::{output, quiet};
+
+/// Delay before retrying a failed sink for the first time.
+const FIRST_RETRY: Duration = Duration::from_secs(60);
+/// Longest delay between retries.
+const MAX_RETRY: Duration = Duration::from_secs(30 * 60);
+
+/// The output of one fetch, for sinks to pick from.
+pub struct Rendered<'a> {
+    /// The line printed to stdout.
+    pub line: &'a str,
+    /// The structured data, as pretty-printed JSON.
+    pub json: &'a str,
+    /// The numbers behind the data, as Prometheus metrics.
+    pub metrics: &'a str,
+    /// The rendered items, and the smoothed values of some, for sinks with a
+    /// format of their own.
+    pub items: &'a [(DataItem, String)],
+    pub smoothed: &'a [(DataItem, String)],
+    /// The tooltip and class of the Waybar format.
+    pub status: &'a output::Status,
+    /// How the items on one line are joined.
+    pub joining: &'a output::Joining,
+    /// The whole weather, in the raw JSON format.
+    pub raw: &'a str,
+}
+
+impl Rendered<'_> {
+    /// Returns the output in `format`, or the line printed to stdout if none
+    /// is given.
+    pub fn text(&self, format: Option<output::Format>) -> String {
+        match format {
+            Some(output::Format::RawJson) => self.raw.to_string(),
+            Some(format) => format.render(self.items, self.smoothed, self.status, self.joining),
+            None => self.line.to_string(),
+        }
+    }
+}
+
+/// An entry of `[[outputs]]` in the config file.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum OutputConfig {
+    /// A file, replaced with each output.
+    File { path: PathBuf, format: Option<output::Format> },
+    /// A shell command, run for each output with it on stdin.
+    Exec {
+        command: String,
+        format: Option<output::Format>,
+        #[serde(flatten)]
+        quiet: QuietConfig,
+    },
+    /// A desktop notification.
+    Notify {
+        format: Option<output::Format>,
+        #[serde(flatten)]
+        quiet: QuietConfig,
+    },
+}
+
+/// Quiet hours of an output, overriding the top-level settings.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct QuietConfig {
+    pub quiet_hours: Option<Vec<quiet::Window>>,
+    pub deliver_after_quiet: Option<bool>,
+}
+
+impl OutputConfig {
+    /// Returns the sink, holding back outputs during its quiet hours, or
+    /// else those of `defaults`, if it is one that gets someone's attention.
+    pub fn sink(&self, defaults: &QuietConfig) -> Box<dyn OutputSink> {
+        let (sink, quiet): (Box<dyn OutputSink>, _) = match self {
+            OutputConfig::File { path, format } => {
+                return Box::new(FileSink {
+                    path: path.clone(),
+                    format: *format,
+                })
+            }
+            OutputConfig::Exec { command, format, quiet } => (
+                Box::new(ExecSink {
+                    command: command.clone(),
+                    format: *format,
+                }),
+                quiet,
+            ),
+            OutputConfig::Notify { format, quiet } => (Box::new(NotifySink { format: *format }), quiet),
+        };
+        let windows = quiet.quiet_hours.as_ref().or(defaults.quiet_hours.as_ref());
+        match windows.filter(|windows| !windows.is_empty()) {
+            Some(windows) => {
+                let deliver_after = quiet.deliver_after_quiet.or(defaults.deliver_after_quiet).unwrap_or(false);
+                Box::new(quiet::Quiet::new(sink, windows.clone(), deliver_after))
+            }
+            None => sink,
+        }
+    }
+}
+
+/// Somewhere rendered output can be delivered to.
+pub trait OutputSink: Send {
+    /// Short description for messages, e.g. "file /tmp/wx".
+    fn name(&self) -> String;
+    fn deliver(&mut self, rendered: &Rendered) -> Result<()>;
+}
+
+/// Writes each output to a file, replacing its contents atomically.
+pub struct FileSink {
+    pub path: PathBuf,
+    /// Format of the output, if not that of stdout.
+    pub format: Option<output::Format>,
+}
+
+impl OutputSink for FileSink {
+    fn name(&self) -> String {
+        format!("file {}", self.path.display())
+    }
+
+    fn deliver(&mut self, rendered: &Rendered) -> Result<()> {
+        replace(&self.path, &format!("{}\n", rendered.text(self.format)))
+    }
+}
+
+/// Runs a shell command for each output, with the output on its stdin.
+pub struct ExecSink {
+    pub command: String,
+    pub format: Option<output::Format>,
+}
+
+impl OutputSink for ExecSink {
+    fn name(&self) -> String {
+        format!("command '{}'", self.command)
+    }
+
+    fn deliver(&mut self, rendered: &Rendered) -> Result<()> {
+        exec(&self.command, &rendered.text(self.format))
+    }
+}
+
+/// Shows each output as a desktop notification.
+pub struct NotifySink {
+    pub format: Option<output::Format>,
+}
+
+impl OutputSink for NotifySink {
+    fn name(&self) -> String {
+        "notification".to_string()
+    }
+
+    fn deliver(&mut self, rendered: &Rendered) -> Result<()> {
+        notify(&rendered.text(self.format))
+    }
+}
+
+/// Writes the structured data of each fetch to a file, replacing its contents
+/// atomically, for other programs to read while stdout has the text. The
+/// file stays when tinywx exits; readers can tell stale data by its
+/// "fetched_at" time.
+pub struct JsonSidecar {
+    pub path: PathBuf,
+}
+
+impl OutputSink for JsonSidecar {
+    fn name(&self) -> String {
+        format!("JSON sidecar {}", self.path.display())
+    }
+
+    fn deliver(&mut self, rendered: &Rendered) -> Result<()> {
+        replace(&self.path, &format!("{}\n", rendered.json))
+    }
+}
+
+/// Writes the metrics of each fetch to a file, replacing its contents
+/// atomically, for node_exporter's textfile collector. As with the sidecar,
+/// the file stays when tinywx exits, and its last success time tells whether
+/// it is stale.
+pub struct Textfile {
+    pub path: PathBuf,
+}
+
+impl OutputSink for Textfile {
+    fn name(&self) -> String {
+        format!("textfile {}", self.path.display())
+    }
+
+    fn deliver(&mut self, rendered: &Rendered) -> Result<()> {
+        replace(&self.path, rendered.metrics)
+    }
+}
+
+/// Runs `command` with `sh -c`, with `input` on its stdin, and waits for it.
+pub fn exec(command: &str, input: &str) -> Result<()> {
+    let mut child = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("cannot run '{}': {}", command, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        writeln!(stdin, "{}", input)?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("'{}' failed ({})", command, status);
+    }
+    Ok(())
+}
+
+/// Shows `body` as a desktop notification, with `notify-send`.
+pub fn notify(body: &str) -> Result<()> {
+    let status = Command::new("notify-send")
+        .args(["tinywx", body])
+        .status()
+        .map_err(|e| anyhow!("cannot run notify-send: {}", e))?;
+    if !status.success() {
+        bail!("notify-send failed ({})", status);
+    }
+    Ok(())
+}
+
+/// Replaces the contents of `path` atomically, by writing a file next to it
+/// and moving that over it.
+fn replace(path: &Path, contents: &str) -> Result<()> {
+    let mut tmp = path.to_path_buf().into_os_string();
+    tmp.push(".tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path).map_err(|e| anyhow!("cannot replace it: {}", e))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    Active,
+    /// Failed `failures` times in a row, next tried at `retry_at`.
+    Degraded { failures: u32, retry_at: Instant },
+    /// Failed and not retried anymore.
+    Disabled,
+}
+
+/// What happened to a line handed to a sink.
+#[derive(Debug)]
+pub enum Delivery {
+    Delivered,
+    /// Delivered after earlier failures.
+    Recovered,
+    /// Not attempted, because the sink is degraded or disabled.
+    Skipped,
+    Failed(anyhow::Error),
+}
+
+/// The state of a sink and its retries.
+#[derive(Clone, Copy, Debug)]
+pub struct Health {
+    pub state: State,
+    /// Whether a failed sink is retried later, or disabled.
+    retry: bool,
+}
+
+impl Health {
+    pub fn new(retry: bool) -> Self {
+        Health { state: State::Active, retry }
+    }
+
+    /// Returns whether delivery should be attempted at `now`.
+    pub fn should_try(&self, now: Instant) -> bool {
+        match self.state {
+            State::Active => true,
+            State::Degraded { retry_at, .. } => now >= retry_at,
+            State::Disabled => false,
+        }
+    }
+
+    /// Records the outcome of an attempt at `now`.
+    pub fn record(&mut self, ok: bool, now: Instant) {
+        self.state = match (self.state, ok) {
+            (_, true) => State::Active,
+            (_, false) if !self.retry => State::Disabled,
+            (State::Degraded { failures, .. }, false) => State::Degraded {
+                failures: failures + 1,
+                retry_at: now + backoff(failures + 1),
+            },
+            (_, false) => State::Degraded { failures: 1, retry_at: now + backoff(1) },
+        };
+    }
+}
+
+/// Returns the delay before retrying after `failures` failures in a row.
+fn backoff(failures: u32) -> Duration {
+    FIRST_RETRY
+        .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+        .min(MAX_RETRY)
+}
+
+/// A sink, and its health.
+pub struct Guarded {
+    sink: Box<dyn OutputSink>,
+    health: Health,
+}
+
+impl Guarded {
+    pub fn new(sink: Box<dyn OutputSink>, retry: bool) -> Self {
+        Guarded { sink, health: Health::new(retry) }
+    }
+
+    /// Delivers `rendered` at `now`, unless the sink is waiting for a retry or
+    /// disabled.
+    pub fn deliver(&mut self, rendered: &Rendered, now: Instant) -> Delivery {
+        if !self.health.should_try(now) {
+            return Delivery::Skipped;
+        }
+        let was_active = self.health.state == State::Active;
+        let result = self.sink.deliver(rendered);
+        self.health.record(result.is_ok(), now);
+        match result {
+            Ok(()) if was_active => Delivery::Delivered,
+            Ok(()) => Delivery::Recovered,
+            Err(e) => Delivery::Failed(e),
+        }
+    }
+}
+
+/// Delivers `rendered` to all sinks at once, each in a thread of its own so
+/// that a slow command does not hold up the others, and reports failures and
+/// recoveries on stderr.
+pub fn deliver_all(sinks: &mut [Guarded], rendered: &Rendered) {
+    let now = Instant::now();
+    let deliveries: Vec<Delivery> = thread::scope(|scope| {
+        let handles: Vec<_> = sinks
+            .iter_mut()
+            .map(|guarded| scope.spawn(move || guarded.deliver(rendered, now)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|_| Delivery::Failed(anyhow!("panicked"))))
+            .collect()
+    });
+    for (guarded, delivery) in sinks.iter().zip(deliveries) {
+        match delivery {
+            Delivery::Delivered | Delivery::Skipped => {}
+            Delivery::Recovered => eprintln!("{} works again", guarded.sink.name()),
+            Delivery::Failed(e) => {
+                let next = match guarded.health.state {
+                    State::Degraded { retry_at, .. } => {
+                        format!("retrying in {} seconds", (retry_at - now).as_secs())
+                    }
+                    _ => "disabled".to_string(),
+                };
+                crate::exit::warning!("{} failed: {}; {}", guarded.sink.name(), e, next);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::output::{Joining, Status};
+
+    /// What a [`Recorder`] got, and whether it fails.
+    #[derive(Default)]
+    struct Delivered {
+        lines: Mutex<Vec<String>>,
+        failing: AtomicBool,
+    }
+
+    /// A sink that records what it gets in its format, the line if none, and
+    /// fails while `failing` is set.
+    struct Recorder(Arc<Delivered>, Option<output::Format>);
+
+    impl OutputSink for Recorder {
+        fn name(&self) -> String {
+            "recorder".to_string()
+        }
+
+        fn deliver(&mut self, rendered: &Rendered) -> Result<()> {
+            if self.0.failing.load(Ordering::SeqCst) {
+                bail!("failing");
+            }
+            self.0.lines.lock().unwrap().push(rendered.text(self.1));
+            Ok(())
+        }
+    }
+
+    /// Returns a guarded [`Recorder`], and what it got.
+    fn recorder(retry: bool, failing: bool) -> (Guarded, Arc<Delivered>) {
+        let delivered = Arc::new(Delivered::default());
+        delivered.failing.store(failing, Ordering::SeqCst);
+        (Guarded::new(Box::new(Recorder(Arc::clone(&delivered), None)), retry), delivered)
+    }
+
+    /// Calls `f` with `line` rendered.
+    fn rendered<T>(line: &str, f: impl FnOnce(&Rendered) -> T) -> T {
+        let (status, joining) = (Status::default(), Joining::default());
+        f(&Rendered {
+            line,
+            json: "{}",
+            metrics: "",
+            items: &[],
+            smoothed: &[],
+            status: &status,
+            joining: &joining,
+            raw: "",
+        })
+    }
+
+    #[test]
+    fn retries_back_off_up_to_half_an_hour() {
+        let delays: Vec<u64> = (1..=7).map(|failures| backoff(failures).as_secs()).collect();
+        assert_eq!(delays, [60, 120, 240, 480, 960, 1800, 1800]);
+        assert_eq!(backoff(u32::MAX), MAX_RETRY);
+    }
+
+    #[test]
+    fn a_failing_sink_degrades_and_recovers() {
+        let start = Instant::now();
+        let mut health = Health::new(true);
+        assert!(health.should_try(start));
+        health.record(false, start);
+        assert_eq!(health.state, State::Degraded { failures: 1, retry_at: start + FIRST_RETRY });
+        assert!(!health.should_try(start + FIRST_RETRY - Duration::from_secs(1)));
+        assert!(health.should_try(start + FIRST_RETRY));
+        let later = start + FIRST_RETRY;
+        health.record(false, later);
+        assert_eq!(health.state, State::Degraded { failures: 2, retry_at: later + 2 * FIRST_RETRY });
+        health.record(true, later + 2 * FIRST_RETRY);
+        assert_eq!(health.state, State::Active);
+    }
+
+    #[test]
+    fn a_failing_sink_without_retries_is_disabled() {
+        let now = Instant::now();
+        let mut health = Health::new(false);
+        health.record(false, now);
+        assert_eq!(health.state, State::Disabled);
+        assert!(!health.should_try(now + MAX_RETRY * 10));
+    }
+
+    #[test]
+    fn deliveries_of_a_guarded_sink() {
+        let start = Instant::now();
+        let (mut guarded, delivered) = recorder(true, false);
+        let deliver = |guarded: &mut Guarded, line, at| rendered(line, |r| guarded.deliver(r, at));
+
+        assert!(matches!(deliver(&mut guarded, "1", start), Delivery::Delivered));
+        delivered.failing.store(true, Ordering::SeqCst);
+        let failed = deliver(&mut guarded, "2", start);
+        assert!(matches!(&failed, Delivery::Failed(e) if e.to_string() == "failing"), "{:?}", failed);
+        assert!(matches!(deliver(&mut guarded, "3", start + Duration::from_secs(59)), Delivery::Skipped));
+        delivered.failing.store(false, Ordering::SeqCst);
+        assert!(matches!(deliver(&mut guarded, "4", start + FIRST_RETRY), Delivery::Recovered));
+        assert!(matches!(deliver(&mut guarded, "5", start + FIRST_RETRY), Delivery::Delivered));
+        assert_eq!(*delivered.lines.lock().unwrap(), ["1", "4", "5"]);
+    }
+
+    #[test]
+    fn a_failing_sink_does_not_hold_up_the_others() {
+        let (failing, _) = recorder(false, true);
+        let (working, delivered) = recorder(false, false);
+        let mut sinks = [failing, working];
+        rendered("12°", |r| deliver_all(&mut sinks, r));
+        rendered("13°", |r| deliver_all(&mut sinks, r));
+        assert_eq!(sinks[0].health.state, State::Disabled);
+        assert_eq!(sinks[1].health.state, State::Active);
+        assert_eq!(*delivered.lines.lock().unwrap(), ["12°", "13°"]);
+    }
+
+    #[test]
+    fn each_sink_gets_its_format_once_a_fetch() {
+        let formats = [None, Some(output::Format::Json), Some(output::Format::Waybar)];
+        let recorded: Vec<Arc<Delivered>> = formats.iter().map(|_| Arc::default()).collect();
+        let mut sinks: Vec<Guarded> = formats
+            .iter()
+            .zip(&recorded)
+            .map(|(format, delivered)| Guarded::new(Box::new(Recorder(Arc::clone(delivered), *format)), true))
+            .collect();
+        let (status, joining) = (Status::default(), Joining::default());
+        for temp in ["12°", "13°"] {
+            let items = [(DataItem::Temp, temp.to_string()), (DataItem::Humidity, "81%".to_string())];
+            let line = format!("{} 81%", temp);
+            let rendered = Rendered {
+                line: &line,
+                json: "{}",
+                metrics: "",
+                items: &items,
+                smoothed: &[],
+                status: &status,
+                joining: &joining,
+                raw: "",
+            };
+            deliver_all(&mut sinks, &rendered);
+        }
+        let lines = |i: usize| recorded[i].lines.lock().unwrap().clone();
+        assert_eq!(lines(0), ["12° 81%", "13° 81%"]);
+        assert_eq!(lines(1), ["{\"humidity\":\"81%\",\"temp\":\"12°\"}", "{\"humidity\":\"81%\",\"temp\":\"13°\"}"]);
+        let texts: Vec<String> = lines(2)
+            .iter()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["text"].to_string())
+            .collect();
+        assert_eq!(texts, ["\"12° 81%\"", "\"13° 81%\""]);
+    }
+
+    #[test]
+    fn files_are_replaced_or_fail() {
+        let dir = std::env::temp_dir().join(format!("tinywx-sink-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("line");
+        let mut file = FileSink { path: path.clone(), format: None };
+        rendered("12°", |r| file.deliver(r)).unwrap();
+        rendered("13°", |r| file.deliver(r)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "13°\n");
+        let mut sidecar = JsonSidecar { path: dir.join("wx.json") };
+        rendered("13°", |r| sidecar.deliver(r)).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("wx.json")).unwrap(), "{}\n");
+
+        let mut missing = FileSink { path: dir.join("no/such/dir/line"), format: None };
+        assert!(rendered("12°", |r| missing.deliver(r)).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn outputs_of_the_config_file() {
+        #[derive(Deserialize)]
+        struct File {
+            outputs: Vec<OutputConfig>,
+        }
+        let file: File = toml::from_str(
+            "[[outputs]]\ntype = \"file\"\npath = \"/tmp/wx\"\nformat = \"json\"\n\n\
+             [[outputs]]\ntype = \"exec\"\ncommand = \"cat\"\nquiet_hours = [\"22:00-07:00\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            file.outputs[0],
+            OutputConfig::File { path: PathBuf::from("/tmp/wx"), format: Some(output::Format::Json) }
+        );
+        let OutputConfig::Exec { command, quiet, .. } = &file.outputs[1] else {
+            panic!("{:?}", file.outputs[1]);
+        };
+        assert_eq!((command.as_str(), quiet.quiet_hours.as_ref().map(Vec::len)), ("cat", Some(1)));
+        assert_eq!(file.outputs[0].sink(&QuietConfig::default()).name(), "file /tmp/wx");
+        assert_eq!(file.outputs[1].sink(&QuietConfig::default()).name(), "command 'cat'");
+    }
+}
@@ -0,0 +1,179 @@
+//! Forecast slots: the items of the current weather, rendered from the step
+//! of the forecast nearest to some time ahead instead, as with
+//! `--at-offset 6h` or `{temp@+6h}` in a template.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Error, Result};
+use serde::{Deserialize, Deserializer};
+use wx::DataItem;
+
+/// How far ahead the forecast can reach, in seconds: 5 days.
+const HORIZON: i64 = 5 * 86400;
+
+/// A time ahead, in seconds from now.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Offset(pub i64);
+
+impl FromStr for Offset {
+    type Err = Error;
+
+    /// Parses an offset as a number with a unit, as in "3h", "90m" or "1d",
+    /// optionally after a "+".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || anyhow!("invalid offset '{}': expected minutes, hours or days, as in 90m, 6h or 1d", s);
+        let text = s.trim().trim_start_matches('+');
+        let unit = text.chars().last().ok_or_else(invalid)?;
+        let number: i64 = text[..text.len() - unit.len_utf8()].parse().map_err(|_| invalid())?;
+        let seconds = match unit {
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            _ => return Err(invalid()),
+        };
+        let offset = number.checked_mul(seconds).filter(|&offset| offset >= 0).ok_or_else(invalid)?;
+        if offset > HORIZON {
+            bail!("offset '{}' is beyond the forecast, which reaches 5 days ahead", s.trim());
+        }
+        Ok(Offset(offset))
+    }
+}
+
+impl<'de> Deserialize<'de> for Offset {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Returns whether `item` renders from a forecast slot; the others always
+/// render from the current weather.
+pub fn from_forecast(item: DataItem) -> bool {
+    matches!(
+        item,
+        DataItem::Icon
+            | DataItem::Temp
+            | DataItem::FeelsLike
+            | DataItem::Description
+            | DataItem::Condition
+            | DataItem::Severity
+            | DataItem::Humidity
+            | DataItem::Pressure
+            | DataItem::Visibility
+            | DataItem::Clouds
+            | DataItem::TempMin
+            | DataItem::TempMax
+            | DataItem::DewPoint
+            | DataItem::HeatIndex
+            | DataItem::Rain
+            | DataItem::Snow
+            | DataItem::Wind
+            | DataItem::WindDeg
+            | DataItem::WindDir
+            | DataItem::WindDirLong
+            | DataItem::WindGust
+            | DataItem::Beaufort
+            | DataItem::WindDesc
+    )
+}
+
+/// Returns the slot of `forecast` at `offset` from `now`, or an error that
+/// tells how far ahead the forecast reaches.
+pub fn at(forecast: &wx::Forecast, now: i64, offset: Offset) -> Result<wx::CurrentWeather> {
+    forecast.slot(now.saturating_add(offset.0)).ok_or_else(|| match forecast.span() {
+        Some((_, last)) => anyhow!(
+            "no forecast {} hours ahead: it reaches {} hours ahead",
+            offset.0 / 3600,
+            last.saturating_sub(now).max(0) / 3600
+        ),
+        None => anyhow!("no forecast {} hours ahead: the forecast is empty", offset.0 / 3600),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use wx::format::UnitStyle;
+    use wx::{ParseMode, Units};
+
+    use super::*;
+
+    const CURRENT: &str = include_str!("../wx/tests/fixtures/current.json");
+    /// Time of the current weather fixture.
+    const NOW: i64 = 1_714_550_400;
+
+    /// Returns a forecast of a step for each of `temps`, 3 hours apart from
+    /// `NOW` on, with the readings of the current weather fixture but for the
+    /// temperature.
+    fn forecast(temps: &[f64]) -> wx::Forecast {
+        let current: Value = serde_json::from_str(CURRENT).unwrap();
+        let list: Vec<Value> = (0..)
+            .zip(temps)
+            .map(|(n, temp)| {
+                let mut main = current["main"].clone();
+                main["temp"] = json!(temp);
+                json!({
+                    "dt": NOW + n * 10800,
+                    "main": main,
+                    "weather": current["weather"],
+                    "clouds": current["clouds"],
+                    "visibility": current["visibility"],
+                    "wind": current["wind"],
+                    "rain": current["rain"],
+                })
+            })
+            .collect();
+        let city = json!({"id": current["id"], "name": current["name"], "coord": current["coord"],
+            "country": "NL", "timezone": current["timezone"]});
+        serde_json::from_value(json!({"cod": "200", "list": list, "city": city})).unwrap()
+    }
+
+    #[test]
+    fn offsets_in_minutes_hours_and_days() {
+        let offsets = [("90m", 5400), ("3h", 10800), ("+6h", 21600), (" 1d ", 86400), ("0h", 0), ("5d", 432_000)];
+        for (text, seconds) in offsets {
+            assert_eq!(text.parse::<Offset>().unwrap(), Offset(seconds), "{:?}", text);
+        }
+        for text in ["", "h", "6", "6s", "-3h", "6 h", "1.5h", "99999999999999999d"] {
+            let e = text.parse::<Offset>().unwrap_err();
+            assert!(e.to_string().starts_with(&format!("invalid offset '{}'", text)), "{:?}: {}", text, e);
+        }
+        let e = "121h".parse::<Offset>().unwrap_err();
+        assert_eq!(e.to_string(), "offset '121h' is beyond the forecast, which reaches 5 days ahead");
+    }
+
+    #[test]
+    fn the_nearest_step_is_taken() {
+        let forecast = forecast(&[10.0, 13.0, 16.0]);
+        let temp = |offset: i64| {
+            let slot = at(&forecast, NOW, Offset(offset)).unwrap();
+            slot.render(DataItem::Temp, Units::Metric, UnitStyle::default()).unwrap()
+        };
+        assert_eq!(temp(0), "10°");
+        assert_eq!(temp(5399), "10°");
+        assert_eq!(temp(5401), "13°");
+        assert_eq!(temp(6 * 3600), "16°");
+        // Up to half a step after the last.
+        assert_eq!(temp(6 * 3600 + 5400), "16°");
+    }
+
+    #[test]
+    fn beyond_the_forecast_tells_how_far_it_reaches() {
+        let e = at(&forecast(&[10.0, 13.0, 16.0]), NOW, Offset(9 * 3600)).unwrap_err();
+        assert_eq!(e.to_string(), "no forecast 9 hours ahead: it reaches 6 hours ahead");
+        let e = at(&forecast(&[]), NOW, Offset(3600)).unwrap_err();
+        assert_eq!(e.to_string(), "no forecast 1 hours ahead: the forecast is empty");
+    }
+
+    #[test]
+    fn slots_render_every_item_as_the_current_weather_does() {
+        let current = wx::CurrentWeather::from_json(CURRENT, ParseMode::Strict).unwrap();
+        let slot = at(&forecast(&[12.35]), NOW, Offset(0)).unwrap();
+        for info in wx::item::ITEMS.iter().filter(|info| from_forecast(info.item)) {
+            for units in Units::ALL {
+                let render = |weather: &wx::CurrentWeather| weather.render(info.item, units, UnitStyle::default());
+                assert_eq!(render(&slot), render(&current), "{} in {:?}", info.name, units);
+            }
+        }
+    }
+}
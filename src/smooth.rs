@@ -0,0 +1,120 @@
+//! Smoothing of jittery readings in watch mode, so that a station alternating
+//! between two values does not make the bar flicker.
+
+use std::collections::HashMap;
+
+use wx::DataItem;
+
+/// Items that are smoothed.
+pub const ITEMS: [DataItem; 3] = [DataItem::Temp, DataItem::FeelsLike, DataItem::Wind];
+
+/// An exponential moving average.
+#[derive(Clone, Copy, Debug)]
+pub struct Ema {
+    /// Weight of each new value, from 0 (exclusive) to 1 (no smoothing).
+    alpha: f64,
+    average: Option<f64>,
+}
+
+impl Ema {
+    pub fn new(alpha: f64) -> Self {
+        Ema { alpha, average: None }
+    }
+
+    /// Adds `value`, and returns the new average. The first value is taken
+    /// as is.
+    pub fn update(&mut self, value: f64) -> f64 {
+        let average = match self.average {
+            Some(average) => average + self.alpha * (value - average),
+            None => value,
+        };
+        self.average = Some(average);
+        average
+    }
+}
+
+/// The averages of the smoothed items, for one location at a time.
+#[derive(Debug)]
+pub struct Smoother {
+    alpha: f64,
+    /// The location the averages are for.
+    key: String,
+    averages: HashMap<DataItem, Ema>,
+}
+
+impl Smoother {
+    pub fn new(alpha: f64) -> Self {
+        Smoother {
+            alpha,
+            key: String::new(),
+            averages: HashMap::new(),
+        }
+    }
+
+    /// Adds a reading of `item` at the location `key`, and returns the
+    /// smoothed value. The averages start over when the location changes.
+    pub fn update(&mut self, key: &str, item: DataItem, value: f64) -> f64 {
+        if key != self.key {
+            self.key = key.to_string();
+            self.averages.clear();
+        }
+        let alpha = self.alpha;
+        self.averages.entry(item).or_insert_with(|| Ema::new(alpha)).update(value)
+    }
+}
+
+/// Returns whether `alpha` is a valid weight for [`Ema::new`].
+pub fn valid_alpha(alpha: f64) -> bool {
+    alpha > 0.0 && alpha <= 1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the averages of `values`, rounded to two decimals.
+    fn averages(alpha: f64, values: &[f64]) -> Vec<f64> {
+        let mut ema = Ema::new(alpha);
+        values.iter().map(|&value| (ema.update(value) * 100.0).round() / 100.0).collect()
+    }
+
+    #[test]
+    fn the_average_starts_at_the_first_value() {
+        assert_eq!(averages(0.5, &[12.0, 14.0, 14.0, 10.0]), [12.0, 13.0, 13.5, 11.75]);
+        assert_eq!(averages(0.25, &[-3.0]), [-3.0]);
+    }
+
+    #[test]
+    fn jitter_is_smoothed_away() {
+        // A station alternating between 12 and 13 stays within a tenth or so
+        // of 12.5 once the average settles.
+        let jitter: Vec<f64> = (0..40).map(|n| if n % 2 == 0 { 12.0 } else { 13.0 }).collect();
+        let smoothed = averages(0.2, &jitter);
+        assert!(smoothed[20..].iter().all(|&t| (12.38..=12.62).contains(&t)), "{:?}", smoothed);
+    }
+
+    #[test]
+    fn an_alpha_of_1_is_no_smoothing() {
+        assert_eq!(averages(1.0, &[12.0, 14.0, 9.0]), [12.0, 14.0, 9.0]);
+    }
+
+    #[test]
+    fn items_are_averaged_apart_and_start_over_at_another_location() {
+        let mut smoother = Smoother::new(0.5);
+        assert_eq!(smoother.update("amsterdam", DataItem::Temp, 12.0), 12.0);
+        assert_eq!(smoother.update("amsterdam", DataItem::Wind, 4.0), 4.0);
+        assert_eq!(smoother.update("amsterdam", DataItem::Temp, 14.0), 13.0);
+        assert_eq!(smoother.update("amsterdam", DataItem::Wind, 6.0), 5.0);
+        assert_eq!(smoother.update("paris", DataItem::Temp, 20.0), 20.0);
+        assert_eq!(smoother.update("paris", DataItem::Temp, 22.0), 21.0);
+        // Going back starts over too.
+        assert_eq!(smoother.update("amsterdam", DataItem::Temp, 10.0), 10.0);
+        assert_eq!(smoother.update("amsterdam", DataItem::Wind, 2.0), 2.0);
+    }
+
+    #[test]
+    fn alphas_from_just_above_0_to_1() {
+        assert!(valid_alpha(1.0) && valid_alpha(0.5) && valid_alpha(f64::MIN_POSITIVE));
+        assert!(!valid_alpha(0.0) && !valid_alpha(-0.5) && !valid_alpha(1.01) && !valid_alpha(f64::NAN));
+    }
+}
@@ -0,0 +1,468 @@
+//! Templates: the output as text with items in braces, like
+//! "{icon} {temp|round:1}", whose values can be passed through a fixed set of
+//! transforms.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Error, Result};
+use serde::{Deserialize, Deserializer};
+use wx::DataItem;
+
+use crate::slot::{self, Offset};
+
+/// A transform of a value, applied with `{item|transform}`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Transform {
+    Upper,
+    Lower,
+    Title,
+    Abs,
+    /// Number of decimals.
+    Round(usize),
+    Compass,
+    /// strftime-like format.
+    Time(String),
+    /// Width in characters.
+    Pad(usize),
+}
+
+/// What `--list-transforms` tells about a transform.
+pub struct TransformInfo {
+    /// How the transform is written, with its argument.
+    pub usage: &'static str,
+    pub description: &'static str,
+}
+
+/// All transforms, in the order they are listed.
+pub const TRANSFORMS: &[TransformInfo] = &[
+    TransformInfo {
+        usage: "upper",
+        description: "The value in upper case",
+    },
+    TransformInfo {
+        usage: "lower",
+        description: "The value in lower case",
+    },
+    TransformInfo {
+        usage: "title",
+        description: "The value with each word capitalized",
+    },
+    TransformInfo {
+        usage: "abs",
+        description: "The number without its sign, as in {temp|abs}",
+    },
+    TransformInfo {
+        usage: "round:N",
+        description: "The number rounded to N decimals",
+    },
+    TransformInfo {
+        usage: "compass",
+        description: "The compass point of a number of degrees, as in {wind_deg|compass}",
+    },
+    TransformInfo {
+        usage: "time:FMT",
+        description: "The local time of unix seconds in strftime format FMT, as in {dt|time:%H:%M}",
+    },
+    TransformInfo {
+        usage: "pad:N",
+        description: "The value padded with spaces to N characters",
+    },
+];
+
+impl Transform {
+    /// Applies the transform to `value`. Transforms of numbers change the
+    /// number the value starts with and keep its unit, and leave values
+    /// without one as they are. Local times are at `offset` seconds from UTC.
+    pub fn apply(&self, value: &str, offset: i64) -> String {
+        match self {
+            Transform::Upper => value.to_uppercase(),
+            Transform::Lower => value.to_lowercase(),
+            Transform::Title => title(value),
+            Transform::Abs => match number(value) {
+                Some((text, _)) => value.replacen(text, text.trim_start_matches('-'), 1),
+                None => value.to_string(),
+            },
+            Transform::Round(decimals) => match number(value) {
+                Some((text, n)) => value.replacen(text, &wx::format::fixed(n, *decimals), 1),
+                None => value.to_string(),
+            },
+            Transform::Compass => match number(value) {
+                Some((_, degrees)) => wx::geo::compass_point(degrees).to_string(),
+                None => value.to_string(),
+            },
+            Transform::Time(format) => match value.trim().parse::<i64>() {
+                Ok(epoch) => wx::format::local_time(epoch, offset, format).unwrap_or_default(),
+                Err(_) => value.to_string(),
+            },
+            Transform::Pad(width) => format!("{:<width$}", value, width = *width),
+        }
+    }
+}
+
+impl FromStr for Transform {
+    type Err = Error;
+
+    /// Parses a transform as written in a template, e.g. "round:1".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, arg) = match s.split_once(':') {
+            Some((name, arg)) => (name.trim(), Some(arg)),
+            None => (s.trim(), None),
+        };
+        let transform = match (name, arg) {
+            ("upper", None) => Transform::Upper,
+            ("lower", None) => Transform::Lower,
+            ("title", None) => Transform::Title,
+            ("abs", None) => Transform::Abs,
+            ("compass", None) => Transform::Compass,
+            ("upper" | "lower" | "title" | "abs" | "compass", Some(_)) => {
+                bail!("transform '{}' takes no argument", name)
+            }
+            ("round", arg) => Transform::Round(
+                arg.and_then(|n| n.trim().parse().ok())
+                    .ok_or_else(|| anyhow!("transform 'round' needs a number of decimals, as in round:1"))?,
+            ),
+            ("pad", arg) => Transform::Pad(
+                arg.and_then(|n| n.trim().parse().ok())
+                    .ok_or_else(|| anyhow!("transform 'pad' needs a width, as in pad:8"))?,
+            ),
+            ("time", None | Some("")) => bail!("transform 'time' needs a format, as in time:%H:%M"),
+            ("time", Some(format)) if !wx::format::valid_time_format(format) => {
+                bail!("invalid time format '{}'", format)
+            }
+            ("time", Some(format)) => Transform::Time(format.to_string()),
+            _ => bail!("unknown transform '{}' (see --list-transforms)", name),
+        };
+        Ok(transform)
+    }
+}
+
+/// A piece of a template.
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Text(String),
+    Field {
+        item: DataItem,
+        /// The item as named in the template.
+        name: String,
+        /// The forecast slot to render the item from, as in "{temp@+6h}".
+        at: Option<Offset>,
+        transforms: Vec<Transform>,
+    },
+}
+
+/// A parsed template. Braces are written doubled, as "{{" and "}}".
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    /// Returns the items in the template, in the order they first appear.
+    pub fn items(&self) -> Vec<DataItem> {
+        let mut items = Vec::new();
+        for segment in &self.segments {
+            if let Segment::Field { item, .. } = segment {
+                if !items.contains(item) {
+                    items.push(*item);
+                }
+            }
+        }
+        items
+    }
+
+    /// Returns the forecast slots the template renders items from, in the
+    /// order they first appear.
+    pub fn offsets(&self) -> Vec<Offset> {
+        let mut offsets = Vec::new();
+        for segment in &self.segments {
+            if let Segment::Field { at: Some(at), .. } = segment {
+                if !offsets.contains(at) {
+                    offsets.push(*at);
+                }
+            }
+        }
+        offsets
+    }
+
+    /// Returns the item names as written in the template.
+    pub fn names(&self) -> Vec<&str> {
+        self.segments
+            .iter()
+            .filter_map(|segment| match segment {
+                Segment::Field { name, .. } => Some(name.as_str()),
+                Segment::Text(_) => None,
+            })
+            .collect()
+    }
+
+    /// Renders the template with the rendered `values` of its items, those
+    /// of its forecast slots in `slots`, and local times at `offset` seconds
+    /// from UTC.
+    pub fn render(
+        &self,
+        values: &[(DataItem, String)],
+        slots: &[(Offset, Vec<(DataItem, String)>)],
+        offset: i64,
+    ) -> String {
+        let mut line = String::with_capacity(self.capacity(values));
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => line.push_str(text),
+                Segment::Field { item, at, transforms, .. } => {
+                    let values = match at {
+                        Some(at) => slots.iter().find(|(o, _)| o == at).map_or(&[][..], |(_, values)| values),
+                        None => values,
+                    };
+                    let value = values.iter().find(|(i, _)| i == item).map_or("", |(_, value)| value.as_str());
+                    if transforms.is_empty() {
+                        // Writing to a String cannot fail.
+                        let _ = wx::format::write_single_line(&mut line, &[value], "");
+                    } else {
+                        let value = wx::format::single_line(&[value], "");
+                        line.push_str(&transforms.iter().fold(value, |value, t| t.apply(&value, offset)));
+                    }
+                }
+            }
+        }
+        line
+    }
+
+    /// Returns about how long the rendered template is, to allocate it once.
+    fn capacity(&self, values: &[(DataItem, String)]) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.len(),
+                Segment::Field { item, .. } => values.iter().find(|(i, _)| i == item).map_or(0, |(_, v)| v.len()),
+            })
+            .sum()
+    }
+}
+
+impl FromStr for Template {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut rest = s;
+        while let Some(c) = rest.chars().next() {
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                text.push(c);
+                rest = &rest[2..];
+                continue;
+            }
+            if c == '}' {
+                bail!("unmatched '}}' in template; write '}}}}' for a brace");
+            }
+            if c != '{' {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+            let end = rest
+                .find('}')
+                .ok_or_else(|| anyhow!("unclosed '{{' in template; write '{{{{' for a brace"))?;
+            let field = &rest[1..end];
+            let mut parts = field.split('|');
+            let head = parts.next().unwrap_or_default();
+            let (name, at) = match head.split_once('@') {
+                Some((name, at)) => (name.trim(), Some(at)),
+                None => (head.trim(), None),
+            };
+            if name.is_empty() {
+                bail!("in template field '{{{}}}': no item", field);
+            }
+            let at = at
+                .map(|at| {
+                    let at: Offset = at.parse()?;
+                    match name.parse::<DataItem>() {
+                        Ok(item) if !slot::from_forecast(item) => bail!("{} is not in the forecast", name),
+                        _ => Ok(at),
+                    }
+                })
+                .transpose()
+                .map_err(|e| anyhow!("in template field '{{{}}}': {}", field, e))?;
+            let transforms = parts.map(str::parse).collect::<Result<_>>();
+            let (item, transforms) = name
+                .parse()
+                .and_then(|item| Ok((item, transforms?)))
+                .map_err(|e| anyhow!("in template field '{{{}}}': {}", field, e))?;
+            if !text.is_empty() {
+                segments.push(Segment::Text(std::mem::take(&mut text)));
+            }
+            segments.push(Segment::Field {
+                item,
+                name: name.to_string(),
+                at,
+                transforms,
+            });
+            rest = &rest[end + 1..];
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(Template { segments })
+    }
+}
+
+impl<'de> Deserialize<'de> for Template {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Returns the number `value` starts with, as written and as a number.
+fn number(value: &str) -> Option<(&str, f64)> {
+    let digits = value
+        .char_indices()
+        .find(|&(i, c)| !(c.is_ascii_digit() || c == '.' || (c == '-' && i == 0)))
+        .map_or(value.len(), |(i, _)| i);
+    let text = &value[..digits];
+    Some((text, text.parse().ok()?))
+}
+
+/// Capitalizes the first letter of each word.
+fn title(value: &str) -> String {
+    let mut titled = String::with_capacity(value.len());
+    let mut start = true;
+    for c in value.chars() {
+        if start {
+            titled.extend(c.to_uppercase());
+        } else {
+            titled.push(c);
+        }
+        start = c.is_whitespace();
+    }
+    titled
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(transform: &str, value: &str) -> String {
+        transform.parse::<Transform>().unwrap().apply(value, 7200)
+    }
+
+    fn error(transform: &str) -> String {
+        transform.parse::<Transform>().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn cases() {
+        assert_eq!(apply("upper", "light rain"), "LIGHT RAIN");
+        assert_eq!(apply("lower", "Light Rain"), "light rain");
+        assert_eq!(apply("title", "light  rain, wind"), "Light  Rain, Wind");
+        assert_eq!(apply("title", ""), "");
+    }
+
+    #[test]
+    fn numbers_keep_their_unit() {
+        assert_eq!(apply("abs", "-3°C"), "3°C");
+        assert_eq!(apply("abs", "3°"), "3°");
+        assert_eq!(apply("abs", "calm"), "calm");
+        assert_eq!(apply("round:1", "12.35°"), "12.4°");
+        assert_eq!(apply("round:0", "-0.4 m/s"), "0 m/s");
+        assert_eq!(apply("round:2", "81%"), "81.00%");
+        assert_eq!(apply("round:1", "n/a"), "n/a");
+        // Only a leading minus is a sign.
+        assert_eq!(apply("abs", "12-3"), "12-3");
+    }
+
+    #[test]
+    fn compass_points_of_degrees() {
+        assert_eq!(apply("compass", "230"), "SW");
+        assert_eq!(apply("compass", "350°"), "N");
+        assert_eq!(apply("compass", "−"), "−");
+    }
+
+    #[test]
+    fn times_of_unix_seconds() {
+        // 1 May 2024, 10:00 UTC, two hours ahead.
+        assert_eq!(apply("time:%H:%M", "1714557600"), "12:00");
+        assert_eq!(apply("time:%a %d", " 1714557600 "), "Wed 01");
+        assert_eq!(apply("time:%H:%M", "06:05"), "06:05");
+    }
+
+    #[test]
+    fn padding_to_a_width() {
+        assert_eq!(apply("pad:6", "12°"), "12°   ");
+        assert_eq!(apply("pad:2", "light rain"), "light rain");
+    }
+
+    #[test]
+    fn bad_transforms_are_named() {
+        assert_eq!(error("shout"), "unknown transform 'shout' (see --list-transforms)");
+        assert_eq!(error("upper:2"), "transform 'upper' takes no argument");
+        assert_eq!(error("round"), "transform 'round' needs a number of decimals, as in round:1");
+        assert_eq!(error("round:x"), "transform 'round' needs a number of decimals, as in round:1");
+        assert_eq!(error("pad:-1"), "transform 'pad' needs a width, as in pad:8");
+        assert_eq!(error("time:"), "transform 'time' needs a format, as in time:%H:%M");
+        assert_eq!(error("time:%Q"), "invalid time format '%Q'");
+        assert_eq!(" round : 1".parse::<Transform>().unwrap(), Transform::Round(1));
+    }
+
+    #[test]
+    fn every_transform_is_listed() {
+        for info in TRANSFORMS {
+            let usage = info.usage.replace('N', "1").replace("FMT", "%H");
+            assert!(usage.parse::<Transform>().is_ok(), "{}", info.usage);
+        }
+        assert_eq!(TRANSFORMS.len(), 8);
+    }
+
+    fn values() -> Vec<(DataItem, String)> {
+        vec![
+            (DataItem::Temp, "-3°".to_string()),
+            (DataItem::Description, "light rain".to_string()),
+            (DataItem::WindDeg, "230°".to_string()),
+        ]
+    }
+
+    fn render(template: &str) -> String {
+        template.parse::<Template>().unwrap().render(&values(), &[], 7200)
+    }
+
+    #[test]
+    fn transforms_chain_left_to_right() {
+        assert_eq!(render("{description|upper|pad:12}|"), "LIGHT RAIN  |");
+        assert_eq!(render("{description|pad:12|upper}|"), "LIGHT RAIN  |");
+        assert_eq!(render("{temp|abs|round:1} {wind_deg|compass|lower}"), "3.0° sw");
+        assert_eq!(render("{{{temp}}} {{}}"), "{-3°} {}");
+        // Items with no value render empty, transformed or not.
+        assert_eq!(render("[{humidity}][{humidity|pad:3}]"), "[][   ]");
+    }
+
+    #[test]
+    fn templates_know_their_items() {
+        let template: Template = "{temp} {description} {temp|abs} {temp@+6h} {wind@6h}".parse().unwrap();
+        assert_eq!(template.items(), [DataItem::Temp, DataItem::Description, DataItem::Wind]);
+        assert_eq!(template.names(), ["temp", "description", "temp", "temp", "wind"]);
+        assert_eq!(template.offsets(), ["6h".parse::<Offset>().unwrap()]);
+    }
+
+    #[test]
+    fn slots_render_from_their_forecast_step() {
+        let template: Template = "{temp} then {temp@3h}".parse().unwrap();
+        let slots = [("3h".parse().unwrap(), vec![(DataItem::Temp, "1°".to_string())])];
+        assert_eq!(template.render(&values(), &slots, 0), "-3° then 1°");
+        assert_eq!(template.render(&values(), &[], 0), "-3° then ");
+    }
+
+    #[test]
+    fn bad_templates_are_named() {
+        let error = |template: &str| template.parse::<Template>().unwrap_err().to_string();
+        assert_eq!(error("{temp"), "unclosed '{' in template; write '{{' for a brace");
+        assert_eq!(error("temp}"), "unmatched '}' in template; write '}}' for a brace");
+        assert_eq!(error("{ |upper}"), "in template field '{ |upper}': no item");
+        assert_eq!(
+            error("{temp|shout}"),
+            "in template field '{temp|shout}': unknown transform 'shout' (see --list-transforms)"
+        );
+        assert!(error("{bogus}").starts_with("in template field '{bogus}': "), "{}", error("{bogus}"));
+        assert_eq!(error("{city@3h}"), "in template field '{city@3h}': city is not in the forecast");
+    }
+}
@@ -0,0 +1,152 @@
+//! What this build of tinywx is, for bug reports: `tinywx --version -v`,
+//! `tinywx doctor` and the JSON output with `--debug-meta` show it.
+//!
+//! The git fields come from `build.rs`, which sets them to "unknown" when
+//! tinywx is not built from a git checkout.
+
+use std::fmt;
+
+use serde_json::{json, Value};
+
+/// The TLS backend reqwest uses, from the features tinywx is built with: the
+/// platform's if it is enabled, as reqwest prefers it.
+const TLS: &str = if cfg!(feature = "native-tls") {
+    "native-tls"
+} else if cfg!(feature = "rustls") {
+    "rustls"
+} else {
+    "none"
+};
+
+/// The build of tinywx that is running.
+#[derive(Clone, Debug)]
+pub struct Info {
+    pub version: &'static str,
+    /// Short hash of the commit, or "unknown".
+    pub commit: &'static str,
+    /// Whether tracked files had changes: "true", "false" or "unknown".
+    pub dirty: &'static str,
+    /// The cargo features of tinywx that are enabled.
+    pub features: Vec<&'static str>,
+    pub target: &'static str,
+    /// The reqwest version and its TLS backend.
+    pub http: String,
+}
+
+/// Returns the build of tinywx that is running.
+pub fn info() -> Info {
+    let mut features = Vec::new();
+    if cfg!(feature = "onecall") {
+        features.push("onecall");
+    }
+    if cfg!(feature = "notify") {
+        features.push("notify");
+    }
+    if cfg!(feature = "offline-geo") {
+        features.push("offline-geo");
+    }
+    if cfg!(feature = "rustls") {
+        features.push("rustls");
+    }
+    Info {
+        version: env!("CARGO_PKG_VERSION"),
+        commit: env!("TINYWX_GIT_COMMIT"),
+        dirty: env!("TINYWX_GIT_DIRTY"),
+        features,
+        target: env!("TINYWX_TARGET"),
+        http: format!("reqwest {} ({})", env!("TINYWX_REQWEST_VERSION"), TLS),
+    }
+}
+
+impl Info {
+    /// Returns the commit, marked when the tree had changes, on one line.
+    pub fn commit(&self) -> String {
+        match self.dirty {
+            "true" => format!("{}-dirty", self.commit),
+            _ => self.commit.to_string(),
+        }
+    }
+
+    /// Returns the features, or "none".
+    pub fn features(&self) -> String {
+        if self.features.is_empty() {
+            "none".to_string()
+        } else {
+            self.features.join(", ")
+        }
+    }
+
+    /// Returns the fields as a JSON object.
+    pub fn json(&self) -> Value {
+        json!({
+            "version": self.version,
+            "commit": self.commit,
+            "dirty": self.dirty,
+            "features": self.features,
+            "target": self.target,
+            "http": self.http,
+        })
+    }
+}
+
+impl fmt::Display for Info {
+    /// Writes the fields on a line each, as `--version -v` prints them.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "tinywx {}", self.version)?;
+        writeln!(f, "commit:   {}", self.commit())?;
+        writeln!(f, "features: {}", self.features())?;
+        writeln!(f, "target:   {}", self.target)?;
+        write!(f, "http:     {}", self.http)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_field_is_known_or_says_it_is_not() {
+        let info = info();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        let hex = info.commit.len() == 12 && info.commit.chars().all(|c| c.is_ascii_hexdigit());
+        assert!(hex || info.commit == "unknown", "{:?}", info.commit);
+        assert!(["true", "false", "unknown"].contains(&info.dirty), "{:?}", info.dirty);
+        assert!(!info.target.is_empty(), "{:?}", info.target);
+        let reqwest = info.http.strip_prefix("reqwest ").and_then(|http| http.strip_suffix(&format!(" ({})", TLS)));
+        let semver = |v: &str| v.split('.').count() == 3 && v.split('.').all(|n| n.parse::<u32>().is_ok());
+        assert!(reqwest.is_some_and(|v| semver(v) || v == "unknown"), "{}", info.http);
+    }
+
+    /// A build from a tarball, without git.
+    fn unknown() -> Info {
+        Info {
+            version: "0.1.0",
+            commit: "unknown",
+            dirty: "unknown",
+            features: Vec::new(),
+            target: "x86_64-unknown-linux-gnu",
+            http: "reqwest 0.11.27 (rustls)".to_string(),
+        }
+    }
+
+    #[test]
+    fn a_build_without_git() {
+        let info = unknown();
+        assert_eq!(info.commit(), "unknown");
+        assert_eq!(info.features(), "none");
+        let expected = "tinywx 0.1.0\ncommit:   unknown\nfeatures: none\ntarget:   x86_64-unknown-linux-gnu\n\
+            http:     reqwest 0.11.27 (rustls)";
+        assert_eq!(info.to_string(), expected);
+        assert_eq!(info.json()["commit"], "unknown");
+        assert_eq!(info.json()["features"], json!([]));
+    }
+
+    #[test]
+    fn a_build_with_changes() {
+        let info = Info { commit: "0123456789ab", dirty: "true", features: vec!["onecall", "notify"], ..unknown() };
+        assert_eq!(info.commit(), "0123456789ab-dirty");
+        assert_eq!(info.features(), "onecall, notify");
+        assert_eq!(Info { dirty: "false", ..info.clone() }.commit(), "0123456789ab");
+        assert_eq!(info.json()["dirty"], "true");
+    }
+}
@@ -0,0 +1,580 @@
+//! Watch mode: keep running and print a fresh line on every refresh.
+//!
+//! Each fetch runs on a thread of its own, under a watchdog: a request that
+//! neither completes nor times out by the deadline, a little before the next
+//! fetch is due, is abandoned, and the tick shows what it would for a failed
+//! fetch. Hung requests are counted, in the cache directory, for
+//! `tinywx doctor` to report.
+//!
+//! A refresh that fails shows the last good line again, marked as stale,
+//! instead of an error. The client is built once, for all refreshes.
+//!
+//! Watch mode ends when what reads its output goes away, without an error,
+//! and on Ctrl-C, with [`INTERRUPTED`], but never halfway through a line.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+
+use crate::paths;
+
+/// Abandoned fetches whose threads may still be running, at most. While
+/// there are this many, no new fetch is started.
+const MAX_ABANDONED: usize = 4;
+
+/// Exit code on Ctrl-C: 128 plus SIGINT's number, as shells report a
+/// process the signal ended.
+pub const INTERRUPTED: i32 = 130;
+
+/// What the watch loop should do next.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Start fetching the weather in the background.
+    Fetch,
+    /// The display tick is due.
+    Tick,
+    /// Nothing to do until the given instant, or until a fetch completes.
+    Wait(Instant),
+}
+
+/// A line to print, as the schedule keeps it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Shown<'a> {
+    pub line: &'a str,
+    /// The line is the last good one, shown again as the refresh failed.
+    pub stale: bool,
+}
+
+impl<'a> Shown<'a> {
+    fn fresh(line: &'a str) -> Self {
+        Shown { line, stale: false }
+    }
+
+    fn stale(line: &'a str) -> Self {
+        Shown { line, stale: true }
+    }
+}
+
+/// Scheduling state of watch mode. It does no I/O and takes the current time
+/// as an argument, so it can be driven by any clock and any fetcher. Lines
+/// are copied into strings it keeps from one tick to the next, so that once
+/// they are long enough, a refresh allocates nothing here.
+#[derive(Debug)]
+pub struct Schedule {
+    interval: Duration,
+    prefetch: Duration,
+    next_tick: Instant,
+    /// A fetch for the upcoming tick has been started.
+    started: bool,
+    /// A fetch is in flight.
+    fetching: bool,
+    /// The in-flight fetch missed its tick; its result is printed on arrival.
+    late: bool,
+    /// The tick that the in-flight fetch missed printed the previous value.
+    shown: bool,
+    /// A completed prefetch is waiting for its tick, in `next`.
+    pending: bool,
+    next: String,
+    /// The last value printed.
+    last: Option<String>,
+}
+
+impl Schedule {
+    /// Creates a schedule whose first tick is at `start`. Fetches begin
+    /// `prefetch` before each tick.
+    pub fn new(interval: Duration, prefetch: Duration, start: Instant) -> Self {
+        Self {
+            interval,
+            prefetch,
+            next_tick: start,
+            started: false,
+            fetching: false,
+            late: false,
+            shown: false,
+            pending: false,
+            next: String::new(),
+            last: None,
+        }
+    }
+
+    /// Returns the next thing to do at `now`.
+    pub fn next_action(&self, now: Instant) -> Action {
+        let fetch_at = self
+            .next_tick
+            .checked_sub(self.prefetch)
+            .unwrap_or(self.next_tick);
+        let may_fetch = !self.started && !self.fetching;
+
+        if may_fetch && now >= fetch_at {
+            Action::Fetch
+        } else if now >= self.next_tick {
+            Action::Tick
+        } else if may_fetch {
+            Action::Wait(fetch_at)
+        } else {
+            Action::Wait(self.next_tick)
+        }
+    }
+
+    /// Records that a fetch has been started.
+    pub fn on_fetch_started(&mut self) {
+        self.started = true;
+        self.fetching = true;
+    }
+
+    /// Records the result of a fetch (`None` if it failed) and returns the
+    /// line to print, if any.
+    pub fn on_fetch_done(&mut self, value: Option<&str>) -> Option<Shown<'_>> {
+        self.fetching = false;
+
+        if !self.late {
+            self.pending = value.is_some();
+            if let Some(value) = value {
+                copy(&mut self.next, value);
+            }
+            return None;
+        }
+
+        self.late = false;
+        match value {
+            Some(value) if !self.shown || self.last.as_deref() != Some(value) => {
+                let last = self.last.get_or_insert_with(String::new);
+                copy(last, value);
+                Some(Shown::fresh(last))
+            }
+            Some(_) => None,
+            None => self.last.as_deref().map(Shown::stale),
+        }
+    }
+
+    /// Handles the display tick at `now` and returns the line to print, if
+    /// any.
+    pub fn on_tick(&mut self, now: Instant) -> Option<Shown<'_>> {
+        while self.next_tick <= now {
+            self.next_tick += self.interval;
+        }
+        self.started = false;
+
+        if self.pending {
+            self.pending = false;
+            // The line printed before becomes the string the next one is
+            // copied into.
+            let last = self.last.get_or_insert_with(String::new);
+            std::mem::swap(last, &mut self.next);
+            return Some(Shown::fresh(last));
+        }
+
+        if self.fetching {
+            // Without a prefetch the fetch was started at the tick itself, so
+            // there is no point in repeating the previous value first.
+            self.late = true;
+            self.shown = !self.prefetch.is_zero() && self.last.is_some();
+            return if self.shown { self.last.as_deref().map(Shown::fresh) } else { None };
+        }
+
+        // The fetch for this tick failed.
+        self.last.as_deref().map(Shown::stale)
+    }
+}
+
+/// Makes `to` a copy of `from`, in the space `to` already has if it is enough.
+fn copy(to: &mut String, from: &str) {
+    to.clear();
+    to.push_str(from);
+}
+
+/// Returns how old, in seconds, the data shown can get when refreshing every
+/// `interval` seconds with fetches that start `prefetch` seconds early: a
+/// fetch is shown from its tick until the next one, which it started before.
+/// A fetch that has not completed by its tick adds the time it still takes.
+pub fn worst_staleness(interval: u64, prefetch: u64) -> u64 {
+    interval.saturating_add(prefetch)
+}
+
+/// Returns how long a fetch may take when refreshing every `interval`
+/// before it is abandoned: nine tenths of the interval, so that it is given
+/// up on before the next fetch is due.
+pub fn deadline(interval: Duration) -> Duration {
+    interval.mul_f64(0.9)
+}
+
+/// Runs watch mode, printing one line per refresh, until stdout is closed,
+/// which ends it with `Ok`, or another error writing to it. `fetch` runs on
+/// a thread of its own, and `show` writes what it fetched as the line into
+/// the buffer it is given, empty, on the loop's thread, so that nothing of an
+/// abandoned fetch is shown or sent. The buffer is the same for all refreshes.
+/// `stale` marks the last line when it is shown again after a failed fetch.
+/// With `verbose`, warnings about hung requests say how many there were.
+/// Ctrl-C exits with [`INTERRUPTED`] once the line being written is done.
+pub fn run<F, T, S, M>(
+    interval: Duration,
+    prefetch: Duration,
+    verbose: bool,
+    fetch: F,
+    mut show: S,
+    stale: M,
+) -> Result<()>
+where
+    F: Fn() -> Result<T> + Send + Sync + 'static,
+    T: Send + 'static,
+    S: FnMut(T, &mut String),
+    M: Fn(&str) -> String,
+{
+    let fetch = Arc::new(fetch);
+    let (tx, rx) = mpsc::channel();
+    let mut schedule = Schedule::new(interval, prefetch, Instant::now());
+    let deadline = deadline(interval);
+    // The fetch in flight, by number, and when it was started.
+    let mut current: Option<(u64, Instant)> = None;
+    let mut started = 0u64;
+    let mut abandoned = 0usize;
+    let mut hung = 0u64;
+    let mut buffer = String::new();
+    // Lines are written with stdout locked, so the handler waits for the
+    // line being written; the handler could only not be set if one were set
+    // already, and Ctrl-C then does what it did before.
+    let _ = ctrlc::set_handler(|| {
+        let _stdout = io::stdout().lock();
+        std::process::exit(INTERRUPTED);
+    });
+
+    loop {
+        let now = Instant::now();
+        let line = match schedule.next_action(now) {
+            Action::Fetch if abandoned >= MAX_ABANDONED => {
+                crate::exit::warning!("{} hung requests are still running; not refreshing", abandoned);
+                schedule.on_fetch_started();
+                schedule.on_fetch_done(None)
+            }
+            Action::Fetch => {
+                schedule.on_fetch_started();
+                started += 1;
+                current = Some((started, now));
+                let fetch = Arc::clone(&fetch);
+                let tx = tx.clone();
+                let id = started;
+                thread::spawn(move || {
+                    // Nothing receives the result once the loop has ended.
+                    let _ = tx.send((id, fetch()));
+                });
+                None
+            }
+            Action::Tick => schedule.on_tick(now),
+            Action::Wait(until) => {
+                let until = match current {
+                    Some((_, since)) => until.min(since + deadline),
+                    None => until,
+                };
+                match rx.recv_timeout(until.saturating_duration_since(now)) {
+                    Ok((id, result)) if current.is_some_and(|(current, _)| current == id) => {
+                        current = None;
+                        buffer.clear();
+                        let value = match result {
+                            Ok(fetched) => {
+                                show(fetched, &mut buffer);
+                                Some(buffer.as_str())
+                            }
+                            Err(e) => {
+                                crate::exit::warning!("{}", e);
+                                None
+                            }
+                        };
+                        schedule.on_fetch_done(value)
+                    }
+                    // A fetch that was abandoned, done at last.
+                    Ok(_) => {
+                        abandoned = abandoned.saturating_sub(1);
+                        None
+                    }
+                    Err(_) => match current {
+                        Some((_, since)) if Instant::now() >= since + deadline => {
+                            current = None;
+                            abandoned += 1;
+                            hung += 1;
+                            let total = record_hung();
+                            crate::exit::warning!("{}", hung_warning(deadline, verbose.then_some((hung, total))));
+                            schedule.on_fetch_done(None)
+                        }
+                        _ => None,
+                    },
+                }
+            }
+        };
+
+        // Each line is written whole and flushed, so that an interrupt
+        // never leaves half of one for what reads the output.
+        if let Some(shown) = line {
+            let marked;
+            let line = if shown.stale {
+                marked = stale(shown.line);
+                &marked
+            } else {
+                shown.line
+            };
+            let mut stdout = io::stdout().lock();
+            match writeln!(stdout, "{}", line).and_then(|()| stdout.flush()) {
+                Ok(()) => {}
+                // What reads the output is gone, as `head` is once it has
+                // its lines: there is no one left to refresh for.
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+                Err(e) => return Err(anyhow!("cannot write the weather: {}", e)),
+            }
+        }
+    }
+}
+
+/// Returns the warning for a request given up on after `deadline`, with the
+/// hung requests of this run and of all runs, if they are to be told.
+fn hung_warning(deadline: Duration, counts: Option<(u64, Option<u64>)>) -> String {
+    let mut warning = format!(
+        "the request did not complete in {:.0} seconds; gave up on it",
+        deadline.as_secs_f64()
+    );
+    if let Some((run, total)) = counts {
+        let total = total.map_or_else(|| "?".to_string(), |total| total.to_string());
+        warning.push_str(&format!(" ({} hung requests in this run, {} in all)", run, total));
+    }
+    warning
+}
+
+/// Hung requests recorded so far, over all runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hung {
+    pub count: u64,
+    /// When the last one was given up on, in unix seconds.
+    pub last: i64,
+}
+
+/// Returns the hung requests recorded so far, or `None` if there were none.
+pub fn load_hung() -> Option<Hung> {
+    let text = fs::read_to_string(hung_path()?).ok()?;
+    let (count, last) = text.trim().split_once(' ')?;
+    Some(Hung { count: count.parse().ok()?, last: last.parse().ok()? })
+}
+
+/// Records a hung request, given up on now, and returns how many there have
+/// been, or `None` if they cannot be recorded.
+fn record_hung() -> Option<u64> {
+    let count = load_hung().map_or(0, |hung| hung.count) + 1;
+    let save = || -> Result<()> {
+        let path = hung_path().ok_or_else(|| anyhow!("no cache directory (is $HOME set?)"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let now = crate::unix_now();
+        fs::write(&path, format!("{} {}", count, now)).map_err(|e| anyhow!("cannot write {}: {}", path.display(), e))
+    };
+    match save() {
+        Ok(()) => Some(count),
+        Err(e) => {
+            crate::exit::warning!("hung request not recorded: {}", e);
+            None
+        }
+    }
+}
+
+fn hung_path() -> Option<PathBuf> {
+    Some(paths::cache_dir()?.join("hung_requests"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::fmt::Write as _;
+
+    /// Counts the allocations of each thread, so that tests running at the
+    /// same time do not count each other's.
+    struct Counting;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: Counting = Counting;
+
+    /// Returns how many allocations `f` makes.
+    fn allocations(f: impl FnOnce()) -> usize {
+        let before = ALLOCATIONS.with(Cell::get);
+        f();
+        ALLOCATIONS.with(Cell::get) - before
+    }
+
+    /// Drives a schedule refreshing every `interval` seconds, fetching
+    /// `prefetch` seconds early, with a clock of its own, for `secs` seconds.
+    /// Fetch n takes `fetches[n].0` seconds and returns `fetches[n].1`, or
+    /// fails with `None`. Returns what happened, by the second: "50 fetch"
+    /// when a fetch starts, and "60 a" when a line is printed, "60 a*" if
+    /// stale.
+    fn simulate(interval: u64, prefetch: u64, fetches: &[(u64, Option<&str>)], secs: u64) -> Vec<String> {
+        let start = Instant::now();
+        let mut schedule = Schedule::new(Duration::from_secs(interval), Duration::from_secs(prefetch), start);
+        let mut now = start;
+        let mut fetches = fetches.iter();
+        let mut in_flight: Option<(Instant, Option<String>)> = None;
+        let mut events = Vec::new();
+        let at = |now: Instant| now.duration_since(start).as_secs();
+        while at(now) < secs {
+            let shown = match schedule.next_action(now) {
+                Action::Fetch => {
+                    assert!(in_flight.is_none(), "a second fetch at {}", at(now));
+                    let &(takes, value) = fetches.next().expect("more fetches than planned");
+                    in_flight = Some((now + Duration::from_secs(takes), value.map(str::to_string)));
+                    schedule.on_fetch_started();
+                    events.push(format!("{} fetch", at(now)));
+                    None
+                }
+                Action::Tick => schedule.on_tick(now),
+                Action::Wait(until) => {
+                    assert!(until > now, "waiting for nothing at {}", at(now));
+                    match in_flight.take() {
+                        Some((done, value)) if done <= until => {
+                            now = now.max(done);
+                            schedule.on_fetch_done(value.as_deref())
+                        }
+                        other => {
+                            in_flight = other;
+                            now = until;
+                            None
+                        }
+                    }
+                }
+            };
+            if let Some(shown) = shown {
+                events.push(format!("{} {}{}", at(now), shown.line, if shown.stale { "*" } else { "" }));
+            }
+        }
+        events
+    }
+
+    #[test]
+    fn prefetches_are_printed_on_their_tick() {
+        let events = simulate(60, 10, &[(2, Some("a")), (2, Some("b")), (2, Some("c"))], 125);
+        // The first fetch starts at the first tick, and is printed when done.
+        assert_eq!(events, ["0 fetch", "2 a", "50 fetch", "60 b", "110 fetch", "120 c"]);
+    }
+
+    #[test]
+    fn without_a_prefetch_fetches_are_printed_when_done() {
+        let events = simulate(60, 0, &[(2, Some("a")), (2, Some("b"))], 65);
+        assert_eq!(events, ["0 fetch", "2 a", "60 fetch", "62 b"]);
+    }
+
+    #[test]
+    fn a_late_fetch_repeats_the_last_line_first() {
+        let events = simulate(60, 10, &[(2, Some("a")), (15, Some("b")), (2, Some("c"))], 125);
+        assert_eq!(events, ["0 fetch", "2 a", "50 fetch", "60 a", "65 b", "110 fetch", "120 c"]);
+    }
+
+    #[test]
+    fn a_late_fetch_of_the_same_is_not_printed_twice() {
+        let events = simulate(60, 10, &[(2, Some("a")), (15, Some("a")), (2, Some("b"))], 125);
+        assert_eq!(events, ["0 fetch", "2 a", "50 fetch", "60 a", "110 fetch", "120 b"]);
+    }
+
+    #[test]
+    fn a_failed_fetch_shows_the_last_line_as_stale() {
+        let events = simulate(60, 10, &[(2, Some("a")), (2, None), (2, Some("b"))], 125);
+        assert_eq!(events, ["0 fetch", "2 a", "50 fetch", "60 a*", "110 fetch", "120 b"]);
+        let events = simulate(60, 10, &[(2, Some("a")), (15, None)], 70);
+        assert_eq!(events, ["0 fetch", "2 a", "50 fetch", "60 a", "65 a*"]);
+    }
+
+    #[test]
+    fn nothing_is_printed_before_the_first_fetch_succeeds() {
+        let events = simulate(60, 10, &[(2, None), (2, None)], 65);
+        assert_eq!(events, ["0 fetch", "50 fetch"]);
+    }
+
+    #[test]
+    fn a_fetch_longer_than_the_interval_is_not_overlapped() {
+        // Ticks pass while it runs, repeating the last line with a prefetch,
+        // and the next fetch starts before the first tick after it is done.
+        let events = simulate(60, 10, &[(2, Some("a")), (75, Some("b")), (2, Some("c"))], 185);
+        assert_eq!(events, ["0 fetch", "2 a", "50 fetch", "60 a", "120 a", "125 b", "170 fetch", "180 c"]);
+        let events = simulate(60, 0, &[(2, Some("a")), (75, Some("b")), (2, Some("c"))], 185);
+        assert_eq!(events, ["0 fetch", "2 a", "60 fetch", "135 b", "180 fetch", "182 c"]);
+    }
+
+    #[test]
+    fn missed_ticks_are_skipped() {
+        let start = Instant::now();
+        let mut schedule = Schedule::new(Duration::from_secs(60), Duration::ZERO, start);
+        schedule.on_fetch_started();
+        assert_eq!(schedule.on_fetch_done(Some("a")), None);
+        let late = start + Duration::from_secs(150);
+        assert_eq!(schedule.next_action(late), Action::Tick);
+        assert_eq!(schedule.on_tick(late), Some(Shown::fresh("a")));
+        assert_eq!(schedule.next_action(late), Action::Wait(start + Duration::from_secs(180)));
+    }
+
+    #[test]
+    fn refreshes_reuse_their_strings() {
+        let start = Instant::now();
+        let interval = Duration::from_secs(60);
+        let mut schedule = Schedule::new(interval, Duration::from_secs(10), start);
+        let mut buffer = String::new();
+        let mut out = Vec::with_capacity(256);
+        // Refresh n is on time, late, or failed, as n goes, and prints each
+        // line as watch::run does: `show` writes into the buffer it is given.
+        let print = |out: &mut Vec<u8>, shown: Shown| {
+            out.clear();
+            writeln!(out, "{}", shown.line).unwrap();
+        };
+        let mut refresh = |n: u32| {
+            let tick = start + interval * n;
+            schedule.on_fetch_started();
+            buffer.clear();
+            write!(buffer, "{}° {}% {}", 10 + n % 7, 60 + n % 30, ["rain", "light rain"][n as usize % 2]).unwrap();
+            let fetched = (n % 5 != 4).then_some(buffer.as_str());
+            let shown = if n.is_multiple_of(3) {
+                if let Some(shown) = schedule.on_tick(tick) {
+                    print(&mut out, shown);
+                }
+                schedule.on_fetch_done(fetched)
+            } else {
+                schedule.on_fetch_done(fetched);
+                schedule.on_tick(tick)
+            };
+            if let Some(shown) = shown {
+                print(&mut out, shown);
+            }
+        };
+        for n in 0..6 {
+            refresh(n);
+        }
+        assert_eq!(allocations(|| (6..206).for_each(&mut refresh)), 0);
+        // A line of its own for each refresh, as they were, would allocate.
+        assert_eq!(allocations(|| (6..206).for_each(|n| drop(format!("{}° {}%", 10 + n % 7, 60 + n % 30)))), 200);
+    }
+
+    #[test]
+    fn staleness_and_deadline() {
+        assert_eq!(worst_staleness(60, 10), 70);
+        assert_eq!(worst_staleness(60, 0), 60);
+        // The longest prefetch there can be, a second short of the interval.
+        assert_eq!(worst_staleness(60, 59), 119);
+        assert_eq!(worst_staleness(1, 0), 1);
+        assert_eq!(worst_staleness(u64::MAX, 10), u64::MAX);
+        assert_eq!(deadline(Duration::from_secs(60)), Duration::from_secs(54));
+    }
+}
@@ -0,0 +1,61 @@
+//! `--accessible`, for screen readers: every item `--list-data` lists,
+//! rendered against a local stand-in for the API, is spelled out without
+//! glyphs, symbols or abbreviated units, with units named in the language of
+//! `--lang`.
+
+mod common;
+
+use common::{MockServer, COORDS};
+use serde_json::Value;
+
+/// Returns whether `value` is words, numbers and punctuation only, without
+/// abbreviations of units, as "15h" or "hPa".
+fn spoken(value: &str) -> bool {
+    let plain = value.chars().all(|c| c.is_alphanumeric() || " .,:;-'".contains(c));
+    let words: Vec<&str> = value.split([' ', ',']).collect();
+    let glued = |word: &&str| word.starts_with(|c: char| c.is_ascii_digit()) && word.contains(char::is_alphabetic);
+    let abbreviated = ["m/s", "mph", "km/h", "hPa", "inHg", "mm", "km", "mi"];
+    plain && !words.iter().any(glued) && !abbreviated.iter().any(|abbreviation| words.contains(abbreviation))
+}
+
+fn stdout(server: &MockServer, args: &[&str]) -> String {
+    let output = server.tinywx().args(["--coords", COORDS, "--accessible"]).args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn every_item_is_spelled_out() {
+    let server = MockServer::api();
+    let output = server.tinywx().arg("--list-data").output().unwrap();
+    let list = String::from_utf8(output.stdout).unwrap();
+    let names: Vec<&str> = list.lines().filter_map(|line| line.split_whitespace().next()).collect();
+    for units in ["metric", "imperial", "standard"] {
+        let mut args = vec!["-o", "json", "--units", units, "-d"];
+        args.extend(&names);
+        let json: Value = serde_json::from_str(&stdout(&server, &args)).unwrap();
+        for name in &names {
+            let value = json[name].as_str().unwrap_or_else(|| panic!("{} missing: {}", name, json));
+            assert!(spoken(value), "{} in {}: {:?}", name, units, value);
+        }
+    }
+}
+
+#[test]
+fn spelled_out_in_words() {
+    let server = MockServer::api();
+    let line = stdout(&server, &["-d", "icon", "description", "temp", "humidity", "wind"]);
+    let expected = "rain, light rain, temperature 12 degrees Celsius, humidity 81 percent, \
+        wind 6 meters per second from the southwest\n";
+    assert_eq!(line, expected);
+    let line = stdout(&server, &["-d", "next_rain", "day_length"]);
+    let day_length = ", day length 15 hours 10 minutes\n";
+    assert!(line.starts_with("rain 80 percent at ") && line.ends_with(day_length), "{}", line);
+}
+
+#[test]
+fn with_the_units_in_the_language_of_lang() {
+    let server = MockServer::api();
+    let line = stdout(&server, &["-d", "temp", "humidity", "wind", "--lang", "de"]);
+    assert_eq!(line, "temperature 12 Grad Celsius, humidity 81 Prozent, wind 6 Meter pro Sekunde from the Südwest\n");
+}
@@ -0,0 +1,79 @@
+//! The response cache against a local stand-in for the API: a second run
+//! answered from it, `--cache-ttl` and the `[cache]` table turning it off,
+//! and a cache file that is garbage or cut short fetched again and
+//! overwritten.
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use common::{MockServer, COORDS};
+
+/// Runs tinywx for the temperature with `args`, and returns what it printed.
+fn temp(server: &MockServer, args: &[&str]) -> String {
+    let output = server.tinywx().args(["--coords", COORDS, "-d", "temp"]).args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Returns the number of requests of the current weather so far.
+fn fetches(server: &MockServer) -> usize {
+    server.requests().iter().filter(|path| path.starts_with("/data/2.5/weather")).count()
+}
+
+/// Returns the files of the current weather in the cache.
+fn cached(server: &MockServer) -> Vec<PathBuf> {
+    let dir = server.home().join("cache/tinywx/responses/current");
+    fs::read_dir(dir).map_or_else(|_| Vec::new(), |dir| dir.map(|entry| entry.unwrap().path()).collect())
+}
+
+#[test]
+fn a_second_run_is_answered_from_the_cache() {
+    let server = MockServer::api();
+    assert_eq!(temp(&server, &[]), "12°\n");
+    assert_eq!(temp(&server, &[]), "12°\n");
+    assert_eq!(fetches(&server), 1);
+    assert_eq!(cached(&server).len(), 1);
+    assert_eq!(temp(&server, &["--no-cache"]), "12°\n");
+    assert_eq!(fetches(&server), 2);
+}
+
+#[test]
+fn a_time_to_live_of_0_turns_it_off() {
+    let server = MockServer::api();
+    temp(&server, &["--cache-ttl", "0"]);
+    temp(&server, &["--cache-ttl", "0"]);
+    assert_eq!(fetches(&server), 2);
+    // Nothing was written for a run without the flag to find.
+    assert!(cached(&server).is_empty(), "{:?}", cached(&server));
+    temp(&server, &[]);
+    assert_eq!(fetches(&server), 3);
+
+    let server = MockServer::api();
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("cache.toml");
+    fs::write(&path, "[cache]\ncurrent = 0\n").unwrap();
+    let path = path.to_str().unwrap();
+    temp(&server, &["-f", path]);
+    temp(&server, &["-f", path]);
+    assert_eq!(fetches(&server), 2);
+    assert!(cached(&server).is_empty(), "{:?}", cached(&server));
+}
+
+#[test]
+fn a_broken_cache_file_is_fetched_again_and_overwritten() {
+    let server = MockServer::api();
+    temp(&server, &[]);
+    let [file] = cached(&server).try_into().unwrap();
+    let entry = fs::read_to_string(&file).unwrap();
+    let cut_short = &entry[..entry.len() / 2];
+    for broken in ["garbage", cut_short, ""] {
+        fs::write(&file, broken).unwrap();
+        let before = fetches(&server);
+        assert_eq!(temp(&server, &[]), "12°\n", "{:?}", broken);
+        assert_eq!(fetches(&server), before + 1, "{:?}", broken);
+        let rewritten = fs::read_to_string(&file).unwrap();
+        assert_eq!(rewritten.lines().nth(1), entry.lines().nth(1), "{:?}", broken);
+    }
+}
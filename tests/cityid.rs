@@ -0,0 +1,64 @@
+//! Querying a city by the id its name resolved to before, against a local
+//! stand-in for the API: which form each run uses, as `-v` says, and the name
+//! query an id that is no longer found falls back to.
+
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use common::MockServer;
+
+const AMSTERDAM: [&str; 7] = ["-c", "amsterdam", "-C", "nl", "-d", "temp", "--no-cache"];
+
+/// Runs tinywx with `args`, and returns which form of query `-v` says it
+/// used.
+fn forms(server: &MockServer, args: &[&str]) -> Vec<String> {
+    let output = server.tinywx().args(AMSTERDAM).args(args).arg("-v").output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12°\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    stderr.lines().filter(|line| line.starts_with("querying by")).map(str::to_string).collect()
+}
+
+/// Returns the query of each request of the current weather so far.
+fn queries(server: &MockServer) -> Vec<String> {
+    let requests = server.requests();
+    let weather = requests.iter().filter(|path| path.starts_with("/data/2.5/weather?"));
+    weather.map(|path| path.split(['?', '&']).nth(1).unwrap().to_string()).collect()
+}
+
+#[test]
+fn the_id_is_queried_after_the_first_name_lookup() {
+    let server = MockServer::api();
+    assert_eq!(forms(&server, &[]), ["querying by name"]);
+    assert_eq!(forms(&server, &[]), ["querying by city id 2759794"]);
+    assert_eq!(forms(&server, &[]), ["querying by city id 2759794"]);
+    assert_eq!(queries(&server), ["q=amsterdam%2Cnl", "id=2759794", "id=2759794"]);
+}
+
+#[test]
+fn an_id_not_found_falls_back_to_the_name_once() {
+    let gone = Arc::new(AtomicBool::new(false));
+    let server = MockServer::start({
+        let gone = Arc::clone(&gone);
+        move |path| match path.contains("id=") && gone.swap(false, Ordering::SeqCst) {
+            true => (404, r#"{"cod":"404","message":"city not found"}"#.to_string()),
+            false => common::api(path),
+        }
+    });
+    forms(&server, &[]);
+    gone.store(true, Ordering::SeqCst);
+    assert_eq!(forms(&server, &[]), ["querying by city id 2759794", "querying by name"]);
+    // The name resolved to the id again, which is stored again.
+    assert_eq!(forms(&server, &[]), ["querying by city id 2759794"]);
+    assert_eq!(queries(&server), ["q=amsterdam%2Cnl", "id=2759794", "q=amsterdam%2Cnl", "id=2759794"]);
+}
+
+#[test]
+fn names_only_without_city_ids() {
+    let server = MockServer::api();
+    forms(&server, &[]);
+    assert_eq!(forms(&server, &["--no-city-id"]), ["querying by name"]);
+    assert_eq!(queries(&server), ["q=amsterdam%2Cnl", "q=amsterdam%2Cnl"]);
+}
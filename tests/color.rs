@@ -0,0 +1,63 @@
+//! Colors with `--color`, against a local stand-in for the API: the escape
+//! sequences of the temperature and of the description, the thresholds of
+//! the `[colors]` table, and output that is byte for byte the same as before
+//! when it is not colored, as when it is piped.
+
+mod common;
+
+use std::fs;
+
+use common::{MockServer, COORDS};
+
+const ITEMS: [&str; 5] = ["--coords", COORDS, "-d", "temp", "description"];
+
+/// Runs tinywx with `args` for the items of [`ITEMS`], and returns what it
+/// printed.
+fn weather(server: &MockServer, args: &[&str]) -> String {
+    let output = server.tinywx().args(ITEMS).args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn always_colors_the_temperature_and_the_condition() {
+    let server = MockServer::api();
+    // 12° is between the green and the yellow of metric units, and the
+    // weather is rain, in blue.
+    let expected = "\x1b[32m12°\x1b[0m \x1b[34mlight rain\x1b[0m\n";
+    assert_eq!(weather(&server, &["--color", "always"]), expected);
+    // In Fahrenheit, 12° is blue.
+    let imperial = weather(&server, &["--color", "always", "--units", "imperial"]);
+    assert!(imperial.starts_with("\x1b[34m12°\x1b[0m "), "{:?}", imperial);
+}
+
+#[test]
+fn without_color_the_line_is_unchanged() {
+    let server = MockServer::api();
+    let plain = "12° light rain\n";
+    assert_eq!(weather(&server, &[]), plain);
+    assert_eq!(weather(&server, &["--color", "never"]), plain);
+    // Piped, auto does not color.
+    assert_eq!(weather(&server, &["--color", "auto"]), plain);
+    let output = server.tinywx().args(ITEMS).args(["--color", "auto"]).env("NO_COLOR", "1").output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), plain);
+    // Structured output is never colored.
+    let json = weather(&server, &["--color", "always", "-o", "json"]);
+    assert!(!json.contains('\x1b'), "{:?}", json);
+}
+
+#[test]
+fn the_colors_table_moves_the_thresholds() {
+    let server = MockServer::api();
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("colors.toml");
+    fs::write(&path, "color = \"always\"\n\n[colors]\nmetric = [0, 5, 12]\n").unwrap();
+    let line = weather(&server, &["-f", path.to_str().unwrap()]);
+    assert!(line.starts_with("\x1b[31m12°\x1b[0m "), "{:?}", line);
+
+    fs::write(&path, "[colors]\nmetric = [20, 10, 30]\n").unwrap();
+    let output = server.tinywx().args(ITEMS).args(["-f", path.to_str().unwrap()]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("expected three increasing temperatures"), "{}", stderr);
+}
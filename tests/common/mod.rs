@@ -0,0 +1,180 @@
+//! A local HTTP server standing in for the API, and tinywx run against it,
+//! for the tests of the binary.
+
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, process, thread};
+
+use assert_cmd::Command;
+use serde_json::{json, Value};
+
+/// The current weather in Amsterdam, as the fixture of the wx tests has it.
+pub const CURRENT: &str = include_str!("../../wx/tests/fixtures/current.json");
+
+/// The coordinates of [`CURRENT`], to query by.
+pub const COORDS: &str = "52.374,4.8897";
+
+/// A response of the mock server: the status and the body, sent as JSON.
+pub type Response = (u16, String);
+
+/// A server on a free local port that answers each request with what its
+/// handler returns for the path, and records the paths it was asked for.
+/// It runs until the test ends; tinywx run against it keeps its cache and
+/// config in a directory of its own, removed with the server.
+pub struct MockServer {
+    pub url: String,
+    home: PathBuf,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    pub fn start(handler: impl Fn(&str) -> Response + Send + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let url = format!("http://127.0.0.1:{}/", port);
+        let home = std::env::temp_dir().join(format!("tinywx-test-{}-{}", process::id(), port));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let mut reader = BufReader::new(&stream);
+                let mut request_line = String::new();
+                if reader.read_line(&mut request_line).is_err() {
+                    continue;
+                }
+                // Skip the headers; requests have no body.
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|n| n > 0) && line != "\r\n" {
+                    line.clear();
+                }
+                let path = request_line.split_whitespace().nth(1).unwrap_or_default().to_string();
+                let (status, body) = handler(&path);
+                recorded.lock().unwrap().push(path);
+                let headers = format!(
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+                    status,
+                    body.len()
+                );
+                let _ = write!(stream, "{}\r\n{}", headers, body);
+            }
+        });
+        MockServer { url, home, requests }
+    }
+
+    /// Starts a server answering as the API does, with [`api`].
+    pub fn api() -> Self {
+        Self::start(api)
+    }
+
+    /// Returns the directory tinywx keeps its cache and config in, for this
+    /// server.
+    pub fn home(&self) -> &std::path::Path {
+        &self.home
+    }
+
+    /// Returns the paths requested so far, with their query.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Returns whether a path starting with `prefix`, as in
+    /// "/data/3.0/onecall", was requested.
+    pub fn requested(&self, prefix: &str) -> bool {
+        self.requests().iter().any(|path| path.starts_with(prefix))
+    }
+
+    /// Returns tinywx to run against this server, with nothing of the
+    /// environment it runs in: no config file, no cache of other tests, and
+    /// no retries.
+    pub fn tinywx(&self) -> Command {
+        Command::from_std(self.spawnable())
+    }
+
+    /// Returns tinywx as [`tinywx`](Self::tinywx) does, as a command of the
+    /// standard library, for tests that talk to it while it runs.
+    pub fn spawnable(&self) -> process::Command {
+        let mut command = process::Command::new(assert_cmd::cargo::cargo_bin("tinywx"));
+        command
+            .env_clear()
+            .env("HOME", &self.home)
+            .env("XDG_CACHE_HOME", self.home.join("cache"))
+            .env("XDG_CONFIG_HOME", self.home.join("config"))
+            .env("TINYWX_API_KEY", "0123456789abcdef0123456789abcdef")
+            .args(["--api-url", &self.url, "--retries", "0"]);
+        command
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.home);
+    }
+}
+
+/// Answers a request as the API does, for Amsterdam: the current weather is
+/// [`CURRENT`], and the forecasts start at the time of the request, so that
+/// items about what comes next have something to render.
+pub fn api(path: &str) -> Response {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    let hour = now - now % 3600;
+    let body = if path.starts_with("/data/2.5/weather") {
+        return (200, CURRENT.to_string());
+    } else if path.starts_with("/data/2.5/forecast") {
+        let list: Vec<Value> = (1..=8)
+            .map(|step| {
+                json!({
+                    "dt": hour + step * 10800,
+                    "main": {"temp": 10 + step, "feels_like": 9 + step, "temp_min": 9, "temp_max": 14,
+                        "pressure": 1012, "humidity": 80},
+                    "weather": [{"id": 500, "main": "Rain", "description": "light rain", "icon": "10d"}],
+                    "clouds": {"all": 75},
+                    "wind": {"speed": 5.1, "deg": 230},
+                    "visibility": 10000,
+                    "pop": if step == 2 { 0.8 } else { 0.1 },
+                })
+            })
+            .collect();
+        let city = json!({"id": 2759794, "name": "Amsterdam", "coord": {"lat": 52.374, "lon": 4.8897},
+            "country": "NL", "timezone": 7200, "sunrise": hour - 18000, "sunset": hour + 28800});
+        json!({"cod": "200", "message": 0, "cnt": list.len(), "list": list, "city": city})
+    } else if path.starts_with("/data/3.0/onecall") {
+        let hourly: Vec<Value> = (0..24)
+            .map(|step| {
+                let uvi = [0, 2, 4, 6, 4, 2][step as usize % 6];
+                json!({"dt": hour + step * 3600, "temp": 12, "feels_like": 11, "pressure": 1012, "humidity": 80,
+                    "dew_point": 8, "uvi": uvi, "clouds": 75,
+                    "wind_speed": 5.1, "wind_deg": 230, "pop": 0.1})
+            })
+            .collect();
+        let daily = json!({"dt": hour, "moon_phase": 0.25,
+            "temp": {"morn": 9, "day": 13, "eve": 12, "night": 8, "min": 8, "max": 14},
+            "feels_like": {"morn": 8, "day": 12, "eve": 11, "night": 7},
+            "pressure": 1012, "humidity": 80, "dew_point": 8, "wind_speed": 5.1, "wind_deg": 230,
+            "clouds": 75, "uvi": 6});
+        json!({"lat": 52.374, "lon": 4.8897, "timezone": "Europe/Amsterdam", "timezone_offset": 7200,
+            "current": {"dt": now, "temp": 12, "feels_like": 11, "pressure": 1012, "humidity": 80, "dew_point": 8,
+                "uvi": 4.2, "clouds": 75, "wind_speed": 5.1, "wind_deg": 230},
+            "hourly": hourly, "daily": [daily]})
+    } else if path.starts_with("/data/2.5/air_pollution") {
+        let list: Vec<Value> = (0..24)
+            .map(|step| {
+                json!({"dt": hour + step * 3600, "main": {"aqi": 2},
+                    "components": {"co": 230, "no2": 10 + step % 6, "o3": 60, "so2": 1, "pm2_5": 5 + step % 4,
+                        "pm10": 8}})
+            })
+            .collect();
+        let list = if path.contains("/forecast") { list } else { list[..1].to_vec() };
+        json!({"coord": {"lat": 52.374, "lon": 4.8897}, "list": list})
+    } else {
+        return (404, json!({"cod": "404", "message": "Internal error"}).to_string());
+    };
+    (200, body.to_string())
+}
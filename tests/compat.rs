@@ -0,0 +1,138 @@
+//! Invocations from before there were subcommands behave as they did: each
+//! gives the same output without a subcommand as with `current`, and the
+//! output it always gave.
+
+mod common;
+
+use std::fs;
+
+use common::{MockServer, COORDS};
+
+/// What a run of tinywx printed, and how it exited.
+#[derive(Debug, PartialEq, Eq)]
+struct Run {
+    code: Option<i32>,
+    stdout: String,
+    stderr: String,
+}
+
+/// Runs tinywx with `args`, against a server of its own so that nothing is
+/// cached from runs before.
+fn run(args: &[&str]) -> (Run, MockServer) {
+    let server = MockServer::api();
+    fs::create_dir_all(server.home()).unwrap();
+    let home = server.home().to_str().unwrap().to_string();
+    let args: Vec<String> = args.iter().map(|arg| arg.replace("$HOME", &home)).collect();
+    let output = server.tinywx().args(&args).output().unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap().replace(&home, "$HOME");
+    let run = Run {
+        code: output.status.code(),
+        stdout: String::from_utf8(output.stdout).unwrap(),
+        stderr: usage(&stderr),
+    };
+    (run, server)
+}
+
+/// Returns `stderr` without the usage clap prints with its errors, which
+/// names the subcommand and the arguments that were given.
+fn usage(stderr: &str) -> String {
+    match stderr.split_once("\nUSAGE:\n") {
+        Some((error, usage)) => format!("{}\n{}", error, usage.split_once("\n\n").map_or("", |(_, rest)| rest)),
+        None => stderr.to_string(),
+    }
+}
+
+/// Runs `args` with and without `current` first, checks both give the same,
+/// and returns what they gave.
+fn compatible(args: &[&str]) -> Run {
+    let (without, _) = run(args);
+    let (with, _) = run(&[&["current"], args].concat());
+    assert_eq!(without, with, "{:?}", args);
+    without
+}
+
+fn succeeds(args: &[&str], stdout: &str) -> Run {
+    let run = compatible(args);
+    assert_eq!(run.code, Some(0), "{:?}: {}", args, run.stderr);
+    assert_eq!(run.stdout, stdout, "{:?}", args);
+    run
+}
+
+fn fails(args: &[&str], code: i32, error: &str) {
+    let run = compatible(args);
+    assert_eq!(run.code, Some(code), "{:?}: {}", args, run.stderr);
+    assert!(run.stderr.contains(error), "{:?}: {}", args, run.stderr);
+}
+
+#[test]
+fn locations() {
+    succeeds(&["-c", "Amsterdam", "-C", "nl", "-d", "temp", "humidity"], "12° 81%\n");
+    succeeds(&["--city", "Amsterdam", "--country", "nl", "--data", "temp"], "12°\n");
+    succeeds(&["--coords", COORDS, "-d", "temp", "pressure"], "12° 1012\n");
+    succeeds(&["-c", "Springfield", "-s", "il", "-C", "us", "-d", "city"], "Amsterdam\n");
+    let (_, server) = run(&["-c", "Amsterdam", "-C", "nl", "-d", "temp"]);
+    assert!(server.requested("/data/2.5/weather?q=Amsterdam%2Cnl"), "{:?}", server.requests());
+}
+
+#[test]
+fn formats() {
+    let args = ["--coords", COORDS, "-d", "temp", "humidity"];
+    succeeds(&[&args[..], &["-o", "plain"]].concat(), "12° 81%\n");
+    succeeds(&[&args[..], &["-o", "json"]].concat(), "{\"humidity\":\"81%\",\"temp\":\"12°\"}\n");
+    succeeds(
+        &[&args[..], &["--output", "waybar"]].concat(),
+        concat!(
+            "{\"class\":[\"rain\",\"light\"],\"text\":\"12° 81%\",",
+            "\"tooltip\":\"light rain\\nfeels like 12°\\nhumidity 81%\\nwind 6\"}\n"
+        ),
+    );
+    succeeds(&["--coords", COORDS, "-d", "icon", "--icons", "emoji"], "🌧️\n");
+    succeeds(&["--coords", COORDS, "-d", "icon", "--icons", "text"], "rain\n");
+    succeeds(&["--coords", COORDS, "-d", "icon", "--icons", "text", "--night-dim", "--dim-style", "polybar"], "rain\n");
+}
+
+#[test]
+fn units() {
+    let (run, server) = run(&["--coords", COORDS, "-d", "temp", "--imperial"]);
+    assert_eq!(run, compatible(&["--coords", COORDS, "-d", "temp", "--imperial"]));
+    assert_eq!(run.code, Some(0), "{}", run.stderr);
+    assert!(server.requests().iter().any(|path| path.contains("units=imperial")), "{:?}", server.requests());
+    succeeds(&["--coords", COORDS, "-d", "temp", "wind", "--unit-style", "spaced"], "12 °C 6 m/s\n");
+    succeeds(&["--coords", COORDS, "-d", "temp", "wind", "--unit-style", "compact"], "12°C 6m/s\n");
+}
+
+#[test]
+fn settings_and_side_outputs() {
+    let config = "coords = \"52.374,4.8897\"\ndata = [\"temp\", \"clouds\"]\n";
+    let (_, server) = run(&[]);
+    let path = server.home().join("tinywx.toml");
+    fs::write(&path, config).unwrap();
+    let file = path.to_str().unwrap();
+    succeeds(&["-f", file], "12° 75%\n");
+    succeeds(&["-f", file, "-d", "humidity"], "81%\n");
+    succeeds(&["-f", file, "--no-city-id", "--strict", "--history"], "12° 75%\n");
+    let run = succeeds(&["--coords", COORDS, "-d", "temp", "--explain"], "12°\n");
+    assert!(run.stderr.contains("temp"), "{}", run.stderr);
+    let run = succeeds(&["--coords", COORDS, "-d", "temp", "-v"], "12°\n");
+    assert!(!run.stderr.is_empty());
+    succeeds(&["--coords", COORDS, "-d", "temp", "--output-file", "$HOME/line"], "12°\n");
+}
+
+#[test]
+fn listings() {
+    let (list, _) = run(&["--list-data"]);
+    assert_eq!(list.code, Some(0));
+    assert!(list.stdout.lines().any(|line| line.starts_with("temp")), "{}", list.stdout);
+    let (contract, _) = run(&["--contract", "json"]);
+    assert_eq!(contract.code, Some(0));
+    assert!(serde_json::from_str::<serde_json::Value>(&contract.stdout).is_ok());
+}
+
+#[test]
+fn usage_errors() {
+    fails(&["--coords", COORDS, "-d", "bogus"], 2, "bogus");
+    fails(&["--coords", COORDS, "-d", "temp", "--prefetch", "5"], 2, "--watch");
+    fails(&["--coords", COORDS, "-d", "temp", "-o", "xml"], 2, "xml");
+    fails(&["--coords", COORDS, "-d", "temp", "--unit-style", "huge"], 2, "huge");
+    fails(&["--coords", "north", "-d", "temp"], 2, "invalid coordinates");
+}
@@ -0,0 +1,68 @@
+//! The script of `tinywx completions bash`, sourced in bash and asked to
+//! complete as the shell would: items after `-d`, the values of a flag, and
+//! subcommands. And the other shells, and one there are none for.
+
+mod common;
+
+use std::process::Command;
+
+use common::MockServer;
+
+/// Returns what the bash completions complete `words` to, the last of them
+/// being the word under the cursor.
+fn complete(script: &str, words: &[&str]) -> Vec<String> {
+    let words: Vec<String> = words.iter().map(|word| format!("'{}'", word)).collect();
+    let simulation = format!(
+        "{}\nCOMP_WORDS=(tinywx {})\nCOMP_CWORD={}\n_tinywx\nprintf '%s\\n' \"${{COMPREPLY[@]}}\"\n",
+        script,
+        words.join(" "),
+        words.len()
+    );
+    let output = Command::new("bash").args(["--norc", "-c", &simulation]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap().lines().map(str::to_string).collect()
+}
+
+fn bash_script(server: &MockServer) -> String {
+    let output = server.tinywx().args(["completions", "bash"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn data_completes_to_items() {
+    let server = MockServer::api();
+    let script = bash_script(&server);
+    assert_eq!(complete(&script, &["-d", "feels"]), ["feels_like"]);
+    // After the first item, as -d takes several.
+    assert_eq!(complete(&script, &["--data", "temp", "humid"]), ["humidity"]);
+    let all = complete(&script, &["-d", ""]);
+    for item in ["icon", "temp", "feels_like", "wind_dir", "sunrise"] {
+        assert!(all.iter().any(|word| word == item), "{} in {:?}", item, all);
+    }
+}
+
+#[test]
+fn flags_and_subcommands_complete() {
+    let server = MockServer::api();
+    let script = bash_script(&server);
+    assert_eq!(complete(&script, &["--color", "a"]), ["auto", "always"]);
+    assert!(complete(&script, &["completions", ""]).ends_with(&["bash", "zsh", "fish"].map(String::from)));
+    assert_eq!(complete(&script, &["completions", "z"]), ["zsh"]);
+    assert!(complete(&script, &["conf"]).contains(&"config".to_string()));
+    assert!(complete(&script, &["--for"]).contains(&"--forecast".to_string()));
+    // Nothing was asked of the API for any of it.
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
+
+#[test]
+fn zsh_and_fish_too_but_no_other_shell() {
+    let server = MockServer::api();
+    for shell in ["zsh", "fish"] {
+        let output = server.tinywx().args(["completions", shell]).output().unwrap();
+        assert!(output.status.success(), "{}: {}", shell, String::from_utf8_lossy(&output.stderr));
+        assert!(String::from_utf8(output.stdout).unwrap().contains("feels_like"), "{}", shell);
+    }
+    let output = server.tinywx().args(["completions", "powershell"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,118 @@
+//! The `config` subcommands on config files written for each test, and a run
+//! failing on a mistake in the file before it asks the API anything.
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use common::MockServer;
+
+/// Writes `contents` to a config file of the home of `server`, and returns
+/// its path.
+fn config_file(server: &MockServer, contents: &str) -> PathBuf {
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("tinywx.toml");
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+/// Runs `config check` on `contents`, and returns the exit code and stdout.
+fn check(contents: &str) -> (Option<i32>, String) {
+    let server = MockServer::api();
+    let path = config_file(&server, contents);
+    let output = server.tinywx().args(["config", "check"]).arg(&path).output().unwrap();
+    (output.status.code(), String::from_utf8(output.stdout).unwrap())
+}
+
+#[test]
+fn check_passes_a_clean_file() {
+    let (code, stdout) = check("city = \"amsterdam\"\ncountry = \"nl\"\nunits = \"metric\"\n");
+    assert_eq!(code, Some(0), "{}", stdout);
+    assert!(stdout.starts_with("OK\nlocations:     amsterdam,nl\n"), "{}", stdout);
+}
+
+#[test]
+fn check_reports_unit_aliases() {
+    for (alias, units) in [("Celsius ", "metric"), ("f", "imperial"), ("kelvin", "standard")] {
+        let (code, stdout) = check(&format!("city = \"amsterdam\"\nunits = \"{}\"\n", alias));
+        assert_eq!(code, Some(1), "{}", stdout);
+        assert_eq!(stdout, format!("units = \"{}\" is read as \"{}\"; write that instead\n", alias, units));
+    }
+}
+
+#[test]
+fn check_reports_deprecated_keys() {
+    let (code, stdout) = check("city = \"amsterdam\"\nimperial = true\n");
+    assert_eq!(code, Some(1), "{}", stdout);
+    assert!(stdout.contains("is deprecated; use units = \"imperial\" instead"), "{}", stdout);
+    assert!(!stdout.contains("OK"), "{}", stdout);
+}
+
+#[test]
+fn check_reports_each_problem_with_its_line() {
+    let contents = "city = \"amsterdam\"\ncountry = \"nld\"\ndata = [\"temperature\"]\n\n\
+        [format]\nunit_stle = \"spaced\"\n";
+    let (code, stdout) = check(contents);
+    assert_eq!(code, Some(1), "{}", stdout);
+    let expected = "line 2: country: 'nld' is not a country code: expected two letters, as in nl or us\n\
+        line 3: data: unknown data item 'temperature', did you mean 'temp'?\n\
+        line 6: format.unit_stle: unknown key\n";
+    assert_eq!(stdout, expected);
+}
+
+#[test]
+fn a_run_fails_fast_on_a_mistake_in_the_file() {
+    let server = MockServer::api();
+    let path = config_file(&server, "coords = \"52.374,4.8897\"\ndata = [\"temperature\"]\n");
+    let output = server.tinywx().arg("-f").arg(&path).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let problem = format!("{}: line 2: data: unknown data item 'temperature', did you mean 'temp'?", path.display());
+    assert!(stderr.contains(&problem), "{}", stderr);
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
+
+#[test]
+fn show_tells_where_migrated_keys_went() {
+    let server = MockServer::api();
+    let path = config_file(&server, "city = \"amsterdam\"\nimperial = true\n");
+    let output = server.tinywx().args(["config", "show", "--origins", "-f"]).arg(&path).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let line = |name: &str| stdout.lines().find(|line| line.split_whitespace().next() == Some(name)).unwrap();
+    let file = format!("file {}", path.display());
+    assert_eq!(line("imperial"), format!("{:<18} {:<24} {}", "imperial", "migrated to units", file));
+    assert_eq!(line("units"), format!("{:<18} {:<24} {}", "units", "\"imperial\"", file));
+}
+
+#[test]
+fn the_old_config_file_name_is_read_but_deprecated() {
+    let server = MockServer::api();
+    let dir = server.home().join("config/tinywx");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("tinywx.toml"), "city = \"amsterdam\"\n").unwrap();
+    let output = server.tinywx().args(["config", "check"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let notice = format!("the config file {} is deprecated", dir.join("tinywx.toml").display());
+    assert!(stdout.contains(&notice), "{}", stdout);
+
+    fs::write(dir.join("config.toml"), "city = \"paris\"\n").unwrap();
+    let output = server.tinywx().args(["config", "check"]).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output.status.code(), Some(0), "{}", stdout);
+    assert!(stdout.contains("locations:     paris"), "{}", stdout);
+}
+
+#[test]
+fn init_config_is_config_init() {
+    let path = |server: &MockServer| server.home().join("config/tinywx/config.toml");
+    let flag = MockServer::api();
+    let subcommand = MockServer::api();
+    assert!(flag.tinywx().arg("--init-config").output().unwrap().status.success());
+    assert!(subcommand.tinywx().args(["config", "init"]).output().unwrap().status.success());
+    assert_eq!(fs::read_to_string(path(&flag)).unwrap(), fs::read_to_string(path(&subcommand)).unwrap());
+    assert!(!flag.tinywx().arg("--init-config").output().unwrap().status.success(), "overwrote the file");
+}
@@ -0,0 +1,126 @@
+//! `--contract json` against what tinywx does: the exit codes, the items, and
+//! real output of each structured format checked against its schema.
+
+mod common;
+
+use serde_json::Value;
+
+use common::{MockServer, COORDS};
+
+/// Returns the contract.
+fn contract(server: &MockServer) -> Value {
+    let output = server.tinywx().args(["--contract", "json"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+/// Returns the schema of the output format `name` in `contract`.
+fn schema<'a>(contract: &'a Value, name: &str) -> &'a Value {
+    let formats = contract["formats"].as_array().unwrap();
+    &formats.iter().find(|format| format["name"] == name).unwrap()["schema"]
+}
+
+/// Checks `value` against `schema`, as far as the keywords the contract uses
+/// go: `type`, `enum`, `properties`, `additionalProperties` and `items`.
+/// Returns the path of the first value that does not match.
+fn validate(value: &Value, schema: &Value, path: &str) -> Result<(), String> {
+    let type_name = match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    };
+    let types: Vec<&str> = match &schema["type"] {
+        Value::String(name) => vec![name.as_str()],
+        Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    };
+    let matches = |name: &str| name == type_name || (name == "number" && type_name == "integer");
+    if !types.is_empty() && !types.iter().any(|&name| matches(name)) {
+        return Err(format!("{}: {} is not {:?}", path, value, types));
+    }
+    if let Some(values) = schema["enum"].as_array() {
+        if !values.contains(value) {
+            return Err(format!("{}: {} is none of {:?}", path, value, values));
+        }
+    }
+    if let Value::Object(object) = value {
+        for (key, member) in object {
+            let path = format!("{}.{}", path, key);
+            match (schema["properties"].get(key), &schema["additionalProperties"]) {
+                (Some(property), _) => validate(member, property, &path)?,
+                (None, Value::Bool(false)) => return Err(format!("{}: not in the schema", path)),
+                (None, additional @ Value::Object(_)) => validate(member, additional, &path)?,
+                (None, _) => {}
+            }
+        }
+    }
+    if let (Value::Array(elements), Some(items)) = (value, schema.get("items")) {
+        for (n, element) in elements.iter().enumerate() {
+            validate(element, items, &format!("{}[{}]", path, n))?;
+        }
+    }
+    Ok(())
+}
+
+/// Runs tinywx with `args`, and returns its output as JSON.
+fn output(server: &MockServer, args: &[&str]) -> Value {
+    let output = server.tinywx().args(["--coords", COORDS]).args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn json_output_of_every_item_matches_its_schema() {
+    let server = MockServer::api();
+    let contract = contract(&server);
+    let items = contract["items"].as_array().unwrap();
+    let items: Vec<&str> = items.iter().map(|item| item["name"].as_str().unwrap()).collect();
+    let json = output(&server, &[&["-o", "json", "--explain", "-d"], &items[..]].concat());
+    assert_eq!(json.as_object().unwrap().len(), items.len() + 1, "{}", json);
+    validate(&json, schema(&contract, "json"), "$").unwrap();
+}
+
+#[test]
+fn raw_json_and_waybar_output_match_their_schemas() {
+    let server = MockServer::api();
+    let contract = contract(&server);
+    let raw = output(&server, &["-o", "raw-json"]);
+    assert!(raw.as_object().is_some_and(|object| !object.is_empty()), "{}", raw);
+    validate(&raw, schema(&contract, "raw-json"), "$").unwrap();
+    let waybar = output(&server, &["-o", "waybar", "-d", "temp", "description"]);
+    validate(&waybar, schema(&contract, "waybar"), "$").unwrap();
+}
+
+#[test]
+fn the_validator_finds_what_does_not_match() {
+    let server = MockServer::api();
+    let contract = contract(&server);
+    let json = schema(&contract, "json");
+    let nonsense = validate(&serde_json::json!({"nonsense": "1"}), json, "$");
+    assert_eq!(nonsense.unwrap_err(), "$.nonsense: not in the schema");
+    assert!(validate(&serde_json::json!({"temp": 12}), json, "$").is_err());
+    assert!(validate(&serde_json::json!({"stale": "yes"}), json, "$").is_err());
+}
+
+#[test]
+fn the_exit_codes_are_those_tinywx_exits_with() {
+    let server = MockServer::start(|_| (401, r#"{"cod":401,"message":"Invalid API key"}"#.to_string()));
+    let contract = contract(&server);
+    let codes = contract["exit_codes"].as_array().unwrap();
+    let codes: Vec<i64> = codes.iter().map(|code| code["code"].as_i64().unwrap()).collect();
+    assert_eq!(codes, [0, 1, 2, 3, 4]);
+    let code = |args: &[&str]| server.tinywx().args(args).output().unwrap().status.code().map(i64::from);
+    assert_eq!(code(&["--list-data"]), Some(codes[0]));
+    assert_eq!(code(&["--coords", COORDS, "-d", "bogus"]), Some(codes[2]));
+    assert_eq!(code(&["--coords", COORDS, "-d", "temp"]), Some(codes[4]));
+    let unanswered = MockServer::start(|path| {
+        std::thread::sleep(std::time::Duration::from_millis(1500));
+        common::api(path)
+    });
+    let output = unanswered.tinywx().args(["--coords", COORDS, "-d", "temp", "--timeout", "1"]).output().unwrap();
+    assert_eq!(output.status.code().map(i64::from), Some(codes[3]));
+}
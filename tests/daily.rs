@@ -0,0 +1,66 @@
+//! `--daily`: today's icon, high and low, from the daily forecast of One
+//! Call, which it asks for alone, at the coordinates given or at those of the
+//! current weather of the city.
+
+#![cfg(feature = "onecall")]
+
+mod common;
+
+use common::{MockServer, COORDS};
+use serde_json::Value;
+
+const PARTS: &str = "exclude=current%2Cminutely%2Chourly%2Calerts";
+
+/// Answers as [`common::api`] does, with the weather of the day, rain, in
+/// the daily forecast.
+fn with_weather(path: &str) -> common::Response {
+    let (status, body) = common::api(path);
+    if !path.starts_with("/data/3.0/onecall") {
+        return (status, body);
+    }
+    let mut one_call: Value = serde_json::from_str(&body).unwrap();
+    one_call["daily"][0]["weather"] = serde_json::json!([{"id": 500, "main": "Rain", "description": "light rain",
+        "icon": "10d"}]);
+    (status, one_call.to_string())
+}
+
+fn daily(server: &MockServer, args: &[&str]) -> String {
+    let output = server.tinywx().arg("--daily").args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn the_high_and_low_of_today() {
+    let server = MockServer::api();
+    assert_eq!(daily(&server, &["--coords", COORDS]), "14°/8°\n");
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1, "{:?}", requests);
+    assert!(requests[0].starts_with("/data/3.0/onecall?lat=52.374&lon=4.8897") && requests[0].contains(PARTS));
+}
+
+#[test]
+fn with_the_icon_of_the_day() {
+    let server = MockServer::start(with_weather);
+    assert_eq!(daily(&server, &["--coords", COORDS, "--icons", "emoji"]), "🌧️ 14°/8°\n");
+    assert_eq!(daily(&server, &["--coords", COORDS, "--icons", "text", "--no-cache"]), "rain 14°/8°\n");
+}
+
+#[test]
+fn a_city_takes_the_coordinates_of_its_weather() {
+    let server = MockServer::api();
+    assert_eq!(daily(&server, &["-c", "amsterdam", "-C", "nl"]), "14°/8°\n");
+    let requests = server.requests();
+    assert!(requests[0].starts_with("/data/2.5/weather?q=amsterdam%2Cnl"), "{:?}", requests);
+    assert!(requests[1].starts_with("/data/3.0/onecall?lat=52.374&lon=4.8897"), "{:?}", requests);
+}
+
+#[test]
+fn daily_goes_alone() {
+    let server = MockServer::api();
+    for other in [&["-t", "{temp}"][..], &["--forecast", "3"], &["--at-offset", "3h"]] {
+        let output = server.tinywx().args(["--coords", COORDS, "--daily"]).args(other).output().unwrap();
+        assert_eq!(output.status.code(), Some(2), "{:?}", other);
+    }
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
@@ -0,0 +1,99 @@
+//! Deprecated names, an old flag, an old config key and an old item name:
+//! they keep working, with a single notice a run listing each and what
+//! replaces it, and with `--strict` they are a usage error instead.
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Output;
+
+use common::{MockServer, COORDS};
+
+/// Writes `contents` to a config file of the home of `server`, and returns
+/// its path.
+fn config_file(server: &MockServer, contents: &str) -> PathBuf {
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("old.toml");
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn run(server: &MockServer, args: &[&str]) -> Output {
+    server.tinywx().args(args).output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+fn stderr(output: &Output) -> &str {
+    std::str::from_utf8(&output.stderr).unwrap()
+}
+
+#[test]
+fn an_old_flag_works_with_a_notice() {
+    let server = MockServer::api();
+    let path = config_file(&server, &format!("coords = \"{}\"\ndata = [\"temp\"]\n", COORDS));
+    let output = run(&server, &["--file", path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "12°\n");
+    assert_eq!(stderr(&output), "warning: --file is deprecated; use --config instead\n");
+
+    let output = run(&server, &["--config", path.to_str().unwrap()]);
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn an_old_key_works_with_a_notice() {
+    let server = MockServer::api();
+    let path = config_file(&server, &format!("coords = \"{}\"\nimperial = true\n", COORDS));
+    let path = path.to_str().unwrap();
+    let output = run(&server, &["-f", path, "-d", "temp"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let notice = format!("warning: imperial in {} is deprecated; use units = \"imperial\" instead\n", path);
+    assert_eq!(stderr(&output), notice);
+    assert!(server.requests().iter().all(|path| path.contains("units=imperial")), "{:?}", server.requests());
+}
+
+#[test]
+fn one_notice_lists_all_of_them() {
+    let server = MockServer::api();
+    let path = config_file(&server, &format!("coords = \"{}\"\nimperial = true\n", COORDS));
+    let path = path.to_str().unwrap();
+    let output = run(&server, &["--file", path, "-d", "temp", "time"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let notice = format!(
+        "warning: these are deprecated:\n  --file: use --config instead\n  imperial in {}: use units = \"imperial\" \
+         instead\n  item 'time': use 'obs_time' instead\n",
+        path
+    );
+    assert_eq!(stderr(&output), notice);
+}
+
+#[test]
+fn strict_makes_them_a_usage_error() {
+    let server = MockServer::api();
+    let path = config_file(&server, &format!("coords = \"{}\"\n", COORDS));
+    let output = run(&server, &["--file", path.to_str().unwrap(), "-d", "temp", "--strict"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert_eq!(stderr(&output), "--file is deprecated; use --config instead\n");
+
+    let path = config_file(&server, &format!("coords = \"{}\"\nimperial = true\n", COORDS));
+    let output = run(&server, &["-f", path.to_str().unwrap(), "-d", "temp", "--strict"]);
+    assert_eq!(output.status.code(), Some(2));
+    let notice = format!("imperial in {} is deprecated; use units = \"imperial\" instead", path.display());
+    assert!(stderr(&output).contains(&notice), "{}", stderr(&output));
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
+
+#[test]
+fn config_check_flags_old_keys() {
+    let server = MockServer::api();
+    let path = config_file(&server, "city = \"amsterdam\"\nimperial = true\n");
+    let output = server.tinywx().args(["config", "check"]).arg(&path).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let notice = format!("imperial in {} is deprecated; use units = \"imperial\" instead", path.display());
+    assert!(stdout(&output).contains(&notice), "{}", stdout(&output));
+}
@@ -0,0 +1,90 @@
+//! Exit codes a script can tell failures apart by, against a local stand-in
+//! for the API: 0 with one line and nothing on stderr, 2 for what is missing
+//! from the arguments, as an empty API key, and 4 for errors of the API, with
+//! or without `--quiet`. A key that only does not look right is a warning.
+
+mod common;
+
+use std::process::Output;
+
+use common::{MockServer, COORDS};
+
+const INVALID_KEY: &str = r#"{"cod":401,"message":"Invalid API key. Please see https://openweathermap.org/faq#error401 for more info."}"#;
+const NOT_FOUND: &str = r#"{"cod":"404","message":"city not found"}"#;
+
+/// Runs tinywx with `args` against a server that answers every request with
+/// `status` and `body`.
+fn run(status: u16, body: &'static str, args: &[&str]) -> Output {
+    let server = MockServer::start(move |_| (status, body.to_string()));
+    server.tinywx().args(args).output().unwrap()
+}
+
+#[test]
+fn success_is_0_with_one_line() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["--coords", COORDS, "-d", "temp", "humidity"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12° 81%\n");
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+}
+
+#[test]
+fn an_invalid_key_is_4() {
+    let output = run(401, INVALID_KEY, &["--coords", COORDS, "-d", "temp"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("401") && stderr.contains("Invalid API key"), "{}", stderr);
+}
+
+#[test]
+fn a_city_not_found_is_4() {
+    let output = run(404, NOT_FOUND, &["-c", "nowhere", "-C", "nl", "-d", "temp", "--no-query-fallback"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("city not found"), "{}", stderr);
+}
+
+#[test]
+fn quiet_keeps_the_code_without_the_message() {
+    for (status, body) in [(401, INVALID_KEY), (404, NOT_FOUND)] {
+        let output = run(status, body, &["--coords", COORDS, "-d", "temp", "--quiet"]);
+        assert_eq!(output.status.code(), Some(4), "{}", status);
+        assert!(output.stdout.is_empty() && output.stderr.is_empty(), "{}: {:?}", status, output);
+    }
+}
+
+#[test]
+fn a_missing_key_is_2() {
+    let server = MockServer::api();
+    let output = server.tinywx().env_remove("TINYWX_API_KEY").args(["--coords", COORDS, "-d", "temp"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("API key provided: pass -k"), "{}", stderr);
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
+
+#[test]
+fn an_empty_key_is_2() {
+    for key in ["", "  "] {
+        let server = MockServer::api();
+        let output = server.tinywx().args(["--coords", COORDS, "-d", "temp", "--api-key", key]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2), "{:?}", key);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("set api_key, api_key_cmd, TINYWX_API_KEY or OPENWEATHERMAP_API_KEY"), "{}", stderr);
+        assert!(server.requests().is_empty(), "{:?}", server.requests());
+    }
+}
+
+#[test]
+fn a_key_that_does_not_look_right_is_a_warning() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["--coords", COORDS, "-d", "temp", "--api-key", "abc123"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12°\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let warning = "warning: the API key does not look like an OpenWeatherMap key, which has 32 \
+        hexadecimal characters\n";
+    assert_eq!(stderr, warning);
+}
@@ -0,0 +1,72 @@
+//! `--explain` against a local stand-in for the API: what it says on stderr
+//! about an item computed from others and about one the cache answered, and
+//! the same in the JSON object.
+
+mod common;
+
+use common::{MockServer, COORDS};
+use serde_json::Value;
+
+/// Runs tinywx with `args` and `--explain`, and returns what it printed to
+/// stdout and to stderr.
+fn explain(server: &MockServer, args: &[&str]) -> (String, String) {
+    let output = server.tinywx().args(["--coords", COORDS]).args(args).arg("--explain").output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    (String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+}
+
+#[test]
+fn a_computed_item_names_its_formula() {
+    let server = MockServer::api();
+    let (stdout, stderr) = explain(&server, &["-d", "temp", "dew_point"]);
+    assert_eq!(stdout.lines().count(), 1, "{}", stdout);
+    let lines: Vec<&str> = stderr.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "temp: data/2.5/weather (live), raw 12.35",
+            "dew_point: data/2.5/weather (live), computed by Magnus formula, raw temp 12.35, humidity 81",
+        ]
+    );
+}
+
+#[test]
+fn a_cached_item_says_so() {
+    let server = MockServer::api();
+    let (_, live) = explain(&server, &["-d", "temp"]);
+    assert_eq!(live, "temp: data/2.5/weather (live), raw 12.35\n");
+    let (_, cached) = explain(&server, &["-d", "temp"]);
+    assert_eq!(cached, "temp: data/2.5/weather (cached), raw 12.35\n");
+    assert_eq!(server.requests().len(), 1, "{:?}", server.requests());
+}
+
+#[test]
+fn json_has_the_explanations_under_explain() {
+    let server = MockServer::api();
+    let json = |server: &MockServer| {
+        let (stdout, _) = explain(server, &["-d", "temp", "dew_point", "-o", "json"]);
+        serde_json::from_str::<Value>(&stdout).unwrap()
+    };
+    let live = json(&server);
+    assert_eq!(live["temp"], "12°");
+    let explain = live["explain"].as_array().unwrap();
+    assert_eq!(explain.len(), 2);
+    assert_eq!(explain[1]["item"], "dew_point");
+    assert_eq!(explain[1]["endpoints"], serde_json::json!(["data/2.5/weather"]));
+    assert_eq!(explain[1]["formula"], "Magnus formula");
+    assert_eq!(explain[1]["raw"], "temp 12.35, humidity 81");
+    assert_eq!(explain[1]["cached"], false);
+    assert!(explain[1]["fallback"].is_null());
+
+    let cached = json(&server);
+    assert_eq!(cached["explain"][0]["cached"], true);
+    assert_eq!(cached["explain"][1]["cached"], true);
+}
+
+#[test]
+fn without_explain_json_has_no_explanations() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["--coords", COORDS, "-d", "temp", "-o", "json"]).output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "{\"temp\":\"12°\"}\n");
+    assert!(output.stderr.is_empty());
+}
@@ -0,0 +1,73 @@
+//! The simpler forms of a city query tinywx tries when the API does not find
+//! the city as it was given, against a local stand-in for the API that finds
+//! none of them by name.
+
+mod common;
+
+use common::MockServer;
+
+const NOT_FOUND: &str = r#"{"cod":"404","message":"city not found"}"#;
+
+const PLACES: &str = r#"[{"name": "New York", "state": "New York", "country": "US", "lat": 40.7127, "lon": -74.006}]"#;
+
+/// A server that finds the city by its coordinates only, and answers the
+/// geocoding API with `geocoded`.
+fn server(geocoded: (u16, &'static str)) -> MockServer {
+    MockServer::start(move |path| {
+        if path.starts_with("/data/2.5/weather?q=") {
+            (404, NOT_FOUND.to_string())
+        } else if path.starts_with("/geo/1.0/direct") {
+            (geocoded.0, geocoded.1.to_string())
+        } else {
+            common::api(path)
+        }
+    })
+}
+
+/// Returns the paths requested, without the API key.
+fn requests(server: &MockServer) -> Vec<String> {
+    let key = "&appid=0123456789abcdef0123456789abcdef";
+    server.requests().iter().map(|path| path.replace(key, "")).collect()
+}
+
+const NEW_YORK: [&str; 6] = ["-c", "new york", "-s", "ny", "-C", "us"];
+
+#[test]
+fn the_ladder_ends_at_the_coordinates() {
+    let server = server((200, PLACES));
+    let output = server.tinywx().args(NEW_YORK).args(["-d", "temp"]).output().unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{}", stderr);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12°\n");
+    assert!(stderr.contains("was not found as given; found it by the coordinates of the geocoding API"), "{}", stderr);
+    assert_eq!(
+        requests(&server),
+        [
+            "/data/2.5/weather?q=new+york%2Cny%2Cus&units=metric",
+            "/data/2.5/weather?q=new+york%2Cus&units=metric",
+            "/geo/1.0/direct?q=new+york%2Cny%2Cus&limit=5",
+            "/data/2.5/weather?lat=40.7127&lon=-74.006&units=metric",
+        ]
+    );
+}
+
+#[test]
+fn a_failed_geocoding_keeps_the_error_of_the_query() {
+    for geocoded in [(500, r#"{"cod":500,"message":"Internal error"}"#), (200, "[]")] {
+        let server = server(geocoded);
+        let output = server.tinywx().args(NEW_YORK).args(["-d", "temp"]).output().unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert_eq!(output.status.code(), Some(4), "{}", stderr);
+        assert!(stderr.contains("/data/2.5/weather?q=new+york%2Cny%2Cus"), "{}", stderr);
+        assert!(stderr.contains("city not found") && !stderr.contains("geo/1.0"), "{}", stderr);
+        assert_eq!(requests(&server).len(), 3, "{:?}", server.requests());
+    }
+}
+
+#[test]
+fn no_query_fallback_asks_once() {
+    let server = server((200, PLACES));
+    let output = server.tinywx().args(NEW_YORK).args(["-d", "temp", "--no-query-fallback"]).output().unwrap();
+    assert!(!output.status.success());
+    assert_eq!(requests(&server), ["/data/2.5/weather?q=new+york%2Cny%2Cus&units=metric"]);
+}
@@ -0,0 +1,95 @@
+//! `--forecast N`: the `data` items of each of the next N steps of the
+//! forecast, a line each, from the forecast alone; and today's range of
+//! `today_high` and `today_low`, from the forecast, or from the current
+//! weather when there is none.
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use common::{MockServer, COORDS, CURRENT};
+use serde_json::Value;
+
+#[test]
+fn each_step_is_a_line_of_the_items() {
+    let server = MockServer::api();
+    let args = ["--coords", COORDS, "-d", "temp", "humidity", "description", "--forecast", "3"];
+    let output = server.tinywx().args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, "11° 80% light rain\n12° 80% light rain\n13° 80% light rain\n");
+    assert!(server.requests().iter().all(|path| path.starts_with("/data/2.5/forecast")), "{:?}", server.requests());
+}
+
+#[test]
+fn no_more_lines_than_the_forecast_has_steps() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["--coords", COORDS, "-d", "temp", "--forecast", "40"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().collect::<Vec<_>>(), ["11°", "12°", "13°", "14°", "15°", "16°", "17°", "18°"]);
+}
+
+#[test]
+fn steps_out_of_range_are_a_usage_error() {
+    for steps in ["0", "41", "three"] {
+        let server = MockServer::api();
+        let output = server.tinywx().args(["--coords", COORDS, "-d", "temp", "--forecast", steps]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2), "{}", steps);
+        assert!(server.requests().is_empty(), "{:?}", server.requests());
+    }
+    let server = MockServer::api();
+    let output = server.tinywx().args(["--coords", COORDS, "-t", "{temp}", "--forecast", "3"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn today_is_no_narrower_than_now() {
+    let server = MockServer::api();
+    let args = ["--coords", COORDS, "-d", "today_high", "today_low", "-o", "json"];
+    let output = server.tinywx().args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let degrees = |name: &str| json[name].as_str().unwrap().trim_end_matches('°').parse::<i64>().unwrap();
+    assert!(degrees("today_high") >= 12 && degrees("today_low") <= 12, "{}", json);
+    assert!(server.requested("/data/2.5/forecast"), "{:?}", server.requests());
+}
+
+#[test]
+fn without_a_forecast_the_current_range_stands_in() {
+    // The first reading is warmer than the range of the current weather,
+    // the next is the fixture's; both are of now, so of the same day.
+    let readings = AtomicUsize::new(0);
+    let server = MockServer::start(move |path| {
+        if !path.starts_with("/data/2.5/weather") {
+            return (500, r#"{"cod":500,"message":"Internal error"}"#.to_string());
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+        let reading = readings.fetch_add(1, Ordering::SeqCst) as i64;
+        let mut current: Value = serde_json::from_str(CURRENT).unwrap();
+        current["dt"] = (now - 60 + reading).into();
+        if reading == 0 {
+            current["main"]["temp"] = 20.0.into();
+        }
+        (200, current.to_string())
+    });
+    let args = ["--coords", COORDS, "-d", "temp", "today_high", "today_low", "--no-cache"];
+    let run = |history: bool| {
+        let mut tinywx = server.tinywx();
+        tinywx.args(args);
+        if history {
+            tinywx.arg("--history");
+        }
+        let output = tinywx.output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        (String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+    };
+
+    let (stdout, stderr) = run(true);
+    assert_eq!(stdout, "20° 20° 11°\n");
+    assert!(stderr.starts_with("warning: forecast unavailable, today's range is the current weather's"), "{}", stderr);
+    // The reading of 20° is kept in the history, and widens the range.
+    assert_eq!(run(true).0, "12° 20° 11°\n");
+    assert_eq!(run(false).0, "12° 13° 11°\n");
+}
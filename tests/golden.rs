@@ -0,0 +1,159 @@
+//! Output that snapshots can rely on: every output format renders the same
+//! fixture to the same bytes every time, and to the golden file of the format
+//! in `tests/golden`, the card at a few terminal widths too, as do two and
+//! three locations in one run, whose lines can do without labels, and a pipe
+//! gets the plain format by default. Raw JSON is the same whatever the items,
+//! and has a golden file pretty-printed too. A change to the output that is meant has
+//! to update the golden files; run with `UPDATE_GOLDEN=1` to write them anew.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{MockServer, COORDS};
+use serde_json::Value;
+
+/// Items of the current weather only, so that nothing depends on the time of
+/// the run.
+const ITEMS: [&str; 15] = [
+    "icon",
+    "temp",
+    "feels_like",
+    "temp_min",
+    "temp_max",
+    "humidity",
+    "pressure",
+    "wind",
+    "wind_dir",
+    "clouds",
+    "visibility",
+    "city",
+    "sunrise",
+    "sunset",
+    "description",
+];
+
+/// The output formats, as `-o` names them.
+const FORMATS: [&str; 5] = ["plain", "json", "card", "waybar", "raw-json"];
+
+/// Widths of the terminal, as `COLUMNS` gives them, the card is cut to.
+const WIDTHS: [&str; 2] = ["24", "12"];
+
+fn render(server: &MockServer, format: &str) -> Vec<u8> {
+    let output = server.tinywx().args(["--coords", COORDS, "-o", format, "-d"]).args(ITEMS).output().unwrap();
+    assert!(output.status.success(), "-o {}: {}", format, String::from_utf8_lossy(&output.stderr));
+    output.stdout
+}
+
+/// Checks `rendered` against the golden file `name`, after writing it there
+/// with `UPDATE_GOLDEN`.
+fn assert_golden(rendered: Vec<u8>, name: &str) {
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&golden, &rendered).unwrap();
+    }
+    let expected = fs::read(&golden).unwrap_or_else(|e| panic!("{}: {}", golden.display(), e));
+    assert_eq!(String::from_utf8(rendered).unwrap(), String::from_utf8(expected).unwrap(), "{}", name);
+}
+
+#[test]
+fn every_format_renders_the_same_twice() {
+    let server = MockServer::api();
+    for format in FORMATS {
+        assert_eq!(render(&server, format), render(&server, format), "-o {}", format);
+    }
+}
+
+#[test]
+fn every_format_renders_its_golden_file() {
+    let server = MockServer::api();
+    for format in FORMATS {
+        assert_golden(render(&server, format), format);
+    }
+}
+
+#[test]
+fn the_card_is_cut_to_the_terminal_width() {
+    let server = MockServer::api();
+    for width in WIDTHS {
+        let mut tinywx = server.tinywx();
+        tinywx.env("COLUMNS", width).args(["--coords", COORDS, "-o", "card", "-d"]).args(ITEMS);
+        let output = tinywx.output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        let stdout = String::from_utf8(output.stdout.clone()).unwrap();
+        let width: usize = width.parse().unwrap();
+        assert!(stdout.lines().all(|line| line.chars().count() <= width), "{}", stdout);
+        assert_golden(output.stdout, &format!("card-{}", width));
+    }
+}
+
+#[test]
+fn a_pipe_gets_the_plain_format() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["--coords", COORDS, "-d"]).args(ITEMS).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(output.stdout, render(&server, "plain"));
+    assert_golden(output.stdout, "plain");
+}
+
+#[test]
+fn raw_json_is_everything_whatever_the_items() {
+    let server = MockServer::api();
+    let raw = render(&server, "raw-json");
+    let output = server.tinywx().args(["--coords", COORDS, "-o", "raw-json", "-d", "temp"]).output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), String::from_utf8(raw.clone()).unwrap());
+
+    let output = server.tinywx().args(["--coords", COORDS, "-o", "raw-json", "--pretty"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let pretty: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(pretty, serde_json::from_slice::<Value>(&raw).unwrap());
+    assert_golden(output.stdout, "raw-json-pretty");
+}
+
+/// Three `[[locations]]`, with labels of their own, one of wide characters.
+const LOCATIONS: &str = "data = [\"temp\", \"humidity\"]\n\n\
+    [[locations]]\ncoords = \"52.374,4.8897\"\nname = \"Amsterdam\"\n\n\
+    [[locations]]\ncoords = \"35.6895,139.6917\"\nname = \"東京\"\n\n\
+    [[locations]]\ncoords = \"52.4385,4.8262\"\nname = \"Zaandam\"\n";
+
+#[test]
+fn two_locations_render_their_golden_files() {
+    let server = MockServer::api();
+    for format in FORMATS {
+        let mut tinywx = server.tinywx();
+        let output = tinywx
+            .args(["--coords", "-", "--label-from", "query", "-d", "temp", "humidity", "-o", format])
+            .write_stdin("52.374,4.8897\n52.4385,4.8262\n")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "-o {}: {}", format, String::from_utf8_lossy(&output.stderr));
+        assert_golden(output.stdout, &format!("locations-2-{}", format));
+    }
+}
+
+#[test]
+fn three_locations_render_their_golden_files() {
+    let server = MockServer::api();
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("locations.toml");
+    fs::write(&path, LOCATIONS).unwrap();
+    for format in FORMATS {
+        let output = server.tinywx().arg("-f").arg(&path).args(["-o", format]).output().unwrap();
+        assert!(output.status.success(), "-o {}: {}", format, String::from_utf8_lossy(&output.stderr));
+        assert_golden(output.stdout, &format!("locations-3-{}", format));
+    }
+}
+
+#[test]
+fn no_labels_leaves_the_lines_alone() {
+    let server = MockServer::api();
+    let mut tinywx = server.tinywx();
+    let output = tinywx
+        .args(["--coords", "-", "--no-labels", "-d", "temp", "humidity"])
+        .write_stdin("52.374,4.8897\n52.4385,4.8262\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12° 81%\n12° 81%\n");
+}
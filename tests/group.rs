@@ -0,0 +1,63 @@
+//! Several cities by id with a repeated `--city-id`, against a local stand-in
+//! for the API: one request of the group endpoint, a line for each city, a
+//! warning for ids it does not know, and the limit of 20 ids.
+
+mod common;
+
+use common::{MockServer, CURRENT};
+use serde_json::{json, Value};
+
+/// Answers the group endpoint with Amsterdam and London, whatever the ids,
+/// and everything else as the API does.
+fn api(path: &str) -> common::Response {
+    if !path.starts_with("/data/2.5/group") {
+        return common::api(path);
+    }
+    let list: Vec<Value> = [(2759794, "Amsterdam", 12.35), (2643743, "London", 9.8)]
+        .into_iter()
+        .map(|(id, name, temp)| {
+            let mut weather: Value = serde_json::from_str(CURRENT).unwrap();
+            weather["id"] = id.into();
+            weather["name"] = name.into();
+            weather["main"]["temp"] = temp.into();
+            weather
+        })
+        .collect();
+    (200, json!({"cnt": list.len(), "list": list}).to_string())
+}
+
+#[test]
+fn each_city_is_a_line_of_one_request() {
+    let server = MockServer::start(api);
+    let args = ["--city-id", "2759794", "--city-id", "2643743", "-d", "temp", "humidity"];
+    let output = server.tinywx().args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "Amsterdam  12° 81%\nLondon     10° 81%\n");
+    assert_eq!(server.requests().len(), 1, "{:?}", server.requests());
+    assert!(server.requested("/data/2.5/group?id=2759794%2C2643743&"), "{:?}", server.requests());
+}
+
+#[test]
+fn ids_not_found_are_warned_of() {
+    let server = MockServer::start(api);
+    let args = ["--city-id", "2759794", "--city-id", "1", "--city-id", "2643743", "-d", "temp", "--no-labels"];
+    let output = server.tinywx().args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12°\n10°\n");
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "warning: no city with id 1\n");
+}
+
+#[test]
+fn more_than_20_ids_are_2() {
+    let server = MockServer::start(api);
+    let ids: Vec<String> = (1..=21).map(|id| id.to_string()).collect();
+    let mut tinywx = server.tinywx();
+    for id in &ids {
+        tinywx.args(["--city-id", id]);
+    }
+    let output = tinywx.args(["-d", "temp"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("21 city ids given, but at most 20 can be fetched together"), "{}", stderr);
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
@@ -0,0 +1,49 @@
+//! Icon sets, picked with `--icons` or the `icons` key of the config file,
+//! on the light rain of the fixture.
+
+mod common;
+
+use std::fs;
+
+use common::{MockServer, COORDS};
+
+fn icon(server: &MockServer, args: &[&str]) -> String {
+    let output = server.tinywx().args(["--coords", COORDS, "-d", "icon"]).args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn the_config_file_picks_the_set_and_the_flag_beats_it() {
+    let server = MockServer::api();
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("icons.toml");
+    for (set, rain) in [("emoji", "🌧️"), ("text", "rain"), ("ascii", "rain"), ("nerd", "\u{e371}")] {
+        fs::write(&path, format!("icons = \"{}\"\n", set)).unwrap();
+        assert_eq!(icon(&server, &["-f", path.to_str().unwrap()]), format!("{}\n", rain), "{}", set);
+    }
+    assert_eq!(icon(&server, &["-f", path.to_str().unwrap(), "--icons", "text"]), "rain\n");
+    assert_eq!(icon(&server, &["--icons", "ascii"]), "rain\n");
+}
+
+#[test]
+fn an_unknown_set_is_a_usage_error() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["--coords", COORDS, "-d", "icon", "--icons", "emojis"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
+
+#[test]
+fn unknown_codes_are_a_question_mark() {
+    let server = MockServer::start(|path| {
+        if path.starts_with("/data/2.5/weather") {
+            (200, common::CURRENT.replace("\"10d\"", "\"42d\""))
+        } else {
+            common::api(path)
+        }
+    });
+    for set in ["nerdfont", "emoji", "text"] {
+        assert_eq!(icon(&server, &["--icons", set]), "?\n", "{}", set);
+    }
+}
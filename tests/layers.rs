@@ -0,0 +1,144 @@
+//! Settings from a config file given with `-f`, from `TINYWX_*` variables,
+//! from the command line, and from all of them, where variables override the
+//! file and flags override both: what tinywx asks the API and prints, and
+//! where `config show --origins` says each value came from.
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use common::MockServer;
+
+/// Writes `contents` to a config file of the home of `server`, and returns
+/// its path.
+fn config_file(server: &MockServer, contents: &str) -> PathBuf {
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("layers.toml");
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+/// Runs tinywx with `args`, and returns what it printed.
+fn weather(server: &MockServer, args: &[&str]) -> String {
+    let output = server.tinywx().args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Runs `config show --origins` with `args`, and returns the value and the
+/// origin of the key `name`.
+fn shown(server: &MockServer, args: &[&str], name: &str) -> (String, String) {
+    shown_with(server, &[], args, name)
+}
+
+/// Returns what [`shown`] does, with the environment variables `vars` set.
+fn shown_with(server: &MockServer, vars: &[(&str, &str)], args: &[&str], name: &str) -> (String, String) {
+    let mut tinywx = server.tinywx();
+    tinywx.envs(vars.iter().copied());
+    let output = tinywx.args(["config", "show", "--origins"]).args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let line = stdout.lines().find(|line| line.split_whitespace().next() == Some(name)).unwrap();
+    // The key, the value and the origin, in columns of 18 and 24.
+    (line[19..43].trim_end().to_string(), line[44..].to_string())
+}
+
+/// Returns the paths requested of the weather API, without the API key.
+fn weather_requests(server: &MockServer) -> Vec<String> {
+    let key = "&appid=0123456789abcdef0123456789abcdef";
+    let requests = server.requests();
+    requests.iter().filter(|path| path.starts_with("/data/2.5/weather")).map(|path| path.replace(key, "")).collect()
+}
+
+#[test]
+fn file_only() {
+    let server = MockServer::api();
+    let path = config_file(&server, "city = \"amsterdam\"\ncountry = \"nl\"\ndata = [\"temp\", \"humidity\"]\n");
+    let path = path.to_str().unwrap();
+    let file = format!("file {}", path);
+
+    assert_eq!(weather(&server, &["-f", path]), "12° 81%\n");
+    assert_eq!(weather_requests(&server), ["/data/2.5/weather?q=amsterdam%2Cnl&units=metric"]);
+    assert_eq!(shown(&server, &["-f", path], "city"), ("\"amsterdam\"".to_string(), file.clone()));
+    assert_eq!(shown(&server, &["-f", path], "country"), ("\"nl\"".to_string(), file.clone()));
+    assert_eq!(shown(&server, &["-f", path], "data"), ("[\"temp\", \"humidity\"]".to_string(), file));
+    assert_eq!(shown(&server, &["-f", path], "units"), ("\"metric\"".to_string(), "default".to_string()));
+}
+
+#[test]
+fn command_line_only() {
+    let server = MockServer::api();
+    let args = ["-c", "amsterdam", "-C", "nl", "-d", "temp", "humidity", "--units", "imperial"];
+    let command_line = "command line".to_string();
+
+    assert_eq!(weather(&server, &args), "12° 81%\n");
+    assert_eq!(weather_requests(&server), ["/data/2.5/weather?q=amsterdam%2Cnl&units=imperial"]);
+    assert_eq!(shown(&server, &args, "city"), ("\"amsterdam\"".to_string(), command_line.clone()));
+    assert_eq!(shown(&server, &args, "country"), ("\"nl\"".to_string(), command_line.clone()));
+    assert_eq!(shown(&server, &args, "data"), ("[\"temp\", \"humidity\"]".to_string(), command_line.clone()));
+    assert_eq!(shown(&server, &args, "units"), ("\"imperial\"".to_string(), command_line));
+}
+
+#[test]
+fn flags_override_the_file() {
+    let server = MockServer::api();
+    let path = config_file(&server, "city = \"amsterdam\"\ncountry = \"nl\"\ndata = [\"temp\", \"humidity\"]\n");
+    let path = path.to_str().unwrap();
+    let args = ["-f", path, "-d", "humidity", "--units", "imperial"];
+    let file = format!("file {}", path);
+    let command_line = "command line".to_string();
+
+    assert_eq!(weather(&server, &args), "81%\n");
+    assert_eq!(weather_requests(&server), ["/data/2.5/weather?q=amsterdam%2Cnl&units=imperial"]);
+    assert_eq!(shown(&server, &args, "city"), ("\"amsterdam\"".to_string(), file.clone()));
+    assert_eq!(shown(&server, &args, "country"), ("\"nl\"".to_string(), file));
+    assert_eq!(shown(&server, &args, "data"), ("[\"humidity\"]".to_string(), command_line.clone()));
+    assert_eq!(shown(&server, &args, "units"), ("\"imperial\"".to_string(), command_line));
+}
+
+#[test]
+fn variables_override_the_file_and_flags_override_both() {
+    let server = MockServer::api();
+    let path = config_file(&server, "city = \"amsterdam\"\ncountry = \"nl\"\nunits = \"metric\"\n");
+    let path = path.to_str().unwrap();
+    let vars = [("TINYWX_UNITS", "imperial"), ("TINYWX_DATA", "temp,humidity")];
+    let env = |name: &str| format!("env {}", name);
+
+    let mut tinywx = server.tinywx();
+    let output = tinywx.envs(vars).args(["-f", path]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12° 81%\n");
+    assert_eq!(weather_requests(&server), ["/data/2.5/weather?q=amsterdam%2Cnl&units=imperial"]);
+    let units = shown_with(&server, &vars, &["-f", path], "units");
+    assert_eq!(units, ("\"imperial\"".to_string(), env("TINYWX_UNITS")));
+    let data = shown_with(&server, &vars, &["-f", path], "data");
+    assert_eq!(data, ("[\"temp\", \"humidity\"]".to_string(), env("TINYWX_DATA")));
+
+    let args = ["-f", path, "--units", "standard"];
+    assert_eq!(shown_with(&server, &vars, &args, "units"), ("\"standard\"".to_string(), "command line".to_string()));
+    assert_eq!(shown_with(&server, &vars, &args, "city"), ("\"amsterdam\"".to_string(), format!("file {}", path)));
+}
+
+#[test]
+fn what_is_missing_after_merging_is_named() {
+    let server = MockServer::api();
+    let path = config_file(&server, "country = \"nl\"\n");
+    let output = server.tinywx().args(["-f", path.to_str().unwrap(), "-d", "temp"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("a city and country, a zip code and country, or coordinates, are required"), "{}", stderr);
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
+
+#[test]
+fn unit_styles_per_item_override_the_format_table() {
+    let server = MockServer::api();
+    let contents = "coords = \"52.374,4.8897\"\ndata = [\"temp\", \"feels_like\", \"wind\", \"humidity\"]\n\n\
+        [format]\nunit_style = \"verbose\"\n\n[format.items.wind]\nunit_style = \"compact\"\n";
+    let path = config_file(&server, contents);
+    let path = path.to_str().unwrap();
+    assert_eq!(weather(&server, &["-f", path]), "12 degrees 12 degrees 6m/s 81%\n");
+    assert_eq!(weather(&server, &["-f", path, "--unit-style", "spaced"]), "12 °C 12 °C 6m/s 81%\n");
+    assert_eq!(weather(&server, &["-f", path, "--units", "imperial"]), "12 degrees 12 degrees 6mph 81%\n");
+}
@@ -0,0 +1,90 @@
+//! The `[[locations]]` of a config file against a local stand-in for the
+//! API that does not know one of them: a line for each of the others,
+//! labeled, the error of the one on stderr, a failure only when every one
+//! fails, a location of the command line in their place, and watch mode and
+//! the subcommands refusing them.
+
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+use common::MockServer;
+
+const NOT_FOUND: &str = r#"{"cod":"404","message":"city not found"}"#;
+
+/// Returns a server answering as the API does, except for Paris, which it
+/// does not know.
+fn server() -> MockServer {
+    MockServer::start(|path| {
+        if path.contains("lat=48.8566") {
+            (404, NOT_FOUND.to_string())
+        } else {
+            common::api(path)
+        }
+    })
+}
+
+/// Writes a config file of the temperature at `locations`, as coordinates
+/// and names, to the home of `server`, and returns its path.
+fn config_file(server: &MockServer, locations: &[(&str, &str)]) -> PathBuf {
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("locations.toml");
+    let mut contents = "data = [\"temp\"]\n".to_string();
+    for (coords, name) in locations {
+        contents.push_str(&format!("\n[[locations]]\ncoords = \"{}\"\nname = \"{}\"\n", coords, name));
+    }
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn run(server: &MockServer, path: &Path, args: &[&str]) -> Output {
+    server.tinywx().arg("-f").arg(path).args(args).output().unwrap()
+}
+
+#[test]
+fn a_failing_location_leaves_the_others() {
+    let server = server();
+    let locations = [("52.374,4.8897", "home"), ("48.8566,2.3522", "Paris"), ("52.4385,4.8262", "office")];
+    let output = run(&server, &config_file(&server, &locations), &[]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "home    12°\noffice  12°\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("city not found"), "{}", stderr);
+    let weather = server.requests().iter().filter(|path| path.starts_with("/data/2.5/weather")).count();
+    assert_eq!(weather, 3);
+}
+
+#[test]
+fn the_run_fails_only_when_every_one_does() {
+    let server = server();
+    let path = config_file(&server, &[("48.8566,2.3522", "Paris"), ("48.8566,2.3522", "Paris again")]);
+    let output = run(&server, &path, &[]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("city not found"));
+}
+
+#[test]
+fn a_location_of_the_command_line_takes_their_place() {
+    let server = server();
+    let path = config_file(&server, &[("52.374,4.8897", "home"), ("52.4385,4.8262", "office")]);
+    let output = run(&server, &path, &["--coords", "35.6895,139.6917"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12°\n");
+    assert!(server.requests().iter().all(|path| !path.contains("lat=52.")), "{:?}", server.requests());
+}
+
+#[test]
+fn watch_mode_and_the_subcommands_take_one_location() {
+    let server = server();
+    let path = config_file(&server, &[("52.374,4.8897", "home"), ("52.4385,4.8262", "office")]);
+    for args in [&["--watch", "600"][..], &["forecast"]] {
+        let output = run(&server, &path, args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("[[locations]]"), "{:?}: {}", args, stderr);
+    }
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
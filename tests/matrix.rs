@@ -0,0 +1,121 @@
+//! Every item in every output format, against a local stand-in for the API:
+//! items render their value, or the placeholder with a warning when this
+//! build cannot render them or the request they need fails. Run with and
+//! without the default features, as `scripts/feature-matrix.sh` does, to
+//! cover the builds without One Call.
+
+mod common;
+
+use common::{MockServer, COORDS};
+use serde_json::Value;
+use wx::item::{Endpoint, ITEMS, PLACEHOLDER};
+use wx::DataItem;
+
+/// The output formats, as `-o` names them.
+const FORMATS: [&str; 5] = ["plain", "json", "card", "waybar", "raw-json"];
+
+/// Items that may render empty with all requests answered: there is no
+/// history for a trend, the golden hour depends on the time of the run, and
+/// a station right at the coordinates has no bearing.
+const MAY_BE_EMPTY: [DataItem; 3] = [DataItem::Trend, DataItem::GoldenHour, DataItem::StationBearing];
+
+struct Run {
+    stdout: String,
+    stderr: String,
+}
+
+fn run(server: &MockServer, format: &str, items: &[&str]) -> Run {
+    let output = server.tinywx().args(["--coords", COORDS, "-o", format, "-d"]).args(items).output().unwrap();
+    let run = Run {
+        stdout: String::from_utf8(output.stdout).unwrap(),
+        stderr: String::from_utf8(output.stderr).unwrap(),
+    };
+    assert!(output.status.success(), "-o {} -d {}: {}", format, items.join(" "), run.stderr);
+    assert!(!run.stderr.contains("panicked"), "-o {} -d {}: {}", format, items.join(" "), run.stderr);
+    run
+}
+
+/// Returns the rendered values of a JSON run, by item name.
+fn values(run: &Run) -> serde_json::Map<String, Value> {
+    match serde_json::from_str(&run.stdout).unwrap() {
+        Value::Object(values) => values,
+        other => panic!("not an object: {}", other),
+    }
+}
+
+#[test]
+fn every_item_renders_in_every_format() {
+    let server = MockServer::api();
+    for info in ITEMS {
+        for format in FORMATS {
+            let run = run(&server, format, &[info.name]);
+            let warning = format!("warning: {} renders {}", info.name, PLACEHOLDER);
+            assert_eq!(run.stderr.contains(&warning), info.item.unsupported().is_some(), "{}", run.stderr);
+            match format {
+                "json" => {
+                    let value = values(&run)[info.name].as_str().unwrap().to_string();
+                    if info.item.unsupported().is_some() {
+                        assert_eq!(value, PLACEHOLDER, "{}", info.name);
+                    } else {
+                        assert_ne!(value, PLACEHOLDER, "{}", info.name);
+                        assert!(!value.is_empty() || MAY_BE_EMPTY.contains(&info.item), "{} is empty", info.name);
+                    }
+                }
+                "plain" if info.item.unsupported().is_some() => assert_eq!(run.stdout, format!("{}\n", PLACEHOLDER)),
+                "waybar" => {
+                    let status: Value = serde_json::from_str(&run.stdout).unwrap();
+                    if info.item.unsupported().is_some() {
+                        assert_eq!(status["text"], PLACEHOLDER);
+                    }
+                }
+                "raw-json" => {
+                    let report: Value = serde_json::from_str(&run.stdout).unwrap();
+                    assert_eq!(report["place"]["name"], "Amsterdam", "{}", run.stdout);
+                }
+                _ => assert!(!run.stdout.trim().is_empty() || MAY_BE_EMPTY.contains(&info.item)),
+            }
+        }
+    }
+}
+
+#[test]
+fn items_of_failed_requests_render_the_placeholder() {
+    let server = MockServer::start(|path| {
+        if path.starts_with("/data/2.5/weather") {
+            common::api(path)
+        } else {
+            (401, r#"{"cod":401,"message":"Invalid API key."}"#.to_string())
+        }
+    });
+    let items: Vec<DataItem> = ITEMS
+        .iter()
+        .map(|info| info.item)
+        .filter(|&x| [Endpoint::OneCall, Endpoint::AirPollution, Endpoint::AirQuality].iter().any(|&e| x.needs(e)))
+        .chain([DataItem::NextRain])
+        .collect();
+    let names: Vec<&str> = items.iter().map(|x| x.name()).collect();
+    for format in FORMATS {
+        let run = run(&server, format, &names);
+        if format == "json" {
+            let values = values(&run);
+            for name in &names {
+                assert_eq!(values[*name], PLACEHOLDER, "{}", name);
+            }
+        }
+        assert!(run.stderr.contains("forecast unavailable"), "{}", run.stderr);
+        assert!(run.stderr.contains("air quality unavailable"), "{}", run.stderr);
+        assert!(run.stderr.contains("air pollution forecast unavailable"), "{}", run.stderr);
+        let one_call = if cfg!(feature = "onecall") { "One Call data unavailable" } else { "uvi renders ?" };
+        assert!(run.stderr.contains(one_call), "{}", run.stderr);
+    }
+}
+
+#[test]
+fn unsupported_items_take_no_request() {
+    let server = MockServer::api();
+    let run = run(&server, "json", &["temp", "uvi", "moon_phase"]);
+    assert_eq!(server.requested("/data/3.0/onecall"), cfg!(feature = "onecall"), "{}", run.stderr);
+    let plan = server.tinywx().args(["--coords", COORDS, "--plan", "-d", "uvi"]).output().unwrap();
+    let plan = String::from_utf8(plan.stdout).unwrap();
+    assert_eq!(plan.contains("data/3.0/onecall"), cfg!(feature = "onecall"), "{}", plan);
+}
@@ -0,0 +1,72 @@
+//! The next_rain item against a local stand-in for the API, whose forecast
+//! has a chance of rain of 80% in its second step, six hours after the hour
+//! of the request, and of 10% in the others: the step it finds with the
+//! threshold and the hours of the flags and of a config file, "dry" or empty
+//! when it finds none, and `?` when the forecast cannot be fetched.
+
+mod common;
+
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use common::{MockServer, COORDS};
+
+/// Returns the local time in Amsterdam, as the mock's forecast has it, of
+/// its step `step`.
+fn step_time(step: i64) -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    let hour = now - now % 3600;
+    format!("{:02}:00", (hour + step * 10800 + 7200).rem_euclid(86400) / 3600)
+}
+
+/// Runs tinywx for next_rain with `args`, and returns what it printed.
+fn next_rain(server: &MockServer, args: &[&str]) -> String {
+    let mut tinywx = server.tinywx();
+    let output = tinywx.args(["--coords", COORDS, "-d", "next_rain", "--no-cache"]).args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn the_first_step_over_the_threshold() {
+    let server = MockServer::api();
+    assert_eq!(next_rain(&server, &[]), format!("rain 80% @{}\n", step_time(2)));
+    assert_eq!(next_rain(&server, &["--rain-threshold", "80"]), format!("rain 80% @{}\n", step_time(2)));
+    assert_eq!(next_rain(&server, &["--rain-threshold", "10"]), format!("rain 10% @{}\n", step_time(1)));
+    assert_eq!(next_rain(&server, &["--rain-threshold", "81"]), "dry\n");
+    let requests = server.requests();
+    assert!(requests.iter().any(|path| path.starts_with("/data/2.5/forecast?")), "{:?}", requests);
+}
+
+#[test]
+fn only_within_the_hours() {
+    let server = MockServer::api();
+    // The second step is five to six hours away.
+    assert_eq!(next_rain(&server, &["--rain-hours", "4"]), "dry\n");
+    assert_eq!(next_rain(&server, &["--rain-hours", "6"]), format!("rain 80% @{}\n", step_time(2)));
+}
+
+#[test]
+fn from_a_config_file() {
+    let server = MockServer::api();
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("next_rain.toml");
+    fs::write(&path, "rain_threshold = 90\nrain_hours = 24\n").unwrap();
+    let path = path.to_str().unwrap();
+    assert_eq!(next_rain(&server, &["-f", path]), "dry\n");
+    assert_eq!(next_rain(&server, &["-f", path, "--rain-threshold", "50"]), format!("rain 80% @{}\n", step_time(2)));
+    fs::write(path, "rain_threshold = 90\n\n[format]\ndry = \"empty\"\n").unwrap();
+    assert_eq!(next_rain(&server, &["-f", path]), "\n");
+}
+
+#[test]
+fn a_forecast_that_cannot_be_fetched_is_a_placeholder() {
+    let server = MockServer::start(|path| {
+        if path.starts_with("/data/2.5/forecast") {
+            (500, "{\"cod\":500,\"message\":\"Internal error\"}".to_string())
+        } else {
+            common::api(path)
+        }
+    });
+    assert_eq!(next_rain(&server, &[]), "?\n");
+}
@@ -0,0 +1,57 @@
+//! Icons at night with `--night-dim`, in each output format.
+
+mod common;
+
+use common::{MockServer, COORDS, CURRENT};
+use serde_json::Value;
+
+/// Starts a server whose current weather has the icon `icon`.
+fn server(icon: &'static str) -> MockServer {
+    MockServer::start(move |path| {
+        let (status, body) = common::api(path);
+        (status, if body == CURRENT { body.replace("\"10d\"", &format!("\"{}\"", icon)) } else { body })
+    })
+}
+
+fn run(server: &MockServer, args: &[&str]) -> String {
+    let output = server.tinywx().args(["--coords", COORDS, "-d", "icon", "temp"]).args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn classes(line: &str) -> Vec<String> {
+    let status: Value = serde_json::from_str(line).unwrap();
+    status["class"].as_array().unwrap().iter().map(|class| class.as_str().unwrap().to_string()).collect()
+}
+
+#[test]
+fn text_formats_dim_the_icon() {
+    let night = server("04n");
+    assert!(run(&night, &["-o", "plain", "--night-dim"]).starts_with("\x1b[90m"));
+    assert!(run(&night, &["-o", "card", "--night-dim"]).contains("\x1b[90m"));
+    assert!(run(&night, &["-o", "plain", "--night-dim", "--dim-style", "polybar"]).starts_with("%{F#808080}"));
+    assert!(!run(&night, &["-o", "plain", "--night-dim", "--dim-style", "waybar"]).contains(['\x1b', '%']));
+    assert!(!run(&night, &["-o", "plain"]).contains('\x1b'));
+    assert!(!run(&server("04d"), &["-o", "plain", "--night-dim"]).contains('\x1b'));
+}
+
+#[test]
+fn waybar_gets_a_night_class_instead() {
+    let night = server("04n");
+    for style in ["ansi", "polybar", "waybar"] {
+        let line = run(&night, &["-o", "waybar", "--night-dim", "--dim-style", style]);
+        assert!(!line.contains("\\u001b") && !line.contains("%{"), "{}", line);
+        assert_eq!(classes(&line), ["rain", "light", "night"]);
+    }
+    assert_eq!(classes(&run(&night, &["-o", "waybar"])), ["rain", "light"]);
+    assert_eq!(classes(&run(&server("04d"), &["-o", "waybar", "--night-dim"])), ["rain", "light"]);
+}
+
+#[test]
+fn json_is_not_dimmed() {
+    let night = server("04n");
+    for format in ["json", "raw-json"] {
+        let line = run(&night, &["-o", format, "--night-dim"]);
+        assert!(!line.contains("\\u001b") && !line.contains('\x1b'), "{}", line);
+    }
+}
@@ -0,0 +1,97 @@
+//! `--notify` against a local stand-in for the API, with a `notify-send` of
+//! the test's own on the `PATH` that writes down what it was asked to show:
+//! the line as the summary, the description, feels like and wind as the body,
+//! critical beyond the limits of `[urgency]`, and the line printed all the
+//! same when there is no `notify-send` or it fails.
+
+#![cfg(all(unix, feature = "notify"))]
+
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+use common::{MockServer, COORDS};
+
+/// Puts a `notify-send` running `script` in the `bin` directory of the home
+/// of `server`, and returns the directory.
+fn notify_send(server: &MockServer, script: &str) -> PathBuf {
+    let bin = server.home().join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let path = bin.join("notify-send");
+    fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    bin
+}
+
+/// Runs tinywx for the temperature with `--notify`, `args` and `bin` as the
+/// `PATH`.
+fn notify(server: &MockServer, bin: &Path, args: &[&str]) -> Output {
+    let mut tinywx = server.tinywx();
+    tinywx.env("PATH", bin).args(["--coords", COORDS, "-d", "temp", "--notify"]).args(args);
+    tinywx.output().unwrap()
+}
+
+/// Returns the arguments the stand-in `notify-send` was run with, one per
+/// line, the lines of the body too.
+fn notified(server: &MockServer) -> String {
+    fs::read_to_string(server.home().join("notified")).unwrap()
+}
+
+const RECORD: &str = "printf '%s\\n' \"$@\" > \"$HOME/notified\"";
+
+#[test]
+fn the_line_is_the_summary_of_the_notification() {
+    let server = MockServer::api();
+    let bin = notify_send(&server, RECORD);
+    let output = notify(&server, &bin, &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12°\n");
+    let expected = "--app-name\ntinywx\n--urgency\nnormal\n12°\nlight rain\nfeels like 12°\nwind 6\n";
+    assert_eq!(notified(&server), expected);
+}
+
+#[test]
+fn beyond_the_limits_it_is_critical() {
+    let server = MockServer::api();
+    let bin = notify_send(&server, RECORD);
+    let path = server.home().join("notify.toml");
+    fs::write(&path, "[urgency]\nmetric = [-10, 10]\n").unwrap();
+    let output = notify(&server, &bin, &["-f", path.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(notified(&server).starts_with("--app-name\ntinywx\n--urgency\ncritical\n"), "{}", notified(&server));
+}
+
+#[test]
+fn the_line_is_printed_when_the_notification_cannot_be_sent() {
+    let server = MockServer::api();
+    let bin = notify_send(&server, "echo 'no daemon' >&2\nexit 1");
+    let output = notify(&server, &bin, &[]);
+    assert!(!output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12°\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("is a notification daemon running? no daemon"), "{}", stderr);
+
+    let empty = server.home().join("empty");
+    fs::create_dir_all(&empty).unwrap();
+    let output = notify(&server, &empty, &[]);
+    assert!(!output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12°\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("cannot run notify-send"), "{}", stderr);
+}
+
+#[test]
+fn it_takes_a_single_location() {
+    let server = MockServer::api();
+    let bin = notify_send(&server, RECORD);
+    let mut tinywx = server.tinywx();
+    tinywx.env("PATH", &bin).args(["--coords", "-", "-d", "temp", "--notify"]).write_stdin(COORDS);
+    let output = tinywx.output().unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(output.status.code(), Some(2), "{}", stderr);
+    assert!(stderr.contains("--notify works with a single location"), "{}", stderr);
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
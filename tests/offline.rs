@@ -0,0 +1,75 @@
+//! Cities looked up in the database built in with the offline-geo feature:
+//! with `--offline` instead of the geocoding API, and in its stead when it
+//! cannot be reached.
+
+mod common;
+
+use common::MockServer;
+
+/// Returns the paths requested of the API, without the API key.
+fn requested(server: &MockServer) -> Vec<String> {
+    let key = "&appid=0123456789abcdef0123456789abcdef";
+    server.requests().iter().map(|path| path.replace(key, "")).collect()
+}
+
+#[cfg(feature = "offline-geo")]
+#[test]
+fn offline_queries_by_the_coordinates_of_the_database() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["-c", "paris", "-C", "fr", "-d", "temp", "--offline"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12°\n");
+    assert_eq!(requested(&server), ["/data/2.5/weather?lat=48.85&lon=2.35&units=metric"]);
+
+    let output = server.tinywx().args(["-c", "nowhere", "-C", "fr", "-d", "temp", "--offline"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("is not in the database of cities built in"), "{}", stderr);
+    assert_eq!(requested(&server).len(), 1);
+}
+
+#[cfg(feature = "offline-geo")]
+#[test]
+fn offline_resolves_to_the_places_of_the_database() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["-c", "springfield", "-C", "us", "--resolve", "--offline"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let expected = "1. Springfield, US  37.2200,-93.3000\n\
+                    2. Springfield, US  42.1000,-72.5900\n\
+                    3. Springfield, US  39.8000,-89.6400\n";
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
+
+#[cfg(feature = "offline-geo")]
+#[test]
+fn the_database_stands_in_for_a_geocoding_api_out_of_reach() {
+    // Geocoding takes longer than the timeout, which fails its request
+    // as the network would; the weather is answered after it.
+    let server = MockServer::start(|path| {
+        if path.starts_with("/geo/") {
+            std::thread::sleep(std::time::Duration::from_millis(1300));
+        }
+        common::api(path)
+    });
+    let args = ["-c", "amsterdam", "-C", "nl", "-d", "temp", "--geocode", "--timeout", "1"];
+    let output = server.tinywx().args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12°\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("finding \"amsterdam, nl\" in the database of cities built in"), "{}", stderr);
+    let requests = requested(&server);
+    assert!(requests[0].starts_with("/geo/1.0/direct?q=amsterdam%2Cnl"), "{:?}", requests);
+    assert_eq!(requests[1..], ["/data/2.5/weather?lat=52.37&lon=4.89&units=metric"]);
+}
+
+#[cfg(not(feature = "offline-geo"))]
+#[test]
+fn offline_needs_the_feature() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["-c", "paris", "-C", "fr", "-d", "temp", "--offline"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("built without the offline-geo feature"), "{}", stderr);
+    assert!(requested(&server).is_empty(), "{:?}", server.requests());
+}
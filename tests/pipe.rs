@@ -0,0 +1,52 @@
+//! Output into a pipe whose reader is gone, as with `tinywx | head -1`: the
+//! reader closing the pipe ends the output, without a panic or a failure.
+
+mod common;
+
+use std::io;
+use std::process::Stdio;
+
+use common::{MockServer, COORDS};
+
+/// Runs tinywx with `args` and its stdout a pipe closed before it starts,
+/// and checks that it succeeds and says nothing.
+fn into_a_closed_pipe(server: &MockServer, args: &[&str]) {
+    let (reader, writer) = io::pipe().unwrap();
+    drop(reader);
+    let output = server.spawnable().args(args).stdout(writer).stderr(Stdio::piped()).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success() && stderr.is_empty(), "{}: {:?} {}", args.join(" "), output.status, stderr);
+}
+
+#[test]
+fn listings_end_with_their_reader() {
+    let server = MockServer::api();
+    for flag in ["--list-data", "--list-transforms"] {
+        into_a_closed_pipe(&server, &[flag]);
+    }
+}
+
+#[test]
+fn the_weather_ends_with_its_reader() {
+    let server = MockServer::api();
+    for format in ["plain", "json", "card", "waybar"] {
+        into_a_closed_pipe(&server, &["--coords", COORDS, "-o", format, "-d", "icon", "temp"]);
+    }
+    into_a_closed_pipe(&server, &["--coords", COORDS, "-o", "raw-json", "--pretty"]);
+    into_a_closed_pipe(&server, &["--coords", COORDS, "--plan", "-d", "temp"]);
+    into_a_closed_pipe(&server, &["--version", "-v"]);
+}
+
+#[test]
+fn the_subcommands_end_with_their_reader() {
+    let server = MockServer::api();
+    std::fs::create_dir_all(server.home()).unwrap();
+    into_a_closed_pipe(&server, &["config", "show", "--origins", "-c", "amsterdam"]);
+    into_a_closed_pipe(&server, &["config", "show", "--watch", "300", "-c", "amsterdam"]);
+    let file = server.home().join("tinywx.toml");
+    std::fs::write(&file, "city = \"amsterdam\"\n").unwrap();
+    into_a_closed_pipe(&server, &["config", "check", file.to_str().unwrap()]);
+    into_a_closed_pipe(&server, &["cache", "stats"]);
+    into_a_closed_pipe(&server, &["completions", "bash"]);
+    into_a_closed_pipe(&server, &["config", "init"]);
+}
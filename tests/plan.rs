@@ -0,0 +1,74 @@
+//! `--plan` and `--max-requests` against a local stand-in for the API: the
+//! requests planned with a cold and a warm cache, by coordinates and by city
+//! name, and runs over the budget stopped before their first request.
+
+mod common;
+
+use common::{MockServer, COORDS};
+
+/// Runs tinywx with `args` and `--plan`, and returns the plan.
+fn plan(server: &MockServer, args: &[&str]) -> String {
+    let output = server.tinywx().args(args).arg("--plan").output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Runs tinywx with `args`, which succeeds.
+fn run(server: &MockServer, args: &[&str]) {
+    let output = server.tinywx().args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn plans_by_coordinates_cold_and_warm() {
+    let server = MockServer::api();
+    let args = ["--coords", COORDS, "-d", "temp", "today_high", "ventilation_window"];
+    let cold = plan(&server, &args);
+    assert_eq!(
+        cold,
+        "GET data/2.5/weather                 by coordinates\n\
+         GET data/2.5/forecast                by the location, as the current weather\n\
+         GET data/2.5/air_pollution/forecast  by the coordinates of the current weather\n\
+         3 requests\n"
+    );
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+
+    run(&server, &args);
+    let warm = plan(&server, &args);
+    assert_eq!(warm.lines().filter(|line| line.ends_with(" (cached)")).count(), 3, "{}", warm);
+    assert!(warm.ends_with("\n0 requests\n"), "{}", warm);
+    // An item of an endpoint not fetched yet is the only request.
+    let more = plan(&server, &["--coords", COORDS, "-d", "temp", "aqi"]);
+    assert!(more.ends_with("\n1 request\n"), "{}", more);
+}
+
+#[test]
+fn plans_by_city_name_then_by_the_stored_id() {
+    let server = MockServer::api();
+    let args = ["-c", "amsterdam", "-C", "nl", "-d", "temp", "today_high"];
+    let by_name = plan(&server, &args);
+    assert!(by_name.starts_with("GET data/2.5/weather                 by city name\n"), "{}", by_name);
+    assert!(by_name.ends_with("\n2 requests\n"), "{}", by_name);
+
+    run(&server, &[&args[..], &["--no-cache"]].concat());
+    let by_id = plan(&server, &[&args[..], &["--no-cache"]].concat());
+    assert!(by_id.starts_with("GET data/2.5/weather                 by city id 2759794, stored"), "{}", by_id);
+    assert!(by_id.ends_with("\n2 requests, 3 if the stored city id is out of date\n"), "{}", by_id);
+}
+
+#[test]
+fn runs_over_the_budget_make_no_request() {
+    let server = MockServer::api();
+    let args = ["--coords", COORDS, "-d", "temp", "today_high", "--max-requests", "1"];
+    let output = server.tinywx().args(args).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("this could take 2 API requests, more than --max-requests 1 (see --plan)"), "{}", stderr);
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+
+    run(&server, &["--coords", COORDS, "-d", "temp", "today_high", "--max-requests", "2"]);
+    assert_eq!(server.requests().len(), 2, "{:?}", server.requests());
+    // Once cached, the same run fits a budget of none.
+    run(&server, &["--coords", COORDS, "-d", "temp", "today_high", "--max-requests", "0"]);
+    assert_eq!(server.requests().len(), 2, "{:?}", server.requests());
+}
@@ -0,0 +1,43 @@
+//! The ventilation window against a local stand-in for the API: fetched only
+//! for the items that need the pollution forecast, cached like the other
+//! endpoints, and rendered in the local time of the location.
+
+mod common;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use common::{MockServer, COORDS};
+
+/// Runs tinywx with `items`, and returns what it printed.
+fn weather(server: &MockServer, items: &[&str]) -> String {
+    let output = server.tinywx().args(["--coords", COORDS, "-d"]).args(items).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", items, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Returns how many requests of `path` the server answered.
+fn requested(server: &MockServer, path: &str) -> usize {
+    server.requests().iter().filter(|request| request.starts_with(path)).count()
+}
+
+#[test]
+fn the_window_is_the_cleanest_hours_in_local_time() {
+    let server = MockServer::api();
+    // The stand-in's air is cleanest in the hour under way and the next,
+    // and Amsterdam is two hours ahead of UTC.
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let local = (now / 3600 + 2) % 24;
+    let expected = format!("12° best air {:02}:00–{:02}:00\n", local, (local + 2) % 24);
+    assert_eq!(weather(&server, &["temp", "ventilation_window"]), expected);
+}
+
+#[test]
+fn the_forecast_is_fetched_when_needed_and_cached() {
+    let server = MockServer::api();
+    weather(&server, &["temp"]);
+    assert_eq!(requested(&server, "/data/2.5/air_pollution"), 0);
+    let window = weather(&server, &["temp", "ventilation_window"]);
+    assert_eq!(requested(&server, "/data/2.5/air_pollution/forecast"), 1);
+    assert_eq!(weather(&server, &["temp", "ventilation_window"]), window);
+    assert_eq!(requested(&server, "/data/2.5/air_pollution/forecast"), 1);
+}
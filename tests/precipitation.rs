@@ -0,0 +1,40 @@
+//! The rain and snow items against a local stand-in for the API, where it
+//! rains and does not snow: how hard it rains, in each of the units, and how
+//! the dry snow renders, as 0 or, with `dry = "empty"` in `[format]` or for
+//! the item, empty.
+
+mod common;
+
+use std::fs;
+
+use common::{MockServer, COORDS};
+
+/// Runs tinywx for rain, snow and the temperature with a config file of
+/// `contents` and `args`, and returns what it printed.
+fn weather(server: &MockServer, contents: &str, args: &[&str]) -> String {
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("precipitation.toml");
+    fs::write(&path, format!("coords = \"{}\"\ndata = [\"rain\", \"snow\", \"temp\"]\n{}", COORDS, contents)).unwrap();
+    let output = server.tinywx().args(["-f", path.to_str().unwrap(), "--no-cache"]).args(args).output().unwrap();
+    assert!(output.status.success(), "{}: {}", contents, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn rain_per_hour_in_each_of_the_units() {
+    let server = MockServer::api();
+    assert_eq!(weather(&server, "", &[]), "0.4 0 12°\n");
+    assert_eq!(weather(&server, "", &["--unit-style", "compact"]), "0.4mm/h 0mm/h 12°C\n");
+    assert_eq!(weather(&server, "", &["--unit-style", "compact", "--units", "imperial"]), "0.02in/h 0in/h 12°F\n");
+}
+
+#[test]
+fn dry_renders_empty_when_asked() {
+    let server = MockServer::api();
+    assert_eq!(weather(&server, "[format]\ndry = \"zero\"\n", &[]), "0.4 0 12°\n");
+    assert_eq!(weather(&server, "[format]\ndry = \"empty\"\n", &[]), "0.4  12°\n");
+    assert_eq!(weather(&server, "[format]\ndry = \"empty\"\n", &["--separator", " | "]), "0.4 | 12°\n");
+    // Per item, over the table.
+    let contents = "[format]\ndry = \"empty\"\n\n[format.items.snow]\ndry = \"zero\"\n";
+    assert_eq!(weather(&server, contents, &[]), "0.4 0 12°\n");
+}
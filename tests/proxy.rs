@@ -0,0 +1,83 @@
+//! Requests through a proxy with `--proxy`, the `proxy` key or `HTTP_PROXY`,
+//! against a local stand-in for the API that is its own proxy, and root
+//! certificates of `--ca-cert`: what is refused before any request.
+
+mod common;
+
+use std::fs;
+
+use common::{MockServer, COORDS};
+
+/// Answers as the API does, whether asked directly or as a proxy, with the
+/// absolute URL of the API.
+fn proxied(path: &str) -> common::Response {
+    match path.strip_prefix("http://") {
+        Some(rest) => common::api(&rest[rest.find('/').unwrap_or(rest.len())..]),
+        None => common::api(path),
+    }
+}
+
+/// Runs tinywx for the temperature with `args` and the environment variables
+/// `vars`, and returns whether each request went through the proxy.
+fn proxied_requests(server: &MockServer, vars: &[(&str, &str)], args: &[&str]) -> Vec<bool> {
+    let mut tinywx = server.tinywx();
+    tinywx.envs(vars.iter().copied()).args(["--coords", COORDS, "-d", "temp", "--no-cache"]).args(args);
+    let output = tinywx.output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12°\n");
+    server.requests().iter().map(|path| path.starts_with(&server.url)).collect()
+}
+
+/// Runs tinywx with `args`, which it refuses, and returns what it said.
+fn refused(server: &MockServer, args: &[&str]) -> String {
+    let output = server.tinywx().args(["--coords", COORDS, "-d", "temp"]).args(args).output().unwrap();
+    assert!(!output.status.success(), "{:?}", args);
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn the_proxy_of_the_flag_the_key_or_the_environment() {
+    let server = MockServer::start(proxied);
+    assert_eq!(proxied_requests(&server, &[], &[]), [false]);
+    assert_eq!(proxied_requests(&server, &[], &["--proxy", &server.url]), [false, true]);
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("proxy.toml");
+    fs::write(&path, format!("proxy = \"{}\"\n", server.url)).unwrap();
+    let file = ["-f", path.to_str().unwrap()];
+    assert_eq!(proxied_requests(&server, &[], &file), [false, true, true]);
+    let env = [("HTTP_PROXY", server.url.as_str())];
+    assert_eq!(proxied_requests(&server, &env, &[]), [false, true, true, true]);
+    // Hosts of NO_PROXY are asked directly.
+    let env = [("HTTP_PROXY", server.url.as_str()), ("NO_PROXY", "127.0.0.1")];
+    assert_eq!(proxied_requests(&server, &env, &[]), [false, true, true, true, false]);
+}
+
+#[test]
+fn a_bad_proxy_url_is_refused() {
+    let server = MockServer::start(proxied);
+    let stderr = refused(&server, &["--proxy", "ftp://proxy.example:21"]);
+    let expected = "invalid proxy URL 'ftp://proxy.example:21': expected http or https, not ftp";
+    assert!(stderr.contains(expected), "{}", stderr);
+}
+
+#[test]
+fn ca_certificates_that_cannot_be_read_are_refused() {
+    let server = MockServer::start(proxied);
+    let missing = server.home().join("missing.pem");
+    let stderr = refused(&server, &["--ca-cert", missing.to_str().unwrap()]);
+    assert!(stderr.contains(&format!("cannot read the CA certificate {}", missing.display())), "{}", stderr);
+
+    fs::create_dir_all(server.home()).unwrap();
+    let garbage = server.home().join("garbage.pem");
+    fs::write(&garbage, "not a certificate\n").unwrap();
+    let stderr = refused(&server, &["--ca-cert", garbage.to_str().unwrap()]);
+    assert!(stderr.contains("invalid root certificate: no PEM certificate in it"), "{}", stderr);
+
+    // A certificate that can be read is taken, by a build with TLS.
+    if cfg!(any(feature = "native-tls", feature = "rustls")) {
+        let ca = server.home().join("ca.pem");
+        fs::write(&ca, include_str!("../wx/tests/fixtures/ca.pem")).unwrap();
+        assert_eq!(proxied_requests(&server, &[], &["--ca-cert", ca.to_str().unwrap()]), [false]);
+    }
+}
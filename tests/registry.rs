@@ -0,0 +1,36 @@
+//! The registry of items end to end: the name of every item parses back to
+//! it, `--list-data` lists it, and it renders from the fixtures of a local
+//! stand-in for the API.
+
+mod common;
+
+use common::{MockServer, COORDS};
+use serde_json::Value;
+use wx::item::{ITEMS, PLACEHOLDER};
+use wx::DataItem;
+
+#[test]
+fn every_item_round_trips() {
+    let names: Vec<&str> = ITEMS.iter().map(|info| info.name).collect();
+    for info in ITEMS {
+        assert_eq!(info.name.parse::<DataItem>().ok(), Some(info.item), "{}", info.name);
+        assert_eq!(info.item.name(), info.name);
+    }
+
+    let server = MockServer::api();
+    let listing = server.tinywx().arg("--list-data").output().unwrap();
+    assert!(listing.status.success(), "{}", String::from_utf8_lossy(&listing.stderr));
+    let listing = String::from_utf8(listing.stdout).unwrap();
+    let listed: Vec<&str> = listing.lines().map(|line| line.split_whitespace().next().unwrap_or_default()).collect();
+    assert_eq!(listed, names);
+
+    let output = server.tinywx().args(["--coords", COORDS, "-o", "json", "-d"]).args(&names).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    let values: Value = serde_json::from_slice(&output.stdout).unwrap();
+    for info in ITEMS {
+        let value = values[info.name].as_str();
+        assert!(value.is_some(), "{} is not in {}", info.name, values);
+        assert_eq!(value == Some(PLACEHOLDER), info.item.unsupported().is_some(), "{}: {}", info.name, stderr);
+    }
+}
@@ -0,0 +1,95 @@
+//! Listing the places a city name could be with `--resolve`, against a local
+//! stand-in for the API with none, one or several of them, and asking for the
+//! weather at the coordinates of the best one with `--geocode`.
+
+mod common;
+
+use std::fs;
+
+use common::MockServer;
+
+const PLACES: &str = include_str!("../wx/tests/fixtures/springfield.json");
+const SPRINGFIELD: [&str; 5] = ["-c", "springfield", "-C", "us", "--no-cache"];
+
+/// Returns a server with `places` for every name, and the weather of
+/// Amsterdam, wherever it is asked for.
+fn server(places: &'static str) -> MockServer {
+    MockServer::start(move |path| match path.starts_with("/geo/1.0/direct") {
+        true => (200, places.to_string()),
+        false => common::api(path),
+    })
+}
+
+#[test]
+fn several_places_are_numbered_with_their_coordinates() {
+    let server = server(PLACES);
+    let output = server.tinywx().args(SPRINGFIELD).arg("--resolve").output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let expected = concat!(
+        "1. Springfield, Illinois, US  39.7990,-89.6440\n",
+        "2. Springfield, Missouri, US  37.2153,-93.2981\n",
+        "3. Springfield, Massachusetts, US  42.1019,-72.5887\n",
+        "4. Springfield, Ohio, US  39.9242,-83.8088\n",
+        "5. Springfield, Oregon, US  44.0462,-123.0220\n",
+    );
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+    // Only the places are asked for, not the weather.
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1, "{:?}", requests);
+    assert!(requests[0].starts_with("/geo/1.0/direct?q=springfield%2Cus&limit=5"), "{}", requests[0]);
+}
+
+#[test]
+fn one_place_is_listed_alone() {
+    let server = server(r#"[{"name": "Amsterdam", "lat": 52.3727598, "lon": 4.8936041, "country": "NL"}]"#);
+    let output = server.tinywx().args(["-c", "amsterdam", "-C", "nl", "--resolve"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "1. Amsterdam, NL  52.3728,4.8936\n");
+}
+
+#[test]
+fn no_places_is_an_error() {
+    let server = server("[]");
+    let output = server.tinywx().args(SPRINGFIELD).arg("--resolve").output().unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("no places found for \"springfield, us\""), "{}", stderr);
+}
+
+#[test]
+fn places_as_json() {
+    let server = server(PLACES);
+    let output = server.tinywx().args(SPRINGFIELD).args(["--resolve", "-o", "json"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let places: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(places.len(), 5);
+    assert_eq!(places[1]["state"], "Missouri");
+    assert_eq!(places[1]["lat"], 37.2153297);
+}
+
+#[test]
+fn only_cities_are_resolved() {
+    let server = server(PLACES);
+    let output = server.tinywx().args(["--coords", common::COORDS, "--resolve"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    // Coordinates from a config file are refused too.
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("coords.toml");
+    fs::write(&path, format!("coords = \"{}\"\n", common::COORDS)).unwrap();
+    let output = server.tinywx().args(["-f", path.to_str().unwrap(), "--resolve"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("--resolve takes a city, not coordinates or a zip code"), "{}", stderr);
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
+
+#[test]
+fn geocode_asks_for_the_weather_of_the_best_match() {
+    let server = server(PLACES);
+    let output = server.tinywx().args(SPRINGFIELD).args(["--geocode", "-d", "temp"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12°\n");
+    assert!(server.requested("/geo/1.0/direct?q=springfield%2Cus"), "{:?}", server.requests());
+    assert!(server.requested("/data/2.5/weather?lat=39.7990175&lon=-89.6439575"), "{:?}", server.requests());
+}
@@ -0,0 +1,86 @@
+//! Pinning one of the places `--resolve` lists with `--select`.
+
+mod common;
+
+use std::fs;
+
+use common::MockServer;
+
+const PLACES: &str = r#"[
+    {"name": "Springfield", "state": "Illinois", "country": "US", "lat": 39.7990175, "lon": -89.6439575},
+    {"name": "Springfield", "state": "Missouri", "country": "US", "lat": 37.2153297, "lon": -93.2980916}
+]"#;
+
+fn server() -> MockServer {
+    MockServer::start(|path| {
+        if path.starts_with("/geo/1.0/direct") {
+            (200, PLACES.to_string())
+        } else {
+            common::api(path)
+        }
+    })
+}
+
+#[test]
+fn select_pins_the_place_in_the_config_file() {
+    let server = server();
+    fs::create_dir_all(server.home()).unwrap();
+    let config = server.home().join("tinywx.toml");
+    fs::write(&config, "city = \"springfield\"\ncountry = \"us\"\ndata = [\"temp\"]\n").unwrap();
+    let file = config.to_str().unwrap();
+    let output = server.tinywx().args(["-f", file, "--resolve", "--select", "2"]).output().unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{}", stderr);
+    assert!(String::from_utf8(output.stdout).unwrap().starts_with("1. Springfield, Illinois, US  39.7990,-89.6440\n"));
+    assert!(stderr.contains("pinned Springfield, Missouri, US at 37.2153297,-93.2980916"), "{}", stderr);
+    let pinned = fs::read_to_string(&config).unwrap();
+    assert_eq!(
+        pinned,
+        concat!(
+            "# Springfield, Missouri, US, picked with tinywx --resolve\n",
+            "coords = \"37.2153297,-93.2980916\"\n",
+            "data = [\"temp\"]\n",
+        )
+    );
+    // The weather is now for the coordinates.
+    let output = server.tinywx().args(["-f", file, "-o", "plain"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(server.requested("/data/2.5/weather?lat=37.2153297&lon=-93.2980916"), "{:?}", server.requests());
+}
+
+#[test]
+fn select_without_a_default_config_file_creates_one() {
+    let server = server();
+    let output = server.tinywx().args(["-c", "springfield", "-C", "us", "--resolve", "--select", "1"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let pinned = fs::read_to_string(server.home().join("config/tinywx/config.toml")).unwrap();
+    assert!(pinned.ends_with("coords = \"39.7990175,-89.6439575\"\n"), "{}", pinned);
+}
+
+#[test]
+fn select_of_a_place_not_listed_fails() {
+    let server = server();
+    let output = server.tinywx().args(["-c", "springfield", "-C", "us", "--resolve", "--select", "3"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("--select 3: there are 2 places to pick from"), "{}", stderr);
+    assert!(!server.home().join("config").exists());
+}
+
+#[test]
+fn select_asks_only_in_a_terminal() {
+    let server = server();
+    let output =
+        server.tinywx().args(["-c", "springfield", "-C", "us", "--resolve", "--select"]).write_stdin("2\n").output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("pass --select N to pin place N of the list instead"), "{}", stderr);
+    assert!(!server.home().join("config").exists());
+}
+
+#[test]
+fn select_needs_resolve() {
+    let server = server();
+    let output = server.tinywx().args(["-c", "springfield", "-C", "us", "--select", "1"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
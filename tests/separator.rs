@@ -0,0 +1,49 @@
+//! Joining the items of a line with `--separator` and labeling them with
+//! `--item-labels`, or with the `separator` and `item_labels` keys, against a
+//! local stand-in for the API.
+
+mod common;
+
+use std::fs;
+
+use common::{MockServer, COORDS};
+
+/// Runs tinywx for the temperature, the description and the humidity with `args`,
+/// and returns what it printed.
+fn weather(server: &MockServer, args: &[&str]) -> String {
+    let items = ["--coords", COORDS, "-d", "temp", "description", "humidity"];
+    let output = server.tinywx().args(items).args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn separators_join_the_items() {
+    let server = MockServer::api();
+    assert_eq!(weather(&server, &[]), "12° light rain 81%\n");
+    assert_eq!(weather(&server, &["--separator", " | "]), "12° | light rain | 81%\n");
+    assert_eq!(weather(&server, &["--separator", "\\t"]), "12°\tlight rain\t81%\n");
+    assert_eq!(weather(&server, &["--separator", "\\n"]), "12°\nlight rain\n81%\n");
+}
+
+#[test]
+fn labels_prefix_the_values_that_have_one() {
+    let server = MockServer::api();
+    assert_eq!(weather(&server, &["--item-labels"]), "temp:12° light rain hum:81%\n");
+    assert_eq!(weather(&server, &["--item-labels", "--separator", ", "]), "temp:12°, light rain, hum:81%\n");
+    // One item has no separator.
+    let args = ["--coords", COORDS, "-d", "humidity", "--item-labels", "--separator", " | "];
+    let output = server.tinywx().args(args).output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "hum:81%\n");
+}
+
+#[test]
+fn the_keys_set_them_and_flags_beat_them() {
+    let server = MockServer::api();
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("separator.toml");
+    fs::write(&path, "separator = ' \\t '\nitem_labels = true\n").unwrap();
+    let path = path.to_str().unwrap();
+    assert_eq!(weather(&server, &["-f", path]), "temp:12° \t light rain \t hum:81%\n");
+    assert_eq!(weather(&server, &["-f", path, "--separator", "/"]), "temp:12°/light rain/hum:81%\n");
+}
@@ -0,0 +1,106 @@
+//! Secondary outputs against a local stand-in for the API: what they get
+//! besides the line on stdout, what their quiet hours hold back, and that one
+//! which cannot be delivered is a warning, with the line still on stdout.
+
+mod common;
+
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+
+use common::{MockServer, COORDS};
+
+#[test]
+fn an_output_file_that_cannot_be_written_is_a_warning() {
+    let server = MockServer::api();
+    let path = server.home().join("no/such/dir/line");
+    let output = server.tinywx().args(["--coords", COORDS, "-d", "temp", "--output-file"]).arg(&path).output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12°\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let failed = format!("warning: file {} failed: ", path.display());
+    assert!(stderr.starts_with(&failed) && stderr.trim_end().ends_with("; disabled"), "{}", stderr);
+}
+
+#[test]
+fn an_output_file_gets_the_line() {
+    let server = MockServer::api();
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("line");
+    let output = server.tinywx().args(["--coords", COORDS, "-d", "temp", "--output-file"]).arg(&path).output().unwrap();
+    assert!(output.status.success() && output.stderr.is_empty(), "{:?}", output);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "12°\n");
+}
+
+#[test]
+fn the_json_sidecar_has_the_data_of_the_line() {
+    let server = MockServer::api();
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("wx.json");
+    let args = ["--coords", COORDS, "-d", "temp", "humidity", "wind"];
+    let output = server.tinywx().args(args).arg("--json-sidecar").arg(&path).output().unwrap();
+    assert!(output.status.success() && output.stderr.is_empty(), "{:?}", output);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12° 81% 6\n");
+
+    let written = fs::read_to_string(&path).unwrap();
+    let mut sidecar: Value = serde_json::from_str(&written).unwrap();
+    let fetched_at = sidecar.as_object_mut().unwrap().remove("fetched_at").unwrap().as_i64().unwrap();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    assert!((now - 60..=now).contains(&fetched_at), "{} at {}", fetched_at, now);
+
+    // The same fetch, from the cache, as JSON on stdout.
+    let json = server.tinywx().args(args).args(["--output", "json", "--pretty"]).output().unwrap();
+    assert!(json.status.success(), "{}", String::from_utf8_lossy(&json.stderr));
+    assert_eq!(sidecar, serde_json::from_slice::<Value>(&json.stdout).unwrap());
+    assert!(written.starts_with("{\n  \""), "not pretty-printed: {}", written);
+}
+
+#[test]
+fn outputs_of_the_config_file_each_get_their_format_once() {
+    let server = MockServer::api();
+    fs::create_dir_all(server.home()).unwrap();
+    let (file, appended) = (server.home().join("wx.json"), server.home().join("appended"));
+    let config = format!(
+        "coords = \"{}\"\ndata = [\"temp\", \"humidity\"]\n\n\
+         [[outputs]]\ntype = \"file\"\npath = \"{}\"\nformat = \"json\"\n\n\
+         [[outputs]]\ntype = \"exec\"\ncommand = \"cat >> '{}'\"\n",
+        COORDS,
+        file.display(),
+        appended.display()
+    );
+    let path = server.home().join("outputs.toml");
+    fs::write(&path, config).unwrap();
+    let output = server.tinywx().arg("-f").arg(&path).output().unwrap();
+    assert!(output.status.success() && output.stderr.is_empty(), "{:?}", output);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12° 81%\n");
+    assert_eq!(fs::read_to_string(&file).unwrap(), "{\"humidity\":\"81%\",\"temp\":\"12°\"}\n");
+    assert_eq!(fs::read_to_string(&appended).unwrap(), "12° 81%\n");
+}
+
+#[test]
+fn quiet_hours_hold_back_commands_unless_their_own_say_otherwise() {
+    let server = MockServer::api();
+    fs::create_dir_all(server.home()).unwrap();
+    let (held, own, file) = (server.home().join("held"), server.home().join("own"), server.home().join("file"));
+    // Quiet all day, in two windows, one over midnight.
+    let config = format!(
+        "coords = \"{}\"\ndata = [\"temp\"]\nquiet_hours = [\"00:00-12:00\", \"12:00-00:00\"]\n\n\
+         [[outputs]]\ntype = \"exec\"\ncommand = \"cat >> '{}'\"\n\n\
+         [[outputs]]\ntype = \"exec\"\ncommand = \"cat >> '{}'\"\nquiet_hours = []\n\n\
+         [[outputs]]\ntype = \"file\"\npath = \"{}\"\n",
+        COORDS,
+        held.display(),
+        own.display(),
+        file.display()
+    );
+    let path = server.home().join("quiet.toml");
+    fs::write(&path, config).unwrap();
+    let output = server.tinywx().arg("-f").arg(&path).output().unwrap();
+    assert!(output.status.success() && output.stderr.is_empty(), "{:?}", output);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12°\n");
+    assert!(!held.exists(), "delivered in the quiet hours");
+    assert_eq!(fs::read_to_string(&own).unwrap(), "12°\n");
+    // Files get no one's attention, so they have no quiet hours.
+    assert_eq!(fs::read_to_string(&file).unwrap(), "12°\n");
+}
@@ -0,0 +1,46 @@
+//! Items rendered from a forecast slot, against a local stand-in for the API,
+//! whose forecast is 3 hours apart from the hour of the request on, 11° in
+//! the first step and a degree more in each after.
+
+mod common;
+
+use common::{MockServer, COORDS};
+
+#[test]
+fn at_offset_renders_the_items_from_the_nearest_step() {
+    let server = MockServer::api();
+    let args = ["--coords", COORDS, "-d", "temp", "humidity", "--at-offset", "12h"];
+    let output = server.tinywx().args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "14° 80%\n");
+    assert!(server.requested("/data/2.5/forecast"), "{:?}", server.requests());
+}
+
+#[test]
+fn template_slots_render_besides_the_current_weather() {
+    let server = MockServer::api();
+    let template = "{temp} now, {temp@+12h} later, {temp@+1d} tomorrow";
+    let output = server.tinywx().args(["--coords", COORDS, "--template", template]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12° now, 14° later, 18° tomorrow\n");
+}
+
+#[test]
+fn an_offset_beyond_the_forecast_says_how_far_it_reaches() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["--coords", COORDS, "-d", "temp", "--at-offset", "2d"]).output().unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("no forecast 48 hours ahead: it reaches 2"), "{}", stderr);
+
+}
+
+#[test]
+fn an_offset_beyond_five_days_is_refused_before_any_request() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["--coords", COORDS, "-d", "temp", "--at-offset", "6d"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("offset '6d' is beyond the forecast, which reaches 5 days ahead"), "{}", stderr);
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
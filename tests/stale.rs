@@ -0,0 +1,73 @@
+//! `--offline-ok` against a local stand-in for the API that stops answering:
+//! the last weather cached, however old, marked with its age as
+//! `--stale-format` says, and the error as before without it or with nothing
+//! cached.
+
+mod common;
+
+use std::process::Output;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use common::{MockServer, COORDS};
+
+const TEMP: [&str; 4] = ["--coords", COORDS, "-d", "temp"];
+
+/// Returns a server that answers as the API does until `down` is set, and
+/// then too late for a timeout of a second, as if the network were down.
+fn server(down: &Arc<AtomicBool>) -> MockServer {
+    let down = Arc::clone(down);
+    MockServer::start(move |path| {
+        if down.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_millis(1500));
+        }
+        common::api(path)
+    })
+}
+
+/// Runs tinywx for the temperature, with the cache going stale at once and
+/// `args`, while the network is down.
+fn offline(server: &MockServer, args: &[&str]) -> Output {
+    server.tinywx().args(TEMP).args(["--cache-ttl", "0", "--timeout", "1"]).args(args).output().unwrap()
+}
+
+#[test]
+fn the_last_cached_weather_is_marked_with_its_age() {
+    let down = Arc::new(AtomicBool::new(false));
+    let server = server(&down);
+    let output = server.tinywx().args(TEMP).output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12°\n");
+    down.store(true, Ordering::SeqCst);
+
+    let output = offline(&server, &["--offline-ok"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let line = String::from_utf8(output.stdout).unwrap();
+    assert!(line.starts_with("12° (") && line.ends_with("s ago)\n"), "{:?}", line);
+
+    let output = offline(&server, &["--offline-ok", "--stale-format", "[{age} old]"]);
+    let line = String::from_utf8(output.stdout).unwrap();
+    assert!(line.starts_with("12° [") && line.ends_with("s old]\n"), "{:?}", line);
+
+    let output = offline(&server, &["--offline-ok", "-o", "json"]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!((&json["temp"], &json["stale"]), (&"12°".into(), &true.into()), "{}", json);
+}
+
+#[test]
+fn without_it_or_a_cache_the_error_stands() {
+    let down = Arc::new(AtomicBool::new(false));
+    let server = server(&down);
+    server.tinywx().args(TEMP).output().unwrap();
+    down.store(true, Ordering::SeqCst);
+
+    let output = offline(&server, &[]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    // Nothing of an earlier run is cached for other coordinates.
+    let args = ["--coords", "48.8566,2.3522", "-d", "temp", "--timeout", "1", "--offline-ok"];
+    let output = server.tinywx().args(args).output().unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
@@ -0,0 +1,41 @@
+//! The station items against a local stand-in for the API, whose station is
+//! in the middle of Amsterdam: where it is from the given coordinates, and
+//! a placeholder when the query was not by coordinates.
+
+mod common;
+
+use common::{MockServer, COORDS};
+
+/// Runs tinywx with `args` and the station items, and returns what it
+/// printed.
+fn station(server: &MockServer, args: &[&str]) -> String {
+    let output = server.tinywx().args(args).args(["-d", "station_distance", "station_bearing"]).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn the_station_from_the_given_coordinates() {
+    let server = MockServer::api();
+    assert_eq!(station(&server, &["--coords", "52.3,4.8"]), "10 NE\n");
+    assert_eq!(station(&server, &["--coords", "52.3,4.8", "--unit-style", "spaced"]), "10 km NE\n");
+    let imperial = ["--coords", "52.3,4.8", "--units", "imperial", "--unit-style", "compact"];
+    assert_eq!(station(&server, &imperial), "6mi NE\n");
+}
+
+#[test]
+fn the_station_at_the_given_coordinates_has_no_direction() {
+    let server = MockServer::api();
+    // The bearing renders empty, keeping its place.
+    assert_eq!(station(&server, &["--coords", COORDS]), "0 \n");
+}
+
+#[test]
+fn a_query_by_city_has_no_coordinates_to_measure_from() {
+    let server = MockServer::api();
+    let args = ["-c", "amsterdam", "-C", "nl", "-d", "temp", "station_distance", "--explain"];
+    let output = server.tinywx().args(args).output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12° ?\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("fallback: not queried by coordinates, rendered ?"), "{}", stderr);
+}
@@ -0,0 +1,36 @@
+//! Templates against a local stand-in for the API: transforms of the
+//! rendered items, the listing of the transforms, and templates that cannot
+//! be parsed stopped before the first request.
+
+mod common;
+
+use common::{MockServer, COORDS};
+
+#[test]
+fn templates_render_with_their_transforms() {
+    let server = MockServer::api();
+    let template = "{description|title}, {temp|pad:4}| wind from the {wind_deg|compass}";
+    let output = server.tinywx().args(["--coords", COORDS, "--template", template]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "Light Rain, 12° | wind from the SW\n");
+}
+
+#[test]
+fn every_transform_is_listed() {
+    let server = MockServer::api();
+    let output = server.tinywx().arg("--list-transforms").output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let usages: Vec<&str> = stdout.lines().filter_map(|line| line.split_whitespace().next()).collect();
+    assert_eq!(usages, ["upper", "lower", "title", "abs", "round:N", "compass", "time:FMT", "pad:N"]);
+}
+
+#[test]
+fn unknown_transforms_stop_the_run() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["--coords", COORDS, "--template", "{temp|shout}"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("unknown transform 'shout' (see --list-transforms)"), "{}", stderr);
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
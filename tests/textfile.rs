@@ -0,0 +1,117 @@
+//! The textfile of metrics for node_exporter's textfile collector, against a
+//! local stand-in for the API: its golden file in `tests/golden`, and that it
+//! parses as the Prometheus text exposition format, by a small checker of it
+//! here. Run with `UPDATE_GOLDEN=1` to write the golden file anew.
+
+mod common;
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use common::{MockServer, COORDS, CURRENT};
+use serde_json::Value;
+
+/// A sample: the name of its family, its labels, and its value.
+type Sample = (String, Vec<(String, String)>, f64);
+
+/// Checks that `text` is in the text exposition format, as node_exporter reads
+/// it: each family of samples has its `# HELP` and then its `# TYPE`, once,
+/// before its samples; names and label names are valid, label values are
+/// quoted and escaped, and values are numbers. Returns the samples.
+fn parse_exposition(text: &str) -> Vec<Sample> {
+    let valid = |name: &str, colons: bool| {
+        let mut chars = name.chars();
+        let first = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || (colons && c == ':'));
+        first && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || (colons && c == ':'))
+    };
+    assert!(text.ends_with('\n'), "no line feed at the end: {:?}", text);
+    let (mut described, mut typed, mut samples) = (HashSet::new(), HashSet::new(), Vec::new());
+    for line in text.lines() {
+        if let Some(help) = line.strip_prefix("# HELP ") {
+            let (name, _) = help.split_once(' ').unwrap_or_else(|| panic!("no help text: {}", line));
+            assert!(valid(name, true) && described.insert(name.to_string()), "{}", line);
+            continue;
+        }
+        if let Some(type_) = line.strip_prefix("# TYPE ") {
+            let (name, kind) = type_.split_once(' ').unwrap_or_else(|| panic!("no type: {}", line));
+            assert!(described.contains(name) && typed.insert(name.to_string()), "TYPE before HELP: {}", line);
+            assert!(["gauge", "counter", "untyped"].contains(&kind), "{}", line);
+            continue;
+        }
+        assert!(!line.starts_with('#') && !line.is_empty(), "{:?}", line);
+        let (name, rest) = line.split_once('{').unwrap_or_else(|| panic!("no labels: {}", line));
+        assert!(valid(name, true) && typed.contains(name), "sample before its TYPE: {}", line);
+        let (mut labels, mut rest) = (Vec::new(), rest);
+        while let Some(label) = rest.strip_prefix(',').or(Some(rest)).filter(|rest| !rest.starts_with('}')) {
+            let (label_name, value) = label.split_once("=\"").unwrap_or_else(|| panic!("bad label: {}", line));
+            assert!(valid(label_name, false), "{}", line);
+            let (mut unescaped, mut chars) = (String::new(), value.char_indices());
+            let end = loop {
+                match chars.next() {
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, '\\')) => unescaped.push('\\'),
+                        Some((_, '"')) => unescaped.push('"'),
+                        Some((_, 'n')) => unescaped.push('\n'),
+                        other => panic!("bad escape {:?}: {}", other, line),
+                    },
+                    Some((i, '"')) => break i,
+                    Some((_, '\n')) | None => panic!("unterminated label value: {}", line),
+                    Some((_, c)) => unescaped.push(c),
+                }
+            };
+            labels.push((label_name.to_string(), unescaped));
+            rest = &value[end + 1..];
+        }
+        let value = rest.strip_prefix("} ").unwrap_or_else(|| panic!("no value: {}", line));
+        let value: f64 = value.parse().unwrap_or_else(|_| panic!("not a number: {}", line));
+        samples.push((name.to_string(), labels, value));
+    }
+    samples
+}
+
+/// Runs tinywx with `args` and `--textfile`, and returns the textfile.
+fn textfile(server: &MockServer, args: &[&str]) -> String {
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("tinywx.prom");
+    let output = server.tinywx().args(args).arg("--textfile").arg(&path).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    fs::read_to_string(&path).unwrap()
+}
+
+#[test]
+fn the_textfile_renders_its_golden_file() {
+    let server = MockServer::api();
+    let text = textfile(&server, &["--coords", COORDS, "-d", "temp"]);
+    let samples = parse_exposition(&text);
+    let (name, labels, fetched) = samples.last().unwrap();
+    assert_eq!((name.as_str(), labels.len()), ("tinywx_last_success_timestamp_seconds", 1));
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as f64;
+    assert!((now - 60.0..=now).contains(fetched), "{} at {}", fetched, now);
+
+    let text = text.replace(&fetched.to_string(), "1714550400");
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/textfile");
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&golden, &text).unwrap();
+    }
+    assert_eq!(text, fs::read_to_string(&golden).unwrap());
+}
+
+#[test]
+fn labels_are_escaped() {
+    let name = "Home \"A\\B\"\nflat";
+    let server = MockServer::start(move |path| {
+        if !path.starts_with("/data/2.5/weather") {
+            return common::api(path);
+        }
+        let mut current: Value = serde_json::from_str(CURRENT).unwrap();
+        current["name"] = name.into();
+        (200, current.to_string())
+    });
+    let text = textfile(&server, &["--coords", COORDS, "-d", "temp"]);
+    assert!(text.contains("{location=\"Home \\\"A\\\\B\\\"\\nflat\",units=\"metric\"} 12.35\n"), "{}", text);
+    let samples = parse_exposition(&text);
+    assert_eq!(samples.len(), 5);
+    assert!(samples.iter().all(|(_, labels, _)| labels[0] == ("location".to_string(), name.to_string())));
+}
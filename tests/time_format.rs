@@ -0,0 +1,50 @@
+//! Times in a format of the user's, against a local stand-in for the API:
+//! `--time-format` and the `time_format` key of `[format]`, in the zone of
+//! the location, and a format chrono does not understand, which is refused
+//! before anything is asked.
+
+mod common;
+
+use std::fs;
+
+use common::{MockServer, COORDS};
+
+const ITEMS: [&str; 4] = ["obs_time", "sunrise", "sunset", "temp"];
+
+/// Runs tinywx with `args` for the items of [`ITEMS`], and returns what it
+/// printed.
+fn weather(server: &MockServer, args: &[&str]) -> String {
+    let output = server.tinywx().args(["--coords", COORDS, "-d"]).args(ITEMS).args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn times_are_in_the_format_given() {
+    let server = MockServer::api();
+    // The response is of 08:00 UTC, in Amsterdam, 2 hours ahead.
+    assert_eq!(weather(&server, &[]), "10:00:00 06:05 21:15 12°\n");
+    assert_eq!(weather(&server, &["--time-format", "%I:%M %p"]), "10:00 AM 06:05 AM 09:15 PM 12°\n");
+    assert_eq!(weather(&server, &["--time-format", "%H:%M"]), "10:00 06:05 21:15 12°\n");
+}
+
+#[test]
+fn the_format_key_sets_it_and_the_flag_beats_it() {
+    let server = MockServer::api();
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("time.toml");
+    fs::write(&path, "[format]\ntime_format = \"%-I:%M%P\"\n").unwrap();
+    let path = path.to_str().unwrap();
+    assert_eq!(weather(&server, &["-f", path]), "10:00am 6:05am 9:15pm 12°\n");
+    assert_eq!(weather(&server, &["-f", path, "--time-format", "%H.%M"]), "10.00 06.05 21.15 12°\n");
+}
+
+#[test]
+fn an_invalid_format_is_2() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["--coords", COORDS, "-d", "obs_time", "--time-format", "%Q"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("invalid time format '%Q': expected a strftime format"), "{}", stderr);
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
@@ -0,0 +1,76 @@
+//! The trend item against a local stand-in for the API whose readings change
+//! from run to run: no arrow without a reading before, an arrow for each way
+//! the temperature went with the delta of the flag, nothing for a reading
+//! before that is too old, and none for a reading in other units.
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::{MockServer, CURRENT, COORDS};
+use serde_json::Value;
+
+/// Time of the fixture's reading.
+const DT: i64 = 1714550400;
+
+/// Returns a server answering as the API does, with the time and the
+/// temperature of its current weather as `reading` holds them.
+fn server(reading: &Arc<Mutex<(i64, f64)>>) -> MockServer {
+    let reading = Arc::clone(reading);
+    MockServer::start(move |path| {
+        if !path.starts_with("/data/2.5/weather") {
+            return common::api(path);
+        }
+        let (dt, temp) = *reading.lock().unwrap();
+        let mut weather: Value = serde_json::from_str(CURRENT).unwrap();
+        weather["dt"] = dt.into();
+        weather["main"]["temp"] = temp.into();
+        (200, weather.to_string())
+    })
+}
+
+/// Runs tinywx for the trend with `args`, and returns what it printed.
+fn trend(server: &MockServer, args: &[&str]) -> String {
+    let mut tinywx = server.tinywx();
+    let output = tinywx.args(["--coords", COORDS, "-d", "trend", "--no-cache"]).args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn each_way_the_temperature_went() {
+    let reading = Arc::new(Mutex::new((DT, 12.0)));
+    let server = server(&reading);
+    assert_eq!(trend(&server, &[]), "\n");
+    *reading.lock().unwrap() = (DT + 3600, 13.0);
+    assert_eq!(trend(&server, &[]), "↑\n");
+    *reading.lock().unwrap() = (DT + 7200, 12.8);
+    assert_eq!(trend(&server, &[]), "→\n");
+    *reading.lock().unwrap() = (DT + 10800, 12.0);
+    assert_eq!(trend(&server, &[]), "↓\n");
+    // The same reading again is compared with the one before it.
+    assert_eq!(trend(&server, &["--trend-delta", "1"]), "→\n");
+    assert_eq!(trend(&server, &["--accessible"]), "temperature falling\n");
+}
+
+#[test]
+fn a_reading_before_that_is_too_old_is_no_trend() {
+    let reading = Arc::new(Mutex::new((DT, 12.0)));
+    let server = server(&reading);
+    trend(&server, &[]);
+    *reading.lock().unwrap() = (DT + 4 * 3600, 14.0);
+    assert_eq!(trend(&server, &["--trend-hours", "5"]), "↑\n");
+    assert_eq!(trend(&server, &[]), "\n");
+}
+
+#[test]
+fn readings_in_other_units_are_not_compared() {
+    let reading = Arc::new(Mutex::new((DT, 12.0)));
+    let server = server(&reading);
+    trend(&server, &[]);
+    // 54°F, were it compared with 12°C, would have risen.
+    *reading.lock().unwrap() = (DT + 3600, 54.0);
+    assert_eq!(trend(&server, &["--units", "imperial"]), "\n");
+    *reading.lock().unwrap() = (DT + 7200, 54.0);
+    assert_eq!(trend(&server, &["--units", "imperial"]), "→\n");
+}
@@ -0,0 +1,122 @@
+//! Switching units between runs with the same config: the response cached in
+//! the other units is not used, so what is printed is in the new units. And
+//! units = "auto", which goes by the country of the response, unless units
+//! are given otherwise. And pressure and visibility, which the API gives in
+//! hPa and meters whatever the units.
+
+mod common;
+
+use std::fs;
+
+use common::{MockServer, COORDS, CURRENT};
+use serde_json::Value;
+
+/// Answers as the API does, with the current weather in the units asked for.
+fn api(path: &str) -> common::Response {
+    if !(path.starts_with("/data/2.5/weather") && path.contains("units=imperial")) {
+        return common::api(path);
+    }
+    let mut current: Value = serde_json::from_str(CURRENT).unwrap();
+    current["main"]["temp"] = 54.23.into();
+    current["wind"]["speed"] = 12.66.into();
+    (200, current.to_string())
+}
+
+/// Returns a handler that answers as the API does for a place in `country`,
+/// with the current weather in standard units.
+fn standard_in(country: &'static str) -> impl Fn(&str) -> common::Response {
+    move |path| {
+        if !(path.starts_with("/data/2.5/weather") && path.contains("units=standard")) {
+            return api(path);
+        }
+        let mut current: Value = serde_json::from_str(CURRENT).unwrap();
+        current["main"]["temp"] = 285.5.into();
+        current["sys"]["country"] = country.into();
+        (200, current.to_string())
+    }
+}
+
+/// Runs tinywx with a config file of `units`, and returns what it printed.
+fn weather(server: &MockServer, units: &str) -> String {
+    let (stdout, _) = run(server, units, &[]);
+    stdout
+}
+
+/// Runs tinywx with a config file of `units` and `args`, and returns what it
+/// printed, and printed on stderr.
+fn run(server: &MockServer, units: &str, args: &[&str]) -> (String, String) {
+    let dir = server.home().join("config/tinywx");
+    fs::create_dir_all(&dir).unwrap();
+    let config = format!("coords = \"{}\"\ndata = [\"temp\", \"wind\"]\nunits = \"{}\"\n", COORDS, units);
+    fs::write(dir.join("config.toml"), config).unwrap();
+    let output = server.tinywx().args(args).output().unwrap();
+    assert!(output.status.success(), "{}: {}", units, String::from_utf8_lossy(&output.stderr));
+    (String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+}
+
+/// Returns the units of each request of the current weather so far.
+fn fetched(server: &MockServer) -> Vec<String> {
+    let requests = server.requests();
+    let weather = requests.iter().filter(|path| path.starts_with("/data/2.5/weather"));
+    weather.map(|path| path.split("units=").nth(1).unwrap().split('&').next().unwrap().to_string()).collect()
+}
+
+#[test]
+fn switching_units_fetches_the_weather_afresh() {
+    let server = MockServer::start(api);
+    let metric = weather(&server, "metric");
+    assert_eq!(metric, "12° 6\n");
+    assert_eq!(fetched(&server), ["metric"]);
+
+    let imperial = weather(&server, "imperial");
+    assert_eq!(fetched(&server), ["metric", "imperial"]);
+    assert_eq!(imperial, "54° 13\n");
+
+    // Each is cached in its own units.
+    assert_eq!(weather(&server, "imperial"), imperial);
+    assert_eq!(weather(&server, "metric"), metric);
+    assert_eq!(fetched(&server), ["metric", "imperial"]);
+}
+
+#[test]
+fn auto_goes_by_the_country_of_the_response() {
+    let countries = [("NL", "12° 6\n", "metric"), ("US", "54° 13\n", "imperial"), ("mm", "54° 13\n", "imperial")];
+    for (country, line, units) in countries {
+        let server = MockServer::start(standard_in(country));
+        let (stdout, stderr) = run(&server, "auto", &[]);
+        assert_eq!(stdout, line, "{}", country);
+        assert_eq!(stderr, format!("warning: units = \"auto\": {} for {}\n", units, country.to_uppercase()));
+        assert_eq!(fetched(&server), ["standard"]);
+    }
+}
+
+#[test]
+fn units_given_otherwise_beat_auto() {
+    let server = MockServer::start(standard_in("US"));
+    let (stdout, stderr) = run(&server, "auto", &["--units", "metric"]);
+    assert_eq!((stdout.as_str(), stderr.as_str()), ("12° 6\n", ""));
+    let (stdout, _) = run(&server, "auto", &["--imperial"]);
+    assert_eq!(stdout, "54° 13\n");
+    assert_eq!(fetched(&server), ["metric", "imperial"]);
+}
+
+#[test]
+fn pressure_and_visibility_convert_with_imperial_units() {
+    let server = MockServer::start(api);
+    let items = ["-d", "pressure", "visibility", "clouds", "temp_min", "temp_max"];
+    let (metric, _) = run(&server, "metric", &items);
+    assert_eq!(metric, "1012 10 75% 11° 13°\n");
+    let (imperial, _) = run(&server, "imperial", &items);
+    assert_eq!(imperial, "29.88 6.2 75% 11° 13°\n");
+}
+
+#[test]
+fn each_of_the_units_is_labeled_in_compact_style() {
+    let server = MockServer::start(standard_in("NL"));
+    let compact = ["--unit-style", "compact", "-d", "temp"];
+    assert_eq!(run(&server, "metric", &compact).0, "12°C\n");
+    assert_eq!(run(&server, "imperial", &compact).0, "54°F\n");
+    assert_eq!(run(&server, "standard", &compact).0, "286K\n");
+    assert_eq!(run(&server, "standard", &["-d", "temp"]).0, "286\n");
+    assert_eq!(fetched(&server), ["metric", "imperial", "standard"]);
+}
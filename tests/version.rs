@@ -0,0 +1,45 @@
+//! What the build reports of itself: with `--version -v`, and in the JSON
+//! output with `--debug-meta`, each field known or "unknown".
+
+mod common;
+
+use common::{MockServer, COORDS};
+use serde_json::Value;
+
+#[test]
+fn version_verbose_has_a_line_for_each_field() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["--version", "-v"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 5, "{}", stdout);
+    assert_eq!(lines[0], format!("tinywx {}", env!("CARGO_PKG_VERSION")));
+    for (line, field) in lines[1..].iter().zip(["commit:   ", "features: ", "target:   ", "http:     reqwest "]) {
+        let value = line.strip_prefix(field).unwrap_or_else(|| panic!("{:?} is not {:?}", line, field));
+        assert!(!value.trim().is_empty(), "{:?}", line);
+    }
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
+
+#[test]
+fn debug_meta_tells_the_build_in_json() {
+    let server = MockServer::api();
+    let args = ["--coords", COORDS, "-d", "temp", "-o", "json", "--debug-meta"];
+    let output = server.tinywx().args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["temp"], "12°");
+    let meta = json["meta"].as_object().unwrap();
+    let mut keys: Vec<&str> = meta.keys().map(String::as_str).collect();
+    keys.sort_unstable();
+    assert_eq!(keys, ["commit", "dirty", "features", "http", "target", "version"]);
+    assert_eq!(meta["version"], env!("CARGO_PKG_VERSION"));
+    assert!(["true", "false", "unknown"].contains(&meta["dirty"].as_str().unwrap()), "{:?}", meta);
+    assert!(meta["features"].is_array(), "{:?}", meta);
+
+    // Without it, the JSON has no meta.
+    let output = server.tinywx().args(&args[..6]).output().unwrap();
+    let json: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(json.get("meta").is_none(), "{}", json);
+}
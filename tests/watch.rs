@@ -0,0 +1,242 @@
+//! Watch mode against a local stand-in for the API, stopped the ways it is
+//! meant to stop, not held up by a request the API does not answer, and
+//! refreshing within the budget of API calls, with smoothed values besides
+//! the fetched ones in JSON, leaving the JSON sidecar behind, and sending the
+//! daily digest once a day across restarts. Refreshes that would show data
+//! older than `max_staleness` are refused.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use common::{MockServer, COORDS};
+
+#[test]
+fn ctrl_c_exits_with_130_after_a_whole_line() {
+    let server = MockServer::api();
+    let mut child = server
+        .spawnable()
+        .args(["--coords", COORDS, "--watch", "60", "-d", "temp"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "12°\n");
+    let kill = Command::new("kill").args(["-INT", &child.id().to_string()]).status().unwrap();
+    assert!(kill.success());
+    let status = child.wait().unwrap();
+    assert_eq!(status.code(), Some(130));
+    line.clear();
+    assert_eq!(stdout.read_line(&mut line).unwrap(), 0, "{:?}", line);
+}
+
+#[test]
+fn a_request_past_the_timeout_fails_the_refresh_in_about_the_timeout() {
+    let server = MockServer::start(|path| {
+        std::thread::sleep(Duration::from_secs(4));
+        common::api(path)
+    });
+    let started = Instant::now();
+    let mut child = server
+        .spawnable()
+        .args(["--coords", COORDS, "--watch", "60", "--timeout", "1", "-d", "temp"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // With nothing fetched before, the failed refresh shows nothing, and says
+    // why on stderr.
+    let mut stderr = BufReader::new(child.stderr.take().unwrap());
+    let mut line = String::new();
+    stderr.read_line(&mut line).unwrap();
+    let elapsed = started.elapsed();
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert!(line.contains("/data/2.5/weather?") && line.contains("timed out"), "{}", line);
+    assert!(elapsed < Duration::from_millis(2500), "{:?}: {}", elapsed, line);
+}
+
+/// Two endpoints a refresh, on a budget of one call a minute.
+const OVER_BUDGET: [&str; 7] = ["-d", "temp", "today_high", "--call-budget", "1", "--watch", "60"];
+
+#[test]
+fn an_interval_over_the_budget_is_lengthened_with_a_warning() {
+    let server = MockServer::api();
+    let mut child = server
+        .spawnable()
+        .args(["--coords", COORDS])
+        .args(OVER_BUDGET)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stderr = BufReader::new(child.stderr.take().unwrap());
+    let mut line = String::new();
+    stderr.read_line(&mut line).unwrap();
+    child.kill().unwrap();
+    child.wait().unwrap();
+    let warning = "warning: refreshing every 60 seconds makes 2 API calls per minute, more than the budget of 1; \
+        refreshing every 120 seconds instead\n";
+    assert_eq!(line, warning);
+}
+
+#[test]
+fn strict_refuses_an_interval_over_the_budget() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["--coords", COORDS]).args(OVER_BUDGET).arg("--strict").output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("makes 2 API calls per minute, more than the budget of 1"), "{}", stderr);
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
+
+#[test]
+fn config_show_tells_the_tuned_interval() {
+    let server = MockServer::api();
+    let run = |args: &[&str]| {
+        let output = server.tinywx().args(["config", "show", "--coords", COORDS]).args(args).output().unwrap();
+        assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        stdout.lines().last().unwrap().split(" seconds").next().unwrap().to_string()
+    };
+    assert_eq!(run(&OVER_BUDGET), "refreshing every 120");
+    assert_eq!(run(&["-d", "temp", "--watch", "30"]), "refreshing every 60");
+    assert_eq!(run(&["-d", "temp", "--watch", "90"]), "refreshing every 90");
+}
+
+#[test]
+fn max_staleness_refuses_a_watch_that_would_show_older_data() {
+    let server = MockServer::api();
+    let args = ["--coords", COORDS, "-d", "temp", "--watch", "60", "--prefetch", "10", "--max-staleness", "69"];
+    let output = server.tinywx().args(args).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let refused = "refreshing every 60 seconds, with 10 seconds of prefetch, shows data up to 70 seconds old, \
+        more than max_staleness 69";
+    assert!(stderr.contains(refused), "{}", stderr);
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+
+    // The interval lengthened for the budget counts.
+    let output = server.tinywx().args(["--coords", COORDS]).args(OVER_BUDGET).args(["--max-staleness", "90"]).output();
+    let stderr = String::from_utf8(output.unwrap().stderr).unwrap();
+    assert!(stderr.contains("shows data up to 120 seconds old, more than max_staleness 90"), "{}", stderr);
+}
+
+#[test]
+fn a_watch_within_max_staleness_runs() {
+    let server = MockServer::api();
+    let mut child = server
+        .spawnable()
+        .args(["--coords", COORDS, "-d", "temp", "--watch", "60", "--prefetch", "10", "--max-staleness", "70"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap()).read_line(&mut line).unwrap();
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert_eq!(line, "12°\n");
+}
+
+#[test]
+fn config_show_tells_the_worst_staleness() {
+    let server = MockServer::api();
+    let args = ["config", "show", "--coords", COORDS, "-d", "temp", "--watch", "60", "--prefetch", "10"];
+    let output = server.tinywx().args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let told = "refreshing every 60 seconds shows data up to 70 seconds old, plus the time a late fetch takes";
+    assert_eq!(stdout.lines().last(), Some(told));
+}
+
+#[test]
+fn smoothed_values_are_besides_the_fetched_ones_in_json() {
+    let server = MockServer::api();
+    let mut child = server
+        .spawnable()
+        .args(["--coords", COORDS, "--watch", "60", "--smooth", "0.5", "-o", "json", "-d", "temp", "humidity"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert_eq!(line, "{\"humidity\":\"81%\",\"smoothed\":{\"temp\":\"12°\"},\"temp\":\"12°\"}\n");
+}
+
+#[test]
+fn the_json_sidecar_stays_after_the_watch() {
+    let server = MockServer::api();
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("wx.json");
+    let mut child = server
+        .spawnable()
+        .args(["--coords", COORDS, "--watch", "60", "-d", "temp", "--json-sidecar"])
+        .arg(&path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    let kill = Command::new("kill").args(["-INT", &child.id().to_string()]).status().unwrap();
+    assert!(kill.success());
+    assert_eq!(child.wait().unwrap().code(), Some(130));
+    let sidecar: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(sidecar["temp"], "12°");
+    assert!(sidecar["fetched_at"].is_i64(), "{}", sidecar);
+}
+
+/// Runs a watch until its first line with the daily digest due from
+/// midnight on, piped to a command that appends it to `digests`, and
+/// returns what it warned of.
+fn watch_with_digest(server: &MockServer, digests: &std::path::Path) -> String {
+    let command = format!("cat >> '{}'", digests.display());
+    let mut child = server
+        .spawnable()
+        .args(["--coords", COORDS, "--watch", "60", "-d", "temp", "--lang", "de", "--digest-time", "00:00"])
+        .args(["--digest-exec", &command])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // The digest is sent before the line is printed.
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap()).read_line(&mut line).unwrap();
+    assert_eq!(line, "12°\n");
+    child.kill().unwrap();
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn the_daily_digest_is_sent_once_across_restarts() {
+    let server = MockServer::api();
+    fs::create_dir_all(server.home()).unwrap();
+    let digests = server.home().join("digests");
+    let warnings = watch_with_digest(&server, &digests);
+    // Late in the local day, the forecast may have nothing left of it.
+    let sent = fs::read_to_string(&digests).unwrap_or_default();
+    if warnings.is_empty() {
+        assert!(sent.starts_with("Today: ") && sent.contains("% chance of precipitation"), "{}", sent);
+        // In English, whatever the language of the items.
+        assert!(sent.contains(" from the southwest\n"), "{}", sent);
+    } else {
+        assert_eq!(warnings, "warning: daily digest not sent: no forecast for the rest of the day\n");
+    }
+    assert_eq!(watch_with_digest(&server, &digests), "");
+    assert_eq!(fs::read_to_string(&digests).unwrap_or_default(), sent);
+}
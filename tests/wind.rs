@@ -0,0 +1,32 @@
+//! `wind_dir_long`, the compass point the wind comes from written out in the
+//! language of `--lang`, on the current weather of the fixture, from 230°,
+//! and on the forecast of a local stand-in for the API, from 230° too.
+
+mod common;
+
+use common::{MockServer, COORDS};
+
+fn weather(server: &MockServer, args: &[&str]) -> String {
+    let output = server.tinywx().args(["--coords", COORDS]).args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn in_the_language_of_lang() {
+    let server = MockServer::api();
+    assert_eq!(weather(&server, &["-d", "wind_dir", "wind_dir_long"]), "SW southwest\n");
+    for (lang, name) in [("de", "Südwest"), ("nl", "zuidwest"), ("fr", "sud-ouest"), ("ja", "southwest")] {
+        assert_eq!(weather(&server, &["-d", "wind_dir_long", "--lang", lang]), format!("{}\n", name), "{}", lang);
+    }
+}
+
+#[test]
+fn in_templates_and_forecast_steps() {
+    let server = MockServer::api();
+    let template = "wind from the {wind_dir_long}, later {wind_dir_long@+3h}";
+    assert_eq!(weather(&server, &["-t", template, "--lang", "nl"]), "wind from the zuidwest, later zuidwest\n");
+    assert_eq!(weather(&server, &["-d", "wind_dir_long", "--forecast", "2", "--lang", "de"]), "Südwest\nSüdwest\n");
+    let json = weather(&server, &["-d", "wind_dir_long", "-o", "json", "--lang", "fr"]);
+    assert_eq!(json, "{\"wind_dir_long\":\"sud-ouest\"}\n");
+}
@@ -0,0 +1,57 @@
+//! Locations by zip code, against a local stand-in for the API: what is asked
+//! for `--zip` and for the `zip` key, and the usage errors of a code without
+//! a country and of a code with a city.
+
+mod common;
+
+use std::fs;
+
+use common::MockServer;
+
+/// Returns the query of each request of the current weather so far.
+fn queries(server: &MockServer) -> Vec<String> {
+    let requests = server.requests();
+    let weather = requests.iter().filter(|path| path.starts_with("/data/2.5/weather?"));
+    weather.map(|path| path.split(['?', '&']).nth(1).unwrap().to_string()).collect()
+}
+
+#[test]
+fn zip_codes_are_asked_with_their_country() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["--zip", "1012", "-C", "nl", "-d", "temp", "--no-cache"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "12°\n");
+    assert_eq!(queries(&server), ["zip=1012%2Cnl"]);
+}
+
+#[test]
+fn the_zip_key_is_a_location() {
+    let server = MockServer::api();
+    fs::create_dir_all(server.home()).unwrap();
+    let path = server.home().join("zip.toml");
+    fs::write(&path, "zip = \"78701\"\ncountry = \"us\"\ndata = [\"humidity\"]\n").unwrap();
+    let output = server.tinywx().args(["-f", path.to_str().unwrap(), "--no-cache"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "81%\n");
+    assert_eq!(queries(&server), ["zip=78701%2Cus"]);
+}
+
+#[test]
+fn a_zip_code_without_a_country_is_2() {
+    let server = MockServer::api();
+    let output = server.tinywx().args(["--zip", "78701", "-d", "temp"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("a zip code needs a country code, as in --zip 78701 -C us"), "{}", stderr);
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
+
+#[test]
+fn a_zip_code_with_a_city_is_2() {
+    for other in [["-c", "amsterdam"], ["--state", "tx"]] {
+        let server = MockServer::api();
+        let output = server.tinywx().args(["--zip", "78701", "-C", "us", "-d", "temp"]).args(other).output().unwrap();
+        assert_eq!(output.status.code(), Some(2), "{:?}", other);
+        assert!(server.requests().is_empty(), "{:?}", server.requests());
+    }
+}
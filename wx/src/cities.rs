@@ -0,0 +1,303 @@
+//! A coarse database of large cities, built in, to look places up by name
+//! where the geocoding API cannot be asked: with no network, or to make no
+//! request for it at all. It knows each city's name in English, country,
+//! coordinates to a hundredth of a degree and population, and no states.
+//!
+//! The cities are kept in `data/cities.tsv`, one a line: the name, latitude,
+//! longitude, country code and population, separated by tabs. That is what
+//! `cut` keeps of a list of GeoNames' cities, so the most populous of
+//! `cities15000.txt` can be taken with
+//!
+//! ```text
+//! sort -t "$(printf '\t')" -k 15,15nr cities15000.txt | head -n 10000 | cut -f 2,5,6,9,15
+//! ```
+//!
+//! The crate embeds them as the compact table of `data/cities.bin`, which is
+//! only decoded the first time it is used, and which the tests write anew
+//! from the list with `UPDATE_CITIES=1`.
+
+use std::sync::OnceLock;
+
+/// The table: [`MAGIC`], then for each city, most populous first, its
+/// latitude and longitude in hundredths of a degree as two little-endian
+/// `i16`, its population as a little-endian `u32`, its two-letter country
+/// code, and its name in UTF-8, after a byte with its length.
+const TABLE: &[u8] = include_bytes!("../data/cities.bin");
+/// First bytes of the table, with the version of its format.
+const MAGIC: &[u8] = b"WXC1";
+/// Bytes of a city in the table before its name.
+const FIXED: usize = 11;
+
+/// A city of the database.
+#[derive(Clone, Debug, PartialEq)]
+pub struct City {
+    /// Name of the city, in English.
+    pub name: String,
+    /// Country code, as in "NL".
+    pub country: String,
+    pub lat: f64,
+    pub lon: f64,
+    pub population: u32,
+}
+
+#[cfg(feature = "http")]
+impl From<&City> for crate::GeoResult {
+    fn from(city: &City) -> Self {
+        crate::GeoResult {
+            name: city.name.clone(),
+            state: None,
+            country: city.country.clone(),
+            lat: city.lat,
+            lon: city.lon,
+        }
+    }
+}
+
+/// Returns the cities of the database, most populous first.
+pub fn all() -> &'static [City] {
+    static CITIES: OnceLock<Vec<City>> = OnceLock::new();
+    CITIES.get_or_init(|| decode(TABLE).expect("the built-in table of cities is well-formed"))
+}
+
+/// Finds up to `limit` cities named `name`, in the country with code
+/// `country` if one is given, best match first. Names are compared without
+/// case, accents or punctuation: those that match come first, then those the
+/// name begins, as in "Frankfurt am Main" for "frankfurt", with a typo or two
+/// in longer names, and the more populous first among equals.
+pub fn find(name: &str, country: Option<&str>, limit: usize) -> Vec<&'static City> {
+    let wanted = normalize(name);
+    if wanted.is_empty() {
+        return Vec::new();
+    }
+    let typos = match wanted.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    };
+    let mut found: Vec<(usize, &City)> = all()
+        .iter()
+        .filter(|city| country.is_none_or(|country| city.country.eq_ignore_ascii_case(country.trim())))
+        .filter_map(|city| {
+            let candidate = normalize(&city.name);
+            let rank = if candidate == wanted {
+                0
+            } else if candidate.strip_prefix(&wanted).is_some_and(|rest| rest.starts_with(' ')) {
+                1
+            } else {
+                Some(distance(&wanted, &candidate)).filter(|&d| d <= typos)?
+            };
+            Some((rank, city))
+        })
+        .collect();
+    // The cities are by population already, and the sort is stable.
+    found.sort_by_key(|(rank, _)| *rank);
+    found.into_iter().take(limit).map(|(_, city)| city).collect()
+}
+
+/// Decodes the table of cities, or returns `None` if it is not well-formed.
+fn decode(table: &[u8]) -> Option<Vec<City>> {
+    let mut rest = table.strip_prefix(MAGIC)?;
+    let mut cities = Vec::new();
+    while !rest.is_empty() {
+        let (fixed, after) = rest.split_at_checked(FIXED)?;
+        let (name, after) = after.split_at_checked(usize::from(fixed[10]))?;
+        let hundredths = |i: usize| f64::from(i16::from_le_bytes([fixed[i], fixed[i + 1]])) / 100.0;
+        cities.push(City {
+            name: std::str::from_utf8(name).ok()?.to_string(),
+            country: std::str::from_utf8(&fixed[8..10]).ok()?.to_string(),
+            lat: hundredths(0),
+            lon: hundredths(2),
+            population: u32::from_le_bytes([fixed[4], fixed[5], fixed[6], fixed[7]]),
+        });
+        rest = after;
+    }
+    Some(cities)
+}
+
+/// Returns `name` lowercased, with the accents of Latin letters taken off and
+/// anything but letters and digits as single spaces between words.
+fn normalize(name: &str) -> String {
+    let mut normalized = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        let plain = match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ą' | 'ă' => "a",
+            'ç' | 'ć' | 'č' => "c",
+            'đ' | 'ď' => "d",
+            'è' | 'é' | 'ê' | 'ë' | 'ę' | 'ě' => "e",
+            'ğ' => "g",
+            'ì' | 'í' | 'î' | 'ï' | 'ı' => "i",
+            'ł' | 'ľ' => "l",
+            'ñ' | 'ń' | 'ň' => "n",
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ő' => "o",
+            'ř' => "r",
+            'ś' | 'š' | 'ş' | 'ș' => "s",
+            'ť' | 'ţ' | 'ț' => "t",
+            'ù' | 'ú' | 'û' | 'ü' | 'ů' | 'ű' => "u",
+            'ý' | 'ÿ' => "y",
+            'ź' | 'ż' | 'ž' => "z",
+            'ß' => "ss",
+            'æ' => "ae",
+            'œ' => "oe",
+            'þ' => "th",
+            // Apostrophes join, as in "Xi'an".
+            '\'' | '’' => "",
+            c if c.is_alphanumeric() => {
+                normalized.push(c);
+                continue;
+            }
+            _ => " ",
+        };
+        if plain != " " || !normalized.is_empty() && !normalized.ends_with(' ') {
+            normalized.push_str(plain);
+        }
+    }
+    normalized.truncate(normalized.trim_end().len());
+    normalized
+}
+
+/// Returns the Levenshtein distance between `a` and `b`: how many characters
+/// have to be inserted, deleted or replaced to make one the other.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let replaced = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = replaced.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encodes the list of cities of `data/cities.tsv` as the table.
+    fn encode(list: &str) -> Vec<u8> {
+        let mut cities: Vec<City> = list
+            .lines()
+            .map(|line| {
+                let fields: Vec<&str> = line.split('\t').collect();
+                assert_eq!(fields.len(), 5, "{:?}", line);
+                City {
+                    name: fields[0].to_string(),
+                    lat: fields[1].parse().unwrap(),
+                    lon: fields[2].parse().unwrap(),
+                    country: fields[3].to_string(),
+                    population: fields[4].parse().unwrap(),
+                }
+            })
+            .collect();
+        cities.sort_by_key(|city| std::cmp::Reverse(city.population));
+        let mut table = MAGIC.to_vec();
+        for city in cities {
+            let hundredths = |degrees: f64| ((degrees * 100.0).round() as i16).to_le_bytes();
+            assert_eq!(city.country.len(), 2, "{:?}", city);
+            table.extend(hundredths(city.lat));
+            table.extend(hundredths(city.lon));
+            table.extend(city.population.to_le_bytes());
+            table.extend(city.country.as_bytes());
+            table.push(u8::try_from(city.name.len()).unwrap());
+            table.extend(city.name.as_bytes());
+        }
+        table
+    }
+
+    fn found(name: &str, country: Option<&str>) -> Vec<String> {
+        find(name, country, 5).iter().map(|city| format!("{}, {}", city.name, city.country)).collect()
+    }
+
+    #[test]
+    fn the_table_is_the_list() {
+        let table = encode(include_str!("../data/cities.tsv"));
+        if std::env::var_os("UPDATE_CITIES").is_some() {
+            std::fs::write(concat!(env!("CARGO_MANIFEST_DIR"), "/data/cities.bin"), &table).unwrap();
+        }
+        assert!(table == TABLE, "data/cities.bin is not data/cities.tsv; run the tests with UPDATE_CITIES=1");
+    }
+
+    #[test]
+    fn every_city_is_on_the_map() {
+        let cities = all();
+        assert!(cities.len() > 300, "{}", cities.len());
+        for city in cities {
+            assert!((-90.0..=90.0).contains(&city.lat) && (-180.0..=180.0).contains(&city.lon), "{:?}", city);
+            assert!(city.country.bytes().all(|b| b.is_ascii_uppercase()), "{:?}", city);
+            assert!(!normalize(&city.name).is_empty(), "{:?}", city);
+        }
+        assert!(cities.windows(2).all(|pair| pair[0].population >= pair[1].population));
+    }
+
+    #[test]
+    fn tables_that_are_cut_short_are_not_decoded() {
+        let table = encode("Amsterdam\t52.37\t4.89\tNL\t741636\nParis\t48.85\t2.35\tFR\t2138551\n");
+        let cities = decode(&table).unwrap();
+        assert_eq!(cities.iter().map(|city| city.name.as_str()).collect::<Vec<_>>(), ["Paris", "Amsterdam"]);
+        assert_eq!((cities[1].lat, cities[1].lon, cities[1].population), (52.37, 4.89, 741636));
+        for len in [0, 3, MAGIC.len() + FIXED, table.len() - 1] {
+            assert_eq!(decode(&table[..len]), None, "{}", len);
+        }
+        assert_eq!(decode(MAGIC), Some(Vec::new()));
+    }
+
+    #[test]
+    fn the_most_populous_comes_first() {
+        assert_eq!(found("paris", None), ["Paris, FR", "Paris, US"]);
+        assert_eq!(found("London", None), ["London, GB", "London, CA"]);
+        assert_eq!(found("springfield", None), ["Springfield, US"; 3]);
+        let springfields = find("springfield", None, 5);
+        assert!(springfields.windows(2).all(|pair| pair[0].population > pair[1].population));
+    }
+
+    #[test]
+    fn countries_narrow_it_down() {
+        assert_eq!(found("paris", Some("us")), ["Paris, US"]);
+        assert_eq!(found("paris", Some(" FR ")), ["Paris, FR"]);
+        assert_eq!(found("valencia", Some("ve")), ["Valencia, VE"]);
+        assert_eq!(found("paris", Some("nl")), Vec::<String>::new());
+    }
+
+    #[test]
+    fn names_match_without_case_accents_or_punctuation() {
+        assert_eq!(found("SAO PAULO", None), ["São Paulo, BR"]);
+        assert_eq!(found("dusseldorf", None), ["Düsseldorf, DE"]);
+        assert_eq!(found("xian", None), ["Xi'an, CN"]);
+        assert_eq!(found("st louis", None), ["St. Louis, US"]);
+        assert_eq!(found("  new   york  ", None), ["New York City, US"]);
+        assert_eq!(found("lodz", None), ["Łódź, PL"]);
+    }
+
+    #[test]
+    fn names_match_from_their_start_and_with_a_typo() {
+        assert_eq!(found("frankfurt", None), ["Frankfurt am Main, DE", "Frankfurt (Oder), DE"]);
+        assert_eq!(found("amsterdm", None), ["Amsterdam, NL"]);
+        assert_eq!(found("rotterdma", None), ["Rotterdam, NL"]);
+        assert_eq!(found("copenhagn", None), ["Copenhagen, DK"]);
+        // Names of four letters or more may have a typo, shorter ones none.
+        assert_eq!(found("rime", None), ["Rome, IT"]);
+        assert_eq!(found("rme", None), Vec::<String>::new());
+        assert_eq!(found("xyzzy", None), Vec::<String>::new());
+        assert_eq!(found("", None), Vec::<String>::new());
+        assert_eq!(found(" - ", None), Vec::<String>::new());
+    }
+
+    #[test]
+    fn limits() {
+        assert_eq!(find("springfield", None, 2).len(), 2);
+        assert!(find("paris", None, 0).is_empty());
+    }
+
+    #[test]
+    fn distances() {
+        let cases = [("", "", 0), ("abc", "", 3), ("", "abc", 3), ("kitten", "sitting", 3), ("rome", "rime", 1)];
+        for (a, b, expected) in cases {
+            assert_eq!(distance(a, b), expected, "{:?} {:?}", a, b);
+            assert_eq!(distance(b, a), expected, "{:?} {:?}", b, a);
+        }
+        assert_eq!(distance("łódź", "lodz"), 3);
+    }
+}
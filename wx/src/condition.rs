@@ -0,0 +1,199 @@
+//! Weather conditions by their OpenWeatherMap id, as in 500 for light rain
+//! and 502 for heavy rain, and how severe each one is.
+
+/// The documented condition ids, with their severity.
+const IDS: &[(u64, Severity)] = &[
+    (200, Severity::Moderate),
+    (201, Severity::Heavy),
+    (202, Severity::Extreme),
+    (210, Severity::Moderate),
+    (211, Severity::Heavy),
+    (212, Severity::Extreme),
+    (221, Severity::Heavy),
+    (230, Severity::Moderate),
+    (231, Severity::Heavy),
+    (232, Severity::Heavy),
+    (300, Severity::Light),
+    (301, Severity::Light),
+    (302, Severity::Moderate),
+    (310, Severity::Light),
+    (311, Severity::Light),
+    (312, Severity::Moderate),
+    (313, Severity::Light),
+    (314, Severity::Moderate),
+    (321, Severity::Light),
+    (500, Severity::Light),
+    (501, Severity::Moderate),
+    (502, Severity::Heavy),
+    (503, Severity::Extreme),
+    (504, Severity::Extreme),
+    (511, Severity::Heavy),
+    (520, Severity::Light),
+    (521, Severity::Moderate),
+    (522, Severity::Heavy),
+    (531, Severity::Moderate),
+    (600, Severity::Light),
+    (601, Severity::Moderate),
+    (602, Severity::Heavy),
+    (611, Severity::Moderate),
+    (612, Severity::Light),
+    (613, Severity::Moderate),
+    (615, Severity::Light),
+    (616, Severity::Moderate),
+    (620, Severity::Light),
+    (621, Severity::Moderate),
+    (622, Severity::Heavy),
+    (701, Severity::Light),
+    (711, Severity::Moderate),
+    (721, Severity::Light),
+    (731, Severity::Moderate),
+    (741, Severity::Moderate),
+    (751, Severity::Moderate),
+    (761, Severity::Moderate),
+    (762, Severity::Extreme),
+    (771, Severity::Heavy),
+    (781, Severity::Extreme),
+    (800, Severity::None),
+    (801, Severity::None),
+    (802, Severity::None),
+    (803, Severity::None),
+    (804, Severity::None),
+];
+
+/// A weather condition, by the group of its id. Each variant but `Clear`
+/// has the id, which tells the conditions within the group apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Condition {
+    /// 2xx
+    Thunderstorm(u64),
+    /// 3xx
+    Drizzle(u64),
+    /// 5xx
+    Rain(u64),
+    /// 6xx
+    Snow(u64),
+    /// 7xx: mist, fog, haze, dust and the like, squalls and tornadoes.
+    Atmosphere(u64),
+    /// 800
+    Clear,
+    /// 801 to 804
+    Clouds(u64),
+    /// An id that is not documented, as a new one would be.
+    Unknown(u64),
+}
+
+/// How severe a condition is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    None,
+    Light,
+    Moderate,
+    Heavy,
+    Extreme,
+}
+
+impl Condition {
+    /// Returns the condition with the given id.
+    pub fn from_id(id: u64) -> Self {
+        if !IDS.iter().any(|&(known, _)| known == id) {
+            return Condition::Unknown(id);
+        }
+        match id / 100 {
+            2 => Condition::Thunderstorm(id),
+            3 => Condition::Drizzle(id),
+            5 => Condition::Rain(id),
+            6 => Condition::Snow(id),
+            7 => Condition::Atmosphere(id),
+            _ if id == 800 => Condition::Clear,
+            _ => Condition::Clouds(id),
+        }
+    }
+
+    /// Returns the id of the condition.
+    pub fn id(self) -> u64 {
+        match self {
+            Condition::Clear => 800,
+            Condition::Thunderstorm(id)
+            | Condition::Drizzle(id)
+            | Condition::Rain(id)
+            | Condition::Snow(id)
+            | Condition::Atmosphere(id)
+            | Condition::Clouds(id)
+            | Condition::Unknown(id) => id,
+        }
+    }
+
+    /// Returns how severe the condition is; `None` for an unknown one, which
+    /// nothing can be told about.
+    pub fn severity(self) -> Severity {
+        IDS.iter()
+            .find(|&&(id, _)| id == self.id())
+            .map_or(Severity::None, |&(_, severity)| severity)
+    }
+}
+
+impl Severity {
+    /// Returns the name of the severity, as in "heavy".
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::None => "none",
+            Severity::Light => "light",
+            Severity::Moderate => "moderate",
+            Severity::Heavy => "heavy",
+            Severity::Extreme => "extreme",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_documented_id_has_its_group_and_severity() {
+        for &(id, severity) in IDS {
+            let condition = Condition::from_id(id);
+            let group = match id {
+                200..=299 => Condition::Thunderstorm(id),
+                300..=399 => Condition::Drizzle(id),
+                500..=599 => Condition::Rain(id),
+                600..=699 => Condition::Snow(id),
+                700..=799 => Condition::Atmosphere(id),
+                800 => Condition::Clear,
+                801..=804 => Condition::Clouds(id),
+                _ => panic!("{} is in no documented group", id),
+            };
+            assert_eq!(condition, group, "{}", id);
+            assert_eq!(condition.id(), id);
+            assert_eq!(condition.severity(), severity, "{}", id);
+        }
+        assert_eq!(IDS.len(), 55);
+        assert!(IDS.windows(2).all(|pair| pair[0].0 < pair[1].0), "sorted, without duplicates");
+    }
+
+    #[test]
+    fn severities_of_some_ids() {
+        let cases = [
+            (500, Severity::Light),
+            (502, Severity::Heavy),
+            (504, Severity::Extreme),
+            (781, Severity::Extreme),
+            (800, Severity::None),
+            (212, Severity::Extreme),
+            (300, Severity::Light),
+            (622, Severity::Heavy),
+        ];
+        for (id, severity) in cases {
+            assert_eq!(Condition::from_id(id).severity(), severity, "{}", id);
+        }
+    }
+
+    #[test]
+    fn undocumented_ids_are_unknown() {
+        for id in [0, 199, 203, 299, 400, 505, 699, 700, 799, 805, 900, 1000, u64::MAX] {
+            assert_eq!(Condition::from_id(id), Condition::Unknown(id), "{}", id);
+            assert_eq!(Condition::from_id(id).id(), id);
+            assert_eq!(Condition::from_id(id).severity(), Severity::None, "{}", id);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Parsing of coordinates given as text.
+
+use anyhow::{anyhow, bail, Result};
+
+/// Parses a latitude/longitude pair into decimal degrees.
+///
+/// Accepted forms include plain decimal degrees (`52.37,4.895`), decimal
+/// degrees with hemisphere letters (`52.37N, 4.895E`) and degrees, minutes
+/// and seconds (`52°22'12"N 4°53'42"E`). The two values may be separated by a
+/// comma, whitespace or both. Southern and western hemispheres give negative
+/// values; a value without a hemisphere letter keeps its own sign. When both
+/// values carry hemisphere letters they may be given in either order.
+pub fn parse_coords(s: &str) -> Result<(f64, f64)> {
+    let err = |e: anyhow::Error| anyhow!("invalid coordinates '{}': {}", s.trim(), e);
+
+    let mut rest = s;
+    let first = component(&mut rest).map_err(err)?;
+    skip_separator(&mut rest);
+    let second = component(&mut rest).map_err(err)?;
+    if !rest.trim().is_empty() {
+        return Err(err(anyhow!("unexpected '{}'", rest.trim())));
+    }
+
+    let (lat, lon) = match (first.axis(), second.axis()) {
+        (Some(Axis::Lon), Some(Axis::Lat)) => (second, first),
+        (Some(a), Some(b)) if a == b => {
+            return Err(err(anyhow!("both values are in the same direction")))
+        }
+        (Some(Axis::Lon), None) | (None, Some(Axis::Lat)) => {
+            return Err(err(anyhow!("latitude must come first")))
+        }
+        _ => (first, second),
+    };
+
+    let (lat, lon) = (lat.value, lon.value);
+    if !(-90.0..=90.0).contains(&lat) {
+        return Err(err(anyhow!("latitude {} is out of range", lat)));
+    }
+    if !(-180.0..=180.0).contains(&lon) {
+        return Err(err(anyhow!("longitude {} is out of range", lon)));
+    }
+    Ok((lat, lon))
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Axis {
+    Lat,
+    Lon,
+}
+
+/// One parsed coordinate value, and the hemisphere letter if there was one.
+#[derive(Debug)]
+struct Component {
+    value: f64,
+    hemisphere: Option<char>,
+}
+
+impl Component {
+    fn axis(&self) -> Option<Axis> {
+        match self.hemisphere? {
+            'N' | 'S' => Some(Axis::Lat),
+            _ => Some(Axis::Lon),
+        }
+    }
+}
+
+/// Parses one coordinate value from the start of `s` and advances `s` past it.
+fn component(s: &mut &str) -> Result<Component> {
+    *s = s.trim_start();
+    let negative = s.starts_with('-');
+    if let Some(r) = s.strip_prefix(['-', '+']) {
+        *s = r;
+    }
+
+    let mut value = number(s)?;
+
+    *s = s.trim_start();
+    if let Some(r) = strip_any(s, &['°', 'º', 'd']) {
+        *s = r.trim_start();
+        if let Some(minutes) = unit_part(s, &["'", "′"])? {
+            value += minutes / 60.0;
+            if let Some(seconds) = unit_part(s, &["\"", "″", "''"])? {
+                value += seconds / 3600.0;
+            }
+        }
+    }
+
+    *s = s.trim_start();
+    let hemisphere = match s.chars().next().map(|c| c.to_ascii_uppercase()) {
+        Some(c @ ('N' | 'S' | 'E' | 'W')) => {
+            *s = &s[1..];
+            Some(c)
+        }
+        _ => None,
+    };
+
+    if hemisphere.is_some() && negative {
+        bail!("use either a sign or a hemisphere letter, not both");
+    }
+    if matches!(hemisphere, Some('S' | 'W')) || negative {
+        value = -value;
+    }
+
+    Ok(Component { value, hemisphere })
+}
+
+/// Parses minutes or seconds: a number followed by one of `symbols`. Returns
+/// `None`, consuming nothing, if `s` doesn't start with such a part.
+fn unit_part(s: &mut &str, symbols: &[&str]) -> Result<Option<f64>> {
+    let mut rest = *s;
+    let Ok(value) = number(&mut rest) else {
+        return Ok(None);
+    };
+    rest = rest.trim_start();
+    let Some(symbol) = symbols.iter().find(|sym| rest.starts_with(**sym)) else {
+        return Ok(None);
+    };
+    if value >= 60.0 {
+        bail!("minutes and seconds must be less than 60");
+    }
+    *s = rest[symbol.len()..].trim_start();
+    Ok(Some(value))
+}
+
+/// Parses an unsigned decimal number from the start of `s`.
+fn number(s: &mut &str) -> Result<f64> {
+    let end = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (digits, rest) = s.split_at(end);
+    if digits.is_empty() {
+        bail!("expected a number");
+    }
+    let value = digits
+        .parse()
+        .map_err(|_| anyhow!("'{}' is not a number", digits))?;
+    *s = rest;
+    Ok(value)
+}
+
+fn strip_any<'a>(s: &'a str, chars: &[char]) -> Option<&'a str> {
+    let c = s.chars().next()?;
+    chars.contains(&c).then(|| &s[c.len_utf8()..])
+}
+
+fn skip_separator(s: &mut &str) {
+    *s = s.trim_start();
+    if let Some(r) = s.strip_prefix(',').or_else(|| s.strip_prefix(';')) {
+        *s = r;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coordinates() {
+        let cases = [
+            ("52.37,4.895", (52.37, 4.895)),
+            ("52.37, 4.895", (52.37, 4.895)),
+            (" 52.37 4.895 ", (52.37, 4.895)),
+            ("52.37;4.895", (52.37, 4.895)),
+            ("-33.87,151.21", (-33.87, 151.21)),
+            ("+52.37,+4.895", (52.37, 4.895)),
+            ("0,0", (0.0, 0.0)),
+            ("90,180", (90.0, 180.0)),
+            ("-90,-180", (-90.0, -180.0)),
+            ("52.37N, 4.895E", (52.37, 4.895)),
+            ("52.37n 4.895e", (52.37, 4.895)),
+            ("33.87S 151.21E", (-33.87, 151.21)),
+            ("40.7 N 74 W", (40.7, -74.0)),
+            ("4.895E 52.37N", (52.37, 4.895)),
+            ("52.37N 4.895", (52.37, 4.895)),
+            ("52°22'12\"N 4°53'42\"E", (52.37, 4.895)),
+            ("52° 22' 12\" N, 4° 53' 42\" E", (52.37, 4.895)),
+            ("52º22′12″N 4º53′42″E", (52.37, 4.895)),
+            ("52d22'12''N 4d53'42''E", (52.37, 4.895)),
+            ("33°52'12\"S 151°12'36\"E", (-33.87, 151.21)),
+            ("4°53'42\"W 52°22'12\"S", (-52.37, -4.895)),
+            ("52°22'N 4°53'E", (52.0 + 22.0 / 60.0, 4.0 + 53.0 / 60.0)),
+            ("52°N 4°E", (52.0, 4.0)),
+            ("-52°22'12\" 4°53'42\"", (-52.37, 4.895)),
+        ];
+        for (text, (lat, lon)) in cases {
+            let parsed = parse_coords(text).unwrap_or_else(|e| panic!("{}: {:#}", text, e));
+            assert!((parsed.0 - lat).abs() < 1e-9 && (parsed.1 - lon).abs() < 1e-9, "{}: {:?}", text, parsed);
+        }
+    }
+
+    #[test]
+    fn invalid_coordinates() {
+        let cases = [
+            ("91,0", "latitude 91 is out of range"),
+            ("-90.5,0", "latitude -90.5 is out of range"),
+            ("0,181", "longitude 181 is out of range"),
+            ("0,-180.01", "longitude -180.01 is out of range"),
+            ("90°1'N 0E", "out of range"),
+            ("0 181W", "out of range"),
+            ("nan,0", "expected a number"),
+            ("NaN,0", "expected a number"),
+            ("inf,0", "expected a number"),
+            ("0,-inf", "expected a number"),
+            ("0,infinity", "expected a number"),
+            ("1e3,0", "unexpected"),
+            ("52.37.1,4", "is not a number"),
+            ("", "expected a number"),
+            ("Amsterdam", "expected a number"),
+            ("52.37", "expected a number"),
+            ("52.37,4.895,1", "unexpected ',1'"),
+            ("52N 4S", "same direction"),
+            ("4E 5W", "same direction"),
+            ("4E 52", "latitude must come first"),
+            ("52 4N", "latitude must come first"),
+            ("-52N 4E", "either a sign or a hemisphere letter"),
+            ("52°60'N 4E", "less than 60"),
+            ("52°22'60\"N 4E", "less than 60"),
+        ];
+        for (text, error) in cases {
+            let e = parse_coords(text).map(|parsed| panic!("{}: {:?}", text, parsed)).unwrap_err();
+            let message = e.to_string();
+            assert!(message.starts_with(&format!("invalid coordinates '{}'", text.trim())), "{}", message);
+            assert!(message.contains(error), "{}: {}", text, message);
+        }
+    }
+}
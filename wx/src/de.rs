@@ -0,0 +1,176 @@
+//! Tolerant deserializers, for fields the API has sent as a number in some
+//! responses and as a string, or null, in others.
+
+use serde::de::{Deserialize, Deserializer, Error};
+use serde_json::Value;
+
+/// Deserializes a string, number or boolean as a string, and null as an
+/// empty one.
+pub(crate) fn string_or_number_as_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(String::new()),
+        Value::String(s) => Ok(s),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        other => Err(D::Error::custom(format!("expected a string or a number, got {}", other))),
+    }
+}
+
+/// Like [`string_or_number_as_string`], but deserializes null as `None`.
+pub(crate) fn optional_string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(None),
+        value => string_or_number_as_string(value).map(Some).map_err(D::Error::custom),
+    }
+}
+
+/// Deserializes a number, or a string holding one, as a float, for fields
+/// every response has: null, an empty string and a string that is not a
+/// finite number, as "NaN" and "inf" are not, are errors.
+pub(crate) fn number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Number(n) => n
+            .as_f64()
+            .ok_or_else(|| D::Error::custom(format!("number {} out of range", n))),
+        Value::String(s) => s
+            .trim()
+            .parse()
+            .ok()
+            .filter(|n: &f64| n.is_finite())
+            .ok_or_else(|| D::Error::custom(format!("expected a number, got \"{}\"", s))),
+        other => Err(D::Error::custom(format!("expected a number, got {}", other))),
+    }
+}
+
+/// Like [`number`], but deserializes null or an empty string as 0, for
+/// fields that have a default.
+pub(crate) fn number_or_string_as_f64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(0.0),
+        Value::String(s) if s.trim().is_empty() => Ok(0.0),
+        value => number(value).map_err(D::Error::custom),
+    }
+}
+
+/// Deserializes a number, or a string holding one, as a float, and null or
+/// an empty string as `None`, for fields that some responses leave out.
+pub(crate) fn optional_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(None),
+        Value::String(s) if s.trim().is_empty() => Ok(None),
+        value => number(value).map(Some).map_err(D::Error::custom),
+    }
+}
+
+/// Deserializes null as the default value, for fields that are sometimes
+/// null rather than left out.
+pub(crate) fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Deserializes an empty string as `None`.
+pub(crate) fn empty_as_none<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let s = Option::<String>::deserialize(deserializer)?;
+    Ok(s.filter(|s| !s.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn numbers() {
+        let cases = [
+            (json!(12.5), Some(12.5)),
+            (json!(-3), Some(-3.0)),
+            (json!(u64::MAX), Some(u64::MAX as f64)),
+            (json!("12.5"), Some(12.5)),
+            (json!(" 7 "), Some(7.0)),
+            (json!("1e3"), Some(1000.0)),
+            (json!(null), None),
+            (json!(""), None),
+            (json!("warm"), None),
+            (json!("NaN"), None),
+            (json!("inf"), None),
+            (json!("-inf"), None),
+            (json!("infinity"), None),
+            (json!("1e999"), None),
+            (json!(true), None),
+            (json!([1]), None),
+            (json!({"value": 1}), None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(number(value.clone()).ok(), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn numbers_with_a_default() {
+        let cases = [
+            (json!(12.5), Some(12.5)),
+            (json!("12.5"), Some(12.5)),
+            (json!(null), Some(0.0)),
+            (json!(""), Some(0.0)),
+            (json!("  "), Some(0.0)),
+            (json!("NaN"), None),
+            (json!("inf"), None),
+            (json!("-inf"), None),
+            (json!("warm"), None),
+            (json!(false), None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(number_or_string_as_f64(value.clone()).ok(), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn optional_numbers() {
+        let cases = [
+            (json!(1012), Some(Some(1012.0))),
+            (json!("1012"), Some(Some(1012.0))),
+            (json!(null), Some(None)),
+            (json!(""), Some(None)),
+            (json!("NaN"), None),
+            (json!("inf"), None),
+            (json!("high"), None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(optional_number(value.clone()).ok(), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn strings() {
+        let cases = [
+            (json!("Amsterdam"), Some("Amsterdam")),
+            (json!(200), Some("200")),
+            (json!(1.5), Some("1.5")),
+            (json!(true), Some("true")),
+            (json!(null), Some("")),
+            (json!([]), None),
+            (json!({}), None),
+        ];
+        for (value, expected) in cases {
+            let parsed = string_or_number_as_string(value.clone()).ok();
+            assert_eq!(parsed.as_deref(), expected, "{}", value);
+        }
+        assert_eq!(optional_string_or_number(json!(null)).unwrap(), None);
+        assert_eq!(optional_string_or_number(json!(404)).unwrap().as_deref(), Some("404"));
+        assert!(optional_string_or_number(json!([404])).is_err());
+    }
+
+    #[test]
+    fn defaults() {
+        assert_eq!(null_as_default::<_, Vec<u8>>(json!(null)).unwrap(), Vec::<u8>::new());
+        assert_eq!(null_as_default::<_, Vec<u8>>(json!([1, 2])).unwrap(), vec![1, 2]);
+        assert!(null_as_default::<_, Vec<u8>>(json!("x")).is_err());
+        assert_eq!(empty_as_none(json!("")).unwrap(), None);
+        assert_eq!(empty_as_none(json!(null)).unwrap(), None);
+        assert_eq!(empty_as_none(json!("10d")).unwrap().as_deref(), Some("10d"));
+    }
+}
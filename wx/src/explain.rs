@@ -0,0 +1,68 @@
+//! Explanations of where rendered values come from.
+
+use std::fmt;
+
+use serde::Serialize;
+
+/// Endpoint of the current weather data.
+pub const CURRENT_ENDPOINT: &str = "data/2.5/weather";
+
+/// Describes how one item was computed.
+#[derive(Serialize, Debug, Clone, PartialEq, Default)]
+pub struct Explanation {
+    /// Name of the item.
+    pub item: String,
+    /// API endpoints the value is based on.
+    pub endpoints: Vec<String>,
+    /// Whether the data was served from the cache rather than fetched live.
+    pub cached: bool,
+    /// The value before rounding or conversion, if there is one.
+    pub raw: Option<String>,
+    /// Name of the formula for values that are computed rather than reported.
+    pub formula: Option<String>,
+    /// Fallback applied because the data was missing or unavailable.
+    pub fallback: Option<String>,
+}
+
+impl Explanation {
+    /// Starts an explanation of `item`, based on data from `endpoint`.
+    pub fn new(item: &str, endpoint: &str) -> Self {
+        Self {
+            item: item.to_string(),
+            endpoints: vec![endpoint.to_string()],
+            ..Default::default()
+        }
+    }
+
+    pub fn raw(mut self, raw: impl ToString) -> Self {
+        self.raw = Some(raw.to_string());
+        self
+    }
+
+    pub fn formula(mut self, formula: &str) -> Self {
+        self.formula = Some(formula.to_string());
+        self
+    }
+
+    pub fn fallback(mut self, fallback: impl ToString) -> Self {
+        self.fallback = Some(fallback.to_string());
+        self
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = if self.cached { "cached" } else { "live" };
+        write!(f, "{}: {} ({})", self.item, self.endpoints.join(" + "), source)?;
+        if let Some(formula) = &self.formula {
+            write!(f, ", computed by {}", formula)?;
+        }
+        if let Some(raw) = &self.raw {
+            write!(f, ", raw {}", raw)?;
+        }
+        if let Some(fallback) = &self.fallback {
+            write!(f, ", fallback: {}", fallback)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,312 @@
+//! The 5 day forecast, in steps of 3 hours.
+
+use serde::{Deserialize, Serialize};
+
+use crate::condition::Condition;
+use crate::{de, Clouds, Coord, CurrentWeather, Main, Precipitation, Sys, Weather, Wind};
+
+#[cfg(feature = "http")]
+use anyhow::Result;
+
+#[cfg(feature = "http")]
+use crate::request::RequestSpec;
+#[cfg(feature = "http")]
+use crate::{Client, Location};
+
+/// Endpoint of the 5 day forecast.
+pub const FORECAST_ENDPOINT: &str = "data/2.5/forecast";
+/// Most steps a forecast has: 5 days of 3 hours.
+pub const MAX_STEPS: usize = 40;
+/// Time between steps, in seconds.
+const STEP: u64 = 3 * 3600;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Forecast {
+    /// Forecasts in chronological order, 3 hours apart.
+    #[serde(default, deserialize_with = "de::null_as_default")]
+    list: Vec<Step>,
+    #[serde(default, deserialize_with = "de::null_as_default")]
+    city: City,
+    /// Internal parameter, a number or a string
+    #[serde(default, deserialize_with = "de::string_or_number_as_string")]
+    cod: String,
+    /// Internal parameter, a number or a string
+    #[serde(default, deserialize_with = "de::string_or_number_as_string")]
+    message: String,
+    /// Number of steps left out beyond [`MAX_STEPS`].
+    #[serde(skip)]
+    truncated: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Step {
+    /// Time of the forecast, unix, UTC
+    dt: i64,
+    /// Temperatures in the units it was fetched with, and the rest as in the
+    /// current weather
+    main: Main,
+    #[serde(default, deserialize_with = "de::null_as_default")]
+    weather: Vec<Weather>,
+    #[serde(default, deserialize_with = "de::null_as_default")]
+    clouds: Clouds,
+    /// Visibility, meter
+    #[serde(default, deserialize_with = "de::number_or_string_as_f64")]
+    visibility: f64,
+    /// Probability of precipitation, from 0 to 1
+    #[serde(default, deserialize_with = "de::number_or_string_as_f64")]
+    pop: f64,
+    /// Wind speed in the units it was fetched with, and direction
+    #[serde(default, deserialize_with = "de::null_as_default")]
+    wind: Wind,
+    /// Rain and snow of the 3 hours of the step, absent when dry
+    #[serde(default)]
+    rain: Option<Precipitation>,
+    #[serde(default)]
+    snow: Option<Precipitation>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+struct City {
+    /// City ID, 0 if there is none
+    id: u64,
+    #[serde(deserialize_with = "de::empty_as_none")]
+    name: Option<String>,
+    coord: Option<Coord>,
+    /// Country code (GB, JP etc.)
+    country: Option<String>,
+    /// Shift in seconds from UTC
+    timezone: i64,
+    /// Sunrise time, unix, UTC; absent during polar day and night
+    sunrise: i64,
+    /// Sunset time, unix, UTC
+    sunset: i64,
+}
+
+/// The rest of a day, summed up from the forecast.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DaySummary {
+    /// Lowest and highest temperature.
+    pub low: f64,
+    pub high: f64,
+    /// Highest probability of precipitation, from 0 to 1.
+    pub precipitation: f64,
+    /// Highest wind speed.
+    pub wind: f64,
+    /// Direction the highest wind comes from, in degrees.
+    pub wind_deg: f64,
+    /// Sunrise and sunset, unix, UTC, or 0 if there are none.
+    pub sunrise: i64,
+    pub sunset: i64,
+}
+
+/// The first step of the forecast likely to see precipitation, as
+/// [`Forecast::next_precipitation`] finds it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PrecipEvent {
+    /// Time of the step, unix, UTC.
+    pub at: i64,
+    /// Shift of the location's time from UTC, in seconds.
+    pub timezone: i64,
+    /// Probability of precipitation, from 0 to 1.
+    pub probability: f64,
+    pub kind: PrecipKind,
+}
+
+/// What falls, by the conditions of the step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrecipKind {
+    Rain,
+    /// Any snow, sleet included.
+    Snow,
+}
+
+impl PrecipKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            PrecipKind::Rain => "rain",
+            PrecipKind::Snow => "snow",
+        }
+    }
+}
+
+impl PrecipEvent {
+    /// Returns the local time of the step, as in "15:00".
+    pub fn local_time(&self) -> String {
+        let minutes = self.at.saturating_add(self.timezone).rem_euclid(86400) / 60;
+        format!("{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+
+    /// Renders the event as in "rain 70% @15:00".
+    pub fn render(&self) -> String {
+        format!(
+            "{} {}% @{}",
+            self.kind.name(),
+            (self.probability * 100.0).round(),
+            self.local_time()
+        )
+    }
+}
+
+impl Forecast {
+    /// Keeps the first [`MAX_STEPS`] steps, as a response with more is not
+    /// what it should be.
+    #[cfg(feature = "http")]
+    fn truncate(mut self) -> Self {
+        self.truncated = self.list.len().saturating_sub(MAX_STEPS);
+        self.list.truncate(MAX_STEPS);
+        self
+    }
+
+    /// Returns how many steps beyond [`MAX_STEPS`] the response had, which
+    /// were left out.
+    pub fn truncated(&self) -> usize {
+        self.truncated
+    }
+
+    /// Returns the location's shift from UTC in seconds.
+    pub fn timezone(&self) -> i64 {
+        self.city.timezone
+    }
+
+    /// Returns the times of the first and the last step, unix, UTC, or
+    /// `None` if there are none.
+    pub fn span(&self) -> Option<(i64, i64)> {
+        Some((self.list.first()?.dt, self.list.last()?.dt))
+    }
+
+    /// Returns the step nearest to `at`, unix, UTC, as if it were the current
+    /// weather, so that the items of the current weather render from it; or
+    /// `None` if `at` is more than half a step after the last step. Before
+    /// the first step, which can be up to a step ahead, that one is nearest.
+    pub fn slot(&self, at: i64) -> Option<CurrentWeather> {
+        let step = self.list.iter().min_by_key(|step| step.dt.abs_diff(at))?;
+        if at > step.dt && at.abs_diff(step.dt) > STEP / 2 {
+            return None;
+        }
+        Some(self.weather(step))
+    }
+
+    /// Returns the first `count` steps after `now`, unix, UTC, as
+    /// [`Forecast::slot`] does.
+    pub fn upcoming(&self, now: i64, count: usize) -> Vec<CurrentWeather> {
+        self.list
+            .iter()
+            .filter(|step| step.dt > now)
+            .take(count)
+            .map(|step| self.weather(step))
+            .collect()
+    }
+
+    /// Returns `step` as if it were the current weather.
+    fn weather(&self, step: &Step) -> CurrentWeather {
+        CurrentWeather {
+            coord: self.city.coord.clone(),
+            weather: step.weather.clone(),
+            base: String::new(),
+            main: step.main.clone(),
+            visibility: Some(step.visibility),
+            wind: step.wind.clone(),
+            clouds: step.clouds.clone(),
+            rain: step.rain.clone(),
+            snow: step.snow.clone(),
+            dt: step.dt,
+            sys: Sys {
+                country: self.city.country.clone(),
+                sunrise: self.city.sunrise,
+                sunset: self.city.sunset,
+                ..Sys::default()
+            },
+            timezone: self.city.timezone,
+            id: self.city.id,
+            name: self.city.name.clone(),
+            cod: self.cod.clone(),
+            missing: Vec::new(),
+        }
+    }
+
+    /// Returns `(time, temperature)` pairs in chronological order, for the
+    /// `hours` hours from `now`.
+    pub fn temperatures(&self, now: i64, hours: i64) -> Vec<(i64, f64)> {
+        self.series(now, hours, |step| step.main.temp)
+    }
+
+    /// Returns `(time, probability of precipitation)` pairs like
+    /// [`Forecast::temperatures`], with probabilities from 0 to 1.
+    pub fn precipitation(&self, now: i64, hours: i64) -> Vec<(i64, f64)> {
+        self.series(now, hours, |step| step.pop.clamp(0.0, 1.0))
+    }
+
+    /// Returns the first step in the `hours` hours after `now`, unix, UTC,
+    /// with a probability of precipitation of at least `threshold`, from 0 to
+    /// 1, or `None` if none has one that high. A step up to half a step
+    /// before `now` counts, as [`Forecast::slot`] would take it for now.
+    pub fn next_precipitation(&self, now: i64, hours: i64, threshold: f64) -> Option<PrecipEvent> {
+        let from = now.saturating_sub((STEP / 2) as i64);
+        let until = now.saturating_add(hours.saturating_mul(3600));
+        let step = self
+            .list
+            .iter()
+            .filter(|step| step.dt >= from && step.dt <= until)
+            .find(|step| step.pop.clamp(0.0, 1.0) >= threshold)?;
+        let snow = step.weather.iter().any(|w| matches!(w.condition(), Condition::Snow(_)));
+        Some(PrecipEvent {
+            at: step.dt,
+            timezone: self.city.timezone,
+            probability: step.pop.clamp(0.0, 1.0),
+            kind: if snow { PrecipKind::Snow } else { PrecipKind::Rain },
+        })
+    }
+
+    /// Sums up the forecast for the rest of the local day of `now`, or
+    /// returns `None` if it has no steps left in it.
+    pub fn rest_of_day(&self, now: i64) -> Option<DaySummary> {
+        let day = now.saturating_add(self.city.timezone).div_euclid(86400);
+        let from = now.saturating_sub(3 * 3600);
+        let steps: Vec<&Step> = self
+            .list
+            .iter()
+            .filter(|step| step.dt > from && step.dt.saturating_add(self.city.timezone).div_euclid(86400) == day)
+            .collect();
+        let first = steps.first()?;
+        let mut summary = DaySummary {
+            low: first.main.temp,
+            high: first.main.temp,
+            precipitation: 0.0,
+            wind: 0.0,
+            wind_deg: first.wind.deg.unwrap_or_default(),
+            sunrise: self.city.sunrise,
+            sunset: self.city.sunset,
+        };
+        for step in steps {
+            summary.low = summary.low.min(step.main.temp);
+            summary.high = summary.high.max(step.main.temp);
+            summary.precipitation = summary.precipitation.max(step.pop.clamp(0.0, 1.0));
+            if let Some(speed) = step.wind.speed.filter(|&speed| speed > summary.wind) {
+                summary.wind = speed;
+                summary.wind_deg = step.wind.deg.unwrap_or(summary.wind_deg);
+            }
+        }
+        Some(summary)
+    }
+
+    fn series(&self, now: i64, hours: i64, value: impl Fn(&Step) -> f64) -> Vec<(i64, f64)> {
+        // Include the step in progress, so the series starts at about `now`.
+        let from = now.saturating_sub(3 * 3600);
+        let until = now.saturating_add(hours.saturating_mul(3600));
+        self.list
+            .iter()
+            .filter(|step| step.dt > from && step.dt <= until)
+            .map(|step| (step.dt, value(step)))
+            .collect()
+    }
+}
+
+#[cfg(feature = "http")]
+impl Client {
+    /// Fetches the 5 day forecast for the given location.
+    pub fn forecast(&self, location: &Location) -> Result<Forecast> {
+        let spec = RequestSpec::new(FORECAST_ENDPOINT, location).param("cnt", &MAX_STEPS.to_string())?;
+        self.get_parsed(&spec).map(Forecast::truncate)
+    }
+}
@@ -0,0 +1,448 @@
+//! Formatting shared by all items, so that the same data always renders to
+//! the same bytes, whatever the platform or locale.
+
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error};
+use serde::Deserialize;
+
+use crate::item::Unit;
+use crate::{locale, DataItem, Units};
+
+/// Formats `value` rounded half away from zero to exactly `precision`
+/// decimals. Rust's own float formatting is locale-independent, but rounds
+/// ties to even, which would make e.g. 20.5° render as "20".
+pub fn fixed(value: f64, precision: usize) -> String {
+    let mut s = String::new();
+    // Writing to a String cannot fail.
+    let _ = write_fixed(&mut s, value, precision);
+    s
+}
+
+/// Writes `value` to `out` as [`fixed`] formats it.
+pub fn write_fixed(out: &mut impl Write, value: f64, precision: usize) -> fmt::Result {
+    write!(out, "{:.*}", precision, round(value, precision))
+}
+
+/// Rounds `value` half away from zero to `precision` decimals, as [`fixed`]
+/// renders it, with values that round to zero from below made 0 rather
+/// than -0: -0.4° renders as "0°". Comparisons with thresholds, like
+/// freezing, should use the unrounded value, so a temperature of -0.4° is
+/// below freezing even though it shows as 0°; -0.5° rounds to -1°.
+pub fn round(value: f64, precision: usize) -> f64 {
+    let factor = 10f64.powi(precision as i32);
+    let rounded = (value * factor).round() / factor;
+    // Adding 0 turns -0 into 0 and leaves everything else as it is.
+    rounded + 0.0
+}
+
+/// Returns whether the temperature `t`, in `units`, is below the freezing
+/// point of water. It compares the unrounded value, as [`round`] says
+/// thresholds should, so -0.4°C is below freezing though it shows as 0°.
+pub fn below_freezing(t: f64, units: Units) -> bool {
+    let freezing = match units {
+        Units::Metric => 0.0,
+        Units::Imperial => 32.0,
+        Units::Standard => 273.15,
+    };
+    t < freezing
+}
+
+/// Joins rendered items into a single line. Line breaks inside items (e.g. in
+/// descriptions from the API) are replaced by spaces, so that the output is
+/// exactly one `\n`-terminated line on every platform once printed.
+pub fn single_line<S: AsRef<str>>(items: &[S], separator: &str) -> String {
+    let length = items.iter().map(|item| item.as_ref().len()).sum::<usize>()
+        + separator.len() * items.len().saturating_sub(1);
+    let mut line = String::with_capacity(length);
+    // Writing to a String cannot fail.
+    let _ = write_single_line(&mut line, items, separator);
+    line
+}
+
+/// Writes the items to `out` as [`single_line`] joins them.
+pub fn write_single_line<S: AsRef<str>>(out: &mut impl Write, items: &[S], separator: &str) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.write_str(separator)?;
+        }
+        let mut rest = item.as_ref();
+        while let Some(end) = rest.find(['\r', '\n']) {
+            out.write_str(&rest[..end])?;
+            out.write_char(' ')?;
+            let newline = if rest[end..].starts_with("\r\n") { 2 } else { 1 };
+            rest = &rest[end + newline..];
+        }
+        out.write_str(rest)?;
+    }
+    Ok(())
+}
+
+/// How a number and its unit are joined.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitStyle {
+    /// "12°C"
+    Compact,
+    /// "12 °C"
+    Spaced,
+    /// "12°": the degree sign for temperatures, and no unit for the rest.
+    #[default]
+    Symbolless,
+    /// "12 degrees"
+    Verbose,
+}
+
+impl UnitStyle {
+    pub const ALL: [UnitStyle; 4] = [
+        UnitStyle::Compact,
+        UnitStyle::Spaced,
+        UnitStyle::Symbolless,
+        UnitStyle::Verbose,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            UnitStyle::Compact => "compact",
+            UnitStyle::Spaced => "spaced",
+            UnitStyle::Symbolless => "symbolless",
+            UnitStyle::Verbose => "verbose",
+        }
+    }
+}
+
+impl FromStr for UnitStyle {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        UnitStyle::ALL
+            .into_iter()
+            .find(|style| style.name() == s)
+            .ok_or_else(|| anyhow!("unknown unit style '{}'", s))
+    }
+}
+
+/// Physical quantities values can have a unit for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quantity {
+    Temperature,
+    /// Wind speed.
+    Speed,
+    Pressure,
+    /// How hard it rains or snows, per hour.
+    Precipitation,
+    Distance,
+}
+
+impl Quantity {
+    /// Returns the symbol, and the singular and plural names of the unit.
+    fn unit(self, units: Units) -> (&'static str, &'static str, &'static str) {
+        match (self, units) {
+            (Quantity::Temperature, Units::Metric) => ("°C", "degree", "degrees"),
+            (Quantity::Temperature, Units::Imperial) => ("°F", "degree", "degrees"),
+            (Quantity::Temperature, Units::Standard) => ("K", "kelvin", "kelvins"),
+            (Quantity::Speed, Units::Metric | Units::Standard) => ("m/s", "meter per second", "meters per second"),
+            (Quantity::Speed, Units::Imperial) => ("mph", "mile per hour", "miles per hour"),
+            (Quantity::Pressure, Units::Metric | Units::Standard) => ("hPa", "hectopascal", "hectopascals"),
+            (Quantity::Pressure, Units::Imperial) => ("inHg", "inch of mercury", "inches of mercury"),
+            (Quantity::Precipitation, Units::Metric | Units::Standard) => {
+                ("mm/h", "millimeter per hour", "millimeters per hour")
+            }
+            (Quantity::Precipitation, Units::Imperial) => ("in/h", "inch per hour", "inches per hour"),
+            (Quantity::Distance, Units::Metric | Units::Standard) => ("km", "kilometer", "kilometers"),
+            (Quantity::Distance, Units::Imperial) => ("mi", "mile", "miles"),
+        }
+    }
+}
+
+/// Joins an already formatted number with the unit of `quantity` in `units`,
+/// in the given style.
+pub fn with_unit(number: &str, quantity: Quantity, units: Units, style: UnitStyle) -> String {
+    let (separator, unit) = unit_suffix(quantity, units, style, number == "1");
+    let mut s = String::with_capacity(number.len() + separator.len() + unit.len());
+    s.push_str(number);
+    s.push_str(separator);
+    s.push_str(unit);
+    s
+}
+
+/// Writes `value` with `precision` decimals and the unit of `quantity` to
+/// `out`, as [`with_unit`] joins them.
+pub fn write_with_unit(
+    out: &mut impl Write,
+    value: f64,
+    precision: usize,
+    quantity: Quantity,
+    units: Units,
+    style: UnitStyle,
+) -> fmt::Result {
+    // The number is written as "1" only if it rounds to 1 without decimals.
+    let one = precision == 0 && round(value, 0) == 1.0;
+    let (separator, unit) = unit_suffix(quantity, units, style, one);
+    write_fixed(out, value, precision)?;
+    out.write_str(separator)?;
+    out.write_str(unit)
+}
+
+/// Returns what goes between a number and its unit, and the unit, in `style`.
+/// `one` tells whether the number is written as "1".
+fn unit_suffix(quantity: Quantity, units: Units, style: UnitStyle, one: bool) -> (&'static str, &'static str) {
+    let (symbol, singular, plural) = quantity.unit(units);
+    match style {
+        UnitStyle::Compact => ("", symbol),
+        UnitStyle::Spaced => (" ", symbol),
+        // Kelvin are not degrees.
+        UnitStyle::Symbolless if quantity == Quantity::Temperature && units != Units::Standard => ("", "°"),
+        UnitStyle::Symbolless => ("", ""),
+        UnitStyle::Verbose if one => (" ", singular),
+        UnitStyle::Verbose => (" ", plural),
+    }
+}
+
+/// Formats `value` with `precision` decimals and the name of `unit` in
+/// `units`, in `lang` where [`locale::unit_names`] has a table for it, as in
+/// "12 degrees Celsius" or "67 percent": the verbose unit style, with the
+/// scale of temperatures and no symbols, for screen readers.
+pub fn spoken(value: f64, precision: usize, unit: Unit, units: Units, lang: &str) -> String {
+    let number = fixed(value, precision);
+    match locale::unit_names(lang).name(unit, units, number == "1") {
+        "" => number,
+        name => format!("{} {}", number, name),
+    }
+}
+
+/// Introduces the value of `item` rendered for screen readers, as in
+/// "humidity 67 percent" (see [`crate::ItemInfo::spoken`]). Empty values
+/// stay empty.
+pub fn accessible(item: DataItem, value: &str) -> String {
+    match item.info().spoken {
+        _ if value.is_empty() => String::new(),
+        "" => value.to_string(),
+        spoken => format!("{} {}", spoken, value),
+    }
+}
+
+/// Formats an age of `seconds` in its largest whole unit, as in "45s", "35m",
+/// "5h" or "2d". Negative ages, from clocks that disagree, are "0s".
+pub fn age(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m", seconds / 60),
+        3600..=86399 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86400),
+    }
+}
+
+/// Formats the time at `epoch` in the zone `offset` seconds from UTC with a
+/// strftime-like `format`, or returns `None` if the time is out of range or
+/// the format invalid (see [`valid_time_format`]).
+#[cfg(feature = "time")]
+pub fn local_time(epoch: i64, offset: i64, format: &str) -> Option<String> {
+    if !valid_time_format(format) {
+        return None;
+    }
+    Some(crate::local_datetime(epoch, offset)?.format(format).to_string())
+}
+
+/// Returns whether `format` is a strftime-like format chrono understands.
+#[cfg(feature = "time")]
+pub fn valid_time_format(format: &str) -> bool {
+    use chrono::format::{Item, StrftimeItems};
+
+    StrftimeItems::new(format).all(|item| item != Item::Error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_rounds_half_away_from_zero() {
+        let cases = [
+            (20.5, 0, "21"),
+            (21.5, 0, "22"),
+            (-20.5, 0, "-21"),
+            (20.49, 0, "20"),
+            (12.25, 1, "12.3"),
+            (-12.25, 1, "-12.3"),
+            (0.125, 2, "0.13"),
+            (1012.0, 0, "1012"),
+            (7.0, 2, "7.00"),
+            (1e15, 0, "1000000000000000"),
+            (f64::NAN, 0, "NaN"),
+            (f64::INFINITY, 1, "inf"),
+            (f64::NEG_INFINITY, 0, "-inf"),
+        ];
+        for (value, precision, expected) in cases {
+            assert_eq!(fixed(value, precision), expected, "{} to {}", value, precision);
+        }
+    }
+
+    #[test]
+    fn nothing_renders_as_minus_zero() {
+        let cases = [
+            (-0.0, 0, "0"),
+            (-0.0, 1, "0.0"),
+            (-0.4, 0, "0"),
+            (-0.49, 0, "0"),
+            (-0.5, 0, "-1"),
+            (-0.04, 1, "0.0"),
+            (-0.05, 1, "-0.1"),
+            (-0.004, 2, "0.00"),
+            (-1e-300, 0, "0"),
+            (0.4, 0, "0"),
+        ];
+        for (value, precision, expected) in cases {
+            assert_eq!(fixed(value, precision), expected, "{} to {}", value, precision);
+            assert!(!round(value, precision).is_sign_negative() || expected.starts_with('-'), "{}", value);
+        }
+    }
+
+    #[test]
+    fn freezing_compares_the_unrounded_value() {
+        let cases = [
+            (-0.4, Units::Metric, true),
+            (-0.0, Units::Metric, false),
+            (0.0, Units::Metric, false),
+            (31.9, Units::Imperial, true),
+            (32.0, Units::Imperial, false),
+            (273.1, Units::Standard, true),
+            (273.15, Units::Standard, false),
+            (f64::NAN, Units::Metric, false),
+        ];
+        for (t, units, below) in cases {
+            assert_eq!(below_freezing(t, units), below, "{} in {:?}", t, units);
+        }
+    }
+
+    #[test]
+    fn round_is_what_fixed_renders() {
+        assert_eq!(round(20.5, 0), 21.0);
+        assert_eq!(round(-0.4, 0), 0.0);
+        assert!(round(-0.4, 0).is_sign_positive());
+        assert_eq!(round(-0.5, 0), -1.0);
+        assert_eq!(round(1012.345, 1), 1012.3);
+        assert!(round(f64::NAN, 1).is_nan());
+        for i in -2000..=2000 {
+            let value = i as f64 / 8.0;
+            for precision in 0..3 {
+                assert_eq!(fixed(value, precision), format!("{:.*}", precision, round(value, precision)));
+            }
+        }
+    }
+
+    #[test]
+    fn formatting_is_deterministic() {
+        // The same value always renders to the same bytes, whichever way it
+        // is written, and on every thread.
+        let values: Vec<f64> = (-500..500).map(|i| i as f64 * 0.37).chain([-0.0, 0.5, -0.5, 1e-9]).collect();
+        let render = |values: &[f64]| -> Vec<String> {
+            values.iter().flat_map(|&value| (0..3).map(move |precision| fixed(value, precision))).collect()
+        };
+        let expected = render(&values);
+        assert_eq!(render(&values), expected);
+        let mut written = Vec::new();
+        for &value in &values {
+            for precision in 0..3 {
+                let mut s = String::new();
+                write_fixed(&mut s, value, precision).unwrap();
+                written.push(s);
+            }
+        }
+        assert_eq!(written, expected);
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let values = values.clone();
+                std::thread::spawn(move || render(&values))
+            })
+            .collect();
+        for thread in threads {
+            assert_eq!(thread.join().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn single_lines() {
+        let cases: [(&[&str], &str, &str); 9] = [
+            (&[], " ", ""),
+            (&["12°"], " ", "12°"),
+            (&["12°", "67%"], " | ", "12° | 67%"),
+            (&["light\nrain", "12°"], " ", "light rain 12°"),
+            (&["light\r\nrain"], " ", "light rain"),
+            (&["light\rrain"], " ", "light rain"),
+            (&["a\n\nb", "c\r\n"], ",", "a  b,c "),
+            (&["", "12°", ""], "|", "|12°|"),
+            (&["🌧 rain", "zéro"], " · ", "🌧 rain · zéro"),
+        ];
+        for (items, separator, expected) in cases {
+            let line = single_line(items, separator);
+            assert_eq!(line, expected, "{:?}", items);
+            assert!(!line.contains(['\n', '\r']));
+            let mut written = String::new();
+            write_single_line(&mut written, items, separator).unwrap();
+            assert_eq!(written, line);
+        }
+        let owned = vec!["a".to_string(), "b\nc".to_string()];
+        assert_eq!(single_line(&owned, "-"), "a-b c");
+    }
+
+    #[test]
+    fn ages_in_their_largest_whole_unit() {
+        let cases = [
+            (0, "0s"),
+            (45, "45s"),
+            (59, "59s"),
+            (60, "1m"),
+            (35 * 60 + 59, "35m"),
+            (3599, "59m"),
+            (3600, "1h"),
+            (5 * 3600 + 1800, "5h"),
+            (86399, "23h"),
+            (86400, "1d"),
+            (2 * 86400 + 3600, "2d"),
+            (400 * 86400, "400d"),
+            // Clocks that disagree.
+            (-30, "0s"),
+            (i64::MIN, "0s"),
+        ];
+        for (seconds, expected) in cases {
+            assert_eq!(age(seconds), expected, "{}", seconds);
+        }
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn local_times_in_12_and_24_hours() {
+        // 1 May 2024, 16:30:05 UTC.
+        let epoch = 1_714_581_005;
+        let cases = [
+            (0, "%H:%M:%S", "16:30:05"),
+            (0, "%I:%M %p", "04:30 PM"),
+            // Tokyo, 9 hours ahead, is past midnight.
+            (32_400, "%H:%M:%S", "01:30:05"),
+            (32_400, "%I:%M %p", "01:30 AM"),
+            (32_400, "%Y-%m-%d %H:%M", "2024-05-02 01:30"),
+            // New York, 4 hours behind in summer.
+            (-14_400, "%-I:%M%P", "12:30pm"),
+            // Kathmandu, 5:45 ahead.
+            (20_700, "%H:%M", "22:15"),
+        ];
+        for (offset, format, expected) in cases {
+            assert_eq!(local_time(epoch, offset, format).as_deref(), Some(expected), "{} at {}", format, offset);
+        }
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn invalid_time_formats_are_refused() {
+        for format in ["%H:%M", "%I:%M %p", "at %T", "", "%%"] {
+            assert!(valid_time_format(format), "{:?}", format);
+        }
+        for format in ["%Q", "%H:%", "%E"] {
+            assert!(!valid_time_format(format), "{:?}", format);
+            assert_eq!(local_time(0, 0, format), None, "{:?}", format);
+        }
+    }
+}
@@ -0,0 +1,332 @@
+//! Distances and directions between points on the earth's surface, in
+//! decimal degrees.
+
+use crate::CurrentWeather;
+
+#[cfg(feature = "http")]
+use anyhow::Result;
+#[cfg(feature = "http")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "http")]
+use crate::request::{RequestSpec, Selector};
+#[cfg(feature = "http")]
+use crate::{Client, Location};
+
+/// Endpoint of the geocoding API, which finds places by name.
+pub const GEOCODING_ENDPOINT: &str = "geo/1.0/direct";
+/// Endpoint of the geocoding API that finds places by zip code.
+pub const ZIP_ENDPOINT: &str = "geo/1.0/zip";
+/// Most places the geocoding API finds for a name. They are always asked for,
+/// as the `limit` is not part of what responses are cached under.
+#[cfg(feature = "http")]
+const MAX_PLACES: &str = "5";
+
+/// Mean radius of the earth, in kilometers.
+const EARTH_RADIUS: f64 = 6371.0088;
+/// Distance below which a direction is not worth giving, in kilometers.
+const NEARBY: f64 = 0.5;
+
+/// Where one point is, seen from another.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Offset {
+    /// Great-circle distance, in kilometers.
+    pub distance: f64,
+    /// Initial bearing, in degrees clockwise from north.
+    pub bearing: f64,
+}
+
+impl Offset {
+    /// Returns the compass point of the bearing, or `None` if the points are
+    /// too close for a direction to mean anything.
+    pub fn direction(&self) -> Option<&'static str> {
+        (self.distance >= NEARBY).then(|| compass_point(self.bearing))
+    }
+}
+
+impl CurrentWeather {
+    /// Returns where the weather station is, seen from `from` as (latitude,
+    /// longitude), or `None` if the response has no coordinates.
+    pub fn station_offset(&self, from: (f64, f64)) -> Option<Offset> {
+        Some(offset(from, self.coords()?))
+    }
+}
+
+/// A place found by the geocoding API.
+#[cfg(feature = "http")]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct GeoResult {
+    /// Name of the place, in English.
+    #[serde(default)]
+    pub name: String,
+    /// State or region, for places that have one.
+    #[serde(default)]
+    pub state: Option<String>,
+    /// Country code, as in "US".
+    #[serde(default)]
+    pub country: String,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+#[cfg(feature = "http")]
+impl std::fmt::Display for GeoResult {
+    /// Writes the place as in "Springfield, Illinois, US".
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts = [Some(&self.name), self.state.as_ref(), Some(&self.country)];
+        let parts: Vec<&str> = parts.into_iter().flatten().map(String::as_str).filter(|p| !p.is_empty()).collect();
+        f.write_str(&parts.join(", "))
+    }
+}
+
+#[cfg(feature = "http")]
+impl Client {
+    /// Looks up the coordinates of a city or a zip code with the geocoding
+    /// API, as (latitude, longitude), or returns `None` if it finds no such
+    /// city. Coordinates are returned as they are, and `None` for city ids.
+    pub fn geocode(&self, location: &Location) -> Result<Option<(f64, f64)>> {
+        match location {
+            Location::City { .. } => {}
+            Location::Coords { lat, lon } => return Ok(Some((*lat, *lon))),
+            Location::CityId { .. } => return Ok(None),
+            Location::Zip { .. } => {
+                // Unlike names, codes resolve to a single place, not a list,
+                // and one that is not found is a 404.
+                return match self.get_tolerant::<GeoResult>(&RequestSpec::new(ZIP_ENDPOINT, location).unlocalized()) {
+                    Ok(place) => Ok(Some((place.lat, place.lon))),
+                    Err(e) if e.downcast_ref().is_some_and(crate::ApiError::is_not_found) => Ok(None),
+                    Err(e) => Err(e),
+                };
+            }
+        }
+        let spec = RequestSpec::new(GEOCODING_ENDPOINT, location).unlocalized().param("limit", MAX_PLACES)?;
+        let places: Vec<GeoResult> = self.get_tolerant(&spec)?;
+        Ok(places.first().map(|place| (place.lat, place.lon)))
+    }
+
+    /// Finds up to `limit` places by name with the geocoding API, as in
+    /// "springfield,us" or "springfield,il,us", best match first. The API
+    /// finds at most 5.
+    pub fn places(&self, query: &str, limit: usize) -> Result<Vec<GeoResult>> {
+        let spec = RequestSpec::new(GEOCODING_ENDPOINT, Selector::Query(query.to_string()))
+            .unlocalized()
+            .param("limit", MAX_PLACES)?;
+        let mut places: Vec<GeoResult> = self.get_tolerant(&spec)?;
+        places.truncate(limit);
+        Ok(places)
+    }
+}
+
+/// Finds up to `limit` places by name. This is a shortcut for building a
+/// [`Client`] and calling [`Client::places`].
+#[cfg(feature = "http")]
+pub fn geocode(query: &str, limit: usize, key: &str) -> Result<Vec<GeoResult>> {
+    Client::builder().api_key(key).build()?.places(query, limit)
+}
+
+/// Returns where `to` is, seen from `from`, both as (latitude, longitude).
+pub fn offset(from: (f64, f64), to: (f64, f64)) -> Offset {
+    Offset {
+        distance: distance(from, to),
+        bearing: bearing(from, to),
+    }
+}
+
+/// Returns the great-circle distance in kilometers, with the haversine
+/// formula.
+pub fn distance((lat1, lon1): (f64, f64), (lat2, lon2): (f64, f64)) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_phi = phi2 - phi1;
+    let d_lambda = (lon2 - lon1).to_radians();
+    let a = (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+}
+
+/// Returns the initial bearing of the great circle from `from` to `to`, in
+/// degrees clockwise from north.
+pub fn bearing((lat1, lon1): (f64, f64), (lat2, lon2): (f64, f64)) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lambda = (lon2 - lon1).to_radians();
+    let y = d_lambda.sin() * phi2.cos();
+    let x = phi1.cos() * phi2.sin() - phi1.sin() * phi2.cos() * d_lambda.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+/// Returns the nearest of the eight compass points to `bearing` in degrees,
+/// e.g. "NE" for 50.
+pub fn compass_point(bearing: f64) -> &'static str {
+    const POINTS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+    POINTS[((bearing.rem_euclid(360.0) / 45.0).round() as usize) % 8]
+}
+
+/// Returns the nearest of the sixteen compass points to `bearing` in degrees,
+/// as wind directions are given, e.g. "NNE" for 20. A bearing halfway between
+/// two points goes to the next one clockwise: 348.75 is "N".
+pub fn compass_point_16(bearing: f64) -> &'static str {
+    const POINTS: [&str; 16] = [
+        "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE",
+        "S", "SSW", "SW", "WSW", "W", "WNW", "NW", "NNW",
+    ];
+    POINTS[((bearing.rem_euclid(360.0) / 22.5).round() as usize) % 16]
+}
+
+/// Returns the name of the nearest of the sixteen compass points to `bearing`
+/// in degrees, written out in `lang`, e.g. "north-northeast" for 20 in
+/// English or "Nord-Nordost" in German. Languages are OpenWeatherMap's codes;
+/// those without a table here get English names.
+pub fn compass_name(bearing: f64, lang: &str) -> &'static str {
+    let names = match lang.to_lowercase().replace('-', "_").as_str() {
+        "de" => &GERMAN,
+        "es" | "sp" => &SPANISH,
+        "fr" => &FRENCH,
+        "it" => &ITALIAN,
+        "nl" => &DUTCH,
+        "pt" | "pt_br" => &PORTUGUESE,
+        _ => &ENGLISH,
+    };
+    names[((bearing.rem_euclid(360.0) / 22.5).round() as usize) % 16]
+}
+
+/// Names of the sixteen compass points from north, clockwise, in one language.
+type CompassNames = [&'static str; 16];
+
+static ENGLISH: CompassNames = [
+    "north", "north-northeast", "northeast", "east-northeast",
+    "east", "east-southeast", "southeast", "south-southeast",
+    "south", "south-southwest", "southwest", "west-southwest",
+    "west", "west-northwest", "northwest", "north-northwest",
+];
+
+static GERMAN: CompassNames = [
+    "Nord", "Nord-Nordost", "Nordost", "Ost-Nordost",
+    "Ost", "Ost-Südost", "Südost", "Süd-Südost",
+    "Süd", "Süd-Südwest", "Südwest", "West-Südwest",
+    "West", "West-Nordwest", "Nordwest", "Nord-Nordwest",
+];
+
+static SPANISH: CompassNames = [
+    "norte", "nornordeste", "nordeste", "estenordeste",
+    "este", "estesudeste", "sudeste", "sudsudeste",
+    "sur", "sudsudoeste", "sudoeste", "oestesudoeste",
+    "oeste", "oestenoroeste", "noroeste", "nornoroeste",
+];
+
+static FRENCH: CompassNames = [
+    "nord", "nord-nord-est", "nord-est", "est-nord-est",
+    "est", "est-sud-est", "sud-est", "sud-sud-est",
+    "sud", "sud-sud-ouest", "sud-ouest", "ouest-sud-ouest",
+    "ouest", "ouest-nord-ouest", "nord-ouest", "nord-nord-ouest",
+];
+
+static ITALIAN: CompassNames = [
+    "nord", "nord-nord-est", "nord-est", "est-nord-est",
+    "est", "est-sud-est", "sud-est", "sud-sud-est",
+    "sud", "sud-sud-ovest", "sud-ovest", "ovest-sud-ovest",
+    "ovest", "ovest-nord-ovest", "nord-ovest", "nord-nord-ovest",
+];
+
+static DUTCH: CompassNames = [
+    "noord", "noord-noordoost", "noordoost", "oost-noordoost",
+    "oost", "oost-zuidoost", "zuidoost", "zuid-zuidoost",
+    "zuid", "zuid-zuidwest", "zuidwest", "west-zuidwest",
+    "west", "west-noordwest", "noordwest", "noord-noordwest",
+];
+
+static PORTUGUESE: CompassNames = [
+    "norte", "nor-nordeste", "nordeste", "lés-nordeste",
+    "leste", "lés-sudeste", "sudeste", "su-sudeste",
+    "sul", "su-sudoeste", "sudoeste", "oés-sudoeste",
+    "oeste", "oés-noroeste", "noroeste", "nor-noroeste",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POINTS_16: [&str; 16] = [
+        "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW", "NW", "NNW",
+    ];
+
+    #[test]
+    fn sixteen_points_at_their_edges() {
+        // Each point covers 22.5°, from 11.25° before it up to 11.25° after
+        // it; the edge itself belongs to the next point clockwise.
+        for (n, point) in POINTS_16.iter().enumerate() {
+            let center = n as f64 * 22.5;
+            let next = POINTS_16[(n + 1) % 16];
+            assert_eq!(compass_point_16(center), *point, "{}", center);
+            assert_eq!(compass_point_16(center - 11.25 + 1e-9), *point, "just after {}", center - 11.25);
+            assert_eq!(compass_point_16(center + 11.25 - 1e-9), *point, "just before {}", center + 11.25);
+            assert_eq!(compass_point_16(center + 11.25), next, "{}", center + 11.25);
+        }
+        assert_eq!(compass_point_16(348.75), "N");
+        assert_eq!(compass_point_16(348.7499), "NNW");
+        assert_eq!(compass_point_16(11.25), "NNE");
+        assert_eq!(compass_point_16(11.2499), "N");
+    }
+
+    #[test]
+    fn bearings_outside_a_turn_wrap() {
+        assert_eq!(compass_point_16(360.0), "N");
+        assert_eq!(compass_point_16(382.5), "NNE");
+        assert_eq!(compass_point_16(-22.5), "NNW");
+        assert_eq!(compass_point_16(-11.25), "N");
+        assert_eq!(compass_point_16(720.0 + 180.0), "S");
+        assert_eq!(compass_point(-45.0), "NW");
+        assert_eq!(compass_point(337.5), "N");
+        assert_eq!(compass_point(337.4999), "NW");
+    }
+
+    #[test]
+    fn names_follow_the_points() {
+        for n in 0..16 {
+            let bearing = n as f64 * 22.5 + 11.25;
+            assert_eq!(compass_name(bearing, "en"), ENGLISH[(n + 1) % 16], "{}", bearing);
+        }
+        assert_eq!(compass_name(348.75, "de"), "Nord");
+        assert_eq!(compass_name(20.0, "pt-BR"), "nor-nordeste");
+        assert_eq!(compass_name(20.0, "xx"), "north-northeast");
+    }
+
+    #[test]
+    fn names_in_each_language() {
+        let names = |lang: &str| [0.0, 22.5, 100.0, 230.0, 292.5, 350.0].map(|bearing| compass_name(bearing, lang));
+        assert_eq!(names("en"), ["north", "north-northeast", "east", "southwest", "west-northwest", "north"]);
+        assert_eq!(names("de"), ["Nord", "Nord-Nordost", "Ost", "Südwest", "West-Nordwest", "Nord"]);
+        assert_eq!(names("nl"), ["noord", "noord-noordoost", "oost", "zuidwest", "west-noordwest", "noord"]);
+        assert_eq!(names("fr"), ["nord", "nord-nord-est", "est", "sud-ouest", "ouest-nord-ouest", "nord"]);
+        assert_eq!(names("es"), ["norte", "nornordeste", "este", "sudoeste", "oestenoroeste", "norte"]);
+        assert_eq!(names("it")[3], "sud-ovest");
+        assert_eq!(names("pt")[4], "oés-noroeste");
+        // Codes are read in any case, and OpenWeatherMap's "sp" is Spanish.
+        assert_eq!(names("DE"), names("de"));
+        assert_eq!(names("sp"), names("es"));
+        assert_eq!(names("ja"), names("en"));
+    }
+
+    #[test]
+    fn offsets_across_the_antimeridian() {
+        let east = offset((0.0, 179.9), (0.0, -179.9));
+        assert!((east.distance - 22.24).abs() < 0.01, "{:?}", east);
+        assert!((east.bearing - 90.0).abs() < 1e-6, "{:?}", east);
+        assert_eq!(east.direction(), Some("E"));
+        let west = offset((0.0, -179.9), (0.0, 179.9));
+        assert_eq!((west.distance, west.direction()), (east.distance, Some("W")));
+        // Fiji, where the stations of a town can be on either side.
+        let fiji = offset((-16.8, 179.95), (-16.7, -179.95));
+        assert!((fiji.distance - 15.4).abs() < 0.01 && fiji.direction() == Some("NE"), "{:?}", fiji);
+        let north = offset((65.0, -179.99), (65.0, 179.99));
+        assert!((north.distance - 0.94).abs() < 0.01 && north.direction() == Some("W"), "{:?}", north);
+    }
+
+    #[test]
+    fn no_direction_without_a_distance() {
+        let same = offset((52.374, 4.8897), (52.374, 4.8897));
+        assert_eq!(same.distance, 0.0);
+        assert_eq!(same.direction(), None);
+        // Up to half a kilometer away has no direction either.
+        assert_eq!(offset((52.374, 4.8897), (52.378, 4.8897)).direction(), None);
+        assert_eq!(offset((52.374, 4.8897), (52.379, 4.8897)).direction(), Some("N"));
+        assert!(offset((90.0, 0.0), (90.0, 120.0)).distance < 1e-9);
+    }
+}
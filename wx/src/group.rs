@@ -0,0 +1,56 @@
+//! The current weather of several cities at once, by their ids, from the
+//! group endpoint.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{de, CurrentWeather};
+
+#[cfg(feature = "http")]
+use anyhow::{anyhow, Result};
+
+#[cfg(feature = "http")]
+use crate::request::{RequestSpec, Selector};
+#[cfg(feature = "http")]
+use crate::{Client, Units};
+
+/// Endpoint of the current weather of several cities.
+pub const GROUP_ENDPOINT: &str = "data/2.5/group";
+/// Most city ids the endpoint takes in one request.
+pub const MAX_IDS: usize = 20;
+
+/// The current weather of several cities, as `data/2.5/group` returns it.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Group {
+    cnt: u64,
+    /// The current weather of each city found, in the order of the ids.
+    #[serde(default, deserialize_with = "de::null_as_default")]
+    list: Vec<CurrentWeather>,
+}
+
+#[cfg(feature = "http")]
+impl Client {
+    /// Fetches the current weather of the cities with the ids `ids`, at most
+    /// [`MAX_IDS`] of them, in one request. Ids the API does not know are
+    /// left out of the result.
+    pub fn group(&self, ids: &[u64]) -> Result<Vec<CurrentWeather>> {
+        if ids.is_empty() {
+            return Err(anyhow!("no city ids to fetch"));
+        }
+        if ids.len() > MAX_IDS {
+            return Err(anyhow!(
+                "{} city ids given, but at most {} can be fetched at once",
+                ids.len(),
+                MAX_IDS
+            ));
+        }
+        let group: Group = self.get_parsed(&RequestSpec::new(GROUP_ENDPOINT, Selector::Ids(ids.to_vec())))?;
+        Ok(group.list)
+    }
+}
+
+/// Fetches the current weather of the cities with the ids `ids`. This is a
+/// shortcut for building a [`Client`] and calling [`Client::group`].
+#[cfg(feature = "http")]
+pub fn get_group(ids: &[u64], units: Units, key: &str) -> Result<Vec<CurrentWeather>> {
+    Client::builder().api_key(key).units(units).build()?.group(ids)
+}
@@ -0,0 +1,300 @@
+//! Past observations, and what is built from them: the "what changed since
+//! sunrise" narrative, which needs the `time` feature, and the temperature
+//! trend.
+
+use serde::{Deserialize, Serialize};
+
+use crate::CurrentWeather;
+#[cfg(feature = "time")]
+use crate::item::Unit;
+#[cfg(feature = "time")]
+use crate::{format, local_datetime, Units};
+
+/// The parts of a reading worth remembering.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Observation {
+    /// Time of data calculation, unix, UTC
+    pub dt: i64,
+    /// Temperature, in the units it was fetched with
+    pub temp: f64,
+    /// Weather condition id, 0 if unknown
+    pub condition: u64,
+}
+
+impl CurrentWeather {
+    /// Returns the parts of this reading that are kept in the history.
+    pub fn observation(&self) -> Observation {
+        Observation {
+            dt: self.dt,
+            temp: self.main.temp,
+            condition: self.weather.first().map_or(0, |w| w.id),
+        }
+    }
+
+    /// Returns which way the temperature went since the latest of the earlier
+    /// observations in `history`, in the same units as this one, or `None`
+    /// if there is none or it is more than `window` seconds older. It rose
+    /// or fell if it changed by more than `delta`.
+    pub fn trend(&self, history: &[Observation], delta: f64, window: i64) -> Option<Trend> {
+        let current = self.observation();
+        let previous = history
+            .iter()
+            .filter(|o| o.dt < current.dt)
+            .max_by_key(|o| o.dt)
+            .filter(|o| current.dt - o.dt <= window)?;
+        Some(trend(previous, &current, delta))
+    }
+
+    /// Describes how the weather changed since sunrise, given the earlier
+    /// observations of this location, up to the time of this observation.
+    #[cfg(feature = "time")]
+    pub fn day_story(&self, history: &[Observation]) -> String {
+        day_story(history, &self.observation(), self.sys.sunrise, self.timezone)
+    }
+
+    /// Describes how the weather changed since sunrise, as
+    /// [`CurrentWeather::day_story`] does, with the temperatures in `units`
+    /// named for screen readers (see [`format::spoken`]).
+    #[cfg(feature = "time")]
+    pub fn day_story_spoken(&self, history: &[Observation], units: Units, lang: &str) -> String {
+        story(history, &self.observation(), self.sys.sunrise, self.timezone, |temp| {
+            format::spoken(temp, 0, Unit::Temperature, units, lang)
+        })
+    }
+}
+
+/// Which way the temperature went.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trend {
+    Rising,
+    Falling,
+    Steady,
+}
+
+impl Trend {
+    /// Returns the arrow of the trend: "↑", "↓" or "→".
+    pub fn arrow(self) -> &'static str {
+        match self {
+            Trend::Rising => "↑",
+            Trend::Falling => "↓",
+            Trend::Steady => "→",
+        }
+    }
+
+    /// Returns the name of the trend, as in "rising", for screen readers.
+    pub fn name(self) -> &'static str {
+        match self {
+            Trend::Rising => "rising",
+            Trend::Falling => "falling",
+            Trend::Steady => "steady",
+        }
+    }
+}
+
+/// Returns which way the temperature went from `previous` to `current`,
+/// which have to be in the same units: rising or falling if it changed by
+/// more than `delta`, and steady otherwise.
+pub fn trend(previous: &Observation, current: &Observation, delta: f64) -> Trend {
+    let change = current.temp - previous.temp;
+    if change > delta {
+        Trend::Rising
+    } else if change < -delta {
+        Trend::Falling
+    } else {
+        Trend::Steady
+    }
+}
+
+/// Describes how the weather changed from the first observation after
+/// `sunrise` up to `current`, e.g. "Warmed from 4° at 07:00 to 13° now; rain
+/// stopped around 11:00."
+///
+/// `history` holds earlier observations in any order; those from before
+/// sunrise or from another local day are ignored. `offset` is the location's
+/// shift from UTC in seconds. With no usable history the sentence only
+/// describes the current temperature.
+#[cfg(feature = "time")]
+pub fn day_story(history: &[Observation], current: &Observation, sunrise: i64, offset: i64) -> String {
+    story(history, current, sunrise, offset, degrees)
+}
+
+/// Tells the story of [`day_story`], with temperatures written by `degrees`.
+#[cfg(feature = "time")]
+fn story(
+    history: &[Observation],
+    current: &Observation,
+    sunrise: i64,
+    offset: i64,
+    degrees: impl Fn(f64) -> String,
+) -> String {
+    let today = |dt: i64| local_datetime(dt, offset).map(|t| t.naive_local().date());
+    // Observations only count if their local day can be told at all, which
+    // also keeps the times below in range.
+    let current_day = today(current.dt);
+    let mut day: Vec<&Observation> = history
+        .iter()
+        .filter(|o| o.dt >= sunrise && o.dt < current.dt && current_day.is_some() && today(o.dt) == current_day)
+        .collect();
+    day.sort_by_key(|o| o.dt);
+
+    let now = degrees(current.temp);
+    let Some(first) = day.first() else {
+        return format!("{} now; no earlier observations today.", now);
+    };
+
+    let start = degrees(first.temp);
+    let since = clock(first.dt, offset);
+    let delta = current.temp - first.temp;
+    let mut story = if delta >= 1.0 {
+        format!("Warmed from {} at {} to {} now", start, since, now)
+    } else if delta <= -1.0 {
+        format!("Cooled from {} at {} to {} now", start, since, now)
+    } else {
+        format!("Steady around {} since {}", now, since)
+    };
+
+    day.push(current);
+    if let Some(change) = last_precipitation_change(&day) {
+        story.push_str("; ");
+        story.push_str(&change.describe(offset));
+    }
+    story.push('.');
+    story
+}
+
+/// A change between precipitation and dry weather.
+#[cfg(feature = "time")]
+struct Change {
+    /// Kind of precipitation that started or stopped.
+    kind: &'static str,
+    started: bool,
+    dt: i64,
+}
+
+#[cfg(feature = "time")]
+impl Change {
+    fn describe(&self, offset: i64) -> String {
+        let verb = if self.started { "started" } else { "stopped" };
+        format!("{} {} around {}", self.kind, verb, clock(round_to_hour(self.dt, offset), offset))
+    }
+}
+
+/// Finds the last time precipitation started or stopped in `day`, which is
+/// in chronological order.
+#[cfg(feature = "time")]
+fn last_precipitation_change(day: &[&Observation]) -> Option<Change> {
+    day.windows(2)
+        .rev()
+        .find_map(|pair| {
+            let (before, after) = (precipitation(pair[0].condition), precipitation(pair[1].condition));
+            match (before, after) {
+                (None, Some(kind)) => Some(Change { kind, started: true, dt: pair[1].dt }),
+                (Some(kind), None) => Some(Change { kind, started: false, dt: pair[1].dt }),
+                _ => None,
+            }
+        })
+}
+
+/// Returns the kind of precipitation for a condition id, if any.
+#[cfg(feature = "time")]
+fn precipitation(condition: u64) -> Option<&'static str> {
+    match condition / 100 {
+        2 => Some("thunderstorm"),
+        3 => Some("drizzle"),
+        5 => Some("rain"),
+        6 => Some("snow"),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "time")]
+fn degrees(temp: f64) -> String {
+    format!("{}°", format::fixed(temp, 0))
+}
+
+#[cfg(feature = "time")]
+fn clock(dt: i64, offset: i64) -> String {
+    local_datetime(dt, offset)
+        .map(|t| t.format("%H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// Rounds to the nearest local full hour.
+#[cfg(feature = "time")]
+fn round_to_hour(dt: i64, offset: i64) -> i64 {
+    (dt + offset + 1800).div_euclid(3600) * 3600 - offset
+}
+
+#[cfg(all(test, feature = "time"))]
+mod tests {
+    use super::*;
+
+    /// 1 May 2024 in Amsterdam, two hours ahead of UTC, where the sun rose
+    /// at 06:05.
+    const OFFSET: i64 = 7200;
+    const SUNRISE: i64 = 1_714_536_300;
+
+    /// Returns an observation at `hh:mm` local time on that day.
+    fn at(hh: i64, mm: i64, temp: f64, condition: u64) -> Observation {
+        Observation { dt: 1_714_521_600 - OFFSET + hh * 3600 + mm * 60, temp, condition }
+    }
+
+    fn story(history: &[Observation], current: Observation) -> String {
+        day_story(history, &current, SUNRISE, OFFSET)
+    }
+
+    #[test]
+    fn a_day_that_warmed_after_the_rain() {
+        let history = [at(7, 0, 4.0, 500), at(9, 0, 7.0, 501), at(11, 5, 10.0, 803)];
+        let expected = "Warmed from 4° at 07:00 to 13° now; rain stopped around 11:00.";
+        assert_eq!(story(&history, at(14, 0, 13.0, 803)), expected);
+    }
+
+    #[test]
+    fn a_day_that_cooled_into_snow() {
+        let history = [at(7, 0, 10.0, 800), at(12, 20, 5.0, 600)];
+        let expected = "Cooled from 10° at 07:00 to 2° now; snow started around 12:00.";
+        assert_eq!(story(&history, at(15, 0, 2.0, 601)), expected);
+    }
+
+    #[test]
+    fn a_steady_day() {
+        let history = [at(7, 30, 12.4, 800), at(10, 0, 13.1, 801)];
+        assert_eq!(story(&history, at(12, 0, 12.9, 800)), "Steady around 13° since 07:30.");
+        // A change of less than a degree either way is steady too.
+        assert_eq!(story(&[at(7, 30, 13.8, 800)], at(12, 0, 12.9, 800)), "Steady around 13° since 07:30.");
+    }
+
+    #[test]
+    fn only_the_last_change_is_told() {
+        let history = [at(7, 0, 8.0, 800), at(8, 0, 8.0, 500), at(10, 0, 9.0, 802), at(12, 31, 10.0, 300)];
+        let expected = "Warmed from 8° at 07:00 to 11° now; drizzle started around 13:00.";
+        assert_eq!(story(&history, at(13, 0, 11.0, 301)), expected);
+        // Rain that turns to snow is no change between wet and dry.
+        let history = [at(7, 0, 1.0, 500), at(9, 0, 0.0, 600)];
+        assert_eq!(story(&history, at(11, 0, 0.5, 600)), "Steady around 1° since 07:00.");
+    }
+
+    #[test]
+    fn history_outside_the_day_is_left_out() {
+        let current = at(9, 0, 13.0, 800);
+        let sparse = "13° now; no earlier observations today.";
+        assert_eq!(story(&[], current), sparse);
+        // Before sunrise, yesterday, and later than the current observation.
+        let history = [at(5, 0, 4.0, 800), at(-10, 0, 4.0, 800), at(9, 0, 4.0, 800), at(10, 0, 4.0, 500)];
+        assert_eq!(story(&history, current), sparse);
+    }
+
+    #[test]
+    fn the_order_of_the_history_does_not_matter() {
+        let history = [at(11, 5, 10.0, 803), at(7, 0, 4.0, 500), at(9, 0, 7.0, 501)];
+        let expected = "Warmed from 4° at 07:00 to 13° now; rain stopped around 11:00.";
+        assert_eq!(story(&history, at(14, 0, 13.0, 803)), expected);
+    }
+
+    #[test]
+    fn an_observation_out_of_range_has_no_story_to_tell() {
+        let current = Observation { dt: i64::MAX, temp: 13.0, condition: 800 };
+        assert_eq!(story(&[at(7, 0, 4.0, 800)], current), "13° now; no earlier observations today.");
+    }
+}
@@ -0,0 +1,360 @@
+//! Glyphs for the icon item, and telling day from night.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error};
+use serde::Deserialize;
+
+use crate::{CurrentWeather, Weather};
+
+/// OpenWeatherMap icon codes.
+const CLEAR_DAY: &str = "01d";
+const CLEAR_NIGHT: &str = "01n";
+const FEW_CLOUDS_DAY: &str = "02d";
+const FEW_CLOUDS_NIGHT: &str = "02n";
+const SCATTERED_CLOUDS_DAY: &str = "03d";
+const SCATTERED_CLOUDS_NIGHT: &str = "03n";
+const BROKEN_CLOUDS_DAY: &str = "04d";
+const BROKEN_CLOUDS_NIGHT: &str = "04n";
+const SHOWER_RAIN_DAY: &str = "09d";
+const SHOWER_RAIN_NIGHT: &str = "09n";
+const RAIN_DAY: &str = "10d";
+const RAIN_NIGHT: &str = "10n";
+const THUNDERSTORM_DAY: &str = "11d";
+const THUNDERSTORM_NIGHT: &str = "11n";
+const SNOW_DAY: &str = "13d";
+const SNOW_NIGHT: &str = "13n";
+const MIST_DAY: &str = "50d";
+const MIST_NIGHT: &str = "50n";
+
+
+/// Glyphs to render icons with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IconSet {
+    /// Weather glyphs from a patched Nerd Font.
+    #[default]
+    #[serde(alias = "nerd")]
+    NerdFont,
+    Emoji,
+    /// Short words like "clear" and "rain", which any font can show.
+    #[serde(alias = "ascii")]
+    Text,
+}
+
+impl FromStr for IconSet {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nerdfont" | "nerd" => Ok(IconSet::NerdFont),
+            "emoji" => Ok(IconSet::Emoji),
+            "text" | "ascii" => Ok(IconSet::Text),
+            _ => Err(anyhow!("unknown icon set '{}'", s)),
+        }
+    }
+}
+
+/// How night-time icons are dimmed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dim {
+    /// Bright black, for terminals.
+    #[default]
+    Ansi,
+    /// A grey foreground color tag, for polybar.
+    Polybar,
+    /// No markup, for Waybar, whose status gets a `night` class instead, to
+    /// style in its CSS.
+    Waybar,
+}
+
+impl Dim {
+    /// Wraps `icon` in the markup that dims it.
+    pub fn apply(self, icon: &str) -> String {
+        match self {
+            Dim::Ansi => format!("\x1b[90m{}\x1b[0m", icon),
+            Dim::Polybar => format!("%{{F#808080}}{}%{{F-}}", icon),
+            Dim::Waybar => icon.to_string(),
+        }
+    }
+}
+
+impl FromStr for Dim {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ansi" => Ok(Dim::Ansi),
+            "polybar" => Ok(Dim::Polybar),
+            "waybar" => Ok(Dim::Waybar),
+            _ => Err(anyhow!("unknown dim style '{}'", s)),
+        }
+    }
+}
+
+impl Weather {
+    /// Returns the icon for the weather condition from the given set.
+    pub fn icon(&self, set: IconSet) -> String {
+        match_icon(&self.icon, set).to_string()
+    }
+}
+
+impl CurrentWeather {
+    /// Returns the icon for the weather condition from the given set.
+    pub fn icon(&self, set: IconSet) -> String {
+        match_icon(self.icon_code(), set).to_string()
+    }
+
+    /// Returns whether it was night at the location at the time of the
+    /// observation, not the time of rendering.
+    pub fn is_night(&self) -> bool {
+        is_night(self.icon_code(), self.dt, self.sys.sunrise, self.sys.sunset)
+    }
+
+    fn icon_code(&self) -> &str {
+        self.weather.first().map_or("", |w| w.icon.as_str())
+    }
+}
+
+/// Tells whether it is night. Icon codes end in "d" or "n" for day and night;
+/// for codes that don't, it is night before `sunrise` and from `sunset` on.
+pub fn is_night(code: &str, dt: i64, sunrise: i64, sunset: i64) -> bool {
+    match code.chars().last() {
+        Some('n') => true,
+        Some('d') => false,
+        _ => dt < sunrise || dt >= sunset,
+    }
+}
+
+/// Convert OpenWeatherMap icon id to icon. Codes not in [`GLYPHS`] are "?"
+/// in every set.
+pub(crate) fn match_icon(code: &str, set: IconSet) -> &'static str {
+    let Some(glyphs) = GLYPHS.iter().find(|glyphs| glyphs.code == code) else {
+        return "?";
+    };
+    match set {
+        IconSet::NerdFont => glyphs.nerd_font,
+        IconSet::Emoji => glyphs.emoji,
+        IconSet::Text => glyphs.text,
+    }
+}
+
+/// The icon of an icon code in each set.
+struct Glyphs {
+    code: &'static str,
+    nerd_font: &'static str,
+    emoji: &'static str,
+    /// A word or two, for where no font has weather glyphs.
+    text: &'static str,
+}
+
+const GLYPHS: &[Glyphs] = &[
+    Glyphs { code: CLEAR_DAY, nerd_font: "", emoji: "☀️", text: "clear" },
+    Glyphs { code: CLEAR_NIGHT, nerd_font: "", emoji: "🌙", text: "clear" },
+    Glyphs { code: FEW_CLOUDS_DAY, nerd_font: "", emoji: "🌤️", text: "few clouds" },
+    Glyphs { code: FEW_CLOUDS_NIGHT, nerd_font: "", emoji: "🌙", text: "few clouds" },
+    Glyphs { code: SCATTERED_CLOUDS_DAY, nerd_font: "摒", emoji: "⛅", text: "clouds" },
+    Glyphs { code: SCATTERED_CLOUDS_NIGHT, nerd_font: "", emoji: "☁️", text: "clouds" },
+    Glyphs { code: BROKEN_CLOUDS_DAY, nerd_font: "", emoji: "🌥️", text: "overcast" },
+    Glyphs { code: BROKEN_CLOUDS_NIGHT, nerd_font: "", emoji: "☁️", text: "overcast" },
+    Glyphs { code: SHOWER_RAIN_DAY, nerd_font: "", emoji: "🌦️", text: "showers" },
+    Glyphs { code: SHOWER_RAIN_NIGHT, nerd_font: "", emoji: "🌦️", text: "showers" },
+    Glyphs { code: RAIN_DAY, nerd_font: "", emoji: "🌧️", text: "rain" },
+    Glyphs { code: RAIN_NIGHT, nerd_font: "", emoji: "🌧️", text: "rain" },
+    Glyphs { code: THUNDERSTORM_DAY, nerd_font: "", emoji: "⛈️", text: "storm" },
+    Glyphs { code: THUNDERSTORM_NIGHT, nerd_font: "", emoji: "⛈️", text: "storm" },
+    Glyphs { code: SNOW_DAY, nerd_font: "", emoji: "❄️", text: "snow" },
+    Glyphs { code: SNOW_NIGHT, nerd_font: "", emoji: "❄️", text: "snow" },
+    Glyphs { code: MIST_DAY, nerd_font: "", emoji: "🌫️", text: "mist" },
+    Glyphs { code: MIST_NIGHT, nerd_font: "", emoji: "🌫️", text: "mist" },
+];
+
+/// Returns the glyph of the moon at `phase`, from 0 to 1 as One Call gives
+/// it: 0 and 1 are new moon, 0.25 first quarter, 0.5 full moon and 0.75 last
+/// quarter. Each of the eight glyphs stands for the phases within a
+/// sixteenth of a cycle of its own, ties going to the later one: 0.0625 is a
+/// waxing crescent and 0.9375 a new moon. Phases out of range wrap around.
+pub fn moon(phase: f64, set: IconSet) -> &'static str {
+    let glyphs = &MOON[moon_index(phase)];
+    match set {
+        IconSet::NerdFont => glyphs.nerd_font,
+        IconSet::Emoji => glyphs.emoji,
+        IconSet::Text => glyphs.text,
+    }
+}
+
+/// Returns the index in [`MOON`] of the moon at `phase`.
+fn moon_index(phase: f64) -> usize {
+    let eighths = (phase.rem_euclid(1.0) * MOON.len() as f64).round() as usize;
+    eighths % MOON.len()
+}
+
+/// The moon of a phase in each set.
+struct MoonGlyphs {
+    nerd_font: &'static str,
+    emoji: &'static str,
+    text: &'static str,
+}
+
+/// The phases, from new moon through full moon.
+const MOON: [MoonGlyphs; 8] = [
+    MoonGlyphs { nerd_font: "", emoji: "🌑", text: "new moon" },
+    MoonGlyphs { nerd_font: "", emoji: "🌒", text: "waxing crescent" },
+    MoonGlyphs { nerd_font: "", emoji: "🌓", text: "first quarter" },
+    MoonGlyphs { nerd_font: "", emoji: "🌔", text: "waxing gibbous" },
+    MoonGlyphs { nerd_font: "", emoji: "🌕", text: "full moon" },
+    MoonGlyphs { nerd_font: "", emoji: "🌖", text: "waning gibbous" },
+    MoonGlyphs { nerd_font: "", emoji: "🌗", text: "last quarter" },
+    MoonGlyphs { nerd_font: "", emoji: "🌘", text: "waning crescent" },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ParseMode;
+
+    /// Sunrise and sunset on 1 May 2024 in Amsterdam.
+    const SUNRISE: i64 = 1_714_536_300;
+    const SUNSET: i64 = 1_714_590_900;
+
+    #[test]
+    fn the_icon_code_tells_night_first() {
+        // Whatever the time: the code has it from the API.
+        for dt in [SUNRISE - 3600, SUNRISE + 3600, SUNSET + 3600] {
+            assert!(is_night("04n", dt, SUNRISE, SUNSET));
+            assert!(!is_night("04d", dt, SUNRISE, SUNSET));
+        }
+        for glyphs in GLYPHS {
+            assert_eq!(is_night(glyphs.code, SUNRISE + 3600, SUNRISE, SUNSET), glyphs.code.ends_with('n'));
+        }
+    }
+
+    #[test]
+    fn without_one_sunrise_and_sunset_tell() {
+        let cases = [
+            (SUNRISE - 1, true),
+            (SUNRISE, false),
+            (SUNRISE + 3600, false),
+            (SUNSET - 1, false),
+            (SUNSET, true),
+            (SUNSET + 3600, true),
+        ];
+        for code in ["", "04", "?", "04x"] {
+            for (dt, night) in cases {
+                assert_eq!(is_night(code, dt, SUNRISE, SUNSET), night, "{:?} at {}", code, dt);
+            }
+        }
+    }
+
+    #[test]
+    fn the_observation_is_night_or_day() {
+        let fixture = include_str!("../tests/fixtures/current.json");
+        let at = |icon: &str, dt: i64| {
+            let json = fixture.replace("\"10d\"", &format!("\"{}\"", icon)).replace("1714550400", &dt.to_string());
+            CurrentWeather::from_json(&json, ParseMode::Strict).unwrap().is_night()
+        };
+        assert!(!at("10d", SUNSET + 3600));
+        assert!(at("10n", SUNRISE + 3600));
+        assert!(at("", SUNSET + 3600));
+        assert!(!at("", SUNRISE + 3600));
+        let weather = r#"[{"id": 500, "main": "Rain", "description": "light rain", "icon": "10d"}]"#;
+        let json = fixture.replace(weather, "[]");
+        assert!(!CurrentWeather::from_json(&json, ParseMode::Strict).unwrap().is_night());
+    }
+
+    #[test]
+    fn moons_at_the_quarters_and_between() {
+        let cases = [
+            (0.0, "new moon"),
+            (0.0624, "new moon"),
+            (0.0625, "waxing crescent"),
+            (0.125, "waxing crescent"),
+            (0.1875, "first quarter"),
+            (0.25, "first quarter"),
+            (0.3124, "first quarter"),
+            (0.3125, "waxing gibbous"),
+            (0.4375, "full moon"),
+            (0.5, "full moon"),
+            (0.5624, "full moon"),
+            (0.5625, "waning gibbous"),
+            (0.6875, "last quarter"),
+            (0.75, "last quarter"),
+            (0.8125, "waning crescent"),
+            (0.9374, "waning crescent"),
+            (0.9375, "new moon"),
+            (1.0, "new moon"),
+            (1.25, "first quarter"),
+            (-0.25, "last quarter"),
+        ];
+        for (phase, text) in cases {
+            assert_eq!(moon(phase, IconSet::Text), text, "{}", phase);
+        }
+    }
+
+    #[test]
+    fn moons_in_each_set() {
+        let emoji = ["🌑", "🌒", "🌓", "🌔", "🌕", "🌖", "🌗", "🌘"];
+        for (eighth, emoji) in emoji.into_iter().enumerate() {
+            let phase = eighth as f64 / 8.0;
+            assert_eq!(moon(phase, IconSet::Emoji), emoji, "{}", phase);
+            assert_eq!(moon(phase, IconSet::NerdFont), MOON[eighth].nerd_font, "{}", phase);
+        }
+        let nerd_fonts: Vec<_> = MOON.iter().map(|glyphs| glyphs.nerd_font).collect();
+        assert!(nerd_fonts.iter().enumerate().all(|(i, glyph)| !nerd_fonts[..i].contains(glyph)), "{:?}", nerd_fonts);
+    }
+
+    #[test]
+    fn icons_in_each_set() {
+        assert_eq!(match_icon("01d", IconSet::Emoji), "☀️");
+        assert_eq!(match_icon("10d", IconSet::Emoji), "🌧️");
+        assert_eq!(match_icon("01d", IconSet::Text), "clear");
+        assert_eq!(match_icon("10n", IconSet::Text), "rain");
+        assert_eq!(match_icon("11d", IconSet::NerdFont), "\u{e315}");
+        // Each code of each set has an icon, and the text is plain ASCII.
+        for glyphs in GLYPHS {
+            for set in [IconSet::NerdFont, IconSet::Emoji, IconSet::Text] {
+                assert!(!match_icon(glyphs.code, set).is_empty(), "{} {:?}", glyphs.code, set);
+            }
+            assert!(glyphs.text.chars().all(|c| c.is_ascii_lowercase() || c == ' '), "{}", glyphs.text);
+        }
+        assert_eq!(GLYPHS.len(), 18);
+    }
+
+    #[test]
+    fn unknown_codes_are_a_question_mark_in_every_set() {
+        for code in ["", "42d", "01", "10x"] {
+            for set in [IconSet::NerdFont, IconSet::Emoji, IconSet::Text] {
+                assert_eq!(match_icon(code, set), "?", "{:?} {:?}", code, set);
+            }
+        }
+    }
+
+    #[test]
+    fn icon_sets_by_name_and_alias() {
+        for (name, set) in [
+            ("nerdfont", IconSet::NerdFont),
+            ("nerd", IconSet::NerdFont),
+            ("emoji", IconSet::Emoji),
+            ("text", IconSet::Text),
+            ("ascii", IconSet::Text),
+        ] {
+            assert_eq!(name.parse::<IconSet>().unwrap(), set, "{}", name);
+            let json = format!("\"{}\"", name);
+            assert_eq!(serde_json::from_str::<IconSet>(&json).unwrap(), set, "{}", name);
+        }
+        assert_eq!("emojis".parse::<IconSet>().unwrap_err().to_string(), "unknown icon set 'emojis'");
+        assert_eq!(IconSet::default(), IconSet::NerdFont);
+    }
+
+    #[test]
+    fn dimming() {
+        assert_eq!(Dim::Ansi.apply("x"), "\x1b[90mx\x1b[0m");
+        assert_eq!(Dim::Polybar.apply("x"), "%{F#808080}x%{F-}");
+        assert_eq!(Dim::Waybar.apply("x"), "x");
+        for (name, dim) in [("ansi", Dim::Ansi), ("polybar", Dim::Polybar), ("waybar", Dim::Waybar)] {
+            assert_eq!(name.parse::<Dim>().unwrap(), dim);
+        }
+        assert!("grey".parse::<Dim>().is_err());
+    }
+}
@@ -0,0 +1,1211 @@
+//! The registry of data items. Everything that needs to know which items
+//! exist — the CLI's accepted values and help, config validation, deciding
+//! which endpoints to call — derives from [`ITEMS`].
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A piece of weather data that can be displayed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DataItem {
+    Icon,
+    Temp,
+    FeelsLike,
+    TodayHigh,
+    TodayLow,
+    NextRain,
+    Description,
+    Condition,
+    Severity,
+    Humidity,
+    Pressure,
+    Visibility,
+    Clouds,
+    TempMin,
+    TempMax,
+    DewPoint,
+    HeatIndex,
+    Rain,
+    Snow,
+    ObsTime,
+    Dt,
+    ObsAge,
+    NowTime,
+    NowDate,
+    City,
+    Country,
+    Coords,
+    Wind,
+    WindDeg,
+    WindDir,
+    WindDirLong,
+    WindGust,
+    Beaufort,
+    WindDesc,
+    Sunrise,
+    Sunset,
+    Daylight,
+    DayLength,
+    MoonPhase,
+    Uvi,
+    UvSafeUntil,
+    UvPeak,
+    DayStory,
+    Trend,
+    VentilationWindow,
+    Aqi,
+    AqiLevel,
+    SunElevation,
+    SunAzimuth,
+    GoldenHour,
+    StationDistance,
+    StationBearing,
+}
+
+/// API endpoints items can need data from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    /// Current weather, `data/2.5/weather`.
+    Current,
+    /// One Call API 3.0, `data/3.0/onecall`.
+    OneCall,
+    /// Air pollution forecast, `data/2.5/air_pollution/forecast`.
+    AirPollution,
+    /// Current air pollution, `data/2.5/air_pollution`.
+    AirQuality,
+    /// 5 day forecast, `data/2.5/forecast`.
+    Forecast,
+}
+
+/// Optional features of the wx crate that items can depend on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feature {
+    /// `time`: local times and days.
+    Time,
+    /// `icons`: the glyph tables.
+    Icons,
+    /// `onecall`: the One Call API, which [`Endpoint::OneCall`] needs.
+    OneCall,
+}
+
+impl Feature {
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::Time => "time",
+            Feature::Icons => "icons",
+            Feature::OneCall => "onecall",
+        }
+    }
+
+    /// Returns whether wx was built with this feature.
+    pub fn enabled(self) -> bool {
+        match self {
+            Feature::Time => cfg!(feature = "time"),
+            Feature::Icons => cfg!(feature = "icons"),
+            Feature::OneCall => cfg!(feature = "onecall"),
+        }
+    }
+}
+
+/// What kind of value an item renders.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// A number, possibly followed by a unit.
+    Numeric,
+    Text,
+    /// A single icon.
+    Glyph,
+}
+
+impl Endpoint {
+    /// Returns the path of the endpoint, relative to the API's base URL.
+    pub fn path(self) -> &'static str {
+        match self {
+            Endpoint::Current => "data/2.5/weather",
+            Endpoint::OneCall => "data/3.0/onecall",
+            Endpoint::AirPollution => "data/2.5/air_pollution/forecast",
+            Endpoint::AirQuality => "data/2.5/air_pollution",
+            Endpoint::Forecast => "data/2.5/forecast",
+        }
+    }
+
+    /// Returns the feature wx needs to read the endpoint's responses, for
+    /// those that need one.
+    pub fn feature(self) -> Option<Feature> {
+        match self {
+            Endpoint::OneCall => Some(Feature::OneCall),
+            _ => None,
+        }
+    }
+}
+
+/// What an item renders when it cannot be rendered at all, as when it is
+/// [`Unsupported`] or the request it needs failed. A reading the response
+/// lacks renders empty instead.
+pub const PLACEHOLDER: &str = "?";
+
+/// Why an item cannot be rendered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unsupported {
+    /// wx was built without a feature the item or its endpoint needs.
+    Feature(Feature),
+    /// The item needs more than the current weather: another endpoint, the
+    /// history, or the time or coordinates it is rendered for.
+    NotCurrent,
+}
+
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unsupported::Feature(feature) => write!(f, "wx was built without the '{}' feature", feature.name()),
+            Unsupported::NotCurrent => f.write_str("it needs more than the current weather"),
+        }
+    }
+}
+
+impl std::error::Error for Unsupported {}
+
+impl Kind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Numeric => "numeric",
+            Kind::Text => "text",
+            Kind::Glyph => "glyph",
+        }
+    }
+}
+
+/// The unit a numeric item is rendered in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    /// No unit, or not numeric.
+    None,
+    /// Degrees Celsius with metric units, Fahrenheit with imperial units,
+    /// kelvin with standard units.
+    Temperature,
+    /// Percent, whatever the units.
+    Percent,
+    /// Degrees of angle, whatever the units.
+    Degrees,
+    /// Meters per second with metric and standard units, miles per hour with
+    /// imperial units.
+    Speed,
+    /// Kilometers with metric and standard units, miles with imperial units.
+    Distance,
+    /// Minutes, whatever the units.
+    Minutes,
+    /// Hectopascals with metric and standard units, inches of mercury with
+    /// imperial units.
+    Pressure,
+    /// Millimeters per hour with metric and standard units, inches per hour
+    /// with imperial units.
+    Precipitation,
+}
+
+impl Unit {
+    /// Describes how the unit depends on the requested units.
+    pub fn describe(self) -> &'static str {
+        match self {
+            Unit::None => "none",
+            Unit::Temperature => "°C with metric units, °F with imperial units, K with standard units",
+            Unit::Percent => "%, whatever the units",
+            Unit::Degrees => "degrees of angle, whatever the units",
+            Unit::Speed => "m/s with metric and standard units, mph with imperial units",
+            Unit::Distance => "km with metric and standard units, mi with imperial units",
+            Unit::Minutes => "min, whatever the units",
+            Unit::Pressure => "hPa with metric and standard units, inHg with imperial units",
+            Unit::Precipitation => "mm/h with metric and standard units, in/h with imperial units",
+        }
+    }
+}
+
+/// Everything known about a data item.
+#[derive(Debug)]
+pub struct ItemInfo {
+    pub item: DataItem,
+    /// Canonical name, as used on the command line and in config files.
+    pub name: &'static str,
+    /// Other accepted names.
+    pub aliases: &'static [&'static str],
+    /// Names the item had before, still accepted with a warning.
+    pub deprecated: &'static [&'static str],
+    /// One-line description.
+    pub description: &'static str,
+    /// What introduces the value in accessible output, as "humidity" does in
+    /// "humidity 67 percent"; empty for values that need no introduction.
+    pub spoken: &'static str,
+    /// Short label to prefix the value with, as "hum" does in "hum:67%";
+    /// empty for values that need none, like the icon.
+    pub label: &'static str,
+    /// Endpoints the item needs data from.
+    pub endpoints: &'static [Endpoint],
+    /// Whether the item looks back at earlier readings.
+    pub history: bool,
+    /// Features of this crate the item needs to be rendered.
+    pub features: &'static [Feature],
+    pub kind: Kind,
+    pub unit: Unit,
+}
+
+/// All data items, in the order they are listed in help texts.
+pub const ITEMS: &[ItemInfo] = &[
+    ItemInfo {
+        item: DataItem::Icon,
+        name: "icon",
+        aliases: &[],
+        deprecated: &[],
+        description: "Icon for the weather condition",
+        spoken: "",
+        label: "",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[Feature::Icons],
+        kind: Kind::Glyph,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::Temp,
+        name: "temp",
+        aliases: &[],
+        deprecated: &[],
+        description: "Temperature",
+        spoken: "temperature",
+        label: "temp",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Temperature,
+    },
+    ItemInfo {
+        item: DataItem::FeelsLike,
+        name: "feels_like",
+        aliases: &["feels"],
+        deprecated: &[],
+        description: "Temperature accounting for human perception",
+        spoken: "feels like",
+        label: "feels",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Temperature,
+    },
+    ItemInfo {
+        item: DataItem::TodayHigh,
+        name: "today_high",
+        aliases: &[],
+        deprecated: &[],
+        description: "Highest temperature of the local day, from the forecast and the readings so far",
+        spoken: "high today",
+        label: "high",
+        endpoints: &[Endpoint::Current, Endpoint::Forecast],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Temperature,
+    },
+    ItemInfo {
+        item: DataItem::TodayLow,
+        name: "today_low",
+        aliases: &[],
+        deprecated: &[],
+        description: "Lowest temperature of the local day, from the forecast and the readings so far",
+        spoken: "low today",
+        label: "low",
+        endpoints: &[Endpoint::Current, Endpoint::Forecast],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Temperature,
+    },
+    ItemInfo {
+        item: DataItem::NextRain,
+        name: "next_rain",
+        aliases: &[],
+        deprecated: &[],
+        description: "First rain or snow likely in the coming hours, its probability and local time, from the forecast",
+        spoken: "",
+        label: "next",
+        endpoints: &[Endpoint::Current, Endpoint::Forecast],
+        history: false,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::Description,
+        name: "description",
+        aliases: &["desc"],
+        deprecated: &[],
+        description: "Description of the weather conditions, as in \"light rain, mist\" when there are several",
+        spoken: "",
+        label: "",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::Condition,
+        name: "condition",
+        aliases: &[],
+        deprecated: &[],
+        description: "Id of the weather condition, as in 502 for heavy rain",
+        spoken: "condition",
+        label: "cond",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::Severity,
+        name: "severity",
+        aliases: &[],
+        deprecated: &[],
+        description: "Severity of the weather condition: none, light, moderate, heavy or extreme",
+        spoken: "severity",
+        label: "sev",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::Humidity,
+        name: "humidity",
+        aliases: &["hum"],
+        deprecated: &[],
+        description: "Relative humidity",
+        spoken: "humidity",
+        label: "hum",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Percent,
+    },
+    ItemInfo {
+        item: DataItem::Pressure,
+        name: "pressure",
+        aliases: &[],
+        deprecated: &[],
+        description: "Atmospheric pressure at sea level",
+        spoken: "pressure",
+        label: "pres",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Pressure,
+    },
+    ItemInfo {
+        item: DataItem::Visibility,
+        name: "visibility",
+        aliases: &["vis"],
+        deprecated: &[],
+        description: "Visibility, up to 10 km, the furthest the API reports; empty if the station has none",
+        spoken: "visibility",
+        label: "vis",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Distance,
+    },
+    ItemInfo {
+        item: DataItem::Clouds,
+        name: "clouds",
+        aliases: &[],
+        deprecated: &[],
+        description: "Cloud cover",
+        spoken: "cloud cover",
+        label: "clouds",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Percent,
+    },
+    ItemInfo {
+        item: DataItem::TempMin,
+        name: "temp_min",
+        aliases: &[],
+        deprecated: &[],
+        description: "Lowest temperature observed now around the location, not the day's low (see today_low)",
+        spoken: "lowest temperature",
+        label: "min",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Temperature,
+    },
+    ItemInfo {
+        item: DataItem::TempMax,
+        name: "temp_max",
+        aliases: &[],
+        deprecated: &[],
+        description: "Highest temperature observed now around the location, not the day's high (see today_high)",
+        spoken: "highest temperature",
+        label: "max",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Temperature,
+    },
+    ItemInfo {
+        item: DataItem::DewPoint,
+        name: "dew_point",
+        aliases: &["dew"],
+        deprecated: &[],
+        description: "Temperature at which dew forms, from the temperature and the humidity",
+        spoken: "dew point",
+        label: "dew",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Temperature,
+    },
+    ItemInfo {
+        item: DataItem::HeatIndex,
+        name: "heat_index",
+        aliases: &[],
+        deprecated: &[],
+        description: "How hot it feels with the humidity, from 27°C (80°F); the temperature below",
+        spoken: "heat index",
+        label: "heat",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Temperature,
+    },
+    ItemInfo {
+        item: DataItem::Rain,
+        name: "rain",
+        aliases: &[],
+        deprecated: &[],
+        description: "How hard it rains, over the last hour; 0 when it does not",
+        spoken: "rain",
+        label: "rain",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Precipitation,
+    },
+    ItemInfo {
+        item: DataItem::Snow,
+        name: "snow",
+        aliases: &[],
+        deprecated: &[],
+        description: "How hard it snows, as water, over the last hour; 0 when it does not",
+        spoken: "snow",
+        label: "snow",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Precipitation,
+    },
+    ItemInfo {
+        item: DataItem::ObsTime,
+        name: "obs_time",
+        aliases: &[],
+        deprecated: &["time"],
+        description: "Local time the station observed the weather at, not the current time",
+        spoken: "observed at",
+        label: "obs",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::Dt,
+        name: "dt",
+        aliases: &["timestamp"],
+        deprecated: &[],
+        description: "Time of the observation, in unix seconds",
+        spoken: "observed at unix time",
+        label: "dt",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::ObsAge,
+        name: "obs_age",
+        aliases: &[],
+        deprecated: &[],
+        description: "Minutes since the observation, at the time of rendering",
+        spoken: "observed",
+        label: "age",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Minutes,
+    },
+    ItemInfo {
+        item: DataItem::NowTime,
+        name: "now_time",
+        aliases: &[],
+        deprecated: &[],
+        description: "Current local time at the location, at the time of rendering",
+        spoken: "time",
+        label: "time",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[Feature::Time],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::NowDate,
+        name: "now_date",
+        aliases: &[],
+        deprecated: &[],
+        description: "Current local date at the location, at the time of rendering",
+        spoken: "date",
+        label: "date",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[Feature::Time],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::City,
+        name: "city",
+        aliases: &["place", "name"],
+        deprecated: &[],
+        description: "Name of the city the query resolved to, or its coordinates",
+        spoken: "",
+        label: "",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::Country,
+        name: "country",
+        aliases: &[],
+        deprecated: &[],
+        description: "Country code of the location the query resolved to",
+        spoken: "country",
+        label: "",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::Coords,
+        name: "coords",
+        aliases: &["coordinates"],
+        deprecated: &[],
+        description: "Latitude and longitude of the location the query resolved to, as 52.37,4.89",
+        spoken: "coordinates",
+        label: "",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::Wind,
+        name: "wind",
+        aliases: &["wind_speed"],
+        deprecated: &[],
+        description: "Wind speed",
+        spoken: "wind",
+        label: "wind",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Speed,
+    },
+    ItemInfo {
+        item: DataItem::WindDeg,
+        name: "wind_deg",
+        aliases: &["wind_direction"],
+        deprecated: &[],
+        description: "Direction the wind comes from, clockwise from north",
+        spoken: "wind from",
+        label: "deg",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Degrees,
+    },
+    ItemInfo {
+        item: DataItem::WindDir,
+        name: "wind_dir",
+        aliases: &[],
+        deprecated: &[],
+        description: "Compass point the wind comes from, like NNE",
+        spoken: "wind from the",
+        label: "dir",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::WindDirLong,
+        name: "wind_dir_long",
+        aliases: &[],
+        deprecated: &[],
+        description: "Direction the wind comes from, written out in the language set, like north-northeast",
+        spoken: "wind from the",
+        label: "dir",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::WindGust,
+        name: "wind_gust",
+        aliases: &[],
+        deprecated: &[],
+        description: "Wind gust speed; empty when the response has none",
+        spoken: "gusts",
+        label: "gust",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Speed,
+    },
+    ItemInfo {
+        item: DataItem::Beaufort,
+        name: "beaufort",
+        aliases: &["bft"],
+        deprecated: &[],
+        description: "Wind force on the Beaufort scale, from 0 (calm) to 12 (hurricane)",
+        spoken: "Beaufort force",
+        label: "bft",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::WindDesc,
+        name: "wind_desc",
+        aliases: &["wind_description"],
+        deprecated: &[],
+        description: "Name of the wind force in English, with the gusts if much stronger, as in \"fresh breeze, gusting near gale\"",
+        spoken: "",
+        label: "",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::Sunrise,
+        name: "sunrise",
+        aliases: &[],
+        deprecated: &[],
+        description: "Local time of today's sunrise",
+        spoken: "sunrise at",
+        label: "rise",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[Feature::Time],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::Sunset,
+        name: "sunset",
+        aliases: &[],
+        deprecated: &[],
+        description: "Local time of today's sunset",
+        spoken: "sunset at",
+        label: "set",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[Feature::Time],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::Daylight,
+        name: "daylight",
+        aliases: &[],
+        deprecated: &[],
+        description: "Time left until sunset, or until sunrise after dark, at the time of rendering",
+        spoken: "",
+        label: "light",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::DayLength,
+        name: "day_length",
+        aliases: &[],
+        deprecated: &[],
+        description: "Time from sunrise to sunset today, as in \"9h 42m\"",
+        spoken: "day length",
+        label: "day",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::MoonPhase,
+        name: "moon_phase",
+        aliases: &["moon"],
+        deprecated: &[],
+        description: "Phase of the moon today, as a glyph of the icon set, from One Call",
+        spoken: "moon",
+        label: "moon",
+        endpoints: &[Endpoint::Current, Endpoint::OneCall],
+        history: false,
+        features: &[Feature::Icons],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::Uvi,
+        name: "uvi",
+        aliases: &["uv_index"],
+        deprecated: &[],
+        description: "Current UV index and its WHO category, as in 3.2 moderate; ? if it cannot be fetched",
+        spoken: "UV index",
+        label: "uvi",
+        endpoints: &[Endpoint::Current, Endpoint::OneCall],
+        history: false,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::UvSafeUntil,
+        name: "uv_safe_until",
+        aliases: &[],
+        deprecated: &[],
+        description: "Time from which the UV index stays below the threshold today",
+        spoken: "UV index low from",
+        label: "uv",
+        endpoints: &[Endpoint::Current, Endpoint::OneCall],
+        history: false,
+        features: &[Feature::Time],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::UvPeak,
+        name: "uv_peak",
+        aliases: &[],
+        deprecated: &[],
+        description: "Today's highest UV index and its time",
+        spoken: "UV index peak",
+        label: "uvmax",
+        endpoints: &[Endpoint::Current, Endpoint::OneCall],
+        history: false,
+        features: &[Feature::Time],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::DayStory,
+        name: "day_story",
+        aliases: &[],
+        deprecated: &[],
+        description: "How the weather changed since sunrise",
+        spoken: "",
+        label: "",
+        endpoints: &[Endpoint::Current],
+        history: true,
+        features: &[Feature::Time],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::Trend,
+        name: "trend",
+        aliases: &[],
+        deprecated: &[],
+        description: "Whether the temperature rose (↑), fell (↓) or held (→) since the reading before",
+        spoken: "temperature",
+        label: "trend",
+        endpoints: &[Endpoint::Current],
+        history: true,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::VentilationWindow,
+        name: "ventilation_window",
+        aliases: &[],
+        deprecated: &[],
+        description: "Coming hours with the least PM2.5 and NO2, to open the windows",
+        spoken: "",
+        label: "vent",
+        endpoints: &[Endpoint::Current, Endpoint::AirPollution],
+        history: false,
+        features: &[Feature::Time],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::Aqi,
+        name: "aqi",
+        aliases: &[],
+        deprecated: &[],
+        description: "Air quality index, from Good to Very Poor; ? if it could not be fetched",
+        spoken: "air quality",
+        label: "aqi",
+        endpoints: &[Endpoint::Current, Endpoint::AirQuality],
+        history: false,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::AqiLevel,
+        name: "aqi_level",
+        aliases: &[],
+        deprecated: &[],
+        description: "Air quality index as a number, from 1 (good) to 5 (very poor); ? if it could not be fetched",
+        spoken: "air quality index",
+        label: "aqi",
+        endpoints: &[Endpoint::Current, Endpoint::AirQuality],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::SunElevation,
+        name: "sun_elevation",
+        aliases: &[],
+        deprecated: &[],
+        description: "Height of the sun above the horizon",
+        spoken: "sun elevation",
+        label: "elev",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Degrees,
+    },
+    ItemInfo {
+        item: DataItem::SunAzimuth,
+        name: "sun_azimuth",
+        aliases: &[],
+        deprecated: &[],
+        description: "Direction of the sun, clockwise from north",
+        spoken: "sun azimuth",
+        label: "azi",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Degrees,
+    },
+    ItemInfo {
+        item: DataItem::GoldenHour,
+        name: "golden_hour",
+        aliases: &[],
+        deprecated: &[],
+        description: "\"golden hour\" while the sun is between 4° below and 6° above the horizon",
+        spoken: "",
+        label: "golden",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+    ItemInfo {
+        item: DataItem::StationDistance,
+        name: "station_distance",
+        aliases: &[],
+        deprecated: &[],
+        description: "Distance from the given coordinates to the weather station",
+        spoken: "station",
+        label: "stn",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Numeric,
+        unit: Unit::Distance,
+    },
+    ItemInfo {
+        item: DataItem::StationBearing,
+        name: "station_bearing",
+        aliases: &[],
+        deprecated: &[],
+        description: "Compass point of the weather station, seen from the given coordinates",
+        spoken: "station to the",
+        label: "brg",
+        endpoints: &[Endpoint::Current],
+        history: false,
+        features: &[],
+        kind: Kind::Text,
+        unit: Unit::None,
+    },
+];
+
+impl DataItem {
+    /// Returns the registry entry of this item.
+    pub fn info(self) -> &'static ItemInfo {
+        // Every variant has an entry; the registry is incomplete otherwise.
+        ITEMS
+            .iter()
+            .find(|info| info.item == self)
+            .expect("data item missing from the registry")
+    }
+
+    pub fn name(self) -> &'static str {
+        self.info().name
+    }
+
+    /// Returns the short label of the item (see [`ItemInfo::label`]).
+    pub fn label(self) -> &'static str {
+        self.info().label
+    }
+
+    /// Returns whether wx was built with the features the item needs.
+    pub fn supported(self) -> bool {
+        self.info().features.iter().all(|f| f.enabled())
+    }
+
+    /// Returns whether the item needs data from `endpoint`.
+    pub fn needs(self, endpoint: Endpoint) -> bool {
+        self.info().endpoints.contains(&endpoint)
+    }
+
+    /// Returns why the item cannot be rendered in this build, if it cannot:
+    /// the first feature it or one of its endpoints needs that wx was built
+    /// without. Such items render [`PLACEHOLDER`]. Unlike [`supported`], this
+    /// also covers endpoints, which [`FromStr`] accepts items of.
+    ///
+    /// [`supported`]: DataItem::supported
+    pub fn unsupported(self) -> Option<Unsupported> {
+        let info = self.info();
+        let endpoints = info.endpoints.iter().filter_map(|endpoint| endpoint.feature());
+        info.features
+            .iter()
+            .copied()
+            .chain(endpoints)
+            .find(|feature| !feature.enabled())
+            .map(Unsupported::Feature)
+    }
+}
+
+/// Returns the item `name` is a deprecated name of, if it is one, so that
+/// callers can warn about it.
+pub fn deprecated(name: &str) -> Option<&'static ItemInfo> {
+    ITEMS.iter().find(|info| info.deprecated.contains(&name))
+}
+
+/// Returns the name of the item `name` was most likely meant to be: one a
+/// typo or two away, or else the nearest in length of those it starts with or
+/// that start with it, as "temp" for "temperature".
+pub fn suggest(name: &str) -> Option<&'static str> {
+    let names = || {
+        ITEMS
+            .iter()
+            .flat_map(|info| std::iter::once(&info.name).chain(info.aliases).map(move |n| (*n, info.name)))
+    };
+    let close = names()
+        .map(|(n, item)| (edit_distance(name, n), item))
+        .filter(|&(distance, _)| distance <= 2)
+        .min_by_key(|&(distance, _)| distance);
+    if let Some((_, item)) = close {
+        return Some(item);
+    }
+    names()
+        .filter(|(n, _)| n.len().min(name.len()) >= 3 && (name.starts_with(n) || n.starts_with(name)))
+        .min_by_key(|(n, _)| n.len().abs_diff(name.len()))
+        .map(|(_, item)| item)
+}
+
+/// Returns the number of characters to insert, delete or replace to turn `a`
+/// into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (diagonal + usize::from(ca != cb)).min(row[j] + 1).min(above + 1);
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+impl FromStr for DataItem {
+    type Err = Error;
+
+    /// Parses an item from its name, one of its aliases or a deprecated name
+    /// (see [`deprecated`]). Items needing a feature wx was built without are
+    /// unsupported.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let info = ITEMS
+            .iter()
+            .find(|info| info.name == s || info.aliases.contains(&s) || info.deprecated.contains(&s))
+            .ok_or_else(|| match suggest(s) {
+                Some(name) => anyhow!("unknown data item '{}', did you mean '{}'?", s, name),
+                None => anyhow!("unknown data item '{}'", s),
+            })?;
+        match info.features.iter().find(|f| !f.enabled()) {
+            Some(feature) => Err(anyhow!(
+                "unsupported data item '{}': wx was built without the '{}' feature",
+                info.name,
+                feature.name()
+            )),
+            None => Ok(info.item),
+        }
+    }
+}
+
+impl fmt::Display for DataItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Serialize for DataItem {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for DataItem {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every item, in the order `--list-data` lists them, with its name,
+    /// label and unit. Names and labels are in config files and bar configs:
+    /// changing one here has to be meant.
+    #[test]
+    fn every_item_has_its_name_label_and_unit() {
+        let expected = [
+            (DataItem::Icon, "icon", "", Unit::None),
+            (DataItem::Temp, "temp", "temp", Unit::Temperature),
+            (DataItem::FeelsLike, "feels_like", "feels", Unit::Temperature),
+            (DataItem::TodayHigh, "today_high", "high", Unit::Temperature),
+            (DataItem::TodayLow, "today_low", "low", Unit::Temperature),
+            (DataItem::NextRain, "next_rain", "next", Unit::None),
+            (DataItem::Description, "description", "", Unit::None),
+            (DataItem::Condition, "condition", "cond", Unit::None),
+            (DataItem::Severity, "severity", "sev", Unit::None),
+            (DataItem::Humidity, "humidity", "hum", Unit::Percent),
+            (DataItem::Pressure, "pressure", "pres", Unit::Pressure),
+            (DataItem::Visibility, "visibility", "vis", Unit::Distance),
+            (DataItem::Clouds, "clouds", "clouds", Unit::Percent),
+            (DataItem::TempMin, "temp_min", "min", Unit::Temperature),
+            (DataItem::TempMax, "temp_max", "max", Unit::Temperature),
+            (DataItem::DewPoint, "dew_point", "dew", Unit::Temperature),
+            (DataItem::HeatIndex, "heat_index", "heat", Unit::Temperature),
+            (DataItem::Rain, "rain", "rain", Unit::Precipitation),
+            (DataItem::Snow, "snow", "snow", Unit::Precipitation),
+            (DataItem::ObsTime, "obs_time", "obs", Unit::None),
+            (DataItem::Dt, "dt", "dt", Unit::None),
+            (DataItem::ObsAge, "obs_age", "age", Unit::Minutes),
+            (DataItem::NowTime, "now_time", "time", Unit::None),
+            (DataItem::NowDate, "now_date", "date", Unit::None),
+            (DataItem::City, "city", "", Unit::None),
+            (DataItem::Country, "country", "", Unit::None),
+            (DataItem::Coords, "coords", "", Unit::None),
+            (DataItem::Wind, "wind", "wind", Unit::Speed),
+            (DataItem::WindDeg, "wind_deg", "deg", Unit::Degrees),
+            (DataItem::WindDir, "wind_dir", "dir", Unit::None),
+            (DataItem::WindDirLong, "wind_dir_long", "dir", Unit::None),
+            (DataItem::WindGust, "wind_gust", "gust", Unit::Speed),
+            (DataItem::Beaufort, "beaufort", "bft", Unit::None),
+            (DataItem::WindDesc, "wind_desc", "", Unit::None),
+            (DataItem::Sunrise, "sunrise", "rise", Unit::None),
+            (DataItem::Sunset, "sunset", "set", Unit::None),
+            (DataItem::Daylight, "daylight", "light", Unit::None),
+            (DataItem::DayLength, "day_length", "day", Unit::None),
+            (DataItem::MoonPhase, "moon_phase", "moon", Unit::None),
+            (DataItem::Uvi, "uvi", "uvi", Unit::None),
+            (DataItem::UvSafeUntil, "uv_safe_until", "uv", Unit::None),
+            (DataItem::UvPeak, "uv_peak", "uvmax", Unit::None),
+            (DataItem::DayStory, "day_story", "", Unit::None),
+            (DataItem::Trend, "trend", "trend", Unit::None),
+            (DataItem::VentilationWindow, "ventilation_window", "vent", Unit::None),
+            (DataItem::Aqi, "aqi", "aqi", Unit::None),
+            (DataItem::AqiLevel, "aqi_level", "aqi", Unit::None),
+            (DataItem::SunElevation, "sun_elevation", "elev", Unit::Degrees),
+            (DataItem::SunAzimuth, "sun_azimuth", "azi", Unit::Degrees),
+            (DataItem::GoldenHour, "golden_hour", "golden", Unit::None),
+            (DataItem::StationDistance, "station_distance", "stn", Unit::Distance),
+            (DataItem::StationBearing, "station_bearing", "brg", Unit::None),
+        ];
+        let items: Vec<_> = ITEMS.iter().map(|info| (info.item, info.name, info.label, info.unit)).collect();
+        assert_eq!(items, expected);
+    }
+
+    #[test]
+    fn names_are_unique() {
+        let all = |info: &ItemInfo| {
+            let others = info.aliases.iter().chain(info.deprecated).copied();
+            std::iter::once(info.name).chain(others)
+        };
+        let mut names: Vec<&str> = ITEMS.iter().flat_map(all).collect();
+        let count = names.len();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), count);
+    }
+
+    #[test]
+    fn unknown_names_suggest_the_item_meant() {
+        assert_eq!(suggest("temperature"), Some("temp"));
+        assert_eq!(suggest("humdity"), Some("humidity"));
+        assert_eq!(suggest("sunsett"), Some("sunset"));
+        assert_eq!(suggest("zzz"), None);
+        let error = "temperature".parse::<DataItem>().unwrap_err();
+        assert_eq!(error.to_string(), "unknown data item 'temperature', did you mean 'temp'?");
+        assert_eq!("zzz".parse::<DataItem>().unwrap_err().to_string(), "unknown data item 'zzz'");
+    }
+}
@@ -1,76 +1,320 @@
-use std::time::{Duration, UNIX_EPOCH};
+//! Fetching and rendering of OpenWeatherMap data.
+//!
+//! Optional features: `http` (the API client), `time` (local times) and
+//! `icons` (glyph tables), all enabled by default, and `onecall`. Without
+//! `http`, responses fetched by other means can still be parsed and rendered.
 
+#[cfg(feature = "http")]
 use anyhow::Result;
+#[cfg(feature = "time")]
 use chrono::prelude::*;
-use serde::{Deserialize, Serialize};
-use url::Url;
-
-/// OpenWeatherMap icon codes.
-const CLEAR_DAY: &str = "01d";
-const CLEAR_NIGHT: &str = "01n";
-const FEW_CLOUDS_DAY: &str = "02d";
-const FEW_CLOUDS_NIGHT: &str = "02n";
-const SCATTERED_CLOUDS_DAY: &str = "03d";
-const SCATTERED_CLOUDS_NIGHT: &str = "03n";
-const BROKEN_CLOUDS_DAY: &str = "04d";
-const BROKEN_CLOUDS_NIGHT: &str = "04n";
-const SHOWER_RAIN_DAY: &str = "09d";
-const SHOWER_RAIN_NIGHT: &str = "09n";
-const RAIN_DAY: &str = "10d";
-const RAIN_NIGHT: &str = "10n";
-const THUNDERSTORM_DAY: &str = "11d";
-const THUNDERSTORM_NIGHT: &str = "11n";
-const SNOW_DAY: &str = "13d";
-const SNOW_NIGHT: &str = "13n";
-const MIST_DAY: &str = "50d";
-const MIST_NIGHT: &str = "50n";
-
-#[derive(Debug)]
-pub struct Location {
-    pub city: String,
-    pub state: String,
-    pub country: String,
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+
+use condition::Condition;
+use format::{Quantity, UnitStyle};
+
+#[cfg(feature = "offline-geo")]
+pub mod cities;
+#[cfg(feature = "http")]
+mod client;
+pub mod condition;
+mod coords;
+mod de;
+pub mod explain;
+pub mod forecast;
+pub mod format;
+pub mod geo;
+pub mod group;
+pub mod history;
+pub mod item;
+pub mod locale;
+#[cfg(feature = "onecall")]
+mod onecall;
+pub mod parse;
+pub mod pollution;
+pub mod report;
+#[cfg(feature = "http")]
+mod request;
+pub mod sun;
+#[cfg(feature = "icons")]
+pub mod icons;
+#[cfg(feature = "time")]
+pub mod uv;
+pub mod wind;
+
+#[cfg(feature = "http")]
+pub use client::{
+    plausible_api_key, ApiError, Client, ClientBuilder, MissingApiKey, NetworkError, ParseError, ResponseCache,
+    Retries, TooLarge,
+};
+pub use condition::Severity;
+pub use coords::parse_coords;
+pub use explain::Explanation;
+pub use forecast::{Forecast, PrecipEvent};
+#[cfg(feature = "http")]
+pub use geo::{geocode, GeoResult};
+#[cfg(feature = "http")]
+pub use group::get_group;
+pub use item::{DataItem, ItemInfo, Unsupported};
+#[cfg(feature = "icons")]
+pub use icons::{Dim, IconSet};
+#[cfg(feature = "onecall")]
+pub use onecall::{Alert, Current, Daily, DailyFeelsLike, DailyTemp, Hourly, Minutely, OneCall, Part};
+pub use parse::ParseMode;
+#[cfg(feature = "http")]
+pub use pollution::{get_air_pollution, get_air_pollution_forecast, AirPollution};
+pub use pollution::AirPollutionForecast;
+pub use report::WeatherReport;
+
+/// Number of decimals coordinates are rendered with.
+const COORD_PRECISION: usize = 2;
+
+#[derive(Clone, Debug)]
+pub enum Location {
+    /// City name, with an optional state and a country code.
+    City {
+        city: String,
+        state: String,
+        country: String,
+    },
+    /// Latitude and longitude in decimal degrees.
+    Coords { lat: f64, lon: f64 },
+    /// OpenWeatherMap city id, as returned by an earlier query.
+    CityId { id: u64 },
+    /// Zip or postal code, with a country code. The API takes a code without
+    /// a country for a US one, so the country should always be given.
+    Zip { code: String, country: String },
 }
 
 impl Location {
     pub fn new(city: &str, state: &str, country: &str) -> Self {
-        Self {
+        Self::City {
             city: city.to_string(),
             state: state.to_string(),
             country: country.to_string(),
         }
     }
 
-    // Returns a string of the location in the format "city,state,country",
-    // unless state is empty, in which case it returns "city,country".
-    pub fn to_string(&self) -> String {
-        if self.state.is_empty() {
-            format!("{},{}", self.city, self.country)
-        } else {
-            format!("{},{},{}", self.city, self.state, self.country)
+    pub fn coords(lat: f64, lon: f64) -> Self {
+        Self::Coords { lat, lon }
+    }
+
+    pub fn city_id(id: u64) -> Self {
+        Self::CityId { id }
+    }
+
+    pub fn zip(code: &str, country: &str) -> Self {
+        Self::Zip {
+            code: code.to_string(),
+            country: country.to_string(),
+        }
+    }
+
+    /// Returns a normalized description of this location, suitable to tell
+    /// cached data of different locations apart.
+    pub fn key(&self) -> String {
+        match self {
+            Self::City { city, state, country } => format!(
+                "{},{},{}",
+                city.trim().to_lowercase(),
+                state.trim().to_lowercase(),
+                country.trim().to_lowercase()
+            ),
+            Self::Coords { lat, lon } => format!("{:.4},{:.4}", lat, lon),
+            Self::CityId { id } => format!("id:{}", id),
+            Self::Zip { code, country } => format!(
+                "zip:{},{}",
+                code.trim().to_lowercase(),
+                country.trim().to_lowercase()
+            ),
+        }
+    }
+
+    // Returns the query parameters selecting this location. Cities are queried
+    // as "city,state,country", unless state is empty, in which case it's
+    // "city,country", and zip codes as "code,country".
+    pub fn to_query_pairs(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Self::City { .. } => vec![("q", self.city_query().unwrap_or_default())],
+            Self::Coords { lat, lon } => vec![("lat", lat.to_string()), ("lon", lon.to_string())],
+            Self::CityId { id } => vec![("id", id.to_string())],
+            Self::Zip { .. } => vec![("zip", self.zip_query().unwrap_or_default())],
+        }
+    }
+
+    /// Returns the `q` parameter of a city, or `None` for other locations.
+    pub(crate) fn city_query(&self) -> Option<String> {
+        match self {
+            Self::City { city, state, country } if state.is_empty() => Some(format!("{},{}", city, country)),
+            Self::City { city, state, country } => Some(format!("{},{},{}", city, state, country)),
+            Self::Coords { .. } | Self::CityId { .. } | Self::Zip { .. } => None,
+        }
+    }
+
+    /// Returns the `zip` parameter of a zip code, or `None` for other
+    /// locations.
+    pub(crate) fn zip_query(&self) -> Option<String> {
+        match self {
+            Self::Zip { code, country } => Some(format!("{},{}", code.trim(), country.trim())),
+            Self::City { .. } | Self::Coords { .. } | Self::CityId { .. } => None,
         }
     }
 }
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum Units {
     #[default]
     Metric,
     Imperial,
+    /// Kelvin, and otherwise metric.
+    Standard,
 }
 
+/// Other names units are accepted by, as found in configuration files.
+pub const UNIT_ALIASES: &[(&str, Units)] = &[
+    ("c", Units::Metric),
+    ("celsius", Units::Metric),
+    ("f", Units::Imperial),
+    ("fahrenheit", Units::Imperial),
+    ("k", Units::Standard),
+    ("kelvin", Units::Standard),
+];
+
+/// Countries that give the weather in imperial units, by ISO 3166 code: the
+/// United States, Liberia and Myanmar.
+pub const IMPERIAL_COUNTRIES: &[&str] = &["US", "LR", "MM"];
+
 impl Units {
-    fn as_str(&self) -> &'static str {
+    pub const ALL: [Units; 3] = [Units::Metric, Units::Imperial, Units::Standard];
+
+    /// Returns the units the weather is given in in `country`, by ISO 3166
+    /// code in any case: imperial in [`IMPERIAL_COUNTRIES`], and metric
+    /// everywhere else.
+    pub fn for_country(country: &str) -> Units {
+        let country = country.trim();
+        if IMPERIAL_COUNTRIES.iter().any(|c| c.eq_ignore_ascii_case(country)) {
+            Units::Imperial
+        } else {
+            Units::Metric
+        }
+    }
+
+    /// Converts a temperature in these units to the `to` units.
+    pub fn convert_temperature(self, value: f64, to: Units) -> f64 {
+        let kelvin = match self {
+            Units::Metric => value + 273.15,
+            Units::Imperial => (value - 32.0) * 5.0 / 9.0 + 273.15,
+            Units::Standard => value,
+        };
+        match to {
+            Units::Metric => kelvin - 273.15,
+            Units::Imperial => (kelvin - 273.15) * 9.0 / 5.0 + 32.0,
+            Units::Standard => kelvin,
+        }
+    }
+
+    /// Converts a pressure in hPa, which the API gives whatever the units, to
+    /// these units: inHg with imperial units, and hPa otherwise.
+    pub fn pressure_from_hpa(self, hpa: f64) -> f64 {
+        const HPA_PER_INHG: f64 = 33.863_886;
+        match self {
+            Units::Imperial => hpa / HPA_PER_INHG,
+            Units::Metric | Units::Standard => hpa,
+        }
+    }
+
+    /// Converts a distance in meters, which the API gives visibility in
+    /// whatever the units, to these units: miles with imperial units, and
+    /// kilometers otherwise.
+    pub fn distance_from_meters(self, meters: f64) -> f64 {
+        const METERS_PER_MILE: f64 = 1609.344;
+        match self {
+            Units::Imperial => meters / METERS_PER_MILE,
+            Units::Metric | Units::Standard => meters / 1000.0,
+        }
+    }
+
+    /// Converts an amount of rain or snow in mm, which the API gives whatever
+    /// the units, to these units: inches with imperial units, and mm
+    /// otherwise.
+    pub fn precipitation_from_mm(self, mm: f64) -> f64 {
+        match self {
+            Units::Imperial => mm / 25.4,
+            Units::Metric | Units::Standard => mm,
+        }
+    }
+
+    /// Converts a speed in these units, m/s or mph, to the `to` units.
+    pub fn convert_speed(self, value: f64, to: Units) -> f64 {
+        const METERS_PER_MILE: f64 = 1609.344;
+        match (self, to) {
+            (Units::Imperial, Units::Imperial) => value,
+            (Units::Imperial, _) => value * METERS_PER_MILE / 3600.0,
+            (_, Units::Imperial) => value * 3600.0 / METERS_PER_MILE,
+            _ => value,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
         match self {
             Units::Metric => "metric",
             Units::Imperial => "imperial",
+            Units::Standard => "standard",
         }
     }
+
+    /// Parses units from their name or one of [`UNIT_ALIASES`], ignoring case
+    /// and surrounding whitespace. Also returns whether it was an alias, so
+    /// that callers can point that out.
+    pub fn lookup(s: &str) -> anyhow::Result<(Units, bool)> {
+        let name = s.trim().to_lowercase();
+        if let Some(units) = Units::ALL.into_iter().find(|units| units.as_str() == name) {
+            return Ok((units, false));
+        }
+        match UNIT_ALIASES.iter().find(|(alias, _)| *alias == name) {
+            Some(&(_, units)) => Ok((units, true)),
+            None => {
+                let aliases: Vec<&str> = UNIT_ALIASES.iter().map(|(alias, _)| *alias).collect();
+                Err(anyhow::anyhow!(
+                    "unknown units '{}': expected metric, imperial or standard (or {})",
+                    s,
+                    aliases.join(", ")
+                ))
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Units {
+    type Err = anyhow::Error;
+
+    /// Parses units as [`Units::lookup`] does.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Units::lookup(s).map(|(units, _)| units)
+    }
+}
+
+impl fmt::Display for Units {
+    /// Writes the name of the units, as the API takes them.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Units {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
 }
 
 /// Data structures from the OpenWeatherMap API. Not everything is used, but
-/// it's all here should it be needed.
-#[derive(Serialize, Deserialize, Debug)]
+/// it's all here should it be needed. Anything the API may leave out, and that
+/// rendering can do without, has a default, or is optional if a default would
+/// render as a reading, as a wind of 0 m/s would; numbers that are integers in
+/// practice are still read as floats, since JSON does not tell them apart.
+#[derive(Clone, Serialize, Deserialize, Debug)]
 struct Coord {
     /// City geo location, longitude
     lon: f64,
@@ -78,7 +322,7 @@ struct Coord {
     lat: f64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Weather {
     /// Weather condition id
     id: u64,
@@ -90,129 +334,1406 @@ pub struct Weather {
     icon: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Weather {
+    /// Returns the weather condition, as in "light rain", in the language of
+    /// the request.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Returns the group of the weather condition, as in "Rain" or "Clear".
+    /// Unlike the description it is always English.
+    pub fn group(&self) -> &str {
+        &self.main
+    }
+
+    /// Returns the weather condition, by its id.
+    pub fn condition(&self) -> Condition {
+        Condition::from_id(self.id)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
 struct Main {
-    /// Temperature, Kelvin.
+    /// Temperature, in the units requested: °C, °F or K.
+    #[serde(deserialize_with = "de::number")]
     temp: f64,
-    /// Temperature accounting for human perception.
+    /// Temperature accounting for human perception, in the units requested.
+    #[serde(deserialize_with = "de::number")]
     feels_like: f64,
-    /// Atmospheric pressure, hPa
-    pressure: u64,
+    /// Atmospheric pressure, hPa; absent for some stations
+    #[serde(default, deserialize_with = "de::optional_number")]
+    pressure: Option<f64>,
     /// Humidity, %
-    humidity: u64,
-    /// Minimum temperature at the moment, Kelvin
-    temp_min: f64,
-    /// Maximum temperature at the moment, Kelvin
-    temp_max: f64,
+    #[serde(deserialize_with = "de::number")]
+    humidity: f64,
+    /// Minimum temperature at the moment, in the units requested
+    #[serde(default, deserialize_with = "de::optional_number")]
+    temp_min: Option<f64>,
+    /// Maximum temperature at the moment, in the units requested
+    #[serde(default, deserialize_with = "de::optional_number")]
+    temp_max: Option<f64>,
+    /// Atmospheric pressure at sea level and at ground level, hPa; absent
+    /// from the responses of older stations
+    #[serde(default, deserialize_with = "de::optional_number")]
+    sea_level: Option<f64>,
+    #[serde(default, deserialize_with = "de::optional_number")]
+    grnd_level: Option<f64>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
 struct Wind {
-    /// Wind speed, m/s
-    speed: f64,
-    /// Wind direction, degrees (meteorological)
-    deg: u16,
+    /// Wind speed, m/s; absent with the whole `wind` object for some
+    /// stations
+    #[serde(deserialize_with = "de::optional_number")]
+    speed: Option<f64>,
+    /// Wind direction, degrees (meteorological), absent for some stations
+    #[serde(deserialize_with = "de::optional_number")]
+    deg: Option<f64>,
     /// Wind gust, m/s
     gust: Option<f64>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
 struct Clouds {
-    /// Cloudiness, %
-    all: u64,
+    /// Cloudiness, %; absent with the whole `clouds` object for some
+    /// stations
+    #[serde(deserialize_with = "de::optional_number")]
+    all: Option<f64>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// An amount of rain or snow.
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Precipitation {
+    /// Amount in the last hour, mm
+    #[serde(rename = "1h", deserialize_with = "de::optional_number")]
+    pub one_hour: Option<f64>,
+    /// Amount in the last 3 hours, mm
+    #[serde(rename = "3h", deserialize_with = "de::optional_number")]
+    pub three_hours: Option<f64>,
+}
+
+impl Precipitation {
+    /// Returns the amount per hour, in mm: that of the last hour, or else a
+    /// third of that of the last 3 hours, as forecast steps have.
+    pub fn per_hour(&self) -> Option<f64> {
+        self.one_hour.or(self.three_hours.map(|amount| amount / 3.0))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
 struct Sys {
     #[serde(rename = "type")]
-    /// Internal parameter
-    type_: i64,
-    /// Internal parameter
-    id: i64,
-    /// Internal parameter
+    /// Internal parameter, absent for some stations
+    type_: Option<i64>,
+    /// Internal parameter, absent for some stations
+    id: Option<i64>,
+    /// Internal parameter, a number or a string; absent from most responses
+    #[serde(deserialize_with = "de::optional_string_or_number")]
     message: Option<String>,
-    /// Country code (GB, JP etc.)
-    country: String,
-    /// Sunrise time, unix, UTC
-    sunrise: u64,
+    /// Country code (GB, JP etc.), absent for some coordinates
+    country: Option<String>,
+    /// Sunrise time, unix, UTC; absent during polar day and night
+    sunrise: i64,
     /// Sunset time, unix, UTC
-    sunset: u64,
+    sunset: i64,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CurrentWeather {
     coord: Option<Coord>,
+    #[serde(default, deserialize_with = "de::null_as_default")]
     weather: Vec<Weather>,
+    #[serde(default, deserialize_with = "de::null_as_default")]
     base: String,
     main: Main,
-    /// Visibility, meter, maximum is 10km
-    visibility: u64,
+    /// Visibility, meter, maximum is 10km; absent for some stations
+    #[serde(default, deserialize_with = "de::optional_number")]
+    visibility: Option<f64>,
+    #[serde(default, deserialize_with = "de::null_as_default")]
     wind: Wind,
+    #[serde(default, deserialize_with = "de::null_as_default")]
     clouds: Clouds,
     /// Time of data calculation, unix, UTC
     dt: i64,
+    /// Rain, absent when it does not rain
+    #[serde(default)]
+    rain: Option<Precipitation>,
+    /// Snow, absent when it does not snow
+    #[serde(default)]
+    snow: Option<Precipitation>,
+    #[serde(default, deserialize_with = "de::null_as_default")]
     sys: Sys,
     /// Shift in seconds from UTC
+    #[serde(default)]
     timezone: i64,
-    /// City ID
+    /// City ID, 0 if there is none
+    #[serde(default)]
     id: u64,
-    /// City name
-    name: String,
-    /// Internal parameter
-    cod: u64,
+    /// City name, absent for coordinates away from any city
+    #[serde(default, deserialize_with = "de::empty_as_none")]
+    name: Option<String>,
+    /// Internal parameter, a number or a string
+    #[serde(default, deserialize_with = "de::string_or_number_as_string")]
+    cod: String,
+    /// Fields that could not be read, see [`CurrentWeather::missing`]
+    #[serde(skip)]
+    missing: Vec<&'static str>,
 }
 
 impl CurrentWeather {
-    /// Returns supported weather data. Modify this if you need more data types.
+    /// Returns the name of the city OpenWeatherMap resolved the query to, if
+    /// there is one; coordinates over sea or in remote areas have none.
+    pub fn city_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the country code of the location, if there is one.
+    pub fn country(&self) -> Option<&str> {
+        self.sys.country.as_deref().filter(|country| !country.is_empty())
+    }
+
+    /// Returns the visibility in meters, up to 10 km, if the station reports
+    /// it.
+    pub fn visibility(&self) -> Option<f64> {
+        self.visibility
+    }
+
+    /// Returns the rain of the last hour in mm, if the response has it.
+    pub fn rain_last_hour(&self) -> Option<f64> {
+        self.rain.as_ref()?.one_hour
+    }
+
+    /// Returns the snow of the last hour in mm, if the response has it.
+    pub fn snow_last_hour(&self) -> Option<f64> {
+        self.snow.as_ref()?.one_hour
+    }
+
+    /// Returns how hard it rains, or snows for [`DataItem::Snow`], in mm per
+    /// hour (see [`Precipitation::per_hour`]), or `None` if the response has
+    /// no amount, as when it is dry: the API leaves the `rain` and `snow`
+    /// objects out then. Other items have none.
+    pub fn precipitation(&self, item: DataItem) -> Option<f64> {
+        match item {
+            DataItem::Rain => self.rain.as_ref()?.per_hour(),
+            DataItem::Snow => self.snow.as_ref()?.per_hour(),
+            _ => None,
+        }
+    }
+
+    /// Converts the values fetched in `from` units to the `to` units, as if
+    /// they had been fetched in those.
+    pub fn convert(&mut self, from: Units, to: Units) {
+        let main = &mut self.main;
+        for temp in [&mut main.temp, &mut main.feels_like] {
+            *temp = from.convert_temperature(*temp, to);
+        }
+        for temp in [&mut main.temp_min, &mut main.temp_max] {
+            *temp = temp.map(|temp| from.convert_temperature(temp, to));
+        }
+        self.wind.speed = self.wind.speed.map(|speed| from.convert_speed(speed, to));
+        self.wind.gust = self.wind.gust.map(|gust| from.convert_speed(gust, to));
+    }
+
+    /// Writes the city name, or the coordinates as "lat,lon" if there is no
+    /// name.
+    fn write_city(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        match &self.name {
+            Some(name) => out.write_str(name),
+            None => self.write_coords(out),
+        }
+    }
+
+    /// Writes the coordinates as "lat,lon", or nothing if there are none.
+    fn write_coords(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        let Some((lat, lon)) = self.coords() else {
+            return Ok(());
+        };
+        format::write_fixed(out, lat, COORD_PRECISION)?;
+        out.write_char(',')?;
+        format::write_fixed(out, lon, COORD_PRECISION)
+    }
+
+    /// Writes the local time at `epoch` as "HH:MM", or nothing for a missing
+    /// time, as sunrise and sunset are during polar day and night.
+    #[cfg(feature = "time")]
+    fn write_clock(&self, out: &mut impl fmt::Write, epoch: i64) -> fmt::Result {
+        match epoch {
+            0 => Ok(()),
+            _ => match local_datetime(epoch, self.timezone) {
+                Some(t) => write!(out, "{}", t.format("%H:%M")),
+                None => Ok(()),
+            },
+        }
+    }
+
+    /// Renders a time item, `obs_time`, `now_time`, `sunrise` or `sunset`, as
+    /// the local time with a strftime-like `format` instead of the default;
+    /// `now` is the time of rendering. A missing sunrise or sunset renders
+    /// empty. Returns `None` for other items, an invalid format, or a time
+    /// out of range.
+    #[cfg(feature = "time")]
+    pub fn render_clock(&self, item: DataItem, now: i64, format: &str) -> Option<String> {
+        let epoch = match item {
+            DataItem::ObsTime => self.dt,
+            DataItem::NowTime => now,
+            DataItem::Sunrise => self.sys.sunrise,
+            DataItem::Sunset => self.sys.sunset,
+            _ => return None,
+        };
+        match epoch {
+            0 if matches!(item, DataItem::Sunrise | DataItem::Sunset) => Some(String::new()),
+            _ => format::local_time(epoch, self.timezone, format),
+        }
+    }
+
+    /// Returns the id of the city OpenWeatherMap resolved the query to, if
+    /// there is one.
+    pub fn city_id(&self) -> Option<u64> {
+        Some(self.id).filter(|&id| id != 0)
+    }
+
+    /// Returns the location's shift from UTC in seconds.
+    pub fn timezone(&self) -> i64 {
+        self.timezone
+    }
+
+    /// Returns the lowest and highest temperature in the response, if it has
+    /// both. For a large city these are the spread of its stations, not the
+    /// range of the day, and for most places they hardly differ from the
+    /// temperature.
+    pub fn temp_range(&self) -> Option<(f64, f64)> {
+        self.main.temp_min.zip(self.main.temp_max)
+    }
+
+    /// Returns the direction the wind comes from, written out in `lang` (see
+    /// [`geo::compass_name`]), or `None` if the response had none.
+    pub fn wind_direction(&self, lang: &str) -> Option<&'static str> {
+        Some(geo::compass_name(self.wind.deg?, lang))
+    }
+
+    /// Returns the Beaufort force of the wind (see [`wind::beaufort`]), its
+    /// speed in `units`, the units it was fetched with, or `None` if the
+    /// response had none.
+    pub fn beaufort(&self, units: Units) -> Option<u8> {
+        Some(wind::beaufort(units.convert_speed(self.number(DataItem::Wind)?, Units::Metric)))
+    }
+
+    /// Describes the wind by its Beaufort force and that of the gusts if they
+    /// are much stronger (see [`wind::describe`]), its speeds in `units`, or
+    /// returns `None` if the response had no wind.
+    pub fn wind_description(&self, units: Units) -> Option<String> {
+        let metric = |speed| units.convert_speed(speed, Units::Metric);
+        Some(wind::describe(metric(self.number(DataItem::Wind)?), self.wind.gust.map(metric)))
+    }
+
+    /// Returns the coordinates of the location as (latitude, longitude).
+    pub fn coords(&self) -> Option<(f64, f64)> {
+        self.coord.as_ref().map(|c| (c.lat, c.lon))
+    }
+
+    /// Returns the temperature, in the units it was fetched with.
+    pub fn temp(&self) -> f64 {
+        self.main.temp
+    }
+
+    /// Returns the temperature accounting for human perception.
+    pub fn feels_like(&self) -> f64 {
+        self.main.feels_like
+    }
+
+    /// Returns the humidity, in percent.
+    pub fn humidity(&self) -> u64 {
+        self.main.humidity.round().max(0.0) as u64
+    }
+
+    /// Returns the dew point, the temperature at which dew forms, in `units`,
+    /// the units the weather was fetched with. It is computed from the
+    /// temperature and the humidity with the Magnus formula, which is good to
+    /// a few tenths of a degree between -45°C and 60°C; `None` if either was
+    /// not read or there is no humidity.
+    pub fn dew_point(&self, units: Units) -> Option<f64> {
+        let (celsius, humidity) = self.temp_and_humidity(units)?;
+        if humidity <= 0.0 {
+            return None;
+        }
+        // Coefficients over water, as the WMO gives them.
+        const B: f64 = 17.62;
+        const C: f64 = 243.12;
+        let gamma = (humidity.min(100.0) / 100.0).ln() + B * celsius / (C + celsius);
+        Some(Units::Metric.convert_temperature(C * gamma / (B - gamma), units))
+    }
+
+    /// Returns the heat index, how hot it feels with the humidity, in `units`,
+    /// the units the weather was fetched with. It is the regression of the US
+    /// National Weather Service, with its adjustments for very dry and very
+    /// humid air, which is only defined from 80°F (about 27°C): below that the
+    /// temperature is returned as it is. `None` if the temperature or the
+    /// humidity was not read.
+    pub fn heat_index(&self, units: Units) -> Option<f64> {
+        let (celsius, humidity) = self.temp_and_humidity(units)?;
+        let t = Units::Metric.convert_temperature(celsius, Units::Imperial);
+        if t < 80.0 {
+            return Some(self.main.temp);
+        }
+        let rh = humidity.clamp(0.0, 100.0);
+        let mut index = -42.379 + 2.049_015_23 * t + 10.143_331_27 * rh
+            - 0.224_755_41 * t * rh
+            - 0.006_837_83 * t * t
+            - 0.054_817_17 * rh * rh
+            + 0.001_228_74 * t * t * rh
+            + 0.000_852_82 * t * rh * rh
+            - 0.000_001_99 * t * t * rh * rh;
+        if rh < 13.0 && t <= 112.0 {
+            index -= (13.0 - rh) / 4.0 * ((17.0 - (t - 95.0).abs()) / 17.0).sqrt();
+        } else if rh > 85.0 && t <= 87.0 {
+            index += (rh - 85.0) / 10.0 * ((87.0 - t) / 5.0);
+        }
+        Some(Units::Imperial.convert_temperature(index, units))
+    }
+
+    /// Returns the temperature in °C, from `units`, and the humidity, if both
+    /// were read.
+    fn temp_and_humidity(&self, units: Units) -> Option<(f64, f64)> {
+        if !self.is_present("main.temp") || !self.is_present("main.humidity") {
+            return None;
+        }
+        Some((units.convert_temperature(self.main.temp, Units::Metric), self.main.humidity))
+    }
+
+    /// Returns the atmospheric pressure, in hPa, if the response had it.
+    pub fn pressure(&self) -> Option<f64> {
+        self.main.pressure
+    }
+
+    /// Returns the wind speed, in the units it was fetched with, if the
+    /// response had it.
+    pub fn wind_speed(&self) -> Option<f64> {
+        self.wind.speed
+    }
+
+    /// Returns the direction the wind comes from, in degrees, if the
+    /// response had it.
+    pub fn wind_deg(&self) -> Option<f64> {
+        self.wind.deg
+    }
+
+    /// Returns the speed of gusts, if there were any.
+    pub fn wind_gust(&self) -> Option<f64> {
+        self.wind.gust
+    }
+
+    /// Returns the weather conditions, the main one first. Most responses
+    /// have one; some have several at once, as in rain with mist, and a few,
+    /// from stations that aggregate others, none. The icon, the group and the
+    /// condition are those of the main one, and the `description` item joins
+    /// all the descriptions.
+    pub fn conditions(&self) -> &[Weather] {
+        &self.weather
+    }
+
+    /// Returns the main weather condition, as in "light rain", or an empty
+    /// string if the response had none.
+    pub fn description(&self) -> &str {
+        self.weather.first().map_or("", Weather::description)
+    }
+
+    /// Returns the descriptions of all the weather conditions, joined by
+    /// commas, as in "light rain, mist", or an empty string if the response
+    /// had none.
+    pub fn descriptions(&self) -> String {
+        let descriptions: Vec<&str> = self.weather.iter().map(Weather::description).collect();
+        descriptions.join(", ")
+    }
+
+    /// Returns the group of the weather condition, as in "Rain", or an
+    /// empty string if the response had none.
+    pub fn group(&self) -> &str {
+        self.weather.first().map_or("", Weather::group)
+    }
+
+    /// Returns the weather condition, or an unknown one with id 0 if the
+    /// response had none.
+    pub fn condition(&self) -> Condition {
+        self.weather.first().map_or(Condition::Unknown(0), Weather::condition)
+    }
+
+    /// Returns the time of the observation, in unix seconds.
+    pub fn observed(&self) -> i64 {
+        self.dt
+    }
+
+    /// Returns supported weather data, or `('item?')` for unknown items.
+    /// Parsing the name into a [`DataItem`] and calling
+    /// [`CurrentWeather::value`] tells unknown items from ones the response
+    /// has no value for.
     pub fn get(&self, item: &str) -> String {
+        item.parse()
+            .ok()
+            .and_then(|item| self.value(item))
+            .unwrap_or_else(|| format!("('{}?')", item))
+    }
+
+    /// Renders `template`, with each item named in braces replaced by its
+    /// value, as in "{icon} {temp} ({feels_like})". Items are named as
+    /// [`CurrentWeather::get`] takes them and rendered as
+    /// [`CurrentWeather::value`] renders them, or at the time of rendering
+    /// (see [`CurrentWeather::render_at`]); those missing from the response
+    /// render empty. Braces are written doubled, as "{{" and "}}". Unknown
+    /// items, empty fields, items that need more than the current weather
+    /// and braces that do not pair up are errors.
+    pub fn render_template(&self, template: &str) -> anyhow::Result<String> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(c) = rest.chars().next() {
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                out.push(c);
+                rest = &rest[2..];
+                continue;
+            }
+            match c {
+                '}' => anyhow::bail!("unmatched '}}' in template; write '}}}}' for a brace"),
+                '{' => {
+                    let end = rest
+                        .find('}')
+                        .ok_or_else(|| anyhow::anyhow!("unclosed '{{' in template; write '{{{{' for a brace"))?;
+                    let name = rest[1..end].trim();
+                    if name.contains('{') {
+                        anyhow::bail!("'{{' inside template field '{}'; write '{{{{' for a brace", &rest[..=end]);
+                    }
+                    if name.is_empty() {
+                        anyhow::bail!("empty template field '{}'", &rest[..=end]);
+                    }
+                    let item: DataItem = name.parse()?;
+                    if !Self::renders(item) {
+                        anyhow::bail!("item '{}' needs more than the current weather", name);
+                    }
+                    out.push_str(&self.value(item).or_else(|| self.render_at(item, now)).unwrap_or_default());
+                    rest = &rest[end + 1..];
+                }
+                _ => {
+                    out.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Returns whether the current weather alone renders `item`, as
+    /// [`CurrentWeather::value`] or [`CurrentWeather::render_at`] does.
+    fn renders(item: DataItem) -> bool {
+        !matches!(
+            item,
+            DataItem::TodayHigh
+                | DataItem::TodayLow
+                | DataItem::NextRain
+                | DataItem::MoonPhase
+                | DataItem::Uvi
+                | DataItem::UvSafeUntil
+                | DataItem::UvPeak
+                | DataItem::DayStory
+                | DataItem::Trend
+                | DataItem::VentilationWindow
+                | DataItem::Aqi
+                | DataItem::AqiLevel
+                | DataItem::SunElevation
+                | DataItem::SunAzimuth
+                | DataItem::GoldenHour
+                | DataItem::StationDistance
+                | DataItem::StationBearing
+        )
+    }
+
+    /// Returns the rendered value of `item`, or `None` if it needs more than
+    /// the current weather (see [`ItemInfo`]). Temperatures are rendered with
+    /// just a degree sign; see [`CurrentWeather::render`] for other styles.
+    pub fn value(&self, item: DataItem) -> Option<String> {
+        self.render(item, Units::default(), UnitStyle::default())
+    }
+
+    /// Returns the rendered value of `item`, with units as fetched in `units`
+    /// joined in `style`, or `None` if it needs more than the current weather
+    /// (see [`ItemInfo`]) or the time of rendering (see
+    /// [`CurrentWeather::render_at`]), or if the response lacks its reading;
+    /// [`CurrentWeather::try_render`] tells these apart. Times are those of
+    /// the observation. Add new items to the registry in `item.rs`, then here.
+    pub fn render(&self, item: DataItem, units: Units, style: UnitStyle) -> Option<String> {
+        let mut value = String::new();
+        // Writing to a String cannot fail.
+        self.write_item(&mut value, item, units, style)
+            .unwrap_or(false)
+            .then_some(value)
+    }
+
+    /// Returns the rendered value of `item` as [`CurrentWeather::render`] or,
+    /// at `now`, [`CurrentWeather::render_at`] does, `Ok(None)` if the
+    /// response lacks its reading, and why not for an item that cannot be
+    /// rendered from the current weather in this build, which then renders
+    /// [`item::PLACEHOLDER`].
+    pub fn try_render(
+        &self,
+        item: DataItem,
+        units: Units,
+        style: UnitStyle,
+        now: i64,
+    ) -> Result<Option<String>, Unsupported> {
+        if let Some(unsupported) = item.unsupported() {
+            return Err(unsupported);
+        }
+        if !Self::renders(item) {
+            return Err(Unsupported::NotCurrent);
+        }
+        Ok(self.render(item, units, style).or_else(|| self.render_at(item, now)))
+    }
+
+    /// Writes the rendered value of `item` to `out`, as
+    /// [`CurrentWeather::render`] returns it but mostly without allocating,
+    /// and returns whether it could be rendered; nothing is written if not.
+    pub fn write_item(
+        &self,
+        out: &mut impl fmt::Write,
+        item: DataItem,
+        units: Units,
+        style: UnitStyle,
+    ) -> Result<bool, fmt::Error> {
+        if !self.can_render(item) {
+            return Ok(false);
+        }
+        match item {
+            #[cfg(feature = "icons")]
+            DataItem::Icon => out.write_str(&self.icon(IconSet::default()))?,
+            #[cfg(not(feature = "icons"))]
+            DataItem::Icon => return Ok(false),
+            DataItem::Temp
+            | DataItem::FeelsLike
+            | DataItem::Humidity
+            | DataItem::Pressure
+            | DataItem::Visibility
+            | DataItem::Clouds
+            | DataItem::TempMin
+            | DataItem::TempMax
+            | DataItem::Wind
+            | DataItem::WindGust
+            | DataItem::Rain
+            | DataItem::Snow => {
+                return match self.number(item) {
+                    Some(value) => write_number(out, item, value, units, style),
+                    None => Ok(false),
+                }
+            }
+            DataItem::Description => {
+                for (i, w) in self.weather.iter().enumerate() {
+                    if i > 0 {
+                        out.write_str(", ")?;
+                    }
+                    out.write_str(&w.description)?;
+                }
+            }
+            DataItem::DewPoint | DataItem::HeatIndex => {
+                let value = if item == DataItem::DewPoint { self.dew_point(units) } else { self.heat_index(units) };
+                return match value {
+                    Some(value) => write_number(out, item, value, units, style),
+                    None => Ok(false),
+                };
+            }
+            DataItem::Condition => write!(out, "{}", self.condition().id())?,
+            DataItem::Severity => out.write_str(self.condition().severity().name())?,
+            #[cfg(feature = "time")]
+            DataItem::ObsTime => {
+                if let Some(t) = local_datetime(self.dt, self.timezone) {
+                    write!(out, "{}", t.format("%H:%M:%S"))?;
+                }
+            }
+            #[cfg(not(feature = "time"))]
+            DataItem::ObsTime => write!(out, "{}", self.dt)?,
+            DataItem::Dt => write!(out, "{}", self.dt)?,
+            DataItem::WindDeg | DataItem::WindDir | DataItem::WindDirLong => {
+                let Some(deg) = self.wind.deg else {
+                    return Ok(false);
+                };
+                match item {
+                    DataItem::WindDeg => {
+                        format::write_fixed(out, deg, 0)?;
+                        out.write_char('°')?;
+                    }
+                    DataItem::WindDir => out.write_str(geo::compass_point_16(deg))?,
+                    _ => out.write_str(geo::compass_name(deg, "en"))?,
+                }
+            }
+            DataItem::Beaufort => match self.beaufort(units) {
+                Some(force) => write!(out, "{}", force)?,
+                None => return Ok(false),
+            },
+            DataItem::WindDesc => match self.wind_description(units) {
+                Some(description) => out.write_str(&description)?,
+                None => return Ok(false),
+            },
+            DataItem::DayLength => match self.day_length() {
+                Some(seconds) => {
+                    let minutes = seconds / 60;
+                    write!(out, "{}h {:02}m", minutes / 60, minutes % 60)?;
+                }
+                None => return Ok(false),
+            },
+            DataItem::City => self.write_city(out)?,
+            DataItem::Country => match self.country() {
+                Some(country) => out.write_str(country)?,
+                None => return Ok(false),
+            },
+            DataItem::Coords => self.write_coords(out)?,
+            #[cfg(feature = "time")]
+            DataItem::Sunrise => self.write_clock(out, self.sys.sunrise)?,
+            #[cfg(feature = "time")]
+            DataItem::Sunset => self.write_clock(out, self.sys.sunset)?,
+            #[cfg(not(feature = "time"))]
+            DataItem::Sunrise | DataItem::Sunset => return Ok(false),
+            DataItem::ObsAge
+            | DataItem::Daylight
+            | DataItem::NowTime
+            | DataItem::NowDate
+            | DataItem::TodayHigh
+            | DataItem::TodayLow
+            | DataItem::NextRain
+            | DataItem::MoonPhase
+            | DataItem::Uvi
+            | DataItem::UvSafeUntil
+            | DataItem::UvPeak
+            | DataItem::DayStory
+            | DataItem::Trend
+            | DataItem::VentilationWindow
+            | DataItem::Aqi
+            | DataItem::AqiLevel
+            | DataItem::SunElevation
+            | DataItem::SunAzimuth
+            | DataItem::GoldenHour
+            | DataItem::StationDistance
+            | DataItem::StationBearing => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Returns the rendered value of an item that depends on when it is
+    /// rendered, at `now` in unix seconds, or `None` for other items. Items
+    /// named `obs_*` are about the time of the observation, and those named
+    /// `now_*` about `now`.
+    pub fn render_at(&self, item: DataItem, now: i64) -> Option<String> {
+        match item {
+            DataItem::ObsAge if self.is_present("dt") => Some(format!("{} min", self.age(now) / 60)),
+            DataItem::Daylight if self.is_present("sys") => self.daylight(now),
+            #[cfg(feature = "time")]
+            DataItem::NowTime => format::local_time(now, self.timezone, "%H:%M:%S"),
+            #[cfg(feature = "time")]
+            DataItem::NowDate => format::local_time(now, self.timezone, "%Y-%m-%d"),
+            _ => None,
+        }
+    }
+
+    /// Returns the rendered value of `item` for screen readers, as with
+    /// `--accessible`: introduced as [`ItemInfo::spoken`] tells, with units
+    /// named in `lang` where there is a table for it (see
+    /// [`format::spoken`]), directions named and no glyphs, as in "wind 5
+    /// meters per second from the northwest". Items about the time of
+    /// rendering are rendered at `now`. Returns `None` for the items neither
+    /// [`CurrentWeather::render`] nor [`CurrentWeather::render_at`] renders.
+    pub fn render_accessible(&self, item: DataItem, units: Units, lang: &str, now: i64) -> Option<String> {
+        if !self.can_render(item) {
+            return None;
+        }
+        let value = match item {
+            #[cfg(feature = "icons")]
+            DataItem::Icon => self.icon(IconSet::Text),
+            DataItem::Temp
+            | DataItem::FeelsLike
+            | DataItem::Humidity
+            | DataItem::Pressure
+            | DataItem::Visibility
+            | DataItem::Clouds
+            | DataItem::TempMin
+            | DataItem::TempMax
+            | DataItem::WindGust
+            | DataItem::Rain
+            | DataItem::Snow => {
+                let (value, precision) = scaled(item, self.number(item)?, units);
+                format::spoken(value, precision, item.info().unit, units, lang)
+            }
+            DataItem::Wind => {
+                let speed = format::spoken(self.number(item)?, 0, item.info().unit, units, lang);
+                match self.wind_direction(lang) {
+                    Some(direction) => format!("{} from the {}", speed, direction),
+                    None => speed,
+                }
+            }
+            DataItem::DewPoint => format::spoken(self.dew_point(units)?, 0, item.info().unit, units, lang),
+            DataItem::HeatIndex => format::spoken(self.heat_index(units)?, 0, item.info().unit, units, lang),
+            DataItem::WindDeg => format::spoken(self.wind.deg?, 0, item.info().unit, units, lang),
+            DataItem::WindDir | DataItem::WindDirLong => geo::compass_name(self.wind.deg?, lang).to_string(),
+            DataItem::ObsAge => {
+                let minutes = (self.age(now) / 60) as f64;
+                format!("{} ago", format::spoken(minutes, 0, item::Unit::Minutes, units, lang))
+            }
+            DataItem::Daylight => {
+                let (minutes, event) = self.until_sun_event(now)?;
+                format!("{} to {}", spoken_duration(minutes, units, lang), event)
+            }
+            DataItem::DayLength => spoken_duration(self.day_length()? / 60, units, lang),
+            _ => self.render(item, units, UnitStyle::Verbose).or_else(|| self.render_at(item, now))?,
+        };
+        Some(format::accessible(item, &value))
+    }
+
+    /// Returns whether the fields `item` renders from were read, which they
+    /// all are unless the response was salvaged (see [`ParseMode::Salvage`]).
+    fn can_render(&self, item: DataItem) -> bool {
         match item {
-            "icon" => match_icon(&self.weather[0].icon),
-            "temp" => format!("{}°", self.main.temp.round()),
-            "feels_like" => format!("{}°", self.main.feels_like.round()),
-            "humidity" => format!("{}%", self.main.humidity),
-            "description" => self.weather[0].description.to_string(),
-            "time" => epoch_to_time(self.dt + self.timezone),
-            _ => format!("('{}?')", item),
-        }
-    }
-}
-
-/// Convert OpenWeatherMap icon id to icon.
-fn match_icon<S: AsRef<str>>(code: S) -> String {
-    match code.as_ref() {
-        CLEAR_DAY => "",
-        CLEAR_NIGHT => "",
-        FEW_CLOUDS_DAY => "",
-        FEW_CLOUDS_NIGHT => "",
-        SCATTERED_CLOUDS_DAY | SCATTERED_CLOUDS_NIGHT => "摒",
-        BROKEN_CLOUDS_DAY | BROKEN_CLOUDS_NIGHT => "",
-        SHOWER_RAIN_DAY | SHOWER_RAIN_NIGHT => "",
-        RAIN_DAY | RAIN_NIGHT => "",
-        THUNDERSTORM_DAY | THUNDERSTORM_NIGHT => "",
-        SNOW_DAY | SNOW_NIGHT => "",
-        MIST_DAY | MIST_NIGHT => "",
-        _ => "?"
-    }.to_string()
-}
-
-/// Fetches the current weather for the given location.
+            DataItem::Icon | DataItem::Description | DataItem::Condition | DataItem::Severity => {
+                self.is_present("weather")
+            }
+            DataItem::ObsTime | DataItem::Dt | DataItem::ObsAge => self.is_present("dt"),
+            DataItem::WindDeg | DataItem::WindDir | DataItem::WindDirLong => self.wind.deg.is_some(),
+            DataItem::Visibility => self.visibility.is_some(),
+            DataItem::Pressure => self.main.pressure.is_some(),
+            DataItem::Clouds => self.clouds.all.is_some(),
+            DataItem::TempMin => self.main.temp_min.is_some(),
+            DataItem::TempMax => self.main.temp_max.is_some(),
+            DataItem::Wind | DataItem::Beaufort | DataItem::WindDesc => self.wind.speed.is_some(),
+            DataItem::Sunrise | DataItem::Sunset => self.is_present("sys"),
+            _ => true,
+        }
+    }
+
+    /// Returns the time from `now`, in unix seconds, to the next sunset, or
+    /// to the next sunrise after dark, as in "2h 05m to sunset", or `None`
+    /// during polar day and night. After sunset, the next sunrise is taken
+    /// to be a day after today's.
+    fn daylight(&self, now: i64) -> Option<String> {
+        let (minutes, event) = self.until_sun_event(now)?;
+        Some(format!("{}h {:02}m to {}", minutes / 60, minutes % 60, event))
+    }
+
+    /// Returns the seconds from sunrise to sunset, or `None` during polar day
+    /// and night.
+    pub fn day_length(&self) -> Option<i64> {
+        let (sunrise, sunset) = (self.sys.sunrise, self.sys.sunset);
+        if sunrise == 0 || sunset == 0 {
+            return None;
+        }
+        Some(sunset.saturating_sub(sunrise).max(0))
+    }
+
+    /// Returns the minutes from `now` to the next sunset, or to the next
+    /// sunrise after dark, and which of the two it is, as
+    /// `daylight` tells them.
+    fn until_sun_event(&self, now: i64) -> Option<(i64, &'static str)> {
+        let (sunrise, sunset) = (self.sys.sunrise, self.sys.sunset);
+        if sunrise == 0 || sunset == 0 {
+            return None;
+        }
+        let (until, event) = if now < sunrise {
+            (sunrise, "sunrise")
+        } else if now < sunset {
+            (sunset, "sunset")
+        } else {
+            (sunrise.saturating_add(86400), "sunrise")
+        };
+        Some((until.saturating_sub(now).max(0) / 60, event))
+    }
+
+    /// Returns the seconds from the observation to `now`, in unix seconds, or
+    /// 0 if the observation seems to be from after it.
+    pub fn age(&self, now: i64) -> i64 {
+        now.saturating_sub(self.dt).max(0)
+    }
+
+    /// Returns the number behind `item`, as fetched, for the items that
+    /// render one from the current weather.
+    pub fn number(&self, item: DataItem) -> Option<f64> {
+        match item {
+            DataItem::Temp => self.is_present("main.temp").then_some(self.main.temp),
+            DataItem::FeelsLike => self.is_present("main.feels_like").then_some(self.main.feels_like),
+            DataItem::Humidity => self.is_present("main.humidity").then_some(self.main.humidity),
+            DataItem::Pressure => self.main.pressure,
+            DataItem::Visibility => self.visibility,
+            DataItem::Clouds => self.clouds.all,
+            DataItem::TempMin => self.main.temp_min,
+            DataItem::TempMax => self.main.temp_max,
+            DataItem::Wind => self.wind.speed,
+            DataItem::WindGust => self.wind.gust,
+            // Dry weather has no amount, which is none at all.
+            DataItem::Rain | DataItem::Snow => Some(self.precipitation(item).unwrap_or(0.0)),
+            _ => None,
+        }
+    }
+
+    /// Explains how `value` computes `item`, including the raw value it starts
+    /// from.
+    pub fn explain(&self, item: DataItem) -> Explanation {
+        let e = Explanation::new(item.name(), explain::CURRENT_ENDPOINT);
+        let weather = self.weather.first();
+        match item {
+            DataItem::Icon => match weather {
+                Some(w) => e.raw(&w.icon),
+                None => e.fallback("no weather conditions in the response"),
+            },
+            DataItem::Temp => e.raw(self.main.temp),
+            DataItem::FeelsLike => e.raw(self.main.feels_like),
+            DataItem::Humidity => e.raw(self.main.humidity),
+            DataItem::Pressure => match self.main.pressure {
+                Some(hpa) => e.raw(hpa).formula("hectopascals, to inches of mercury with imperial units"),
+                None => e.fallback("no pressure in the response, rendered empty"),
+            },
+            DataItem::Visibility => match self.visibility {
+                Some(meters) => e.raw(meters).formula("meters to kilometers, or to miles with imperial units"),
+                None => e.fallback("no visibility in the response, rendered empty"),
+            },
+            DataItem::Clouds | DataItem::TempMin | DataItem::TempMax => match self.number(item) {
+                Some(value) => e.raw(value),
+                None => e.fallback(format!("no {} in the response, rendered empty", item.name())),
+            },
+            DataItem::DewPoint => e
+                .raw(format!("temp {}, humidity {}", self.main.temp, self.main.humidity))
+                .formula("Magnus formula"),
+            DataItem::HeatIndex => e
+                .raw(format!("temp {}, humidity {}", self.main.temp, self.main.humidity))
+                .formula("NWS heat index regression from 80°F, the temperature below"),
+            DataItem::Description => match self.weather.len() {
+                0 => e.fallback("no weather conditions in the response"),
+                1 => e.raw(self.descriptions()),
+                _ => e.raw(self.descriptions()).formula("descriptions of all the conditions, main one first"),
+            },
+            DataItem::Condition | DataItem::Severity => match weather {
+                Some(w) if item == DataItem::Severity => e.raw(w.id).formula("severity of the condition id"),
+                Some(w) => e.raw(w.id),
+                None => e.fallback("no weather conditions in the response"),
+            },
+            DataItem::Rain | DataItem::Snow => {
+                let (amount, name) = if item == DataItem::Rain { (&self.rain, "rain") } else { (&self.snow, "snow") };
+                match amount {
+                    Some(Precipitation { one_hour: Some(mm), .. }) => e.raw(mm),
+                    Some(Precipitation { three_hours: Some(mm), .. }) => e.raw(mm).formula("a third of the amount of the last 3 hours"),
+                    _ => e.fallback(format!("no {} in the response, rendered 0", name)),
+                }
+            }
+            DataItem::ObsTime => e.raw(self.dt).formula("dt at the location's timezone"),
+            DataItem::Dt => e.raw(self.dt),
+            DataItem::ObsAge => e.raw(self.dt).formula("minutes from dt to the time of rendering"),
+            DataItem::NowTime | DataItem::NowDate => e.formula("time of rendering, at the location's timezone"),
+            DataItem::Wind | DataItem::Beaufort | DataItem::WindDesc if self.wind.speed.is_none() => {
+                e.fallback("no wind speed in the response, rendered empty")
+            }
+            DataItem::Wind => e.raw(self.wind.speed.unwrap_or_default()),
+            DataItem::WindDeg | DataItem::WindDir | DataItem::WindDirLong => match self.wind.deg {
+                Some(deg) => e.raw(deg),
+                None => e.fallback("no wind direction in the response, rendered empty"),
+            },
+            DataItem::WindGust => match self.wind.gust {
+                Some(gust) => e.raw(gust),
+                None => e.fallback("no gusts in the response, rendered empty"),
+            },
+            DataItem::Beaufort => e.raw(self.wind.speed.unwrap_or_default()).formula("Beaufort force of the speed in m/s"),
+            DataItem::WindDesc => match self.wind.gust {
+                Some(gust) => e
+                    .raw(format!("speed {}, gust {}", self.wind.speed.unwrap_or_default(), gust))
+                    .formula("names of the Beaufort forces of the speed and of gusts two forces stronger"),
+                None => e
+                    .raw(self.wind.speed.unwrap_or_default())
+                    .formula("name of the Beaufort force of the speed"),
+            },
+            DataItem::Sunrise => e.raw(self.sys.sunrise),
+            DataItem::Daylight => e
+                .raw(format!("sunrise {}, sunset {}", self.sys.sunrise, self.sys.sunset))
+                .formula("time of rendering to the next sunset, or sunrise after dark"),
+            DataItem::Sunset => e.raw(self.sys.sunset),
+            DataItem::DayLength => e
+                .raw(format!("sunrise {}, sunset {}", self.sys.sunrise, self.sys.sunset))
+                .formula("sunset minus sunrise"),
+            DataItem::City => match &self.name {
+                Some(name) => e.raw(name),
+                None => e.fallback("no city name in the response, using coordinates"),
+            },
+            DataItem::Country => match self.country() {
+                Some(country) => e.raw(country),
+                None => e.fallback("no country in the response, rendered empty"),
+            },
+            DataItem::Coords => match self.coords() {
+                Some((lat, lon)) => e.raw(format!("lat {}, lon {}", lat, lon)),
+                None => e.fallback("no coordinates in the response, rendered empty"),
+            },
+            DataItem::TodayHigh
+            | DataItem::TodayLow
+            | DataItem::NextRain
+            | DataItem::MoonPhase
+            | DataItem::Uvi
+            | DataItem::UvSafeUntil
+            | DataItem::UvPeak
+            | DataItem::DayStory
+            | DataItem::Trend
+            | DataItem::VentilationWindow
+            | DataItem::Aqi
+            | DataItem::AqiLevel
+            | DataItem::SunElevation
+            | DataItem::SunAzimuth
+            | DataItem::GoldenHour
+            | DataItem::StationDistance
+            | DataItem::StationBearing => e,
+        }
+    }
+}
+
+/// Renders `value` as `item` renders its number (see
+/// [`CurrentWeather::number`]), with units as fetched in `units` joined in
+/// `style`, or returns `None` for other items.
+pub fn render_number(item: DataItem, value: f64, units: Units, style: UnitStyle) -> Option<String> {
+    let mut s = String::new();
+    // Writing to a String cannot fail.
+    write_number(&mut s, item, value, units, style).unwrap_or(false).then_some(s)
+}
+
+/// Writes `value` to `out` as [`render_number`] renders it, and returns
+/// whether `item` renders a number; nothing is written if not.
+pub fn write_number(
+    out: &mut impl fmt::Write,
+    item: DataItem,
+    value: f64,
+    units: Units,
+    style: UnitStyle,
+) -> Result<bool, fmt::Error> {
+    let quantity = match item {
+        DataItem::Temp
+        | DataItem::FeelsLike
+        | DataItem::TodayHigh
+        | DataItem::TodayLow
+        | DataItem::TempMin
+        | DataItem::TempMax
+        | DataItem::DewPoint
+        | DataItem::HeatIndex => Quantity::Temperature,
+        DataItem::Wind | DataItem::WindGust => Quantity::Speed,
+        DataItem::Pressure => Quantity::Pressure,
+        DataItem::Visibility => Quantity::Distance,
+        DataItem::Rain | DataItem::Snow => Quantity::Precipitation,
+        DataItem::Humidity | DataItem::Clouds => {
+            format::write_fixed(out, value, 0)?;
+            out.write_char('%')?;
+            return Ok(true);
+        }
+        _ => return Ok(false),
+    };
+    let (value, precision) = scaled(item, value, units);
+    format::write_with_unit(out, value, precision, quantity, units, style)?;
+    Ok(true)
+}
+
+/// "15 hours 10 minutes" for 910 `minutes`: the hours, if there are any,
+/// and the minutes named in `lang`, for screen readers.
+fn spoken_duration(minutes: i64, units: Units, lang: &str) -> String {
+    let hours = match minutes / 60 {
+        1 => "1 hour ".to_string(),
+        0 => String::new(),
+        hours => format!("{} hours ", hours),
+    };
+    let minutes = format::spoken((minutes % 60) as f64, 0, item::Unit::Minutes, units, lang);
+    format!("{}{}", hours, minutes)
+}
+
+/// Returns the number behind `item` in the unit it is rendered in, and the
+/// decimals to render it with. The API gives the pressure in hPa, the
+/// visibility in meters and rain and snow in mm whatever the units, so those
+/// are converted here; inches of mercury and of rain get two decimals,
+/// distances under 10 and mm of rain one, and no rain none.
+fn scaled(item: DataItem, value: f64, units: Units) -> (f64, usize) {
+    match item {
+        DataItem::Pressure if units == Units::Imperial => (units.pressure_from_hpa(value), 2),
+        DataItem::Rain | DataItem::Snow => {
+            let amount = units.precipitation_from_mm(value);
+            let precision = match units {
+                _ if amount == 0.0 => 0,
+                Units::Imperial => 2,
+                _ => 1,
+            };
+            (amount, precision)
+        }
+        DataItem::Visibility => {
+            let distance = units.distance_from_meters(value);
+            (distance, usize::from(distance < 10.0))
+        }
+        _ => (value, 0),
+    }
+}
+
+/// Fetches the current weather for the given location. This is a shortcut for
+/// building a [`Client`] and calling [`Client::current`]. Clients built like
+/// this share their connections, so calling it again, or another shortcut,
+/// reuses them.
+#[cfg(feature = "http")]
 pub fn get(location: Location, units: Units, key: &str) -> Result<CurrentWeather> {
-    let mut url = Url::parse("https://api.openweathermap.org/data/2.5/weather")?;
-    url.query_pairs_mut()
-       .append_pair("q", location.to_string().as_str());
-    url.query_pairs_mut().append_pair("units", units.as_str());
-    url.query_pairs_mut().append_pair("appid", key);
+    Client::builder()
+        .api_key(key)
+        .units(units)
+        .build()?
+        .current(&location)
+}
 
-    let body: String = reqwest::blocking::get(url.as_str())?.text()?;
-    let result: CurrentWeather = serde_json::from_str(&body)?;
+/// Fetches One Call data for the given coordinates, leaving out the
+/// `exclude`d parts. This is a shortcut for building a [`Client`] and calling
+/// [`Client::one_call`].
+#[cfg(feature = "onecall")]
+pub fn one_call(coords: (f64, f64), units: Units, key: &str, exclude: &[Part]) -> Result<OneCall> {
+    Client::builder()
+        .api_key(key)
+        .units(units)
+        .build()?
+        .one_call(coords, exclude)
+}
+
+/// Fetches the current UV index at the given coordinates from One Call. This
+/// is a shortcut for building a [`Client`] and calling [`Client::uvi`].
+#[cfg(feature = "onecall")]
+pub fn get_uvi(lat: f64, lon: f64, key: &str) -> Result<f64> {
+    Client::builder().api_key(key).build()?.uvi((lat, lon))
+}
 
-    Ok(result)
+/// Fetches the current weather for the given location without blocking. This
+/// is a shortcut for building a [`Client`] and calling
+/// [`Client::current_async`].
+#[cfg(feature = "async")]
+pub async fn get_async(location: Location, units: Units, key: &str) -> Result<CurrentWeather> {
+    Client::builder()
+        .api_key(key)
+        .units(units)
+        .build()?
+        .current_async(&location)
+        .await
 }
 
-/// Converts epoch time to a human-readable time.
-#[must_use]
-fn epoch_to_time(epoch: i64) -> String {
-    let st = UNIX_EPOCH + Duration::from_secs(epoch.try_into().unwrap());
-    let dt = DateTime::<Utc>::from(st);
-    dt.format("%H:%M:%S").to_string()
+/// Fetches the 5 day forecast for the given location. This is a shortcut for
+/// building a [`Client`] and calling [`Client::forecast`].
+#[cfg(feature = "http")]
+pub fn get_forecast(location: Location, units: Units, key: &str) -> Result<Forecast> {
+    Client::builder()
+        .api_key(key)
+        .units(units)
+        .build()?
+        .forecast(&location)
+}
+
+/// Returns the local date and time at `offset` seconds from UTC, if the
+/// timestamp and offset are in range.
+#[cfg(feature = "time")]
+pub(crate) fn local_datetime(epoch: i64, offset: i64) -> Option<DateTime<FixedOffset>> {
+    let offset = FixedOffset::east_opt(offset.try_into().ok()?)?;
+    offset.timestamp_opt(epoch, 0).single()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::ParseMode;
+
+    /// Returns the weather of a response with only `temp` and `humidity`.
+    fn weather(temp: f64, humidity: f64) -> CurrentWeather {
+        let body = serde_json::json!({"main": {"temp": temp, "humidity": humidity}}).to_string();
+        CurrentWeather::from_json(&body, ParseMode::Salvage).unwrap()
+    }
+
+    #[test]
+    fn units_of_countries() {
+        for country in ["US", "us", " LR ", "Mm"] {
+            assert_eq!(Units::for_country(country), Units::Imperial, "{:?}", country);
+        }
+        for country in ["NL", "GB", "CA", "", "USA"] {
+            assert_eq!(Units::for_country(country), Units::Metric, "{:?}", country);
+        }
+    }
+
+    #[test]
+    fn units_round_trip_through_their_names() {
+        for units in Units::ALL {
+            assert_eq!(units.to_string().parse::<Units>().unwrap(), units);
+            assert_eq!(units.to_string(), units.as_str());
+            assert_eq!(Units::lookup(&units.to_string().to_uppercase()).unwrap(), (units, false));
+        }
+        for (alias, units) in UNIT_ALIASES {
+            assert_eq!(Units::lookup(alias).unwrap(), (*units, true), "{}", alias);
+        }
+        let e = "rankine".parse::<Units>().unwrap_err();
+        let expected = "unknown units 'rankine': expected metric, imperial or standard (or c, celsius, f, \
+            fahrenheit, k, kelvin)";
+        assert_eq!(e.to_string(), expected);
+    }
+
+    #[test]
+    fn temperatures_round_trip_through_every_unit() {
+        for celsius in [-40.0, 0.0, 21.0, 36.6] {
+            for from in Units::ALL {
+                let value = Units::Metric.convert_temperature(celsius, from);
+                for to in Units::ALL {
+                    let back = to.convert_temperature(from.convert_temperature(value, to), from);
+                    assert!((back - value).abs() < 1e-9, "{} {:?} through {:?}: {}", value, from, to, back);
+                }
+            }
+        }
+        assert_eq!(Units::Metric.convert_temperature(-40.0, Units::Imperial), -40.0);
+        assert_eq!(Units::Metric.convert_temperature(0.0, Units::Standard), 273.15);
+        assert_eq!(Units::Standard.convert_temperature(294.15, Units::Metric), 21.0);
+    }
+
+    #[test]
+    fn temperatures_are_labeled_by_their_units() {
+        use format::UnitStyle;
+
+        // 21°C as the API gives it in each of the units, labeled and not.
+        let cases = [
+            (Units::Metric, 21.0, "21°C", "21°"),
+            (Units::Imperial, 69.8, "70°F", "70°"),
+            // Kelvin has no degrees.
+            (Units::Standard, 294.15, "294K", "294"),
+        ];
+        for (units, temp, labeled, plain) in cases {
+            let weather = weather(temp, 50.0);
+            for (style, expected) in [(UnitStyle::Compact, labeled), (UnitStyle::Symbolless, plain)] {
+                let rendered = weather.render(DataItem::Temp, units, style);
+                assert_eq!(rendered.as_deref(), Some(expected), "{:?} {:?}", units, style);
+            }
+        }
+    }
+
+    #[test]
+    fn dew_points_of_the_reference_tables() {
+        // (°C, %, °C), as the NOAA dew point calculator gives them.
+        let cases = [
+            (25.0, 60.0, 16.7),
+            (30.0, 70.0, 23.9),
+            (0.0, 50.0, -9.2),
+            (-10.0, 80.0, -12.8),
+            (35.0, 20.0, 8.7),
+            (15.0, 40.0, 1.5),
+            (20.0, 100.0, 20.0),
+        ];
+        for (celsius, humidity, expected) in cases {
+            let dew_point = weather(celsius, humidity).dew_point(Units::Metric).unwrap();
+            assert!((dew_point - expected).abs() < 0.1, "{}°C at {}%: {}", celsius, humidity, dew_point);
+            let fahrenheit = Units::Metric.convert_temperature(celsius, Units::Imperial);
+            let dew_point = weather(fahrenheit, humidity).dew_point(Units::Imperial).unwrap();
+            let expected = Units::Metric.convert_temperature(expected, Units::Imperial);
+            assert!((dew_point - expected).abs() < 0.2, "{}°F at {}%: {}", fahrenheit, humidity, dew_point);
+        }
+    }
+
+    #[test]
+    fn dew_points_need_a_humidity() {
+        assert_eq!(weather(20.0, 0.0).dew_point(Units::Metric), None);
+        let body = serde_json::json!({"main": {"temp": 20.0}}).to_string();
+        let weather = CurrentWeather::from_json(&body, ParseMode::Salvage).unwrap();
+        assert_eq!(weather.dew_point(Units::Metric), None);
+        assert_eq!(weather.heat_index(Units::Metric), None);
+    }
+
+    #[test]
+    fn heat_indices_of_the_reference_table() {
+        // (°F, %, °F), from the heat index chart of the National Weather
+        // Service, which rounds to whole degrees.
+        let cases = [
+            (80.0, 40.0, 80.0),
+            (88.0, 60.0, 95.0),
+            (90.0, 50.0, 95.0),
+            (90.0, 70.0, 106.0),
+            (96.0, 65.0, 121.0),
+            (100.0, 40.0, 109.0),
+            (104.0, 55.0, 137.0),
+            (86.0, 90.0, 105.0),
+        ];
+        for (fahrenheit, humidity, expected) in cases {
+            let index = weather(fahrenheit, humidity).heat_index(Units::Imperial).unwrap();
+            assert!((index - expected).abs() <= 0.5, "{}°F at {}%: {}", fahrenheit, humidity, index);
+            let celsius = Units::Imperial.convert_temperature(fahrenheit, Units::Metric);
+            let index = weather(celsius, humidity).heat_index(Units::Metric).unwrap();
+            let expected = Units::Imperial.convert_temperature(expected, Units::Metric);
+            assert!((index - expected).abs() <= 0.3, "{}°C at {}%: {}", celsius, humidity, index);
+        }
+    }
+
+    #[test]
+    fn heat_indices_below_80_f_are_the_temperature() {
+        let cases = [(79.9, Units::Imperial), (26.6, Units::Metric), (299.8, Units::Standard), (-5.0, Units::Metric)];
+        for (temp, units) in cases {
+            assert_eq!(weather(temp, 90.0).heat_index(units), Some(temp), "{} {:?}", temp, units);
+        }
+        // 27°C is 80.6°F, over the threshold.
+        assert_ne!(weather(27.0, 90.0).heat_index(Units::Metric), Some(27.0));
+    }
+
+    #[test]
+    fn pressures_and_distances_of_each_unit_system() {
+        // One standard atmosphere, and the 10 km visibility is capped at.
+        assert!((Units::Imperial.pressure_from_hpa(1013.25) - 29.921).abs() < 0.001);
+        assert_eq!(Units::Metric.pressure_from_hpa(1013.25), 1013.25);
+        assert_eq!(Units::Standard.pressure_from_hpa(1013.25), 1013.25);
+        assert!((Units::Imperial.distance_from_meters(10_000.0) - 6.2137).abs() < 0.001);
+        assert_eq!(Units::Imperial.distance_from_meters(1609.344), 1.0);
+        assert_eq!(Units::Metric.distance_from_meters(10_000.0), 10.0);
+        assert_eq!(Units::Standard.distance_from_meters(250.0), 0.25);
+    }
+
+    #[test]
+    fn pressure_visibility_clouds_and_range_render_from_the_response() {
+        use format::UnitStyle;
+
+        let body = serde_json::json!({
+            "main": {"temp": 12.0, "humidity": 80, "pressure": 1021, "temp_min": 9.4, "temp_max": 14.6},
+            "visibility": 10000,
+            "clouds": {"all": 40},
+        });
+        let weather = CurrentWeather::from_json(&body.to_string(), ParseMode::Salvage).unwrap();
+        let render = |item, units| weather.render(item, units, UnitStyle::Compact);
+        let cases = [
+            (DataItem::Pressure, "1021hPa", "30.15inHg"),
+            (DataItem::Visibility, "10km", "6.2mi"),
+            (DataItem::Clouds, "40%", "40%"),
+            (DataItem::TempMin, "9°C", "9°F"),
+            (DataItem::TempMax, "15°C", "15°F"),
+        ];
+        for (item, metric, imperial) in cases {
+            assert_eq!(render(item, Units::Metric).as_deref(), Some(metric), "{}", item.name());
+            assert_eq!(render(item, Units::Imperial).as_deref(), Some(imperial), "{}", item.name());
+        }
+
+        // What the response does not have renders empty.
+        let body = serde_json::json!({"main": {"temp": 12.0, "humidity": 80}}).to_string();
+        let weather = CurrentWeather::from_json(&body, ParseMode::Salvage).unwrap();
+        for item in [DataItem::Pressure, DataItem::Visibility, DataItem::Clouds, DataItem::TempMin, DataItem::TempMax] {
+            assert_eq!(weather.render(item, Units::Imperial, UnitStyle::Compact), None, "{}", item.name());
+        }
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn local_datetimes() {
+        let cases = [
+            // 1 May 2024, 08:00 UTC, 11 hours behind, as in Niue.
+            (1_714_550_400, -39_600, Some("2024-04-30T21:00:00-11:00")),
+            (1_714_550_400, 0, Some("2024-05-01T08:00:00+00:00")),
+            (1_714_550_400, 50_400, Some("2024-05-01T22:00:00+14:00")),
+            (0, 0, Some("1970-01-01T00:00:00+00:00")),
+            (0, -39_600, Some("1969-12-31T13:00:00-11:00")),
+            (-1, 0, Some("1969-12-31T23:59:59+00:00")),
+            (-86_400 * 365, 3600, Some("1969-01-01T01:00:00+01:00")),
+            (i64::MAX, 0, None),
+            (i64::MIN, 0, None),
+            (0, 86_400, None),
+            (0, -86_400, None),
+            (0, i64::MAX, None),
+        ];
+        for (epoch, offset, expected) in cases {
+            let local = local_datetime(epoch, offset).map(|t| t.to_rfc3339());
+            assert_eq!(local.as_deref(), expected, "{} at {}", epoch, offset);
+        }
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn clock_items_are_local_to_the_location() {
+        // 1 May 2024, 08:00 UTC, in Tokyo, with sunrise and sunset.
+        let body = serde_json::json!({"dt": 1_714_550_400, "timezone": 32_400, "main": {"temp": 18.0, "humidity": 60},
+            "sys": {"sunrise": 1_714_506_300, "sunset": 1_714_556_100}});
+        let weather = CurrentWeather::from_json(&body.to_string(), ParseMode::Salvage).unwrap();
+        let clock = |item, format| weather.render_clock(item, 1_714_552_200, format);
+        assert_eq!(clock(DataItem::ObsTime, "%H:%M:%S").as_deref(), Some("17:00:00"));
+        assert_eq!(clock(DataItem::ObsTime, "%I:%M %p").as_deref(), Some("05:00 PM"));
+        assert_eq!(clock(DataItem::NowTime, "%I:%M %p").as_deref(), Some("05:30 PM"));
+        assert_eq!(clock(DataItem::Sunrise, "%I:%M %p").as_deref(), Some("04:45 AM"));
+        assert_eq!(clock(DataItem::Sunset, "%H:%M").as_deref(), Some("18:35"));
+        assert_eq!(clock(DataItem::Temp, "%H:%M"), None);
+        assert_eq!(clock(DataItem::ObsTime, "%Q"), None);
+
+        // Without a sunrise, as near the poles, it renders empty.
+        let body = serde_json::json!({"dt": 1_714_550_400, "main": {"temp": 18.0, "humidity": 60}}).to_string();
+        let weather = CurrentWeather::from_json(&body, ParseMode::Salvage).unwrap();
+        assert_eq!(weather.render_clock(DataItem::Sunrise, 0, "%H:%M").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn every_item_in_every_unit_style() {
+        use format::UnitStyle;
+
+        // (item, value, in metric, in imperial), each compact, spaced,
+        // symbolless and verbose.
+        let cases = [
+            (DataItem::Temp, 12.35, ["12°C", "12 °C", "12°", "12 degrees"], ["12°F", "12 °F", "12°", "12 degrees"]),
+            (DataItem::DewPoint, -0.4, ["0°C", "0 °C", "0°", "0 degrees"], ["0°F", "0 °F", "0°", "0 degrees"]),
+            (
+                DataItem::Wind,
+                5.66,
+                ["6m/s", "6 m/s", "6", "6 meters per second"],
+                ["6mph", "6 mph", "6", "6 miles per hour"],
+            ),
+            (
+                DataItem::WindGust,
+                1.0,
+                ["1m/s", "1 m/s", "1", "1 meter per second"],
+                ["1mph", "1 mph", "1", "1 mile per hour"],
+            ),
+            (
+                DataItem::Pressure,
+                1013.0,
+                ["1013hPa", "1013 hPa", "1013", "1013 hectopascals"],
+                ["29.91inHg", "29.91 inHg", "29.91", "29.91 inches of mercury"],
+            ),
+            (
+                DataItem::Visibility,
+                10000.0,
+                ["10km", "10 km", "10", "10 kilometers"],
+                ["6.2mi", "6.2 mi", "6.2", "6.2 miles"],
+            ),
+            (
+                DataItem::Visibility,
+                1000.0,
+                ["1.0km", "1.0 km", "1.0", "1.0 kilometers"],
+                ["0.6mi", "0.6 mi", "0.6", "0.6 miles"],
+            ),
+            (
+                DataItem::Rain,
+                0.5,
+                ["0.5mm/h", "0.5 mm/h", "0.5", "0.5 millimeters per hour"],
+                ["0.02in/h", "0.02 in/h", "0.02", "0.02 inches per hour"],
+            ),
+            (
+                DataItem::Snow,
+                0.0,
+                ["0mm/h", "0 mm/h", "0", "0 millimeters per hour"],
+                ["0in/h", "0 in/h", "0", "0 inches per hour"],
+            ),
+            (DataItem::Humidity, 81.0, ["81%"; 4], ["81%"; 4]),
+            (DataItem::Clouds, 75.0, ["75%"; 4], ["75%"; 4]),
+        ];
+        for (item, value, metric, imperial) in cases {
+            for (units, expected) in [(Units::Metric, metric), (Units::Imperial, imperial)] {
+                let rendered = UnitStyle::ALL.map(|style| render_number(item, value, units, style).unwrap());
+                assert_eq!(rendered, expected, "{} in {:?}", item.name(), units);
+            }
+        }
+
+        // The other temperatures join as the temperature does, and no other
+        // items render a number.
+        for info in item::ITEMS {
+            for units in [Units::Metric, Units::Imperial] {
+                for style in UnitStyle::ALL {
+                    let rendered = render_number(info.item, 12.35, units, style);
+                    let temp = render_number(DataItem::Temp, 12.35, units, style);
+                    if info.unit == item::Unit::Temperature {
+                        assert_eq!(rendered, temp, "{}", info.name);
+                    } else if !cases.iter().any(|&(item, ..)| item == info.item) {
+                        assert_eq!(rendered, None, "{}", info.name);
+                    }
+                }
+            }
+        }
+    }
 }
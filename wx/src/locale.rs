@@ -0,0 +1,538 @@
+//! Weekday, month and unit names in the languages descriptions can be
+//! fetched in.
+//!
+//! Languages are OpenWeatherMap's codes, as passed to
+//! [`ClientBuilder::lang`](crate::ClientBuilder::lang). Those without a table
+//! here get English names.
+
+use anyhow::{anyhow, Result};
+
+use crate::item::Unit;
+use crate::Units;
+
+/// The language codes OpenWeatherMap documents, as [`normalize`] writes
+/// them. The API answers in English for any other code, without an error.
+pub const LANGUAGES: &[&str] = &[
+    "af", "al", "ar", "az", "bg", "ca", "cz", "da", "de", "el", "en", "es", "eu", "fa", "fi", "fr",
+    "gl", "he", "hi", "hr", "hu", "id", "it", "ja", "kr", "la", "lt", "mk", "nl", "no", "pl", "pt",
+    "pt_br", "ro", "ru", "se", "sk", "sl", "sp", "sr", "sv", "th", "tr", "ua", "uk", "vi", "zh_cn",
+    "zh_tw", "zu",
+];
+
+/// Returns `lang` in lower case with underscores, as in "pt_br" for "pt-BR".
+pub fn normalize(lang: &str) -> String {
+    lang.trim().to_lowercase().replace('-', "_")
+}
+
+/// Returns `lang`, normalized, if OpenWeatherMap documents it, or an error
+/// that lists the codes it does.
+pub fn validate(lang: &str) -> Result<String> {
+    let code = normalize(lang);
+    if LANGUAGES.contains(&code.as_str()) {
+        return Ok(code);
+    }
+    Err(anyhow!(
+        "unknown language '{}': expected an OpenWeatherMap code, one of {}",
+        lang,
+        LANGUAGES.join(", ")
+    ))
+}
+
+/// Whether a name is abbreviated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Form {
+    /// "Tue", "Mar"
+    Short,
+    /// "Tuesday", "March"
+    Full,
+}
+
+/// Weekday names from Monday and month names from January, in one language.
+#[derive(Debug)]
+pub struct Names {
+    weekdays_short: [&'static str; 7],
+    weekdays: [&'static str; 7],
+    months_short: [&'static str; 12],
+    months: [&'static str; 12],
+}
+
+impl Names {
+    /// Returns the name of `weekday`, counted from Monday as 0.
+    pub fn weekday(&self, weekday: usize, form: Form) -> &'static str {
+        let names = match form {
+            Form::Short => &self.weekdays_short,
+            Form::Full => &self.weekdays,
+        };
+        names[weekday % 7]
+    }
+
+    /// Returns the name of `month`, counted from January as 1.
+    pub fn month(&self, month: u32, form: Form) -> &'static str {
+        let names = match form {
+            Form::Short => &self.months_short,
+            Form::Full => &self.months,
+        };
+        names[(month.clamp(1, 12) - 1) as usize]
+    }
+}
+
+/// Returns the names for `lang`, or English ones if there is no table for it.
+pub fn names(lang: &str) -> &'static Names {
+    match normalize(lang).as_str() {
+        "cz" | "cs" => &CZECH,
+        "da" => &DANISH,
+        "de" => &GERMAN,
+        "es" | "sp" => &SPANISH,
+        "fi" => &FINNISH,
+        "fr" => &FRENCH,
+        "it" => &ITALIAN,
+        "nl" => &DUTCH,
+        "no" => &NORWEGIAN,
+        "pl" => &POLISH,
+        "pt" | "pt_br" => &PORTUGUESE,
+        "ru" => &RUSSIAN,
+        "se" | "sv" => &SWEDISH,
+        "tr" => &TURKISH,
+        "ua" | "uk" => &UKRAINIAN,
+        _ => &ENGLISH,
+    }
+}
+
+/// Names of units, singular and plural, in one language.
+#[derive(Debug)]
+pub struct UnitNames {
+    celsius: [&'static str; 2],
+    fahrenheit: [&'static str; 2],
+    kelvin: [&'static str; 2],
+    percent: [&'static str; 2],
+    degrees: [&'static str; 2],
+    meters_per_second: [&'static str; 2],
+    miles_per_hour: [&'static str; 2],
+    kilometers: [&'static str; 2],
+    miles: [&'static str; 2],
+    minutes: [&'static str; 2],
+    hectopascals: [&'static str; 2],
+    inches_of_mercury: [&'static str; 2],
+    millimeters_per_hour: [&'static str; 2],
+    inches_per_hour: [&'static str; 2],
+}
+
+impl UnitNames {
+    /// Returns the name of `unit` in `units`, singular if `one`, or an empty
+    /// string for [`Unit::None`].
+    pub fn name(&self, unit: Unit, units: Units, one: bool) -> &'static str {
+        let names = match (unit, units) {
+            (Unit::None, _) => return "",
+            (Unit::Temperature, Units::Metric) => &self.celsius,
+            (Unit::Temperature, Units::Imperial) => &self.fahrenheit,
+            (Unit::Temperature, Units::Standard) => &self.kelvin,
+            (Unit::Percent, _) => &self.percent,
+            (Unit::Degrees, _) => &self.degrees,
+            (Unit::Speed, Units::Metric | Units::Standard) => &self.meters_per_second,
+            (Unit::Speed, Units::Imperial) => &self.miles_per_hour,
+            (Unit::Distance, Units::Metric | Units::Standard) => &self.kilometers,
+            (Unit::Distance, Units::Imperial) => &self.miles,
+            (Unit::Minutes, _) => &self.minutes,
+            (Unit::Pressure, Units::Metric | Units::Standard) => &self.hectopascals,
+            (Unit::Pressure, Units::Imperial) => &self.inches_of_mercury,
+            (Unit::Precipitation, Units::Metric | Units::Standard) => &self.millimeters_per_hour,
+            (Unit::Precipitation, Units::Imperial) => &self.inches_per_hour,
+        };
+        names[usize::from(!one)]
+    }
+}
+
+/// Returns the unit names for `lang`, or English ones if there is no table
+/// for it.
+pub fn unit_names(lang: &str) -> &'static UnitNames {
+    match normalize(lang).as_str() {
+        "de" => &GERMAN_UNITS,
+        "es" | "sp" => &SPANISH_UNITS,
+        "fr" => &FRENCH_UNITS,
+        "it" => &ITALIAN_UNITS,
+        "nl" => &DUTCH_UNITS,
+        "pt" | "pt_br" => &PORTUGUESE_UNITS,
+        _ => &ENGLISH_UNITS,
+    }
+}
+
+/// Returns the weekday at `epoch` unix seconds, `offset` seconds from UTC,
+/// counted from Monday as 0.
+pub fn weekday(epoch: i64, offset: i64) -> usize {
+    // 1 January 1970 was a Thursday.
+    (epoch.saturating_add(offset).div_euclid(86400) + 3).rem_euclid(7) as usize
+}
+
+/// Formats the local date at `epoch` unix seconds, `offset` seconds from UTC,
+/// as the short weekday, day of the month and short month, e.g. "Tue 05 Mar"
+/// in English or "Di 05 Mär" in German. Returns `None` if it is out of range.
+#[cfg(feature = "time")]
+pub fn date(epoch: i64, offset: i64, lang: &str) -> Option<String> {
+    use chrono::Datelike;
+
+    let date = crate::local_datetime(epoch, offset)?;
+    let names = names(lang);
+    Some(format!(
+        "{} {:02} {}",
+        names.weekday(date.weekday().num_days_from_monday() as usize, Form::Short),
+        date.day(),
+        names.month(date.month(), Form::Short)
+    ))
+}
+
+static ENGLISH: Names = Names {
+    weekdays_short: ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+    weekdays: ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"],
+    months_short: ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"],
+    months: [
+        "January", "February", "March", "April", "May", "June", "July", "August", "September", "October",
+        "November", "December",
+    ],
+};
+
+static CZECH: Names = Names {
+    weekdays_short: ["po", "út", "st", "čt", "pá", "so", "ne"],
+    weekdays: ["pondělí", "úterý", "středa", "čtvrtek", "pátek", "sobota", "neděle"],
+    months_short: ["led", "úno", "bře", "dub", "kvě", "čvn", "čvc", "srp", "zář", "říj", "lis", "pro"],
+    months: [
+        "leden", "únor", "březen", "duben", "květen", "červen", "červenec", "srpen", "září", "říjen", "listopad",
+        "prosinec",
+    ],
+};
+
+static DANISH: Names = Names {
+    weekdays_short: ["man", "tir", "ons", "tor", "fre", "lør", "søn"],
+    weekdays: ["mandag", "tirsdag", "onsdag", "torsdag", "fredag", "lørdag", "søndag"],
+    months_short: ["jan", "feb", "mar", "apr", "maj", "jun", "jul", "aug", "sep", "okt", "nov", "dec"],
+    months: [
+        "januar", "februar", "marts", "april", "maj", "juni", "juli", "august", "september", "oktober",
+        "november", "december",
+    ],
+};
+
+static GERMAN: Names = Names {
+    weekdays_short: ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+    weekdays: ["Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag", "Samstag", "Sonntag"],
+    months_short: ["Jan", "Feb", "Mär", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez"],
+    months: [
+        "Januar", "Februar", "März", "April", "Mai", "Juni", "Juli", "August", "September", "Oktober",
+        "November", "Dezember",
+    ],
+};
+
+static SPANISH: Names = Names {
+    weekdays_short: ["lun", "mar", "mié", "jue", "vie", "sáb", "dom"],
+    weekdays: ["lunes", "martes", "miércoles", "jueves", "viernes", "sábado", "domingo"],
+    months_short: ["ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sept", "oct", "nov", "dic"],
+    months: [
+        "enero", "febrero", "marzo", "abril", "mayo", "junio", "julio", "agosto", "septiembre", "octubre",
+        "noviembre", "diciembre",
+    ],
+};
+
+static FINNISH: Names = Names {
+    weekdays_short: ["ma", "ti", "ke", "to", "pe", "la", "su"],
+    weekdays: ["maanantai", "tiistai", "keskiviikko", "torstai", "perjantai", "lauantai", "sunnuntai"],
+    months_short: [
+        "tammi", "helmi", "maalis", "huhti", "touko", "kesä", "heinä", "elo", "syys", "loka", "marras", "joulu",
+    ],
+    months: [
+        "tammikuu", "helmikuu", "maaliskuu", "huhtikuu", "toukokuu", "kesäkuu", "heinäkuu", "elokuu", "syyskuu",
+        "lokakuu", "marraskuu", "joulukuu",
+    ],
+};
+
+static FRENCH: Names = Names {
+    weekdays_short: ["lun", "mar", "mer", "jeu", "ven", "sam", "dim"],
+    weekdays: ["lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche"],
+    months_short: ["janv", "févr", "mars", "avr", "mai", "juin", "juil", "août", "sept", "oct", "nov", "déc"],
+    months: [
+        "janvier", "février", "mars", "avril", "mai", "juin", "juillet", "août", "septembre", "octobre",
+        "novembre", "décembre",
+    ],
+};
+
+static ITALIAN: Names = Names {
+    weekdays_short: ["lun", "mar", "mer", "gio", "ven", "sab", "dom"],
+    weekdays: ["lunedì", "martedì", "mercoledì", "giovedì", "venerdì", "sabato", "domenica"],
+    months_short: ["gen", "feb", "mar", "apr", "mag", "giu", "lug", "ago", "set", "ott", "nov", "dic"],
+    months: [
+        "gennaio", "febbraio", "marzo", "aprile", "maggio", "giugno", "luglio", "agosto", "settembre", "ottobre",
+        "novembre", "dicembre",
+    ],
+};
+
+static DUTCH: Names = Names {
+    weekdays_short: ["ma", "di", "wo", "do", "vr", "za", "zo"],
+    weekdays: ["maandag", "dinsdag", "woensdag", "donderdag", "vrijdag", "zaterdag", "zondag"],
+    months_short: ["jan", "feb", "mrt", "apr", "mei", "jun", "jul", "aug", "sep", "okt", "nov", "dec"],
+    months: [
+        "januari", "februari", "maart", "april", "mei", "juni", "juli", "augustus", "september", "oktober",
+        "november", "december",
+    ],
+};
+
+static NORWEGIAN: Names = Names {
+    weekdays_short: ["man", "tir", "ons", "tor", "fre", "lør", "søn"],
+    weekdays: ["mandag", "tirsdag", "onsdag", "torsdag", "fredag", "lørdag", "søndag"],
+    months_short: ["jan", "feb", "mar", "apr", "mai", "jun", "jul", "aug", "sep", "okt", "nov", "des"],
+    months: [
+        "januar", "februar", "mars", "april", "mai", "juni", "juli", "august", "september", "oktober",
+        "november", "desember",
+    ],
+};
+
+static POLISH: Names = Names {
+    weekdays_short: ["pon", "wt", "śr", "czw", "pt", "sob", "niedz"],
+    weekdays: ["poniedziałek", "wtorek", "środa", "czwartek", "piątek", "sobota", "niedziela"],
+    months_short: ["sty", "lut", "mar", "kwi", "maj", "cze", "lip", "sie", "wrz", "paź", "lis", "gru"],
+    months: [
+        "styczeń", "luty", "marzec", "kwiecień", "maj", "czerwiec", "lipiec", "sierpień", "wrzesień",
+        "październik", "listopad", "grudzień",
+    ],
+};
+
+static PORTUGUESE: Names = Names {
+    weekdays_short: ["seg", "ter", "qua", "qui", "sex", "sáb", "dom"],
+    weekdays: [
+        "segunda-feira", "terça-feira", "quarta-feira", "quinta-feira", "sexta-feira", "sábado", "domingo",
+    ],
+    months_short: ["jan", "fev", "mar", "abr", "mai", "jun", "jul", "ago", "set", "out", "nov", "dez"],
+    months: [
+        "janeiro", "fevereiro", "março", "abril", "maio", "junho", "julho", "agosto", "setembro", "outubro",
+        "novembro", "dezembro",
+    ],
+};
+
+static RUSSIAN: Names = Names {
+    weekdays_short: ["пн", "вт", "ср", "чт", "пт", "сб", "вс"],
+    weekdays: ["понедельник", "вторник", "среда", "четверг", "пятница", "суббота", "воскресенье"],
+    months_short: ["янв", "фев", "мар", "апр", "май", "июн", "июл", "авг", "сен", "окт", "ноя", "дек"],
+    months: [
+        "январь", "февраль", "март", "апрель", "май", "июнь", "июль", "август", "сентябрь", "октябрь", "ноябрь",
+        "декабрь",
+    ],
+};
+
+static SWEDISH: Names = Names {
+    weekdays_short: ["mån", "tis", "ons", "tors", "fre", "lör", "sön"],
+    weekdays: ["måndag", "tisdag", "onsdag", "torsdag", "fredag", "lördag", "söndag"],
+    months_short: ["jan", "feb", "mars", "apr", "maj", "juni", "juli", "aug", "sep", "okt", "nov", "dec"],
+    months: [
+        "januari", "februari", "mars", "april", "maj", "juni", "juli", "augusti", "september", "oktober",
+        "november", "december",
+    ],
+};
+
+static TURKISH: Names = Names {
+    weekdays_short: ["Pzt", "Sal", "Çar", "Per", "Cum", "Cmt", "Paz"],
+    weekdays: ["Pazartesi", "Salı", "Çarşamba", "Perşembe", "Cuma", "Cumartesi", "Pazar"],
+    months_short: ["Oca", "Şub", "Mar", "Nis", "May", "Haz", "Tem", "Ağu", "Eyl", "Eki", "Kas", "Ara"],
+    months: [
+        "Ocak", "Şubat", "Mart", "Nisan", "Mayıs", "Haziran", "Temmuz", "Ağustos", "Eylül", "Ekim", "Kasım",
+        "Aralık",
+    ],
+};
+
+static UKRAINIAN: Names = Names {
+    weekdays_short: ["пн", "вт", "ср", "чт", "пт", "сб", "нд"],
+    weekdays: ["понеділок", "вівторок", "середа", "четвер", "пʼятниця", "субота", "неділя"],
+    months_short: ["січ", "лют", "бер", "кві", "тра", "чер", "лип", "сер", "вер", "жов", "лис", "гру"],
+    months: [
+        "січень", "лютий", "березень", "квітень", "травень", "червень", "липень", "серпень", "вересень",
+        "жовтень", "листопад", "грудень",
+    ],
+};
+
+static ENGLISH_UNITS: UnitNames = UnitNames {
+    celsius: ["degree Celsius", "degrees Celsius"],
+    fahrenheit: ["degree Fahrenheit", "degrees Fahrenheit"],
+    kelvin: ["kelvin", "kelvins"],
+    percent: ["percent", "percent"],
+    degrees: ["degree", "degrees"],
+    meters_per_second: ["meter per second", "meters per second"],
+    miles_per_hour: ["mile per hour", "miles per hour"],
+    kilometers: ["kilometer", "kilometers"],
+    miles: ["mile", "miles"],
+    minutes: ["minute", "minutes"],
+    hectopascals: ["hectopascal", "hectopascals"],
+    inches_of_mercury: ["inch of mercury", "inches of mercury"],
+    millimeters_per_hour: ["millimeter per hour", "millimeters per hour"],
+    inches_per_hour: ["inch per hour", "inches per hour"],
+};
+
+static GERMAN_UNITS: UnitNames = UnitNames {
+    celsius: ["Grad Celsius", "Grad Celsius"],
+    fahrenheit: ["Grad Fahrenheit", "Grad Fahrenheit"],
+    kelvin: ["Kelvin", "Kelvin"],
+    percent: ["Prozent", "Prozent"],
+    degrees: ["Grad", "Grad"],
+    meters_per_second: ["Meter pro Sekunde", "Meter pro Sekunde"],
+    miles_per_hour: ["Meile pro Stunde", "Meilen pro Stunde"],
+    kilometers: ["Kilometer", "Kilometer"],
+    miles: ["Meile", "Meilen"],
+    minutes: ["Minute", "Minuten"],
+    hectopascals: ["Hektopascal", "Hektopascal"],
+    inches_of_mercury: ["Zoll Quecksilbersäule", "Zoll Quecksilbersäule"],
+    millimeters_per_hour: ["Millimeter pro Stunde", "Millimeter pro Stunde"],
+    inches_per_hour: ["Zoll pro Stunde", "Zoll pro Stunde"],
+};
+
+static SPANISH_UNITS: UnitNames = UnitNames {
+    celsius: ["grado Celsius", "grados Celsius"],
+    fahrenheit: ["grado Fahrenheit", "grados Fahrenheit"],
+    kelvin: ["kelvin", "kelvin"],
+    percent: ["por ciento", "por ciento"],
+    degrees: ["grado", "grados"],
+    meters_per_second: ["metro por segundo", "metros por segundo"],
+    miles_per_hour: ["milla por hora", "millas por hora"],
+    kilometers: ["kilómetro", "kilómetros"],
+    miles: ["milla", "millas"],
+    minutes: ["minuto", "minutos"],
+    hectopascals: ["hectopascal", "hectopascales"],
+    inches_of_mercury: ["pulgada de mercurio", "pulgadas de mercurio"],
+    millimeters_per_hour: ["milímetro por hora", "milímetros por hora"],
+    inches_per_hour: ["pulgada por hora", "pulgadas por hora"],
+};
+
+static FRENCH_UNITS: UnitNames = UnitNames {
+    celsius: ["degré Celsius", "degrés Celsius"],
+    fahrenheit: ["degré Fahrenheit", "degrés Fahrenheit"],
+    kelvin: ["kelvin", "kelvins"],
+    percent: ["pour cent", "pour cent"],
+    degrees: ["degré", "degrés"],
+    meters_per_second: ["mètre par seconde", "mètres par seconde"],
+    miles_per_hour: ["mille par heure", "milles par heure"],
+    kilometers: ["kilomètre", "kilomètres"],
+    miles: ["mille", "milles"],
+    minutes: ["minute", "minutes"],
+    hectopascals: ["hectopascal", "hectopascals"],
+    inches_of_mercury: ["pouce de mercure", "pouces de mercure"],
+    millimeters_per_hour: ["millimètre par heure", "millimètres par heure"],
+    inches_per_hour: ["pouce par heure", "pouces par heure"],
+};
+
+static ITALIAN_UNITS: UnitNames = UnitNames {
+    celsius: ["grado Celsius", "gradi Celsius"],
+    fahrenheit: ["grado Fahrenheit", "gradi Fahrenheit"],
+    kelvin: ["kelvin", "kelvin"],
+    percent: ["per cento", "per cento"],
+    degrees: ["grado", "gradi"],
+    meters_per_second: ["metro al secondo", "metri al secondo"],
+    miles_per_hour: ["miglio orario", "miglia orarie"],
+    kilometers: ["chilometro", "chilometri"],
+    miles: ["miglio", "miglia"],
+    minutes: ["minuto", "minuti"],
+    hectopascals: ["ettopascal", "ettopascal"],
+    inches_of_mercury: ["pollice di mercurio", "pollici di mercurio"],
+    millimeters_per_hour: ["millimetro all'ora", "millimetri all'ora"],
+    inches_per_hour: ["pollice all'ora", "pollici all'ora"],
+};
+
+static DUTCH_UNITS: UnitNames = UnitNames {
+    celsius: ["graad Celsius", "graden Celsius"],
+    fahrenheit: ["graad Fahrenheit", "graden Fahrenheit"],
+    kelvin: ["kelvin", "kelvin"],
+    percent: ["procent", "procent"],
+    degrees: ["graad", "graden"],
+    meters_per_second: ["meter per seconde", "meter per seconde"],
+    miles_per_hour: ["mijl per uur", "mijl per uur"],
+    kilometers: ["kilometer", "kilometer"],
+    miles: ["mijl", "mijl"],
+    minutes: ["minuut", "minuten"],
+    hectopascals: ["hectopascal", "hectopascal"],
+    inches_of_mercury: ["inch kwik", "inch kwik"],
+    millimeters_per_hour: ["millimeter per uur", "millimeter per uur"],
+    inches_per_hour: ["inch per uur", "inch per uur"],
+};
+
+static PORTUGUESE_UNITS: UnitNames = UnitNames {
+    celsius: ["grau Celsius", "graus Celsius"],
+    fahrenheit: ["grau Fahrenheit", "graus Fahrenheit"],
+    kelvin: ["kelvin", "kelvins"],
+    percent: ["por cento", "por cento"],
+    degrees: ["grau", "graus"],
+    meters_per_second: ["metro por segundo", "metros por segundo"],
+    miles_per_hour: ["milha por hora", "milhas por hora"],
+    kilometers: ["quilômetro", "quilômetros"],
+    miles: ["milha", "milhas"],
+    minutes: ["minuto", "minutos"],
+    hectopascals: ["hectopascal", "hectopascais"],
+    inches_of_mercury: ["polegada de mercúrio", "polegadas de mercúrio"],
+    millimeters_per_hour: ["milímetro por hora", "milímetros por hora"],
+    inches_per_hour: ["polegada por hora", "polegadas por hora"],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tuesday 5 March 2024, 12:00 UTC.
+    const TUESDAY_NOON: i64 = 1_709_640_000;
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn dates_in_their_language() {
+        let cases = [
+            ("de", "Di 05 Mär"),
+            ("fr", "mar 05 mars"),
+            ("en", "Tue 05 Mar"),
+            ("es", "mar 05 mar"),
+            ("pt-BR", "ter 05 mar"),
+            ("FR", "mar 05 mars"),
+            // Languages without a table get English names.
+            ("ja", "Tue 05 Mar"),
+            ("xx", "Tue 05 Mar"),
+        ];
+        for (lang, expected) in cases {
+            assert_eq!(date(TUESDAY_NOON, 0, lang).as_deref(), Some(expected), "{}", lang);
+        }
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn dates_are_local() {
+        // 23:00 on Monday at UTC-13, and 02:00 on Wednesday at UTC+14.
+        assert_eq!(date(TUESDAY_NOON, -13 * 3600, "de").as_deref(), Some("Mo 04 Mär"));
+        assert_eq!(date(TUESDAY_NOON, 14 * 3600, "fr").as_deref(), Some("mer 06 mars"));
+        assert_eq!(date(i64::MAX, 0, "en"), None);
+    }
+
+    #[test]
+    fn short_and_full_names() {
+        let german = names("de");
+        assert_eq!(german.weekday(weekday(TUESDAY_NOON, 0), Form::Short), "Di");
+        assert_eq!(german.weekday(weekday(TUESDAY_NOON, 0), Form::Full), "Dienstag");
+        assert_eq!(german.month(3, Form::Full), "März");
+        let french = names("fr");
+        assert_eq!(french.weekday(1, Form::Full), "mardi");
+        assert_eq!(french.month(3, Form::Short), "mars");
+        assert_eq!(french.month(12, Form::Full), "décembre");
+        // Out of range counts wrap or clamp rather than panic.
+        assert_eq!(names("en").weekday(8, Form::Short), "Tue");
+        assert_eq!(names("en").month(0, Form::Short), "Jan");
+        assert_eq!(names("en").month(13, Form::Short), "Dec");
+    }
+
+    #[test]
+    fn weekdays_from_monday() {
+        // 1 January 1970, a Thursday, and the days around it.
+        assert_eq!(weekday(0, 0), 3);
+        assert_eq!(weekday(-1, 0), 2);
+        assert_eq!(weekday(86400 * 4, 0), 0);
+        assert_eq!(weekday(TUESDAY_NOON, 0), 1);
+    }
+
+    #[test]
+    fn every_table_is_whole() {
+        for lang in LANGUAGES {
+            let names = names(lang);
+            for n in 0..7 {
+                assert!(!names.weekday(n, Form::Short).is_empty() && !names.weekday(n, Form::Full).is_empty());
+            }
+            for month in 1..=12 {
+                assert!(!names.month(month, Form::Short).is_empty() && !names.month(month, Form::Full).is_empty());
+            }
+        }
+    }
+}
@@ -0,0 +1,313 @@
+//! One Call API 3.0. This needs a separate "One Call by Call" subscription on
+//! OpenWeatherMap, so it is behind the `onecall` feature.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::request::{RequestSpec, Selector};
+use crate::{format, local_datetime, uv, Client, Precipitation, Weather};
+
+/// Blocks of the One Call response that can be excluded from the request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Part {
+    Current,
+    Minutely,
+    Hourly,
+    Daily,
+    Alerts,
+}
+
+impl Part {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Part::Current => "current",
+            Part::Minutely => "minutely",
+            Part::Hourly => "hourly",
+            Part::Daily => "daily",
+            Part::Alerts => "alerts",
+        }
+    }
+}
+
+/// A One Call response. Parts excluded from the request are empty.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OneCall {
+    /// Geo location, latitude
+    lat: f64,
+    /// Geo location, longitude
+    lon: f64,
+    /// Timezone name, as in "Europe/Berlin"
+    #[serde(default)]
+    timezone: String,
+    /// Shift in seconds from UTC
+    timezone_offset: i64,
+    #[serde(default)]
+    current: Option<Current>,
+    #[serde(default)]
+    minutely: Option<Vec<Minutely>>,
+    #[serde(default)]
+    hourly: Option<Vec<Hourly>>,
+    #[serde(default)]
+    daily: Option<Vec<Daily>>,
+    #[serde(default)]
+    alerts: Option<Vec<Alert>>,
+}
+
+/// The current weather, in a One Call response.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Current {
+    /// Time of data calculation, unix, UTC
+    pub dt: i64,
+    /// Sunrise time, unix, UTC; absent during polar day and night
+    #[serde(default)]
+    pub sunrise: Option<i64>,
+    /// Sunset time, unix, UTC
+    #[serde(default)]
+    pub sunset: Option<i64>,
+    /// Temperature
+    pub temp: f64,
+    /// Temperature accounting for human perception
+    pub feels_like: f64,
+    /// Atmospheric pressure at sea level, hPa
+    pub pressure: f64,
+    /// Humidity, %
+    pub humidity: f64,
+    /// Temperature below which dew forms
+    pub dew_point: f64,
+    /// UV index
+    pub uvi: f64,
+    /// Cloudiness, %
+    pub clouds: f64,
+    /// Visibility, meter, maximum is 10km
+    #[serde(default)]
+    pub visibility: Option<f64>,
+    /// Wind speed
+    pub wind_speed: f64,
+    /// Wind direction, degrees (meteorological)
+    pub wind_deg: f64,
+    /// Wind gust
+    #[serde(default)]
+    pub wind_gust: Option<f64>,
+    #[serde(default)]
+    pub weather: Vec<Weather>,
+    /// Rain, absent when it does not rain
+    #[serde(default)]
+    pub rain: Option<Precipitation>,
+    /// Snow, absent when it does not snow
+    #[serde(default)]
+    pub snow: Option<Precipitation>,
+}
+
+/// Precipitation in one minute of the next hour.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Minutely {
+    /// Time of the forecasted data, unix, UTC
+    pub dt: i64,
+    /// Precipitation, mm/h
+    pub precipitation: f64,
+}
+
+/// The forecast for one of the next 48 hours.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Hourly {
+    /// Time of the forecasted data, unix, UTC
+    pub dt: i64,
+    pub temp: f64,
+    pub feels_like: f64,
+    /// Atmospheric pressure at sea level, hPa
+    pub pressure: f64,
+    /// Humidity, %
+    pub humidity: f64,
+    pub dew_point: f64,
+    /// UV index
+    pub uvi: f64,
+    /// Cloudiness, %
+    pub clouds: f64,
+    /// Visibility, meter, maximum is 10km
+    #[serde(default)]
+    pub visibility: Option<f64>,
+    pub wind_speed: f64,
+    pub wind_deg: f64,
+    #[serde(default)]
+    pub wind_gust: Option<f64>,
+    #[serde(default)]
+    pub weather: Vec<Weather>,
+    /// Probability of precipitation, from 0 to 1
+    #[serde(default)]
+    pub pop: f64,
+    #[serde(default)]
+    pub rain: Option<Precipitation>,
+    #[serde(default)]
+    pub snow: Option<Precipitation>,
+}
+
+/// The forecast for one of the next 8 days.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Daily {
+    /// Noon of the day, unix, UTC
+    pub dt: i64,
+    #[serde(default)]
+    pub sunrise: Option<i64>,
+    #[serde(default)]
+    pub sunset: Option<i64>,
+    #[serde(default)]
+    pub moonrise: Option<i64>,
+    #[serde(default)]
+    pub moonset: Option<i64>,
+    /// Moon phase: 0 and 1 are new moon, 0.5 full moon
+    #[serde(default)]
+    pub moon_phase: f64,
+    /// Description of the day's weather, in English
+    #[serde(default)]
+    pub summary: Option<String>,
+    pub temp: DailyTemp,
+    pub feels_like: DailyFeelsLike,
+    /// Atmospheric pressure at sea level, hPa
+    pub pressure: f64,
+    /// Humidity, %
+    pub humidity: f64,
+    pub dew_point: f64,
+    pub wind_speed: f64,
+    pub wind_deg: f64,
+    #[serde(default)]
+    pub wind_gust: Option<f64>,
+    #[serde(default)]
+    pub weather: Vec<Weather>,
+    /// Cloudiness, %
+    pub clouds: f64,
+    /// Probability of precipitation, from 0 to 1
+    #[serde(default)]
+    pub pop: f64,
+    /// Rain, mm
+    #[serde(default)]
+    pub rain: Option<f64>,
+    /// Snow, mm
+    #[serde(default)]
+    pub snow: Option<f64>,
+    /// Highest UV index of the day
+    pub uvi: f64,
+}
+
+/// The temperatures of a day.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct DailyTemp {
+    pub morn: f64,
+    pub day: f64,
+    pub eve: f64,
+    pub night: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// How warm a day feels.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct DailyFeelsLike {
+    pub morn: f64,
+    pub day: f64,
+    pub eve: f64,
+    pub night: f64,
+}
+
+/// A weather alert from a national agency.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Alert {
+    pub sender_name: String,
+    pub event: String,
+    /// Start and end of the alert, unix, UTC
+    pub start: i64,
+    pub end: i64,
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl OneCall {
+    /// Returns the local time from which the UV index stays below `threshold`
+    /// for the rest of today, or an empty string if it already does.
+    pub fn uv_safe_until(&self, threshold: f64, now: i64) -> String {
+        uv::uv_safe_until(&self.hourly_uvi(), threshold, now, self.timezone_offset)
+            .map(|dt| self.local_time(dt))
+            .unwrap_or_default()
+    }
+
+    /// Returns today's highest UV index and its local time, e.g. "7.2 @13:00".
+    pub fn uv_peak(&self, now: i64) -> String {
+        uv::uv_peak(&self.hourly_uvi(), now, self.timezone_offset)
+            .map(|(dt, uvi)| format!("{} @{}", format::fixed(uvi, 1), self.local_time(dt)))
+            .unwrap_or_default()
+    }
+
+    /// Returns the current weather, unless it was excluded.
+    pub fn current(&self) -> Option<&Current> {
+        self.current.as_ref()
+    }
+
+    /// Returns the precipitation minute by minute for the next hour.
+    pub fn minutely(&self) -> &[Minutely] {
+        self.minutely.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the hourly forecast for the next 48 hours.
+    pub fn hourly(&self) -> &[Hourly] {
+        self.hourly.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the daily forecast, today first.
+    pub fn daily(&self) -> &[Daily] {
+        self.daily.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the weather alerts for the location.
+    pub fn alerts(&self) -> &[Alert] {
+        self.alerts.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the forecast for the local day of `now`, if the response has
+    /// it.
+    pub fn today(&self, now: i64) -> Option<&Daily> {
+        let day = |epoch: i64| epoch.saturating_add(self.timezone_offset).div_euclid(86400);
+        self.daily().iter().find(|daily| day(daily.dt) == day(now))
+    }
+
+    /// Returns the location's timezone name and shift from UTC in seconds.
+    pub fn timezone(&self) -> (&str, i64) {
+        (&self.timezone, self.timezone_offset)
+    }
+
+    fn hourly_uvi(&self) -> Vec<(i64, f64)> {
+        self.hourly().iter().map(|h| (h.dt, h.uvi)).collect()
+    }
+
+    fn local_time(&self, epoch: i64) -> String {
+        local_datetime(epoch, self.timezone_offset)
+            .map(|dt| dt.format("%H:%M").to_string())
+            .unwrap_or_default()
+    }
+}
+
+impl Client {
+    /// Fetches One Call data for the given coordinates, leaving out the
+    /// `exclude`d parts.
+    pub fn one_call(&self, (lat, lon): (f64, f64), exclude: &[Part]) -> Result<OneCall> {
+        let exclude = exclude
+            .iter()
+            .map(Part::as_str)
+            .collect::<Vec<&str>>()
+            .join(",");
+        let mut spec = RequestSpec::new("data/3.0/onecall", Selector::Coords { lat, lon });
+        if !exclude.is_empty() {
+            spec = spec.param("exclude", &exclude)?;
+        }
+        self.get_parsed(&spec)
+    }
+
+    /// Fetches the current UV index at the given coordinates, with only the
+    /// current part of One Call.
+    pub fn uvi(&self, coords: (f64, f64)) -> Result<f64> {
+        let one_call = self.one_call(coords, &[Part::Minutely, Part::Hourly, Part::Daily, Part::Alerts])?;
+        match one_call.current() {
+            Some(current) => Ok(current.uvi),
+            None => anyhow::bail!("no current weather in the One Call response"),
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! How strictly responses are parsed. Some uses want any response of an
+//! unexpected shape to be an error, so that it gets noticed; others want
+//! whatever can be made of it.
+
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{de, Clouds, Coord, CurrentWeather, Main, Wind};
+
+/// How strictly to parse a response.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Fails on a field the data structures do not have, and on a field they
+    /// have that the response leaves out, even one with a default, unless it
+    /// is optional, like `wind.gust`.
+    Strict,
+    /// Takes fields that are left out, null or of the other JSON type (a
+    /// number as a string, say) as long as rendering can do without them,
+    /// and ignores unknown fields.
+    #[default]
+    Tolerant,
+    /// Like [`ParseMode::Tolerant`], but a current weather response that
+    /// still does not parse is read field by field: what can be read is
+    /// kept, and the rest is listed by [`CurrentWeather::missing`]. Items
+    /// missing what they render from are not rendered: temp, feels_like,
+    /// humidity and wind need their own field, wind_deg `wind.deg`,
+    /// obs_time, dt and obs_age `dt`, icon and description `weather`, and
+    /// sunrise and sunset `sys`. Other responses are parsed as in
+    /// [`ParseMode::Tolerant`].
+    Salvage,
+}
+
+/// Parses the JSON response `json` into `T` in `mode`.
+pub fn from_json<T: DeserializeOwned + Serialize>(json: &str, mode: ParseMode) -> Result<T> {
+    match mode {
+        ParseMode::Strict => {
+            let value: Value = serde_json::from_str(json)?;
+            let parsed = T::deserialize(&value)?;
+            compare(&value, &serde_json::to_value(&parsed)?, "")?;
+            Ok(parsed)
+        }
+        ParseMode::Tolerant | ParseMode::Salvage => Ok(serde_json::from_str(json)?),
+    }
+}
+
+/// Checks that `input` has the fields of `parsed`, the same input parsed and
+/// serialized again, and no others. Fields that are null in `parsed` are
+/// optional.
+fn compare(input: &Value, parsed: &Value, path: &str) -> Result<()> {
+    let field = |key: &str| match path {
+        "" => key.to_string(),
+        _ => format!("{}.{}", path, key),
+    };
+    match (input, parsed) {
+        (Value::Object(input), Value::Object(parsed)) => {
+            if let Some(key) = input.keys().find(|key| !parsed.contains_key(*key)) {
+                return Err(anyhow!("unknown field `{}`", field(key)));
+            }
+            let required = |(key, value): &(&String, &Value)| !value.is_null() && !input.contains_key(*key);
+            if let Some((key, _)) = parsed.iter().find(required) {
+                return Err(anyhow!("missing field `{}`", field(key)));
+            }
+            for (key, value) in input {
+                compare(value, &parsed[key], &field(key))?;
+            }
+        }
+        (Value::Array(input), Value::Array(parsed)) => {
+            for (n, (input, parsed)) in input.iter().zip(parsed).enumerate() {
+                compare(input, parsed, &format!("{}[{}]", path, n))?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+impl CurrentWeather {
+    /// Parses a current weather response in `mode`.
+    pub fn from_json(json: &str, mode: ParseMode) -> Result<Self> {
+        match (from_json(json, mode), mode) {
+            (Err(_), ParseMode::Salvage) => {
+                let value: Value = serde_json::from_str(json)?;
+                if !value.is_object() {
+                    return Err(anyhow!("expected an object, got {}", value));
+                }
+                Ok(CurrentWeather::salvage(&value))
+            }
+            (result, _) => result,
+        }
+    }
+
+    /// Returns the fields, with tables joined by dots, that could not be
+    /// read from a response parsed with [`ParseMode::Salvage`]; empty for a
+    /// response that parsed as it is.
+    pub fn missing(&self) -> &[&'static str] {
+        &self.missing
+    }
+
+    /// Returns whether `field`, with tables joined by dots, was read from the
+    /// response.
+    pub fn is_present(&self, field: &str) -> bool {
+        !self.missing.contains(&field)
+    }
+
+    /// Reads what can be read from `value`, field by field.
+    fn salvage(value: &Value) -> Self {
+        let mut missing = Vec::new();
+        let mut number = |field: &'static str| {
+            let n = optional_number(value, field);
+            if n.is_none() {
+                missing.push(field);
+            }
+            n
+        };
+        let main = Main {
+            temp: number("main.temp").unwrap_or_default(),
+            feels_like: number("main.feels_like").unwrap_or_default(),
+            pressure: number("main.pressure"),
+            humidity: number("main.humidity").unwrap_or_default(),
+            temp_min: number("main.temp_min"),
+            temp_max: number("main.temp_max"),
+            sea_level: optional_number(value, "main.sea_level"),
+            grnd_level: optional_number(value, "main.grnd_level"),
+        };
+        let wind = Wind {
+            speed: number("wind.speed"),
+            deg: optional_number(value, "wind.deg"),
+            gust: value.pointer("/wind/gust").and_then(Value::as_f64),
+        };
+        let clouds = Clouds {
+            all: number("clouds.all"),
+        };
+        let visibility = optional_number(value, "visibility");
+        let dt = number("dt").unwrap_or_default() as i64;
+        let timezone = number("timezone").unwrap_or_default() as i64;
+        let id = number("id").unwrap_or_default() as u64;
+        if wind.deg.is_none() {
+            missing.push("wind.deg");
+        }
+        let coord = match (value.pointer("/coord/lat"), value.pointer("/coord/lon")) {
+            (Some(lat), Some(lon)) => lat.as_f64().zip(lon.as_f64()).map(|(lat, lon)| Coord { lat, lon }),
+            _ => None,
+        };
+        if coord.is_none() {
+            missing.push("coord");
+        }
+        let weather: Vec<_> = value
+            .get("weather")
+            .and_then(Value::as_array)
+            .map(|list| list.iter().filter_map(|w| Deserialize::deserialize(w).ok()).collect())
+            .unwrap_or_default();
+        if weather.is_empty() {
+            missing.push("weather");
+        }
+        let sys = part(value, "sys", &mut missing);
+        let base = part(value, "base", &mut missing);
+        let name: Option<String> = part(value, "name", &mut missing);
+        let cod = value.get("cod").map(|v| de::string_or_number_as_string(v).unwrap_or_default());
+        CurrentWeather {
+            coord,
+            weather,
+            base,
+            main,
+            visibility,
+            wind,
+            clouds,
+            rain: value.get("rain").and_then(|v| Deserialize::deserialize(v).ok()),
+            snow: value.get("snow").and_then(|v| Deserialize::deserialize(v).ok()),
+            dt,
+            sys,
+            timezone,
+            id,
+            name: name.filter(|name| !name.is_empty()),
+            cod: cod.unwrap_or_default(),
+            missing,
+        }
+    }
+}
+
+/// Reads the number at `field` of `value`, with tables joined by dots.
+fn optional_number(value: &Value, field: &str) -> Option<f64> {
+    value
+        .pointer(&format!("/{}", field.replace('.', "/")))
+        .filter(|v| !v.is_null())
+        .and_then(|v| de::number(v).ok())
+}
+
+/// Reads `field` of `value`, or adds it to `missing` and returns the default.
+fn part<T: DeserializeOwned + Default>(
+    value: &Value,
+    field: &'static str,
+    missing: &mut Vec<&'static str>,
+) -> T {
+    match value.get(field).map(T::deserialize) {
+        Some(Ok(part)) => part,
+        _ => {
+            missing.push(field);
+            T::default()
+        }
+    }
+}
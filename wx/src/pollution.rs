@@ -0,0 +1,270 @@
+//! Air pollution now and forecast, the air quality index, and the best
+//! window to air a room.
+
+use serde::{Deserialize, Serialize};
+
+use crate::de;
+
+#[cfg(feature = "http")]
+use anyhow::Result;
+
+#[cfg(feature = "http")]
+use crate::request::{RequestSpec, Selector};
+#[cfg(feature = "http")]
+use crate::Client;
+#[cfg(feature = "time")]
+use crate::local_datetime;
+
+/// Endpoint of the air pollution forecast.
+pub const FORECAST_ENDPOINT: &str = "data/2.5/air_pollution/forecast";
+/// Endpoint of the current air pollution.
+pub const CURRENT_ENDPOINT: &str = "data/2.5/air_pollution";
+
+/// Names of the levels of the air quality index, from 1 to 5.
+const AQI_NAMES: [&str; 5] = ["Good", "Fair", "Moderate", "Poor", "Very Poor"];
+
+/// Shortest and longest window suggested by [`ventilation_window`], in hours.
+const WINDOW_HOURS: (usize, usize) = (2, 3);
+/// How far ahead [`ventilation_window`] looks, in hours.
+const HORIZON_HOURS: i64 = 24;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AirPollutionForecast {
+    /// Hourly forecast, in chronological order
+    #[serde(default, deserialize_with = "de::null_as_default")]
+    list: Vec<Pollution>,
+}
+
+/// The current air pollution, as `data/2.5/air_pollution` returns it.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AirPollution {
+    /// A single reading, for now
+    #[serde(default, deserialize_with = "de::null_as_default")]
+    list: Vec<Pollution>,
+}
+
+impl AirPollution {
+    /// Returns the reading, or `None` if the response had none.
+    pub fn current(&self) -> Option<&Pollution> {
+        self.list.first()
+    }
+}
+
+/// Air pollution at one time.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Pollution {
+    /// Time of the data, unix, UTC
+    pub dt: i64,
+    main: Aqi,
+    pub components: Components,
+}
+
+impl Pollution {
+    /// Returns the air quality index, from 1 (good) to 5 (very poor).
+    pub fn aqi(&self) -> u8 {
+        self.main.aqi
+    }
+
+    /// Returns the name of the air quality index, from "Good" to "Very
+    /// Poor", or `None` for a level outside 1 to 5.
+    pub fn aqi_name(&self) -> Option<&'static str> {
+        AQI_NAMES.get(usize::from(self.main.aqi).checked_sub(1)?).copied()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+struct Aqi {
+    /// Air quality index, 1 to 5
+    aqi: u8,
+}
+
+/// Concentrations of pollutants, in μg/m3.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Components {
+    /// CO (carbon monoxide)
+    #[serde(default, deserialize_with = "de::number_or_string_as_f64")]
+    pub co: f64,
+    /// NO2 (nitrogen dioxide)
+    #[serde(default, deserialize_with = "de::number_or_string_as_f64")]
+    pub no2: f64,
+    /// O3 (ozone)
+    #[serde(default, deserialize_with = "de::number_or_string_as_f64")]
+    pub o3: f64,
+    /// SO2 (sulphur dioxide)
+    #[serde(default, deserialize_with = "de::number_or_string_as_f64")]
+    pub so2: f64,
+    /// PM2.5 (fine particles)
+    #[serde(default, deserialize_with = "de::number_or_string_as_f64")]
+    pub pm2_5: f64,
+    /// PM10 (coarse particles)
+    #[serde(default, deserialize_with = "de::number_or_string_as_f64")]
+    pub pm10: f64,
+}
+
+impl AirPollutionForecast {
+    /// Returns the coming block of a few hours with the cleanest air, in the
+    /// local time of a location `offset` seconds from UTC, as e.g.
+    /// "best air 14:00–16:00". Empty if the forecast has too few hours.
+    #[cfg(feature = "time")]
+    pub fn ventilation_window(&self, now: i64, offset: i64) -> String {
+        let clock = |dt| {
+            local_datetime(dt, offset)
+                .map(|t| t.format("%H:%M").to_string())
+                .unwrap_or_default()
+        };
+        let hourly: Vec<(i64, f64)> = self
+            .list
+            .iter()
+            .map(|p| (p.dt, p.components.pm2_5 + p.components.no2))
+            .collect();
+        ventilation_window(&hourly, now)
+            .map(|(start, end)| format!("best air {}–{}", clock(start), clock(end)))
+            .unwrap_or_default()
+    }
+}
+
+/// Finds the block of two to three consecutive hours with the lowest average
+/// pollution within the coming day, as `(start, end)` unix times.
+///
+/// `hourly` holds `(time, pollution)` pairs in chronological order, one per
+/// hour; pollution is any measure where lower is better, e.g. PM2.5 plus NO2
+/// in μg/m3. Hours that have already passed are skipped. The earliest block
+/// wins ties, and the longer one if they start at the same time.
+pub fn ventilation_window(hourly: &[(i64, f64)], now: i64) -> Option<(i64, i64)> {
+    let ahead: Vec<(i64, f64)> = hourly
+        .iter()
+        .filter(|(dt, _)| dt.saturating_add(3600) > now && *dt < now.saturating_add(HORIZON_HOURS * 3600))
+        .copied()
+        .collect();
+
+    let mut best: Option<(f64, i64, i64)> = None;
+    for start in 0..ahead.len() {
+        for hours in (WINDOW_HOURS.0..=WINDOW_HOURS.1).rev() {
+            let Some(block) = ahead.get(start..start + hours) else {
+                continue;
+            };
+            let average = block.iter().map(|(_, p)| p).sum::<f64>() / hours as f64;
+            let end = block[hours - 1].0.saturating_add(3600);
+            if best.is_none_or(|(lowest, _, _)| average < lowest) {
+                best = Some((average, block[0].0, end));
+            }
+        }
+    }
+    best.map(|(_, start, end)| (start, end))
+}
+
+#[cfg(feature = "http")]
+impl Client {
+    /// Fetches the hourly air pollution forecast for the given coordinates.
+    pub fn air_pollution_forecast(&self, (lat, lon): (f64, f64)) -> Result<AirPollutionForecast> {
+        self.get_parsed(&RequestSpec::new(FORECAST_ENDPOINT, Selector::Coords { lat, lon }))
+    }
+
+    /// Fetches the current air pollution for the given coordinates.
+    pub fn air_pollution(&self, (lat, lon): (f64, f64)) -> Result<AirPollution> {
+        self.get_parsed(&RequestSpec::new(CURRENT_ENDPOINT, Selector::Coords { lat, lon }))
+    }
+}
+
+/// Fetches the current air pollution for the given coordinates. This is a
+/// shortcut for building a [`Client`] and calling [`Client::air_pollution`].
+#[cfg(feature = "http")]
+pub fn get_air_pollution(lat: f64, lon: f64, key: &str) -> Result<AirPollution> {
+    Client::builder().api_key(key).build()?.air_pollution((lat, lon))
+}
+
+/// Fetches the air pollution forecast for the given coordinates. This is a
+/// shortcut for building a [`Client`] and calling
+/// [`Client::air_pollution_forecast`].
+#[cfg(feature = "http")]
+pub fn get_air_pollution_forecast(lat: f64, lon: f64, key: &str) -> Result<AirPollutionForecast> {
+    Client::builder()
+        .api_key(key)
+        .build()?
+        .air_pollution_forecast((lat, lon))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// 1 May 2024, 10:00 UTC.
+    const HOUR_0: i64 = 1_714_557_600;
+
+    /// Returns hourly pairs from [`HOUR_0`] on, with `pollution`.
+    fn hourly(pollution: &[f64]) -> Vec<(i64, f64)> {
+        pollution.iter().enumerate().map(|(hour, &p)| (HOUR_0 + hour as i64 * 3600, p)).collect()
+    }
+
+    fn hours(start: i64, end: i64) -> Option<(i64, i64)> {
+        Some((HOUR_0 + start * 3600, HOUR_0 + end * 3600))
+    }
+
+    #[test]
+    fn the_cleanest_block_is_found() {
+        assert_eq!(ventilation_window(&hourly(&[10.0, 5.0, 4.0, 6.0, 20.0, 1.0]), HOUR_0), hours(1, 3));
+        // Three clean hours average lower than two, if they are cleaner.
+        assert_eq!(ventilation_window(&hourly(&[9.0, 2.0, 3.0, 2.0, 9.0]), HOUR_0), hours(1, 4));
+    }
+
+    #[test]
+    fn ties_go_to_the_earliest_and_longest_block() {
+        assert_eq!(ventilation_window(&hourly(&[5.0; 6]), HOUR_0), hours(0, 3));
+        assert_eq!(ventilation_window(&hourly(&[9.0, 1.0, 1.0, 9.0, 1.0, 1.0]), HOUR_0), hours(1, 3));
+    }
+
+    #[test]
+    fn hours_passed_and_beyond_a_day_are_left_out() {
+        let pollution = [1.0, 1.0, 8.0, 9.0, 8.0, 9.0];
+        // The hour under way still counts, the one before does not.
+        assert_eq!(ventilation_window(&hourly(&pollution), HOUR_0 + 3600 + 1800), hours(1, 3));
+        assert_eq!(ventilation_window(&hourly(&pollution), HOUR_0 + 2 * 3600), hours(2, 5));
+        let mut far = vec![9.0; 24];
+        far.extend([1.0, 1.0]);
+        assert_eq!(ventilation_window(&hourly(&far), HOUR_0), hours(0, 3));
+    }
+
+    #[test]
+    fn too_few_hours_have_no_window() {
+        assert_eq!(ventilation_window(&[], HOUR_0), None);
+        assert_eq!(ventilation_window(&hourly(&[1.0]), HOUR_0), None);
+        assert_eq!(ventilation_window(&hourly(&[1.0, 1.0]), HOUR_0 + 3600), None);
+        assert_eq!(ventilation_window(&hourly(&[1.0, 1.0]), HOUR_0), hours(0, 2));
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn windows_are_in_local_time() {
+        // PM2.5 plus NO2 is 40, 5, 6, 15 and 41, given as numbers or strings.
+        let components = [json!([10.0, 30.0]), json!([2.0, 3.0]), json!([1.5, "4.5"]), json!([6, 9]), json!([40, 1])];
+        let list: Vec<_> = components
+            .iter()
+            .enumerate()
+            .map(|(hour, c)| {
+                let components = json!({"pm2_5": c[0], "no2": c[1]});
+                json!({"dt": HOUR_0 + hour as i64 * 3600, "main": {"aqi": 2}, "components": components})
+            })
+            .collect();
+        let forecast: AirPollutionForecast = serde_json::from_value(json!({ "list": list })).unwrap();
+        assert_eq!(forecast.ventilation_window(HOUR_0, 7200), "best air 13:00–15:00");
+        assert_eq!(forecast.ventilation_window(HOUR_0, -4 * 3600), "best air 07:00–09:00");
+        let empty: AirPollutionForecast = serde_json::from_value(json!({ "list": null })).unwrap();
+        assert_eq!(empty.ventilation_window(HOUR_0, 7200), "");
+    }
+
+    #[test]
+    fn aqi_names() {
+        let reading = |aqi| {
+            let json = json!({"list": [{"dt": HOUR_0, "main": {"aqi": aqi}, "components": {}}]});
+            serde_json::from_value::<AirPollution>(json).unwrap()
+        };
+        assert_eq!(reading(1).current().unwrap().aqi_name(), Some("Good"));
+        assert_eq!(reading(5).current().unwrap().aqi_name(), Some("Very Poor"));
+        assert_eq!(reading(0).current().unwrap().aqi_name(), None);
+        assert_eq!(reading(6).current().unwrap().aqi_name(), None);
+        assert!(serde_json::from_value::<AirPollution>(json!({})).unwrap().current().is_none());
+    }
+}
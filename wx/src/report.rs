@@ -0,0 +1,175 @@
+//! The current weather in a shape of its own, for scripts: everything in the
+//! response, with the units and local times worked out, as `tinywx -o
+//! raw-json` prints it. It is mapped from the parsed response instead of
+//! serializing that, so that the shape stays the same when the API's
+//! changes.
+
+use serde::Serialize;
+
+use crate::{geo, CurrentWeather, DataItem, Units};
+
+/// Version of the shape, bumped when a field changes or goes away; new
+/// fields keep it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The current weather at a place. Missing values are `null`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct WeatherReport {
+    /// [`SCHEMA_VERSION`].
+    pub schema: u32,
+    /// The units of the temperatures and wind speeds: "metric", "imperial"
+    /// or "standard".
+    pub units: &'static str,
+    pub place: Place,
+    /// Time of the observation, in unix seconds.
+    pub observed: i64,
+    /// The weather conditions, most significant first.
+    pub conditions: Vec<Condition>,
+    pub temperature: Temperature,
+    pub pressure_hpa: Option<f64>,
+    /// Relative humidity, in percent.
+    pub humidity: Option<f64>,
+    /// Cloudiness, in percent.
+    pub clouds: Option<f64>,
+    /// Visibility in meters, up to 10 km.
+    pub visibility_m: Option<f64>,
+    pub wind: Wind,
+    /// Rain and snow, in mm per hour; 0 when dry.
+    pub rain_mm_h: f64,
+    pub snow_mm_h: f64,
+    pub sun: Sun,
+}
+
+/// Where OpenWeatherMap resolved the query to.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Place {
+    pub name: Option<String>,
+    /// ISO 3166 country code.
+    pub country: Option<String>,
+    pub city_id: Option<u64>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    /// Shift of local time from UTC, in seconds.
+    pub utc_offset: i64,
+}
+
+/// A weather condition.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Condition {
+    /// Condition id, as in 502 for heavy rain.
+    pub id: u64,
+    /// Group of the condition, as in "Rain".
+    pub group: String,
+    /// In the language of the request.
+    pub description: String,
+    /// Icon id, as in "10d".
+    pub icon: String,
+    /// "none", "light", "moderate", "heavy" or "extreme".
+    pub severity: &'static str,
+}
+
+/// Temperatures, in the units of the report.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Temperature {
+    pub current: Option<f64>,
+    pub feels_like: Option<f64>,
+    /// Lowest and highest at the moment, across the area.
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub dew_point: Option<f64>,
+}
+
+/// Wind, its speeds in the units of the report.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Wind {
+    pub speed: Option<f64>,
+    pub gust: Option<f64>,
+    /// Direction the wind comes from, in degrees.
+    pub deg: Option<f64>,
+    /// That direction as one of 16 compass points, as in "NNE".
+    pub direction: Option<&'static str>,
+    /// Force on the Beaufort scale, from 0 to 12.
+    pub beaufort: Option<u8>,
+}
+
+/// Sunrise and sunset, `null` during polar day and night.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Sun {
+    /// In unix seconds.
+    pub sunrise: Option<i64>,
+    pub sunset: Option<i64>,
+    /// In local time, in RFC 3339, as in "2024-05-01T06:12:00+02:00"; `null`
+    /// without the `time` feature.
+    pub sunrise_local: Option<String>,
+    pub sunset_local: Option<String>,
+}
+
+impl CurrentWeather {
+    /// Returns the weather in the shape of a [`WeatherReport`], its values in
+    /// `units`, the units they were fetched with.
+    pub fn report(&self, units: Units) -> WeatherReport {
+        let event = |epoch: i64| Some(epoch).filter(|&epoch| epoch != 0);
+        let (sunrise, sunset) = (event(self.sys.sunrise), event(self.sys.sunset));
+        WeatherReport {
+            schema: SCHEMA_VERSION,
+            units: units.as_str(),
+            place: Place {
+                name: self.name.clone(),
+                country: self.country().map(str::to_string),
+                city_id: self.city_id(),
+                lat: self.coords().map(|(lat, _)| lat),
+                lon: self.coords().map(|(_, lon)| lon),
+                utc_offset: self.timezone,
+            },
+            observed: self.dt,
+            conditions: self
+                .weather
+                .iter()
+                .map(|w| Condition {
+                    id: w.id,
+                    group: w.main.clone(),
+                    description: w.description.clone(),
+                    icon: w.icon.clone(),
+                    severity: w.condition().severity().name(),
+                })
+                .collect(),
+            temperature: Temperature {
+                current: self.number(DataItem::Temp),
+                feels_like: self.number(DataItem::FeelsLike),
+                min: self.number(DataItem::TempMin),
+                max: self.number(DataItem::TempMax),
+                dew_point: self.dew_point(units),
+            },
+            pressure_hpa: self.number(DataItem::Pressure),
+            humidity: self.number(DataItem::Humidity),
+            clouds: self.number(DataItem::Clouds),
+            visibility_m: self.visibility,
+            wind: Wind {
+                speed: self.number(DataItem::Wind),
+                gust: self.wind.gust,
+                deg: self.wind.deg,
+                direction: self.wind.deg.map(geo::compass_point_16),
+                beaufort: self.beaufort(units),
+            },
+            rain_mm_h: self.precipitation(DataItem::Rain).unwrap_or(0.0),
+            snow_mm_h: self.precipitation(DataItem::Snow).unwrap_or(0.0),
+            sun: Sun {
+                sunrise,
+                sunset,
+                sunrise_local: sunrise.and_then(|epoch| self.rfc3339(epoch)),
+                sunset_local: sunset.and_then(|epoch| self.rfc3339(epoch)),
+            },
+        }
+    }
+
+    /// Formats `epoch` in the location's local time, in RFC 3339.
+    #[cfg(feature = "time")]
+    fn rfc3339(&self, epoch: i64) -> Option<String> {
+        Some(crate::local_datetime(epoch, self.timezone)?.to_rfc3339())
+    }
+
+    #[cfg(not(feature = "time"))]
+    fn rfc3339(&self, _: i64) -> Option<String> {
+        None
+    }
+}
@@ -0,0 +1,312 @@
+//! Request URLs. A [`RequestSpec`] has the endpoint, exactly one way of
+//! selecting the location and the parameters particular to the endpoint; the
+//! parameters every request has, like the units and the API key, are added
+//! when it is turned into a URL, all in one go, so that no parameter can end
+//! up in the query twice.
+
+use anyhow::{anyhow, Result};
+use url::Url;
+
+use crate::{Location, Units};
+
+/// Parameters that select the location.
+const SELECTORS: &[&str] = &["q", "id", "lat", "lon", "zip"];
+/// Parameters the client adds to every request. `mode` is here as responses
+/// are always read as JSON.
+const COMMON: &[&str] = &["units", "lang", "appid", "mode"];
+/// Version of how locations are written into queries; bumped whenever that
+/// changes, so that responses cached for a query written the old way are not
+/// taken for the new one.
+const QUERY_VERSION: u32 = 1;
+
+/// Returns a fingerprint of what decides the response to a request besides
+/// its path and location: the base URL, the units, the language and how
+/// queries are written. It is a 64-bit FNV-1a hash, in hex, which unlike the
+/// standard library's hasher is the same in every run and on every platform.
+pub(crate) fn fingerprint(base: &Url, units: Units, lang: Option<&str>) -> String {
+    let settings = [
+        base.as_str(),
+        units.as_str(),
+        lang.unwrap_or_default(),
+        &QUERY_VERSION.to_string(),
+    ];
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in settings.join("\0").bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// How a request selects the location.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Selector {
+    /// A name, as in "city,country", in `q`.
+    Query(String),
+    /// A city id, in `id`.
+    Id(u64),
+    /// Several city ids, comma-separated in `id`, for the group endpoint.
+    Ids(Vec<u64>),
+    /// Coordinates, in `lat` and `lon`.
+    Coords { lat: f64, lon: f64 },
+    /// A zip code and a country, as in "94040,us", in `zip`.
+    Zip(String),
+}
+
+impl From<&Location> for Selector {
+    fn from(location: &Location) -> Self {
+        match location {
+            Location::City { .. } => Selector::Query(location.city_query().unwrap_or_default()),
+            Location::Coords { lat, lon } => Selector::Coords { lat: *lat, lon: *lon },
+            Location::CityId { id } => Selector::Id(*id),
+            Location::Zip { .. } => Selector::Zip(location.zip_query().unwrap_or_default()),
+        }
+    }
+}
+
+impl Selector {
+    /// Returns the query parameters of the selector.
+    pub(crate) fn pairs(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Selector::Query(q) => vec![("q", q.clone())],
+            Selector::Id(id) => vec![("id", id.to_string())],
+            Selector::Ids(ids) => {
+                let ids: Vec<String> = ids.iter().map(u64::to_string).collect();
+                vec![("id", ids.join(","))]
+            }
+            Selector::Coords { lat, lon } => vec![("lat", lat.to_string()), ("lon", lon.to_string())],
+            Selector::Zip(zip) => vec![("zip", zip.clone())],
+        }
+    }
+}
+
+/// A request to an endpoint, before the client adds what every request has.
+#[derive(Clone, Debug)]
+pub(crate) struct RequestSpec {
+    path: String,
+    selector: Selector,
+    params: Vec<(String, String)>,
+    /// Whether the endpoint takes the units and the language.
+    localized: bool,
+}
+
+impl RequestSpec {
+    /// Starts a request to `path`, relative to the base URL, for the location
+    /// `selector` selects.
+    pub(crate) fn new(path: &str, selector: impl Into<Selector>) -> Self {
+        RequestSpec {
+            path: path.trim_start_matches('/').to_string(),
+            selector: selector.into(),
+            params: Vec::new(),
+            localized: true,
+        }
+    }
+
+    /// Leaves the units and the language out of the URL, for endpoints that
+    /// take neither, as those of the geocoding API.
+    pub(crate) fn unlocalized(mut self) -> Self {
+        self.localized = false;
+        self
+    }
+
+    /// Starts a request from query parameters as [`crate::Client::get_json`]
+    /// takes them: with exactly one selector among them (`q`, `id`, `zip`, or
+    /// `lat` and `lon`) and the others particular to the endpoint.
+    pub(crate) fn from_params(path: &str, params: &[(&str, &str)]) -> Result<Self> {
+        let given = |name: &str| params.iter().filter(|(k, _)| *k == name).count();
+        if let Some(name) = SELECTORS.iter().find(|&&name| given(name) > 1) {
+            return Err(anyhow!("parameter '{}' is given twice", name));
+        }
+        let value = |name: &str| params.iter().find(|(k, _)| *k == name).map(|(_, v)| *v);
+        fn number<T: std::str::FromStr>(name: &str, v: &str) -> Result<T> {
+            v.trim().parse().map_err(|_| anyhow!("invalid {} '{}'", name, v))
+        }
+        let selector = match (value("q"), value("id"), value("zip"), value("lat"), value("lon")) {
+            (Some(q), None, None, None, None) => Selector::Query(q.to_string()),
+            (None, Some(id), None, None, None) => Selector::Id(number("id", id)?),
+            (None, None, Some(zip), None, None) => Selector::Zip(zip.to_string()),
+            (None, None, None, Some(lat), Some(lon)) => Selector::Coords {
+                lat: number("lat", lat)?,
+                lon: number("lon", lon)?,
+            },
+            _ => return Err(anyhow!("expected exactly one location: q, id, zip, or lat and lon")),
+        };
+        let mut spec = RequestSpec::new(path, selector);
+        for (name, value) in params.iter().filter(|(k, _)| !SELECTORS.contains(k)) {
+            spec = spec.param(name, value)?;
+        }
+        Ok(spec)
+    }
+
+    /// Adds a parameter particular to the endpoint. Selectors, parameters the
+    /// client adds itself and parameters already added are refused.
+    pub(crate) fn param(mut self, name: &str, value: &str) -> Result<Self> {
+        if SELECTORS.contains(&name) {
+            return Err(anyhow!("parameter '{}' selects the location, which the request does", name));
+        }
+        if COMMON.contains(&name) {
+            return Err(anyhow!("parameter '{}' is set by the client", name));
+        }
+        if self.params.iter().any(|(k, _)| k == name) {
+            return Err(anyhow!("parameter '{}' is given twice", name));
+        }
+        self.params.push((name.to_string(), value.to_string()));
+        Ok(self)
+    }
+
+    /// Returns the endpoint, relative to the base URL.
+    pub(crate) fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the key responses to the request under `base` are cached
+    /// under: the [`fingerprint`] of the settings, then the path and the
+    /// query, without the API key and the parameters particular to the
+    /// endpoint (see [`crate::ResponseCache`]).
+    pub(crate) fn cache_key(&self, base: &Url, units: Units, lang: Option<&str>) -> String {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        query.extend_pairs(self.selector.pairs());
+        query.append_pair("units", units.as_str());
+        if let Some(lang) = lang {
+            query.append_pair("lang", lang);
+        }
+        format!("{}/{}?{}", fingerprint(base, units, lang), self.path, query.finish())
+    }
+
+    /// Returns the URL of the request under `base`, with the parameters every
+    /// request has, the units and the language unless it is
+    /// [unlocalized](Self::unlocalized).
+    pub(crate) fn url(
+        &self,
+        base: &Url,
+        api_key: &str,
+        units: Units,
+        lang: Option<&str>,
+    ) -> Result<Url> {
+        let mut url = base
+            .join(&self.path)
+            .map_err(|e| anyhow!("invalid path '{}': {}", self.path, e))?;
+        {
+            let mut query = url.query_pairs_mut();
+            query.clear();
+            query.extend_pairs(self.selector.pairs());
+            query.extend_pairs(&self.params);
+            if self.localized {
+                query.append_pair("units", units.as_str());
+                if let Some(lang) = lang {
+                    query.append_pair("lang", lang);
+                }
+            }
+            query.append_pair("appid", api_key);
+        }
+        Ok(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Text as city names have it: spaces, commas, accents, scripts other
+    /// than Latin, and what URLs give a meaning to.
+    fn name() -> impl Strategy<Value = String> {
+        prop_oneof!["[a-zA-Z ]{1,12}", "[a-zé中ö ,&=?#%+/]{1,12}", any::<String>()]
+    }
+
+    fn location() -> impl Strategy<Value = Location> {
+        prop_oneof![
+            (name(), prop_oneof![Just(String::new()), name()], name())
+                .prop_map(|(city, state, country)| Location::new(&city, &state, &country)),
+            (-90.0f64..90.0, -180.0f64..180.0).prop_map(|(lat, lon)| Location::Coords { lat, lon }),
+            any::<u64>().prop_map(Location::city_id),
+            (name(), name()).prop_map(|(code, country)| Location::Zip { code, country }),
+        ]
+    }
+
+    /// Returns the query of `url`, decoded.
+    fn query(url: &Url) -> Vec<(String, String)> {
+        url.query_pairs().map(|(k, v)| (k.into_owned(), v.into_owned())).collect()
+    }
+
+    #[test]
+    fn fingerprints_change_with_each_setting() {
+        let base = Url::parse("https://api.openweathermap.org/").unwrap();
+        let other = Url::parse("http://127.0.0.1:8080/").unwrap();
+        let dutch = fingerprint(&base, Units::Metric, Some("nl"));
+        assert_eq!(dutch, fingerprint(&base, Units::Metric, Some("nl")));
+        assert_eq!(dutch.len(), 16);
+        assert!(dutch.chars().all(|c| c.is_ascii_hexdigit()), "{}", dutch);
+        let others = [
+            fingerprint(&other, Units::Metric, Some("nl")),
+            fingerprint(&base, Units::Imperial, Some("nl")),
+            fingerprint(&base, Units::Standard, Some("nl")),
+            fingerprint(&base, Units::Metric, Some("de")),
+            fingerprint(&base, Units::Metric, None),
+        ];
+        for (i, other) in others.iter().enumerate() {
+            assert_ne!(*other, dutch, "{}", i);
+            assert!(!others[..i].contains(other), "{}", i);
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig { failure_persistence: None, ..ProptestConfig::default() })]
+
+        #[test]
+        fn urls_have_one_of_each_parameter(
+            location in location(),
+            lang in prop::option::of("[a-z]{2}(_[a-z]{2})?"),
+            units in prop::sample::select(Units::ALL.to_vec()),
+            cnt in prop::option::of(1u32..40),
+            localized in any::<bool>(),
+        ) {
+            let mut spec = RequestSpec::new("data/2.5/weather", &location);
+            if let Some(cnt) = cnt {
+                spec = spec.param("cnt", &cnt.to_string()).unwrap();
+            }
+            if !localized {
+                spec = spec.unlocalized();
+            }
+            let base = Url::parse("https://api.openweathermap.org/").unwrap();
+            let url = spec.url(&base, "0123456789abcdef", units, lang.as_deref()).unwrap();
+            let pairs = query(&url);
+            let names: Vec<&str> = pairs.iter().map(|(k, _)| k.as_str()).collect();
+            for name in &names {
+                prop_assert_eq!(names.iter().filter(|n| *n == name).count(), 1, "{} twice in {}", name, url);
+            }
+            let selectors: Vec<&str> = names.iter().copied().filter(|n| SELECTORS.contains(n)).collect();
+            let expected: &[&str] = match location {
+                Location::City { .. } => &["q"],
+                Location::Coords { .. } => &["lat", "lon"],
+                Location::CityId { .. } => &["id"],
+                Location::Zip { .. } => &["zip"],
+            };
+            prop_assert_eq!(selectors, expected, "{}", url);
+            prop_assert!(names.contains(&"appid"), "{}", url);
+            prop_assert_eq!(names.contains(&"units"), localized, "{}", url);
+            prop_assert_eq!(names.contains(&"lang"), localized && lang.is_some(), "{}", url);
+            prop_assert_eq!(names.contains(&"cnt"), cnt.is_some(), "{}", url);
+            // What the location is written as comes back whole, whatever
+            // it has in it.
+            let value = |name: &str| pairs.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone());
+            prop_assert_eq!(value("q"), location.city_query());
+            prop_assert_eq!(value("zip"), location.zip_query());
+        }
+
+        #[test]
+        fn params_select_exactly_one_location(q in name(), id in any::<u64>(), twice in any::<bool>()) {
+            let id = id.to_string();
+            prop_assert!(RequestSpec::from_params("find", &[("q", &q)]).is_ok());
+            prop_assert!(RequestSpec::from_params("find", &[("q", &q), ("id", &id)]).is_err());
+            prop_assert!(RequestSpec::from_params("find", &[("lat", "1")]).is_err());
+            prop_assert!(RequestSpec::from_params("find", &[]).is_err());
+            let repeated = if twice { "q" } else { "cnt" };
+            let params = [("q", q.as_str()), (repeated, "2"), (repeated, "3")];
+            prop_assert!(RequestSpec::from_params("find", &params).is_err());
+            for common in COMMON {
+                prop_assert!(RequestSpec::from_params("find", &[("q", &q), (common, "x")]).is_err(), "{}", common);
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Position of the sun in the sky, computed locally with the NOAA solar
+//! position algorithm (the one behind NOAA's solar calculator). It is accurate
+//! to about a minute of arc for dates between 1901 and 2099.
+
+use crate::CurrentWeather;
+
+/// The golden hour is when the sun's elevation is between these, in degrees.
+const GOLDEN_HOUR: (f64, f64) = (-4.0, 6.0);
+
+/// Where the sun is, as seen from a point on earth.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SunPosition {
+    /// Degrees above the horizon, corrected for atmospheric refraction;
+    /// negative below it.
+    pub elevation: f64,
+    /// Degrees clockwise from north.
+    pub azimuth: f64,
+}
+
+impl SunPosition {
+    /// Returns whether the light is that of the golden hour, with the sun
+    /// between 4° below and 6° above the horizon.
+    pub fn is_golden_hour(&self) -> bool {
+        self.elevation >= GOLDEN_HOUR.0 && self.elevation <= GOLDEN_HOUR.1
+    }
+}
+
+impl CurrentWeather {
+    /// Returns the position of the sun at the location at `now`, in unix
+    /// seconds, or `None` if the response has no coordinates.
+    pub fn sun_position(&self, now: i64) -> Option<SunPosition> {
+        let (lat, lon) = self.coords()?;
+        Some(position(now, lat, lon))
+    }
+}
+
+/// Computes the position of the sun at `time`, in unix seconds, seen from
+/// latitude `lat` and longitude `lon` in decimal degrees.
+pub fn position(time: i64, lat: f64, lon: f64) -> SunPosition {
+    let julian_day = time as f64 / 86400.0 + 2440587.5;
+    let t = (julian_day - 2451545.0) / 36525.0;
+
+    let mean_longitude = (280.46646 + t * (36000.76983 + t * 0.0003032)).rem_euclid(360.0);
+    let mean_anomaly = 357.52911 + t * (35999.05029 - 0.0001537 * t);
+    let eccentricity = 0.016708634 - t * (0.000042037 + 0.0000001267 * t);
+    let m = mean_anomaly.to_radians();
+    let center = m.sin() * (1.914602 - t * (0.004817 + 0.000014 * t))
+        + (2.0 * m).sin() * (0.019993 - 0.000101 * t)
+        + (3.0 * m).sin() * 0.000289;
+
+    let omega = (125.04 - 1934.136 * t).to_radians();
+    let apparent_longitude = (mean_longitude + center - 0.00569 - 0.00478 * omega.sin()).to_radians();
+    let mean_obliquity =
+        23.0 + (26.0 + (21.448 - t * (46.815 + t * (0.00059 - t * 0.001813))) / 60.0) / 60.0;
+    let obliquity = (mean_obliquity + 0.00256 * omega.cos()).to_radians();
+    let declination = (obliquity.sin() * apparent_longitude.sin()).asin();
+
+    // Equation of time, in minutes.
+    let y = (obliquity / 2.0).tan().powi(2);
+    let l0 = mean_longitude.to_radians();
+    let equation_of_time = 4.0
+        * (y * (2.0 * l0).sin() - 2.0 * eccentricity * m.sin()
+            + 4.0 * eccentricity * y * m.sin() * (2.0 * l0).cos()
+            - 0.5 * y * y * (4.0 * l0).sin()
+            - 1.25 * eccentricity * eccentricity * (2.0 * m).sin())
+        .to_degrees();
+
+    let minutes = time.rem_euclid(86400) as f64 / 60.0;
+    let true_solar_time = (minutes + equation_of_time + 4.0 * lon).rem_euclid(1440.0);
+    let hour_angle = true_solar_time / 4.0 - 180.0;
+
+    let lat_rad = lat.to_radians();
+    let cos_zenith = lat_rad.sin() * declination.sin()
+        + lat_rad.cos() * declination.cos() * hour_angle.to_radians().cos();
+    let zenith = cos_zenith.clamp(-1.0, 1.0).acos();
+
+    let elevation = 90.0 - zenith.to_degrees();
+    let azimuth = {
+        let denominator = lat_rad.cos() * zenith.sin();
+        let cos_azimuth = if denominator.abs() < 1e-12 {
+            // At a pole or with the sun straight overhead, any direction will do.
+            1.0
+        } else {
+            ((lat_rad.sin() * zenith.cos() - declination.sin()) / denominator).clamp(-1.0, 1.0)
+        };
+        let angle = cos_azimuth.acos().to_degrees();
+        if hour_angle > 0.0 {
+            (angle + 180.0).rem_euclid(360.0)
+        } else {
+            (540.0 - angle).rem_euclid(360.0)
+        }
+    };
+
+    SunPosition {
+        elevation: elevation + refraction(elevation),
+        azimuth,
+    }
+}
+
+/// Approximates how much the atmosphere lifts the sun, in degrees, at the
+/// given geometric elevation.
+fn refraction(elevation: f64) -> f64 {
+    let tan = elevation.to_radians().tan();
+    let arcseconds = if elevation > 85.0 {
+        0.0
+    } else if elevation > 5.0 {
+        58.1 / tan - 0.07 / tan.powi(3) + 0.000086 / tan.powi(5)
+    } else if elevation > -0.575 {
+        1735.0 + elevation * (-518.2 + elevation * (103.4 + elevation * (-12.79 + elevation * 0.711)))
+    } else {
+        -20.772 / tan
+    };
+    arcseconds / 3600.0
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::ParseMode;
+
+    /// Amsterdam, as the API gives its coordinates.
+    const AMSTERDAM: (f64, f64) = (52.374, 4.8897);
+
+    /// Returns the highest the sun gets at `lat` and `lon` in the day from
+    /// `midnight`, in unix seconds, to the minute.
+    fn highest(midnight: i64, (lat, lon): (f64, f64)) -> f64 {
+        (0..1440).map(|minute| position(midnight + minute * 60, lat, lon).elevation).fold(f64::MIN, f64::max)
+    }
+
+    #[test]
+    fn the_reference_position() {
+        // The worked example of the NREL solar position algorithm (Reda and
+        // Andreas, 2004), which NOAA's calculator agrees with to hundredths of
+        // a degree: Golden, Colorado, 17 October 2003 at 19:30:30 UTC, with
+        // the sun at a zenith of 50.11162° and an azimuth of 194.34024°.
+        let sun = position(1_066_419_030, 39.742476, -105.1786);
+        assert!((sun.elevation - (90.0 - 50.11162)).abs() < 0.02, "{:?}", sun);
+        assert!((sun.azimuth - 194.34024).abs() < 0.01, "{:?}", sun);
+    }
+
+    #[test]
+    fn noons_at_the_solstices() {
+        // The sun culminates at 90° less the latitude, plus or less the
+        // declination of 23.44° at the solstices, lifted a little by the air.
+        let june = highest(1_718_841_600, AMSTERDAM);
+        assert!((june - (90.0 - AMSTERDAM.0 + 23.44)).abs() < 0.05, "{}", june);
+        let december = highest(1_703_116_800, AMSTERDAM);
+        assert!((december - (90.0 - AMSTERDAM.0 - 23.44)).abs() < 0.1, "{}", december);
+    }
+
+    #[test]
+    fn the_midnight_sun_at_the_pole() {
+        // On 20 June 2024 the sun circles the North Pole at its declination.
+        for hour in 0..24 {
+            let sun = position(1_718_841_600 + hour * 3600, 90.0, 0.0);
+            assert!((sun.elevation - 23.4).abs() < 0.1, "{}h: {:?}", hour, sun);
+            assert!((0.0..360.0).contains(&sun.azimuth), "{}h: {:?}", hour, sun);
+        }
+    }
+
+    #[test]
+    fn the_sun_stands_south_at_noon_north_of_the_tropics() {
+        let noon = (0..1440).map(|minute| 1_718_841_600 + minute * 60);
+        let noon = noon.max_by(|a, b| {
+            let (a, b) = (position(*a, AMSTERDAM.0, AMSTERDAM.1), position(*b, AMSTERDAM.0, AMSTERDAM.1));
+            a.elevation.total_cmp(&b.elevation)
+        });
+        let sun = position(noon.unwrap(), AMSTERDAM.0, AMSTERDAM.1);
+        assert!((sun.azimuth - 180.0).abs() < 0.5, "{:?}", sun);
+    }
+
+    #[test]
+    fn golden_hour_edges() {
+        let at = |elevation| SunPosition { elevation, azimuth: 270.0 }.is_golden_hour();
+        assert!(!at(-4.01));
+        assert!(at(-4.0));
+        assert!(at(0.0));
+        assert!(at(6.0));
+        assert!(!at(6.01));
+        assert!(!at(45.0));
+        assert!(!at(-45.0));
+    }
+
+    #[test]
+    fn positions_need_coordinates() {
+        let body = r#"{"main": {"temp": 20.0}}"#;
+        let weather = CurrentWeather::from_json(body, ParseMode::Salvage).unwrap();
+        assert_eq!(weather.sun_position(1_066_419_030), None);
+        let body = r#"{"coord": {"lat": 39.742476, "lon": -105.1786}, "main": {"temp": 20.0}}"#;
+        let weather = CurrentWeather::from_json(body, ParseMode::Salvage).unwrap();
+        let expected = position(1_066_419_030, 39.742476, -105.1786);
+        assert_eq!(weather.sun_position(1_066_419_030), Some(expected));
+    }
+}
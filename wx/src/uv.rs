@@ -0,0 +1,60 @@
+//! UV index summaries over an hourly series.
+
+use chrono::NaiveDate;
+
+use crate::local_datetime;
+
+/// Finds the time from which the UV index stays below `threshold` for the rest
+/// of the local day containing `now`.
+///
+/// `hourly` holds `(time, uvi)` pairs in chronological order, times in unix
+/// seconds, and `offset` is the location's shift from UTC in seconds. Returns
+/// `None` if the UV index doesn't reach the threshold during the rest of the
+/// day, i.e. it is already safe.
+pub fn uv_safe_until(hourly: &[(i64, f64)], threshold: f64, now: i64, offset: i64) -> Option<i64> {
+    let rest = rest_of_day(hourly, now, offset);
+    let last_high = rest.iter().rposition(|(_, uvi)| *uvi >= threshold)?;
+    match rest.get(last_high + 1) {
+        Some((dt, _)) => Some(*dt),
+        None => Some(rest[last_high].0.saturating_add(3600)),
+    }
+}
+
+/// Finds today's highest UV index and the time it occurs, as a `(time, uvi)`
+/// pair. The earliest hour wins if the peak is reached more than once.
+pub fn uv_peak(hourly: &[(i64, f64)], now: i64, offset: i64) -> Option<(i64, f64)> {
+    let today = local_date(now, offset)?;
+    hourly
+        .iter()
+        .filter(|(dt, _)| local_date(*dt, offset) == Some(today))
+        .fold(None, |peak: Option<(i64, f64)>, &(dt, uvi)| match peak {
+            Some((_, max)) if max >= uvi => peak,
+            _ => Some((dt, uvi)),
+        })
+}
+
+/// Returns the WHO category of UV index `uvi`: "low" below 3, "moderate"
+/// below 6, "high" below 8, "very high" below 11 and "extreme" from 11.
+pub fn category(uvi: f64) -> &'static str {
+    match uvi {
+        uvi if uvi < 3.0 => "low",
+        uvi if uvi < 6.0 => "moderate",
+        uvi if uvi < 8.0 => "high",
+        uvi if uvi < 11.0 => "very high",
+        _ => "extreme",
+    }
+}
+
+/// Returns the entries from the current hour up to the end of the local day.
+fn rest_of_day(hourly: &[(i64, f64)], now: i64, offset: i64) -> Vec<(i64, f64)> {
+    let today = local_date(now, offset);
+    hourly
+        .iter()
+        .filter(|(dt, _)| dt.saturating_add(3600) > now && today.is_some() && local_date(*dt, offset) == today)
+        .copied()
+        .collect()
+}
+
+fn local_date(epoch: i64, offset: i64) -> Option<NaiveDate> {
+    local_datetime(epoch, offset).map(|dt| dt.naive_local().date())
+}
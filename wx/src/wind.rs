@@ -0,0 +1,77 @@
+//! The Beaufort scale, which tells wind speeds by their effects, from calm
+//! (force 0) to hurricane (force 12), with the names the WMO gives them.
+
+/// Lowest speed of each force from 1 to 12, in m/s.
+const THRESHOLDS: [f64; 12] = [0.5, 1.6, 3.4, 5.5, 8.0, 10.8, 13.9, 17.2, 20.8, 24.5, 28.5, 32.7];
+
+/// Names of the forces, from 0 to 12.
+const NAMES: [&str; 13] = [
+    "calm",
+    "light air",
+    "light breeze",
+    "gentle breeze",
+    "moderate breeze",
+    "fresh breeze",
+    "strong breeze",
+    "near gale",
+    "gale",
+    "strong gale",
+    "storm",
+    "violent storm",
+    "hurricane",
+];
+
+/// Forces gusts must be above the sustained wind to be worth telling.
+const SIGNIFICANT_GUSTS: u8 = 2;
+
+/// Returns the Beaufort force of a wind of `speed`, in m/s, from 0 to 12.
+pub fn beaufort(speed: f64) -> u8 {
+    THRESHOLDS.iter().take_while(|&&threshold| speed >= threshold).count() as u8
+}
+
+/// Returns the name of the Beaufort `force`, as in "fresh breeze" for 5;
+/// forces above 12 are hurricanes too.
+pub fn description(force: u8) -> &'static str {
+    NAMES[usize::from(force).min(NAMES.len() - 1)]
+}
+
+/// Describes a wind of `speed` with `gust`, both in m/s, as in "fresh breeze",
+/// or "fresh breeze, gusting near gale" if the gusts are at least two forces
+/// stronger.
+pub fn describe(speed: f64, gust: Option<f64>) -> String {
+    let force = beaufort(speed);
+    match gust.map(beaufort) {
+        Some(gusts) if gusts >= force + SIGNIFICANT_GUSTS => {
+            format!("{}, gusting {}", description(force), description(gusts))
+        }
+        _ => description(force).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forces_start_at_their_thresholds() {
+        for (n, threshold) in THRESHOLDS.iter().enumerate() {
+            let force = n as u8 + 1;
+            assert_eq!(beaufort(*threshold), force, "{}", threshold);
+            assert_eq!(beaufort(threshold - 0.01), force - 1, "{}", threshold - 0.01);
+        }
+        assert_eq!(beaufort(0.0), 0);
+        assert_eq!(beaufort(-1.0), 0);
+        assert_eq!(beaufort(100.0), 12);
+        assert_eq!(beaufort(f64::NAN), 0);
+    }
+
+    #[test]
+    fn gusts_two_forces_stronger_are_told() {
+        // 8.0 m/s is force 5; 13.9 is 7 and 13.8 is 6.
+        assert_eq!(describe(8.0, Some(13.9)), "fresh breeze, gusting near gale");
+        assert_eq!(describe(8.0, Some(13.8)), "fresh breeze");
+        assert_eq!(describe(8.0, None), "fresh breeze");
+        assert_eq!(describe(40.0, Some(60.0)), "hurricane");
+        assert_eq!(description(13), "hurricane");
+    }
+}
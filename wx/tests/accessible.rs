@@ -0,0 +1,69 @@
+//! Accessible rendering, for screen readers: every item of the registry that
+//! renders from the current weather fixture has a rendering without glyphs,
+//! symbols or abbreviated units, in every unit system and in English and
+//! another language.
+
+use wx::format::UnitStyle;
+use wx::item::ITEMS;
+use wx::{CurrentWeather, DataItem, ParseMode, Units};
+
+const CURRENT: &str = include_str!("fixtures/current.json");
+
+/// Units as they are abbreviated, which read out poorly.
+const ABBREVIATIONS: [&str; 10] = ["m/s", "mph", "km/h", "hPa", "inHg", "mm", "km", "mi", "°", "%"];
+
+/// Returns whether `value` is words, numbers and punctuation only, without
+/// abbreviated units, standing alone or after a number as in "15h".
+fn spoken(value: &str) -> bool {
+    let plain = value.chars().all(|c| c.is_alphanumeric() || " .,:;-'".contains(c));
+    let words: Vec<&str> = value.split([' ', ',']).collect();
+    let glued = |word: &&str| word.starts_with(|c: char| c.is_ascii_digit()) && word.contains(char::is_alphabetic);
+    plain && !words.iter().any(glued) && !ABBREVIATIONS.iter().any(|abbreviation| words.contains(abbreviation))
+}
+
+#[test]
+fn every_item_that_renders_is_spelled_out() {
+    let weather = CurrentWeather::from_json(CURRENT, ParseMode::Strict).unwrap();
+    let now = 1714550400 + 600;
+    for info in ITEMS {
+        let item = info.item;
+        for units in Units::ALL {
+            let rendered =
+                weather.render(item, units, UnitStyle::Verbose).is_some() || weather.render_at(item, now).is_some();
+            for lang in ["en", "de"] {
+                let accessible = weather.render_accessible(item, units, lang, now);
+                assert_eq!(accessible.is_some(), rendered, "{} in {:?}, {}", info.name, units, lang);
+                let accessible = accessible.unwrap_or_default();
+                assert!(spoken(&accessible), "{} in {:?}, {}: {:?}", info.name, units, lang, accessible);
+            }
+        }
+    }
+}
+
+#[test]
+fn spelled_out_with_the_units_named() {
+    let weather = CurrentWeather::from_json(CURRENT, ParseMode::Strict).unwrap();
+    let now = 1714550400 + 600;
+    let accessible = |item: DataItem, units: Units, lang: &str| weather.render_accessible(item, units, lang, now);
+    let temp = accessible(DataItem::Temp, Units::Metric, "en");
+    assert_eq!(temp.as_deref(), Some("temperature 12 degrees Celsius"));
+    let humidity = accessible(DataItem::Humidity, Units::Metric, "en");
+    assert_eq!(humidity.as_deref(), Some("humidity 81 percent"));
+    let wind = accessible(DataItem::Wind, Units::Metric, "en");
+    assert_eq!(wind.as_deref(), Some("wind 6 meters per second from the southwest"));
+    let wind = accessible(DataItem::Wind, Units::Imperial, "en");
+    assert_eq!(wind.as_deref(), Some("wind 6 miles per hour from the southwest"));
+    let description = accessible(DataItem::Description, Units::Metric, "en");
+    assert_eq!(description.as_deref(), Some("light rain"));
+    #[cfg(feature = "icons")]
+    assert_eq!(accessible(DataItem::Icon, Units::Metric, "en").as_deref(), Some("rain"));
+    // Units are named in the languages that have a table, and in English
+    // otherwise.
+    let temp = accessible(DataItem::Temp, Units::Metric, "de");
+    assert_eq!(temp.as_deref(), Some("temperature 12 Grad Celsius"));
+    let humidity = accessible(DataItem::Humidity, Units::Metric, "nl");
+    assert_eq!(humidity.as_deref(), Some("humidity 81 procent"));
+    assert_eq!(accessible(DataItem::Temp, Units::Metric, "ja"), accessible(DataItem::Temp, Units::Metric, "en"));
+    let day_length = accessible(DataItem::DayLength, Units::Metric, "en");
+    assert_eq!(day_length.as_deref(), Some("day length 15 hours 10 minutes"));
+}
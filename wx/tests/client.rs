@@ -0,0 +1,419 @@
+//! The client against a local mock of the API: a normal response, an error
+//! the API answers with, a body that is not what it should be, what of these
+//! is cached, how strictly it is parsed, and API keys the client is not
+//! built with.
+
+#![cfg(feature = "http")]
+
+mod common;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use common::MockServer;
+use serde_json::Value;
+use wx::{ApiError, Location, MissingApiKey, ParseError, ParseMode, ResponseCache, Retries};
+
+const CURRENT: &str = include_str!("fixtures/current.json");
+const MANGLED: &str = include_str!("fixtures/mangled.json");
+const NOT_FOUND: &str = r#"{"cod":"404","message":"city not found"}"#;
+
+fn amsterdam() -> Location {
+    Location::new("Amsterdam", "", "NL")
+}
+
+/// A cache in memory, where everything stays fresh.
+#[derive(Debug, Default)]
+struct Memory(Mutex<HashMap<String, String>>);
+
+impl ResponseCache for Memory {
+    fn get(&self, _: &str, key: &str) -> Option<String> {
+        self.0.lock().unwrap().get(key).cloned()
+    }
+
+    fn put(&self, _: &str, key: &str, body: &str) {
+        self.0.lock().unwrap().insert(key.to_string(), body.to_string());
+    }
+
+    fn evict(&self, _: &str, key: &str) {
+        self.0.lock().unwrap().remove(key);
+    }
+}
+
+impl Memory {
+    /// Replaces every cached body with `body`, as an older version that
+    /// cached something else leaves the cache.
+    fn seed(&self, body: &str) {
+        self.0.lock().unwrap().values_mut().for_each(|cached| *cached = body.to_string());
+    }
+}
+
+#[test]
+fn current_weather() {
+    let server = MockServer::always(200, CURRENT);
+    let weather = server.client().current(&amsterdam()).unwrap();
+    assert_eq!(weather.city_name(), Some("Amsterdam"));
+    assert_eq!(weather.temp(), 12.35);
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].starts_with("/data/2.5/weather?q=Amsterdam%2CNL&units=metric&appid="), "{}", requests[0]);
+}
+
+#[test]
+fn city_not_found() {
+    let server = MockServer::always(404, NOT_FOUND);
+    let e = server.client().current(&amsterdam()).unwrap_err();
+    let e = e.downcast_ref::<ApiError>().expect("an ApiError");
+    assert_eq!(e.status, 404);
+    assert!(e.is_not_found());
+    assert_eq!(e.message, "city not found");
+    assert!(e.url.contains("appid=REDACTED"), "{}", e.url);
+}
+
+#[test]
+fn city_not_found_with_200_ok() {
+    // The API sometimes answers an error with 200 OK and the code in the body.
+    let server = MockServer::always(200, NOT_FOUND);
+    let e = server.client().current(&amsterdam()).unwrap_err();
+    assert_eq!(e.downcast_ref::<ApiError>().map(|e| e.status), Some(404));
+}
+
+#[test]
+fn error_codes_with_200_ok_as_numbers_and_strings() {
+    let bodies = [
+        (r#"{"cod":401,"message":"Invalid API key."}"#, 401, "Invalid API key."),
+        (r#"{"cod":"429","message":"too many requests"}"#, 429, "too many requests"),
+    ];
+    for (body, status, message) in bodies {
+        let server = MockServer::always(200, body);
+        let e = server.client().current(&amsterdam()).unwrap_err();
+        let e = e.downcast_ref::<ApiError>().unwrap_or_else(|| panic!("{}: not an ApiError", body));
+        assert_eq!((e.status, e.message.as_str()), (status, message), "{}", body);
+    }
+}
+
+#[test]
+fn malformed_json() {
+    for body in [r#"{"coord":{"lon":4.9,"lat":52.4},"main":"#, "<html>Welcome</html>", r#"{"main":{"temp":"warm"}}"#] {
+        let server = MockServer::always(200, body);
+        let e = server.client().current(&amsterdam()).unwrap_err();
+        assert!(e.is::<ParseError>(), "{}: {:#}", body, e);
+    }
+}
+
+#[test]
+fn responses_are_cached() {
+    let server = MockServer::always(200, CURRENT);
+    let cache = Arc::new(Memory::default());
+    let client = server.builder().cache(cache.clone()).build().unwrap();
+    client.current(&amsterdam()).unwrap();
+    client.current(&amsterdam()).unwrap();
+    assert_eq!(server.requests().len(), 1);
+    assert_eq!(cache.0.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn malformed_responses_are_not_cached() {
+    let server = MockServer::always(200, r#"{"main":{"temp":"warm"},"list":"none"}"#);
+    let cache = Arc::new(Memory::default());
+    let client = server.builder().cache(cache.clone()).build().unwrap();
+    for _ in 0..2 {
+        assert!(client.current(&amsterdam()).unwrap_err().is::<ParseError>());
+        let forecast = client.get_json::<wx::Forecast>("data/2.5/forecast", &[("q", "Amsterdam,NL")]);
+        assert!(forecast.unwrap_err().is::<ParseError>());
+    }
+    // Each run asks again, rather than failing on what it cached.
+    assert_eq!(server.requests().len(), 4);
+    assert!(cache.0.lock().unwrap().is_empty());
+}
+
+#[test]
+fn a_cached_body_that_does_not_parse_is_fetched_again() {
+    let server = MockServer::always(200, CURRENT);
+    let cache = Arc::new(Memory::default());
+    let client = server.builder().cache(cache.clone()).build().unwrap();
+    client.current(&amsterdam()).unwrap();
+    for stale in [r#"{"main":{"temp":"warm"}}"#, "[]", r#"{"coord":"#] {
+        cache.seed(stale);
+        client.reset_cache_hits();
+        let before = server.requests().len();
+        assert_eq!(client.current(&amsterdam()).unwrap().temp(), 12.35, "{}", stale);
+        assert_eq!(server.requests().len(), before + 1, "{}", stale);
+        assert!(client.cache_hits().is_empty(), "{}", stale);
+        assert_eq!(cache.0.lock().unwrap().values().collect::<Vec<_>>(), [CURRENT], "{}", stale);
+    }
+}
+
+#[test]
+fn a_cached_body_is_fetched_again_only_once() {
+    let answered = AtomicUsize::new(0);
+    let server = MockServer::start(move |_| match answered.fetch_add(1, Ordering::SeqCst) {
+        0 => (200, CURRENT.to_string()),
+        _ => (200, r#"{"main":{"temp":"warm"}}"#.to_string()),
+    });
+    let cache = Arc::new(Memory::default());
+    let client = server.builder().cache(cache.clone()).build().unwrap();
+    client.current(&amsterdam()).unwrap();
+    cache.seed("[]");
+    // The body fetched again does not parse either: that is the error, and
+    // neither body stays cached.
+    assert!(client.current(&amsterdam()).unwrap_err().is::<ParseError>());
+    assert_eq!(server.requests().len(), 2);
+    assert!(cache.0.lock().unwrap().is_empty());
+}
+
+#[test]
+fn the_cached_current_weather_is_read_without_a_request() {
+    let server = MockServer::always(200, CURRENT);
+    let cache = Arc::new(Memory::default());
+    let client = server.builder().cache(cache.clone()).build().unwrap();
+    assert!(client.current_cached(&amsterdam()).is_none());
+    client.current(&amsterdam()).unwrap();
+    assert_eq!(client.current_cached(&amsterdam()).map(|weather| weather.temp()), Some(12.35));
+    assert!(client.current_cached(&Location::new("Paris", "", "FR")).is_none());
+    cache.seed("[]");
+    assert!(client.current_cached(&amsterdam()).is_none());
+    assert_eq!(server.requests().len(), 1);
+    // Without a cache, there is nothing to read.
+    assert!(server.client().current_cached(&amsterdam()).is_none());
+}
+
+#[test]
+fn a_response_past_the_timeout_is_a_network_error() {
+    let server = MockServer::start(|_| {
+        std::thread::sleep(Duration::from_secs(3));
+        (200, CURRENT.to_string())
+    });
+    let client = server.builder().timeout(Duration::from_millis(300)).build().unwrap();
+    let started = std::time::Instant::now();
+    let e = client.current(&amsterdam()).unwrap_err();
+    let elapsed = started.elapsed();
+    let e = e.downcast_ref::<wx::NetworkError>().expect("a NetworkError");
+    assert!(e.url.contains("appid=REDACTED"), "{}", e.url);
+    assert!(elapsed >= Duration::from_millis(300) && elapsed < Duration::from_millis(1500), "{:?}", elapsed);
+}
+
+#[test]
+fn a_response_over_the_limit_is_too_large() {
+    let server = MockServer::always(200, CURRENT);
+    let limit = CURRENT.len() as u64 - 1;
+    let e = server.builder().max_response_size(limit).build().unwrap().current(&amsterdam()).unwrap_err();
+    let e = e.downcast_ref::<wx::TooLarge>().expect("a TooLarge");
+    assert_eq!(e.limit, limit);
+    assert!(e.url.contains("/data/2.5/weather?") && e.url.contains("appid=REDACTED"), "{}", e.url);
+    // It is not retried, and what fits the limit exactly is taken.
+    assert_eq!(server.requests().len(), 1);
+    let client = server.builder().max_response_size(limit + 1).build().unwrap();
+    assert_eq!(client.current(&amsterdam()).unwrap().temp(), 12.35);
+}
+
+#[test]
+fn a_response_that_is_not_json_is_a_parse_error() {
+    let server = MockServer::start_as("text/html; charset=utf-8", |_| (200, CURRENT.to_string()));
+    let e = server.client().current(&amsterdam()).unwrap_err();
+    assert!(!e.is::<ApiError>() && !e.is::<wx::NetworkError>(), "{:#}", e);
+    let e = e.downcast_ref::<ParseError>().expect("a ParseError");
+    assert_eq!(e.message, "expected JSON, got text/html; charset=utf-8");
+    assert!(e.url.contains("appid=REDACTED"), "{}", e.url);
+    // An error is still the API's, whatever it says its body is.
+    let server = MockServer::start_as("text/html", |_| (404, NOT_FOUND.to_string()));
+    let e = server.client().current(&amsterdam()).unwrap_err();
+    assert_eq!(e.downcast_ref::<ApiError>().map(|e| e.status), Some(404), "{:#}", e);
+}
+
+#[test]
+fn requests_reuse_the_connection() {
+    let server = MockServer::keep_alive(|_| (200, CURRENT.to_string()));
+    let client = server.client();
+    client.current(&amsterdam()).unwrap();
+    client.current(&amsterdam()).unwrap();
+    assert_eq!((server.requests().len(), server.connections()), (2, 1));
+    // Clients with the default transport share one pool, as those of
+    // wx::get do.
+    server.client().current(&amsterdam()).unwrap();
+    assert_eq!((server.requests().len(), server.connections()), (3, 1));
+    // One of its own has a pool of its own.
+    let client = server.builder().timeout(Duration::from_secs(3)).build().unwrap();
+    client.current(&amsterdam()).unwrap();
+    client.current(&amsterdam()).unwrap();
+    assert_eq!((server.requests().len(), server.connections()), (5, 2));
+}
+
+#[test]
+fn get_json_requests_a_custom_path() {
+    let server = MockServer::always(200, r#"{"list":[1,2]}"#);
+    let client = server.builder().lang("de").build().unwrap();
+    let value: Value = client.get_json("data/2.5/find", &[("lat", "52.37"), ("lon", "4.89"), ("cnt", "2")]).unwrap();
+    assert_eq!(value["list"][1], 2);
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    let expected = "/data/2.5/find?lat=52.37&lon=4.89&cnt=2&units=metric&lang=de&appid=0123456789abcdef0123456789abcdef";
+    assert_eq!(requests[0], expected);
+}
+
+#[test]
+fn get_json_retries_transient_failures() {
+    let answered = AtomicUsize::new(0);
+    let server = MockServer::start(move |_| match answered.fetch_add(1, Ordering::SeqCst) {
+        0 => (503, r#"{"cod":503,"message":"busy"}"#.to_string()),
+        _ => (200, r#"{"cod":200}"#.to_string()),
+    });
+    let retries = Retries { count: 1, backoff: Duration::from_millis(1) };
+    let client = server.builder().retries(retries).build().unwrap();
+    let value: Value = client.get_json("data/2.5/custom", &[("id", "2759794")]).unwrap();
+    assert_eq!(value["cod"], 200);
+    assert_eq!(server.requests().len(), 2);
+
+    // Client errors are not retried.
+    let server = MockServer::always(401, r#"{"cod":401,"message":"Invalid API key."}"#);
+    let client = server.builder().retries(retries).build().unwrap();
+    assert!(client.get_json::<Value>("data/2.5/custom", &[("id", "2759794")]).is_err());
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[test]
+fn get_json_redacts_the_api_key() {
+    let server = MockServer::always(401, r#"{"cod":401,"message":"Invalid API key."}"#);
+    let e = server.client().get_json::<Value>("data/2.5/custom", &[("q", "Amsterdam")]).unwrap_err();
+    let text = format!("{:#}", e);
+    assert!(text.contains("data/2.5/custom?q=Amsterdam&units=metric&appid=REDACTED"), "{}", text);
+    assert!(!text.contains("0123456789abcdef"), "{}", text);
+    assert_eq!(e.downcast_ref::<ApiError>().map(|e| e.message.as_str()), Some("Invalid API key."));
+
+    // Without a server to answer, the network error has no key either.
+    let client = wx::Client::builder()
+        .base_url("http://127.0.0.1:9/")
+        .api_key("0123456789abcdef0123456789abcdef")
+        .retries(Retries { count: 0, ..Retries::default() })
+        .build()
+        .unwrap();
+    let e = client.get_json::<Value>("data/2.5/custom", &[("q", "Amsterdam")]).unwrap_err();
+    let text = format!("{:#}", e);
+    assert!(e.is::<wx::NetworkError>(), "{}", text);
+    assert!(text.contains("appid=REDACTED") && !text.contains("0123456789abcdef"), "{}", text);
+}
+
+#[test]
+fn empty_api_keys_are_refused_before_any_request() {
+    let server = MockServer::always(200, CURRENT);
+    for key in ["", "   ", "\t\n"] {
+        let e = server.builder().api_key(key).build().unwrap_err();
+        assert!(e.downcast_ref::<MissingApiKey>().is_some(), "{:?}: {}", key, e);
+        assert_eq!(e.to_string(), "an OpenWeatherMap API key is required, but it is empty");
+    }
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
+
+#[test]
+fn keys_that_do_not_look_right_are_still_sent() {
+    for (key, sent) in [
+        ("abc123", "abc123"),
+        (" 0123456789abcdef0123456789abcdef ", "0123456789abcdef0123456789abcdef"),
+        ("0123456789ABCDEF0123456789ABCDEG", "0123456789ABCDEF0123456789ABCDEG"),
+    ] {
+        let server = MockServer::always(200, CURRENT);
+        server.builder().api_key(key).build().unwrap().current(&amsterdam()).unwrap();
+        let requests = server.requests();
+        assert!(requests[0].contains(&format!("appid={}", sent)), "{:?}: {:?}", key, requests);
+    }
+}
+
+#[test]
+fn plausible_api_keys() {
+    assert!(wx::plausible_api_key("0123456789abcdef0123456789abcdef"));
+    assert!(wx::plausible_api_key(" 0123456789ABCDEF0123456789ABCDEF\n"));
+    for key in ["", "   ", "abc123", "0123456789abcdef0123456789abcde", "0123456789abcdef0123456789abcdeg"] {
+        assert!(!wx::plausible_api_key(key), "{:?}", key);
+    }
+}
+
+#[test]
+fn responses_are_parsed_in_the_mode_of_the_client() {
+    let server = MockServer::always(200, MANGLED);
+    for mode in [ParseMode::Strict, ParseMode::Tolerant] {
+        let e = server.builder().parse_mode(mode).build().unwrap().current(&amsterdam()).unwrap_err();
+        assert!(e.downcast_ref::<ParseError>().is_some(), "{:?}: {:#}", mode, e);
+    }
+    let client = server.builder().parse_mode(ParseMode::Salvage).build().unwrap();
+    let weather = client.current(&amsterdam()).unwrap();
+    assert!(!weather.is_present("weather") && weather.is_present("main.temp"), "{:?}", weather.missing());
+
+    let server = MockServer::always(200, &CURRENT.replace("\"base\"", "\"extra\": 1, \"base\""));
+    let e = server.builder().parse_mode(ParseMode::Strict).build().unwrap().current(&amsterdam()).unwrap_err();
+    assert!(format!("{:#}", e).contains("unknown field `extra`"), "{:#}", e);
+    assert!(server.client().current(&amsterdam()).is_ok());
+}
+
+#[test]
+fn parses_fixture_without_a_server() {
+    let weather = wx::CurrentWeather::from_json(CURRENT, wx::ParseMode::default()).unwrap();
+    assert_eq!(weather.country(), Some("NL"));
+}
+
+#[test]
+#[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+fn https_needs_tls() {
+    let builder = || wx::Client::builder().api_key("0123456789abcdef0123456789abcdef");
+    let e = builder().build().unwrap().current(&amsterdam()).unwrap_err();
+    assert!(format!("{:#}", e).contains("this build has no TLS"), "{:#}", e);
+    let pem = b"-----BEGIN CERTIFICATE-----\n-----END CERTIFICATE-----\n";
+    let e = builder().root_certificate(pem).build().unwrap_err();
+    assert!(e.to_string().contains("this build has no TLS"), "{}", e);
+}
+
+/// Runs `future` to its end on a runtime of its own.
+#[cfg(feature = "async")]
+fn block_on<T>(future: impl std::future::Future<Output = T>) -> T {
+    tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(future)
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_and_blocking_agree() {
+    let malformed = r#"{"main":{"temp":"warm"}}"#;
+    for (status, body) in [(200, CURRENT), (404, NOT_FOUND), (200, malformed)] {
+        let server = MockServer::always(status, body);
+        let client = server.client();
+        let blocking = client.current(&amsterdam());
+        let nonblocking = block_on(client.current_async(&amsterdam()));
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2, "{}", body);
+        assert_eq!(requests[0], requests[1]);
+        match (blocking, nonblocking) {
+            (Ok(blocking), Ok(nonblocking)) => {
+                assert_eq!(serde_json::to_value(&blocking).unwrap(), serde_json::to_value(&nonblocking).unwrap());
+                assert_eq!(nonblocking.temp(), 12.35);
+            }
+            (Err(blocking), Err(nonblocking)) => {
+                assert_eq!(blocking.to_string(), nonblocking.to_string());
+                assert_eq!(blocking.is::<ApiError>(), nonblocking.is::<ApiError>(), "{}", body);
+                assert_eq!(blocking.is::<ParseError>(), nonblocking.is::<ParseError>(), "{}", body);
+            }
+            (blocking, nonblocking) => panic!("{}: {:?} and {:?}", body, blocking.err(), nonblocking.err()),
+        }
+    }
+}
+
+#[test]
+fn zip_codes_are_asked_with_their_country() {
+    let server = MockServer::always(200, CURRENT);
+    let weather = server.client().current(&Location::zip(" 1012 AB ", "nl")).unwrap();
+    assert_eq!(weather.city_name(), Some("Amsterdam"));
+    let requests = server.requests();
+    assert!(requests[0].starts_with("/data/2.5/weather?zip=1012+AB%2Cnl&units=metric&appid="), "{}", requests[0]);
+}
+
+#[test]
+fn zip_codes_are_geocoded_to_a_single_place() {
+    let place = r#"{"zip":"1012 AB","name":"Amsterdam","lat":52.3731,"lon":4.8922,"country":"NL"}"#;
+    let server = MockServer::always(200, place);
+    let coords = server.client().geocode(&Location::zip("1012 AB", "nl")).unwrap();
+    assert_eq!(coords, Some((52.3731, 4.8922)));
+    let requests = server.requests();
+    assert!(requests[0].starts_with("/geo/1.0/zip?zip=1012+AB%2Cnl&appid="), "{}", requests[0]);
+    // A code that is not found is no place, rather than an error.
+    let server = MockServer::always(404, r#"{"cod":"404","message":"not found"}"#);
+    assert_eq!(server.client().geocode(&Location::zip("0000", "nl")).unwrap(), None);
+}
@@ -0,0 +1,133 @@
+//! A local HTTP server standing in for the API, for the tests of the client.
+
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use wx::{Client, ClientBuilder, Retries};
+
+/// A response of the mock server: the status and the body, sent as JSON.
+pub type Response = (u16, String);
+
+/// A server on a free local port that answers each request with what its
+/// handler returns for the path, and records the paths it was asked for and
+/// how many connections it accepted. It runs until the test ends.
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+    connections: Arc<AtomicUsize>,
+}
+
+impl MockServer {
+    pub fn start(handler: impl Fn(&str) -> Response + Send + 'static) -> Self {
+        Self::start_as("application/json", handler)
+    }
+
+    /// Starts a server as [`start`](Self::start) does, that says its bodies
+    /// are of `content_type`.
+    pub fn start_as(content_type: &'static str, handler: impl Fn(&str) -> Response + Send + 'static) -> Self {
+        Self::listen(move |stream, requests| {
+            // One request a connection, answered before the next is accepted.
+            let _ = answer(&stream, requests, content_type, false, &handler);
+        })
+    }
+
+    /// Starts a server as [`start`](Self::start) does, that keeps each
+    /// connection open for more requests, as the API does, rather than
+    /// closing it after the first.
+    pub fn keep_alive(handler: impl Fn(&str) -> Response + Send + Sync + 'static) -> Self {
+        let handler = Arc::new(handler);
+        Self::listen(move |stream, requests| {
+            let (handler, requests) = (Arc::clone(&handler), Arc::clone(requests));
+            thread::spawn(move || while answer(&stream, &requests, "application/json", true, &*handler) {});
+        })
+    }
+
+    /// Accepts connections on a free local port, handing each to `serve`
+    /// with the paths requested so far.
+    fn listen(serve: impl Fn(TcpStream, &Arc<Mutex<Vec<String>>>) + Send + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let connections = Arc::new(AtomicUsize::new(0));
+        let (recorded, accepted) = (Arc::clone(&requests), Arc::clone(&connections));
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                accepted.fetch_add(1, Ordering::SeqCst);
+                serve(stream, &recorded);
+            }
+        });
+        MockServer { url, requests, connections }
+    }
+
+    /// Starts a server that answers every request alike.
+    pub fn always(status: u16, body: &str) -> Self {
+        let body = body.to_string();
+        Self::start(move |_| (status, body.clone()))
+    }
+
+    /// Returns the paths requested so far, with their query.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Returns how many connections the server accepted so far.
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+
+    /// Returns a builder for a client of this server, which does not retry.
+    pub fn builder(&self) -> ClientBuilder {
+        Client::builder()
+            .base_url(&self.url)
+            .api_key("0123456789abcdef0123456789abcdef")
+            .retries(Retries { count: 0, ..Retries::default() })
+    }
+
+    pub fn client(&self) -> Client {
+        self.builder().build().unwrap()
+    }
+}
+
+/// Reads a request from `stream`, records its path in `requests`, and
+/// writes what `handler` returns for it. Returns whether the connection
+/// stays open for another request: only with `keep_alive`, and only if this
+/// one was read whole.
+fn answer(
+    stream: &TcpStream,
+    requests: &Mutex<Vec<String>>,
+    content_type: &str,
+    keep_alive: bool,
+    handler: &dyn Fn(&str) -> Response,
+) -> bool {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).map_or(true, |n| n == 0) {
+        return false;
+    }
+    // Skip the headers; requests have no body.
+    let mut line = String::new();
+    while reader.read_line(&mut line).is_ok_and(|n| n > 0) && line != "\r\n" {
+        line.clear();
+    }
+    let path = request_line.split_whitespace().nth(1).unwrap_or_default().to_string();
+    let (status, body) = handler(&path);
+    requests.lock().unwrap().push(path);
+    let connection = if keep_alive { "keep-alive" } else { "close" };
+    let headers = format!(
+        "HTTP/1.1 {} X\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: {}\r\n",
+        status,
+        content_type,
+        body.len(),
+        connection
+    );
+    let mut stream = stream;
+    write!(stream, "{}\r\n{}", headers, body).is_ok() && keep_alive
+}
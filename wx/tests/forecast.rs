@@ -0,0 +1,190 @@
+//! The 5 day forecast, from a response captured in `tests/fixtures`: its
+//! own shape, with a city block, `pop` and `dt_txt`, the next steps rendered
+//! as current weather, the rest of the local day at times through it, the
+//! next precipitation with chances on both sides of the threshold, with snow
+//! and with none at all, and the client asking for it of a local mock.
+
+#[cfg(feature = "http")]
+mod common;
+
+use serde_json::Value;
+use wx::format::UnitStyle;
+use wx::forecast::PrecipKind;
+use wx::{CurrentWeather, DataItem, Forecast, PrecipEvent, Units};
+
+const FORECAST: &str = include_str!("fixtures/forecast.json");
+/// The time of the first step of the fixture, 2024-05-01 09:00 UTC.
+const FIRST: i64 = 1714554000;
+const STEP: i64 = 3 * 3600;
+
+fn forecast() -> Forecast {
+    serde_json::from_str(FORECAST).unwrap()
+}
+
+fn render(weather: &CurrentWeather, item: DataItem) -> String {
+    weather.render(item, Units::Metric, UnitStyle::default()).unwrap_or_default()
+}
+
+#[test]
+fn fixture_parses_with_its_city() {
+    let forecast = forecast();
+    assert_eq!(forecast.span(), Some((FIRST, FIRST + 7 * STEP)));
+    assert_eq!(forecast.timezone(), 7200);
+    assert_eq!(forecast.truncated(), 0);
+    let step = forecast.slot(FIRST).unwrap();
+    assert_eq!(render(&step, DataItem::City), "Amsterdam");
+    assert_eq!(render(&step, DataItem::Country), "NL");
+}
+
+#[test]
+fn upcoming_steps_are_the_next_after_now() {
+    let forecast = forecast();
+    // An hour before the first step, and on the second: it is not upcoming.
+    for (now, temps) in [(FIRST - 3600, ["13°", "14°", "15°"]), (FIRST + STEP, ["15°", "13°", "11°"])] {
+        let steps = forecast.upcoming(now, 3);
+        let rendered: Vec<String> = steps.iter().map(|step| render(step, DataItem::Temp)).collect();
+        assert_eq!(rendered, temps, "{}", now);
+    }
+    let steps = forecast.upcoming(FIRST - 3600, 3);
+    let descriptions: Vec<String> = steps.iter().map(|step| render(step, DataItem::Description)).collect();
+    assert_eq!(descriptions, ["broken clouds", "light rain", "moderate rain"]);
+    assert_eq!(render(&steps[2], DataItem::Humidity), "91%");
+}
+
+#[test]
+fn upcoming_stops_at_the_end_of_the_forecast() {
+    let forecast = forecast();
+    assert_eq!(forecast.upcoming(FIRST + 5 * STEP, 40).len(), 2);
+    assert!(forecast.upcoming(FIRST + 7 * STEP, 3).is_empty());
+}
+
+#[test]
+fn missing_city_and_list_are_empty() {
+    let mut value: Value = serde_json::from_str(FORECAST).unwrap();
+    let object = value.as_object_mut().unwrap();
+    object.remove("city");
+    object.insert("list".to_string(), Value::Null);
+    let forecast: Forecast = serde_json::from_value(value).unwrap();
+    assert_eq!(forecast.span(), None);
+    assert!(forecast.upcoming(0, 3).is_empty());
+}
+
+#[test]
+fn rest_of_day_is_of_the_local_day() {
+    let forecast = forecast();
+    // The fixture is of UTC+2: its steps are at 11:00, 14:00, 17:00, 20:00
+    // and 23:00 on the 1st, and at 02:00, 05:00 and 08:00 on the 2nd.
+    let range = |hours: f64| {
+        // Local midnight of the 1st is 11 hours before the first step.
+        let summary = forecast.rest_of_day(FIRST - 11 * 3600 + (hours * 3600.0) as i64).unwrap();
+        (summary.low, summary.high)
+    };
+    // In the morning, before the first step, and at midday, in the step of
+    // 11:00, all the steps of the 1st count.
+    assert_eq!(range(8.0), (10.93, 15.02));
+    assert_eq!(range(13.0), (10.93, 15.02));
+    // Late in the evening, the steps of 20:00 and 23:00 are left.
+    assert_eq!(range(22.5), (10.93, 13.4));
+    // Past midnight local time, though not yet in UTC, it is the 2nd.
+    assert_eq!(range(24.5), (8.85, 11.62));
+    let summary = forecast.rest_of_day(FIRST - 4 * 3600).unwrap();
+    assert_eq!((summary.precipitation, summary.wind, summary.wind_deg), (0.87, 7.24, 241.0));
+    assert_eq!((summary.sunrise, summary.sunset), (1714535557, 1714590379));
+    // After the last step, there is nothing left of the day.
+    assert_eq!(forecast.rest_of_day(FIRST + 8 * STEP), None);
+}
+
+/// Returns the fixture with `edit` made to each of its steps, by index.
+fn edited(edit: impl Fn(usize, &mut Value)) -> Forecast {
+    let mut value: Value = serde_json::from_str(FORECAST).unwrap();
+    for (i, step) in value["list"].as_array_mut().unwrap().iter_mut().enumerate() {
+        edit(i, step);
+    }
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn next_precipitation_is_the_first_step_over_the_threshold() {
+    let forecast = forecast();
+    // The chances of the steps are 12%, 64%, 87%, 30% and then none to speak
+    // of, at 11:00, 14:00 and 17:00 local time and on.
+    let event = forecast.next_precipitation(FIRST - 3600, 12, 0.5).unwrap();
+    let expected = PrecipEvent { at: FIRST + STEP, timezone: 7200, probability: 0.64, kind: PrecipKind::Rain };
+    assert_eq!(event, expected);
+    assert_eq!(event.render(), "rain 64% @14:00");
+    // A threshold is reached, not only exceeded.
+    assert_eq!(forecast.next_precipitation(FIRST - 3600, 12, 0.64).unwrap().at, FIRST + STEP);
+    assert_eq!(forecast.next_precipitation(FIRST - 3600, 12, 0.8).unwrap().render(), "rain 87% @17:00");
+    assert_eq!(forecast.next_precipitation(FIRST - 3600, 12, 0.9), None);
+}
+
+#[test]
+fn next_precipitation_is_within_the_hours() {
+    let forecast = forecast();
+    assert_eq!(forecast.next_precipitation(FIRST - 3600, 2, 0.5), None);
+    assert_eq!(forecast.next_precipitation(FIRST - 3600, 4, 0.5).unwrap().at, FIRST + STEP);
+    // A step up to half a step old counts; one older does not.
+    let half = STEP / 2;
+    assert_eq!(forecast.next_precipitation(FIRST + STEP + half, 1, 0.5).unwrap().at, FIRST + STEP);
+    assert_eq!(forecast.next_precipitation(FIRST + STEP + half + 1, 3, 0.5).unwrap().at, FIRST + 2 * STEP);
+}
+
+#[test]
+fn snow_and_no_precipitation_at_all() {
+    let snowy = edited(|i, step| {
+        if i == 1 {
+            step["weather"] = serde_json::json!([{"id": 611, "main": "Snow", "description": "sleet", "icon": "13d"}]);
+        }
+    });
+    let event = snowy.next_precipitation(FIRST - 3600, 12, 0.5).unwrap();
+    assert_eq!((event.kind, event.render().as_str()), (PrecipKind::Snow, "snow 64% @14:00"));
+
+    let dry = edited(|_, step| step["pop"] = 0.into());
+    assert_eq!(dry.next_precipitation(FIRST - 3600, 120, 0.01), None);
+}
+
+#[test]
+fn local_times_wrap_around_midnight() {
+    let event = |at: i64, timezone: i64| PrecipEvent { at, timezone, probability: 0.5, kind: PrecipKind::Rain };
+    assert_eq!(event(FIRST, 7200).local_time(), "11:00");
+    assert_eq!(event(FIRST, -10 * 3600).local_time(), "23:00");
+    assert_eq!(event(FIRST + 15 * 3600 + 1800, 7200).local_time(), "02:30");
+}
+
+#[cfg(feature = "http")]
+mod client {
+    use super::*;
+
+    use crate::common::MockServer;
+    use wx::Location;
+
+    #[test]
+    fn forecast_asks_for_every_step() {
+        let server = MockServer::always(200, FORECAST);
+        let location = Location::Coords { lat: 52.374, lon: 4.8897 };
+        let forecast = server.client().forecast(&location).unwrap();
+        assert_eq!(forecast.span(), Some((FIRST, FIRST + 7 * STEP)));
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("/data/2.5/forecast?"), "{}", requests[0]);
+        assert!(requests[0].contains("&cnt=40"), "{}", requests[0]);
+    }
+
+    #[test]
+    fn steps_beyond_five_days_are_left_out() {
+        let mut value: Value = serde_json::from_str(FORECAST).unwrap();
+        let list = value["list"].as_array().unwrap().clone();
+        let longer: Vec<Value> = (0..45)
+            .map(|i| {
+                let mut step = list[i % list.len()].clone();
+                step["dt"] = (FIRST + i as i64 * STEP).into();
+                step
+            })
+            .collect();
+        value["list"] = longer.into();
+        let server = MockServer::always(200, &value.to_string());
+        let forecast = server.client().forecast(&Location::CityId { id: 2759794 }).unwrap();
+        assert_eq!(forecast.truncated(), 5);
+        assert_eq!(forecast.span(), Some((FIRST, FIRST + 39 * STEP)));
+    }
+}
@@ -0,0 +1,88 @@
+//! The geocoding API against a local mock, from a response captured in
+//! `tests/fixtures`: no places, one, and the five Springfields it lists at
+//! most, as the places a name could be and as the coordinates of a city.
+
+#![cfg(feature = "http")]
+
+mod common;
+
+use common::MockServer;
+use wx::{GeoResult, Location};
+
+const SPRINGFIELD: &str = include_str!("fixtures/springfield.json");
+
+fn springfield() -> Location {
+    Location::new("Springfield", "", "US")
+}
+
+/// Returns the first of the places of the fixture, alone in a list.
+fn one() -> String {
+    let places: Vec<serde_json::Value> = serde_json::from_str(SPRINGFIELD).unwrap();
+    serde_json::to_string(&places[..1]).unwrap()
+}
+
+#[test]
+fn no_places() {
+    let server = MockServer::always(200, "[]");
+    assert!(server.client().places("Nowhere,US", 5).unwrap().is_empty());
+    assert_eq!(server.client().geocode(&Location::new("Nowhere", "", "US")).unwrap(), None);
+}
+
+#[test]
+fn one_place() {
+    let server = MockServer::always(200, &one());
+    let places = server.client().places("Springfield,US", 5).unwrap();
+    let expected = GeoResult {
+        name: "Springfield".to_string(),
+        state: Some("Illinois".to_string()),
+        country: "US".to_string(),
+        lat: 39.7990175,
+        lon: -89.6439575,
+    };
+    assert_eq!(places, [expected]);
+    assert_eq!(server.client().geocode(&springfield()).unwrap(), Some((39.7990175, -89.6439575)));
+}
+
+#[test]
+fn many_places_best_match_first() {
+    let server = MockServer::always(200, SPRINGFIELD);
+    let places = server.client().places("Springfield,US", 5).unwrap();
+    let names: Vec<String> = places.iter().map(GeoResult::to_string).collect();
+    assert_eq!(
+        names,
+        [
+            "Springfield, Illinois, US",
+            "Springfield, Missouri, US",
+            "Springfield, Massachusetts, US",
+            "Springfield, Ohio, US",
+            "Springfield, Oregon, US",
+        ]
+    );
+    // Fewer are kept, though all are asked for, so that the response is
+    // cached whatever the limit.
+    let places = server.client().places("Springfield,US", 2).unwrap();
+    assert_eq!(places.len(), 2);
+    assert_eq!(places[1].state.as_deref(), Some("Missouri"));
+    for path in server.requests() {
+        assert!(path.starts_with("/geo/1.0/direct?q=Springfield%2CUS&limit=5&appid="), "{}", path);
+    }
+    // A city is at the best match.
+    assert_eq!(server.client().geocode(&springfield()).unwrap(), Some((39.7990175, -89.6439575)));
+}
+
+#[test]
+fn places_without_a_state() {
+    let body = r#"[{"name": "Amsterdam", "lat": 52.3727598, "lon": 4.8936041, "country": "NL"}]"#;
+    let server = MockServer::always(200, body);
+    let places = server.client().places("Amsterdam,NL", 5).unwrap();
+    assert_eq!(places[0].state, None);
+    assert_eq!(places[0].to_string(), "Amsterdam, NL");
+}
+
+#[test]
+fn coordinates_and_ids_are_not_asked_for() {
+    let server = MockServer::always(200, SPRINGFIELD);
+    assert_eq!(server.client().geocode(&Location::coords(39.8, -89.6)).unwrap(), Some((39.8, -89.6)));
+    assert_eq!(server.client().geocode(&Location::city_id(4250542)).unwrap(), None);
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+}
@@ -0,0 +1,63 @@
+//! The current weather of several cities by id, from the group endpoint of a
+//! local mock: its envelope, ids it does not know, and the limit of 20 ids a
+//! request, which is kept to before anything is asked.
+
+#![cfg(feature = "http")]
+
+mod common;
+
+use common::MockServer;
+use serde_json::{json, Value};
+use wx::group::MAX_IDS;
+
+const CURRENT: &str = include_str!("fixtures/current.json");
+
+/// Returns the response of the group endpoint for the cities `cities`, as
+/// (id, name, temperature), from the current weather fixture.
+fn group(cities: &[(u64, &str, f64)]) -> String {
+    let list: Vec<Value> = cities
+        .iter()
+        .map(|&(id, name, temp)| {
+            let mut weather: Value = serde_json::from_str(CURRENT).unwrap();
+            weather["id"] = id.into();
+            weather["name"] = name.into();
+            weather["main"]["temp"] = temp.into();
+            weather
+        })
+        .collect();
+    json!({"cnt": list.len(), "list": list}).to_string()
+}
+
+#[test]
+fn cities_come_in_the_order_of_their_ids() {
+    let server = MockServer::always(200, &group(&[(2759794, "Amsterdam", 12.35), (2643743, "London", 9.8)]));
+    let cities = server.client().group(&[2759794, 2643743]).unwrap();
+    let found: Vec<(Option<u64>, Option<&str>, f64)> =
+        cities.iter().map(|weather| (weather.city_id(), weather.city_name(), weather.temp())).collect();
+    assert_eq!(found, [(Some(2759794), Some("Amsterdam"), 12.35), (Some(2643743), Some("London"), 9.8)]);
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].starts_with("/data/2.5/group?id=2759794%2C2643743&units=metric&appid="), "{}", requests[0]);
+}
+
+#[test]
+fn ids_not_found_are_left_out() {
+    let server = MockServer::always(200, &group(&[(2759794, "Amsterdam", 12.35)]));
+    let cities = server.client().group(&[2759794, 1]).unwrap();
+    assert_eq!(cities.len(), 1);
+    // A list of null is no cities.
+    let server = MockServer::always(200, r#"{"cnt": 0, "list": null}"#);
+    assert!(server.client().group(&[1]).unwrap().is_empty());
+}
+
+#[test]
+fn at_most_20_ids_and_at_least_one() {
+    let server = MockServer::always(200, &group(&[]));
+    let ids: Vec<u64> = (1..=MAX_IDS as u64 + 1).collect();
+    let e = server.client().group(&ids).unwrap_err();
+    assert_eq!(e.to_string(), "21 city ids given, but at most 20 can be fetched at once");
+    let e = server.client().group(&[]).unwrap_err();
+    assert_eq!(e.to_string(), "no city ids to fetch");
+    assert!(server.requests().is_empty(), "{:?}", server.requests());
+    assert!(server.client().group(&ids[..MAX_IDS]).is_ok());
+}
@@ -0,0 +1,107 @@
+//! One Call API 3.0, from a response captured in `tests/fixtures`, in
+//! Amsterdam on the morning of 1 May 2024: the current weather, the
+//! minutely, hourly and daily forecasts and an alert, parsed strictly, and
+//! the parts left out of a request asked for as the API takes them.
+
+#![cfg(feature = "onecall")]
+
+mod common;
+
+use common::MockServer;
+use serde_json::Value;
+use wx::parse::{from_json, ParseMode};
+use wx::{IconSet, OneCall, Part};
+
+const ONE_CALL: &str = include_str!("fixtures/onecall.json");
+/// The time of the current weather of the fixture, 10:00 in Amsterdam.
+const NOW: i64 = 1714550400;
+const COORDS: (f64, f64) = (52.374, 4.8897);
+
+fn one_call() -> OneCall {
+    from_json(ONE_CALL, ParseMode::Strict).unwrap()
+}
+
+#[test]
+fn fixture_parses_strictly_and_tolerantly() {
+    for mode in [ParseMode::Strict, ParseMode::Tolerant] {
+        let one_call: OneCall = from_json(ONE_CALL, mode).unwrap();
+        assert_eq!(one_call.timezone(), ("Europe/Amsterdam", 7200), "{:?}", mode);
+        assert_eq!(
+            (one_call.minutely().len(), one_call.hourly().len(), one_call.daily().len(), one_call.alerts().len()),
+            (5, 12, 3, 1),
+            "{:?}",
+            mode
+        );
+    }
+}
+
+#[test]
+fn each_part_has_its_readings() {
+    let one_call = one_call();
+    let current = one_call.current().unwrap();
+    assert_eq!((current.dt, current.temp, current.uvi, current.dew_point), (NOW, 12.35, 2.41, 9.17));
+    assert_eq!(current.rain.as_ref().and_then(|rain| rain.one_hour), Some(0.42));
+    assert_eq!(current.weather[0].description(), "light rain");
+
+    assert_eq!(one_call.minutely()[4].precipitation, 0.0);
+    let hourly = &one_call.hourly()[3];
+    assert_eq!((hourly.dt, hourly.uvi, hourly.pop), (NOW + 3 * 3600, 5.3, 0.2));
+
+    let daily = &one_call.daily()[0];
+    assert_eq!((daily.temp.min, daily.temp.max, daily.pop, daily.rain), (8.9, 15.02, 0.87, Some(2.57)));
+    assert_eq!(daily.feels_like.morn, 7.9);
+    assert_eq!(daily.weather[0].icon(IconSet::Text), "rain");
+    assert_eq!(daily.weather[0].icon(IconSet::Emoji), "🌧️");
+    assert_eq!(one_call.daily()[2].weather[0].icon(IconSet::Text), "clear");
+
+    let alert = &one_call.alerts()[0];
+    assert_eq!((alert.sender_name.as_str(), alert.event.as_str()), ("KNMI", "Wind"));
+    assert_eq!(alert.tags, ["Wind"]);
+}
+
+#[test]
+fn today_is_the_local_day() {
+    let one_call = one_call();
+    assert_eq!(one_call.today(NOW).map(|daily| daily.temp.max), Some(15.02));
+    // 23:30 on the 1st is still the 1st in Amsterdam, 00:30 on the 2nd is
+    // not, though it is still the 1st in UTC.
+    let midnight = NOW - 10 * 3600 + 86400;
+    assert_eq!(one_call.today(midnight - 1800).map(|daily| daily.temp.max), Some(15.02));
+    assert_eq!(one_call.today(midnight + 1800).map(|daily| daily.temp.max), Some(17.3));
+    assert!(one_call.today(midnight + 3 * 86400).is_none());
+}
+
+#[test]
+fn the_uv_index_of_the_rest_of_the_day() {
+    let one_call = one_call();
+    assert_eq!(one_call.uv_peak(NOW), "5.3 @13:00");
+    assert_eq!(one_call.uv_safe_until(3.0, NOW), "16:00");
+}
+
+#[test]
+fn excluded_parts_are_empty() {
+    let mut value: Value = serde_json::from_str(ONE_CALL).unwrap();
+    for part in ["current", "minutely", "hourly", "alerts"] {
+        value.as_object_mut().unwrap().remove(part);
+    }
+    let one_call: OneCall = from_json(&value.to_string(), ParseMode::Strict).unwrap();
+    assert!(one_call.current().is_none());
+    assert!(one_call.minutely().is_empty() && one_call.hourly().is_empty() && one_call.alerts().is_empty());
+    assert_eq!(one_call.daily().len(), 3);
+    assert_eq!(one_call.uv_peak(NOW), "");
+}
+
+#[test]
+fn exclude_is_one_comma_separated_parameter() {
+    let server = MockServer::always(200, ONE_CALL);
+    let client = server.client();
+    client.one_call(COORDS, &[]).unwrap();
+    client.one_call(COORDS, &[Part::Minutely, Part::Alerts]).unwrap();
+    assert_eq!(client.uvi(COORDS).unwrap(), 2.41);
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3, "{:?}", requests);
+    assert!(requests.iter().all(|path| path.starts_with("/data/3.0/onecall?lat=52.374&lon=4.8897")), "{:?}", requests);
+    assert!(!requests[0].contains("exclude"), "{}", requests[0]);
+    assert!(requests[1].contains("&exclude=minutely%2Calerts"), "{}", requests[1]);
+    assert!(requests[2].contains("&exclude=minutely%2Chourly%2Cdaily%2Calerts"), "{}", requests[2]);
+}
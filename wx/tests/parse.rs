@@ -0,0 +1,317 @@
+//! Parsing of current weather responses, from the fixtures in
+//! `tests/fixtures`, in each parse mode: Amsterdam in the rain, a polar
+//! station without visibility or a wind direction, the monsoon with the rain
+//! of 3 hours, and a point in the ocean with no city or country. And
+//! Amsterdam with no weather condition, or with two at once, and its readings
+//! through the typed getters.
+
+use serde_json::Value;
+use wx::format::UnitStyle;
+use wx::condition::Condition;
+use wx::item::{Feature, ITEMS};
+use wx::{CurrentWeather, DataItem, ParseMode, Units, Unsupported};
+
+const CURRENT: &str = include_str!("fixtures/current.json");
+const POLAR: &str = include_str!("fixtures/polar.json");
+const MONSOON: &str = include_str!("fixtures/monsoon.json");
+const OCEAN: &str = include_str!("fixtures/ocean.json");
+/// The fixture mangled: blocks of the wrong type, numbers as strings and in
+/// arrays, and a wind direction in words.
+const MANGLED: &str = include_str!("fixtures/mangled.json");
+
+/// Returns the current weather fixture with the fields at `pointers` taken
+/// out, as in "/wind".
+fn without(pointers: &[&str]) -> String {
+    let mut value: Value = serde_json::from_str(CURRENT).unwrap();
+    for pointer in pointers {
+        let (parent, key) = pointer.rsplit_once('/').unwrap();
+        value.pointer_mut(parent).and_then(Value::as_object_mut).unwrap().remove(key);
+    }
+    value.to_string()
+}
+
+fn render(weather: &CurrentWeather, item: DataItem) -> Option<String> {
+    weather.render(item, Units::Metric, UnitStyle::default())
+}
+
+#[test]
+fn fixture_renders_its_readings() {
+    let weather = CurrentWeather::from_json(CURRENT, ParseMode::Strict).unwrap();
+    assert_eq!(render(&weather, DataItem::Temp).as_deref(), Some("12°"));
+    assert_eq!(render(&weather, DataItem::Pressure).as_deref(), Some("1012"));
+    assert_eq!(render(&weather, DataItem::Clouds).as_deref(), Some("75%"));
+    assert_eq!(render(&weather, DataItem::City).as_deref(), Some("Amsterdam"));
+}
+
+#[test]
+fn every_fixture_parses_in_every_mode() {
+    for (name, json) in [("current", CURRENT), ("polar", POLAR), ("monsoon", MONSOON), ("ocean", OCEAN)] {
+        for mode in [ParseMode::Strict, ParseMode::Tolerant, ParseMode::Salvage] {
+            let weather = CurrentWeather::from_json(json, mode);
+            let weather = weather.unwrap_or_else(|e| panic!("{} in {:?}: {}", name, mode, e));
+            assert!(weather.missing().is_empty(), "{} in {:?}: {:?}", name, mode, weather.missing());
+        }
+    }
+}
+
+#[test]
+fn a_polar_station_without_visibility_or_direction() {
+    let weather = CurrentWeather::from_json(POLAR, ParseMode::Strict).unwrap();
+    assert_eq!(weather.visibility(), None);
+    assert_eq!(render(&weather, DataItem::Visibility), None);
+    assert_eq!(render(&weather, DataItem::Wind).as_deref(), Some("2"));
+    for item in [DataItem::WindDeg, DataItem::WindDir, DataItem::WindDirLong, DataItem::WindGust] {
+        assert_eq!(render(&weather, item), None, "{}", item.name());
+    }
+    let wind = weather.render_accessible(DataItem::Wind, Units::Metric, "en", 1713952800);
+    assert_eq!(wind.as_deref(), Some("wind 2 meters per second"));
+    assert_eq!(weather.snow_last_hour(), Some(0.25));
+    assert_eq!(weather.rain_last_hour(), None);
+    assert_eq!(render(&weather, DataItem::Snow).as_deref(), Some("0.3"));
+    assert_eq!(render(&weather, DataItem::Temp).as_deref(), Some("-7°"));
+    assert_eq!(render(&weather, DataItem::Country).as_deref(), Some("SJ"));
+}
+
+#[test]
+fn rain_of_3_hours_is_per_hour() {
+    let weather = CurrentWeather::from_json(MONSOON, ParseMode::Strict).unwrap();
+    assert_eq!(weather.rain_last_hour(), None);
+    assert_eq!(render(&weather, DataItem::Rain).as_deref(), Some("2.0"));
+    assert_eq!(render(&weather, DataItem::Snow).as_deref(), Some("0"));
+    assert_eq!(render(&weather, DataItem::Description).as_deref(), Some("heavy intensity rain, mist"));
+    assert_eq!(render(&weather, DataItem::Visibility).as_deref(), Some("2.0"));
+    assert_eq!(render(&weather, DataItem::WindDir).as_deref(), Some("WSW"));
+}
+
+#[test]
+fn none_one_or_two_conditions() {
+    let mist = serde_json::json!({"id": 701, "main": "Mist", "description": "mist", "icon": "50d"});
+    let mut value: Value = serde_json::from_str(CURRENT).unwrap();
+    let rain = value["weather"][0].clone();
+    let cases = [(vec![], ""), (vec![rain.clone()], "light rain"), (vec![rain, mist], "light rain, mist")];
+    for (conditions, description) in cases {
+        let count = conditions.len();
+        value["weather"] = conditions.into();
+        for mode in [ParseMode::Strict, ParseMode::Tolerant, ParseMode::Salvage] {
+            let weather = CurrentWeather::from_json(&value.to_string(), mode).unwrap();
+            assert_eq!(weather.conditions().len(), count, "{:?}", mode);
+            assert_eq!(weather.descriptions(), description, "{} in {:?}", count, mode);
+            // The description item joins them all, and is empty without
+            // any; the rest is of the first.
+            assert_eq!(render(&weather, DataItem::Description).as_deref(), Some(description), "{}", count);
+            let (group, condition) = match count {
+                0 => ("", Condition::Unknown(0)),
+                _ => ("Rain", Condition::Rain(500)),
+            };
+            assert_eq!((weather.group(), weather.condition()), (group, condition), "{} in {:?}", count, mode);
+            assert_eq!(weather.description(), description.split(", ").next().unwrap(), "{}", count);
+        }
+    }
+}
+
+#[test]
+fn rain_and_snow_of_rainy_snowy_and_dry_responses() {
+    // (fixture, rain and snow in metric units, and in imperial ones), each
+    // compact: Amsterdam in light rain, snow at the polar station, and the
+    // dry ocean.
+    let cases = [
+        (CURRENT, ["0.4mm/h", "0mm/h"], ["0.02in/h", "0in/h"]),
+        (POLAR, ["0mm/h", "0.3mm/h"], ["0in/h", "0.01in/h"]),
+        (OCEAN, ["0mm/h", "0mm/h"], ["0in/h", "0in/h"]),
+    ];
+    for (json, metric, imperial) in cases {
+        let weather = CurrentWeather::from_json(json, ParseMode::Strict).unwrap();
+        for (units, expected) in [(Units::Metric, metric), (Units::Imperial, imperial)] {
+            let rendered = [DataItem::Rain, DataItem::Snow].map(|item| weather.render(item, units, UnitStyle::Compact));
+            assert_eq!(rendered, expected.map(|value| Some(value.to_string())), "{:?}", units);
+        }
+    }
+    let weather = CurrentWeather::from_json(CURRENT, ParseMode::Strict).unwrap();
+    assert_eq!(weather.rain_last_hour(), Some(0.42));
+    assert_eq!(weather.precipitation(DataItem::Rain), Some(0.42));
+    assert_eq!(weather.precipitation(DataItem::Snow), None);
+    let weather = CurrentWeather::from_json(POLAR, ParseMode::Strict).unwrap();
+    assert_eq!(weather.precipitation(DataItem::Snow), Some(0.25));
+}
+
+#[test]
+fn a_point_without_a_place() {
+    let weather = CurrentWeather::from_json(OCEAN, ParseMode::Strict).unwrap();
+    assert_eq!(render(&weather, DataItem::City), render(&weather, DataItem::Coords));
+    assert_eq!(render(&weather, DataItem::Country), None);
+    assert_eq!(render(&weather, DataItem::Pressure).as_deref(), Some("1021"));
+    assert_eq!(weather.rain_last_hour(), None);
+    assert_eq!(render(&weather, DataItem::Rain).as_deref(), Some("0"));
+}
+
+#[test]
+fn missing_blocks_are_not_rendered_as_zero() {
+    let json = without(&["/wind", "/clouds", "/main/pressure", "/main/temp_min", "/main/temp_max"]);
+    for mode in [ParseMode::Tolerant, ParseMode::Salvage] {
+        let weather = CurrentWeather::from_json(&json, mode).unwrap();
+        for item in [
+            DataItem::Wind,
+            DataItem::Beaufort,
+            DataItem::WindDesc,
+            DataItem::Clouds,
+            DataItem::Pressure,
+            DataItem::TempMin,
+            DataItem::TempMax,
+        ] {
+            assert_eq!(render(&weather, item), None, "{} in {:?}", item.name(), mode);
+        }
+        assert_eq!(weather.temp_range(), None);
+        assert_eq!(render(&weather, DataItem::Temp).as_deref(), Some("12°"));
+    }
+}
+
+#[test]
+fn typed_getters_give_the_raw_readings() {
+    let weather = CurrentWeather::from_json(CURRENT, ParseMode::Strict).unwrap();
+    assert_eq!((weather.temp(), weather.feels_like(), weather.humidity()), (12.35, 11.68, 81));
+    assert_eq!((weather.pressure(), weather.wind_speed()), (Some(1012.0), Some(5.66)));
+    assert_eq!((weather.wind_deg(), weather.wind_gust()), (Some(230.0), Some(9.26)));
+    assert_eq!((weather.description(), weather.observed()), ("light rain", 1714550400));
+    let polar = CurrentWeather::from_json(POLAR, ParseMode::Strict).unwrap();
+    assert_eq!((polar.wind_deg(), polar.wind_gust(), polar.humidity()), (None, None, 79));
+    // Items by name go through the same readings, rendered.
+    assert_eq!(weather.value(DataItem::Humidity).as_deref(), Some("81%"));
+    assert_eq!(weather.get("temp"), "12°");
+    assert_eq!(weather.get("temperature"), "('temperature?')");
+}
+
+#[test]
+fn null_blocks_are_missing_too() {
+    let mut value: Value = serde_json::from_str(CURRENT).unwrap();
+    value["wind"] = Value::Null;
+    value["main"]["pressure"] = Value::Null;
+    let weather = CurrentWeather::from_json(&value.to_string(), ParseMode::Tolerant).unwrap();
+    assert_eq!(weather.wind_speed(), None);
+    assert_eq!(weather.pressure(), None);
+    assert_eq!(render(&weather, DataItem::WindDir), None);
+}
+
+#[test]
+fn every_item_renders_or_says_why_not() {
+    let weather = CurrentWeather::from_json(CURRENT, ParseMode::Strict).unwrap();
+    let now = 1714550400 + 600;
+    for info in ITEMS {
+        match weather.try_render(info.item, Units::Metric, UnitStyle::default(), now) {
+            Ok(value) => assert!(value.is_some_and(|value| !value.is_empty()), "{} is empty", info.name),
+            Err(Unsupported::Feature(feature)) => {
+                let endpoints = info.endpoints.iter().filter_map(|endpoint| endpoint.feature());
+                assert!(info.features.iter().copied().chain(endpoints).any(|f| f == feature), "{}", info.name);
+                assert!(!feature.enabled(), "{}", info.name);
+            }
+            Err(Unsupported::NotCurrent) => {
+                assert_eq!(weather.render(info.item, Units::Metric, UnitStyle::default()), None, "{}", info.name)
+            }
+        }
+    }
+    let onecall = DataItem::Uvi.unsupported();
+    assert_eq!(onecall, (!cfg!(feature = "onecall")).then_some(Unsupported::Feature(Feature::OneCall)));
+}
+
+#[test]
+fn required_readings_that_are_not_numbers() {
+    for field in ["temp", "feels_like", "humidity"] {
+        for bad in [Value::Null, Value::from(""), Value::from("NaN"), Value::from("inf"), Value::from("-inf")] {
+            let mut value: Value = serde_json::from_str(CURRENT).unwrap();
+            value["main"][field] = bad.clone();
+            let json = value.to_string();
+            for mode in [ParseMode::Strict, ParseMode::Tolerant] {
+                assert!(CurrentWeather::from_json(&json, mode).is_err(), "{} = {} in {:?}", field, bad, mode);
+            }
+            let weather = CurrentWeather::from_json(&json, ParseMode::Salvage).unwrap();
+            let path = format!("main.{}", field);
+            assert!(weather.missing().contains(&path.as_str()), "{} = {}", field, bad);
+            let item: DataItem = field.parse().unwrap();
+            assert_eq!(render(&weather, item), None, "{} = {}", field, bad);
+        }
+    }
+}
+
+#[test]
+fn the_observation_time_is_explained_as_reported() {
+    let mut value: Value = serde_json::from_str(CURRENT).unwrap();
+    for (timezone, time) in [(-39_600, "21:00:00"), (0, "08:00:00"), (7200, "10:00:00")] {
+        value["timezone"] = Value::from(timezone);
+        let weather = CurrentWeather::from_json(&value.to_string(), ParseMode::Strict).unwrap();
+        let explanation = weather.explain(DataItem::ObsTime);
+        assert_eq!(explanation.raw.as_deref(), Some("1714550400"), "at {}", timezone);
+        assert_eq!(weather.explain(DataItem::Dt).raw, explanation.raw);
+        if cfg!(feature = "time") {
+            assert_eq!(render(&weather, DataItem::ObsTime).as_deref(), Some(time));
+        }
+    }
+}
+
+#[test]
+fn strict_refuses_fields_it_does_not_know() {
+    for (pointer, field) in [("", "extra"), ("/main", "main.extra"), ("/weather/0", "weather[0].extra")] {
+        let mut value: Value = serde_json::from_str(CURRENT).unwrap();
+        value.pointer_mut(pointer).and_then(Value::as_object_mut).unwrap().insert("extra".into(), Value::from(1));
+        let json = value.to_string();
+        let e = CurrentWeather::from_json(&json, ParseMode::Strict).unwrap_err();
+        assert_eq!(e.to_string(), format!("unknown field `{}`", field));
+        for mode in [ParseMode::Tolerant, ParseMode::Salvage] {
+            let weather = CurrentWeather::from_json(&json, mode).unwrap();
+            assert_eq!(render(&weather, DataItem::Temp).as_deref(), Some("12°"), "{:?}", mode);
+        }
+    }
+}
+
+#[test]
+fn strict_refuses_fields_left_out_unless_optional() {
+    for (pointer, field) in [("/timezone", "timezone"), ("/sys/sunrise", "sys.sunrise")] {
+        let e = CurrentWeather::from_json(&without(&[pointer]), ParseMode::Strict).unwrap_err();
+        assert_eq!(e.to_string(), format!("missing field `{}`", field));
+        for mode in [ParseMode::Tolerant, ParseMode::Salvage] {
+            assert!(CurrentWeather::from_json(&without(&[pointer]), mode).is_ok(), "{} in {:?}", field, mode);
+        }
+    }
+    // The gust is left out in calm weather, and the others when they are not
+    // measured.
+    let json = without(&["/wind/gust", "/name", "/main/pressure"]);
+    let weather = CurrentWeather::from_json(&json, ParseMode::Strict).unwrap();
+    assert_eq!(render(&weather, DataItem::Wind).as_deref(), Some("6"));
+    assert_eq!(render(&weather, DataItem::Pressure), None);
+    assert!(weather.missing().is_empty(), "{:?}", weather.missing());
+}
+
+#[test]
+fn a_mangled_response_is_salvaged() {
+    for mode in [ParseMode::Strict, ParseMode::Tolerant] {
+        assert!(CurrentWeather::from_json(MANGLED, mode).is_err(), "{:?}", mode);
+    }
+    let weather = CurrentWeather::from_json(MANGLED, ParseMode::Salvage).unwrap();
+    let missing = [
+        "main.feels_like",
+        "main.temp_min",
+        "main.temp_max",
+        "clouds.all",
+        "id",
+        "wind.deg",
+        "coord",
+        "weather",
+        "sys",
+        "base",
+    ];
+    assert_eq!(weather.missing(), missing);
+    assert_eq!(render(&weather, DataItem::Temp).as_deref(), Some("12°"));
+    assert_eq!(render(&weather, DataItem::Humidity).as_deref(), Some("81%"));
+    assert_eq!(render(&weather, DataItem::Pressure).as_deref(), Some("1012"));
+    assert_eq!(render(&weather, DataItem::City).as_deref(), Some("Amsterdam"));
+    for item in [DataItem::FeelsLike, DataItem::WindDeg, DataItem::Clouds, DataItem::Description, DataItem::Sunrise] {
+        assert_eq!(render(&weather, item), None, "{}", item.name());
+    }
+}
+
+#[test]
+fn what_is_not_an_object_is_not_salvaged() {
+    for json in ["[]", "12", "\"weather\""] {
+        let e = CurrentWeather::from_json(json, ParseMode::Salvage).unwrap_err();
+        assert!(e.to_string().starts_with("expected an object, got "), "{}: {}", json, e);
+    }
+    assert!(CurrentWeather::from_json("{", ParseMode::Salvage).is_err());
+}
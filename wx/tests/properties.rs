@@ -0,0 +1,361 @@
+//! Property tests of the whole pipeline, from a response body to every item
+//! rendered, and of the conversions the items are computed with: responses
+//! of the shape OpenWeatherMap sends, with random values,
+//! either fail to parse cleanly or parse into something every item renders
+//! without panicking. Bodies reach the client through a [`ResponseCache`],
+//! so nothing goes over the network: a body that does not parse is evicted,
+//! and asking for it again fails to connect.
+
+#![cfg(feature = "http")]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use proptest::prelude::*;
+use serde_json::{json, Map, Value};
+use wx::format::UnitStyle;
+use wx::item::ITEMS;
+use wx::{Client, CurrentWeather, Forecast, Location, NetworkError, ParseMode, ResponseCache, Retries, Units};
+
+/// A cache that answers every request with the same body, until the client
+/// evicts it.
+#[derive(Debug)]
+struct Canned {
+    body: String,
+    evicted: AtomicBool,
+}
+
+impl ResponseCache for Canned {
+    fn get(&self, _: &str, _: &str) -> Option<String> {
+        (!self.evicted.load(Ordering::SeqCst)).then(|| self.body.clone())
+    }
+
+    fn put(&self, _: &str, _: &str, _: &str) {}
+
+    fn evict(&self, _: &str, _: &str) {
+        self.evicted.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Returns a client that reads `body` from its cache, and the cache. Nothing
+/// listens at its API.
+fn client(body: String, mode: ParseMode) -> (Client, Arc<Canned>) {
+    let cache = Arc::new(Canned { body, evicted: AtomicBool::new(false) });
+    let client = Client::builder()
+        .base_url("http://127.0.0.1:9/")
+        .api_key("0123456789abcdef0123456789abcdef")
+        .retries(Retries { count: 0, ..Retries::default() })
+        .parse_mode(mode)
+        .cache(cache.clone())
+        .build()
+        .unwrap();
+    (client, cache)
+}
+
+/// A number as the API may send it: an integer, a float, the same as a
+/// string, or null.
+fn number() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        4 => (-100i64..2000).prop_map(Value::from),
+        4 => (-100.0f64..2000.0).prop_map(Value::from),
+        1 => any::<i64>().prop_map(Value::from),
+        1 => any::<u64>().prop_map(Value::from),
+        1 => any::<f64>().prop_map(Value::from),
+        1 => (-100.0f64..2000.0).prop_map(|n| Value::from(n.to_string())),
+        1 => Just(Value::Null),
+    ]
+}
+
+/// A time, unix, UTC: mostly around now, sometimes anything.
+fn epoch() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        4 => (1_600_000_000i64..1_900_000_000).prop_map(Value::from),
+        1 => any::<i64>().prop_map(Value::from),
+        1 => Just(Value::from(0)),
+    ]
+}
+
+/// A string with unicode, quotes and backslashes.
+fn text() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[a-zA-Z ]{0,12}",
+        "[\"'\\\\a-zé中🌧\u{200b}]{0,12}",
+        any::<String>(),
+    ]
+}
+
+/// A table with the fields of `fields`, each left out when `None`.
+fn object(fields: Vec<(&str, Option<Value>)>) -> Value {
+    let map: Map<String, Value> = fields
+        .into_iter()
+        .filter_map(|(key, value)| Some((key.to_string(), value?)))
+        .collect();
+    Value::Object(map)
+}
+
+fn weather() -> impl Strategy<Value = Value> {
+    (prop_oneof![200u64..900, any::<u64>()], text(), text(), "[0-9]{2}[dn]|.{0,3}").prop_map(
+        |(id, main, description, icon)| json!({"id": id, "main": main, "description": description, "icon": icon}),
+    )
+}
+
+fn main_block() -> impl Strategy<Value = Value> {
+    (number(), number(), number(), number(), prop::option::of(number()), prop::option::of(number())).prop_map(
+        |(temp, feels_like, pressure, humidity, temp_min, temp_max)| {
+            object(vec![
+                ("temp", Some(temp)),
+                ("feels_like", Some(feels_like)),
+                ("pressure", Some(pressure)),
+                ("humidity", Some(humidity)),
+                ("temp_min", temp_min),
+                ("temp_max", temp_max),
+            ])
+        },
+    )
+}
+
+fn wind() -> impl Strategy<Value = Value> {
+    (number(), prop::option::of(number()), prop::option::of(number()))
+        .prop_map(|(speed, deg, gust)| object(vec![("speed", Some(speed)), ("deg", deg), ("gust", gust)]))
+}
+
+fn precipitation() -> impl Strategy<Value = Value> {
+    (prop::option::of(number()), prop::option::of(number()))
+        .prop_map(|(one, three)| object(vec![("1h", one), ("3h", three)]))
+}
+
+fn coord() -> impl Strategy<Value = Value> {
+    (number(), number()).prop_map(|(lat, lon)| json!({"lat": lat, "lon": lon}))
+}
+
+prop_compose! {
+    fn sys()(
+        country in prop::option::of(text()),
+        sunrise in prop::option::of(epoch()),
+        sunset in prop::option::of(epoch()),
+        message in prop::option::of(prop_oneof![number(), text().prop_map(Value::from)]),
+    ) -> Value {
+        object(vec![
+            ("country", country.map(Value::from)),
+            ("sunrise", sunrise),
+            ("sunset", sunset),
+            ("message", message),
+        ])
+    }
+}
+
+prop_compose! {
+    fn readings()(
+        main in main_block(),
+        visibility in prop::option::of(number()),
+        wind in prop::option::of(wind()),
+        clouds in prop::option::of(number()),
+        rain in prop::option::of(precipitation()),
+        snow in prop::option::of(precipitation()),
+    ) -> Vec<(&'static str, Option<Value>)> {
+        vec![
+            ("main", Some(main)),
+            ("visibility", visibility),
+            ("wind", wind),
+            ("clouds", clouds.map(|all| json!({"all": all}))),
+            ("rain", rain),
+            ("snow", snow),
+        ]
+    }
+}
+
+prop_compose! {
+    fn current()(
+        readings in readings().boxed(),
+        sys in sys().boxed(),
+        coord in prop::option::of(coord()),
+        weather in prop::option::of(prop::collection::vec(weather(), 0..3)),
+        dt in epoch(),
+        timezone in prop::option::of(prop_oneof![(-50_400i64..50_400).prop_map(Value::from), number()]),
+        id in prop::option::of(any::<u64>()),
+        name in prop::option::of(text()),
+        cod in prop_oneof![Just(json!(200)), Just(json!("200"))],
+    ) -> Value {
+        let mut fields = vec![
+            ("coord", coord),
+            ("weather", weather.map(Value::from)),
+            ("base", Some(json!("stations"))),
+            ("dt", Some(dt)),
+            ("sys", Some(sys)),
+            ("timezone", timezone),
+            ("id", id.map(Value::from)),
+            ("name", name.map(Value::from)),
+            ("cod", Some(cod)),
+        ];
+        fields.extend(readings);
+        object(fields)
+    }
+}
+
+prop_compose! {
+    fn step()(
+        dt in epoch(),
+        main in main_block(),
+        weather in prop::collection::vec(weather(), 0..2),
+        clouds in prop::option::of(number()),
+        visibility in prop::option::of(number()),
+        pop in prop::option::of(number()),
+        wind in prop::option::of(wind()),
+        rain in prop::option::of(precipitation()),
+    ) -> Value {
+        object(vec![
+            ("dt", Some(dt)),
+            ("main", Some(main)),
+            ("weather", Some(Value::from(weather))),
+            ("clouds", clouds.map(|all| json!({"all": all}))),
+            ("visibility", visibility),
+            ("pop", pop),
+            ("wind", wind),
+            ("rain", rain),
+        ])
+    }
+}
+
+prop_compose! {
+    fn forecast()(
+        list in prop::collection::vec(step().boxed(), 0..6),
+        name in prop::option::of(text()),
+        coord in prop::option::of(coord()),
+        timezone in prop::option::of(number()),
+        sunrise in prop::option::of(epoch()),
+        sunset in prop::option::of(epoch()),
+    ) -> Value {
+        let city = object(vec![
+            ("id", Some(json!(2759794))),
+            ("name", name.map(Value::from)),
+            ("coord", coord),
+            ("timezone", timezone),
+            ("sunrise", sunrise),
+            ("sunset", sunset),
+        ]);
+        json!({"cod": "200", "message": 0, "cnt": list.len(), "list": list, "city": city})
+    }
+}
+
+/// Renders every item of `weather` every way there is, to find panics.
+fn render_everything(weather: &CurrentWeather, now: i64) {
+    for info in ITEMS {
+        let item = info.item;
+        for units in Units::ALL {
+            for style in UnitStyle::ALL {
+                let _ = weather.render(item, units, style);
+            }
+            let _ = weather.render_accessible(item, units, "en", now);
+            let _ = weather.report(units);
+        }
+        let _ = weather.render_at(item, now);
+        let _ = weather.explain(item);
+        let _ = weather.value(item);
+        #[cfg(feature = "time")]
+        let _ = weather.render_clock(item, now, "%Y-%m-%d %H:%M");
+    }
+    #[cfg(feature = "icons")]
+    for set in [wx::IconSet::NerdFont, wx::IconSet::Emoji, wx::IconSet::Text] {
+        let _ = weather.icon(set);
+    }
+    #[cfg(feature = "time")]
+    let _ = weather.day_story(&[weather.observation()]);
+}
+
+/// Checks that the body in `cache` failed to parse, rather than anything
+/// else failing: the client evicted it, and `e` is from asking again.
+fn check_error(e: &anyhow::Error, cache: &Canned) -> Result<(), TestCaseError> {
+    prop_assert!(cache.evicted.load(Ordering::SeqCst), "not evicted: {:#}", e);
+    prop_assert!(e.is::<NetworkError>(), "not from asking again: {:#}", e);
+    Ok(())
+}
+
+// 256 cases each by default; set PROPTEST_CASES for more. Failing cases are
+// printed rather than saved, as proptest saves them next to lib.rs.
+proptest! {
+    #![proptest_config(ProptestConfig { failure_persistence: None, ..ProptestConfig::default() })]
+
+    #[test]
+    fn current_weather_parses_or_fails_cleanly(body in current(), now in epoch()) {
+        let now = now.as_i64().unwrap_or_default();
+        for mode in [ParseMode::Strict, ParseMode::Tolerant, ParseMode::Salvage] {
+            let (client, cache) = client(body.to_string(), mode);
+            match client.current(&Location::city_id(1)) {
+                Ok(weather) => render_everything(&weather, now),
+                Err(e) => check_error(&e, &cache)?,
+            }
+        }
+    }
+
+    #[test]
+    fn forecast_parses_or_fails_cleanly(body in forecast(), now in epoch()) {
+        let now = now.as_i64().unwrap_or_default();
+        let (client, cache) = client(body.to_string(), ParseMode::Tolerant);
+        let forecast: Forecast = match client.forecast(&Location::city_id(1)) {
+            Ok(forecast) => forecast,
+            Err(e) => return check_error(&e, &cache),
+        };
+        let _ = forecast.temperatures(now, 48);
+        let _ = forecast.rest_of_day(now);
+        if let Some(event) = forecast.next_precipitation(now, 120, 0.5) {
+            let _ = event.render();
+        }
+        if let Some((first, last)) = forecast.span() {
+            for at in [first, last, now] {
+                if let Some(slot) = forecast.slot(at) {
+                    render_everything(&slot, now);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn any_json_fails_cleanly(body in any::<String>()) {
+        let body = Value::from(body).to_string();
+        let (client, cache) = client(body, ParseMode::Salvage);
+        if let Err(e) = client.current(&Location::city_id(1)) {
+            check_error(&e, &cache)?;
+        }
+    }
+}
+
+/// Whether `a` and `b` are the same but for rounding.
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0)
+}
+
+fn units() -> impl Strategy<Value = Units> {
+    prop::sample::select(Units::ALL.to_vec())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig { failure_persistence: None, ..ProptestConfig::default() })]
+
+    #[test]
+    fn conversions_round_trip(value in -1e6f64..1e6, from in units(), to in units()) {
+        let there = from.convert_temperature(value, to);
+        prop_assert!(close(to.convert_temperature(there, from), value), "{} {:?} -> {} {:?}", value, from, there, to);
+        let there = from.convert_speed(value, to);
+        prop_assert!(close(to.convert_speed(there, from), value), "{} {:?} -> {} {:?}", value, from, there, to);
+        prop_assert!(close(from.convert_temperature(value, from), value));
+    }
+
+    #[test]
+    fn conversions_keep_the_order(a in -1e6f64..1e6, b in -1e6f64..1e6, from in units(), to in units()) {
+        prop_assume!(a < b);
+        prop_assert!(from.convert_temperature(a, to) < from.convert_temperature(b, to));
+        prop_assert!(from.convert_speed(a, to) <= from.convert_speed(b, to));
+    }
+
+    #[test]
+    fn the_dew_point_is_not_above_the_temperature(celsius in -45.0f64..60.0, humidity in 1u32..=100, units in units()) {
+        let temp = Units::Metric.convert_temperature(celsius, units);
+        let body = json!({"main": {"temp": temp, "humidity": humidity}}).to_string();
+        let weather = CurrentWeather::from_json(&body, ParseMode::Salvage).unwrap();
+        let dew_point = weather.dew_point(units).unwrap();
+        prop_assert!(dew_point <= temp + 1e-9, "{} at {}%: dew point {}", temp, humidity, dew_point);
+        if humidity == 100 {
+            prop_assert!((dew_point - temp).abs() < 1e-6, "{} at 100%: dew point {}", temp, dew_point);
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! The client through a proxy, a local one that logs what it is asked and
+//! answers for the API itself, and root certificates: what is refused when
+//! the client is built, before any request.
+
+#![cfg(feature = "http")]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use wx::{Client, ClientBuilder, Location, Retries};
+
+const CURRENT: &str = include_str!("fixtures/current.json");
+/// A self-signed certificate, of an authority of its own.
+const CA: &str = include_str!("fixtures/ca.pem");
+
+/// A proxy on a free local port that answers every request with the current
+/// weather fixture, and records the request line and headers of each.
+struct Proxy {
+    url: String,
+    requests: Arc<Mutex<Vec<Vec<String>>>>,
+}
+
+impl Proxy {
+    fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut lines = Vec::new();
+                for line in BufReader::new(&stream).lines() {
+                    match line {
+                        Ok(line) if !line.is_empty() => lines.push(line),
+                        _ => break,
+                    }
+                }
+                recorded.lock().unwrap().push(lines);
+                let mut stream = stream;
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+                    Connection: close\r\n\r\n{}",
+                    CURRENT.len(),
+                    CURRENT
+                );
+            }
+        });
+        Proxy { url, requests }
+    }
+
+    /// Returns the request line and the headers of each request so far.
+    fn requests(&self) -> Vec<Vec<String>> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Returns a builder for a client of an API no one answers but the proxy,
+/// which does not retry.
+fn builder() -> ClientBuilder {
+    Client::builder()
+        .base_url("http://api.invalid/")
+        .api_key("0123456789abcdef0123456789abcdef")
+        .retries(Retries { count: 0, ..Retries::default() })
+}
+
+#[test]
+fn requests_go_through_the_proxy() {
+    let proxy = Proxy::start();
+    let client = builder().proxy(&proxy.url).build().unwrap();
+    let weather = client.current(&Location::new("Amsterdam", "", "NL")).unwrap();
+    assert_eq!(weather.city_name(), Some("Amsterdam"));
+    let requests = proxy.requests();
+    assert_eq!(requests.len(), 1);
+    let line = &requests[0][0];
+    assert!(line.starts_with("GET http://api.invalid/data/2.5/weather?q=Amsterdam%2CNL&"), "{}", line);
+    assert!(!requests[0].iter().any(|header| header.to_lowercase().starts_with("proxy-authorization")));
+}
+
+#[test]
+fn credentials_of_the_proxy_are_sent() {
+    let proxy = Proxy::start();
+    let url = proxy.url.replace("http://", "http://ann:s3cret@");
+    let client = builder().proxy(&url).build().unwrap();
+    client.current(&Location::coords(52.374, 4.8897)).unwrap();
+    let headers = &proxy.requests()[0];
+    // "ann:s3cret" in base64.
+    assert!(headers.iter().any(|header| header == "proxy-authorization: Basic YW5uOnMzY3JldA=="), "{:?}", headers);
+}
+
+#[test]
+fn proxies_that_are_not_http_are_refused() {
+    let cases = [
+        ("ftp://proxy.example:21", "invalid proxy URL 'ftp://proxy.example:21': expected http or https, not ftp"),
+        ("proxy.example:8080", "invalid proxy URL 'proxy.example:8080': expected http or https, not proxy.example"),
+        ("not a url", "invalid proxy URL 'not a url': relative URL without a base"),
+        ("http://", "invalid proxy URL 'http://': empty host"),
+    ];
+    for (url, expected) in cases {
+        let e = builder().proxy(url).build().unwrap_err();
+        assert_eq!(e.to_string(), expected, "{}", url);
+    }
+    assert!(builder().proxy(" https://proxy.example:3128 ").build().is_ok());
+}
+
+#[cfg(any(feature = "native-tls", feature = "rustls"))]
+#[test]
+fn root_certificates_in_pem() {
+    assert!(builder().root_certificate(CA.as_bytes()).build().is_ok());
+    let e = builder().root_certificate(b"not a certificate").build().unwrap_err();
+    assert_eq!(e.to_string(), "invalid root certificate: no PEM certificate in it");
+    let broken = CA.replace(&CA[40..80], "");
+    let e = builder().root_certificate(broken.as_bytes()).build().unwrap_err();
+    assert_eq!(e.to_string(), "invalid root certificate: the PEM certificate in it cannot be read");
+}
@@ -0,0 +1,125 @@
+//! The shape of [`wx::WeatherReport`], as `tinywx -o raw-json` prints it,
+//! locked on the fixtures in `tests/fixtures`: every field of Amsterdam in the
+//! rain, the polar station without a wind direction or visibility, and with
+//! polar day, the point in the ocean with no city or country, and the units
+//! a report is in.
+
+use serde_json::{json, Value};
+use wx::report::SCHEMA_VERSION;
+use wx::{CurrentWeather, ParseMode, Units};
+
+const CURRENT: &str = include_str!("fixtures/current.json");
+const POLAR: &str = include_str!("fixtures/polar.json");
+const OCEAN: &str = include_str!("fixtures/ocean.json");
+
+/// Returns the report of `json` in `units`, as JSON.
+fn report(json: &str, units: Units) -> Value {
+    let weather = CurrentWeather::from_json(json, ParseMode::Strict).unwrap();
+    serde_json::to_value(weather.report(units)).unwrap()
+}
+
+/// Returns the local time `rfc3339` is, or `null` without the `time` feature.
+fn local(rfc3339: &str) -> Value {
+    if cfg!(feature = "time") {
+        rfc3339.into()
+    } else {
+        Value::Null
+    }
+}
+
+#[test]
+fn every_field_of_the_fixture() {
+    let expected = json!({
+        "schema": 1,
+        "units": "metric",
+        "place": {
+            "name": "Amsterdam",
+            "country": "NL",
+            "city_id": 2759794,
+            "lat": 52.374,
+            "lon": 4.8897,
+            "utc_offset": 7200,
+        },
+        "observed": 1714550400,
+        "conditions": [
+            {"id": 500, "group": "Rain", "description": "light rain", "icon": "10d", "severity": "light"},
+        ],
+        "temperature": {
+            "current": 12.35,
+            "feels_like": 11.68,
+            "min": 11.1,
+            "max": 13.4,
+            "dew_point": 9.179429110640626,
+        },
+        "pressure_hpa": 1012.0,
+        "humidity": 81.0,
+        "clouds": 75.0,
+        "visibility_m": 10000.0,
+        "wind": {"speed": 5.66, "gust": 9.26, "deg": 230.0, "direction": "SW", "beaufort": 4},
+        "rain_mm_h": 0.42,
+        "snow_mm_h": 0.0,
+        "sun": {
+            "sunrise": 1714536300,
+            "sunset": 1714590900,
+            "sunrise_local": local("2024-05-01T06:05:00+02:00"),
+            "sunset_local": local("2024-05-01T21:15:00+02:00"),
+        },
+    });
+    assert_eq!(report(CURRENT, Units::Metric), expected);
+    assert_eq!(SCHEMA_VERSION, 1);
+}
+
+#[test]
+fn fields_follow_the_declared_order() {
+    let weather = CurrentWeather::from_json(CURRENT, ParseMode::Strict).unwrap();
+    let serialized = serde_json::to_string(&weather.report(Units::Metric)).unwrap();
+    let keys = [
+        "\"schema\"",
+        "\"units\"",
+        "\"place\"",
+        "\"observed\"",
+        "\"conditions\"",
+        "\"temperature\"",
+        "\"pressure_hpa\"",
+        "\"humidity\"",
+        "\"clouds\"",
+        "\"visibility_m\"",
+        "\"wind\"",
+        "\"rain_mm_h\"",
+        "\"snow_mm_h\"",
+        "\"sun\"",
+    ];
+    let positions: Vec<usize> = keys.iter().map(|key| serialized.find(key).unwrap()).collect();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]), "{}", serialized);
+}
+
+#[test]
+fn missing_values_are_null() {
+    let polar = report(POLAR, Units::Metric);
+    assert_eq!(polar["visibility_m"], Value::Null);
+    assert_eq!(polar["wind"], json!({"speed": 2.06, "gust": null, "deg": null, "direction": null, "beaufort": 2}));
+    assert_eq!((&polar["rain_mm_h"], &polar["snow_mm_h"]), (&json!(0.0), &json!(0.25)));
+
+    let mut value: Value = serde_json::from_str(POLAR).unwrap();
+    value["sys"]["sunrise"] = 0.into();
+    value["sys"]["sunset"] = 0.into();
+    let polar_day = report(&value.to_string(), Units::Metric);
+    let sun = json!({"sunrise": null, "sunset": null, "sunrise_local": null, "sunset_local": null});
+    assert_eq!(polar_day["sun"], sun);
+
+    let ocean = report(OCEAN, Units::Metric);
+    assert_eq!(ocean["place"]["country"], Value::Null);
+    assert_eq!(ocean["place"]["city_id"], Value::Null);
+    assert_eq!((&ocean["place"]["lat"], &ocean["place"]["lon"]), (&json!(45.0), &json!(-30.0)));
+    assert_eq!(ocean["place"]["utc_offset"], -7200);
+}
+
+#[test]
+fn the_units_are_those_of_the_request() {
+    let imperial = report(CURRENT, Units::Imperial);
+    assert_eq!(imperial["units"], "imperial");
+    // The values are as fetched, in mph: 5.66mph is a gentle breeze.
+    assert_eq!(imperial["wind"]["speed"], 5.66);
+    assert_eq!(imperial["wind"]["beaufort"], 2);
+    assert_eq!(report(CURRENT, Units::Standard)["units"], "standard");
+}
@@ -0,0 +1,59 @@
+//! Templates of the library, rendered from the current weather fixture:
+//! items in braces, braces doubled to write them, and the templates that are
+//! errors rather than a value with a question mark in it.
+
+use serde_json::Value;
+use wx::{CurrentWeather, ParseMode};
+
+const CURRENT: &str = include_str!("fixtures/current.json");
+
+fn render(template: &str) -> Result<String, String> {
+    let weather = CurrentWeather::from_json(CURRENT, ParseMode::Strict).unwrap();
+    weather.render_template(template).map_err(|e| e.to_string())
+}
+
+#[test]
+fn items_in_braces_are_replaced() {
+    assert_eq!(render("{temp} ({feels_like}) {humidity}").unwrap(), "12° (12°) 81%");
+    assert_eq!(render("{ description }, wind {wind_dir}").unwrap(), "light rain, wind SW");
+    assert_eq!(render("in {city}: {temp}").unwrap(), "in Amsterdam: 12°");
+    assert_eq!(render("{time}").unwrap(), render("{obs_time}").unwrap());
+}
+
+#[test]
+fn empty_and_plain_templates_are_themselves() {
+    assert_eq!(render("").unwrap(), "");
+    assert_eq!(render("no items, 東京").unwrap(), "no items, 東京");
+}
+
+#[test]
+fn doubled_braces_are_braces() {
+    assert_eq!(render("{{temp}}").unwrap(), "{temp}");
+    assert_eq!(render("{{{temp}}}").unwrap(), "{12°}");
+    assert_eq!(render("}}{{").unwrap(), "}{");
+}
+
+#[test]
+fn braces_that_do_not_pair_up_are_errors() {
+    assert_eq!(render("{temp").unwrap_err(), "unclosed '{' in template; write '{{' for a brace");
+    assert_eq!(render("temp}").unwrap_err(), "unmatched '}' in template; write '}}' for a brace");
+    assert_eq!(render("{te{mp}").unwrap_err(), "'{' inside template field '{te{mp}'; write '{{' for a brace");
+    assert_eq!(render("{{temp}").unwrap_err(), "unmatched '}' in template; write '}}' for a brace");
+    assert_eq!(render("{}").unwrap_err(), "empty template field '{}'");
+    assert_eq!(render("{ }").unwrap_err(), "empty template field '{ }'");
+}
+
+#[test]
+fn unknown_items_are_errors() {
+    assert_eq!(render("{temperature}").unwrap_err(), "unknown data item 'temperature', did you mean 'temp'?");
+    assert_eq!(render("{uvi}").unwrap_err(), "item 'uvi' needs more than the current weather");
+    assert_eq!(render("{today_high}").unwrap_err(), "item 'today_high' needs more than the current weather");
+}
+
+#[test]
+fn items_missing_from_the_response_are_empty() {
+    let mut value: Value = serde_json::from_str(CURRENT).unwrap();
+    value.as_object_mut().unwrap().remove("wind");
+    let weather = CurrentWeather::from_json(&value.to_string(), ParseMode::Tolerant).unwrap();
+    assert_eq!(weather.render_template("[{wind}] {temp}").unwrap(), "[] 12°");
+}
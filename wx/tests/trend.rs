@@ -0,0 +1,61 @@
+//! The temperature trend, from the current weather fixture in
+//! `tests/fixtures` and observations before it: which way it went with the
+//! delta, which of the earlier observations counts, and none when there is
+//! no observation recent enough.
+
+use wx::history::{self, Observation, Trend};
+use wx::{CurrentWeather, ParseMode};
+
+const CURRENT: &str = include_str!("fixtures/current.json");
+/// Time and temperature of the fixture's reading.
+const DT: i64 = 1714550400;
+const TEMP: f64 = 12.35;
+const HOUR: i64 = 3600;
+
+fn weather() -> CurrentWeather {
+    CurrentWeather::from_json(CURRENT, ParseMode::Strict).unwrap()
+}
+
+/// Returns an observation `ago` seconds before the fixture's, at `temp`.
+fn before(ago: i64, temp: f64) -> Observation {
+    Observation { dt: DT - ago, temp, condition: 500 }
+}
+
+#[test]
+fn rising_falling_or_steady_by_the_delta() {
+    let current = weather().observation();
+    assert_eq!(current, Observation { dt: DT, temp: TEMP, condition: 500 });
+    assert_eq!(history::trend(&before(HOUR, 11.0), &current, 0.5), Trend::Rising);
+    assert_eq!(history::trend(&before(HOUR, 14.0), &current, 0.5), Trend::Falling);
+    assert_eq!(history::trend(&before(HOUR, 12.0), &current, 0.5), Trend::Steady);
+    // A change of the delta itself is steady.
+    assert_eq!(history::trend(&before(HOUR, 11.35), &current, 1.0), Trend::Steady);
+    assert_eq!(history::trend(&before(HOUR, 11.0), &current, 2.0), Trend::Steady);
+    assert_eq!(history::trend(&before(HOUR, 12.3), &current, 0.0), Trend::Rising);
+}
+
+#[test]
+fn the_latest_earlier_observation_counts() {
+    let weather = weather();
+    let history = [before(2 * HOUR, 20.0), before(HOUR, 11.0), before(3 * HOUR, 20.0)];
+    assert_eq!(weather.trend(&history, 0.5, 3 * HOUR), Some(Trend::Rising));
+    // The fixture's own reading, and later ones, are not before it.
+    let history = [before(HOUR, 11.0), before(0, 20.0), before(-HOUR, 20.0)];
+    assert_eq!(weather.trend(&history, 0.5, 3 * HOUR), Some(Trend::Rising));
+}
+
+#[test]
+fn no_trend_without_a_recent_observation() {
+    let weather = weather();
+    assert_eq!(weather.trend(&[], 0.5, 3 * HOUR), None);
+    assert_eq!(weather.trend(&[before(0, 11.0)], 0.5, 3 * HOUR), None);
+    assert_eq!(weather.trend(&[before(3 * HOUR, 11.0)], 0.5, 3 * HOUR), Some(Trend::Rising));
+    assert_eq!(weather.trend(&[before(3 * HOUR + 1, 11.0)], 0.5, 3 * HOUR), None);
+}
+
+#[test]
+fn arrows_and_names() {
+    let trends = [Trend::Rising, Trend::Falling, Trend::Steady];
+    let rendered: Vec<_> = trends.iter().map(|trend| (trend.arrow(), trend.name())).collect();
+    assert_eq!(rendered, [("↑", "rising"), ("↓", "falling"), ("→", "steady")]);
+}